@@ -4,6 +4,44 @@ use crate::*;
 pub struct RubyError {
     pub kind: RubyErrorKind,
     pub info: Vec<(SourceInfoRef, Loc)>,
+    /// Call stack at the point where the error was raised. (the innermost frame first)
+    pub backtrace: Vec<BacktraceLoc>,
+}
+
+/// A frame in the backtrace of an error.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BacktraceLoc {
+    pub source_info: SourceInfoRef,
+    pub loc: Loc,
+    /// Label of the frame. (e.g. "foo", "block in foo", "<main>")
+    pub label: String,
+}
+
+impl BacktraceLoc {
+    pub fn new(source_info: SourceInfoRef, loc: Loc, label: String) -> Self {
+        Self {
+            source_info,
+            loc,
+            label,
+        }
+    }
+
+    pub fn path(&self) -> String {
+        self.source_info.get_file_name()
+    }
+
+    pub fn lineno(&self) -> usize {
+        match self.source_info.get_lines(&self.loc).first() {
+            Some(line) => line.no,
+            None => 0,
+        }
+    }
+}
+
+impl std::fmt::Display for BacktraceLoc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:in `{}'", self.path(), self.lineno(), self.label)
+    }
 }
 
 impl std::fmt::Debug for RubyError {
@@ -16,7 +54,9 @@ impl std::fmt::Debug for RubyError {
             RubyErrorKind::MethodReturn => write!(f, "MethodReturn"),
             RubyErrorKind::BlockReturn => write!(f, "BlockReturn"),
             RubyErrorKind::Exception => write!(f, "Exception"),
+            RubyErrorKind::Throw => write!(f, "Throw"),
             RubyErrorKind::SystemExit(code) => write!(f, "SystemExit({})", code),
+            RubyErrorKind::ThreadKill => write!(f, "ThreadKill"),
             RubyErrorKind::Internal(msg) => write!(f, "InternalError {}", msg),
             RubyErrorKind::None(msg) => write!(f, "{}", msg),
         }
@@ -31,9 +71,13 @@ pub enum RubyErrorKind {
         message: String,
     },
     Exception,
+    /// Non-local exit by `throw`. The tag and value are held in vm.globals.val.
+    Throw,
     MethodReturn,
     BlockReturn,
     SystemExit(i64),
+    /// Termination of a thread by Thread#kill. Can not be rescued.
+    ThreadKill,
     Internal(String),
     None(String),
 }
@@ -59,6 +103,7 @@ pub enum RuntimeErrKind {
     NoMethod,
     Argument,
     Index,
+    Key,
     Type,
     Regexp,
     Fiber,
@@ -69,6 +114,16 @@ pub enum RuntimeErrKind {
     Range,
     ZeroDivision,
     DomainError,
+    NoMatchingPattern,
+    NoMatchingPatternKey,
+    Thread,
+    ClosedQueue,
+    Frozen,
+    Fatal,
+    IO,
+    EOF,
+    Errno(&'static str),
+    Encoding(&'static str),
 }
 
 impl std::fmt::Debug for RuntimeErrKind {
@@ -78,6 +133,7 @@ impl std::fmt::Debug for RuntimeErrKind {
             Self::NoMethod => write!(f, "NoMethodError"),
             Self::Argument => write!(f, "ArgumentError"),
             Self::Index => write!(f, "IndexError"),
+            Self::Key => write!(f, "KeyError"),
             Self::Type => write!(f, "TypeError"),
             Self::Regexp => write!(f, "RegexpError"),
             Self::Fiber => write!(f, "FiberError"),
@@ -88,19 +144,34 @@ impl std::fmt::Debug for RuntimeErrKind {
             Self::Range => write!(f, "RangeError"),
             Self::ZeroDivision => write!(f, "ZeroDivisionError"),
             Self::DomainError => write!(f, "Math::DomainError"),
+            Self::NoMatchingPattern => write!(f, "NoMatchingPatternError"),
+            Self::NoMatchingPatternKey => write!(f, "NoMatchingPatternKeyError"),
+            Self::Thread => write!(f, "ThreadError"),
+            Self::ClosedQueue => write!(f, "ClosedQueueError"),
+            Self::Frozen => write!(f, "FrozenError"),
+            Self::Fatal => write!(f, "fatal"),
+            Self::IO => write!(f, "IOError"),
+            Self::EOF => write!(f, "EOFError"),
+            Self::Errno(name) => write!(f, "Errno::{}", name),
+            Self::Encoding(name) => write!(f, "Encoding::{}", name),
         }
     }
 }
 
 impl RubyError {
     pub fn new(kind: RubyErrorKind) -> Self {
-        Self { kind, info: vec![] }
+        Self {
+            kind,
+            info: vec![],
+            backtrace: vec![],
+        }
     }
 
     fn new_with_info(kind: RubyErrorKind, source_info: SourceInfoRef, loc: Loc) -> Self {
         Self {
             kind,
             info: vec![(source_info, loc)],
+            backtrace: vec![],
         }
     }
 
@@ -118,6 +189,10 @@ impl RubyError {
         matches!(&self.kind, RubyErrorKind::BlockReturn)
     }
 
+    pub fn is_throw(&self) -> bool {
+        matches!(&self.kind, RubyErrorKind::Throw)
+    }
+
     pub fn is_exception(&self) -> bool {
         matches!(&self.kind, RubyErrorKind::Exception)
     }
@@ -125,6 +200,10 @@ impl RubyError {
     pub fn is_system_exit(&self) -> bool {
         matches!(&self.kind, RubyErrorKind::SystemExit(_))
     }
+
+    pub fn is_thread_kill(&self) -> bool {
+        matches!(&self.kind, RubyErrorKind::ThreadKill)
+    }
 }
 
 impl RubyError {
@@ -159,7 +238,9 @@ impl RubyError {
             RubyErrorKind::MethodReturn => "LocalJumpError".to_string(),
             RubyErrorKind::BlockReturn => "LocalJumpError".to_string(),
             RubyErrorKind::Exception => "Exception".to_string(),
+            RubyErrorKind::Throw => "Throw".to_string(),
             RubyErrorKind::SystemExit(_) => "SystemExit".to_string(),
+            RubyErrorKind::ThreadKill => "ThreadKill".to_string(),
             RubyErrorKind::None(msg) => msg.to_owned(),
             RubyErrorKind::Internal(msg) => {
                 format!("InternalError\n{}", msg)
@@ -201,6 +282,10 @@ impl RubyError {
         RubyError::new(RubyErrorKind::SystemExit(code))
     }
 
+    pub fn thread_kill() -> RubyError {
+        RubyError::new(RubyErrorKind::ThreadKill)
+    }
+
     pub fn internal(msg: impl Into<String>) -> RubyError {
         RubyError::new(RubyErrorKind::Internal(msg.into()))
     }
@@ -243,6 +328,10 @@ impl RubyError {
         RubyError::new_runtime_err(RuntimeErrKind::Index, msg.into())
     }
 
+    pub fn key(msg: impl Into<String>) -> RubyError {
+        RubyError::new_runtime_err(RuntimeErrKind::Key, msg.into())
+    }
+
     pub fn fiber(msg: impl Into<String>) -> RubyError {
         RubyError::new_runtime_err(RuntimeErrKind::Fiber, msg.into())
     }
@@ -262,6 +351,48 @@ impl RubyError {
     pub fn math_domain(msg: impl Into<String>) -> RubyError {
         RubyError::new_runtime_err(RuntimeErrKind::DomainError, msg.into())
     }
+
+    pub fn no_matching_pattern(msg: impl Into<String>) -> RubyError {
+        RubyError::new_runtime_err(RuntimeErrKind::NoMatchingPattern, msg.into())
+    }
+
+    pub fn no_matching_pattern_key(msg: impl Into<String>) -> RubyError {
+        RubyError::new_runtime_err(RuntimeErrKind::NoMatchingPatternKey, msg.into())
+    }
+
+    pub fn thread(msg: impl Into<String>) -> RubyError {
+        RubyError::new_runtime_err(RuntimeErrKind::Thread, msg.into())
+    }
+
+    pub fn closed_queue(msg: impl Into<String>) -> RubyError {
+        RubyError::new_runtime_err(RuntimeErrKind::ClosedQueue, msg.into())
+    }
+
+    pub fn frozen(msg: impl Into<String>) -> RubyError {
+        RubyError::new_runtime_err(RuntimeErrKind::Frozen, msg.into())
+    }
+
+    pub fn fatal(msg: impl Into<String>) -> RubyError {
+        RubyError::new_runtime_err(RuntimeErrKind::Fatal, msg.into())
+    }
+
+    pub fn io(msg: impl Into<String>) -> RubyError {
+        RubyError::new_runtime_err(RuntimeErrKind::IO, msg.into())
+    }
+
+    pub fn eof(msg: impl Into<String>) -> RubyError {
+        RubyError::new_runtime_err(RuntimeErrKind::EOF, msg.into())
+    }
+
+    /// Errno::`name` error. `name` must be a constant defined in Errno module.
+    pub fn errno(name: &'static str, msg: impl Into<String>) -> RubyError {
+        RubyError::new_runtime_err(RuntimeErrKind::Errno(name), msg.into())
+    }
+
+    /// Encoding::`name` error. `name` must be a constant defined in Encoding class.
+    pub fn encoding(name: &'static str, msg: impl Into<String>) -> RubyError {
+        RubyError::new_runtime_err(RuntimeErrKind::Encoding(name), msg.into())
+    }
 }
 
 impl RubyError {
@@ -277,6 +408,10 @@ impl RubyError {
         RubyError::new(RubyErrorKind::Exception)
     }
 
+    pub fn throw() -> RubyError {
+        RubyError::new(RubyErrorKind::Throw)
+    }
+
     pub fn stop_iteration(msg: impl Into<String>) -> RubyError {
         RubyError::new_runtime_err(RuntimeErrKind::StopIteration, msg.into())
     }
@@ -77,6 +77,12 @@ impl IdentId {
     pub const EACH: IdentId = id!(27);
     pub const MAP: IdentId = id!(28);
     pub const _NAME: IdentId = id!(29);
+    pub const _DEFAULT: IdentId = id!(30);
+    pub const _UNDEF_METHOD: IdentId = id!(31);
+    pub const _DEFAULT_PROC: IdentId = id!(32);
+    pub const _ENCODING: IdentId = id!(33);
+    pub const SIZE: IdentId = id!(34);
+    pub const EMPTY: IdentId = id!(35);
 }
 
 impl IdentId {
@@ -185,6 +191,12 @@ impl IdentifierTable {
         table.set_ident_id("each", IdentId::EACH);
         table.set_ident_id("map", IdentId::MAP);
         table.set_ident_id("/name", IdentId::_NAME);
+        table.set_ident_id("/default", IdentId::_DEFAULT);
+        table.set_ident_id("/undef_method", IdentId::_UNDEF_METHOD);
+        table.set_ident_id("/default_proc", IdentId::_DEFAULT_PROC);
+        table.set_ident_id("/encoding", IdentId::_ENCODING);
+        table.set_ident_id("size", IdentId::SIZE);
+        table.set_ident_id("empty?", IdentId::EMPTY);
         table
     }
 
@@ -77,6 +77,23 @@ impl LvarCollector {
         }
     }
 
+    /// Restore `LvarCollector` from its components.
+    pub fn restore(
+        kw: Vec<LvarId>,
+        table: Vec<IdentId>,
+        kwrest: Option<LvarId>,
+        block: Option<LvarId>,
+        delegate_param: Option<LvarId>,
+    ) -> Self {
+        LvarCollector {
+            kw,
+            table: LvarTable(table),
+            kwrest,
+            block,
+            delegate_param,
+        }
+    }
+
     /// Check whether `val` exists in `LvarCollector` or not, and return `LvarId` if exists.
     /// If not, add new variable `val` to the `LvarCollector`.
     pub fn insert(&mut self, val: IdentId) -> LvarId {
@@ -125,7 +142,7 @@ impl LvarCollector {
         Some(lvar)
     }
 
-    fn get_name_id(&self, id: LvarId) -> Option<IdentId> {
+    pub fn get_name_id(&self, id: LvarId) -> Option<IdentId> {
         self.table.get(id.into())
     }
 
@@ -30,6 +30,7 @@ impl Inst {
     pub const SET_CVAR: u8 = 33;
     pub const GET_SVAR: u8 = 34;
     pub const SET_SVAR: u8 = 35;
+    pub const TEE_LOCAL: u8 = 36;
 
     pub const GET_INDEX: u8 = 40;
     pub const SET_INDEX: u8 = 41;
@@ -46,6 +47,8 @@ impl Inst {
     pub const SEND: u8 = 60;
     pub const OPT_SEND: u8 = 66;
     pub const OPT_SEND_N: u8 = 68;
+    pub const OPT_SIZE: u8 = 70;
+    pub const OPT_EMPTY: u8 = 71;
 
     pub const POP: u8 = 80;
     pub const DUP: u8 = 81;
@@ -74,7 +77,8 @@ impl Inst {
     pub const THROW: u8 = 110;
     pub const OPT_CASE2: u8 = 111;
     pub const SUPER: u8 = 112;
-    
+    pub const NO_MATCH: u8 = 113;
+
     pub const ADD: u8 = 120;
     pub const SUB: u8 = 121;
     pub const MUL: u8 = 122;
@@ -177,6 +181,7 @@ impl Inst {
 
             Inst::SET_LOCAL => "SET_LOCAL",
             Inst::GET_LOCAL => "GET_LOCAL",
+            Inst::TEE_LOCAL => "TEE_LOCAL",
             Inst::SET_DYNLOCAL => "SET_DYNLOCAL",
             Inst::GET_DYNLOCAL => "GET_DYNLOCAL",
             Inst::GET_CONST => "GET_CONST",
@@ -207,6 +212,8 @@ impl Inst {
             Inst::SEND => "SEND",
             Inst::OPT_SEND => "O_SEND",
             Inst::OPT_SEND_N => "O_SEND_N",
+            Inst::OPT_SIZE => "O_SIZE",
+            Inst::OPT_EMPTY => "O_EMPTY",
 
             Inst::CREATE_RANGE => "CREATE_RANGE",
             Inst::CREATE_ARRAY => "CREATE_ARRAY",
@@ -242,6 +249,7 @@ impl Inst {
             Inst::RESCUE => "RESCUE",
             Inst::THROW => "THROW",
             Inst::SUPER => "SUPER",
+            Inst::NO_MATCH => "NO_MATCH",
 
             _ => return format!("undefined {}", inst),
         };
@@ -288,14 +296,13 @@ impl Inst {
                                         // operand
             Inst::SET_LOCAL           // LvarId: u32
             | Inst::GET_LOCAL           // LVarId: u32
+            | Inst::TEE_LOCAL           // LVarId: u32
             | Inst::SET_CONST           // IdentId: u32
             | Inst::CHECK_CONST         // IdentId: u32
             | Inst::CHECK_SCOPE         // IdentId: u32
             | Inst::CHECK_METHOD        // IdentId: u32
             | Inst::GET_CONST_TOP       // IdentId: u32
             | Inst::GET_SCOPE           // IdentId: u32
-            | Inst::GET_IVAR            // IdentId: u32
-            | Inst::SET_IVAR            // IdentId: u32
             | Inst::CHECK_IVAR          // IdentId: u32
             | Inst::GET_GVAR            // IdentId: u32
             | Inst::SET_GVAR            // IdentId: u32
@@ -308,6 +315,7 @@ impl Inst {
             | Inst::SET_IDX_I           // immediate: u32
             | Inst::CREATE_ARRAY        // number of items: u32
             | Inst::CONST_VAL           // ConstId: u32
+            | Inst::NO_MATCH            // ConstId: u32
 
             | Inst::JMP                 // disp: i32
             | Inst::JMP_BACK            // disp: i32
@@ -335,6 +343,8 @@ impl Inst {
             | Inst::LTI                 // immediate: i32
             | Inst::LEI                 // immediate: i32
             | Inst::CREATE_PROC         // block: u32
+            | Inst::OPT_SIZE            // icache: u32
+            | Inst::OPT_EMPTY           // icache: u32
             | Inst::DEF_SCLASS          // block: u32
             | Inst::CREATE_HASH         // number of items: u32
             | Inst::YIELD               // number of items: u32
@@ -349,6 +359,8 @@ impl Inst {
             | Inst::GET_DYNLOCAL
             | Inst::CHECK_LOCAL
             | Inst::GET_CONST           // IdentId: u32 / cache: u32
+            | Inst::GET_IVAR            // IdentId: u32 / cache: u32
+            | Inst::SET_IVAR            // IdentId: u32 / cache: u32
             | Inst::OPT_CASE
             | Inst::OPT_CASE2
             | Inst::DEF_METHOD          // method_id: u32 / method: u32
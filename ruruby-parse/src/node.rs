@@ -1,5 +1,5 @@
 use super::*;
-use num::BigInt;
+use num::{BigInt, BigRational};
 use ruruby_common::IdentId;
 
 pub type Node = Annot<NodeKind>;
@@ -11,9 +11,10 @@ pub enum NodeKind {
     Integer(i64),
     Bignum(BigInt),
     Float(f64),
+    Rational(BigRational),
     Imaginary(NReal),
     Bool(bool),
-    String(String),
+    String(Vec<u8>),
     InterporatedString(Vec<Node>),
     Command(Box<Node>),
     Symbol(IdentId),
@@ -70,6 +71,16 @@ pub enum NodeKind {
         when_: Vec<CaseBranch>,
         else_: Box<Node>,
     },
+    CaseIn {
+        cond: Box<Node>,
+        in_: Vec<CaseInBranch>,
+        else_: Option<Box<Node>>,
+    },
+    PatternMatch {
+        value: Box<Node>,
+        pattern: Box<Node>,
+        raise: bool, // true: `expr => pattern`, false: `expr in pattern`
+    },
     Begin {
         body: Box<Node>,
         rescue: Vec<RescueEntry>, // (ex_class_list, ex_param)
@@ -79,6 +90,8 @@ pub enum NodeKind {
     Lambda(BlockInfo),
     Break(Box<Node>),
     Next(Box<Node>),
+    Redo,
+    Retry,
     Return(Box<Node>),
     Yield(ArgList),
     MethodDef(IdentId, Vec<FormalParam>, Box<Node>, LvarCollector), // id, params, body
@@ -112,6 +125,29 @@ pub enum NodeKind {
     Defined(Box<Node>),
     Super(Option<ArgList>),
     AliasMethod(Box<Node>, Box<Node>), // (new_method, old_method)
+    UndefMethod(Vec<Node>),
+
+    ArrayPattern {
+        const_: Option<Box<Node>>,
+        pre: Vec<Node>,
+        rest: Option<Option<IdentId>>, // None: no rest, Some(None): `*`, Some(Some(id)): `*id`
+        post: Vec<Node>,
+    },
+    FindPattern {
+        const_: Option<Box<Node>>,
+        pre: Option<IdentId>,
+        mid: Vec<Node>,
+        post: Option<IdentId>,
+    },
+    HashPattern {
+        const_: Option<Box<Node>>,
+        pairs: Vec<(IdentId, Option<Node>)>, // Vec<KEY, PATTERN>
+        rest: HashPatternRest,
+    },
+    AltPattern(Vec<Node>),
+    CapturePattern(Box<Node>, IdentId), // pattern => id
+    PinPattern(Box<Node>),
+    VarPattern(IdentId),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -235,6 +271,33 @@ impl CaseBranch {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaseInBranch {
+    pub pattern: Box<Node>,
+    pub guard: Option<Box<Node>>,
+    pub body: Box<Node>,
+}
+
+impl CaseInBranch {
+    pub(crate) fn new(pattern: Node, guard: Option<Node>, body: Node) -> Self {
+        CaseInBranch {
+            pattern: Box::new(pattern),
+            guard: guard.map(Box::new),
+            body: Box::new(body),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HashPatternRest {
+    /// No rest pattern, or `**`.
+    None,
+    /// `**id`
+    Bind(IdentId),
+    /// `**nil`
+    Nil,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum BinOp {
     Add,
@@ -363,12 +426,16 @@ impl Node {
         Node::new(NodeKind::Float(num), loc)
     }
 
+    pub(crate) fn new_rational(num: BigRational, loc: Loc) -> Self {
+        Node::new(NodeKind::Rational(num), loc)
+    }
+
     pub(crate) fn new_imaginary(num: NReal, loc: Loc) -> Self {
         Node::new(NodeKind::Imaginary(num), loc)
     }
 
-    pub(crate) fn new_string(s: String, loc: Loc) -> Self {
-        Node::new(NodeKind::String(s), loc)
+    pub(crate) fn new_string(s: impl Into<Vec<u8>>, loc: Loc) -> Self {
+        Node::new(NodeKind::String(s.into()), loc)
     }
 
     pub(crate) fn new_array(nodes: Vec<Node>, loc: Loc) -> Self {
@@ -427,6 +494,10 @@ impl Node {
         Node::new(NodeKind::AliasMethod(Box::new(new), Box::new(old)), loc)
     }
 
+    pub(crate) fn new_undef(names: Vec<Node>, loc: Loc) -> Self {
+        Node::new(NodeKind::UndefMethod(names), loc)
+    }
+
     pub(crate) fn new_comp_stmt(nodes: Vec<Node>, mut loc: Loc) -> Self {
         if let Some(node) = nodes.first() {
             loc = node.loc();
@@ -662,6 +733,104 @@ impl Node {
         )
     }
 
+    pub(crate) fn new_case_in(
+        cond: Node,
+        in_: Vec<CaseInBranch>,
+        else_: Option<Node>,
+        loc: Loc,
+    ) -> Self {
+        Node::new(
+            NodeKind::CaseIn {
+                cond: Box::new(cond),
+                in_,
+                else_: else_.map(Box::new),
+            },
+            loc,
+        )
+    }
+
+    pub(crate) fn new_pattern_match(value: Node, pattern: Node, raise: bool) -> Self {
+        let loc = value.loc().merge(pattern.loc());
+        Node::new(
+            NodeKind::PatternMatch {
+                value: Box::new(value),
+                pattern: Box::new(pattern),
+                raise,
+            },
+            loc,
+        )
+    }
+
+    pub(crate) fn new_array_pattern(
+        const_: Option<Node>,
+        pre: Vec<Node>,
+        rest: Option<Option<IdentId>>,
+        post: Vec<Node>,
+        loc: Loc,
+    ) -> Self {
+        Node::new(
+            NodeKind::ArrayPattern {
+                const_: const_.map(Box::new),
+                pre,
+                rest,
+                post,
+            },
+            loc,
+        )
+    }
+
+    pub(crate) fn new_find_pattern(
+        const_: Option<Node>,
+        pre: Option<IdentId>,
+        mid: Vec<Node>,
+        post: Option<IdentId>,
+        loc: Loc,
+    ) -> Self {
+        Node::new(
+            NodeKind::FindPattern {
+                const_: const_.map(Box::new),
+                pre,
+                mid,
+                post,
+            },
+            loc,
+        )
+    }
+
+    pub(crate) fn new_hash_pattern(
+        const_: Option<Node>,
+        pairs: Vec<(IdentId, Option<Node>)>,
+        rest: HashPatternRest,
+        loc: Loc,
+    ) -> Self {
+        Node::new(
+            NodeKind::HashPattern {
+                const_: const_.map(Box::new),
+                pairs,
+                rest,
+            },
+            loc,
+        )
+    }
+
+    pub(crate) fn new_alt_pattern(alts: Vec<Node>, loc: Loc) -> Self {
+        Node::new(NodeKind::AltPattern(alts), loc)
+    }
+
+    pub(crate) fn new_capture_pattern(pattern: Node, id: IdentId, loc: Loc) -> Self {
+        let loc = pattern.loc().merge(loc);
+        Node::new(NodeKind::CapturePattern(Box::new(pattern), id), loc)
+    }
+
+    pub(crate) fn new_pin_pattern(node: Node, loc: Loc) -> Self {
+        let loc = loc.merge(node.loc());
+        Node::new(NodeKind::PinPattern(Box::new(node)), loc)
+    }
+
+    pub(crate) fn new_var_pattern(id: IdentId, loc: Loc) -> Self {
+        Node::new(NodeKind::VarPattern(id), loc)
+    }
+
     pub(crate) fn new_begin(
         body: Node,
         rescue: Vec<RescueEntry>, //Vec<(Vec<Node>, Box<Node>)>,
@@ -702,6 +871,14 @@ impl Node {
         Node::new(NodeKind::Next(Box::new(val)), loc)
     }
 
+    pub(crate) fn new_redo(loc: Loc) -> Self {
+        Node::new(NodeKind::Redo, loc)
+    }
+
+    pub(crate) fn new_retry(loc: Loc) -> Self {
+        Node::new(NodeKind::Retry, loc)
+    }
+
     pub(crate) fn new_return(val: Node, loc: Loc) -> Self {
         Node::new(NodeKind::Return(Box::new(val)), loc)
     }
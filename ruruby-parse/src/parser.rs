@@ -8,6 +8,8 @@ mod expression;
 mod flow_control;
 mod lexer;
 mod literals;
+mod pattern;
+pub use lexer::magic_comment;
 use lexer::*;
 
 pub trait LocalsContext: Copy + Sized {
@@ -638,12 +640,16 @@ fn parse(
 ) -> Result<ParseResult, RubyError> {
     match Parser::new(&code, path.clone(), extern_context, parse_context) {
         Ok((node, lvar_collector, tok)) => {
+            let frozen_string_literal = frozen_string_literal(&code);
+            let source_encoding = magic_comment(&code, "encoding");
             let source_info = SourceInfoRef::new(SourceInfo::new(path, code));
             if tok.is_eof() {
                 let result = ParseResult {
                     node,
                     lvar_collector,
                     source_info,
+                    frozen_string_literal,
+                    source_encoding,
                 };
                 Ok(result)
             } else {
@@ -663,6 +669,15 @@ pub struct ParseResult {
     pub node: Node,
     pub lvar_collector: LvarCollector,
     pub source_info: SourceInfoRef,
+    /// true if `# frozen_string_literal: true` magic comment exists.
+    pub frozen_string_literal: bool,
+    /// The source encoding given by `# encoding: ...` magic comment.
+    pub source_encoding: Option<String>,
+}
+
+/// Scan the leading comment lines of *code* for `frozen_string_literal: true`.
+fn frozen_string_literal(code: &str) -> bool {
+    magic_comment(code, "frozen_string_literal").is_some_and(|val| val.eq_ignore_ascii_case("true"))
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -85,7 +85,16 @@ impl<'a, A: LocalsContext> Parser<'a, A> {
         // EXPR : NOT
         // | EXPR [no term] and NOT
         // | EXPR [no term] or NOT
+        // | ARG [no term] => PATTERN
+        // | ARG [no term] in PATTERN
         let mut node = self.parse_not()?;
+        if self.consume_punct_no_term(Punct::FatArrow)? {
+            let pattern = self.parse_pattern_top()?;
+            node = Node::new_pattern_match(node, pattern, true);
+        } else if self.consume_reserved_no_skip_line_term(Reserved::In)? {
+            let pattern = self.parse_pattern_top()?;
+            node = Node::new_pattern_match(node, pattern, false);
+        }
         loop {
             if self.consume_reserved_no_skip_line_term(Reserved::And)? {
                 let rhs = self.parse_not()?;
@@ -354,7 +363,7 @@ impl<'a, A: LocalsContext> Parser<'a, A> {
         Ok(lhs)
     }
 
-    fn parse_arg_shift(&mut self) -> Result<Node, ParseErr> {
+    pub(super) fn parse_arg_shift(&mut self) -> Result<Node, ParseErr> {
         let mut lhs = self.parse_arg_add()?;
         loop {
             if self.consume_punct_no_term(Punct::Shl)? {
@@ -412,7 +421,10 @@ impl<'a, A: LocalsContext> Parser<'a, A> {
             let save2 = self.save_state();
             let loc = self.prev_loc();
             match self.peek_no_term()?.kind {
-                TokenKind::IntegerLit(_) | TokenKind::FloatLit(_) | TokenKind::BignumLit(_) => {
+                TokenKind::IntegerLit(_)
+                | TokenKind::FloatLit(_)
+                | TokenKind::BignumLit(_)
+                | TokenKind::RationalLit(_) => {
                     self.parse_primary(true)?;
                     if self.consume_punct_no_term(Punct::DMul)? {
                         self.restore_state(save2);
@@ -629,7 +641,16 @@ impl<'a, A: LocalsContext> Parser<'a, A> {
                     }
                     "__FILE__" => {
                         let file = self.path.to_string_lossy();
-                        return Ok(Node::new_string(file.into(), loc));
+                        return Ok(Node::new_string(file.into_owned(), loc));
+                    }
+                    "__ENCODING__" => {
+                        // Encoding.find(<source encoding>)
+                        let encoding = Node::new_const("Encoding", true, loc);
+                        let mut arglist = ArgList::default();
+                        let name = self.lexer.source_encoding();
+                        arglist.args.push(Node::new_string(name, loc));
+                        let method = self.get_ident_id("find");
+                        return Ok(Node::new_send(encoding, method, arglist, false, loc));
                     }
                     _ => {}
                 };
@@ -681,6 +702,7 @@ impl<'a, A: LocalsContext> Parser<'a, A> {
             TokenKind::IntegerLit(num) => Ok(Node::new_integer(num, loc)),
             TokenKind::BignumLit(num) => Ok(Node::new_bignum(num, loc)),
             TokenKind::FloatLit(num) => Ok(Node::new_float(num, loc)),
+            TokenKind::RationalLit(num) => Ok(Node::new_rational(num, loc)),
             TokenKind::ImaginaryLit(num) => Ok(Node::new_imaginary(num, loc)),
             TokenKind::StringLit(s) => Ok(self.parse_string_literal(&s)?),
             TokenKind::CommandLit(s) => {
@@ -702,6 +724,7 @@ impl<'a, A: LocalsContext> Parser<'a, A> {
                     },
                     TokenKind::BignumLit(num) => Ok(Node::new_bignum(-num, loc)),
                     TokenKind::FloatLit(num) => Ok(Node::new_float(-num, loc)),
+                    TokenKind::RationalLit(num) => Ok(Node::new_rational(-num, loc)),
                     _ => unreachable!(),
                 },
                 Punct::LParen => {
@@ -767,6 +790,8 @@ impl<'a, A: LocalsContext> Parser<'a, A> {
                 Reserved::Return => self.parse_return(),
                 Reserved::Break => self.parse_break(),
                 Reserved::Next => self.parse_next(),
+                Reserved::Redo => Ok(Node::new_redo(loc)),
+                Reserved::Retry => Ok(Node::new_retry(loc)),
                 Reserved::Begin => self.parse_begin(),
                 Reserved::Defined => {
                     if self.consume_punct_no_term(Punct::LParen)? {
@@ -784,6 +809,14 @@ impl<'a, A: LocalsContext> Parser<'a, A> {
                     let loc = loc.merge(self.prev_loc());
                     Ok(Node::new_alias(new_name, old_name, loc))
                 }
+                Reserved::Undef => {
+                    let mut names = vec![self.alias_name()?];
+                    while self.consume_punct_no_term(Punct::Comma)? {
+                        names.push(self.alias_name()?);
+                    }
+                    let loc = loc.merge(self.prev_loc());
+                    Ok(Node::new_undef(names, loc))
+                }
                 Reserved::Super => self.parse_super(),
                 _ => Err(error_unexpected(
                     loc,
@@ -843,6 +876,9 @@ impl<'a, A: LocalsContext> Parser<'a, A> {
             } else if self.consume_punct(Punct::BitAnd)? {
                 // block argument
                 arglist.block = Some(Box::new(self.parse_arg()?));
+            } else if let Some(id) = self.consume_reserved_label()? {
+                // keyword args whose name is a reserved word (e.g. `if:`)
+                arglist.kw_args.push((id, self.parse_arg()?));
             } else {
                 let node = self.parse_arg()?;
                 let loc = node.loc();
@@ -897,6 +933,15 @@ impl<'a, A: LocalsContext> Parser<'a, A> {
         Ok(arglist)
     }
 
+    /// If the next tokens are a reserved word and ':', consume them and return Some(id).
+    fn consume_reserved_label(&mut self) -> Result<Option<IdentId>, ParseErr> {
+        if let TokenKind::Reserved(_) = self.peek()?.kind {
+            self.consume_label()
+        } else {
+            Ok(None)
+        }
+    }
+
     pub(super) fn parse_begin(&mut self) -> Result<Node, ParseErr> {
         // begin式 :: "begin"  複合文  rescue節*  else節?  ensure節?  "end"
         // rescue節 :: "rescue" [行終端子禁止] 例外クラスリスト?  例外変数代入?  then節
@@ -121,6 +121,15 @@ impl<'a, A: LocalsContext> Parser<'a, A> {
             None
         };
         self.consume_term()?;
+        if self.peek()?.kind == TokenKind::Reserved(Reserved::In) {
+            let cond = match cond {
+                Some(cond) => cond,
+                None => return Err(error_unexpected(self.loc(), "Expect expression.")),
+            };
+            let node = self.parse_case_in(cond, loc)?;
+            self.suppress_mul_assign = old;
+            return Ok(node);
+        }
         let mut when_ = vec![];
         while self.consume_reserved(Reserved::When)? {
             let arg = self.parse_mul_assign_rhs(None)?;
@@ -1,6 +1,6 @@
 use super::*;
 //use crate::value::real::Real;
-use num::{BigInt, ToPrimitive};
+use num::{BigInt, BigRational, ToPrimitive};
 use ruruby_common::ParseErrKind;
 use std::ops::Range;
 
@@ -39,9 +39,45 @@ pub enum VarKind {
     GlobalVar,
 }
 #[derive(Debug, Clone, PartialEq)]
-enum InterpolateState {
-    Finished(String),
-    NewInterpolation(String, usize), // (string, paren_level)
+enum InterpolateState<T> {
+    Finished(T),
+    NewInterpolation(T, usize), // (string, paren_level)
+}
+
+/// Scan the leading comment lines of `code` for the magic comment `key: value`,
+/// and return the value.
+///
+/// Keys are case-insensitive and '-' is treated as '_'. `coding` is an alias of `encoding`.
+pub fn magic_comment(code: &str, key: &str) -> Option<String> {
+    for line in code.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let comment = line.strip_prefix('#')?;
+        for directive in comment.split(';') {
+            let directive = directive
+                .trim()
+                .trim_matches(|c| c == '-' || c == '*')
+                .trim();
+            if let Some((k, val)) = directive.split_once(':') {
+                let k = k.trim().to_ascii_lowercase().replace('-', "_");
+                let k = match k.as_str() {
+                    "coding" => "encoding",
+                    k => k,
+                };
+                if k == key {
+                    return Some(val.trim().to_string());
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Push `ch` to `s` as UTF-8 bytes.
+fn push_char(s: &mut Vec<u8>, ch: char) {
+    s.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes());
 }
 
 impl<'a> Lexer<'a> {
@@ -67,6 +103,11 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    /// The source encoding given by the magic comment. (default: UTF-8)
+    pub(crate) fn source_encoding(&self) -> String {
+        magic_comment(self.code, "encoding").unwrap_or_else(|| "UTF-8".to_string())
+    }
+
     fn error_unexpected(&self, pos: usize) -> ParseErr {
         let loc = Loc(pos, pos);
         ParseErr(
@@ -630,6 +671,17 @@ impl<'a> Lexer<'a> {
                 break;
             }
         }
+        if self.peek() == Some('r') && !self.ident_char_follows(1) {
+            self.get()?;
+            let frac_len = s.find('.').map_or(0, |i| s.len() - i - 1);
+            let digits = s.replace('.', "");
+            let numer = match BigInt::parse_bytes(digits.as_bytes(), 10) {
+                Some(n) => n,
+                None => return Err(Self::error_parse("Invalid number literal.", self.pos)),
+            };
+            let denom = num::pow(BigInt::from(10), frac_len);
+            return Ok(self.new_rationallit(BigRational::new(numer, denom)));
+        }
         if self.consume('e') || self.consume('E') {
             s.push('e');
             if !self.consume('+') && self.consume('-') {
@@ -750,8 +802,8 @@ impl<'a> Lexer<'a> {
         open: Option<char>,
         term: Option<char>,
         mut level: usize,
-    ) -> Result<InterpolateState, ParseErr> {
-        let mut s = "".to_string();
+    ) -> Result<InterpolateState<Vec<u8>>, ParseErr> {
+        let mut s = vec![];
         loop {
             let ch = match self.get() {
                 Ok(c) => c,
@@ -765,14 +817,14 @@ impl<'a> Lexer<'a> {
             };
             match ch {
                 c if open == Some(c) => {
-                    s.push(c);
+                    push_char(&mut s, c);
                     level += 1;
                 }
                 c if Some(c) == term => {
                     if level == 0 {
                         return Ok(InterpolateState::Finished(s));
                     } else {
-                        s.push(c);
+                        push_char(&mut s, c);
                         level -= 1;
                     }
                 }
@@ -781,22 +833,22 @@ impl<'a> Lexer<'a> {
                     if self.consume_newline() {
                         continue;
                     };
-                    s.push(self.read_escaped_char()?);
+                    self.read_escape_sequence(&mut s)?;
                 }
                 '#' => match self.peek() {
                     // string interpolation
                     Some(ch) if ch == '{' || ch == '$' || ch == '@' => {
                         return Ok(InterpolateState::NewInterpolation(s, level))
                     }
-                    _ => s.push('#'),
+                    _ => s.push(b'#'),
                 },
                 '\n' => {
                     if self.heredoc_pos > self.pos {
                         self.pos = self.heredoc_pos;
                     }
-                    s.push('\n')
+                    s.push(b'\n')
                 }
-                c => s.push(c),
+                c => push_char(&mut s, c),
             }
         }
     }
@@ -884,7 +936,7 @@ impl<'a> Lexer<'a> {
     }
 
     /// Scan as regular expression.
-    fn read_regexp_sub(&mut self) -> Result<InterpolateState, ParseErr> {
+    fn read_regexp_sub(&mut self) -> Result<InterpolateState<String>, ParseErr> {
         let mut s = "".to_string();
         let mut char_class = 0;
         loop {
@@ -987,6 +1039,26 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    /// Read an escape sequence in a double-quoted string and push it to `s`.
+    ///
+    /// `\xNN` and octal escapes denote a raw byte, not a code point.
+    fn read_escape_sequence(&mut self, s: &mut Vec<u8>) -> Result<(), ParseErr> {
+        match self.peek() {
+            Some('x') => {
+                self.get()?;
+                let c1 = self.expect_hex()?;
+                let c2 = self.expect_hex()?;
+                s.push((c1 * 16 + c2) as u8);
+            }
+            Some(c) if ('0'..='7').contains(&c) => {
+                self.get()?;
+                s.push(self.consume_tri_octal(c).unwrap());
+            }
+            _ => push_char(s, self.read_escaped_char()?),
+        }
+        Ok(())
+    }
+
     fn read_escaped_char(&mut self) -> Result<char, ParseErr> {
         let ch = match self.get()? {
             c @ '\'' | c @ '"' | c @ '?' | c @ '\\' => c,
@@ -1126,6 +1198,18 @@ impl<'a> Lexer<'a> {
         iter.next()
     }
 
+    /// Check whether the char at `offset` from the cursor can continue an identifier.
+    fn ident_char_follows(&self, offset: usize) -> bool {
+        match self
+            .code
+            .get(self.pos..)
+            .and_then(|s| s.chars().nth(offset))
+        {
+            Some(ch) => ch.is_ascii_alphanumeric() || ch == '_' || !ch.is_ascii(),
+            None => false,
+        }
+    }
+
     /// Get one char and move to the next.
     /// Returns Ok(char) or ParseErr if the cursor reached EOF.
     fn get(&mut self) -> Result<char, ParseErr> {
@@ -1332,15 +1416,19 @@ impl<'a> Lexer<'a> {
         Token::new_floatlit(num, self.cur_loc())
     }
 
+    fn new_rationallit(&self, num: BigRational) -> Token {
+        Token::new_rationallit(num, self.cur_loc())
+    }
+
     fn new_imaginarylit(&self, num: NReal) -> Token {
         Token::new_imaginarylit(num, self.cur_loc())
     }
 
-    fn new_stringlit(&self, string: impl Into<String>) -> Token {
+    fn new_stringlit(&self, string: impl Into<Vec<u8>>) -> Token {
         Annot::new(TokenKind::StringLit(string.into()), self.cur_loc())
     }
 
-    fn new_commandlit(&self, string: impl Into<String>) -> Token {
+    fn new_commandlit(&self, string: impl Into<Vec<u8>>) -> Token {
         Annot::new(TokenKind::CommandLit(string.into()), self.cur_loc())
     }
 
@@ -1348,7 +1436,7 @@ impl<'a> Lexer<'a> {
         Annot::new(TokenKind::Punct(punc), self.cur_loc())
     }
 
-    fn new_open_string(&self, s: String, delimiter: Option<char>, level: usize) -> Token {
+    fn new_open_string(&self, s: Vec<u8>, delimiter: Option<char>, level: usize) -> Token {
         Token::new_open_string(s, delimiter, level, self.cur_loc())
     }
 
@@ -1356,7 +1444,7 @@ impl<'a> Lexer<'a> {
         Token::new_open_reg(s, self.cur_loc())
     }
 
-    fn new_open_command(&self, s: String, delimiter: Option<char>, level: usize) -> Token {
+    fn new_open_command(&self, s: Vec<u8>, delimiter: Option<char>, level: usize) -> Token {
         Token::new_open_command(s, delimiter, level, self.cur_loc())
     }
 
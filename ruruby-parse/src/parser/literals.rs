@@ -12,18 +12,18 @@ impl<'a, A: LocalsContext> Parser<'a, A> {
 
     /// Parse string literals.
     /// Adjacent string literals are to be combined.
-    pub(super) fn parse_string_literal(&mut self, s: &str) -> Result<Node, ParseErr> {
+    pub(super) fn parse_string_literal(&mut self, s: &[u8]) -> Result<Node, ParseErr> {
         let loc = self.prev_loc();
-        let mut s = s.to_string();
+        let mut s = s.to_vec();
         loop {
             match self.peek_no_term()?.kind {
                 TokenKind::StringLit(next_s) => {
                     self.get()?;
-                    s += &next_s;
+                    s.extend_from_slice(&next_s);
                 }
                 TokenKind::OpenString(next_s, delimiter, level) => {
                     self.get()?;
-                    s += &next_s;
+                    s.extend_from_slice(&next_s);
                     return self.parse_interporated_string_literal(&s, delimiter, level);
                 }
                 _ => break,
@@ -34,12 +34,12 @@ impl<'a, A: LocalsContext> Parser<'a, A> {
 
     pub(super) fn parse_interporated_string_literal(
         &mut self,
-        s: &str,
+        s: &[u8],
         delimiter: Option<char>,
         level: usize,
     ) -> Result<Node, ParseErr> {
         let start_loc = self.prev_loc();
-        let mut nodes = vec![Node::new_string(s.to_string(), start_loc)];
+        let mut nodes = vec![Node::new_string(s, start_loc)];
         loop {
             self.parse_template(&mut nodes)?;
             let tok = self
@@ -52,12 +52,12 @@ impl<'a, A: LocalsContext> Parser<'a, A> {
                         match self.peek_no_term()?.kind {
                             TokenKind::StringLit(next_s) => {
                                 let t = self.get()?;
-                                s += &next_s;
+                                s.extend_from_slice(&next_s);
                                 loc = loc.merge(t.loc);
                             }
                             TokenKind::OpenString(next_s, _, _) => {
                                 let t = self.get()?;
-                                s += &next_s;
+                                s.extend_from_slice(&next_s);
                                 loc = loc.merge(t.loc);
                                 break;
                             }
@@ -215,7 +215,7 @@ impl<'a, A: LocalsContext> Parser<'a, A> {
                 let loc = symbol_loc.merge(node.loc());
                 return Ok(Node::new_send_noarg(node, method, false, loc));
             }
-            TokenKind::StringLit(ident) => IdentId::get_id(ident),
+            TokenKind::StringLit(ident) => IdentId::get_id(&String::from_utf8_lossy(ident)),
             _ => return Err(error_unexpected(symbol_loc, "Expect identifier or string.")),
         };
         Ok(Node::new_symbol(id, loc.merge(self.prev_loc())))
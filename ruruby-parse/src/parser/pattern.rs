@@ -0,0 +1,389 @@
+use super::*;
+
+impl<'a, A: LocalsContext> Parser<'a, A> {
+    /// Parse `in` clauses of case expression.
+    ///
+    /// case <cond>
+    /// in <pattern> [if|unless <guard>] then
+    ///   COMP_STMT
+    /// ...
+    /// [else
+    ///   COMP_STMT]
+    /// end
+    pub(super) fn parse_case_in(&mut self, cond: Node, loc: Loc) -> Result<Node, ParseErr> {
+        let mut in_ = vec![];
+        while self.consume_reserved(Reserved::In)? {
+            let pattern = self.parse_pattern_top()?;
+            let guard = self.parse_pattern_guard()?;
+            self.parse_then()?;
+            let body = self.parse_comp_stmt()?;
+            in_.push(CaseInBranch::new(pattern, guard, body));
+        }
+        let else_ = if self.consume_reserved(Reserved::Else)? {
+            Some(self.parse_comp_stmt()?)
+        } else {
+            None
+        };
+        self.expect_reserved(Reserved::End)?;
+        Ok(Node::new_case_in(
+            cond,
+            in_,
+            else_,
+            loc.merge(self.prev_loc()),
+        ))
+    }
+
+    fn parse_pattern_guard(&mut self) -> Result<Option<Node>, ParseErr> {
+        if self.consume_reserved_no_skip_line_term(Reserved::If)? {
+            Ok(Some(self.parse_expr()?))
+        } else if self.consume_reserved_no_skip_line_term(Reserved::Unless)? {
+            let loc = self.prev_loc();
+            let cond = self.parse_expr()?;
+            Ok(Some(Node::new_unop(UnOp::Not, cond, loc)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Parse a top-level pattern.
+    /// Array patterns and hash patterns without brackets are allowed here.
+    /// cf: `in a, *rest` / `in name:, age: Integer`
+    pub(super) fn parse_pattern_top(&mut self) -> Result<Node, ParseErr> {
+        let loc = self.loc();
+        if self.peek_label()? || self.peek_punct_no_term(Punct::DMul) {
+            return self.parse_hash_pattern(None, None, loc);
+        }
+        if self.peek_punct_no_term(Punct::Mul) {
+            return self.parse_array_pattern(None, None, vec![], loc);
+        }
+        let pattern = self.parse_pattern()?;
+        if self.consume_punct_no_term(Punct::Comma)? {
+            return self.parse_array_pattern(None, None, vec![pattern], loc);
+        }
+        Ok(pattern)
+    }
+
+    /// Parse a pattern.
+    ///
+    /// PATTERN : ALT-PATTERN [=> IDENT]
+    /// ALT-PATTERN : PRIMARY-PATTERN [| PRIMARY-PATTERN]*
+    fn parse_pattern(&mut self) -> Result<Node, ParseErr> {
+        let loc = self.loc();
+        let mut alts = vec![self.parse_pattern_primary()?];
+        while self.consume_punct_no_term(Punct::BitOr)? {
+            alts.push(self.parse_pattern_primary()?);
+        }
+        let pattern = if alts.len() == 1 {
+            alts.pop().unwrap()
+        } else {
+            Node::new_alt_pattern(alts, loc.merge(self.prev_loc()))
+        };
+        if self.consume_punct_no_term(Punct::FatArrow)? {
+            let id = self.expect_ident()?;
+            self.add_local_var_if_new(id);
+            return Ok(Node::new_capture_pattern(pattern, id, self.prev_loc()));
+        }
+        Ok(pattern)
+    }
+
+    fn parse_pattern_primary(&mut self) -> Result<Node, ParseErr> {
+        let tok = self.peek()?;
+        let loc = tok.loc();
+        match tok.kind {
+            TokenKind::Punct(Punct::LBracket) => {
+                self.get()?;
+                self.parse_array_pattern(None, Punct::RBracket, vec![], loc)
+            }
+            TokenKind::Punct(Punct::LBrace) => {
+                self.get()?;
+                self.parse_hash_pattern(None, Punct::RBrace, loc)
+            }
+            TokenKind::Punct(Punct::LParen) => {
+                self.get()?;
+                let pattern = self.parse_pattern()?;
+                self.expect_punct(Punct::RParen)?;
+                Ok(pattern)
+            }
+            TokenKind::Punct(Punct::BitXor) => {
+                self.get()?;
+                self.parse_pin_pattern(loc)
+            }
+            TokenKind::Punct(Punct::Scope) | TokenKind::Const(_) => self.parse_const_pattern(),
+            TokenKind::Ident(name) => match name.as_str() {
+                "nil" | "true" | "false" | "self" | "__LINE__" | "__FILE__" => {
+                    self.parse_value_pattern()
+                }
+                _ => {
+                    self.get()?;
+                    let id = self.get_ident_id(&name);
+                    self.add_local_var_if_new(id);
+                    Ok(Node::new_var_pattern(id, loc))
+                }
+            },
+            _ => self.parse_value_pattern(),
+        }
+    }
+
+    /// Parse a value pattern, which is matched by `===`.
+    fn parse_value_pattern(&mut self) -> Result<Node, ParseErr> {
+        let lhs = self.parse_arg_shift()?;
+        if self.consume_punct_no_term(Punct::Range2)? {
+            let rhs = self.parse_arg_shift()?;
+            let loc = lhs.loc().merge(rhs.loc());
+            Ok(Node::new_range(lhs, rhs, false, loc))
+        } else if self.consume_punct_no_term(Punct::Range3)? {
+            let rhs = self.parse_arg_shift()?;
+            let loc = lhs.loc().merge(rhs.loc());
+            Ok(Node::new_range(lhs, rhs, true, loc))
+        } else {
+            Ok(lhs)
+        }
+    }
+
+    /// Parse a pinned expression. cf: `^a` / `^@a` / `^$a` / `^(expr)`
+    fn parse_pin_pattern(&mut self, loc: Loc) -> Result<Node, ParseErr> {
+        let tok = self.get()?;
+        let node = match tok.kind {
+            TokenKind::Ident(name) => {
+                let id = self.get_ident_id(&name);
+                if !self.is_local_var(id) {
+                    return Err(error_unexpected(
+                        tok.loc,
+                        format!("{}: no such local variable", name),
+                    ));
+                }
+                Node::new_lvar(id, tok.loc)
+            }
+            TokenKind::InstanceVar(name) => Node::new_instance_var(&name, tok.loc),
+            TokenKind::GlobalVar(name) => Node::new_global_var(&name, tok.loc),
+            TokenKind::ClassVar(name) => Node::new_class_var(&name, tok.loc),
+            TokenKind::Punct(Punct::LParen) => {
+                let node = self.parse_expr()?;
+                self.expect_punct(Punct::RParen)?;
+                node
+            }
+            _ => return Err(error_unexpected(tok.loc, "Unexpected token after '^'.")),
+        };
+        Ok(Node::new_pin_pattern(node, loc))
+    }
+
+    /// Parse a constant, optionally followed by an array pattern or a hash pattern.
+    /// cf: `Integer` / `Point(x, y)` / `Point(x:, y:)` / `Point[x, y]`
+    fn parse_const_pattern(&mut self) -> Result<Node, ParseErr> {
+        let loc = self.loc();
+        let mut const_ = if self.consume_punct(Punct::Scope)? {
+            let name = self.expect_const()?;
+            Node::new_const(&name, true, loc.merge(self.prev_loc()))
+        } else {
+            let name = self.expect_const()?;
+            Node::new_const(&name, false, loc)
+        };
+        while self.consume_punct_no_term(Punct::Scope)? {
+            let name = self.expect_const()?;
+            const_ = Node::new_scope(const_, &name, loc.merge(self.prev_loc()));
+        }
+        let (term, const_) = if self.lexer.trailing_lparen() {
+            self.expect_punct(Punct::LParen)?;
+            (Punct::RParen, const_)
+        } else if !self.lexer.trailing_space() && self.consume_punct_no_term(Punct::LBracket)? {
+            (Punct::RBracket, const_)
+        } else if self.consume_punct_no_term(Punct::Range2)? {
+            let rhs = self.parse_arg_shift()?;
+            let loc = const_.loc().merge(rhs.loc());
+            return Ok(Node::new_range(const_, rhs, false, loc));
+        } else if self.consume_punct_no_term(Punct::Range3)? {
+            let rhs = self.parse_arg_shift()?;
+            let loc = const_.loc().merge(rhs.loc());
+            return Ok(Node::new_range(const_, rhs, true, loc));
+        } else {
+            return Ok(const_);
+        };
+        if self.peek_label()? || self.peek_punct_no_term(Punct::DMul) {
+            self.parse_hash_pattern(const_, term, loc)
+        } else {
+            self.parse_array_pattern(const_, term, vec![], loc)
+        }
+    }
+
+    /// Parse elements of an array pattern or a find pattern.
+    /// If `term` is None, the pattern is a top-level pattern without brackets.
+    fn parse_array_pattern(
+        &mut self,
+        const_: impl Into<Option<Node>>,
+        term: impl Into<Option<Punct>>,
+        mut pre: Vec<Node>,
+        loc: Loc,
+    ) -> Result<Node, ParseErr> {
+        let const_ = const_.into();
+        let term = term.into();
+        let mut post = vec![];
+        let mut rest = None;
+        let mut find_post = None;
+        let is_empty = match term {
+            Some(term) => self.consume_punct(term)?,
+            None => false,
+        };
+        if !is_empty {
+            loop {
+                if let Some(term) = term {
+                    // allow trailing comma.
+                    if self.peek()?.kind == TokenKind::Punct(term) {
+                        break;
+                    }
+                }
+                if self.consume_punct(Punct::Mul)? {
+                    let id = self.parse_rest_ident()?;
+                    if rest.is_none() {
+                        rest = Some(id);
+                    } else if find_post.is_none() {
+                        find_post = Some(id);
+                    } else {
+                        return Err(error_unexpected(
+                            self.prev_loc(),
+                            "Multiple rest patterns are not allowed.",
+                        ));
+                    }
+                } else {
+                    if find_post.is_some() {
+                        return Err(error_unexpected(
+                            self.loc(),
+                            "Unexpected pattern after the rest pattern.",
+                        ));
+                    }
+                    let pattern = self.parse_pattern()?;
+                    if rest.is_none() {
+                        pre.push(pattern);
+                    } else {
+                        post.push(pattern);
+                    }
+                }
+                let comma = if term.is_some() {
+                    self.consume_punct(Punct::Comma)?
+                } else {
+                    self.consume_punct_no_term(Punct::Comma)?
+                };
+                if !comma {
+                    break;
+                }
+            }
+            if let Some(term) = term {
+                self.expect_punct(term)?;
+            }
+        }
+        let loc = loc.merge(self.prev_loc());
+        match (rest, find_post) {
+            (Some(pre_rest), Some(post_rest)) => {
+                if !pre.is_empty() {
+                    return Err(error_unexpected(loc, "Invalid find pattern."));
+                }
+                Ok(Node::new_find_pattern(
+                    const_, pre_rest, post, post_rest, loc,
+                ))
+            }
+            _ => Ok(Node::new_array_pattern(const_, pre, rest, post, loc)),
+        }
+    }
+
+    /// Parse elements of a hash pattern.
+    /// If `term` is None, the pattern is a top-level pattern without braces.
+    fn parse_hash_pattern(
+        &mut self,
+        const_: impl Into<Option<Node>>,
+        term: impl Into<Option<Punct>>,
+        loc: Loc,
+    ) -> Result<Node, ParseErr> {
+        let const_ = const_.into();
+        let term = term.into();
+        let mut pairs = vec![];
+        let mut rest = HashPatternRest::None;
+        let is_empty = match term {
+            Some(term) => self.consume_punct(term)?,
+            None => false,
+        };
+        if !is_empty {
+            loop {
+                if self.consume_punct(Punct::DMul)? {
+                    rest = match self.peek_no_term()?.kind {
+                        TokenKind::Ident(name) if name == "nil" => {
+                            self.get()?;
+                            HashPatternRest::Nil
+                        }
+                        _ => match self.parse_rest_ident()? {
+                            Some(id) => HashPatternRest::Bind(id),
+                            None => HashPatternRest::None,
+                        },
+                    };
+                } else {
+                    let key = match self.consume_label()? {
+                        Some(key) => key,
+                        None => return Err(error_unexpected(self.loc(), "Expect a key.")),
+                    };
+                    let pattern = if self.is_pattern_end()? {
+                        // `key:` binds the value to a local variable `key`.
+                        self.add_local_var_if_new(key);
+                        None
+                    } else {
+                        Some(self.parse_pattern()?)
+                    };
+                    pairs.push((key, pattern));
+                }
+                let comma = if term.is_some() {
+                    self.consume_punct(Punct::Comma)?
+                } else {
+                    self.consume_punct_no_term(Punct::Comma)?
+                };
+                if !comma {
+                    break;
+                }
+            }
+            if let Some(term) = term {
+                self.expect_punct(term)?;
+            }
+        }
+        let loc = loc.merge(self.prev_loc());
+        Ok(Node::new_hash_pattern(const_, pairs, rest, loc))
+    }
+
+    /// Parse an optional identifier after `*` or `**`.
+    fn parse_rest_ident(&mut self) -> Result<Option<IdentId>, ParseErr> {
+        match self.peek_no_term()?.kind {
+            TokenKind::Ident(name) => {
+                self.get()?;
+                let id = self.get_ident_id(&name);
+                self.add_local_var_if_new(id);
+                Ok(Some(id))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// If the next tokens are a label (`key:`), consume them and return Some(key).
+    pub(super) fn consume_label(&mut self) -> Result<Option<IdentId>, ParseErr> {
+        let save = self.save_state();
+        if let Some(id) = self.peek()?.can_be_symbol() {
+            self.get()?;
+            if self.consume_punct_no_term(Punct::Colon)? {
+                return Ok(Some(id));
+            }
+        }
+        self.restore_state(save);
+        Ok(None)
+    }
+
+    fn peek_label(&mut self) -> Result<bool, ParseErr> {
+        let save = self.save_state();
+        let is_label = self.consume_label()?.is_some();
+        self.restore_state(save);
+        Ok(is_label)
+    }
+
+    /// Examine whether the next token terminates a pattern.
+    fn is_pattern_end(&mut self) -> Result<bool, ParseErr> {
+        let tok = self.peek_no_term()?;
+        Ok(match tok.kind {
+            TokenKind::Punct(p) => matches!(p, Punct::Comma | Punct::RBrace | Punct::RParen),
+            TokenKind::Reserved(r) => matches!(r, Reserved::Then | Reserved::If | Reserved::Unless),
+            _ => tok.is_term(),
+        })
+    }
+}
@@ -2,7 +2,7 @@ use super::parser::NReal;
 use super::*;
 use enum_iterator::Sequence;
 use node::BinOp;
-use num::BigInt;
+use num::{BigInt, BigRational};
 use std::fmt::*;
 
 pub(crate) type Token = Annot<TokenKind>;
@@ -49,14 +49,15 @@ pub(crate) enum TokenKind {
     IntegerLit(i64),
     BignumLit(BigInt),
     FloatLit(f64),
+    RationalLit(BigRational),
     ImaginaryLit(NReal),
-    StringLit(String),
-    CommandLit(String),
+    StringLit(Vec<u8>),
+    CommandLit(Vec<u8>),
     Reserved(Reserved),
     Punct(Punct),
-    OpenString(String, Option<char>, usize), // (content, delimiter, paren_level)
+    OpenString(Vec<u8>, Option<char>, usize), // (content, delimiter, paren_level)
     OpenRegex(String),
-    OpenCommand(String, Option<char>, usize),
+    OpenCommand(Vec<u8>, Option<char>, usize),
     PercentNotation(char, String),
     LineTerm,
 }
@@ -84,10 +85,13 @@ pub enum Reserved {
     Module,
     Next,
     Or,
+    Redo,
     Rescue,
+    Retry,
     Return,
     Super,
     Then,
+    Undef,
     Until,
     Unless,
     When,
@@ -119,10 +123,13 @@ impl Debug for Reserved {
             Reserved::Module => "module",
             Reserved::Next => "next",
             Reserved::Or => "or",
+            Reserved::Redo => "redo",
             Reserved::Rescue => "rescue",
+            Reserved::Retry => "retry",
             Reserved::Return => "return",
             Reserved::Super => "super",
             Reserved::Then => "then",
+            Reserved::Undef => "undef",
             Reserved::Until => "until",
             Reserved::Unless => "unless",
             Reserved::When => "when",
@@ -218,16 +225,20 @@ impl Token {
         Annot::new(TokenKind::FloatLit(num), loc)
     }
 
+    pub(crate) fn new_rationallit(num: BigRational, loc: Loc) -> Self {
+        Annot::new(TokenKind::RationalLit(num), loc)
+    }
+
     pub(crate) fn new_imaginarylit(num: NReal, loc: Loc) -> Self {
         Annot::new(TokenKind::ImaginaryLit(num), loc)
     }
 
-    pub(crate) fn new_stringlit(string: impl Into<String>, loc: Loc) -> Self {
+    pub(crate) fn new_stringlit(string: impl Into<Vec<u8>>, loc: Loc) -> Self {
         Annot::new(TokenKind::StringLit(string.into()), loc)
     }
 
     pub(crate) fn new_open_string(
-        s: impl Into<String>,
+        s: impl Into<Vec<u8>>,
         delimiter: Option<char>,
         level: usize,
         loc: Loc,
@@ -236,7 +247,7 @@ impl Token {
     }
 
     pub(crate) fn new_open_command(
-        s: impl Into<String>,
+        s: impl Into<Vec<u8>>,
         delimiter: Option<char>,
         level: usize,
         loc: Loc,
@@ -289,7 +300,7 @@ impl Token {
             TokenKind::Ident(ident) => IdentId::get_id(ident),
             TokenKind::Const(ident) => IdentId::get_id(ident),
             TokenKind::InstanceVar(ident) => IdentId::get_id(ident),
-            TokenKind::StringLit(ident) => IdentId::get_id(ident),
+            TokenKind::StringLit(ident) => IdentId::get_id(&String::from_utf8_lossy(ident)),
             TokenKind::Reserved(reserved) => {
                 let s = get_string_from_reserved(reserved);
                 IdentId::get_id_from_string(s)
@@ -308,6 +319,7 @@ impl Token {
                     | Reserved::Elsif
                     | Reserved::End
                     | Reserved::When
+                    | Reserved::In
                     | Reserved::Rescue
                     | Reserved::Ensure
             ),
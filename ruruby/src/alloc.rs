@@ -143,6 +143,37 @@ impl<T: GCBox> Allocator<T> {
         self.pages.len() + 1
     }
 
+    ///
+    /// Returns a number of total object slots in active pages.
+    ///
+    pub fn total_slots(&self) -> usize {
+        self.pages_len() * DATA_LEN
+    }
+
+    ///
+    /// Call `f` for every used slot (including freed ones) in active pages.
+    ///
+    pub fn for_each_slot(&self, mut f: impl FnMut(&T)) {
+        for page in self.pages.iter() {
+            for i in 0..DATA_LEN {
+                f(unsafe { &*page.get_data_ptr(i) });
+            }
+        }
+        for i in 0..self.used_in_current {
+            f(unsafe { &*self.current.get_data_ptr(i) });
+        }
+    }
+
+    ///
+    /// Check whether the object was marked in the last GC cycle.
+    ///
+    pub fn is_marked(&self, ptr: *const T) -> bool {
+        let ptr = ptr as *mut T;
+        let page_ptr = PageRef::from_inner(ptr);
+        let index = unsafe { ptr.offset_from(page_ptr.get_data_ptr(0)) } as usize;
+        page_ptr.mark_bits()[index / 64] & (1 << (index % 64)) != 0
+    }
+
     ///
     /// Allocate object.
     ///
@@ -1,4 +1,4 @@
-use crate::num::{Integer, Signed, ToPrimitive, Zero};
+use crate::num::{BigRational, Integer, Signed, ToPrimitive, Zero};
 use crate::num_bigint::{BigInt, ToBigInt};
 use crate::*;
 use divrem::RemFloor;
@@ -24,6 +24,11 @@ pub(crate) fn rem_fixnum(lhsi: i64, rhs: Value) -> VMResult {
             return Err(RubyError::zero_div("Divided by zero."));
         }
         Value::bignum(BigInt::from(lhsi).mod_floor(&b2))
+    } else if let Some(r2) = rhs.as_rational() {
+        return rem_rational(
+            &BigRational::from_integer(BigInt::from(lhsi)),
+            Value::rational(r2.clone()),
+        );
     } else {
         return Err(VMError::cant_coerse(rhs, "Integer"));
     };
@@ -46,6 +51,11 @@ pub(crate) fn rem_bignum(lhsb: &BigInt, rhs: Value) -> VMResult {
             return Err(RubyError::zero_div("Divided by zero."));
         }
         Value::bignum(lhsb.mod_floor(&b2))
+    } else if let Some(r2) = rhs.as_rational() {
+        return rem_rational(
+            &BigRational::from_integer(lhsb.clone()),
+            Value::rational(r2.clone()),
+        );
     } else {
         return Err(VMError::cant_coerse(rhs, "Integer"));
     };
@@ -68,12 +78,36 @@ pub(crate) fn rem_float(lhsf: f64, rhs: Value) -> VMResult {
             return Err(RubyError::zero_div("Divided by zero."));
         }
         Value::float(rem_floorf64(lhsf, rhs.to_f64().unwrap()))
+    } else if let Some(rhs) = rhs.as_rational() {
+        if rhs.is_zero() {
+            return Err(RubyError::zero_div("Divided by zero."));
+        }
+        Value::float(rem_floorf64(lhsf, rhs.to_f64().unwrap()))
     } else {
         unreachable!()
     };
     Ok(val)
 }
 
+pub(crate) fn rem_rational(lhsr: &BigRational, rhs: Value) -> VMResult {
+    let val = if let Some(rhsf) = rhs.as_float() {
+        if rhsf.is_zero() {
+            return Err(RubyError::zero_div("Divided by zero."));
+        }
+        Value::float(rem_floorf64(lhsr.to_f64().unwrap(), rhsf))
+    } else if let Some(rhs) = rhs.to_real() {
+        let rhsr = rhs.into_rational();
+        if rhsr.is_zero() {
+            return Err(RubyError::zero_div("Divided by zero."));
+        }
+        let quo = (lhsr / &rhsr).floor();
+        Value::rational(lhsr - quo * rhsr)
+    } else {
+        return Err(VMError::cant_coerse(rhs, "Rational"));
+    };
+    Ok(val)
+}
+
 pub(crate) fn rem_floorf64(self_: f64, other: f64) -> f64 {
     if self_ > 0.0 && other < 0.0 {
         ((self_ - 1.0) % other) + other + 1.0
@@ -95,7 +129,7 @@ pub(crate) fn exp_fixnum(lhsi: i64, rhs: Value) -> VMResult {
                 None => Value::bignum(BigInt::from(lhsi).pow(rhsu)),
             }
         } else {
-            Value::float((lhsi as f64).powf(rhsi as f64))
+            return exp_rational(&BigRational::from_integer(BigInt::from(lhsi)), rhs);
         }
     } else if let Some(rhsf) = rhs.as_float() {
         // fixnum, float
@@ -103,6 +137,12 @@ pub(crate) fn exp_fixnum(lhsi: i64, rhs: Value) -> VMResult {
     } else if let Some(rhsb) = rhs.as_bignum() {
         // fixnum, bignum
         Value::float((lhsi as f64).powf(rhsb.to_f64().unwrap()))
+    } else if let Some(rhsr) = rhs.as_rational() {
+        // fixnum, rational
+        if rhsr.is_integer() {
+            return exp_fixnum(lhsi, Real::integer(rhsr.to_integer()).into_val());
+        }
+        Value::float((lhsi as f64).powf(rhsr.to_f64().unwrap()))
     } else {
         return Err(VMError::cant_coerse(rhs, "Integer"));
     };
@@ -119,12 +159,36 @@ pub(crate) fn exp_float(lhsf: f64, rhs: Value) -> VMResult {
         lhsf.powf(rhsf)
     } else if let Some(rhsb) = rhs.as_bignum() {
         lhsf.powf(rhsb.to_f64().unwrap())
+    } else if let Some(rhsr) = rhs.as_rational() {
+        lhsf.powf(rhsr.to_f64().unwrap())
     } else {
         return Err(VMError::cant_coerse(rhs, "Integer"));
     };
     Ok(Value::float(f))
 }
 
+pub(crate) fn exp_rational(lhsr: &BigRational, rhs: Value) -> VMResult {
+    let val = if let Some(rhsi) = rhs.as_fixnum() {
+        match TryInto::<i32>::try_into(rhsi) {
+            Ok(_) if rhsi < 0 && lhsr.is_zero() => {
+                return Err(RubyError::zero_div("Divided by zero."));
+            }
+            Ok(e) => Value::rational(Pow::pow(lhsr, e)),
+            Err(_) => Value::float(lhsr.to_f64().unwrap().powf(rhsi as f64)),
+        }
+    } else if let Some(rhsr) = rhs.as_rational() {
+        if rhsr.is_integer() {
+            return exp_rational(lhsr, Real::integer(rhsr.to_integer()).into_val());
+        }
+        Value::float(lhsr.to_f64().unwrap().powf(rhsr.to_f64().unwrap()))
+    } else if let Some(rhs) = rhs.to_real() {
+        Value::float(lhsr.to_f64().unwrap().powf(rhs.to_f64()))
+    } else {
+        return Err(VMError::cant_coerse(rhs, "Rational"));
+    };
+    Ok(val)
+}
+
 /// compare operation (<=>)
 pub(crate) fn cmp_fixnum(lhsi: i64, rhs: Value) -> Option<std::cmp::Ordering> {
     if let Some(rhsi) = rhs.as_fixnum() {
@@ -137,6 +201,8 @@ pub(crate) fn cmp_fixnum(lhsi: i64, rhs: Value) -> Option<std::cmp::Ordering> {
         } else {
             Some(std::cmp::Ordering::Greater)
         }
+    } else if let Some(rhsr) = rhs.as_rational() {
+        BigRational::from_integer(BigInt::from(lhsi)).partial_cmp(rhsr)
     } else {
         None
     }
@@ -159,6 +225,8 @@ pub(crate) fn cmp_float(lhsf: f64, rhs: Value) -> Option<std::cmp::Ordering> {
         } else {
             lhsf.partial_cmp(&rhsb.to_f64().unwrap())
         }
+    } else if let Some(rhsr) = rhs.as_rational() {
+        lhsf.partial_cmp(&rhsr.to_f64().unwrap())
     } else {
         None
     }
@@ -205,11 +273,23 @@ pub fn cmp_bignum(lhsb: &BigInt, rhs: Value) -> Option<std::cmp::Ordering> {
         }
     } else if let Some(rhsb) = rhs.as_bignum() {
         lhsb.partial_cmp(&rhsb)
+    } else if let Some(rhsr) = rhs.as_rational() {
+        BigRational::from_integer(lhsb.clone()).partial_cmp(rhsr)
     } else {
         None
     }
 }
 
+/// Compare `lhsr` to `rhs` (`lhsr` <=> `rhs`).
+pub(crate) fn cmp_rational(lhsr: &BigRational, rhs: Value) -> Option<std::cmp::Ordering> {
+    if let Some(rhsf) = rhs.as_float() {
+        lhsr.to_f64().unwrap().partial_cmp(&rhsf)
+    } else {
+        let rhsr = rhs.to_real()?.into_rational();
+        lhsr.partial_cmp(&rhsr)
+    }
+}
+
 /// Safe arithmetic shift left operation (`lhs` << `rhs`).
 ///
 /// # Examples
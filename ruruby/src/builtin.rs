@@ -5,30 +5,41 @@ mod class;
 mod comparable;
 mod complex;
 mod dir;
+pub mod encoding;
+pub mod enumerable;
 pub mod enumerator;
 mod exception;
 mod falseclass;
 pub mod fiber;
 pub mod file;
 mod float;
+pub mod format;
 mod gc;
 mod hash;
+mod instruction_sequence;
 mod integer;
 pub mod io;
-mod kernel;
+pub mod kernel;
+pub mod marshal;
 pub mod math;
 pub mod method;
 pub mod module;
+pub mod mutex;
 pub mod nilclass;
 pub mod numeric;
 pub mod object;
+pub mod object_space;
 pub mod process;
 pub mod procobj;
+pub mod queue;
 pub mod range;
+pub mod rational;
 pub mod regexp;
+pub mod set;
 pub mod string;
 pub mod structobj;
 pub mod symbol;
+pub mod thread;
 pub mod time;
 pub mod trueclass;
 pub mod unbound_method;
@@ -86,6 +97,7 @@ pub struct BuiltinClass {
     pub integer: Value,
     pub float: Value,
     pub complex: Value,
+    pub rational: Value,
     pub array: Value,
     pub symbol: Value,
     pub procobj: Value,
@@ -105,6 +117,7 @@ pub struct BuiltinClass {
     pub falseclass: Value,
     pub kernel: Module,
     pub comparable: Module,
+    pub enumerable: Module,
     pub numeric: Module,
 }
 
@@ -117,6 +130,7 @@ impl BuiltinClass {
             integer: nil,
             float: nil,
             complex: nil,
+            rational: nil,
             array: nil,
             symbol: nil,
             procobj: nil,
@@ -136,6 +150,7 @@ impl BuiltinClass {
             falseclass: nil,
             kernel: nilmod,
             comparable: nilmod,
+            enumerable: nilmod,
             numeric: nilmod,
         }
     }
@@ -156,13 +171,16 @@ impl BuiltinClass {
                 $module::init(globals);
             )*}
         }
-        init_builtin!(comparable, numeric, kernel);
+        init_builtin!(comparable, enumerable, numeric, kernel);
         init!(module, class, basicobject, object);
         init_builtin!(exception);
-        init_builtin!(integer, float, complex, nilclass, trueclass, falseclass);
+        init_builtin!(integer, float, complex, rational);
+        init_builtin!(nilclass, trueclass, falseclass);
         init_builtin!(array, symbol, procobj, range, string, hash);
         init_builtin!(method, unbound_method, regexp, fiber, enumerator, binding);
-        init!(math, dir, process, gc, structobj, time);
+        init!(math, dir, process, gc, structobj, time, marshal, set, encoding);
+        init!(object_space, instruction_sequence);
+        init!(thread, mutex, queue);
     }
 
     pub(crate) fn object() -> Module {
@@ -197,6 +215,10 @@ impl BuiltinClass {
         BUILTINS.with(|b| b.borrow().complex).into_module()
     }
 
+    pub(crate) fn rational() -> Module {
+        BUILTINS.with(|b| b.borrow().rational).into_module()
+    }
+
     pub(crate) fn range() -> Module {
         BUILTINS.with(|b| b.borrow().range).into_module()
     }
@@ -268,6 +290,10 @@ impl BuiltinClass {
     pub(crate) fn comparable() -> Module {
         BUILTINS.with(|b| b.borrow().comparable)
     }
+
+    pub(crate) fn enumerable() -> Module {
+        BUILTINS.with(|b| b.borrow().enumerable)
+    }
 }
 
 impl GC<RValue> for EssentialClass {
@@ -1,21 +1,26 @@
 //use crate::error::RubyError;
 use crate::*;
 use fxhash::FxHashSet;
+use num::{bigint::Sign, BigInt, ToPrimitive};
 
 pub(crate) fn init(globals: &mut Globals) -> Value {
-    let class = Module::class_under_object();
+    let mut class = Module::class_under_object();
     globals.set_toplevel_constant("Array", class);
+    class.append_include_without_increment_version(BuiltinClass::enumerable());
     class.add_builtin_method_by_str(globals, "inspect", inspect);
     class.add_builtin_method_by_str(globals, "to_s", inspect);
     class.add_builtin_method_by_str(globals, "to_a", toa);
+    class.add_builtin_method_by_str(globals, "deconstruct", deconstruct);
     class.add_builtin_method_by_str(globals, "length", length);
     class.add_builtin_method_by_str(globals, "size", length);
     class.add_builtin_method_by_str(globals, "empty?", empty);
     class.add_builtin_method_by_str(globals, "[]", get_elem);
+    class.add_builtin_method_by_str(globals, "slice", get_elem);
     class.add_builtin_method_by_str(globals, "at", at);
     class.add_builtin_method_by_str(globals, "[]=", set_elem);
     class.add_builtin_method_by_str(globals, "push", push);
     class.add_builtin_method_by_str(globals, "<<", push);
+    class.add_builtin_method_by_str(globals, "append", push);
     class.add_builtin_method_by_str(globals, "pop", pop);
     class.add_builtin_method_by_str(globals, "*", mul);
     class.add_builtin_method_by_str(globals, "+", add);
@@ -24,6 +29,15 @@ pub(crate) fn init(globals: &mut Globals) -> Value {
 
     class.add_builtin_method_by_str(globals, "shift", shift);
     class.add_builtin_method_by_str(globals, "unshift", unshift);
+    class.add_builtin_method_by_str(globals, "prepend", unshift);
+    class.add_builtin_method_by_str(globals, "insert", insert);
+    class.add_builtin_method_by_str(globals, "delete_at", delete_at);
+    class.add_builtin_method_by_str(globals, "replace", replace);
+    class.add_builtin_method_by_str(globals, "fetch", fetch);
+    class.add_builtin_method_by_str(globals, "values_at", values_at);
+    class.add_builtin_method_by_str(globals, "dig", dig);
+    class.add_builtin_method_by_str(globals, "assoc", assoc);
+    class.add_builtin_method_by_str(globals, "rassoc", rassoc);
 
     class.add_builtin_method_by_str(globals, "concat", concat);
     class.add_builtin_method_by_str(globals, "map", map);
@@ -39,12 +53,12 @@ pub(crate) fn init(globals: &mut Globals) -> Value {
     class.add_builtin_method_by_str(globals, "include?", include);
     class.add_builtin_method_by_str(globals, "reverse", reverse);
     class.add_builtin_method_by_str(globals, "reverse!", reverse_);
+    class.add_builtin_method_by_str(globals, "rotate", rotate);
     class.add_builtin_method_by_str(globals, "rotate!", rotate_);
     class.add_builtin_method_by_str(globals, "compact", compact);
     class.add_builtin_method_by_str(globals, "compact!", compact_);
 
     class.add_builtin_method_by_str(globals, "transpose", transpose);
-    class.add_builtin_method_by_str(globals, "min", min);
     class.add_builtin_method_by_str(globals, "fill", fill);
     class.add_builtin_method_by_str(globals, "clear", clear);
     class.add_builtin_method_by_str(globals, "uniq!", uniq_);
@@ -53,34 +67,53 @@ pub(crate) fn init(globals: &mut Globals) -> Value {
     class.add_builtin_method_by_str(globals, "all?", all_);
 
     class.add_builtin_method_by_str(globals, "slice!", slice_);
-    class.add_builtin_method_by_str(globals, "max", max);
     class.add_builtin_method_by_str(globals, "first", first);
     class.add_builtin_method_by_str(globals, "last", last);
     class.add_builtin_method_by_str(globals, "dup", dup);
-    class.add_builtin_method_by_str(globals, "clone", dup);
+    class.add_builtin_method_by_str(globals, "clone", clone);
     class.add_builtin_method_by_str(globals, "pack", pack);
     class.add_builtin_method_by_str(globals, "join", join);
     class.add_builtin_method_by_str(globals, "drop", drop);
     class.add_builtin_method_by_str(globals, "zip", zip);
-    class.add_builtin_method_by_str(globals, "grep", grep);
     class.add_builtin_method_by_str(globals, "sort", sort);
     class.add_builtin_method_by_str(globals, "sort_by", sort_by);
-    class.add_builtin_method_by_str(globals, "count", count);
-    class.add_builtin_method_by_str(globals, "inject", inject);
-    class.add_builtin_method_by_str(globals, "reduce", inject);
+    class.add_builtin_method_by_str(globals, "sort!", sort_);
+    class.add_builtin_method_by_str(globals, "sort_by!", sort_by_);
     class.add_builtin_method_by_str(globals, "find_index", find_index);
     class.add_builtin_method_by_str(globals, "index", find_index);
+    class.add_builtin_method_by_str(globals, "rindex", rindex);
 
     class.add_builtin_method_by_str(globals, "reject", reject);
     class.add_builtin_method_by_str(globals, "find", find);
     class.add_builtin_method_by_str(globals, "detect", find);
     class.add_builtin_method_by_str(globals, "select", select);
     class.add_builtin_method_by_str(globals, "filter", select);
+    class.add_builtin_method_by_str(globals, "select!", select_);
+    class.add_builtin_method_by_str(globals, "filter!", select_);
+    class.add_builtin_method_by_str(globals, "keep_if", keep_if);
+    class.add_builtin_method_by_str(globals, "reject!", reject_);
+    class.add_builtin_method_by_str(globals, "delete_if", delete_if);
     class.add_builtin_method_by_str(globals, "bsearch", bsearch);
     class.add_builtin_method_by_str(globals, "bsearch_index", bsearch_index);
     class.add_builtin_method_by_str(globals, "delete", delete);
     class.add_builtin_method_by_str(globals, "flatten", flatten);
     class.add_builtin_method_by_str(globals, "flatten!", flatten_);
+    class.add_builtin_method_by_str(globals, "union", union);
+    class.add_builtin_method_by_str(globals, "|", union);
+    class.add_builtin_method_by_str(globals, "intersection", intersection);
+    class.add_builtin_method_by_str(globals, "&", intersection);
+    class.add_builtin_method_by_str(globals, "intersect?", intersect);
+    class.add_builtin_method_by_str(globals, "difference", difference);
+
+    class.add_builtin_method_by_str(globals, "product", product);
+    class.add_builtin_method_by_str(globals, "combination", combination);
+    class.add_builtin_method_by_str(globals, "repeated_combination", repeated_combination);
+    class.add_builtin_method_by_str(globals, "permutation", permutation);
+    class.add_builtin_method_by_str(globals, "repeated_permutation", repeated_permutation);
+    class.add_builtin_method_by_str(globals, "cycle", cycle);
+    class.add_builtin_method_by_str(globals, "sample", sample);
+    class.add_builtin_method_by_str(globals, "shuffle", shuffle);
+    class.add_builtin_method_by_str(globals, "shuffle!", shuffle_);
 
     class.add_builtin_class_method(globals, "new", array_new);
     class.add_builtin_class_method(globals, "allocate", array_allocate);
@@ -157,6 +190,15 @@ fn inspect(vm: &mut VM, self_val: Value, _args: &Args2) -> VMResult {
     Ok(Value::string(s))
 }
 
+/// ## instance method Array#deconstruct
+/// - deconstruct -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/deconstruct.html
+fn deconstruct(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(self_val)
+}
+
 fn toa(vm: &mut VM, self_val: Value, _args: &Args2) -> VMResult {
     let array = vm.globals.classes.array;
     if vm.globals.get_class(self_val).id() == array.id() {
@@ -195,6 +237,7 @@ fn at(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
 /// https://docs.ruby-lang.org/ja/latest/method/Array/i/=5b=5d=3d.html
 fn set_elem(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_range(2, 3)?;
+    self_val.check_frozen()?;
     self_val.into_array().set_elem(vm.args())
 }
 
@@ -234,16 +277,31 @@ fn cmp(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
 }
 
 fn push(vm: &mut VM, self_val: Value, _args: &Args2) -> VMResult {
+    self_val.check_frozen()?;
     let mut ary = self_val.into_array();
     ary.extend_from_slice(vm.args());
     Ok(self_val)
 }
 
-fn pop(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
-    args.check_args_num(0)?;
+/// ## instance method Array#pop
+/// - pop -> object | nil
+/// - pop(n) -> Array
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/pop.html
+fn pop(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
+    self_val.check_frozen()?;
     let mut ary = self_val.into_array();
-    let res = ary.pop().unwrap_or_default();
-    Ok(res)
+    if args.len() == 0 {
+        return Ok(ary.pop().unwrap_or_default());
+    }
+    let num = vm[0].coerce_to_fixnum("1st arg")?;
+    if num < 0 {
+        return Err(RubyError::argument("Negative array size."));
+    }
+    let len = ary.len();
+    let start = len.saturating_sub(num as usize);
+    Ok(Value::array_from(ary.drain(start..len)))
 }
 
 /// ## instance method Array#shift
@@ -253,6 +311,7 @@ fn pop(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
 /// https://docs.ruby-lang.org/ja/latest/method/Array/i/shift.html
 fn shift(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_range(0, 1)?;
+    self_val.check_frozen()?;
     let mut array_flag = false;
     let num = if args.len() == 0 {
         0
@@ -285,6 +344,7 @@ fn shift(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
 }
 
 fn unshift(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    self_val.check_frozen()?;
     if args.len() == 0 {
         return Ok(self_val);
     }
@@ -346,11 +406,17 @@ fn add(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     Ok(Value::array_from(lhs))
 }
 
-fn concat(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
-    args.check_args_num(1)?;
-    let lhs = &mut *self_val.into_array();
-    let rhs = &**vm[0].expect_array("Argument")?;
-    lhs.extend_from_slice(rhs);
+/// ## instance method Array#concat
+/// - concat(*other_arrays) -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/concat.html
+fn concat(vm: &mut VM, self_val: Value, _: &Args2) -> VMResult {
+    self_val.check_frozen()?;
+    let mut rhs = vec![];
+    for arg in vm.args() {
+        rhs.extend_from_slice(&arg.expect_array("Argument")?);
+    }
+    self_val.into_array().extend_from_slice(&rhs);
     Ok(self_val)
 }
 
@@ -419,6 +485,7 @@ fn map(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
 
 fn map_(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(0)?;
+    self_val.check_frozen()?;
     let mut aref = self_val.into_array();
     let block = to_enum_id!(vm, self_val, args, IdentId::MAP);
     let mut i = 0;
@@ -553,6 +620,7 @@ fn reverse(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
 
 fn reverse_(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(0)?;
+    self_val.check_frozen()?;
     let mut aref = self_val.into_array();
     aref.reverse();
     Ok(self_val)
@@ -564,6 +632,7 @@ fn reverse_(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
 /// https://docs.ruby-lang.org/ja/latest/method/Array/i/rotate=21.html
 fn rotate_(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_range(0, 1)?;
+    self_val.check_frozen()?;
     let i = if args.len() == 0 {
         1
     } else {
@@ -594,6 +663,7 @@ fn compact(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
 
 fn compact_(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(0)?;
+    self_val.check_frozen()?;
     let mut aref = self_val.into_array();
     let mut flag = false;
     aref.retain(|x| {
@@ -639,53 +709,9 @@ fn transpose(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     Ok(res)
 }
 
-fn min(_: &mut VM, self_val: Value, _args: &Args2) -> VMResult {
-    fn to_float(val: Value) -> Result<f64, RubyError> {
-        if let Some(i) = val.as_fixnum() {
-            Ok(i as f64)
-        } else if let Some(f) = val.as_flonum() {
-            Ok(f)
-        } else {
-            Err(RubyError::typeerr(
-                "Currently, each element must be Numeric.",
-            ))
-        }
-    }
-
-    let aref = self_val.into_array();
-    if aref.len() == 0 {
-        return Ok(Value::nil());
-    }
-    let mut min_obj = aref[0];
-    let mut min = to_float(min_obj)?;
-    for elem in &**aref {
-        let elem_f = to_float(*elem)?;
-        if elem_f < min {
-            min_obj = *elem;
-            min = elem_f;
-        }
-    }
-
-    return Ok(min_obj);
-}
-
-fn max(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
-    args.check_args_num(0)?;
-    let aref = self_val.into_array();
-    if aref.len() == 0 {
-        return Ok(Value::nil());
-    }
-    let mut max = aref[0];
-    for elem in &**aref {
-        if vm.eval_gt2(max, *elem)? {
-            max = *elem;
-        };
-    }
-    Ok(max)
-}
-
 fn fill(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(1)?;
+    self_val.check_frozen()?;
     let mut aref = self_val.into_array();
     for elem in &mut **aref {
         *elem = vm[0];
@@ -695,6 +721,7 @@ fn fill(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
 
 fn clear(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(0)?;
+    self_val.check_frozen()?;
     let mut aref = self_val.into_array();
     aref.clear();
     Ok(self_val)
@@ -744,6 +771,7 @@ fn uniq(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
 /// https://docs.ruby-lang.org/ja/latest/method/Array/i/uniq.html
 fn uniq_(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(0)?;
+    self_val.check_frozen()?;
     let mut h = FxHashSet::default();
     let deleted = match &args.block {
         None => {
@@ -780,13 +808,31 @@ fn uniq_(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
 }
 
 /// ## instance method Array#slice!
-/// - slice!(nth) -> object | nil         NOT SUPPORTED
+/// - slice!(nth) -> object | nil
 /// - slice!(start, len) -> Array | nil
-/// - slice!(range) -> Array | nil        NOT SUPPORTED
+/// - slice!(range) -> Array | nil
 ///
 /// https://docs.ruby-lang.org/ja/latest/method/Array/i/slice=21.html
 fn slice_(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
-    args.check_args_num(2)?;
+    args.check_args_range(1, 2)?;
+    self_val.check_frozen()?;
+    if args.len() == 1 {
+        if let Some(range) = vm[0].as_range() {
+            let mut aref = self_val.into_array();
+            let len = aref.len() as i64;
+            let start = match range.start.coerce_to_fixnum("Start of the range")? {
+                i if i < 0 => len + i,
+                i => i,
+            };
+            let res = aref.get_elem1(vm[0])?;
+            if let Some(ary) = res.as_array() {
+                let start = start as usize;
+                aref.drain(start..start + ary.len());
+            }
+            return Ok(res);
+        }
+        return delete_at(vm, self_val, args);
+    }
     let start = vm[0].coerce_to_fixnum("Currently, first arg must be Integer.")?;
     if start < 0 {
         return Err(RubyError::argument("First arg must be positive value."));
@@ -814,9 +860,27 @@ fn slice_(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     Ok(Value::array_from(new))
 }
 
-fn first(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
-    args.check_args_num(0)?;
+/// Get the number of elements from 1st arg for `first(n)`-like methods.
+fn arg_to_size(val: Value) -> Result<usize, RubyError> {
+    let num = val.coerce_to_fixnum("1st arg")?;
+    if num < 0 {
+        return Err(RubyError::argument("Negative array size."));
+    }
+    Ok(num as usize)
+}
+
+/// ## instance method Array#first
+/// - first -> object | nil
+/// - first(n) -> Array
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/first.html
+fn first(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
     let aref = self_val.into_array();
+    if args.len() == 1 {
+        let num = std::cmp::min(arg_to_size(vm[0])?, aref.len());
+        return Ok(Value::array_from_slice(&aref[..num]));
+    }
     if aref.len() == 0 {
         Ok(Value::nil())
     } else {
@@ -824,9 +888,18 @@ fn first(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     }
 }
 
-fn last(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
-    args.check_args_num(0)?;
+/// ## instance method Array#last
+/// - last -> object | nil
+/// - last(n) -> Array
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/last.html
+fn last(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
     let aref = self_val.into_array();
+    if args.len() == 1 {
+        let num = std::cmp::min(arg_to_size(vm[0])?, aref.len());
+        return Ok(Value::array_from_slice(&aref[aref.len() - num..]));
+    }
     if aref.len() == 0 {
         Ok(Value::nil())
     } else {
@@ -840,18 +913,327 @@ fn dup(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     Ok(Value::array_from(aref.to_vec()))
 }
 
-fn pack(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+fn clone(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    let val = dup(vm, self_val, args)?;
+    if self_val.is_frozen() {
+        Ok(val.freeze())
+    } else {
+        Ok(val)
+    }
+}
+
+/// ## instance method Array#pack
+/// - pack(template) -> String
+///
+/// Supported directives:
+/// - integers: C c S s L l Q q J j n N v V U w
+/// - floats: D d F f E e G g
+/// - strings: a A Z B b H h m M
+/// - x (null byte)
+///
+/// `<` and `>` modifiers specify the endianness. `_` and `!` are ignored.
+/// The result is ASCII-8BIT unless `U` is used.
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/pack.html
+fn pack(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    /// A field which is packed from each item.
+    enum Field {
+        /// Integer of the byte size.
+        Int(usize),
+        /// Float of the byte size. (4 or 8)
+        Float(usize),
+        /// UTF-8 character.
+        Utf8,
+        /// BER-compressed integer.
+        Ber,
+    }
     args.check_args_range(0, 1)?;
+    let template = if args.len() == 0 {
+        "C*".to_string()
+    } else {
+        let mut template = vm[0];
+        template.expect_string("Template")?.to_string()
+    };
     let aref = self_val.into_array();
+    let mut items = aref.iter();
     let mut v = vec![];
-    for elem in &**aref {
-        let i = match elem.as_fixnum() {
-            Some(i) => i as i8 as u8,
-            None => return Err(RubyError::argument("Must be Array of Integer.")),
+    let mut utf8 = false;
+    let mut chars = template.chars().peekable();
+    while let Some(directive) = chars.next() {
+        if directive.is_ascii_whitespace() {
+            continue;
+        }
+        let mut big_endian = cfg!(target_endian = "big");
+        while let Some(m) = chars.peek() {
+            match m {
+                '_' | '!' => {}
+                '<' => big_endian = false,
+                '>' => big_endian = true,
+                _ => break,
+            }
+            chars.next();
+        }
+        // None means '*'.
+        let count = if chars.peek() == Some(&'*') {
+            chars.next();
+            None
+        } else {
+            let mut n = None;
+            while let Some(d) = chars.peek().and_then(|c| c.to_digit(10)) {
+                n = Some(n.unwrap_or(0) * 10 + d as usize);
+                chars.next();
+            }
+            Some(n.unwrap_or(1))
+        };
+        let field = match directive {
+            'C' | 'c' => Field::Int(1),
+            'S' | 's' => Field::Int(2),
+            'L' | 'l' => Field::Int(4),
+            'Q' | 'q' | 'J' | 'j' => Field::Int(8),
+            'n' | 'v' => {
+                big_endian = directive == 'n';
+                Field::Int(2)
+            }
+            'N' | 'V' => {
+                big_endian = directive == 'N';
+                Field::Int(4)
+            }
+            'F' | 'f' => Field::Float(4),
+            'D' | 'd' => Field::Float(8),
+            'e' | 'g' => {
+                big_endian = directive == 'g';
+                Field::Float(4)
+            }
+            'E' | 'G' => {
+                big_endian = directive == 'G';
+                Field::Float(8)
+            }
+            'U' => {
+                utf8 = true;
+                Field::Utf8
+            }
+            'w' => Field::Ber,
+            'x' => {
+                v.resize(v.len() + count.unwrap_or(0), 0);
+                continue;
+            }
+            'a' | 'A' | 'Z' | 'B' | 'b' | 'H' | 'h' | 'm' | 'M' => {
+                let item = match items.next() {
+                    Some(item) => *item,
+                    None => return Err(RubyError::argument("too few arguments")),
+                };
+                match directive {
+                    'B' | 'b' => {
+                        pack_bits(&mut v, item.expect_bytes("Item")?, count, directive == 'B')
+                    }
+                    'H' | 'h' => {
+                        pack_hex(&mut v, item.expect_bytes("Item")?, count, directive == 'H')
+                    }
+                    'm' => pack_base64(&mut v, item.expect_bytes("Item")?, count),
+                    'M' => {
+                        let s = item.val_to_s(vm)?;
+                        pack_qp(&mut v, s.as_bytes(), count)
+                    }
+                    _ => {
+                        let mut bytes = item.expect_bytes("Item")?.to_vec();
+                        let pad = if directive == 'A' { b' ' } else { 0 };
+                        match count {
+                            None => {
+                                if directive == 'Z' {
+                                    bytes.push(0);
+                                }
+                            }
+                            Some(count) => bytes.resize(count, pad),
+                        }
+                        v.extend_from_slice(&bytes);
+                    }
+                }
+                continue;
+            }
+            _ => {
+                return Err(RubyError::argument(format!(
+                    "unknown pack directive '{}' in '{}'",
+                    directive, template
+                )))
+            }
+        };
+        let mut n = 0;
+        while !matches!(count, Some(count) if n >= count) {
+            let item = match items.next() {
+                Some(item) => *item,
+                None if count.is_none() => break,
+                None => return Err(RubyError::argument("too few arguments")),
+            };
+            match field {
+                Field::Float(size) => {
+                    let f = match item.unpack() {
+                        RV::Integer(i) => i as f64,
+                        RV::Float(f) => f,
+                        _ => match item.as_bignum() {
+                            Some(b) => b.to_f64().unwrap_or(f64::INFINITY),
+                            None => return Err(VMError::no_implicit_conv(item, "Float")),
+                        },
+                    };
+                    match (size, big_endian) {
+                        (4, true) => v.extend_from_slice(&(f as f32).to_be_bytes()),
+                        (4, false) => v.extend_from_slice(&(f as f32).to_le_bytes()),
+                        (_, true) => v.extend_from_slice(&f.to_be_bytes()),
+                        (_, false) => v.extend_from_slice(&f.to_le_bytes()),
+                    }
+                }
+                Field::Ber => {
+                    let i = match item.as_bignum() {
+                        Some(b) => b.clone(),
+                        None => match item.as_fixnum() {
+                            Some(i) => BigInt::from(i),
+                            None => return Err(VMError::no_implicit_conv(item, "Integer")),
+                        },
+                    };
+                    if i.sign() == Sign::Minus {
+                        return Err(RubyError::argument("can't compress negative numbers"));
+                    }
+                    let (_, mut digits) = i.to_radix_be(128);
+                    let last = digits.len() - 1;
+                    digits[..last].iter_mut().for_each(|d| *d |= 0x80);
+                    v.extend_from_slice(&digits);
+                }
+                Field::Int(_) | Field::Utf8 => {
+                    let i = match item.as_fixnum() {
+                        Some(i) => i,
+                        None => return Err(VMError::no_implicit_conv(item, "Integer")),
+                    };
+                    match field {
+                        Field::Utf8 => {
+                            let c = Some(i)
+                                .filter(|i| 0 <= *i && *i <= u32::MAX as i64)
+                                .and_then(|i| char::from_u32(i as u32))
+                                .ok_or_else(|| RubyError::range("pack(U): value out of range"))?;
+                            let mut buf = [0; 4];
+                            v.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                        }
+                        Field::Int(size) if big_endian => {
+                            v.extend_from_slice(&i.to_be_bytes()[8 - size..])
+                        }
+                        Field::Int(size) => v.extend_from_slice(&i.to_le_bytes()[..size]),
+                        _ => unreachable!(),
+                    }
+                }
+            }
+            n += 1;
+        }
+    }
+    if utf8 {
+        if let Ok(s) = String::from_utf8(v.clone()) {
+            return Ok(Value::string(s));
+        }
+    }
+    let res = Value::bytes(v);
+    Encoding::Ascii8Bit.set(res);
+    Ok(res)
+}
+
+/// Pack a bit string ("B": MSB first, "b": LSB first). `count` is the number of bits.
+fn pack_bits(v: &mut Vec<u8>, bits: &[u8], count: Option<usize>, msb_first: bool) {
+    let count = count.unwrap_or(bits.len());
+    let mut bytes = vec![0u8; count.div_ceil(8)];
+    for (i, bit) in bits.iter().take(count).enumerate() {
+        if bit & 1 == 1 {
+            let shift = if msb_first { 7 - i % 8 } else { i % 8 };
+            bytes[i / 8] |= 1 << shift;
+        }
+    }
+    v.extend_from_slice(&bytes);
+}
+
+/// Pack a hex string ("H": high nibble first, "h": low nibble first).
+/// `count` is the number of nibbles.
+fn pack_hex(v: &mut Vec<u8>, hex: &[u8], count: Option<usize>, high_first: bool) {
+    let count = count.unwrap_or(hex.len());
+    let mut bytes = vec![0u8; count.div_ceil(2)];
+    for (i, c) in hex.iter().take(count).enumerate() {
+        let nibble = if c.is_ascii_alphabetic() {
+            ((c & 15) + 9) & 15
+        } else {
+            c & 15
         };
-        v.push(i);
+        let shift = if high_first == (i % 2 == 0) { 4 } else { 0 };
+        bytes[i / 2] |= nibble << shift;
+    }
+    v.extend_from_slice(&bytes);
+}
+
+/// Pack in base64. Lines of `count` / 3 * 3 bytes are terminated by "\n",
+/// and `count` == 0 means no line feeds.
+fn pack_base64(v: &mut Vec<u8>, bytes: &[u8], count: Option<usize>) {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let encode = |v: &mut Vec<u8>, chunk: &[u8]| {
+        for triple in chunk.chunks(3) {
+            let b = [
+                triple[0],
+                *triple.get(1).unwrap_or(&0),
+                *triple.get(2).unwrap_or(&0),
+            ];
+            let n = (b[0] as usize) << 16 | (b[1] as usize) << 8 | b[2] as usize;
+            for i in 0..4 {
+                if i <= triple.len() {
+                    v.push(TABLE[(n >> (18 - 6 * i)) & 63]);
+                } else {
+                    v.push(b'=');
+                }
+            }
+        }
+    };
+    match count {
+        Some(0) => encode(v, bytes),
+        _ => {
+            let len = match count {
+                Some(count) if count > 2 => count / 3 * 3,
+                _ => 45,
+            };
+            for line in bytes.chunks(len) {
+                encode(v, line);
+                v.push(b'\n');
+            }
+        }
+    }
+}
+
+/// Pack in quoted-printable. Lines are broken when they get longer than `count` (72 by default).
+fn pack_qp(v: &mut Vec<u8>, bytes: &[u8], count: Option<usize>) {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+    let len = match count {
+        Some(count) if count > 1 => count,
+        _ => 72,
+    };
+    let mut n = 0;
+    let mut prev = None;
+    for &b in bytes {
+        if b > 126 || (b < 32 && b != b'\n' && b != b'\t') || b == b'=' {
+            v.extend_from_slice(&[b'=', HEX[(b >> 4) as usize], HEX[(b & 15) as usize]]);
+            n += 3;
+            prev = None;
+        } else if b == b'\n' {
+            if matches!(prev, Some(b' ' | b'\t')) {
+                v.extend_from_slice(b"=\n");
+            }
+            v.push(b);
+            n = 0;
+            prev = Some(b);
+            continue;
+        } else {
+            v.push(b);
+            n += 1;
+            prev = Some(b);
+        }
+        if n > len {
+            v.extend_from_slice(b"=\n");
+            n = 0;
+            prev = Some(b'\n');
+        }
+    }
+    if n > 0 {
+        v.extend_from_slice(b"=\n");
     }
-    Ok(Value::bytes(v))
 }
 
 fn join(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
@@ -920,22 +1302,6 @@ fn zip(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     }
 }
 
-fn grep(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
-    args.check_args_num(1)?;
-    let aref = self_val.into_array();
-    let ary = match &args.block {
-        None => aref
-            .iter()
-            .filter_map(|x| match vm.eval_teq(*x, vm[0]) {
-                Ok(true) => Some(*x),
-                _ => None,
-            })
-            .collect(),
-        _ => unimplemented!(),
-    };
-    Ok(Value::array_from(ary))
-}
-
 /// ## instance method Array#sort
 /// - Array#sort -> Array
 /// - Array#sort { |a, b| .. } -> Array
@@ -1052,42 +1418,6 @@ fn all_(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     Ok(Value::true_val())
 }
 
-fn count(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
-    args.check_args_range(0, 1)?;
-    if args.block.is_some() {
-        return Err(RubyError::argument("Currently, block is not supported."));
-    }
-    let ary = self_val.expect_array("").unwrap();
-    match args.len() {
-        0 => {
-            let len = ary.len() as i64;
-            Ok(Value::integer(len))
-        }
-        1 => {
-            let other = vm[0];
-            let mut count = 0;
-            for elem in &**ary {
-                if vm.eval_eq2(*elem, other)? {
-                    count += 1;
-                }
-            }
-            Ok(Value::integer(count))
-        }
-        _ => unreachable!(),
-    }
-}
-
-fn inject(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
-    args.check_args_num(1)?;
-    let block = args.expect_block()?;
-    let ary = self_val.expect_array("").unwrap();
-    let mut res = vm[0];
-    for elem in ary.iter() {
-        res = vm.eval_block2(block, res, *elem)?;
-    }
-    Ok(res)
-}
-
 fn find_index(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_range(0, 1)?;
     let ary = self_val.expect_array("").unwrap();
@@ -1212,6 +1542,7 @@ fn bsearch_index(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
 
 fn delete(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(1)?;
+    self_val.check_frozen()?;
     let arg = vm[0];
     args.expect_no_block()?;
     let mut aref = self_val.into_array();
@@ -1248,6 +1579,7 @@ fn flatten(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
 
 fn flatten_(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_range(0, 1)?;
+    self_val.check_frozen()?;
     let level = if args.len() == 0 {
         None
     } else {
@@ -1301,6 +1633,643 @@ fn ary_flatten(
     Ok(flag)
 }
 
+/// ## instance method Array#insert
+/// - insert(nth, *val) -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/insert.html
+fn insert(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_min(1)?;
+    self_val.check_frozen()?;
+    let mut aref = self_val.into_array();
+    let len = aref.len() as i64;
+    let nth = vm[0].coerce_to_fixnum("1st arg")?;
+    let pos = if nth < 0 {
+        let pos = len + nth + 1;
+        if pos < 0 {
+            return Err(RubyError::index(format!(
+                "index {} too small for array; minimum: -{}",
+                nth,
+                len + 1
+            )));
+        }
+        pos as usize
+    } else {
+        nth as usize
+    };
+    if aref.len() < pos {
+        aref.resize(pos, Value::nil());
+    }
+    let mut new = ArrayInfo::new_from_slice(&aref[..pos]);
+    new.extend_from_slice(&vm.args()[1..]);
+    new.extend_from_slice(&aref[pos..]);
+    *aref = new;
+    Ok(self_val)
+}
+
+/// ## instance method Array#delete_at
+/// - delete_at(pos) -> object | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/delete_at.html
+fn delete_at(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    self_val.check_frozen()?;
+    let mut aref = self_val.into_array();
+    let len = aref.len() as i64;
+    let pos = match vm[0].coerce_to_fixnum("1st arg")? {
+        i if i < 0 => len + i,
+        i => i,
+    };
+    if pos < 0 || len <= pos {
+        return Ok(Value::nil());
+    }
+    let pos = pos as usize;
+    Ok(aref.drain(pos..pos + 1)[0])
+}
+
+/// ## instance method Array#replace
+/// - replace(another) -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/replace.html
+fn replace(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    self_val.check_frozen()?;
+    let new = ArrayInfo::new_from_slice(&vm[0].expect_array("1st arg")?);
+    *self_val.into_array() = new;
+    Ok(self_val)
+}
+
+/// ## instance method Array#fetch
+/// - fetch(nth) -> object
+/// - fetch(nth, ifnone) -> object
+/// - fetch(nth) {|nth| ... } -> object
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/fetch.html
+fn fetch(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(1, 2)?;
+    let aref = self_val.into_array();
+    let len = aref.len() as i64;
+    let nth = vm[0].coerce_to_fixnum("1st arg")?;
+    let pos = if nth < 0 { len + nth } else { nth };
+    if 0 <= pos && pos < len {
+        return Ok(aref[pos as usize]);
+    }
+    if let Some(block) = &args.block {
+        return vm.eval_block1(block, vm[0]);
+    }
+    if args.len() == 2 {
+        return Ok(vm[1]);
+    }
+    Err(RubyError::index(format!(
+        "index {} outside of array bounds: {}...{}",
+        nth, -len, len
+    )))
+}
+
+/// ## instance method Array#values_at
+/// - values_at(*selectors) -> Array
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/values_at.html
+fn values_at(vm: &mut VM, self_val: Value, _: &Args2) -> VMResult {
+    let aref = self_val.into_array();
+    let mut res = vec![];
+    for selector in vm.args() {
+        let val = aref.get_elem1(*selector)?;
+        if selector.as_range().is_some() {
+            if let Some(ary) = val.as_array() {
+                res.extend_from_slice(&ary);
+            }
+        } else {
+            res.push(val);
+        }
+    }
+    Ok(Value::array_from(res))
+}
+
+/// ## instance method Array#dig
+/// - dig(idx, ...) -> object | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/dig.html
+fn dig(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_min(1)?;
+    let rest = vm.args()[1..].to_vec();
+    let val = self_val.into_array().get_elem1(vm[0])?;
+    if rest.is_empty() || val.is_nil() {
+        return Ok(val);
+    }
+    vm.eval_send(IdentId::get_id("dig"), val, &Args::from_slice(&rest))
+}
+
+/// ## instance method Array#assoc
+/// - assoc(key) -> Array | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/assoc.html
+fn assoc(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    for elem in self_val.into_array().iter() {
+        if let Some(ary) = elem.as_array() {
+            if !ary.is_empty() && vm.eval_eq2(ary[0], vm[0])? {
+                return Ok(*elem);
+            }
+        }
+    }
+    Ok(Value::nil())
+}
+
+/// ## instance method Array#rassoc
+/// - rassoc(obj) -> Array | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/rassoc.html
+fn rassoc(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    for elem in self_val.into_array().iter() {
+        if let Some(ary) = elem.as_array() {
+            if ary.len() >= 2 && vm.eval_eq2(ary[1], vm[0])? {
+                return Ok(*elem);
+            }
+        }
+    }
+    Ok(Value::nil())
+}
+
+/// ## instance method Array#rotate
+/// - rotate(cnt = 1) -> Array
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/rotate.html
+fn rotate(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    let new = Value::array_from(self_val.into_array().to_vec());
+    rotate_(vm, new, args)
+}
+
+/// ## instance method Array#sort!
+/// - sort! -> self
+/// - sort! {|a, b| ... } -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/sort=21.html
+fn sort_(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    self_val.check_frozen()?;
+    let sorted = sort(vm, self_val, args)?;
+    *self_val.into_array() = ArrayInfo::new_from_slice(&sorted.into_array());
+    Ok(self_val)
+}
+
+/// ## instance method Array#sort_by!
+/// - sort_by! {|item| ... } -> self
+/// - sort_by! -> Enumerator
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/sort_by=21.html
+fn sort_by_(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    to_enum_str!(vm, self_val, args, "sort_by!");
+    self_val.check_frozen()?;
+    let sorted = sort_by(vm, self_val, args)?;
+    *self_val.into_array() = ArrayInfo::new_from_slice(&sorted.into_array());
+    Ok(self_val)
+}
+
+/// ## instance method Array#rindex
+/// - rindex(val) -> Integer | nil
+/// - rindex {|item| ... } -> Integer | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/rindex.html
+fn rindex(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
+    let ary = self_val.into_array();
+    if args.len() == 1 {
+        for (i, v) in ary.iter().enumerate().rev() {
+            if vm.eval_eq2(*v, vm[0])? {
+                return Ok(Value::integer(i as i64));
+            };
+        }
+        return Ok(Value::nil());
+    };
+    let block = to_enum_str!(vm, self_val, args, "rindex");
+    let f = vm.eval_block_map1(block);
+    for (i, elem) in ary.iter().enumerate().rev() {
+        if f(vm, *elem)?.to_bool() {
+            return Ok(Value::integer(i as i64));
+        };
+    }
+    Ok(Value::nil())
+}
+
+/// Retains only elements for which the block returns `keep_if`.
+///
+/// Returns true when one or some elements were removed.
+fn retain_by_block(
+    vm: &mut VM,
+    self_val: Value,
+    block: &Block,
+    keep_if: bool,
+) -> Result<bool, RubyError> {
+    self_val.check_frozen()?;
+    let mut aref = self_val.into_array();
+    let f = vm.eval_block_map1(block);
+    aref.retain(|x| Ok(f(vm, *x)?.to_bool() == keep_if))
+}
+
+/// ## instance method Array#select!
+/// - select! {|item| ... } -> self | nil
+/// - filter! {|item| ... } -> self | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/filter=21.html
+fn select_(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let block = to_enum_str!(vm, self_val, args, "select!");
+    if retain_by_block(vm, self_val, block, true)? {
+        Ok(self_val)
+    } else {
+        Ok(Value::nil())
+    }
+}
+
+/// ## instance method Array#keep_if
+/// - keep_if {|item| ... } -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/keep_if.html
+fn keep_if(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let block = to_enum_str!(vm, self_val, args, "keep_if");
+    retain_by_block(vm, self_val, block, true)?;
+    Ok(self_val)
+}
+
+/// ## instance method Array#reject!
+/// - reject! {|item| ... } -> self | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/reject=21.html
+fn reject_(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let block = to_enum_str!(vm, self_val, args, "reject!");
+    if retain_by_block(vm, self_val, block, false)? {
+        Ok(self_val)
+    } else {
+        Ok(Value::nil())
+    }
+}
+
+/// ## instance method Array#delete_if
+/// - delete_if {|item| ... } -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/delete_if.html
+fn delete_if(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let block = to_enum_str!(vm, self_val, args, "delete_if");
+    retain_by_block(vm, self_val, block, false)?;
+    Ok(self_val)
+}
+
+/// ## instance method Array#union
+/// - union(*other_arrays) -> Array
+/// - self | other -> Array
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/union.html
+fn union(vm: &mut VM, self_val: Value, _: &Args2) -> VMResult {
+    let mut h = FxHashSet::default();
+    let mut v = vec![];
+    let mut push_uniq = |ary: &[Value]| {
+        for elem in ary {
+            if h.insert(HashKey(*elem)) {
+                v.push(*elem);
+            }
+        }
+    };
+    push_uniq(&self_val.into_array());
+    for arg in vm.args() {
+        push_uniq(&arg.expect_array("Argument")?);
+    }
+    Ok(Value::array_from(v))
+}
+
+/// ## instance method Array#intersection
+/// - intersection(*other_arrays) -> Array
+/// - self & other -> Array
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/intersection.html
+fn intersection(vm: &mut VM, self_val: Value, _: &Args2) -> VMResult {
+    let mut others = vec![];
+    for arg in vm.args() {
+        let ary = arg.expect_array("Argument")?;
+        others.push(ary.iter().map(|v| HashKey(*v)).collect::<FxHashSet<_>>());
+    }
+    let mut h = FxHashSet::default();
+    let mut v = vec![];
+    for elem in self_val.into_array().iter() {
+        let key = HashKey(*elem);
+        if others.iter().all(|other| other.contains(&key)) && h.insert(key) {
+            v.push(*elem);
+        }
+    }
+    Ok(Value::array_from(v))
+}
+
+/// ## instance method Array#intersect?
+/// - intersect?(other) -> bool
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/intersect=3f.html
+fn intersect(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let other: FxHashSet<_> = vm[0]
+        .expect_array("1st arg")?
+        .iter()
+        .map(|v| HashKey(*v))
+        .collect();
+    let res = self_val
+        .into_array()
+        .iter()
+        .any(|elem| other.contains(&HashKey(*elem)));
+    Ok(Value::bool(res))
+}
+
+/// ## instance method Array#difference
+/// - difference(*other_arrays) -> Array
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/difference.html
+fn difference(vm: &mut VM, self_val: Value, _: &Args2) -> VMResult {
+    let mut h = FxHashSet::default();
+    for arg in vm.args() {
+        h.extend(arg.expect_array("Argument")?.iter().map(|v| HashKey(*v)));
+    }
+    let v = self_val
+        .into_array()
+        .iter()
+        .filter(|elem| !h.contains(&HashKey(**elem)))
+        .cloned()
+        .collect();
+    Ok(Value::array_from(v))
+}
+
+/// Call `f` with each combination of `k` indices out of `0..n` in lexicographic order.
+///
+/// If `repeated` is true, indices may appear more than once.
+fn each_combination(
+    n: usize,
+    k: usize,
+    repeated: bool,
+    f: &mut dyn FnMut(&[usize]) -> Result<(), RubyError>,
+) -> Result<(), RubyError> {
+    if k == 0 {
+        return f(&[]);
+    }
+    if n == 0 || (!repeated && n < k) {
+        return Ok(());
+    }
+    let mut idx: Vec<usize> = if repeated {
+        vec![0; k]
+    } else {
+        (0..k).collect()
+    };
+    loop {
+        f(&idx)?;
+        // Find the rightmost index which can be incremented.
+        let mut i = k;
+        loop {
+            if i == 0 {
+                return Ok(());
+            }
+            i -= 1;
+            let max = if repeated { n - 1 } else { n - k + i };
+            if idx[i] < max {
+                break;
+            }
+        }
+        idx[i] += 1;
+        for j in i + 1..k {
+            idx[j] = if repeated { idx[i] } else { idx[j - 1] + 1 };
+        }
+    }
+}
+
+/// Call `f` with each permutation of `k` indices out of `0..n` in lexicographic order.
+///
+/// If `repeated` is true, indices may appear more than once.
+fn each_permutation(
+    n: usize,
+    k: usize,
+    repeated: bool,
+    f: &mut dyn FnMut(&[usize]) -> Result<(), RubyError>,
+) -> Result<(), RubyError> {
+    fn perm(
+        n: usize,
+        k: usize,
+        repeated: bool,
+        idx: &mut Vec<usize>,
+        used: &mut Vec<bool>,
+        f: &mut dyn FnMut(&[usize]) -> Result<(), RubyError>,
+    ) -> Result<(), RubyError> {
+        if idx.len() == k {
+            return f(idx);
+        }
+        for i in 0..n {
+            if !repeated && used[i] {
+                continue;
+            }
+            used[i] = true;
+            idx.push(i);
+            perm(n, k, repeated, idx, used, f)?;
+            idx.pop();
+            used[i] = false;
+        }
+        Ok(())
+    }
+    if !repeated && n < k {
+        return Ok(());
+    }
+    perm(n, k, repeated, &mut vec![], &mut vec![false; n], f)
+}
+
+type IndexGenerator = fn(
+    usize,
+    usize,
+    bool,
+    &mut dyn FnMut(&[usize]) -> Result<(), RubyError>,
+) -> Result<(), RubyError>;
+
+/// Yield arrays of elements of `self_val` chosen by `gen`, and return `self_val`.
+fn yield_indices(
+    vm: &mut VM,
+    self_val: Value,
+    block: &Block,
+    k: i64,
+    repeated: bool,
+    gen: IndexGenerator,
+) -> VMResult {
+    if k < 0 {
+        return Ok(self_val);
+    }
+    // Elements are copied so that the block can modify the receiver safely.
+    let ary = self_val.into_array().to_vec();
+    let temp_len = vm.temp_len();
+    vm.temp_extend_from_slice(&ary);
+    let res = gen(ary.len(), k as usize, repeated, &mut |idx| {
+        let elems = idx.iter().map(|i| ary[*i]).collect();
+        vm.eval_block1(block, Value::array_from(elems))?;
+        Ok(())
+    });
+    vm.temp_pop_vec(temp_len);
+    res?;
+    Ok(self_val)
+}
+
+/// ## instance method Array#combination
+/// - combination(n) {|c| ... } -> self
+/// - combination(n) -> Enumerator
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/combination.html
+fn combination(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let block = to_enum_str!(vm, self_val, args, "combination");
+    let k = vm[0].coerce_to_fixnum("1st arg")?;
+    yield_indices(vm, self_val, block, k, false, each_combination)
+}
+
+/// ## instance method Array#repeated_combination
+/// - repeated_combination(n) {|c| ... } -> self
+/// - repeated_combination(n) -> Enumerator
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/repeated_combination.html
+fn repeated_combination(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let block = to_enum_str!(vm, self_val, args, "repeated_combination");
+    let k = vm[0].coerce_to_fixnum("1st arg")?;
+    yield_indices(vm, self_val, block, k, true, each_combination)
+}
+
+/// ## instance method Array#permutation
+/// - permutation(n = self.length) {|p| ... } -> self
+/// - permutation(n = self.length) -> Enumerator
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/permutation.html
+fn permutation(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
+    let block = to_enum_str!(vm, self_val, args, "permutation");
+    let k = if args.len() == 0 {
+        self_val.into_array().len() as i64
+    } else {
+        vm[0].coerce_to_fixnum("1st arg")?
+    };
+    yield_indices(vm, self_val, block, k, false, each_permutation)
+}
+
+/// ## instance method Array#repeated_permutation
+/// - repeated_permutation(n) {|p| ... } -> self
+/// - repeated_permutation(n) -> Enumerator
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/repeated_permutation.html
+fn repeated_permutation(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let block = to_enum_str!(vm, self_val, args, "repeated_permutation");
+    let k = vm[0].coerce_to_fixnum("1st arg")?;
+    yield_indices(vm, self_val, block, k, true, each_permutation)
+}
+
+/// ## instance method Array#product
+/// - product(*lists) -> Array
+/// - product(*lists) {|e| ... } -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/product.html
+fn product(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    let mut lists = vec![self_val.into_array().to_vec()];
+    for arg in vm.args() {
+        lists.push(arg.expect_array("Argument")?.to_vec());
+    }
+    let mut res: Vec<Vec<Value>> = vec![vec![]];
+    for list in &lists {
+        res = res
+            .iter()
+            .flat_map(|prefix| {
+                list.iter().map(move |elem| {
+                    let mut v = prefix.clone();
+                    v.push(*elem);
+                    v
+                })
+            })
+            .collect();
+    }
+    let res: Vec<_> = res.into_iter().map(Value::array_from).collect();
+    match &args.block {
+        None => Ok(Value::array_from(res)),
+        Some(block) => {
+            let temp_len = vm.temp_len();
+            vm.temp_extend_from_slice(&res);
+            for val in res {
+                if let Err(err) = vm.eval_block1(block, val) {
+                    vm.temp_pop_vec(temp_len);
+                    return Err(err);
+                }
+            }
+            vm.temp_pop_vec(temp_len);
+            Ok(self_val)
+        }
+    }
+}
+
+/// ## instance method Array#cycle
+/// - cycle {|obj| ... } -> nil
+/// - cycle(n) {|obj| ... } -> nil
+/// - cycle(n = nil) -> Enumerator
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/cycle.html
+fn cycle(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
+    let block = to_enum_str!(vm, self_val, args, "cycle");
+    let count = if args.len() == 0 || vm[0].is_nil() {
+        None
+    } else {
+        Some(vm[0].coerce_to_fixnum("1st arg")?)
+    };
+    let aref = self_val.into_array();
+    let f = vm.eval_block_map1(block);
+    let mut n = 0;
+    while !matches!(count, Some(count) if n >= count) && !aref.is_empty() {
+        let mut i = 0;
+        while i < aref.len() {
+            f(vm, aref[i])?;
+            i += 1;
+        }
+        n += 1;
+    }
+    Ok(Value::nil())
+}
+
+/// ## instance method Array#sample
+/// - sample -> object | nil
+/// - sample(n) -> Array
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/sample.html
+fn sample(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    use rand::seq::SliceRandom;
+    args.check_args_range(0, 1)?;
+    let aref = self_val.into_array();
+    let mut rng = rand::thread_rng();
+    if args.len() == 0 {
+        return Ok(aref.choose(&mut rng).cloned().unwrap_or_default());
+    }
+    let num = arg_to_size(vm[0])?;
+    let v = aref.choose_multiple(&mut rng, num).cloned().collect();
+    Ok(Value::array_from(v))
+}
+
+/// ## instance method Array#shuffle
+/// - shuffle -> Array
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/shuffle.html
+fn shuffle(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    let new = Value::array_from(self_val.into_array().to_vec());
+    shuffle_(vm, new, args)
+}
+
+/// ## instance method Array#shuffle!
+/// - shuffle! -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Array/i/shuffle=21.html
+fn shuffle_(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    use rand::seq::SliceRandom;
+    args.check_args_num(0)?;
+    self_val.check_frozen()?;
+    self_val.into_array().shuffle(&mut rand::thread_rng());
+    Ok(self_val)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::tests::*;
@@ -2019,4 +2988,132 @@ mod tests {
         "#;
         assert_script(program);
     }
+
+    #[test]
+    fn array_access() {
+        let program = r#"
+        a = [1, 2, 3]
+        assert [1, 2], a.first(2)
+        assert [2, 3], a.last(2)
+        assert [1, 2, 3], a.first(5)
+        assert 2, a.fetch(1)
+        assert 3, a.fetch(-1)
+        assert 0, a.fetch(9, 0)
+        assert 18, a.fetch(9) { |i| i * 2 }
+        assert_error { a.fetch(9) }
+        assert [1, 3, nil, 1, 2], a.values_at(0, 2, 5, 0..1)
+        assert 2, [[1, [2, 3]]].dig(0, 1, 0)
+        assert 2, {a: [1, 2]}.dig(:a, 1)
+        assert [2, :b], [[1, :a], [2, :b]].assoc(2)
+        assert [1, :a], [[1, :a], [2, :b]].rassoc(:a)
+        assert 2, [1, 2, 1].rindex(1)
+        assert 1, [1, 2, 1].rindex { |x| x == 2 }
+        assert [2, 3, 1], a.rotate
+        assert [3, 1, 2], a.rotate(-1)
+        assert [1, 2, 3], a
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn array_destructive() {
+        let program = r#"
+        a = [1, 2, 3]
+        assert [1, 9, 8, 2, 3], a.insert(1, 9, 8)
+        assert [1, 9, 8, 2, 7, 3], a.insert(-2, 7)
+        assert 9, a.delete_at(1)
+        assert nil, a.delete_at(10)
+        assert [7, 3], a.pop(2)
+        assert [1, 8, 2], a
+        assert [1, 8, 2, 4, 5], a.concat([4], [5])
+        assert [9], a.replace([9])
+        a = [1, 2, 3, 4, 5]
+        assert 2, a.slice!(1)
+        assert [3, 4], a.slice!(1..2)
+        assert [1, 5], a
+        assert [2, 4], [1, 2, 3, 4].delete_if(&:odd?)
+        assert [1, 3], [1, 2, 3].keep_if(&:odd?)
+        assert nil, [1, 2].select! { true }
+        assert [1], [1, 2].select!(&:odd?)
+        assert nil, [1, 2].reject! { false }
+        assert [2], [1, 2].reject!(&:odd?)
+        a = [3, 1, 2]
+        assert [1, 2, 3], a.sort!
+        assert [3, 2, 1], a.sort_by! { |x| -x }
+        assert [3, 2, 1], a
+        assert [1, 2, 3], a.shuffle.sort
+        assert [1, 2, 3], a.shuffle!.sort
+        assert true, a.include?(a.sample)
+        assert [1, 2, 3], a.sample(5).sort
+        assert_error { [1].freeze.delete_if { true } }
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn array_set_operations() {
+        let program = r#"
+        a = [1, 2, 3, 3]
+        assert [1, 2, 3, 4, 5], a.union([3, 4], [5])
+        assert [1, 2, 3, 9], a | [1, 9]
+        assert [3], a.intersection([2, 3], [3])
+        assert [1, 3], a & [3, 1]
+        assert [1, 3, 3], a.difference([2])
+        assert true, a.intersect?([3])
+        assert false, a.intersect?([4])
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn array_combinatorics() {
+        let program = r#"
+        a = [1, 2, 3]
+        assert [[1, 4], [1, 5], [2, 4], [2, 5], [3, 4], [3, 5]], a.product([4, 5])
+        assert [[1, 2], [1, 3], [2, 3]], a.combination(2).to_a
+        assert [[]], a.combination(0).to_a
+        assert [], a.combination(4).to_a
+        assert [[1, 2], [1, 3], [2, 1], [2, 3], [3, 1], [3, 2]], a.permutation(2).to_a
+        assert 6, a.permutation.to_a.size
+        assert [[1, 1], [1, 2], [1, 3], [2, 2], [2, 3], [3, 3]], a.repeated_combination(2).to_a
+        assert 9, a.repeated_permutation(2).to_a.size
+        assert [1, 2, 3, 1, 2], a.cycle.first(5)
+        assert [1, 2, 3, 1, 2, 3], a.cycle(2).to_a
+        res = []
+        assert a, a.combination(2) { |c| res << c.sum }
+        assert [3, 4, 5], res
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn array_pack() {
+        let program = r#"
+        assert "AB", [65, 66].pack("C*")
+        assert "\x00\x01\x02\x00\x00\x00", [1, 2].pack("s>l<")
+        assert "\x00\x00\x00\x01", [1].pack("N")
+        assert "\x01\x00", [1].pack("v")
+        assert "\u3042A", [0x3042, 65].pack("U*")
+        assert "ab\x00c  ", ["ab", "c"].pack("a3A3")
+        assert "x\x00", ["x"].pack("Z*")
+        assert_error { [1].pack("C2") }
+        assert [0, 0, 192, 63], [1.5].pack("e").bytes
+        assert [63, 248, 0, 0, 0, 0, 0, 0], [1.5].pack("G").bytes
+        assert [63, 128, 0, 0, 64, 0, 0, 0], [1, 2].pack("g2").bytes
+        assert [1.5].pack("E"), [1.5].pack("D<")
+        assert_error { ["x"].pack("d") }
+        assert [176, 13], ["10110", "10110"].pack("B*b*").bytes
+        assert [161, 240, 26, 15], ["a1f", "a1f"].pack("H*h*").bytes
+        assert [160, 0], ["a"].pack("H4").bytes
+        assert "YWJj\n", ["abc"].pack("m")
+        assert "YWI=", ["ab"].pack("m0")
+        assert "YWFh\nYWFh\nYQ==\n", ["aaaaaaa"].pack("m3")
+        assert "a=3Db\tc\n", ["a=b\tc\n"].pack("M")
+        assert "12=\n", [12].pack("M")
+        assert [1, 129, 0, 129, 128, 0], [1, 128, 16384].pack("w*").bytes
+        assert_error { [-1].pack("w") }
+        assert_error { [1].pack("y") }
+        "#;
+        assert_script(program);
+    }
 }
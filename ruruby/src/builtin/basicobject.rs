@@ -4,6 +4,7 @@ pub(crate) fn init(globals: &mut Globals) {
     let mut class = BuiltinClass::object().superclass().unwrap();
     globals.set_toplevel_constant("BasicObject", class);
     class.add_builtin_method(globals, IdentId::_ALIAS_METHOD, alias_method);
+    class.add_builtin_method(globals, IdentId::_UNDEF_METHOD, undef_method);
     class.add_builtin_method(globals, IdentId::_METHOD_MISSING, method_missing);
     class.add_builtin_method_by_str(globals, "__id__", basicobject_id);
     class.add_builtin_method_by_str(globals, "instance_exec", instance_exec);
@@ -39,6 +40,13 @@ fn alias_method(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     Ok(Value::nil())
 }
 
+/// An undef statement is compiled to method call for this func.
+fn undef_method(vm: &mut VM, self_val: Value, _args: &Args2) -> VMResult {
+    let class = vm.globals.get_class_if_object(self_val);
+    builtin::module::undef_methods(vm, class)?;
+    Ok(Value::nil())
+}
+
 fn method_missing(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_min(1)?;
     let method_id = match vm[0].as_symbol() {
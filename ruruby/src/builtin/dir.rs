@@ -190,7 +190,7 @@ mod test {
         let program = r##"
         assert ENV["HOME"], Dir.home
         #assert ENV["PWD"], Dir.pwd  this fails in GitHub Actions 2021.2
-        assert ["src/builtin/enumerator.rs"], Dir["**/en*?.rs"]
+        assert ["src/builtin/encoding.rs", "src/builtin/enumerable.rs", "src/builtin/enumerator.rs"], Dir["**/en*?.rs"].sort
         assert [
             "src/alloc.rs","src/arith.rs","src/builtin/array.rs",
             "src/coroutine/asm_windows_x64.rs",
@@ -198,7 +198,8 @@ mod test {
             "src/coroutine/asm_arm64.rs",
             "src/value/array.rs",
             "src/vm/args.rs",
-            "src/vm/executor/frame/arg_handler.rs"
+            "src/vm/executor/frame/arg_handler.rs",
+            "src/vm/executor/jit/asm.rs"
         ].sort, Dir["src/**/a*s"].sort
         assert true, Dir.exist?("src")
         assert false, Dir.exist?("srd")
@@ -0,0 +1,830 @@
+use crate::*;
+use std::ops::Range;
+
+/// Character encodings supported by ruruby.
+///
+/// A String without an explicit encoding is UTF-8.
+/// The encoding of a String is held in its `/encoding` ivar as an Encoding object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Encoding {
+    Utf8,
+    Ascii8Bit,
+    UsAscii,
+    Utf16Le,
+    Utf16Be,
+    Utf32Le,
+    Utf32Be,
+    ShiftJis,
+    Windows31J,
+    EucJp,
+    Iso8859_1,
+    Windows1252,
+    Ibm437,
+}
+
+/// (encoding, names, constant names). The first name is the canonical one.
+const ENCODINGS: &[(Encoding, &[&str], &[&str])] = &[
+    (Encoding::Utf8, &["UTF-8", "CP65001"], &["UTF_8", "CP65001"]),
+    (
+        Encoding::Ascii8Bit,
+        &["ASCII-8BIT", "BINARY"],
+        &["ASCII_8BIT", "BINARY"],
+    ),
+    (
+        Encoding::UsAscii,
+        &["US-ASCII", "ASCII", "ANSI_X3.4-1968", "646"],
+        &["US_ASCII", "ASCII", "ANSI_X3_4_1968"],
+    ),
+    (Encoding::Utf16Le, &["UTF-16LE"], &["UTF_16LE"]),
+    (
+        Encoding::Utf16Be,
+        &["UTF-16BE", "UCS-2BE"],
+        &["UTF_16BE", "UCS_2BE"],
+    ),
+    (
+        Encoding::Utf32Le,
+        &["UTF-32LE", "UCS-4LE"],
+        &["UTF_32LE", "UCS_4LE"],
+    ),
+    (
+        Encoding::Utf32Be,
+        &["UTF-32BE", "UCS-4BE"],
+        &["UTF_32BE", "UCS_4BE"],
+    ),
+    (
+        Encoding::ShiftJis,
+        &["Shift_JIS"],
+        &["Shift_JIS", "SHIFT_JIS"],
+    ),
+    (
+        Encoding::Windows31J,
+        &["Windows-31J", "CP932", "csWindows31J", "SJIS", "PCK"],
+        &[
+            "Windows_31J",
+            "WINDOWS_31J",
+            "CP932",
+            "CsWindows31J",
+            "CSWINDOWS31J",
+            "SJIS",
+            "PCK",
+        ],
+    ),
+    (
+        Encoding::EucJp,
+        &["EUC-JP", "eucJP"],
+        &["EUC_JP", "EucJP", "EUCJP"],
+    ),
+    (
+        Encoding::Iso8859_1,
+        &["ISO-8859-1", "ISO8859-1"],
+        &["ISO_8859_1", "ISO8859_1"],
+    ),
+    (
+        Encoding::Windows1252,
+        &["Windows-1252", "CP1252"],
+        &["Windows_1252", "WINDOWS_1252", "CP1252"],
+    ),
+    (Encoding::Ibm437, &["IBM437", "CP437"], &["IBM437", "CP437"]),
+];
+
+/// Called with an undefined byte sequence and the output buffer in decoding.
+type DecodeUndefHandler<'a> = dyn FnMut(&[u8], &mut String) -> Result<(), RubyError> + 'a;
+/// Called with an undefined char and the output buffer in encoding.
+type EncodeUndefHandler<'a> = dyn FnMut(char, &mut Vec<u8>) -> Result<(), RubyError> + 'a;
+/// Returns the replacement of an invalid byte sequence.
+type InvalidHandler<'a> = dyn FnMut(&[u8]) -> Result<Vec<u8>, RubyError> + 'a;
+
+pub(crate) fn init(globals: &mut Globals) {
+    let mut class = Module::class_under_object();
+    globals.set_toplevel_constant("Encoding", class);
+    class.add_builtin_method_by_str(globals, "name", name);
+    class.add_builtin_method_by_str(globals, "to_s", name);
+    class.add_builtin_method_by_str(globals, "names", names);
+    class.add_builtin_method_by_str(globals, "inspect", inspect);
+    class.add_builtin_method_by_str(globals, "ascii_compatible?", ascii_compatible);
+    class.add_builtin_method_by_str(globals, "dummy?", dummy);
+    class.add_builtin_class_method(globals, "find", find);
+    class.add_builtin_class_method(globals, "list", list);
+    class.add_builtin_class_method(globals, "name_list", name_list);
+    class.add_builtin_class_method(globals, "aliases", aliases);
+    class.add_builtin_class_method(globals, "compatible?", compatible);
+    class.add_builtin_class_method(globals, "default_external", default_external);
+    class.add_builtin_class_method(globals, "default_external=", set_default_external);
+    class.add_builtin_class_method(globals, "default_internal", default_internal);
+    class.add_builtin_class_method(globals, "default_internal=", set_default_internal);
+
+    for (_, names, consts) in ENCODINGS {
+        let enc = Value::ordinary_object(class);
+        enc.set_var(IdentId::_NAME, Value::string(names[0]));
+        for name in consts.iter() {
+            class.set_const_by_str(name, enc);
+        }
+    }
+    class.set_var_by_str("/default_external", Encoding::Utf8.to_value());
+
+    let encoding_error = globals.get_toplevel_constant("EncodingError").into_module();
+    for name in &[
+        "CompatibilityError",
+        "UndefinedConversionError",
+        "InvalidByteSequenceError",
+        "ConverterNotFoundError",
+    ] {
+        let err = Module::class_under(encoding_error);
+        class.set_const_by_str(name, err.into());
+    }
+}
+
+impl Encoding {
+    pub(crate) fn name(self) -> &'static str {
+        self.entry().1[0]
+    }
+
+    fn entry(self) -> &'static (Encoding, &'static [&'static str], &'static [&'static str]) {
+        ENCODINGS.iter().find(|(enc, _, _)| *enc == self).unwrap()
+    }
+
+    /// Find the encoding by its name or alias. Names are case-insensitive.
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        ENCODINGS
+            .iter()
+            .find(|(_, names, _)| names.iter().any(|n| n.eq_ignore_ascii_case(name)))
+            .map(|(enc, _, _)| *enc)
+    }
+
+    /// Get the encoding from an Encoding object or a name of the encoding.
+    pub(crate) fn from_value(val: Value) -> Result<Self, RubyError> {
+        let name = match val.as_rstring() {
+            Some(name) => name.to_s().into_owned(),
+            None => match val.get_var(IdentId::_NAME) {
+                Some(name) if val.get_class_name() == "Encoding" => match name.as_rstring() {
+                    Some(name) => name.to_s().into_owned(),
+                    None => return Err(VMError::no_implicit_conv(val, "String")),
+                },
+                _ => return Err(VMError::no_implicit_conv(val, "String")),
+            },
+        };
+        Self::from_name(&name)
+            .ok_or_else(|| RubyError::argument(format!("unknown encoding name - {}", name)))
+    }
+
+    /// Get the encoding if `val` is an Encoding object.
+    pub(crate) fn of_object(val: Value) -> Option<Self> {
+        match val.get_var(IdentId::_NAME) {
+            Some(_) if val.get_class_name() == "Encoding" => Self::from_value(val).ok(),
+            _ => None,
+        }
+    }
+
+    fn class() -> Module {
+        BuiltinClass::object()
+            .get_const_noautoload(IdentId::get_id("Encoding"))
+            .unwrap()
+            .into_module()
+    }
+
+    /// Get the Encoding object.
+    pub(crate) fn to_value(self) -> Value {
+        Self::class()
+            .get_const_noautoload(IdentId::get_id(self.entry().2[0]))
+            .unwrap()
+    }
+
+    /// Get Encoding.default_internal.
+    pub(crate) fn default_internal() -> Option<Self> {
+        match Self::class().get_var(IdentId::get_id("/default_internal")) {
+            Some(enc) if !enc.is_nil() => Self::from_value(enc).ok(),
+            _ => None,
+        }
+    }
+
+    /// Get the encoding of String `val`.
+    pub(crate) fn of(val: Value) -> Self {
+        match val.get_var(IdentId::_ENCODING) {
+            Some(enc) if !enc.is_nil() => Self::from_value(enc).unwrap_or(Encoding::Utf8),
+            _ => Encoding::Utf8,
+        }
+    }
+
+    /// Inspect String `val`.
+    ///
+    /// Non-ASCII bytes are escaped as `\xHH` if the encoding of `val` is ASCII-8BIT or US-ASCII.
+    pub(crate) fn inspect_string(val: Value) -> String {
+        let rstring = val.as_rstring().unwrap();
+        match Self::of(val) {
+            Encoding::Ascii8Bit | Encoding::UsAscii => rstring.inspect_binary(),
+            _ => rstring.inspect(),
+        }
+    }
+
+    /// Set the encoding of String `val`.
+    pub(crate) fn set(self, val: Value) {
+        let enc = match self {
+            Encoding::Utf8 => Value::nil(),
+            _ => self.to_value(),
+        };
+        val.set_var(IdentId::_ENCODING, enc);
+    }
+
+    pub(crate) fn is_ascii_compatible(self) -> bool {
+        !matches!(
+            self,
+            Encoding::Utf16Le | Encoding::Utf16Be | Encoding::Utf32Le | Encoding::Utf32Be
+        )
+    }
+
+    pub(crate) fn is_unicode(self) -> bool {
+        matches!(
+            self,
+            Encoding::Utf8
+                | Encoding::Utf16Le
+                | Encoding::Utf16Be
+                | Encoding::Utf32Le
+                | Encoding::Utf32Be
+        )
+    }
+
+    /// The transcoding table in encoding_rs.
+    fn table(self) -> Option<&'static encoding_rs::Encoding> {
+        match self {
+            Encoding::ShiftJis | Encoding::Windows31J => Some(encoding_rs::SHIFT_JIS),
+            Encoding::EucJp => Some(encoding_rs::EUC_JP),
+            Encoding::Windows1252 => Some(encoding_rs::WINDOWS_1252),
+            _ => None,
+        }
+    }
+
+    fn has_converter(self) -> bool {
+        self != Encoding::Ibm437
+    }
+
+    /// Split `bytes` into valid and invalid byte sequences.
+    fn segments(self, bytes: &[u8]) -> Vec<(Range<usize>, bool)> {
+        let mut res: Vec<(Range<usize>, bool)> = vec![];
+        let mut push = |range: Range<usize>, valid: bool| {
+            if range.is_empty() {
+                return;
+            }
+            match res.last_mut() {
+                Some((last, true)) if valid && last.end == range.start => last.end = range.end,
+                _ => res.push((range, valid)),
+            }
+        };
+        match self {
+            Encoding::Utf8 => {
+                let mut pos = 0;
+                for chunk in bytes.utf8_chunks() {
+                    let valid = chunk.valid().len();
+                    let invalid = chunk.invalid().len();
+                    push(pos..pos + valid, true);
+                    push(pos + valid..pos + valid + invalid, false);
+                    pos += valid + invalid;
+                }
+            }
+            Encoding::UsAscii => {
+                for (i, b) in bytes.iter().enumerate() {
+                    push(i..i + 1, b.is_ascii());
+                }
+            }
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                let unit = |i: usize| {
+                    let pair = [bytes[i], bytes[i + 1]];
+                    if self == Encoding::Utf16Le {
+                        u16::from_le_bytes(pair)
+                    } else {
+                        u16::from_be_bytes(pair)
+                    }
+                };
+                let mut i = 0;
+                while i + 1 < bytes.len() {
+                    let u = unit(i);
+                    if (0xd800..0xdc00).contains(&u)
+                        && i + 3 < bytes.len()
+                        && (0xdc00..0xe000).contains(&unit(i + 2))
+                    {
+                        push(i..i + 4, true);
+                        i += 4;
+                    } else {
+                        push(i..i + 2, !(0xd800..0xe000).contains(&u));
+                        i += 2;
+                    }
+                }
+                push(i..bytes.len(), false);
+            }
+            Encoding::Utf32Le | Encoding::Utf32Be => {
+                let mut i = 0;
+                while i + 3 < bytes.len() {
+                    let quad = [bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]];
+                    let c = if self == Encoding::Utf32Le {
+                        u32::from_le_bytes(quad)
+                    } else {
+                        u32::from_be_bytes(quad)
+                    };
+                    push(i..i + 4, char::from_u32(c).is_some());
+                    i += 4;
+                }
+                push(i..bytes.len(), false);
+            }
+            Encoding::ShiftJis | Encoding::Windows31J | Encoding::EucJp => {
+                use encoding_rs::DecoderResult;
+                let mut decoder = self.table().unwrap().new_decoder_without_bom_handling();
+                let mut out = String::with_capacity(
+                    decoder
+                        .max_utf8_buffer_length_without_replacement(bytes.len())
+                        .unwrap_or_default(),
+                );
+                let mut pos = 0;
+                loop {
+                    let (result, read) =
+                        decoder.decode_to_string_without_replacement(&bytes[pos..], &mut out, true);
+                    let end = pos + read;
+                    match result {
+                        DecoderResult::InputEmpty => {
+                            push(pos..end, true);
+                            break;
+                        }
+                        DecoderResult::OutputFull => {
+                            push(pos..end, true);
+                            out.reserve(bytes.len());
+                        }
+                        DecoderResult::Malformed(bad, extra) => {
+                            let bad_end = end - extra as usize;
+                            let bad_start = bad_end - bad as usize;
+                            push(pos..bad_start, true);
+                            push(bad_start..bad_end, false);
+                            // Bytes after the malformed sequence are decoded again.
+                            decoder = self.table().unwrap().new_decoder_without_bom_handling();
+                            out.clear();
+                            pos = bad_end;
+                            continue;
+                        }
+                    }
+                    pos = end;
+                }
+            }
+            Encoding::Ascii8Bit
+            | Encoding::Iso8859_1
+            | Encoding::Windows1252
+            | Encoding::Ibm437 => push(0..bytes.len(), true),
+        }
+        res
+    }
+
+    pub(crate) fn is_valid(self, bytes: &[u8]) -> bool {
+        self.segments(bytes).iter().all(|(_, valid)| *valid)
+    }
+
+    /// Decode a valid byte sequence `bytes` and push the chars to `out`.
+    ///
+    /// `undef` is called for a byte which can not be converted to Unicode.
+    fn decode_valid(
+        self,
+        bytes: &[u8],
+        out: &mut String,
+        undef: &mut DecodeUndefHandler,
+    ) -> Result<(), RubyError> {
+        match self {
+            Encoding::Utf8 => out.push_str(std::str::from_utf8(bytes).unwrap()),
+            Encoding::UsAscii | Encoding::Iso8859_1 => out.extend(bytes.iter().map(|b| *b as char)),
+            Encoding::Ascii8Bit => {
+                for (i, b) in bytes.iter().enumerate() {
+                    if b.is_ascii() {
+                        out.push(*b as char);
+                    } else {
+                        undef(&bytes[i..i + 1], out)?;
+                    }
+                }
+            }
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                let units = bytes.chunks(2).map(|pair| {
+                    if self == Encoding::Utf16Le {
+                        u16::from_le_bytes([pair[0], pair[1]])
+                    } else {
+                        u16::from_be_bytes([pair[0], pair[1]])
+                    }
+                });
+                out.extend(std::char::decode_utf16(units).map(|c| c.unwrap()));
+            }
+            Encoding::Utf32Le | Encoding::Utf32Be => {
+                out.extend(bytes.chunks(4).map(|quad| {
+                    let quad = [quad[0], quad[1], quad[2], quad[3]];
+                    let c = if self == Encoding::Utf32Le {
+                        u32::from_le_bytes(quad)
+                    } else {
+                        u32::from_be_bytes(quad)
+                    };
+                    char::from_u32(c).unwrap()
+                }));
+            }
+            Encoding::ShiftJis | Encoding::Windows31J | Encoding::EucJp | Encoding::Windows1252 => {
+                let table = self.table().unwrap();
+                out.push_str(&table.decode_without_bom_handling(bytes).0)
+            }
+            Encoding::Ibm437 => unreachable!(),
+        }
+        Ok(())
+    }
+
+    /// Encode `s` into this encoding and push the bytes to `out`.
+    ///
+    /// `undef` is called for a char which can not be represented in this encoding.
+    fn encode_str(
+        self,
+        s: &str,
+        out: &mut Vec<u8>,
+        undef: &mut EncodeUndefHandler,
+    ) -> Result<(), RubyError> {
+        match self {
+            Encoding::Utf8 => out.extend_from_slice(s.as_bytes()),
+            Encoding::UsAscii | Encoding::Ascii8Bit | Encoding::Iso8859_1 => {
+                let max = if self == Encoding::Iso8859_1 {
+                    0xff
+                } else {
+                    0x7f
+                };
+                for c in s.chars() {
+                    if c as u32 <= max {
+                        out.push(c as u8);
+                    } else {
+                        undef(c, out)?;
+                    }
+                }
+            }
+            Encoding::Utf16Le => s
+                .encode_utf16()
+                .for_each(|u| out.extend_from_slice(&u.to_le_bytes())),
+            Encoding::Utf16Be => s
+                .encode_utf16()
+                .for_each(|u| out.extend_from_slice(&u.to_be_bytes())),
+            Encoding::Utf32Le => s
+                .chars()
+                .for_each(|c| out.extend_from_slice(&(c as u32).to_le_bytes())),
+            Encoding::Utf32Be => s
+                .chars()
+                .for_each(|c| out.extend_from_slice(&(c as u32).to_be_bytes())),
+            Encoding::ShiftJis | Encoding::Windows31J | Encoding::EucJp | Encoding::Windows1252 => {
+                use encoding_rs::EncoderResult;
+                let mut encoder = self.table().unwrap().new_encoder();
+                let mut pos = 0;
+                loop {
+                    out.reserve(
+                        encoder
+                            .max_buffer_length_from_utf8_without_replacement(s.len() - pos)
+                            .unwrap_or_default(),
+                    );
+                    let (result, read) =
+                        encoder.encode_from_utf8_to_vec_without_replacement(&s[pos..], out, true);
+                    pos += read;
+                    match result {
+                        EncoderResult::InputEmpty => break,
+                        EncoderResult::OutputFull => {}
+                        EncoderResult::Unmappable(c) => undef(c, out)?,
+                    }
+                }
+            }
+            Encoding::Ibm437 => unreachable!(),
+        }
+        Ok(())
+    }
+
+    /// Replace invalid byte sequences in `bytes` by `replace`.
+    pub(crate) fn scrub(
+        self,
+        bytes: &[u8],
+        replace: &mut InvalidHandler,
+    ) -> Result<Vec<u8>, RubyError> {
+        let mut res = vec![];
+        for (range, valid) in self.segments(bytes) {
+            if valid {
+                res.extend_from_slice(&bytes[range]);
+            } else {
+                res.extend(replace(&bytes[range])?);
+            }
+        }
+        Ok(res)
+    }
+
+    /// The default replacement string for invalid or undefined chars.
+    pub(crate) fn replacement(self) -> &'static str {
+        if self.is_unicode() {
+            "\u{fffd}"
+        } else {
+            "?"
+        }
+    }
+
+    /// Encode the replacement string `s` into this encoding.
+    pub(crate) fn encode_replacement(self, s: &str) -> Result<Vec<u8>, RubyError> {
+        let mut res = vec![];
+        self.encode_str(s, &mut res, &mut |c, _| {
+            Err(undefined_conversion(
+                &format!("U+{:04X}", c as u32),
+                Encoding::Utf8,
+                self,
+            ))
+        })?;
+        Ok(res)
+    }
+}
+
+/// Options for String#encode.
+#[derive(Debug, Default)]
+pub(crate) struct TranscodeOptions {
+    /// Replace invalid byte sequences. (`invalid: :replace`)
+    pub invalid_replace: bool,
+    /// Replace undefined chars. (`undef: :replace`)
+    pub undef_replace: bool,
+    /// The replacement string. (`replace: str`)
+    pub replace: Option<String>,
+}
+
+impl TranscodeOptions {
+    pub(crate) fn from_hash(hash: Value) -> Result<Self, RubyError> {
+        let mut opt = Self::default();
+        let hash = match hash.as_hash() {
+            Some(hash) => hash,
+            None => return Ok(opt),
+        };
+        let is_replace = |key: &str| {
+            hash.get(&Value::symbol_from_str(key))
+                .is_some_and(|v| v.as_symbol() == Some(IdentId::get_id("replace")))
+        };
+        opt.invalid_replace = is_replace("invalid");
+        opt.undef_replace = is_replace("undef");
+        if let Some(mut replace) = hash.get(&Value::symbol_from_str("replace")).cloned() {
+            opt.replace = Some(replace.expect_string("replace")?.to_string());
+        }
+        Ok(opt)
+    }
+}
+
+fn inspect_bytes(bytes: &[u8]) -> String {
+    let s: String = bytes.iter().map(|b| format!("\\x{:02X}", b)).collect();
+    format!("\"{}\"", s)
+}
+
+fn undefined_conversion(what: &str, src: Encoding, dst: Encoding) -> RubyError {
+    RubyError::encoding(
+        "UndefinedConversionError",
+        format!("{} from {} to {}", what, src.name(), dst.name()),
+    )
+}
+
+/// Convert `bytes` in `src` encoding into `dst` encoding.
+pub(crate) fn transcode(
+    bytes: &[u8],
+    src: Encoding,
+    dst: Encoding,
+    opt: &TranscodeOptions,
+) -> Result<Vec<u8>, RubyError> {
+    let replace = opt.replace.as_deref().unwrap_or_else(|| dst.replacement());
+    if src == dst {
+        if opt.invalid_replace {
+            let replace = dst.encode_replacement(replace)?;
+            return src.scrub(bytes, &mut |_| Ok(replace.clone()));
+        }
+        return Ok(bytes.to_vec());
+    }
+    if !src.has_converter() || !dst.has_converter() {
+        return Err(RubyError::encoding(
+            "ConverterNotFoundError",
+            format!(
+                "code converter not found ({} to {})",
+                src.name(),
+                dst.name()
+            ),
+        ));
+    }
+    let mut chars = String::new();
+    for (range, valid) in src.segments(bytes) {
+        let seg = &bytes[range];
+        if !valid {
+            if !opt.invalid_replace {
+                return Err(RubyError::encoding(
+                    "InvalidByteSequenceError",
+                    format!("{} on {}", inspect_bytes(seg), src.name()),
+                ));
+            }
+            chars.push_str(replace);
+            continue;
+        }
+        src.decode_valid(seg, &mut chars, &mut |b, out| {
+            if opt.undef_replace {
+                out.push_str(replace);
+                Ok(())
+            } else {
+                Err(undefined_conversion(&inspect_bytes(b), src, dst))
+            }
+        })?;
+    }
+    let mut res = vec![];
+    dst.encode_str(&chars, &mut res, &mut |c, out| {
+        if opt.undef_replace {
+            out.extend(dst.encode_replacement(replace)?);
+            Ok(())
+        } else {
+            Err(undefined_conversion(
+                &format!("U+{:04X}", c as u32),
+                src,
+                dst,
+            ))
+        }
+    })?;
+    Ok(res)
+}
+
+// Instance methods
+
+fn self_encoding(self_val: Value) -> Encoding {
+    Encoding::from_value(self_val).unwrap()
+}
+
+/// Encoding#name -> String
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Encoding/i/name.html
+fn name(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::string(self_encoding(self_val).name()))
+}
+
+/// Encoding#names -> [String]
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Encoding/i/names.html
+fn names(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let names = self_encoding(self_val).entry().1;
+    let v = names.iter().map(|name| Value::string(*name)).collect();
+    Ok(Value::array_from(v))
+}
+
+/// Encoding#inspect -> String
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Encoding/i/inspect.html
+fn inspect(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let s = format!("#<Encoding:{}>", self_encoding(self_val).name());
+    Ok(Value::string(s))
+}
+
+/// Encoding#ascii_compatible? -> bool
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Encoding/i/ascii_compatible=3f.html
+fn ascii_compatible(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::bool(self_encoding(self_val).is_ascii_compatible()))
+}
+
+/// Encoding#dummy? -> bool
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Encoding/i/dummy=3f.html
+fn dummy(_: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::false_val())
+}
+
+// Class methods
+
+/// Encoding.find(name) -> Encoding
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Encoding/s/find.html
+fn find(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    Ok(Encoding::from_value(vm[0])?.to_value())
+}
+
+/// Encoding.list -> [Encoding]
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Encoding/s/list.html
+fn list(_: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let v = ENCODINGS.iter().map(|(enc, _, _)| enc.to_value()).collect();
+    Ok(Value::array_from(v))
+}
+
+/// Encoding.name_list -> [String]
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Encoding/s/name_list.html
+fn name_list(_: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let v = ENCODINGS
+        .iter()
+        .flat_map(|(_, names, _)| names.iter().map(|name| Value::string(*name)))
+        .collect();
+    Ok(Value::array_from(v))
+}
+
+/// Encoding.aliases -> Hash
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Encoding/s/aliases.html
+fn aliases(_: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let mut map = FxIndexMap::default();
+    for (_, names, _) in ENCODINGS {
+        for alias in &names[1..] {
+            map.insert(HashKey(Value::string(*alias)), Value::string(names[0]));
+        }
+    }
+    Ok(Value::hash_from_map(map))
+}
+
+/// Encoding.compatible?(obj1, obj2) -> Encoding | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Encoding/s/compatible=3f.html
+fn compatible(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_num(2)?;
+    let encoding_of = |val: Value| match val.as_rstring() {
+        Some(_) => Ok(Encoding::of(val)),
+        None => Encoding::from_value(val),
+    };
+    let (enc1, enc2) = (encoding_of(vm[0])?, encoding_of(vm[1])?);
+    if enc1 == enc2 {
+        return Ok(enc1.to_value());
+    }
+    let is_ascii = |val: Value| match val.as_rstring() {
+        Some(s) => s.as_bytes().is_ascii(),
+        None => false,
+    };
+    let res = if enc1.is_ascii_compatible() && enc2.is_ascii_compatible() {
+        if is_ascii(vm[1]) {
+            Some(enc1)
+        } else if is_ascii(vm[0]) {
+            Some(enc2)
+        } else {
+            None
+        }
+    } else {
+        None
+    };
+    Ok(res.map_or_else(Value::nil, Encoding::to_value))
+}
+
+/// Encoding.default_external -> Encoding
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Encoding/s/default_external.html
+fn default_external(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(self_val
+        .get_var(IdentId::get_id("/default_external"))
+        .unwrap_or_default())
+}
+
+/// Encoding.default_external = encoding
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Encoding/s/default_external=3d.html
+fn set_default_external(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let enc = Encoding::from_value(vm[0])?.to_value();
+    self_val.set_var_by_str("/default_external", enc);
+    Ok(enc)
+}
+
+/// Encoding.default_internal -> Encoding | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Encoding/s/default_internal.html
+fn default_internal(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(self_val
+        .get_var(IdentId::get_id("/default_internal"))
+        .unwrap_or_default())
+}
+
+/// Encoding.default_internal = encoding | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Encoding/s/default_internal=3d.html
+fn set_default_internal(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let enc = if vm[0].is_nil() {
+        Value::nil()
+    } else {
+        Encoding::from_value(vm[0])?.to_value()
+    };
+    self_val.set_var_by_str("/default_internal", enc);
+    Ok(enc)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::*;
+
+    #[test]
+    fn encoding() {
+        let program = r##"
+        assert "UTF-8", Encoding::UTF_8.name
+        assert "#<Encoding:UTF-8>", Encoding::UTF_8.inspect
+        assert Encoding::ASCII_8BIT, Encoding::BINARY
+        assert Encoding::Shift_JIS, Encoding.find("shift_jis")
+        assert Encoding::Windows_31J, Encoding.find("CP932")
+        assert Encoding::UTF_8, Encoding.find(Encoding::UTF_8)
+        assert_error { Encoding.find("foo") }
+        assert true, Encoding.list.include?(Encoding::UTF_16LE)
+        assert true, Encoding.name_list.include?("BINARY")
+        assert ["US-ASCII", "ASCII", "ANSI_X3.4-1968", "646"], Encoding::US_ASCII.names
+        assert false, Encoding::UTF_16BE.ascii_compatible?
+        assert Encoding::UTF_8, Encoding.default_external
+        assert nil, Encoding.default_internal
+        assert Encoding::UTF_8, Encoding.compatible?("a", "あ")
+        assert nil, Encoding.compatible?("あ", "い".encode("Shift_JIS"))
+        assert EncodingError, Encoding::UndefinedConversionError.superclass
+        "##;
+        assert_script(program);
+    }
+}
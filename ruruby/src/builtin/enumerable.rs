@@ -0,0 +1,1462 @@
+///
+/// Enumerable module
+///
+/// Every method is built on top of the receiver's `each`.
+/// `each` is called with a native block, so blocks are evaluated in step with `each`,
+/// and methods like `find` or `take_while` can stop the iteration early.
+///
+use crate::*;
+use fxhash::FxHashSet;
+use std::cmp::Ordering;
+
+pub(crate) fn init(globals: &mut Globals) -> Module {
+    let class = Module::module();
+    globals.set_toplevel_constant("Enumerable", class);
+    class.add_builtin_method_by_str(globals, "to_a", to_a);
+    class.add_builtin_method_by_str(globals, "entries", to_a);
+    class.add_builtin_method_by_str(globals, "map", map);
+    class.add_builtin_method_by_str(globals, "collect", map);
+    class.add_builtin_method_by_str(globals, "flat_map", flat_map);
+    class.add_builtin_method_by_str(globals, "collect_concat", flat_map);
+    class.add_builtin_method_by_str(globals, "select", select);
+    class.add_builtin_method_by_str(globals, "filter", select);
+    class.add_builtin_method_by_str(globals, "find_all", select);
+    class.add_builtin_method_by_str(globals, "filter_map", filter_map);
+    class.add_builtin_method_by_str(globals, "reject", reject);
+    class.add_builtin_method_by_str(globals, "find", find);
+    class.add_builtin_method_by_str(globals, "detect", find);
+    class.add_builtin_method_by_str(globals, "find_index", find_index);
+    class.add_builtin_method_by_str(globals, "first", first);
+    class.add_builtin_method_by_str(globals, "take", take);
+    class.add_builtin_method_by_str(globals, "take_while", take_while);
+    class.add_builtin_method_by_str(globals, "drop", drop);
+    class.add_builtin_method_by_str(globals, "drop_while", drop_while);
+    class.add_builtin_method_by_str(globals, "inject", inject);
+    class.add_builtin_method_by_str(globals, "reduce", inject);
+    class.add_builtin_method_by_str(globals, "sum", sum);
+    class.add_builtin_method_by_str(globals, "count", count);
+    class.add_builtin_method_by_str(globals, "include?", include);
+    class.add_builtin_method_by_str(globals, "member?", include);
+    class.add_builtin_method_by_str(globals, "all?", all);
+    class.add_builtin_method_by_str(globals, "any?", any);
+    class.add_builtin_method_by_str(globals, "none?", none);
+    class.add_builtin_method_by_str(globals, "one?", one);
+    class.add_builtin_method_by_str(globals, "grep", grep);
+    class.add_builtin_method_by_str(globals, "grep_v", grep_v);
+    class.add_builtin_method_by_str(globals, "each_entry", each_entry);
+    class.add_builtin_method_by_str(globals, "each_with_index", each_with_index);
+    class.add_builtin_method_by_str(globals, "each_with_object", each_with_object);
+    class.add_builtin_method_by_str(globals, "each_slice", each_slice);
+    class.add_builtin_method_by_str(globals, "each_cons", each_cons);
+    class.add_builtin_method_by_str(globals, "reverse_each", reverse_each);
+    class.add_builtin_method_by_str(globals, "group_by", group_by);
+    class.add_builtin_method_by_str(globals, "partition", partition);
+    class.add_builtin_method_by_str(globals, "chunk_while", chunk_while);
+    class.add_builtin_method_by_str(globals, "slice_when", slice_when);
+    class.add_builtin_method_by_str(globals, "tally", tally);
+    class.add_builtin_method_by_str(globals, "uniq", uniq);
+    class.add_builtin_method_by_str(globals, "zip", zip);
+    class.add_builtin_method_by_str(globals, "to_h", to_h);
+    class.add_builtin_method_by_str(globals, "min", min);
+    class.add_builtin_method_by_str(globals, "max", max);
+    class.add_builtin_method_by_str(globals, "minmax", minmax);
+    class.add_builtin_method_by_str(globals, "min_by", min_by);
+    class.add_builtin_method_by_str(globals, "max_by", max_by);
+    class.add_builtin_method_by_str(globals, "sort", sort);
+    class.add_builtin_method_by_str(globals, "sort_by", sort_by);
+    class
+}
+
+type EachFn<'a> = dyn FnMut(&mut VM, Value) -> Result<bool, RubyError> + 'a;
+
+/// A native block which Enumerable methods pass to `each` of the receiver.
+///
+/// The Proc of the block has `tag` as self, and `throw`s to `tag` to stop the iteration.
+#[derive(Debug)]
+pub(crate) struct EachBlock {
+    tag: Value,
+    /// None while `f` is running.
+    f: Option<*mut EachFn<'static>>,
+    /// Values which `f` pushed on the temporary area.
+    kept: Vec<Value>,
+}
+
+impl GC<RValue> for EachBlock {
+    fn mark(&self, alloc: &mut Allocator<RValue>) {
+        self.tag.mark(alloc);
+        self.kept.iter().for_each(|v| v.mark(alloc));
+    }
+}
+
+/// Iteration over the elements of the receiver.
+///
+/// Plain Arrays are walked directly.
+/// Any other receiver is iterated by calling `each` with a native block.
+enum Each {
+    Array(Value),
+    Block {
+        receiver: Value,
+        tag: Value,
+        proc: Value,
+    },
+}
+
+impl Each {
+    /// Prepare the iteration.
+    ///
+    /// The context of the caller is moved to the heap here,
+    /// so this must be called before the caller's block is set up with `eval_block_map1`.
+    fn new(vm: &mut VM, receiver: Value) -> Self {
+        if receiver.as_array().is_some()
+            && vm.globals.get_class(receiver).id() == BuiltinClass::array().id()
+        {
+            return Each::Array(receiver);
+        }
+        let tag = Value::ordinary_object(BuiltinClass::object());
+        let proc = vm.create_builtin_proc(tag, METHOD_ENUMERABLE);
+        vm.temp_push(proc);
+        Each::Block {
+            receiver,
+            tag,
+            proc,
+        }
+    }
+
+    /// Call `f` with each element until `f` returns false.
+    ///
+    /// When `f` stops the iteration, `each` is left by `throw`, so that `ensure` clauses are executed.
+    /// Values which `f` pushed on the temporary area are kept there after the iteration.
+    fn run(
+        self,
+        vm: &mut VM,
+        mut f: impl FnMut(&mut VM, Value) -> Result<bool, RubyError>,
+    ) -> Result<(), RubyError> {
+        let (receiver, tag, proc) = match self {
+            Each::Array(ary) => {
+                let mut i = 0;
+                while i < ary.into_array().len() {
+                    if !f(vm, ary.into_array()[i])? {
+                        break;
+                    }
+                    i += 1;
+                }
+                return Ok(());
+            }
+            Each::Block {
+                receiver,
+                tag,
+                proc,
+            } => (receiver, tag, proc),
+        };
+        let f: *mut EachFn = &mut f;
+        // The block is removed from vm.each_blocks before `f` goes out of scope.
+        let f = unsafe { std::mem::transmute::<*mut EachFn, *mut EachFn<'static>>(f) };
+        vm.each_blocks.push(EachBlock {
+            tag,
+            f: Some(f),
+            kept: vec![],
+        });
+        let mut args = Args::new0();
+        args.block = Some(proc.into());
+        let res = vm.eval_send(IdentId::EACH, receiver, &args);
+        let block = vm.each_blocks.pop().unwrap();
+        debug_assert_eq!(tag.id(), block.tag.id());
+        vm.temp_extend_from_slice(&block.kept);
+        match res {
+            Ok(_) => Ok(()),
+            Err(err) => match kernel::thrown_value(vm, &err, tag) {
+                Some(_) => Ok(()),
+                None => Err(err),
+            },
+        }
+    }
+}
+
+/// The body of the native block of Enumerable methods.
+///
+/// Yielded values are passed to `f` of the EachBlock which has `self_val` as its tag.
+pub(crate) fn each_block(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    let val = match args.len() {
+        0 => Value::nil(),
+        1 => vm[0],
+        _ => Value::array_from(vm.args().to_vec()),
+    };
+    let i = match vm
+        .each_blocks
+        .iter()
+        .rposition(|b| b.tag.id() == self_val.id())
+    {
+        Some(i) => i,
+        None => return Err(RubyError::local_jump("The iteration has already finished.")),
+    };
+    let f = match vm.each_blocks[i].f.take() {
+        Some(f) => f,
+        None => return Err(RubyError::local_jump("The iteration is already running.")),
+    };
+    let len = vm.temp_len();
+    let res = unsafe { (*f)(vm, val) };
+    // The temporary area is truncated on return from the block, so pushed values are moved.
+    let kept = vm.temp_pop_vec(len);
+    let block = &mut vm.each_blocks[i];
+    block.f = Some(f);
+    block.kept.extend(kept);
+    if res? {
+        Ok(Value::nil())
+    } else {
+        Err(kernel::throw_to(vm, self_val, Value::nil()))
+    }
+}
+
+/// Collect all elements of the receiver.
+///
+/// The elements are also kept on the temporary area until the method returns.
+fn collect(vm: &mut VM, receiver: Value) -> Result<Vec<Value>, RubyError> {
+    let each = Each::new(vm, receiver);
+    let start = vm.temp_len();
+    each.run(vm, |vm, v| {
+        vm.temp_push(v);
+        Ok(true)
+    })?;
+    let res = vm.temp_pop_vec(start);
+    vm.temp_extend_from_slice(&res);
+    Ok(res)
+}
+
+macro_rules! to_enum_str {
+    ($vm:ident, $self_val:ident, $args:ident, $id:expr) => {
+        match &$args.block {
+            None => {
+                let val =
+                    $vm.create_enumerator(IdentId::get_id($id), $self_val, $args.into($vm))?;
+                return Ok(val);
+            }
+            Some(block) => block,
+        }
+    };
+}
+
+fn compare(
+    vm: &mut VM,
+    block: &Option<Block>,
+    lhs: Value,
+    rhs: Value,
+) -> Result<Ordering, RubyError> {
+    let res = match block {
+        None => vm.eval_compare(rhs, lhs)?,
+        Some(block) => vm.eval_block2(block, lhs, rhs)?,
+    };
+    if res.is_nil() {
+        return Err(RubyError::argument(format!(
+            "Comparison of {} with {} failed.",
+            lhs.get_class_name(),
+            rhs.get_class_name()
+        )));
+    }
+    res.to_ordering()
+}
+
+fn sort_values(vm: &mut VM, ary: &mut Vec<Value>, block: &Option<Block>) -> Result<(), RubyError> {
+    match block {
+        None => vm.sort_array(ary),
+        Some(_) => vm.sort_by(ary, |vm, a, b| compare(vm, block, *a, *b)),
+    }
+}
+
+/// Get the pattern argument of predicate methods like `all?`.
+fn pattern_arg(vm: &VM, args: &Args2) -> Option<Value> {
+    if args.len() == 1 {
+        Some(vm[0])
+    } else {
+        None
+    }
+}
+
+/// Evaluate `pattern === val` if `pattern` is given, the block if given, or the truthiness of `val`.
+fn test_elem(
+    vm: &mut VM,
+    args: &Args2,
+    pattern: Option<Value>,
+    val: Value,
+) -> Result<bool, RubyError> {
+    match pattern {
+        Some(pattern) => vm.eval_teq(val, pattern),
+        None => match &args.block {
+            None => Ok(val.to_bool()),
+            Some(block) => Ok(vm.eval_block1(block, val)?.to_bool()),
+        },
+    }
+}
+
+// Instance methods
+
+/// ## instance method Enumerable#to_a
+/// - to_a -> [object]
+/// - entries -> [object]
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/entries.html
+fn to_a(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let v = collect(vm, self_val)?;
+    Ok(Value::array_from(v))
+}
+
+/// ## instance method Enumerable#map
+/// - map {|item| ... } -> [object]
+/// - collect {|item| ... } -> [object]
+/// - map -> Enumerator
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/collect.html
+fn map(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let block = to_enum_str!(vm, self_val, args, "map");
+    let each = Each::new(vm, self_val);
+    let f = vm.eval_block_map1(block);
+    let start = vm.temp_len();
+    each.run(vm, |vm, v| {
+        let res = f(vm, v)?;
+        vm.temp_push(res);
+        Ok(true)
+    })?;
+    Ok(Value::array_from(vm.temp_pop_vec(start)))
+}
+
+/// ## instance method Enumerable#flat_map
+/// - flat_map {|item| ... } -> [object]
+/// - collect_concat {|item| ... } -> [object]
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/collect_concat.html
+fn flat_map(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let block = to_enum_str!(vm, self_val, args, "flat_map");
+    let each = Each::new(vm, self_val);
+    let f = vm.eval_block_map1(block);
+    let start = vm.temp_len();
+    each.run(vm, |vm, v| {
+        let res = f(vm, v)?;
+        match res.as_array() {
+            Some(aref) => vm.temp_extend_from_slice(&aref),
+            None => vm.temp_push(res),
+        }
+        Ok(true)
+    })?;
+    Ok(Value::array_from(vm.temp_pop_vec(start)))
+}
+
+/// ## instance method Enumerable#select
+/// - select {|item| ... } -> [object]
+/// - filter {|item| ... } -> [object]
+/// - find_all {|item| ... } -> [object]
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/filter.html
+fn select(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let block = to_enum_str!(vm, self_val, args, "select");
+    filter_by(vm, self_val, block, true)
+}
+
+/// ## instance method Enumerable#reject
+/// - reject {|item| ... } -> [object]
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/reject.html
+fn reject(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let block = to_enum_str!(vm, self_val, args, "reject");
+    filter_by(vm, self_val, block, false)
+}
+
+fn filter_by(vm: &mut VM, self_val: Value, block: &Block, cond: bool) -> VMResult {
+    let each = Each::new(vm, self_val);
+    let f = vm.eval_block_map1(block);
+    let start = vm.temp_len();
+    each.run(vm, |vm, v| {
+        if f(vm, v)?.to_bool() == cond {
+            vm.temp_push(v);
+        }
+        Ok(true)
+    })?;
+    Ok(Value::array_from(vm.temp_pop_vec(start)))
+}
+
+/// ## instance method Enumerable#filter_map
+/// - filter_map {|item| ... } -> [object]
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/filter_map.html
+fn filter_map(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let block = to_enum_str!(vm, self_val, args, "filter_map");
+    let each = Each::new(vm, self_val);
+    let f = vm.eval_block_map1(block);
+    let start = vm.temp_len();
+    each.run(vm, |vm, v| {
+        let res = f(vm, v)?;
+        if res.to_bool() {
+            vm.temp_push(res);
+        }
+        Ok(true)
+    })?;
+    Ok(Value::array_from(vm.temp_pop_vec(start)))
+}
+
+/// ## instance method Enumerable#find
+/// - find {|item| ... } -> object | nil
+/// - detect {|item| ... } -> object | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/detect.html
+fn find(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let block = to_enum_str!(vm, self_val, args, "find");
+    let each = Each::new(vm, self_val);
+    let f = vm.eval_block_map1(block);
+    let mut res = Value::nil();
+    each.run(vm, |vm, v| {
+        if f(vm, v)?.to_bool() {
+            vm.temp_push(v);
+            res = v;
+            return Ok(false);
+        }
+        Ok(true)
+    })?;
+    Ok(res)
+}
+
+/// ## instance method Enumerable#find_index
+/// - find_index(val) -> Integer | nil
+/// - find_index {|obj| ... } -> Integer | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/find_index.html
+fn find_index(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
+    if args.len() == 0 && args.block.is_none() {
+        return vm.create_enumerator(IdentId::get_id("find_index"), self_val, args.into(vm));
+    }
+    let val = pattern_arg(vm, args);
+    let mut i = 0;
+    let mut res = Value::nil();
+    Each::new(vm, self_val).run(vm, |vm, v| {
+        let found = match val {
+            Some(val) => vm.eval_eq2(v, val)?,
+            None => vm.eval_block1(args.block.as_ref().unwrap(), v)?.to_bool(),
+        };
+        if found {
+            res = Value::integer(i);
+            return Ok(false);
+        }
+        i += 1;
+        Ok(true)
+    })?;
+    Ok(res)
+}
+
+fn take_n(vm: &mut VM, self_val: Value, n: Value) -> VMResult {
+    let n = n.coerce_to_fixnum("1st arg")?;
+    if n < 0 {
+        return Err(RubyError::argument("Attempt to take negative size."));
+    }
+    if n == 0 {
+        return Ok(Value::array_empty());
+    }
+    let each = Each::new(vm, self_val);
+    let start = vm.temp_len();
+    let mut i = 0;
+    each.run(vm, |vm, v| {
+        vm.temp_push(v);
+        i += 1;
+        Ok(i < n)
+    })?;
+    Ok(Value::array_from(vm.temp_pop_vec(start)))
+}
+
+/// ## instance method Enumerable#first
+/// - first -> object | nil
+/// - first(n) -> Array
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/first.html
+fn first(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
+    if args.len() == 1 {
+        return take_n(vm, self_val, vm[0]);
+    }
+    let mut res = Value::nil();
+    Each::new(vm, self_val).run(vm, |vm, v| {
+        vm.temp_push(v);
+        res = v;
+        Ok(false)
+    })?;
+    Ok(res)
+}
+
+/// ## instance method Enumerable#take
+/// - take(n) -> Array
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/take.html
+fn take(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    take_n(vm, self_val, vm[0])
+}
+
+/// ## instance method Enumerable#take_while
+/// - take_while {|element| ... } -> Array
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/take_while.html
+fn take_while(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let block = to_enum_str!(vm, self_val, args, "take_while");
+    let each = Each::new(vm, self_val);
+    let f = vm.eval_block_map1(block);
+    let start = vm.temp_len();
+    each.run(vm, |vm, v| {
+        if !f(vm, v)?.to_bool() {
+            return Ok(false);
+        }
+        vm.temp_push(v);
+        Ok(true)
+    })?;
+    Ok(Value::array_from(vm.temp_pop_vec(start)))
+}
+
+/// ## instance method Enumerable#drop
+/// - drop(n) -> Array
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/drop.html
+fn drop(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let n = vm[0].coerce_to_fixnum("1st arg")?;
+    if n < 0 {
+        return Err(RubyError::argument("Attempt to drop negative size."));
+    }
+    let v = collect(vm, self_val)?;
+    let n = std::cmp::min(n as usize, v.len());
+    Ok(Value::array_from(v[n..].to_vec()))
+}
+
+/// ## instance method Enumerable#drop_while
+/// - drop_while {|element| ... } -> Array
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/drop_while.html
+fn drop_while(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let block = to_enum_str!(vm, self_val, args, "drop_while");
+    let each = Each::new(vm, self_val);
+    let f = vm.eval_block_map1(block);
+    let start = vm.temp_len();
+    let mut dropping = true;
+    each.run(vm, |vm, v| {
+        if dropping && f(vm, v)?.to_bool() {
+            return Ok(true);
+        }
+        dropping = false;
+        vm.temp_push(v);
+        Ok(true)
+    })?;
+    Ok(Value::array_from(vm.temp_pop_vec(start)))
+}
+
+/// ## instance method Enumerable#inject
+/// - inject(init, sym) -> object
+/// - inject(sym) -> object
+/// - inject(init) {|result, item| ... } -> object
+/// - inject {|result, item| ... } -> object
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/inject.html
+fn inject(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 2)?;
+    let (init, sym) = match (args.len(), &args.block) {
+        (2, _) => (Some(vm[0]), Some(vm[1].expect_symbol_or_string("2nd arg")?)),
+        (1, None) => (None, Some(vm[0].expect_symbol_or_string("1st arg")?)),
+        (1, Some(_)) => (Some(vm[0]), None),
+        (0, Some(_)) => (None, None),
+        _ => return Err(RubyError::argument("Block or symbol must be given.")),
+    };
+    let mut res = init;
+    Each::new(vm, self_val).run(vm, |vm, v| {
+        let acc = match res {
+            None => v,
+            Some(acc) => match sym {
+                Some(sym) => vm.eval_send1(sym, acc, v)?,
+                None => vm.eval_block2(args.block.as_ref().unwrap(), acc, v)?,
+            },
+        };
+        vm.temp_push(acc);
+        res = Some(acc);
+        Ok(true)
+    })?;
+    Ok(res.unwrap_or_default())
+}
+
+/// ## instance method Enumerable#sum
+/// - sum(init = 0) -> object
+/// - sum(init = 0) {|obj| ... } -> object
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/sum.html
+fn sum(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
+    let mut res = if args.len() == 1 {
+        vm[0]
+    } else {
+        Value::integer(0)
+    };
+    Each::new(vm, self_val).run(vm, |vm, v| {
+        let v = match &args.block {
+            None => v,
+            Some(block) => vm.eval_block1(block, v)?,
+        };
+        res = vm.eval_send1(IdentId::_ADD, res, v)?;
+        vm.temp_push(res);
+        Ok(true)
+    })?;
+    Ok(res)
+}
+
+/// ## instance method Enumerable#count
+/// - count -> Integer
+/// - count(item) -> Integer
+/// - count {|obj| ... } -> Integer
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/count.html
+fn count(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
+    let val = pattern_arg(vm, args);
+    let mut count = 0;
+    Each::new(vm, self_val).run(vm, |vm, v| {
+        let b = match val {
+            Some(val) => vm.eval_eq2(v, val)?,
+            None => match &args.block {
+                None => true,
+                Some(block) => vm.eval_block1(block, v)?.to_bool(),
+            },
+        };
+        if b {
+            count += 1;
+        }
+        Ok(true)
+    })?;
+    Ok(Value::integer(count))
+}
+
+/// ## instance method Enumerable#include?
+/// - include?(val) -> bool
+/// - member?(val) -> bool
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/include=3f.html
+fn include(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let val = vm[0];
+    let mut found = false;
+    Each::new(vm, self_val).run(vm, |vm, v| {
+        found = vm.eval_eq2(v, val)?;
+        Ok(!found)
+    })?;
+    Ok(Value::bool(found))
+}
+
+/// ## instance method Enumerable#all?
+/// - all? -> bool
+/// - all?(pattern) -> bool
+/// - all? {|item| ... } -> bool
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/all=3f.html
+fn all(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
+    let pattern = pattern_arg(vm, args);
+    let mut res = true;
+    Each::new(vm, self_val).run(vm, |vm, v| {
+        res = test_elem(vm, args, pattern, v)?;
+        Ok(res)
+    })?;
+    Ok(Value::bool(res))
+}
+
+/// ## instance method Enumerable#any?
+/// - any? -> bool
+/// - any?(pattern) -> bool
+/// - any? {|item| ... } -> bool
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/any=3f.html
+fn any(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
+    let pattern = pattern_arg(vm, args);
+    let mut res = false;
+    Each::new(vm, self_val).run(vm, |vm, v| {
+        res = test_elem(vm, args, pattern, v)?;
+        Ok(!res)
+    })?;
+    Ok(Value::bool(res))
+}
+
+/// ## instance method Enumerable#none?
+/// - none? -> bool
+/// - none?(pattern) -> bool
+/// - none? {|item| ... } -> bool
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/none=3f.html
+fn none(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
+    let pattern = pattern_arg(vm, args);
+    let mut res = true;
+    Each::new(vm, self_val).run(vm, |vm, v| {
+        res = !test_elem(vm, args, pattern, v)?;
+        Ok(res)
+    })?;
+    Ok(Value::bool(res))
+}
+
+/// ## instance method Enumerable#one?
+/// - one? -> bool
+/// - one?(pattern) -> bool
+/// - one? {|item| ... } -> bool
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/one=3f.html
+fn one(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
+    let pattern = pattern_arg(vm, args);
+    let mut count = 0;
+    Each::new(vm, self_val).run(vm, |vm, v| {
+        if test_elem(vm, args, pattern, v)? {
+            count += 1;
+        }
+        Ok(count < 2)
+    })?;
+    Ok(Value::bool(count == 1))
+}
+
+fn grep_by(vm: &mut VM, self_val: Value, args: &Args2, cond: bool) -> VMResult {
+    args.check_args_num(1)?;
+    let pattern = vm[0];
+    let each = Each::new(vm, self_val);
+    let start = vm.temp_len();
+    each.run(vm, |vm, v| {
+        if vm.eval_teq(v, pattern)? == cond {
+            let v = match &args.block {
+                None => v,
+                Some(block) => vm.eval_block1(block, v)?,
+            };
+            vm.temp_push(v);
+        }
+        Ok(true)
+    })?;
+    Ok(Value::array_from(vm.temp_pop_vec(start)))
+}
+
+/// ## instance method Enumerable#grep
+/// - grep(pattern) -> [object]
+/// - grep(pattern) {|item| ... } -> [object]
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/grep.html
+fn grep(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    grep_by(vm, self_val, args, true)
+}
+
+/// ## instance method Enumerable#grep_v
+/// - grep_v(pattern) -> [object]
+/// - grep_v(pattern) {|item| ... } -> [object]
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/grep_v.html
+fn grep_v(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    grep_by(vm, self_val, args, false)
+}
+
+/// ## instance method Enumerable#each_entry
+/// - each_entry {|obj| ... } -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/each_entry.html
+fn each_entry(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let block = to_enum_str!(vm, self_val, args, "each_entry");
+    let each = Each::new(vm, self_val);
+    let f = vm.eval_block_map1(block);
+    each.run(vm, |vm, v| {
+        f(vm, v)?;
+        Ok(true)
+    })?;
+    Ok(self_val)
+}
+
+/// ## instance method Enumerable#each_with_index
+/// - each_with_index {|item, index| ... } -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/each_with_index.html
+fn each_with_index(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let block = to_enum_str!(vm, self_val, args, "each_with_index");
+    let mut i = 0;
+    Each::new(vm, self_val).run(vm, |vm, v| {
+        vm.eval_block2(block, v, Value::integer(i))?;
+        i += 1;
+        Ok(true)
+    })?;
+    Ok(self_val)
+}
+
+/// ## instance method Enumerable#each_with_object
+/// - each_with_object(obj) {|item, memo| ... } -> object
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/each_with_object.html
+fn each_with_object(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let block = to_enum_str!(vm, self_val, args, "each_with_object");
+    let memo = vm[0];
+    Each::new(vm, self_val).run(vm, |vm, v| {
+        vm.eval_block2(block, v, memo)?;
+        Ok(true)
+    })?;
+    Ok(memo)
+}
+
+fn expect_positive_size(val: Value) -> Result<usize, RubyError> {
+    let n = val.coerce_to_fixnum("1st arg")?;
+    if n <= 0 {
+        return Err(RubyError::argument("Invalid size."));
+    }
+    Ok(n as usize)
+}
+
+/// ## instance method Enumerable#each_slice
+/// - each_slice(n) {|list| ... } -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/each_slice.html
+fn each_slice(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let n = expect_positive_size(vm[0])?;
+    let block = to_enum_str!(vm, self_val, args, "each_slice");
+    let mut slice = vec![];
+    Each::new(vm, self_val).run(vm, |vm, v| {
+        vm.temp_push(v);
+        slice.push(v);
+        if slice.len() == n {
+            let slice = Value::array_from(std::mem::take(&mut slice));
+            vm.eval_block1(block, slice)?;
+        }
+        Ok(true)
+    })?;
+    if !slice.is_empty() {
+        vm.eval_block1(block, Value::array_from(slice))?;
+    }
+    Ok(self_val)
+}
+
+/// ## instance method Enumerable#each_cons
+/// - each_cons(n) {|list| ... } -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/each_cons.html
+fn each_cons(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let n = expect_positive_size(vm[0])?;
+    let block = to_enum_str!(vm, self_val, args, "each_cons");
+    let mut window = vec![];
+    Each::new(vm, self_val).run(vm, |vm, v| {
+        vm.temp_push(v);
+        window.push(v);
+        if window.len() > n {
+            window.remove(0);
+        }
+        if window.len() == n {
+            let cons = Value::array_from(window.clone());
+            vm.eval_block1(block, cons)?;
+        }
+        Ok(true)
+    })?;
+    Ok(self_val)
+}
+
+/// ## instance method Enumerable#reverse_each
+/// - reverse_each {|element| ... } -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/reverse_each.html
+fn reverse_each(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let block = to_enum_str!(vm, self_val, args, "reverse_each");
+    let v = collect(vm, self_val)?;
+    let f = vm.eval_block_map1(block);
+    for elem in v.into_iter().rev() {
+        f(vm, elem)?;
+    }
+    Ok(self_val)
+}
+
+/// ## instance method Enumerable#group_by
+/// - group_by {|obj| ... } -> Hash
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/group_by.html
+fn group_by(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let block = to_enum_str!(vm, self_val, args, "group_by");
+    let hash = Value::hash_from_map(FxIndexMap::default());
+    vm.temp_push(hash);
+    let each = Each::new(vm, self_val);
+    let f = vm.eval_block_map1(block);
+    each.run(vm, |vm, v| {
+        let key = f(vm, v)?;
+        let mut h = hash;
+        let map = h.as_mut_hash().unwrap();
+        match map.get(&key) {
+            Some(group) => group.into_array().push(v),
+            None => map.insert(key, Value::array_from(vec![v])),
+        };
+        Ok(true)
+    })?;
+    Ok(hash)
+}
+
+/// ## instance method Enumerable#partition
+/// - partition {|item| ... } -> [[object], [object]]
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/partition.html
+fn partition(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let block = to_enum_str!(vm, self_val, args, "partition");
+    let res_true = Value::array_empty();
+    let res_false = Value::array_empty();
+    vm.temp_push(res_true);
+    vm.temp_push(res_false);
+    let each = Each::new(vm, self_val);
+    let f = vm.eval_block_map1(block);
+    each.run(vm, |vm, v| {
+        if f(vm, v)?.to_bool() {
+            res_true.into_array().push(v);
+        } else {
+            res_false.into_array().push(v);
+        }
+        Ok(true)
+    })?;
+    Ok(Value::array_from(vec![res_true, res_false]))
+}
+
+/// Split the elements between `a` and `b` where the block returns `split`.
+fn chunk_by(vm: &mut VM, self_val: Value, block: &Block, split: bool) -> VMResult {
+    let res = Value::array_empty();
+    vm.temp_push(res);
+    let mut chunk: Option<Value> = None;
+    Each::new(vm, self_val).run(vm, |vm, v| {
+        match chunk {
+            Some(mut c) => {
+                let prev = *c.into_array().last().unwrap();
+                if vm.eval_block2(block, prev, v)?.to_bool() == split {
+                    c = Value::array_from(vec![v]);
+                    res.into_array().push(c);
+                    chunk = Some(c);
+                } else {
+                    c.into_array().push(v);
+                }
+            }
+            None => {
+                let c = Value::array_from(vec![v]);
+                res.into_array().push(c);
+                chunk = Some(c);
+            }
+        }
+        Ok(true)
+    })?;
+    vm.create_enumerator(IdentId::EACH, res, Args::new0())
+}
+
+/// ## instance method Enumerable#chunk_while
+/// - chunk_while {|elt_before, elt_after| ... } -> Enumerator
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/chunk_while.html
+fn chunk_while(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let block = args.expect_block()?;
+    chunk_by(vm, self_val, block, false)
+}
+
+/// ## instance method Enumerable#slice_when
+/// - slice_when {|elt_before, elt_after| ... } -> Enumerator
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/slice_when.html
+fn slice_when(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let block = args.expect_block()?;
+    chunk_by(vm, self_val, block, true)
+}
+
+/// ## instance method Enumerable#tally
+/// - tally -> Hash
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/tally.html
+fn tally(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let hash = Value::hash_from_map(FxIndexMap::default());
+    vm.temp_push(hash);
+    Each::new(vm, self_val).run(vm, |_, v| {
+        let mut h = hash;
+        let map = h.as_mut_hash().unwrap();
+        let count = match map.get(&v) {
+            Some(c) => c.as_fixnum().unwrap() + 1,
+            None => 1,
+        };
+        map.insert(v, Value::integer(count));
+        Ok(true)
+    })?;
+    Ok(hash)
+}
+
+/// ## instance method Enumerable#uniq
+/// - uniq -> Array
+/// - uniq {|item| ... } -> Array
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/uniq.html
+fn uniq(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let each = Each::new(vm, self_val);
+    let mut set = FxHashSet::default();
+    let start = vm.temp_len();
+    each.run(vm, |vm, v| {
+        let key = match &args.block {
+            None => v,
+            Some(block) => vm.eval_block1(block, v)?,
+        };
+        if set.insert(HashKey(key)) {
+            vm.temp_push(key);
+            vm.temp_push(v);
+        }
+        Ok(true)
+    })?;
+    let res = vm
+        .temp_pop_vec(start)
+        .chunks(2)
+        .map(|pair| pair[1])
+        .collect();
+    Ok(Value::array_from(res))
+}
+
+/// ## instance method Enumerable#zip
+/// - zip(*lists) -> [[object]]
+/// - zip(*lists) {|v1, v2, ...| ... } -> nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/zip.html
+fn zip(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    let mut lists = vec![];
+    for arg in vm.args().to_vec() {
+        let ary = match arg.as_array() {
+            Some(_) => arg,
+            None => vm.eval_send0(IdentId::get_id("to_a"), arg)?,
+        };
+        vm.temp_push(ary);
+        lists.push(ary.expect_array("Args")?.to_vec());
+    }
+    let each = Each::new(vm, self_val);
+    let start = vm.temp_len();
+    let mut i = 0;
+    each.run(vm, |vm, v| {
+        let mut tuple = vec![v];
+        for list in &lists {
+            tuple.push(list.get(i).cloned().unwrap_or_default());
+        }
+        let tuple = Value::array_from(tuple);
+        match &args.block {
+            None => vm.temp_push(tuple),
+            Some(block) => {
+                vm.eval_block1(block, tuple)?;
+            }
+        }
+        i += 1;
+        Ok(true)
+    })?;
+    let res = vm.temp_pop_vec(start);
+    match &args.block {
+        None => Ok(Value::array_from(res)),
+        Some(_) => Ok(Value::nil()),
+    }
+}
+
+/// ## instance method Enumerable#to_h
+/// - to_h -> Hash
+/// - to_h {|elem| ... } -> Hash
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/to_h.html
+fn to_h(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let hash = Value::hash_from_map(FxIndexMap::default());
+    vm.temp_push(hash);
+    Each::new(vm, self_val).run(vm, |vm, v| {
+        let pair = match &args.block {
+            None => v,
+            Some(block) => vm.eval_block1(block, v)?,
+        };
+        let (key, val) = match pair.as_array() {
+            Some(aref) if aref.len() == 2 => (aref[0], aref[1]),
+            Some(aref) => {
+                return Err(RubyError::argument(format!(
+                    "Wrong array length (expected 2, was {}).",
+                    aref.len()
+                )))
+            }
+            None => {
+                return Err(RubyError::typeerr(format!(
+                    "Wrong element type {} (expected array).",
+                    pair.get_class_name()
+                )))
+            }
+        };
+        let mut h = hash;
+        h.as_mut_hash().unwrap().insert(key, val);
+        Ok(true)
+    })?;
+    Ok(hash)
+}
+
+fn min_max(vm: &mut VM, self_val: Value, args: &Args2, ord: Ordering) -> VMResult {
+    args.check_args_range(0, 1)?;
+    if args.len() == 1 {
+        let n = vm[0].coerce_to_fixnum("1st arg")?;
+        if n < 0 {
+            return Err(RubyError::argument(format!("Negative size ({}).", n)));
+        }
+        let mut v = collect(vm, self_val)?;
+        sort_values(vm, &mut v, &args.block)?;
+        if ord == Ordering::Greater {
+            v.reverse();
+        }
+        v.truncate(n as usize);
+        return Ok(Value::array_from(v));
+    }
+    let mut res: Option<Value> = None;
+    Each::new(vm, self_val).run(vm, |vm, v| {
+        if let Some(res) = res {
+            if compare(vm, &args.block, v, res)? != ord {
+                return Ok(true);
+            }
+        }
+        vm.temp_push(v);
+        res = Some(v);
+        Ok(true)
+    })?;
+    Ok(res.unwrap_or_default())
+}
+
+/// ## instance method Enumerable#min
+/// - min -> object | nil
+/// - min {|a, b| ... } -> object | nil
+/// - min(n) -> Array
+/// - min(n) {|a, b| ... } -> Array
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/min.html
+fn min(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    min_max(vm, self_val, args, Ordering::Less)
+}
+
+/// ## instance method Enumerable#max
+/// - max -> object | nil
+/// - max {|a, b| ... } -> object | nil
+/// - max(n) -> Array
+/// - max(n) {|a, b| ... } -> Array
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/max.html
+fn max(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    min_max(vm, self_val, args, Ordering::Greater)
+}
+
+/// ## instance method Enumerable#minmax
+/// - minmax -> [object, object]
+/// - minmax {|a, b| ... } -> [object, object]
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/minmax.html
+fn minmax(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let mut res: Option<(Value, Value)> = None;
+    Each::new(vm, self_val).run(vm, |vm, v| {
+        let (mut min, mut max) = res.unwrap_or((v, v));
+        if compare(vm, &args.block, v, min)? == Ordering::Less {
+            min = v;
+        }
+        if compare(vm, &args.block, v, max)? == Ordering::Greater {
+            max = v;
+        }
+        vm.temp_push(v);
+        res = Some((min, max));
+        Ok(true)
+    })?;
+    let (min, max) = res.unwrap_or_default();
+    Ok(Value::array_from(vec![min, max]))
+}
+
+fn min_max_by(vm: &mut VM, self_val: Value, args: &Args2, ord: Ordering, id: &str) -> VMResult {
+    args.check_args_num(0)?;
+    let block = to_enum_str!(vm, self_val, args, id);
+    let each = Each::new(vm, self_val);
+    let f = vm.eval_block_map1(block);
+    let mut res: Option<(Value, Value)> = None;
+    each.run(vm, |vm, v| {
+        let key = f(vm, v)?;
+        if let Some((_, res_key)) = res {
+            if compare(vm, &None, key, res_key)? != ord {
+                return Ok(true);
+            }
+        }
+        vm.temp_push(v);
+        vm.temp_push(key);
+        res = Some((v, key));
+        Ok(true)
+    })?;
+    Ok(res.map(|(v, _)| v).unwrap_or_default())
+}
+
+/// ## instance method Enumerable#min_by
+/// - min_by {|item| ... } -> object | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/min_by.html
+fn min_by(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    min_max_by(vm, self_val, args, Ordering::Less, "min_by")
+}
+
+/// ## instance method Enumerable#max_by
+/// - max_by {|item| ... } -> object | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/max_by.html
+fn max_by(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    min_max_by(vm, self_val, args, Ordering::Greater, "max_by")
+}
+
+/// ## instance method Enumerable#sort
+/// - sort -> [object]
+/// - sort {|a, b| ... } -> [object]
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/sort.html
+fn sort(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let mut v = collect(vm, self_val)?;
+    sort_values(vm, &mut v, &args.block)?;
+    Ok(Value::array_from(v))
+}
+
+/// ## instance method Enumerable#sort_by
+/// - sort_by {|item| ... } -> [object]
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/sort_by.html
+fn sort_by(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let block = to_enum_str!(vm, self_val, args, "sort_by");
+    let each = Each::new(vm, self_val);
+    let mut ary = vec![];
+    {
+        let f = vm.eval_block_map1(block);
+        each.run(vm, |vm, v| {
+            let key = f(vm, v)?;
+            vm.temp_push(v);
+            vm.temp_push(key);
+            ary.push((v, key));
+            Ok(true)
+        })?;
+    }
+    vm.sort_by(&mut ary, |vm, a, b| compare(vm, &None, a.1, b.1))?;
+    Ok(Value::array_from(ary.iter().map(|x| x.0).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::*;
+
+    #[test]
+    fn enumerable_custom_class() {
+        let program = r#"
+        class Foo
+          include Enumerable
+          def initialize(*a)
+            @a = a
+          end
+          def each
+            @a.each { |x| yield x }
+            self
+          end
+        end
+        f = Foo.new(3, 1, 4, 1, 5, 9, 2, 6)
+        assert [3, 1, 4, 1, 5, 9, 2, 6], f.to_a
+        assert [3, 1, 4, 1, 5, 9, 2, 6], f.entries
+        assert [6, 2, 8, 2, 10, 18, 4, 12], f.map { |x| x * 2 }
+        assert [4, 2, 6], f.select(&:even?)
+        assert [3, 1, 1, 5, 9], f.reject(&:even?)
+        assert [8, 4, 12], f.filter_map { |x| x * 2 if x.even? }
+        assert [3, 3, 1, 1], Foo.new(3, 1).flat_map { |x| [x, x] }
+        assert 4, f.find { |x| x > 3 }
+        assert nil, f.find { |x| x > 10 }
+        assert 2, f.find_index(4)
+        assert 5, f.find_index { |x| x > 8 }
+        assert 31, f.inject { |s, x| s + x }
+        assert 41, f.inject(10) { |s, x| s + x }
+        assert 31, f.inject(:+)
+        assert 6480, f.reduce(1, :*)
+        assert 31, f.sum
+        assert 62, f.sum { |x| x * 2 }
+        assert 8, f.count
+        assert 2, f.count(1)
+        assert 3, f.count(&:even?)
+        assert true, f.include?(9)
+        assert false, f.member?(7)
+        assert 3, f.first
+        assert [3, 1, 4], f.first(3)
+        assert [3, 1], f.take(2)
+        assert [3, 1], f.take_while { |x| x < 4 }
+        assert [9, 2, 6], f.drop(5)
+        assert [4, 1, 5, 9, 2, 6], f.drop_while { |x| x < 4 }
+        assert 1, f.min
+        assert 9, f.max
+        assert [1, 1], f.min(2)
+        assert [9, 6, 5], f.max(3)
+        assert [1, 9], f.minmax
+        assert 9, f.min { |a, b| b <=> a }
+        assert 1, f.min_by { |x| (x - 1).abs }
+        assert 9, f.max_by { |x| x }
+        assert [1, 1, 2, 3, 4, 5, 6, 9], f.sort
+        assert [9, 6, 5, 4, 3, 2, 1, 1], f.sort { |a, b| b <=> a }
+        assert [9, 6, 5, 4, 3, 2, 1, 1], f.sort_by { |x| -x }
+        assert [3, 1, 4, 5, 9, 2, 6], f.uniq
+        assert({3=>1, 1=>2, 4=>1, 5=>1, 9=>1, 2=>1, 6=>1}, f.tally)
+        assert({1=>[3, 1, 1, 5, 9], 0=>[4, 2, 6]}, f.group_by { |x| x % 2 })
+        assert [[4, 2, 6], [3, 1, 1, 5, 9]], f.partition(&:even?)
+        assert [[3, :a], [1, :b], [4, nil]], Foo.new(3, 1, 4).zip([:a, :b])
+        assert({3=>6, 1=>2}, Foo.new(3, 1).to_h { |x| [x, x * 2] })
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn enumerable_predicates() {
+        let program = r#"
+        class Foo
+          include Enumerable
+          def initialize(*a)
+            @a = a
+          end
+          def each
+            @a.each { |x| yield x }
+          end
+        end
+        assert true, Foo.new(1, 2, 3).all? { |x| x > 0 }
+        assert false, Foo.new(1, 2, 3).all?(2..3)
+        assert true, Foo.new(1, 2, 3).any?(Integer)
+        assert false, Foo.new(nil, false).any?
+        assert true, Foo.new(nil, false).none?
+        assert true, Foo.new(1, 2, 3).one? { |x| x > 2 }
+        assert false, Foo.new(1, 2, 3).one? { |x| x > 1 }
+        assert ["ant", "cat"], Foo.new("ant", "bear", "cat").grep(/t/)
+        assert ["bear"], Foo.new("ant", "bear", "cat").grep_v(/t/)
+        assert [3, 4, 3], Foo.new("ant", "bear", "cat").grep(/a/) { |s| s.size }
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn enumerable_iteration() {
+        let program = r#"
+        class Foo
+          include Enumerable
+          def initialize(*a)
+            @a = a
+          end
+          def each
+            @a.each { |x| yield x }
+          end
+        end
+        f = Foo.new(1, 2, 3, 4, 5)
+        res = []
+        f.each_slice(2) { |x| res << x }
+        assert [[1, 2], [3, 4], [5]], res
+        res = []
+        f.each_cons(3) { |x| res << x }
+        assert [[1, 2, 3], [2, 3, 4], [3, 4, 5]], res
+        res = []
+        f.each_with_index { |x, i| res << x * i }
+        assert [0, 2, 6, 12, 20], res
+        assert [1, 2, 3, 4, 5], f.each_with_object([]) { |x, a| a << x }
+        res = []
+        f.reverse_each { |x| res << x }
+        assert [5, 4, 3, 2, 1], res
+        res = []
+        f.each_entry { |x| res << x }
+        assert [1, 2, 3, 4, 5], res
+        assert [[1, 2], [4, 5]], Foo.new(1, 2, 4, 5).chunk_while { |a, b| b == a + 1 }.to_a
+        assert [[1, 2], [4, 5]], Foo.new(1, 2, 4, 5).slice_when { |a, b| b != a + 1 }.to_a
+        assert [1, 3, 5, 7, 9], f.map.with_index { |x, i| x + i }
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn enumerable_early_exit() {
+        let program = r#"
+        class Foo
+          include Enumerable
+          attr_reader :count
+          def each
+            @count = 0
+            i = 0
+            while true
+              @count += 1
+              yield i
+              i += 1
+            end
+          end
+        end
+        f = Foo.new
+        assert 5, f.find { |x| x == 5 }
+        assert 6, f.count
+        assert [0, 1, 2], f.first(3)
+        assert [0, 1, 2, 3], f.take_while { |x| x < 4 }
+        assert true, f.include?(10)
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn enumerable_early_exit_ensure() {
+        let program = r#"
+        class Res
+          include Enumerable
+          attr_reader :log
+          def initialize
+            @log = []
+          end
+          def each
+            @log << :open
+            begin
+              yield 1
+              yield 2
+              yield 3
+            rescue Exception
+              @log << :rescue
+            end
+            @log << :after
+          ensure
+            @log << :close
+          end
+        end
+        r = Res.new
+        assert 2, r.find { |x| x == 2 }
+        assert [:open, :close], r.log
+        r = Res.new
+        assert 1, r.first
+        assert [:open, :close], r.log
+        r = Res.new
+        assert true, r.include?(2)
+        assert [:open, :close], r.log
+        r = Res.new
+        assert [1], r.take_while { |x| x < 2 }
+        assert [:open, :close], r.log
+        r = Res.new
+        assert [2, 4, 6], r.map { |x| x * 2 }
+        assert [:open, :after, :close], r.log
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn enumerable_builtin_classes() {
+        let program = r#"
+        assert true, Array.include?(Enumerable)
+        assert true, Hash.include?(Enumerable)
+        assert true, Range.include?(Enumerable)
+        assert true, Struct.include?(Enumerable)
+        assert true, Enumerator.include?(Enumerable)
+        assert [[1, 2], [3, 4], [5]], (1..5).each_slice(2).to_a
+        assert({1=>[1, 3, 5], 0=>[2, 4]}, (1..5).group_by { |x| x % 2 })
+        assert [[:a, 1], [:b, 2]], {a: 1, b: 2}.sort_by { |k, v| v }
+        assert [:b, 2], {a: 1, b: 2}.max_by { |k, v| v }
+        assert 3, {a: 1, b: 2}.sum { |k, v| v }
+        S = Struct.new(:a, :b)
+        assert [1, 2], S.new(1, 2).to_a
+        assert 3, S.new(1, 2).sum
+        assert [2, 4], [1, 2].each.map { |x| x * 2 }
+        "#;
+        assert_script(program);
+    }
+}
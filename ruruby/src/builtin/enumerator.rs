@@ -2,12 +2,11 @@ use crate::coroutine::*;
 use crate::*;
 
 pub(crate) fn init(globals: &mut Globals) -> Value {
-    let class = Module::class_under_object();
+    let mut class = Module::class_under_object();
     globals.set_toplevel_constant("Enumerator", class);
+    class.append_include_without_increment_version(BuiltinClass::enumerable());
     class.add_builtin_method_by_str(globals, "next", next);
     class.add_builtin_method_by_str(globals, "each", each);
-    class.add_builtin_method_by_str(globals, "map", map);
-    class.add_builtin_method_by_str(globals, "collect", map);
     class.add_builtin_method_by_str(globals, "with_index", with_index);
     class.add_builtin_method_by_str(globals, "inspect", inspect);
 
@@ -101,9 +100,10 @@ fn each(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
         Some(block) => block,
     };
     let mut fiber = vm.dup_enum(eref);
+    vm.temp_push(fiber);
     let f = vm.eval_block_map1(block);
     loop {
-        let val = match fiber.resume(Value::nil()) {
+        let val = match fiber.as_enumerator().unwrap().resume(Value::nil()) {
             Ok(val) => val,
             Err(err) if err.is_stop_iteration() => break,
             Err(err) => return Err(err),
@@ -127,38 +127,9 @@ fn each(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
     Ok(recv)
 }
 
-fn map(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
-    args.check_args_num(0)?;
-    let eref = self_val.as_enumerator().unwrap();
-    let mut info = vm.dup_enum(eref);
-    let block = match &args.block {
-        None => {
-            // return Enumerator
-            let id = IdentId::MAP;
-            let e = vm.create_enumerator(id, self_val, args.into(vm))?;
-            return Ok(e);
-        }
-        Some(block) => block,
-    };
-    let len = vm.temp_len();
-    let f = vm.eval_block_map1(block);
-    loop {
-        let val = match info.resume(Value::nil()) {
-            Ok(val) => val,
-            Err(err) if err.is_stop_iteration() => break,
-            Err(err) => return Err(err),
-        };
-        //let res = vm.eval_block1(block, val)?;
-        let res = f(vm, val)?;
-        vm.temp_push(res);
-    }
-    Ok(Value::array_from(vm.temp_pop_vec(len)))
-}
-
 fn with_index(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(0)?;
     let eref = self_val.as_enumerator().unwrap();
-    let mut info = vm.dup_enum(eref);
     let block = match &args.block {
         None => {
             // return Enumerator
@@ -169,10 +140,12 @@ fn with_index(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
         Some(block) => block,
     };
 
+    let mut info = vm.dup_enum(eref);
+    vm.temp_push(info);
     let mut c = 0;
     let len = vm.temp_len();
     loop {
-        let val = match info.resume(Value::nil()) {
+        let val = match info.as_enumerator().unwrap().resume(Value::nil()) {
             Ok(val) => val,
             Err(err) => {
                 if err.is_stop_iteration() {
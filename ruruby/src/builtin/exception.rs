@@ -1,7 +1,7 @@
 use crate::*;
 
 pub(crate) fn init(globals: &mut Globals) -> Value {
-    let exception = Module::class_under_object();
+    let mut exception = Module::class_under_object();
     globals.set_toplevel_constant("Exception", exception);
     exception.add_builtin_class_method(globals, "new", exception_new);
     exception.add_builtin_class_method(globals, "exception", exception_new);
@@ -9,13 +9,17 @@ pub(crate) fn init(globals: &mut Globals) -> Value {
 
     exception.add_builtin_method_by_str(globals, "inspect", inspect);
     exception.add_builtin_method_by_str(globals, "to_s", tos);
+    exception.add_builtin_method_by_str(globals, "==", eq);
+    exception.add_builtin_method_by_str(globals, "backtrace", backtrace);
+    exception.add_builtin_method_by_str(globals, "backtrace_locations", backtrace_locations);
+    exception.add_builtin_method_by_str(globals, "set_backtrace", set_backtrace);
+    exception.add_builtin_method_by_str(globals, "cause", cause);
+    exception.add_builtin_method_by_str(globals, "detailed_message", detailed_message);
+    exception.add_builtin_method_by_str(globals, "full_message", full_message);
     builtin::module::set_attr_accessor(
         globals,
         exception,
-        &Args::new2(
-            Value::symbol_from_str("message"),
-            Value::symbol_from_str("backtrace"),
-        ),
+        &Args::new1(Value::symbol_from_str("message")),
     )
     .unwrap();
     // StandardError.
@@ -23,11 +27,18 @@ pub(crate) fn init(globals: &mut Globals) -> Value {
     BUILTINS.with(|m| m.borrow_mut().standard = standard_error.into());
     globals.set_toplevel_constant("StandardError", standard_error);
 
-    let err = Module::class_under(standard_error);
-    globals.set_toplevel_constant("ArgumentError", err);
+    let argument_error = Module::class_under(standard_error);
+    globals.set_toplevel_constant("ArgumentError", argument_error);
+    let uncaught_throw_error = Module::class_under(argument_error);
+    globals.set_toplevel_constant("UncaughtThrowError", uncaught_throw_error);
+    for name in &["tag", "value"] {
+        builtin::module::define_reader(globals, uncaught_throw_error, IdentId::get_id(name));
+    }
 
-    let err = Module::class_under(standard_error);
-    globals.set_toplevel_constant("IndexError", err);
+    let index_error = Module::class_under(standard_error);
+    globals.set_toplevel_constant("IndexError", index_error);
+    let err = Module::class_under(index_error);
+    globals.set_toplevel_constant("KeyError", err);
 
     let err = Module::class_under(standard_error);
     globals.set_toplevel_constant("RegexpError", err);
@@ -46,12 +57,44 @@ pub(crate) fn init(globals: &mut Globals) -> Value {
     let err = Module::class_under(standard_error);
     globals.set_toplevel_constant("ZeroDivisionError", err);
 
+    let stop_iteration = Module::class_under(standard_error);
+    globals.set_toplevel_constant("StopIteration", stop_iteration);
+    let err = Module::class_under(stop_iteration);
+    globals.set_toplevel_constant("ClosedQueueError", err);
+
     let err = Module::class_under(standard_error);
-    globals.set_toplevel_constant("StopIteration", err);
+    globals.set_toplevel_constant("ThreadError", err);
 
     let err = Module::class_under(standard_error);
     globals.set_toplevel_constant("LocalJumpError", err);
 
+    let io_error = Module::class_under(standard_error);
+    globals.set_toplevel_constant("IOError", io_error);
+    let err = Module::class_under(io_error);
+    globals.set_toplevel_constant("EOFError", err);
+
+    // SystemCallError and Errno::EXXX.
+    let system_call_error = Module::class_under(standard_error);
+    globals.set_toplevel_constant("SystemCallError", system_call_error);
+    let mut errno = Module::module();
+    globals.set_toplevel_constant("Errno", errno);
+    for name in &[
+        "ENOENT", "EACCES", "EEXIST", "EISDIR", "ENOTDIR", "EBADF", "ESPIPE", "EINVAL", "EPIPE",
+        "ENOSYS", "ENOTSUP", "EROFS", "ECHILD", "ESRCH", "EPERM",
+    ] {
+        let err = Module::class_under(system_call_error);
+        errno.set_const_by_str(name, err.into());
+    }
+
+    // Subclasses of EncodingError are defined under Encoding class.
+    let err = Module::class_under(standard_error);
+    globals.set_toplevel_constant("EncodingError", err);
+
+    let no_matching_pattern_error = Module::class_under(standard_error);
+    globals.set_toplevel_constant("NoMatchingPatternError", no_matching_pattern_error);
+    let err = Module::class_under(no_matching_pattern_error);
+    globals.set_toplevel_constant("NoMatchingPatternKeyError", err);
+
     // RuntimeError
     let runtime_error = Module::class_under(standard_error);
     globals.set_toplevel_constant("RuntimeError", runtime_error);
@@ -67,6 +110,11 @@ pub(crate) fn init(globals: &mut Globals) -> Value {
     let err = Module::class_under(exception);
     globals.set_toplevel_constant("SystemExit", err);
 
+    // `fatal` is not a toplevel constant, so it can not be referred from Ruby code.
+    let mut err = Module::class_under(exception);
+    err.set_name("fatal");
+    exception.set_const_by_str("fatal", err.into());
+
     exception.into()
 }
 
@@ -121,6 +169,240 @@ fn tos(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     Ok(Value::string(err.message()))
 }
 
+/// ## instance method Exception#==
+/// - self == other -> bool
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Exception/i/=3d=3d.html
+fn eq(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let other = vm[0];
+    if self_val.id() == other.id() {
+        return Ok(Value::true_val());
+    }
+    if other.if_exception().is_none()
+        || vm.globals.get_class(self_val).id() != vm.globals.get_class(other).id()
+    {
+        return Ok(Value::false_val());
+    }
+    for method in &["message", "backtrace"] {
+        let method = IdentId::get_id(method);
+        let lhs = vm.eval_send0(method, self_val)?;
+        let rhs = vm.eval_send0(method, other)?;
+        if !vm.eval_eq2(rhs, lhs)? {
+            return Ok(Value::false_val());
+        }
+    }
+    Ok(Value::true_val())
+}
+
+/// ## instance method Exception#backtrace
+/// - backtrace -> [String] | nil
+///
+/// Returns nil if the exception has not been raised.
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Exception/i/backtrace.html
+fn backtrace(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    if let Some(backtrace) = self_val.get_var(IdentId::get_id("@backtrace")) {
+        return Ok(backtrace);
+    }
+    let err = self_val.if_exception().unwrap();
+    if err.backtrace.is_empty() {
+        return Ok(Value::nil());
+    }
+    let backtrace = err
+        .backtrace
+        .iter()
+        .map(|loc| Value::string(loc.to_string()))
+        .collect();
+    Ok(Value::array_from(backtrace))
+}
+
+/// ## instance method Exception#backtrace_locations
+/// - backtrace_locations -> [Thread::Backtrace::Location] | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Exception/i/backtrace_locations.html
+fn backtrace_locations(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    if self_val.get_var(IdentId::get_id("@backtrace")).is_some() {
+        return Ok(Value::nil());
+    }
+    let err = self_val.if_exception().unwrap();
+    if err.backtrace.is_empty() {
+        return Ok(Value::nil());
+    }
+    let locations = err
+        .backtrace
+        .iter()
+        .map(|loc| thread::backtrace_location(&vm.globals, loc))
+        .collect();
+    Ok(Value::array_from(locations))
+}
+
+/// ## instance method Exception#set_backtrace
+/// - set_backtrace(errinfo) -> nil | String | [String]
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Exception/i/set_backtrace.html
+fn set_backtrace(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let arg = vm[0];
+    let backtrace = if arg.is_nil() {
+        arg
+    } else if arg.as_string().is_some() {
+        Value::array_from(vec![arg])
+    } else {
+        match arg.as_array() {
+            Some(ary) if ary.iter().all(|v| v.as_string().is_some()) => arg,
+            _ => {
+                return Err(RubyError::typeerr(
+                    "backtrace must be Array of String".to_string(),
+                ))
+            }
+        }
+    };
+    self_val.set_var_by_str("@backtrace", backtrace);
+    Ok(backtrace)
+}
+
+/// ## instance method Exception#cause
+/// - cause -> Exception | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Exception/i/cause.html
+fn cause(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(self_val
+        .get_var(IdentId::get_id("/cause"))
+        .unwrap_or_default())
+}
+
+/// Get `highlight:` keyword argument.
+fn highlight_option(args: &Args2) -> bool {
+    match args.kw_arg.as_hash() {
+        Some(hash) => hash
+            .get(&Value::symbol_from_str("highlight"))
+            .is_some_and(|v| v.to_bool()),
+        None => false,
+    }
+}
+
+/// "message (ClassName)" of `ex`.
+fn detailed_message_main(vm: &mut VM, ex: Value, highlight: bool) -> Result<String, RubyError> {
+    let message = vm.eval_send0(IdentId::get_id("message"), ex)?;
+    let message = message.val_to_s(vm)?.to_string();
+    let class_name = ex.get_class_name();
+    let res = if message.is_empty() {
+        if highlight {
+            format!("\x1b[1;4m{}\x1b[m", class_name)
+        } else {
+            class_name
+        }
+    } else if highlight {
+        format!(
+            "\x1b[1m{} (\x1b[1;4m{}\x1b[m\x1b[1m)\x1b[m",
+            message, class_name
+        )
+    } else {
+        format!("{} ({})", message, class_name)
+    };
+    Ok(res)
+}
+
+/// ## instance method Exception#detailed_message
+/// - detailed_message(highlight: false) -> String
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Exception/i/detailed_message.html
+fn detailed_message(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let highlight = highlight_option(args);
+    Ok(Value::string(detailed_message_main(
+        vm, self_val, highlight,
+    )?))
+}
+
+/// ## instance method Exception#full_message
+/// - full_message(highlight: false, order: :top) -> String
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Exception/i/full_message.html
+fn full_message(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let highlight = highlight_option(args);
+    let order = match args.kw_arg.as_hash() {
+        Some(hash) => hash
+            .get(&Value::symbol_from_str("order"))
+            .cloned()
+            .unwrap_or_default(),
+        None => Value::nil(),
+    };
+    let top = match order.as_symbol() {
+        None if order.is_nil() => true,
+        Some(id) if id == IdentId::get_id("top") => true,
+        Some(id) if id == IdentId::get_id("bottom") => false,
+        _ => {
+            return Err(RubyError::argument(format!(
+                "expected :top or :bottom as order: {}",
+                vm.val_inspect(order)?
+            )))
+        }
+    };
+    let mut res = String::new();
+    let mut ex = self_val;
+    let mut shown = vec![];
+    // Causes are shown after the exception itself in :top order.
+    while ex.if_exception().is_some() && !shown.contains(&ex.id()) {
+        shown.push(ex.id());
+        full_message_main(vm, ex, highlight, top, &mut res)?;
+        if !top {
+            break;
+        }
+        ex = ex.get_var(IdentId::get_id("/cause")).unwrap_or_default();
+    }
+    Ok(Value::string(res))
+}
+
+fn full_message_main(
+    vm: &mut VM,
+    ex: Value,
+    highlight: bool,
+    top: bool,
+    res: &mut String,
+) -> Result<(), RubyError> {
+    let message = detailed_message_main(vm, ex, highlight)?;
+    let backtrace = vm.eval_send0(IdentId::get_id("backtrace"), ex)?;
+    let mut lines = vec![];
+    if let Some(ary) = backtrace.as_array() {
+        for loc in ary.iter() {
+            lines.push(loc.val_to_s(vm)?.to_string());
+        }
+    }
+    if lines.is_empty() {
+        let prog = vm.get_global_var(IdentId::get_id("$0")).unwrap_or_default();
+        let prog = if prog.is_nil() {
+            "ruruby".to_string()
+        } else {
+            prog.val_to_s(vm)?.to_string()
+        };
+        *res += &format!("{}: {}\n", prog, message);
+    } else if top {
+        *res += &format!("{}: {}\n", lines[0], message);
+        for line in &lines[1..] {
+            *res += &format!("\tfrom {}\n", line);
+        }
+    } else {
+        if lines.len() > 1 {
+            if highlight {
+                *res += "\x1b[1mTraceback\x1b[m (most recent call last):\n";
+            } else {
+                *res += "Traceback (most recent call last):\n";
+            }
+            for (i, line) in lines.iter().enumerate().skip(1).rev() {
+                *res += &format!("\t{}: from {}\n", i, line);
+            }
+        }
+        *res += &format!("{}: {}\n", lines[0], message);
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use crate::tests::*;
@@ -158,4 +440,93 @@ mod tests {
         "##;
         assert_script(program);
     }
+
+    #[test]
+    fn exception_backtrace() {
+        let program = r##"
+        def foo
+          raise ArgumentError, "bad"
+        end
+        begin
+          foo
+        rescue => e
+          assert Array, e.backtrace.class
+          assert true, e.backtrace[0].end_with?(":3:in `foo'")
+          assert true, e.backtrace[1].end_with?(":6:in `<main>'")
+          locs = e.backtrace_locations
+          assert [3, 6], locs.map(&:lineno)
+          assert ["foo", "<main>"], locs.map(&:label)
+          assert e.backtrace[0], locs[0].to_s
+        end
+        assert nil, RuntimeError.new("a").backtrace
+        assert nil, RuntimeError.new("a").backtrace_locations
+        e = RuntimeError.new("a")
+        assert ["x.rb:1"], e.set_backtrace("x.rb:1")
+        assert ["x.rb:1"], e.backtrace
+        assert nil, e.backtrace_locations
+        assert "x.rb:1: a (RuntimeError)\n", e.full_message
+        begin
+          raise TypeError, "t", ["y.rb:2", "y.rb:3"]
+        rescue => e
+          assert ["y.rb:2", "y.rb:3"], e.backtrace
+          assert "y.rb:2: t (TypeError)\n\tfrom y.rb:3\n", e.full_message
+          assert "Traceback (most recent call last):\n\t1: from y.rb:3\ny.rb:2: t (TypeError)\n", e.full_message(order: :bottom)
+          assert "\e[1mt (\e[1;4mTypeError\e[m\e[1m)\e[m", e.detailed_message(highlight: true)
+        end
+        assert "a (RuntimeError)", RuntimeError.new("a").detailed_message
+        assert "RuntimeError", RuntimeError.new("").detailed_message
+        assert true, RuntimeError.new("a") == RuntimeError.new("a")
+        assert false, RuntimeError.new("a") == RuntimeError.new("b")
+        assert false, RuntimeError.new("a") == TypeError.new("a")
+        "##;
+        assert_script(program);
+    }
+
+    #[test]
+    fn exception_cause() {
+        let program = r##"
+        begin
+          begin
+            raise "inner"
+          rescue => e
+            raise TypeError, "outer"
+          end
+        rescue => e
+          assert TypeError, e.class
+          assert "inner", e.cause.message
+          assert nil, e.cause.cause
+        end
+        assert nil, $!
+        begin
+          raise "x"
+        rescue => e
+          assert nil, e.cause
+        end
+        begin
+          begin
+            raise "inner"
+          rescue
+            raise TypeError, "outer", cause: nil
+          end
+        rescue => e
+          assert nil, e.cause
+        end
+        c = ArgumentError.new("c")
+        begin
+          raise "x", cause: c
+        rescue => e
+          assert c, e.cause
+        end
+        begin
+          begin
+            raise "a"
+          rescue
+            raise
+          end
+        rescue => e
+          assert "a", e.message
+        end
+        "##;
+        assert_script(program);
+    }
 }
@@ -6,8 +6,19 @@ use std::path::*;
 
 pub(crate) fn init(globals: &mut Globals) -> Value {
     let io_class = globals.get_toplevel_constant("IO");
-    let class = Module::class_under(Module::new(io_class));
+    let mut class = Module::class_under(Module::new(io_class));
     globals.set_toplevel_constant("File", class);
+    class.set_const_by_str("RDONLY", Value::integer(io::RDONLY));
+    class.set_const_by_str("WRONLY", Value::integer(io::WRONLY));
+    class.set_const_by_str("RDWR", Value::integer(io::RDWR));
+    class.set_const_by_str("CREAT", Value::integer(io::CREAT));
+    class.set_const_by_str("EXCL", Value::integer(io::EXCL));
+    class.set_const_by_str("TRUNC", Value::integer(io::TRUNC));
+    class.set_const_by_str("APPEND", Value::integer(io::APPEND));
+    let null = if cfg!(windows) { "NUL" } else { "/dev/null" };
+    class.set_const_by_str("NULL", Value::string(null));
+    class.add_builtin_class_method(globals, "new", new);
+    class.add_builtin_class_method(globals, "open", open);
     class.add_builtin_class_method(globals, "join", join);
     class.add_builtin_class_method(globals, "basename", basename);
     class.add_builtin_class_method(globals, "extname", extname);
@@ -16,12 +27,16 @@ pub(crate) fn init(globals: &mut Globals) -> Value {
     class.add_builtin_class_method(globals, "read", read);
     class.add_builtin_class_method(globals, "readlines", readlines);
     class.add_builtin_class_method(globals, "write", write);
+    class.add_builtin_class_method(globals, "delete", delete);
+    class.add_builtin_class_method(globals, "unlink", delete);
     class.add_builtin_class_method(globals, "expand_path", expand_path);
     class.add_builtin_class_method(globals, "exist?", exist);
     class.add_builtin_class_method(globals, "executable?", executable);
     class.add_builtin_class_method(globals, "directory?", directory);
     class.add_builtin_class_method(globals, "file?", file);
     class.add_builtin_class_method(globals, "realpath", realpath);
+    class.add_builtin_method_by_str(globals, "path", io::path);
+    class.add_builtin_method_by_str(globals, "to_path", io::path);
     class.into()
 }
 
@@ -70,6 +85,44 @@ pub(crate) fn string_to_canonicalized_path(
 
 // Class methods
 
+/// Open the file with the arguments (path, mode = "r", perm = 0666).
+fn open_file(vm: &mut VM, class: Value, args: &Args2) -> VMResult {
+    args.check_args_range(1, 3)?;
+    let mut path = vm[0];
+    let path = path.expect_string("1st arg")?.to_string();
+    let mode = if args.len() >= 2 { vm[1] } else { Value::nil() };
+    let perm = if args.len() == 3 && !vm[2].is_nil() {
+        Some(vm[2].coerce_to_fixnum("perm")? as u32)
+    } else {
+        None
+    };
+    let info = IoInfo::open(&path, mode, perm)?;
+    Ok(Value::io(class.into_module(), info))
+}
+
+/// File.new(path, mode = "r", perm = 0666) -> File
+fn new(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    open_file(vm, self_val, args)
+}
+
+/// File.open(path, mode = "r", perm = 0666) -> File
+/// File.open(path, mode = "r", perm = 0666) {|file| ... } -> object
+///
+/// The file is closed when the block exits.
+///
+/// https://docs.ruby-lang.org/ja/latest/method/File/s/open.html
+fn open(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    let file = open_file(vm, self_val, args)?;
+    match &args.block {
+        None => Ok(file),
+        Some(block) => {
+            let res = vm.eval_block1(block, file);
+            io::close_io(vm, file)?;
+            res
+        }
+    }
+}
+
 fn join(vm: &mut VM, _self_val: Value, args: &Args2) -> VMResult {
     fn flatten(vm: &mut VM, path: &mut String, mut val: Value) -> Result<(), RubyError> {
         match val.as_array() {
@@ -211,6 +264,16 @@ fn write(vm: &mut VM, _self_val: Value, args: &Args2) -> VMResult {
     Ok(Value::integer(contents.len() as i64))
 }
 
+/// File.delete(*filename) -> Integer
+fn delete(vm: &mut VM, _self_val: Value, args: &Args2) -> VMResult {
+    for i in 0..args.len() {
+        let mut filename = vm[i];
+        let path = filename.expect_string("filename")?;
+        std::fs::remove_file(path).map_err(|err| io::io_error(err, Some(path)))?;
+    }
+    Ok(Value::integer(args.len() as i64))
+}
+
 /// File.expand_path(path, default_dir = '.') -> String
 fn expand_path(vm: &mut VM, _self_val: Value, args: &Args2) -> VMResult {
     let len = args.len();
@@ -342,6 +405,70 @@ mod tests {
         assert_script(program);
     }
 
+    #[test]
+    fn file_open() {
+        let program = r###"
+            File.open("file_open.txt", "w") do |f|
+              assert false, f.closed?
+              f.puts "foo", "bar"
+              f.print "baz"
+              f << "\n" << 1
+              $f = f
+            end
+            assert true, $f.closed?
+            assert_error { $f.puts "x" }
+            assert "foo\nbar\nbaz\n1", File.read("file_open.txt")
+
+            f = File.open("file_open.txt")
+            assert "file_open.txt", f.path
+            assert "#<File:file_open.txt>", f.inspect
+            assert "foo\n", f.gets
+            assert 1, f.lineno
+            assert 4, f.pos
+            assert "bar", f.gets(chomp: true)
+            assert "ba", f.readpartial(2)
+            assert "z\n1", f.read
+            assert true, f.eof?
+            assert nil, f.gets
+            assert "", f.read
+            assert_error { f.readpartial(1) }
+            f.rewind
+            assert ["foo\n", "bar\n", "baz\n", "1"], f.each_line.to_a
+            f.seek(-1, IO::SEEK_END)
+            assert "1", f.read
+            f.seek(4)
+            assert "bar\n", f.readline
+            f.pos = 0
+            assert ["foo", "bar", "baz", "1"], f.readlines(chomp: true)
+            assert_error { f.write "x" }
+            f.close
+            assert_error { f.gets }
+
+            File.open("file_open.txt", "a+") do |f|
+              f.write "\nqux"
+              f.rewind
+              assert "foo\n", f.gets
+            end
+            res = File.open("file_open.txt", "r+") do |f|
+              f.seek(4)
+              f.write "BAR"
+              f.seek(0)
+              f.read
+            end
+            assert "foo\nBAR\nbaz\n1\nqux", res
+            assert_error { File.open("file_open.txt", "z") }
+            assert_error { File.open("file_open.txt", "wx") }
+            begin
+              File.open("no_such_file.txt")
+            rescue Errno::ENOENT => e
+              assert Errno::ENOENT, e.class
+            end
+            File.delete("file_open.txt")
+            assert false, File.exist?("file_open.txt")
+        "###;
+        assert_script(program);
+    }
+
     #[test]
     fn file_expand_path() {
         #[cfg(not(windows))]
@@ -16,6 +16,8 @@ pub(crate) fn init(globals: &mut Globals) -> Value {
     class.add_builtin_method_by_str(globals, "nan?", nan);
     class.add_builtin_method_by_str(globals, "infinite?", infinite);
     class.add_builtin_method_by_str(globals, "to_i", toi);
+    class.add_builtin_method_by_str(globals, "to_r", tor);
+    class.add_builtin_method_by_str(globals, "rationalize", rationalize);
     class.set_const_by_str("DIG", Value::integer(std::f64::DIGITS as i64));
     class.set_const_by_str("INFINITY", Value::float(std::f64::INFINITY));
     class.set_const_by_str("EPSILON", Value::float(std::f64::EPSILON));
@@ -108,6 +110,26 @@ fn toi(_: &mut VM, self_val: Value, _: &Args2) -> VMResult {
     Ok(Value::integer(num))
 }
 
+fn tor(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let f = self_val.as_float().unwrap();
+    Ok(Value::rational(rational::float_to_rational(f)?))
+}
+
+/// rationalize([eps]) -> Rational
+///
+/// Returns the simplest rational within `eps`, or the simplest one which rounds to `self`.
+fn rationalize(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
+    let f = self_val.as_float().unwrap();
+    if args.len() == 0 {
+        return Ok(Value::rational(rational::rationalize_float(f)?));
+    }
+    let eps = rational::to_rational(vm, vm[0])?;
+    let r = rational::float_to_rational(f)?;
+    Ok(Value::rational(rational::rationalize_with(&r, &eps)))
+}
+
 #[cfg(test)]
 mod tests {
     use crate::tests::*;
@@ -0,0 +1,621 @@
+///
+/// Format engine
+///
+/// Shared by Kernel#format, Kernel#sprintf, Kernel#printf and String#%.
+///
+/// https://docs.ruby-lang.org/ja/latest/doc/print_format.html
+///
+use crate::*;
+use num::{BigInt, FromPrimitive, Num, Signed, ToPrimitive, Zero};
+
+/// Flags, width and precision of a format directive.
+#[derive(Debug, Default)]
+struct Spec {
+    /// '-': left-justify.
+    minus: bool,
+    /// '+': show '+' for non-negative numbers.
+    plus: bool,
+    /// ' ': show ' ' for non-negative numbers.
+    space: bool,
+    /// '0': pad numbers with '0'.
+    zero: bool,
+    /// '#': alternative form.
+    sharp: bool,
+    width: Option<usize>,
+    precision: Option<usize>,
+}
+
+/// Arguments for the format string.
+struct FormatArgs<'a> {
+    args: &'a [Value],
+    next: usize,
+}
+
+impl<'a> FormatArgs<'a> {
+    fn next(&mut self) -> VMResult {
+        let val = self.nth(self.next + 1)?;
+        self.next += 1;
+        Ok(val)
+    }
+
+    /// Get `n`th argument. (`%n$d`, 1-origin)
+    fn nth(&self, n: usize) -> VMResult {
+        match self.args.get(n - 1) {
+            Some(val) => Ok(*val),
+            None => Err(RubyError::argument("too few arguments")),
+        }
+    }
+
+    /// Get the value for the name reference `%<name>` or `%{name}`.
+    fn named(&self, vm: &mut VM, name: &str) -> VMResult {
+        let hash = match self.args {
+            [hash] if hash.as_hash().is_some() => *hash,
+            _ => return Err(RubyError::argument("one hash required")),
+        };
+        let key = Value::symbol_from_str(name);
+        if let Some(val) = hash.as_hash().unwrap().get(&key) {
+            return Ok(*val);
+        }
+        let default = vm.eval_send1(IdentId::get_id("default"), hash, key)?;
+        if default.is_nil() {
+            Err(RubyError::key(format!("key<{}> not found", name)))
+        } else {
+            Ok(default)
+        }
+    }
+}
+
+/// Format `args` according to the format string `fmt`.
+pub(crate) fn sprintf(vm: &mut VM, fmt: &str, args: &[Value]) -> Result<String, RubyError> {
+    let fmt: Vec<char> = fmt.chars().collect();
+    let mut args = FormatArgs { args, next: 0 };
+    let mut res = String::new();
+    let mut i = 0;
+    while i < fmt.len() {
+        let ch = fmt[i];
+        i += 1;
+        if ch != '%' {
+            res.push(ch);
+            continue;
+        }
+        let start = i;
+        let mut spec = Spec::default();
+        // The argument given by `%<name>` or `%n$`.
+        let mut arg = None;
+        loop {
+            let ch = match fmt.get(i) {
+                Some(ch) => *ch,
+                None => {
+                    return Err(RubyError::argument(
+                        "incomplete format specifier; use %% (double %) instead",
+                    ))
+                }
+            };
+            i += 1;
+            match ch {
+                '-' => spec.minus = true,
+                '+' => spec.plus = true,
+                ' ' => spec.space = true,
+                '0' => spec.zero = true,
+                '#' => spec.sharp = true,
+                '1'..='9' => {
+                    let n = read_number(&fmt, &mut i, ch);
+                    if fmt.get(i) == Some(&'$') {
+                        i += 1;
+                        arg = Some(args.nth(n)?);
+                    } else {
+                        spec.width = Some(n);
+                    }
+                }
+                '*' => {
+                    let width = args.next()?.coerce_to_fixnum("width")?;
+                    if width < 0 {
+                        spec.minus = true;
+                    }
+                    spec.width = Some(width.unsigned_abs() as usize);
+                }
+                '.' => {
+                    if fmt.get(i) == Some(&'*') {
+                        i += 1;
+                        let prec = args.next()?.coerce_to_fixnum("precision")?;
+                        spec.precision = if prec < 0 { None } else { Some(prec as usize) };
+                    } else {
+                        spec.precision = Some(read_number(&fmt, &mut i, '0'));
+                    }
+                }
+                '<' | '{' => {
+                    let close = if ch == '<' { '>' } else { '}' };
+                    let name: String = fmt[i..].iter().take_while(|c| **c != close).collect();
+                    i += name.chars().count();
+                    if fmt.get(i) != Some(&close) {
+                        return Err(RubyError::argument(
+                            "malformed name - unmatched parenthesis",
+                        ));
+                    }
+                    i += 1;
+                    let val = args.named(vm, &name)?;
+                    if ch == '{' {
+                        // `%{name}` is replaced by the value without format conversion.
+                        let s = val.val_to_s(vm)?.into_owned();
+                        res += &format_str(&spec, s);
+                        break;
+                    }
+                    arg = Some(val);
+                }
+                '%' if i == start + 1 => {
+                    res.push('%');
+                    break;
+                }
+                'd' | 'i' | 'u' | 'x' | 'X' | 'o' | 'b' | 'B' | 'f' | 'e' | 'E' | 'g' | 'G'
+                | 'a' | 'A' | 's' | 'p' | 'c' => {
+                    let val = match arg {
+                        Some(val) => val,
+                        None => args.next()?,
+                    };
+                    res += &format_value(vm, ch, &spec, val)?;
+                    break;
+                }
+                ch => {
+                    return Err(RubyError::argument(format!(
+                        "malformed format string - %{}",
+                        ch
+                    )))
+                }
+            }
+        }
+    }
+    Ok(res)
+}
+
+/// Read a decimal number which begins with `first`.
+fn read_number(fmt: &[char], i: &mut usize, first: char) -> usize {
+    let mut n = first.to_digit(10).unwrap() as usize;
+    while let Some(d) = fmt.get(*i).and_then(|c| c.to_digit(10)) {
+        n = n.saturating_mul(10).saturating_add(d as usize);
+        *i += 1;
+    }
+    n
+}
+
+fn format_value(vm: &mut VM, ty: char, spec: &Spec, val: Value) -> Result<String, RubyError> {
+    let s = match ty {
+        'd' | 'i' | 'u' => format_integer(&to_integer(vm, val)?, 10, ty, spec),
+        'x' | 'X' => format_integer(&to_integer(vm, val)?, 16, ty, spec),
+        'o' => format_integer(&to_integer(vm, val)?, 8, ty, spec),
+        'b' | 'B' => format_integer(&to_integer(vm, val)?, 2, ty, spec),
+        'f' | 'e' | 'E' | 'g' | 'G' | 'a' | 'A' => format_float(to_float(vm, val)?, ty, spec),
+        's' => format_str(spec, val.val_to_s(vm)?.into_owned()),
+        'p' => format_str(spec, vm.val_inspect(val)?),
+        'c' => {
+            let ch = match val.as_rstring() {
+                Some(s) => s.to_s().chars().next().unwrap_or_default().to_string(),
+                None => {
+                    let code = to_integer(vm, val)?;
+                    match code.to_u32().and_then(char::from_u32) {
+                        Some(ch) => ch.to_string(),
+                        None => {
+                            return Err(RubyError::range(format!("{} out of char range", code)))
+                        }
+                    }
+                }
+            };
+            pad(spec, ch)
+        }
+        _ => unreachable!(),
+    };
+    Ok(s)
+}
+
+/// Convert `val` to Integer in the manner of Kernel#Integer.
+fn to_integer(vm: &mut VM, val: Value) -> Result<BigInt, RubyError> {
+    if let Some(i) = val.as_fixnum() {
+        return Ok(BigInt::from(i));
+    }
+    if let Some(b) = val.as_bignum() {
+        return Ok(b.clone());
+    }
+    if let Some(f) = val.as_float() {
+        return BigInt::from_f64(f.trunc())
+            .ok_or_else(|| RubyError::range(format!("{}", val.val_to_s(vm).unwrap())));
+    }
+    if let Some(s) = val.as_rstring() {
+        let s = s.to_s();
+        let (neg, body) = match s.trim().replace('_', "") {
+            s if s.starts_with('-') => (true, s[1..].to_string()),
+            s if s.starts_with('+') => (false, s[1..].to_string()),
+            s => (false, s),
+        };
+        let lower = body.to_ascii_lowercase();
+        let (radix, digits) = if let Some(d) = lower.strip_prefix("0x") {
+            (16, d)
+        } else if let Some(d) = lower.strip_prefix("0b") {
+            (2, d)
+        } else if let Some(d) = lower.strip_prefix("0o") {
+            (8, d)
+        } else if lower.len() > 1 && lower.starts_with('0') {
+            (8, &lower[1..])
+        } else {
+            (10, lower.as_str())
+        };
+        return match BigInt::from_str_radix(digits, radix) {
+            Ok(i) if !digits.starts_with(['+', '-']) => Ok(if neg { -i } else { i }),
+            _ => Err(RubyError::argument(format!(
+                "invalid value for Integer(): {}",
+                vm.val_inspect(val)?
+            ))),
+        };
+    }
+    if val.is_nil() {
+        return Err(RubyError::typeerr("can't convert nil into Integer"));
+    }
+    Err(VMError::no_implicit_conv(val, "Integer"))
+}
+
+/// Convert `val` to Float in the manner of Kernel#Float.
+fn to_float(vm: &mut VM, val: Value) -> Result<f64, RubyError> {
+    if let Some(f) = val.as_float() {
+        return Ok(f);
+    }
+    if let Some(i) = val.as_fixnum() {
+        return Ok(i as f64);
+    }
+    if let Some(b) = val.as_bignum() {
+        return Ok(b.to_f64().unwrap_or(f64::NAN));
+    }
+    if let Some(s) = val.as_rstring() {
+        return match s.to_s().trim().replace('_', "").parse::<f64>() {
+            Ok(f) => Ok(f),
+            Err(_) => Err(RubyError::argument(format!(
+                "invalid value for Float(): {}",
+                vm.val_inspect(val)?
+            ))),
+        };
+    }
+    if val.is_nil() {
+        return Err(RubyError::typeerr("can't convert nil into Float"));
+    }
+    Err(VMError::no_implicit_conv(val, "Float"))
+}
+
+/// Pad `s` with spaces to the width.
+fn pad(spec: &Spec, s: String) -> String {
+    let len = s.chars().count();
+    match spec.width {
+        Some(width) if width > len => {
+            let fill = " ".repeat(width - len);
+            if spec.minus {
+                s + &fill
+            } else {
+                fill + &s
+            }
+        }
+        _ => s,
+    }
+}
+
+/// Format a string. (`%s`, `%p`, `%{name}`)
+fn format_str(spec: &Spec, s: String) -> String {
+    let s = match spec.precision {
+        Some(prec) => s.chars().take(prec).collect(),
+        None => s,
+    };
+    pad(spec, s)
+}
+
+/// Assemble a number and pad it to the width.
+///
+/// When `zero_fill` is Some, the number is filled with the char between the prefix and the digits.
+fn pad_number(
+    spec: &Spec,
+    sign: &str,
+    prefix: &str,
+    digits: &str,
+    zero_fill: Option<char>,
+) -> String {
+    let len = sign.len() + prefix.len() + digits.chars().count();
+    let fill = match (spec.width, zero_fill) {
+        (Some(width), Some(ch)) if !spec.minus && width > len => ch.to_string().repeat(width - len),
+        _ => String::new(),
+    };
+    pad(spec, format!("{}{}{}{}", sign, prefix, fill, digits))
+}
+
+fn sign_of(spec: &Spec, negative: bool) -> &'static str {
+    if negative {
+        "-"
+    } else if spec.plus {
+        "+"
+    } else if spec.space {
+        " "
+    } else {
+        ""
+    }
+}
+
+/// Format an integer. (`%d`, `%x`, `%o`, `%b` ..)
+///
+/// A negative number is shown as two's complement (e.g. `..f01`)
+/// in `%x`, `%o` and `%b` unless '+' or ' ' flag is given.
+fn format_integer(n: &BigInt, radix: u32, ty: char, spec: &Spec) -> String {
+    let complement = n.is_negative() && radix != 10 && !spec.plus && !spec.space;
+    let (sign, mut digits, fill) = if complement {
+        let max_digit = std::char::from_digit(radix - 1, radix).unwrap();
+        ("", twos_complement(n, radix), max_digit)
+    } else {
+        (
+            sign_of(spec, n.is_negative()),
+            n.abs().to_str_radix(radix),
+            '0',
+        )
+    };
+    if ty == 'X' {
+        digits = digits.to_ascii_uppercase();
+    }
+    let prefix = match ty {
+        _ if !spec.sharp || n.is_zero() => "",
+        'x' => "0x",
+        'X' => "0X",
+        'b' => "0b",
+        'B' => "0B",
+        'o' => "0",
+        _ => "",
+    };
+    // ".." is counted in the precision and the width.
+    let dots = if complement { 2 } else { 0 };
+    if let Some(prec) = spec.precision {
+        if prec > digits.len() + dots {
+            digits = fill.to_string().repeat(prec - digits.len() - dots) + &digits;
+        }
+    }
+    if complement {
+        digits = format!("..{}", digits);
+    }
+    let zero_fill = if spec.zero && spec.precision.is_none() {
+        Some(fill)
+    } else {
+        None
+    };
+    if complement && zero_fill.is_some() {
+        // Fill between ".." and the digits.
+        let len = prefix.len() + digits.len();
+        if let Some(width) = spec.width {
+            if !spec.minus && width > len {
+                let fill = fill.to_string().repeat(width - len);
+                digits = format!("..{}{}", fill, &digits[2..]);
+            }
+        }
+        return pad(spec, format!("{}{}", prefix, digits));
+    }
+    pad_number(spec, sign, prefix, &digits, zero_fill)
+}
+
+/// Digits of the two's complement of negative `n` without redundant leading digits.
+fn twos_complement(n: &BigInt, radix: u32) -> String {
+    let max_digit = std::char::from_digit(radix - 1, radix).unwrap();
+    let mut len = 1;
+    loop {
+        let m = BigInt::from(radix).pow(len) + n;
+        if !m.is_negative() {
+            let s = m.to_str_radix(radix);
+            if s.len() == len as usize && s.starts_with(max_digit) {
+                return s;
+            }
+        }
+        len += 1;
+    }
+}
+
+/// Format a float. (`%f`, `%e`, `%g`, `%a` ..)
+fn format_float(f: f64, ty: char, spec: &Spec) -> String {
+    let upper = ty.is_ascii_uppercase();
+    if !f.is_finite() {
+        let body = if f.is_nan() { "NaN" } else { "Inf" };
+        let sign = sign_of(spec, f.is_sign_negative() && !f.is_nan());
+        return pad_number(spec, sign, "", body, None);
+    }
+    let sign = sign_of(spec, f.is_sign_negative());
+    let f = f.abs();
+    let zero_fill = if spec.zero { Some('0') } else { None };
+    let body = match ty {
+        'f' => {
+            let prec = spec.precision.unwrap_or(6);
+            let mut s = format!("{:.*}", prec, f);
+            if spec.sharp && prec == 0 {
+                s.push('.');
+            }
+            s
+        }
+        'e' | 'E' => format_exp(f, spec.precision.unwrap_or(6), spec.sharp, upper),
+        'g' | 'G' => {
+            let prec = match spec.precision {
+                Some(0) => 1,
+                Some(prec) => prec,
+                None => 6,
+            };
+            // The exponent after rounding to `prec` significant digits.
+            let exp = if f == 0.0 {
+                0
+            } else {
+                let s = format!("{:.*e}", prec - 1, f);
+                s[s.find('e').unwrap() + 1..].parse::<i32>().unwrap()
+            };
+            let s = if exp < -4 || exp >= prec as i32 {
+                format_exp(f, prec - 1, spec.sharp, upper)
+            } else {
+                let mut s = format!("{:.*}", (prec as i32 - 1 - exp) as usize, f);
+                if spec.sharp && !s.contains('.') {
+                    s.push('.');
+                }
+                s
+            };
+            if spec.sharp {
+                s
+            } else {
+                strip_fraction_zeros(&s)
+            }
+        }
+        'a' | 'A' => {
+            let s = format_hex_float(f, spec.precision, spec.sharp);
+            let (prefix, body) = if upper {
+                ("0X", s.to_ascii_uppercase())
+            } else {
+                ("0x", s)
+            };
+            return pad_number(spec, sign, prefix, &body, zero_fill);
+        }
+        _ => unreachable!(),
+    };
+    pad_number(spec, sign, "", &body, zero_fill)
+}
+
+/// Format `f` as "d.ddde+dd".
+fn format_exp(f: f64, prec: usize, sharp: bool, upper: bool) -> String {
+    let s = format!("{:.*e}", prec, f);
+    let (mantissa, exp) = s.split_at(s.find('e').unwrap());
+    let exp: i32 = exp[1..].parse().unwrap();
+    let point = if sharp && prec == 0 { "." } else { "" };
+    let e = if upper { 'E' } else { 'e' };
+    let exp_sign = if exp < 0 { '-' } else { '+' };
+    format!("{}{}{}{}{:02}", mantissa, point, e, exp_sign, exp.abs())
+}
+
+/// Remove trailing zeros (and '.') in the fraction part of "ddd.ddd" or "d.ddde+dd".
+fn strip_fraction_zeros(s: &str) -> String {
+    let (num, exp) = match s.find(['e', 'E']) {
+        Some(pos) => s.split_at(pos),
+        None => (s, ""),
+    };
+    let num = if num.contains('.') {
+        num.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        num
+    };
+    format!("{}{}", num, exp)
+}
+
+/// Format a non-negative finite `f` in hexadecimal as "h.hhhp+d" without "0x".
+fn format_hex_float(f: f64, prec: Option<usize>, sharp: bool) -> String {
+    const FRAC_BITS: u32 = 52;
+    const FRAC_MASK: u64 = (1 << FRAC_BITS) - 1;
+    let bits = f.to_bits();
+    let (mut lead, mut frac, exp) = if f == 0.0 {
+        (0u64, 0u64, 0i64)
+    } else {
+        let exp_bits = ((bits >> FRAC_BITS) & 0x7ff) as i64;
+        let frac = bits & FRAC_MASK;
+        if exp_bits == 0 {
+            // Normalize a subnormal number.
+            let shift = frac.leading_zeros() - (63 - FRAC_BITS);
+            (1, (frac << shift) & FRAC_MASK, -1022 - shift as i64)
+        } else {
+            (1, frac, exp_bits - 1023)
+        }
+    };
+    // The fraction part has 13 hex digits.
+    let digits = match prec {
+        None => format!("{:013x}", frac).trim_end_matches('0').to_string(),
+        Some(prec) if prec < 13 => {
+            let shift = (13 - prec) * 4;
+            let rem = frac & ((1 << shift) - 1);
+            let half = 1 << (shift - 1);
+            frac >>= shift;
+            // Round half to even. The last digit is the leading digit when `prec` is 0.
+            let odd = if prec == 0 {
+                lead & 1 == 1
+            } else {
+                frac & 1 == 1
+            };
+            if rem > half || (rem == half && odd) {
+                frac += 1;
+                if frac == 1 << (prec * 4) {
+                    frac = 0;
+                    lead += 1;
+                }
+            }
+            if prec == 0 {
+                String::new()
+            } else {
+                format!("{:0w$x}", frac, w = prec)
+            }
+        }
+        Some(prec) => format!("{:013x}{}", frac, "0".repeat(prec - 13)),
+    };
+    let point = if digits.is_empty() && !sharp { "" } else { "." };
+    let exp_sign = if exp < 0 { '-' } else { '+' };
+    format!("{}{}{}p{}{}", lead, point, digits, exp_sign, exp.abs())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::*;
+
+    #[test]
+    fn format_integer() {
+        let program = r#"
+        assert "42", format("%d", 42)
+        assert "-42", sprintf("%i", -42)
+        assert "  42|42   |00042", format("%4d|%-5d|%05d", 42, 42, 42)
+        assert "+42| 42|-42", format("%+d|% d|%+d", 42, 42, -42)
+        assert "00042", format("%.5d", 42)
+        assert "   00042", format("%8.5d", 42)
+        assert "3", format("%d", 3.99)
+        assert "-3", format("%d", -3.99)
+        assert "255", format("%d", "0xff")
+        assert "123456789012345678901234567890", format("%d", 123456789012345678901234567890)
+        assert "ff|FF|0xff|0XFF", format("%x|%X|%#x|%#X", 255, 255, 255, 255)
+        assert "377|0377|1010|0b1010|0B1010", format("%o|%#o|%b|%#b|%#B", 255, 255, 10, 10, 10)
+        assert "..f01|-ff|+ff|0x..f01", format("%x|%+x|%+x|%#x", -255, -255, 255, -255)
+        assert "..7401|..10101", format("%o|%b", -255, -11)
+        assert "..110101", format("%08b", -11)
+        assert "..f|0", format("%x|%#x", -1, 0)
+        assert "1fffffffffffffffffffff", format("%x", 2**85 - 1)
+        assert_error { format("%d", nil) }
+        assert_error { format("%d", "foo") }
+        assert_error { format("%d") }
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn format_float() {
+        let program = r#"
+        assert "3.141593", format("%f", 3.14159265)
+        assert "3.14|  3.14|3.14  |003.14", format("%.2f|%6.2f|%-6.2f|%06.2f", 3.14159, 3.14159, 3.14159, 3.14159)
+        assert "+3.0|3.|-0.500", format("%+.1f|%#.0f|%.3f", 3, 3, -0.5)
+        assert "1.234568e+04|1.234568E-04|1e+02", format("%e|%E|%.0e", 12345.678, 0.0001234568, 100)
+        assert "12345.7|1.23457e+06|0.0001|1e-05|100|100.000", format("%g|%g|%g|%g|%g|%#g", 12345.67, 1234567.0, 0.0001, 0.00001, 100, 100)
+        assert "1.5E+10", format("%G", 1.5e10)
+        assert "0x1p+0|0x1.8p+1|-0x1p-2|0x0p+0", format("%a|%a|%a|%a", 1.0, 3.0, -0.25, 0.0)
+        assert "0X1.8P+1|0x1.80p+1|0x2p+0", format("%A|%.2a|%.0a", 3.0, 3.0, 1.5)
+        assert "NaN|Inf|-Inf|+Inf", format("%f|%f|%e|%+g", Float::NAN, Float::INFINITY, -Float::INFINITY, Float::INFINITY)
+        assert "       Inf|NaN       ", format("%010f|%-10f", Float::INFINITY, Float::NAN)
+        assert "1.0e+30", format("%.1e", 10**30)
+        assert "2.50", format("%.2f", "2.5")
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn format_others() {
+        let program = r#"
+        assert "abc|  abc|abc  |ab", format("%s|%5s|%-5s|%.2s", "abc", "abc", "abc", "abc")
+        assert "[1, 2]|nil|:a", format("%s|%p|%p", [1, 2], nil, :a)
+        assert "A|a|  x", format("%c|%c|%3c", 65, "abc", "x")
+        assert "100%", format("%d%%", 100)
+        assert "   42|42   |  3.1", format("%*d|%-*d|%*.*f", 5, 42, 5, 42, 5, 1, 3.14159)
+        assert "b a", format("%2$s %1$s", "a", "b")
+        assert "x=   1, y=2.50", format("x=%<x>4d, y=%<y>.2f", x: 1, y: 2.5)
+        assert "Hello, world!|  abc", format("Hello, %{name}!|%5{s}", name: "world", s: "abc")
+        assert "x", format("%{a}", Hash.new("x"))
+        assert_error { format("%{a}", {}) }
+        assert_error { format("%{a}", 1) }
+        assert_error { format("%y", 1) }
+        assert_error { format("%", 1) }
+        assert "05: abc", "%02d: %s" % [5, "abc"]
+        assert "1.50", "%.2f" % 1.5
+        assert "a-b", "%{x}-%{y}" % {x: "a", y: "b"}
+        assert "ff", "%x" % 255
+        "#;
+        assert_script(program);
+    }
+}
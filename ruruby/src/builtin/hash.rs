@@ -1,22 +1,48 @@
 use crate::*;
 
 pub(crate) fn init(globals: &mut Globals) -> Value {
-    let class = Module::class_under_object();
+    let mut class = Module::class_under_object();
     globals.set_toplevel_constant("Hash", class);
+    class.append_include_without_increment_version(BuiltinClass::enumerable());
     class.add_builtin_method_by_str(globals, "to_s", inspect);
     class.add_builtin_method_by_str(globals, "inspect", inspect);
+    class.add_builtin_method_by_str(globals, "[]", index);
+    class.add_builtin_method_by_str(globals, "[]=", store);
+    class.add_builtin_method_by_str(globals, "store", store);
     class.add_builtin_method_by_str(globals, "clear", clear);
     class.add_builtin_method_by_str(globals, "clone", clone);
-    class.add_builtin_method_by_str(globals, "dup", clone);
+    class.add_builtin_method_by_str(globals, "dup", dup);
     class.add_builtin_method_by_str(globals, "compact", compact);
     class.add_builtin_method_by_str(globals, "delete", delete);
     class.add_builtin_method_by_str(globals, "empty?", empty);
     class.add_builtin_method_by_str(globals, "default", default);
+    class.add_builtin_method_by_str(globals, "default=", set_default);
+    class.add_builtin_method_by_str(globals, "default_proc", default_proc);
+    class.add_builtin_method_by_str(globals, "default_proc=", set_default_proc);
+    class.add_builtin_method_by_str(globals, "dig", dig);
+    class.add_builtin_method_by_str(globals, "to_a", to_a);
+    class.add_builtin_method_by_str(globals, "key", key);
+    class.add_builtin_method_by_str(globals, "values_at", values_at);
+    class.add_builtin_method_by_str(globals, "fetch_values", fetch_values);
+    class.add_builtin_method_by_str(globals, "slice", slice);
+    class.add_builtin_method_by_str(globals, "except", except);
+    class.add_builtin_method_by_str(globals, "shift", shift);
+    class.add_builtin_method_by_str(globals, "assoc", assoc);
+    class.add_builtin_method_by_str(globals, "rassoc", rassoc);
 
     class.add_builtin_method_by_str(globals, "select", select);
     class.add_builtin_method_by_str(globals, "find_all", select);
     class.add_builtin_method_by_str(globals, "filter", select);
     class.add_builtin_method_by_str(globals, "reject", reject);
+    class.add_builtin_method_by_str(globals, "select!", select_);
+    class.add_builtin_method_by_str(globals, "filter!", select_);
+    class.add_builtin_method_by_str(globals, "keep_if", keep_if);
+    class.add_builtin_method_by_str(globals, "reject!", reject_);
+    class.add_builtin_method_by_str(globals, "delete_if", delete_if);
+    class.add_builtin_method_by_str(globals, "transform_values", transform_values);
+    class.add_builtin_method_by_str(globals, "transform_values!", transform_values_);
+    class.add_builtin_method_by_str(globals, "transform_keys", transform_keys);
+    class.add_builtin_method_by_str(globals, "transform_keys!", transform_keys_);
 
     class.add_builtin_method_by_str(globals, "has_key?", has_key);
     class.add_builtin_method_by_str(globals, "key?", has_key);
@@ -32,24 +58,119 @@ pub(crate) fn init(globals: &mut Globals) -> Value {
     class.add_builtin_method_by_str(globals, "each", each);
     class.add_builtin_method_by_str(globals, "each_pair", each);
     class.add_builtin_method_by_str(globals, "merge", merge);
+    class.add_builtin_method_by_str(globals, "merge!", merge_);
+    class.add_builtin_method_by_str(globals, "update", merge_);
     class.add_builtin_method_by_str(globals, "fetch", fetch);
     class.add_builtin_method_by_str(globals, "compare_by_identity", compare_by_identity);
-    class.add_builtin_method_by_str(globals, "sort", sort);
+    class.add_builtin_method_by_str(globals, "compare_by_identity?", is_compare_by_identity);
     class.add_builtin_method_by_str(globals, "invert", invert);
+    class.add_builtin_method_by_str(globals, "deconstruct_keys", deconstruct_keys);
 
     class.add_builtin_class_method(globals, "new", hash_new);
+    class.add_builtin_class_method(globals, "[]", hash_elems);
     class.into()
 }
 
+/// Get the default proc of `hash`.
+fn get_default_proc(hash: Value) -> Option<Value> {
+    hash.get_var(IdentId::_DEFAULT_PROC)
+        .filter(|proc| !proc.is_nil())
+}
+
+/// Get the default value of `hash` for `key`.
+///
+/// If the default proc was set, it is called with `hash` and `key`.
+fn get_default(vm: &mut VM, hash: Value, key: Value) -> VMResult {
+    match get_default_proc(hash) {
+        Some(proc) => vm.eval_block(&Block::Proc(proc), &[hash, key]),
+        None => Ok(hash.get_var(IdentId::_DEFAULT).unwrap_or_default()),
+    }
+}
+
+/// Get the value for `key`, or the default value.
+fn get_or_default(vm: &mut VM, hash: Value, key: Value) -> VMResult {
+    match hash.as_hash().unwrap().get(&key) {
+        Some(val) => Ok(*val),
+        None => get_default(vm, hash, key),
+    }
+}
+
+fn key_not_found(vm: &mut VM, key: Value) -> RubyError {
+    match vm.val_inspect(key) {
+        Ok(s) => RubyError::key(format!("key not found: {}", s)),
+        Err(err) => err,
+    }
+}
+
 // Class methods
 
-fn hash_new(_: &mut VM, _: Value, args: &Args2) -> VMResult {
-    args.check_args_num(0)?;
+/// Hash.new(ifnone = nil) -> Hash
+/// Hash.new {|hash, key| ... } -> Hash
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Hash/s/new.html
+fn hash_new(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
     let map = FxIndexMap::default();
     let hash = Value::hash_from_map(map);
+    if let Some(block) = &args.block {
+        if args.len() == 1 {
+            return Err(RubyError::argument_wrong(1, 0));
+        }
+        let proc = vm.create_proc(block);
+        hash.set_var(IdentId::_DEFAULT_PROC, proc);
+    } else if args.len() == 1 {
+        hash.set_var(IdentId::_DEFAULT, vm[0]);
+    }
     Ok(hash)
 }
 
+/// Hash[other] -> Hash
+/// Hash[[[key, value], ...]] -> Hash
+/// Hash[key, value, ...] -> Hash
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Hash/s/=5b=5d.html
+fn hash_elems(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    if args.len() == 1 {
+        let arg = vm[0];
+        if let Some(hash) = arg.as_hash() {
+            return Ok(Value::hash_from(HashInfo::new(
+                hash.iter().map(|(k, v)| (HashKey(k), v)).collect(),
+            )));
+        }
+        if let Some(ary) = arg.as_array() {
+            let mut map = FxIndexMap::default();
+            for pair in ary.iter() {
+                match pair.as_array() {
+                    Some(pair) if pair.len() == 1 || pair.len() == 2 => {
+                        let v = if pair.len() == 2 {
+                            pair[1]
+                        } else {
+                            Value::nil()
+                        };
+                        map.insert(HashKey(pair[0]), v);
+                    }
+                    _ => {
+                        return Err(RubyError::argument(format!(
+                            "invalid element {} for Hash",
+                            vm.val_inspect(*pair)?
+                        )))
+                    }
+                }
+            }
+            return Ok(Value::hash_from_map(map));
+        }
+    }
+    if args.len() % 2 != 0 {
+        return Err(RubyError::argument("odd number of arguments for Hash"));
+    }
+    let map = vm
+        .args()
+        .chunks(2)
+        .map(|pair| (HashKey(pair[0]), pair[1]))
+        .collect();
+    Ok(Value::hash_from_map(map))
+}
+
 // Instance methods
 
 fn inspect(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
@@ -59,14 +180,44 @@ fn inspect(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     Ok(Value::string(s))
 }
 
+/// self[key] -> object | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Hash/i/=5b=5d.html
+fn index(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    get_or_default(vm, self_val, vm[0])
+}
+
+/// self[key] = value
+/// store(key, value) -> object
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Hash/i/=5b=5d=3d.html
+fn store(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(2)?;
+    self_val.check_frozen()?;
+    let hash = self_val.as_mut_hash().unwrap();
+    hash.insert(vm[0], vm[1]);
+    Ok(vm[1])
+}
+
 fn clear(_: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(0)?;
+    self_val.check_frozen()?;
     let hash = self_val.as_mut_hash().unwrap();
     hash.clear();
     Ok(self_val)
 }
 
-fn clone(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+fn clone(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    let val = dup(vm, self_val, args)?;
+    if self_val.is_frozen() {
+        Ok(val.freeze())
+    } else {
+        Ok(val)
+    }
+}
+
+fn dup(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(0)?;
     let hash = self_val.as_hash().unwrap();
     Ok(Value::hash_from(hash.clone()))
@@ -82,12 +233,20 @@ fn compact(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     Ok(Value::hash_from(hash))
 }
 
+/// delete(key) -> object | nil
+/// delete(key) {|key| ... } -> object
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Hash/i/delete.html
 fn delete(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(1)?;
+    self_val.check_frozen()?;
     let hash = self_val.as_mut_hash().unwrap();
     let res = match hash.remove(vm[0]) {
         Some(v) => v,
-        None => Value::nil(),
+        None => match &args.block {
+            Some(block) => vm.eval_block1(block, vm[0])?,
+            None => Value::nil(),
+        },
     };
     Ok(res)
 }
@@ -99,10 +258,189 @@ fn empty(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
 }
 
 /// default -> object | nil
+/// default(key) -> object | nil
 ///
 /// https://docs.ruby-lang.org/ja/latest/method/Hash/i/default.html
-fn default(_: &mut VM, _: Value, args: &Args2) -> VMResult {
+fn default(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
+    if args.len() == 1 {
+        get_default(vm, self_val, vm[0])
+    } else if get_default_proc(self_val).is_some() {
+        Ok(Value::nil())
+    } else {
+        Ok(self_val.get_var(IdentId::_DEFAULT).unwrap_or_default())
+    }
+}
+
+/// default = value
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Hash/i/default=3d.html
+fn set_default(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    self_val.check_frozen()?;
+    self_val.set_var(IdentId::_DEFAULT, vm[0]);
+    self_val.set_var(IdentId::_DEFAULT_PROC, Value::nil());
+    Ok(vm[0])
+}
+
+/// default_proc -> Proc | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Hash/i/default_proc.html
+fn default_proc(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(0)?;
+    Ok(get_default_proc(self_val).unwrap_or_default())
+}
+
+/// default_proc = proc | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Hash/i/default_proc=3d.html
+fn set_default_proc(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    self_val.check_frozen()?;
+    let proc = vm[0];
+    if !proc.is_nil() && proc.as_proc().is_none() {
+        return Err(VMError::wrong_type("1st arg", "Proc", proc));
+    }
+    self_val.set_var(IdentId::_DEFAULT, Value::nil());
+    self_val.set_var(IdentId::_DEFAULT_PROC, proc);
+    Ok(proc)
+}
+
+/// dig(key, ...) -> object | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Hash/i/dig.html
+fn dig(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_min(1)?;
+    let rest = vm.args()[1..].to_vec();
+    let val = get_or_default(vm, self_val, vm[0])?;
+    if rest.is_empty() || val.is_nil() {
+        return Ok(val);
+    }
+    vm.eval_send(IdentId::get_id("dig"), val, &Args::from_slice(&rest))
+}
+
+/// to_a -> [[object, object]]
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Hash/i/to_a.html
+fn to_a(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let hash = self_val.as_hash().unwrap();
+    let ary = hash
+        .iter()
+        .map(|(k, v)| Value::array_from(vec![k, v]))
+        .collect();
+    Ok(Value::array_from(ary))
+}
+
+/// key(val) -> object
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Hash/i/key.html
+fn key(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let hash = self_val.as_hash().unwrap();
+    for (k, v) in hash.iter() {
+        if vm.eval_eq2(vm[0], v)? {
+            return Ok(k);
+        }
+    }
+    Ok(Value::nil())
+}
+
+/// values_at(*keys) -> [object]
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Hash/i/values_at.html
+fn values_at(vm: &mut VM, self_val: Value, _: &Args2) -> VMResult {
+    let mut res = vec![];
+    for key in vm.args().to_owned() {
+        res.push(get_or_default(vm, self_val, key)?);
+    }
+    Ok(Value::array_from(res))
+}
+
+/// fetch_values(*keys) -> [object]
+/// fetch_values(*keys) {|key| ... } -> [object]
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Hash/i/fetch_values.html
+fn fetch_values(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    let mut res = vec![];
+    for key in vm.args().to_owned() {
+        let val = match self_val.as_hash().unwrap().get(&key) {
+            Some(val) => *val,
+            None => match &args.block {
+                Some(block) => vm.eval_block1(block, key)?,
+                None => return Err(key_not_found(vm, key)),
+            },
+        };
+        res.push(val);
+    }
+    Ok(Value::array_from(res))
+}
+
+/// slice(*keys) -> Hash
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Hash/i/slice.html
+fn slice(vm: &mut VM, self_val: Value, _: &Args2) -> VMResult {
+    let hash = self_val.as_hash().unwrap();
+    let mut map = FxIndexMap::default();
+    for key in vm.args() {
+        if let Some(v) = hash.get(key) {
+            map.insert(HashKey(*key), *v);
+        }
+    }
+    Ok(Value::hash_from_map(map))
+}
+
+/// except(*keys) -> Hash
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Hash/i/except.html
+fn except(vm: &mut VM, self_val: Value, _: &Args2) -> VMResult {
+    let mut hash = self_val.as_hash().unwrap().clone();
+    for key in vm.args() {
+        hash.remove(*key);
+    }
+    Ok(Value::hash_from(hash))
+}
+
+/// shift -> [object, object] | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Hash/i/shift.html
+fn shift(_: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    self_val.check_frozen()?;
+    let hash = self_val.as_mut_hash().unwrap();
+    let (k, v) = match hash.iter().next() {
+        Some(pair) => pair,
+        None => return Ok(Value::nil()),
+    };
+    hash.remove(k);
+    Ok(Value::array_from(vec![k, v]))
+}
+
+/// assoc(key) -> [object, object] | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Hash/i/assoc.html
+fn assoc(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let hash = self_val.as_hash().unwrap();
+    for (k, v) in hash.iter() {
+        if vm.eval_eq2(vm[0], k)? {
+            return Ok(Value::array_from(vec![k, v]));
+        }
+    }
+    Ok(Value::nil())
+}
+
+/// rassoc(value) -> [object, object] | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Hash/i/rassoc.html
+fn rassoc(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let hash = self_val.as_hash().unwrap();
+    for (k, v) in hash.iter() {
+        if vm.eval_eq2(vm[0], v)? {
+            return Ok(Value::array_from(vec![k, v]));
+        }
+    }
     Ok(Value::nil())
 }
 
@@ -138,6 +476,174 @@ fn reject(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     Ok(Value::hash_from_map(res))
 }
 
+/// Remove pairs for which the block returns `remove_if`.
+///
+/// Returns true if any pair was removed.
+fn retain_pairs(
+    vm: &mut VM,
+    mut self_val: Value,
+    block: &Block,
+    remove_if: bool,
+) -> Result<bool, RubyError> {
+    self_val.check_frozen()?;
+    let pairs: Vec<_> = self_val.as_hash().unwrap().iter().collect();
+    let mut removed = false;
+    for (k, v) in pairs {
+        if vm.eval_block2(block, k, v)?.to_bool() == remove_if {
+            self_val.as_mut_hash().unwrap().remove(k);
+            removed = true;
+        }
+    }
+    Ok(removed)
+}
+
+/// select! {|key, value| ... } -> self | nil
+/// filter! {|key, value| ... } -> self | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Hash/i/filter=21.html
+fn select_(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let block = args.expect_block()?;
+    if retain_pairs(vm, self_val, block, false)? {
+        Ok(self_val)
+    } else {
+        Ok(Value::nil())
+    }
+}
+
+/// keep_if {|key, value| ... } -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Hash/i/keep_if.html
+fn keep_if(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let block = args.expect_block()?;
+    retain_pairs(vm, self_val, block, false)?;
+    Ok(self_val)
+}
+
+/// reject! {|key, value| ... } -> self | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Hash/i/reject=21.html
+fn reject_(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let block = args.expect_block()?;
+    if retain_pairs(vm, self_val, block, true)? {
+        Ok(self_val)
+    } else {
+        Ok(Value::nil())
+    }
+}
+
+/// delete_if {|key, value| ... } -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Hash/i/delete_if.html
+fn delete_if(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let block = args.expect_block()?;
+    retain_pairs(vm, self_val, block, true)?;
+    Ok(self_val)
+}
+
+/// Map values of `self_val` by the block.
+fn map_values(vm: &mut VM, self_val: Value, block: &Block) -> Result<HashInfo, RubyError> {
+    let mut hash = self_val.as_hash().unwrap().clone();
+    let pairs: Vec<_> = hash.iter().collect();
+    let temp_len = vm.temp_len();
+    for (k, v) in pairs {
+        let res = vm.eval_block1(block, v);
+        let v = match res {
+            Ok(v) => v,
+            Err(err) => {
+                vm.temp_pop_vec(temp_len);
+                return Err(err);
+            }
+        };
+        vm.temp_push(v);
+        hash.insert(k, v);
+    }
+    vm.temp_pop_vec(temp_len);
+    Ok(hash)
+}
+
+/// transform_values {|value| ... } -> Hash
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Hash/i/transform_values.html
+fn transform_values(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let block = args.expect_block()?;
+    let hash = map_values(vm, self_val, block)?;
+    Ok(Value::hash_from(hash))
+}
+
+/// transform_values! {|value| ... } -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Hash/i/transform_values=21.html
+fn transform_values_(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    self_val.check_frozen()?;
+    let block = args.expect_block()?;
+    let hash = map_values(vm, self_val, block)?;
+    *self_val.as_mut_hash().unwrap() = hash;
+    Ok(self_val)
+}
+
+/// Map keys of `self_val` by the block, or by the hash given as an argument.
+fn map_keys(vm: &mut VM, self_val: Value, args: &Args2) -> Result<HashInfo, RubyError> {
+    args.check_args_range(0, 1)?;
+    let mapping = if args.len() == 1 {
+        vm[0].expect_hash("1st arg")?;
+        Some(vm[0])
+    } else if args.kw_arg.as_hash().is_some() {
+        // transform_keys(a: :b) passes the mapping as keyword arguments.
+        Some(args.kw_arg)
+    } else {
+        args.expect_block()?;
+        None
+    };
+    let pairs: Vec<_> = self_val.as_hash().unwrap().iter().collect();
+    let mut map = FxIndexMap::default();
+    let temp_len = vm.temp_len();
+    for (k, v) in pairs {
+        let new_key = match mapping.and_then(|m| m.as_hash().unwrap().get(&k).cloned()) {
+            Some(new_key) => new_key,
+            None => match &args.block {
+                Some(block) => match vm.eval_block1(block, k) {
+                    Ok(new_key) => new_key,
+                    Err(err) => {
+                        vm.temp_pop_vec(temp_len);
+                        return Err(err);
+                    }
+                },
+                None => k,
+            },
+        };
+        vm.temp_push(new_key);
+        map.insert(HashKey(new_key), v);
+    }
+    vm.temp_pop_vec(temp_len);
+    Ok(HashInfo::new(map))
+}
+
+/// transform_keys {|key| ... } -> Hash
+/// transform_keys(hash) -> Hash
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Hash/i/transform_keys.html
+fn transform_keys(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    let hash = map_keys(vm, self_val, args)?;
+    Ok(Value::hash_from(hash))
+}
+
+/// transform_keys! {|key| ... } -> self
+/// transform_keys!(hash) -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Hash/i/transform_keys=21.html
+fn transform_keys_(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    self_val.check_frozen()?;
+    let hash = map_keys(vm, self_val, args)?;
+    *self_val.as_mut_hash().unwrap() = hash;
+    Ok(self_val)
+}
+
 fn has_key(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(1)?;
     let hash = self_val.as_hash().unwrap();
@@ -175,10 +681,23 @@ fn values(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     Ok(Value::array_from(hash.values()))
 }
 
+macro_rules! to_enum_str {
+    ($vm:ident, $self_val:ident, $args:ident, $id:expr) => {
+        match &$args.block {
+            None => {
+                let val =
+                    $vm.create_enumerator(IdentId::get_id($id), $self_val, $args.into($vm))?;
+                return Ok(val);
+            }
+            Some(block) => block,
+        }
+    };
+}
+
 fn each_value(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(0)?;
     let hash = self_val.as_hash().unwrap();
-    let block = args.expect_block()?;
+    let block = to_enum_str!(vm, self_val, args, "each_value");
     let f = vm.eval_block_map1(block);
     for (_, v) in hash.iter() {
         //vm.eval_block1(&block, v)?;
@@ -191,7 +710,7 @@ fn each_value(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
 fn each_key(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(0)?;
     let hash = self_val.as_hash().unwrap();
-    let block = args.expect_block()?;
+    let block = to_enum_str!(vm, self_val, args, "each_key");
     let f = vm.eval_block_map1(block);
     for (k, _) in hash.iter() {
         //vm.eval_block1(&block, k)?;
@@ -206,7 +725,7 @@ fn each_key(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
 fn each(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(0)?;
     let hash = self_val.as_hash().unwrap();
-    let block = args.expect_block()?;
+    let block = to_enum_str!(vm, self_val, args, "each");
     for (k, v) in hash.iter() {
         vm.eval_block2(&block, k, v)?;
     }
@@ -214,18 +733,70 @@ fn each(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     Ok(self_val)
 }
 
-fn merge(vm: &mut VM, self_val: Value, _args: &Args2) -> VMResult {
-    let mut new = (self_val.expect_hash("Receiver")?).clone();
-    for arg in vm.args() {
-        let other = arg.expect_hash("First arg")?;
-        for (k, v) in other.iter() {
-            new.insert(k, v);
+/// Merge `others` into `hash`. Conflicts are resolved by the block if given.
+fn merge_into(
+    vm: &mut VM,
+    hash: &mut HashInfo,
+    others: Vec<Value>,
+    block: &Option<Block>,
+) -> Result<(), RubyError> {
+    for other in others {
+        let pairs: Vec<_> = other.expect_hash("1st arg")?.iter().collect();
+        for (k, v) in pairs {
+            let v = match (block, hash.get(&k)) {
+                (Some(block), Some(old)) => {
+                    let old = *old;
+                    let v = vm.eval_block(block, &[k, old, v])?;
+                    vm.temp_push(v);
+                    v
+                }
+                _ => v,
+            };
+            hash.insert(k, v);
         }
     }
+    Ok(())
+}
 
+/// Collect hashes to be merged. `merge(a: 1)` passes a hash as keyword arguments.
+fn merge_args(vm: &VM, args: &Args2) -> Vec<Value> {
+    let mut others = vm.args().to_vec();
+    if args.kw_arg.as_hash().is_some() {
+        others.push(args.kw_arg);
+    }
+    others
+}
+
+/// merge(*others) -> Hash
+/// merge(*others) {|key, self_val, other_val| ... } -> Hash
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Hash/i/merge.html
+fn merge(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    let mut new = (self_val.expect_hash("Receiver")?).clone();
+    let others = merge_args(vm, args);
+    let temp_len = vm.temp_len();
+    let res = merge_into(vm, &mut new, others, &args.block);
+    vm.temp_pop_vec(temp_len);
+    res?;
     Ok(Value::hash_from(new))
 }
 
+/// merge!(*others) -> self
+/// update(*others) {|key, self_val, other_val| ... } -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Hash/i/merge=21.html
+fn merge_(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    self_val.check_frozen()?;
+    let mut new = (self_val.expect_hash("Receiver")?).clone();
+    let others = merge_args(vm, args);
+    let temp_len = vm.temp_len();
+    let res = merge_into(vm, &mut new, others, &args.block);
+    vm.temp_pop_vec(temp_len);
+    res?;
+    *self_val.as_mut_hash().unwrap() = new;
+    Ok(self_val)
+}
+
 fn fetch(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_range(1, 2)?;
     let key = vm[0];
@@ -240,8 +811,7 @@ fn fetch(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
                     if args.len() == 2 {
                         vm[1]
                     } else {
-                        // TODO: Should be KeyError.
-                        return Err(RubyError::argument("Key not found."));
+                        return Err(key_not_found(vm, key));
                     }
                 }
                 Some(block) => vm.eval_block1(block, key)?,
@@ -254,6 +824,7 @@ fn fetch(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
 
 fn compare_by_identity(_: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(0)?;
+    self_val.check_frozen()?;
     let hash = self_val.as_mut_hash().unwrap();
     match hash {
         HashInfo::Map(map) => {
@@ -265,16 +836,13 @@ fn compare_by_identity(_: &mut VM, mut self_val: Value, args: &Args2) -> VMResul
     Ok(self_val)
 }
 
-fn sort(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+/// compare_by_identity? -> bool
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Hash/i/compare_by_identity=3f.html
+fn is_compare_by_identity(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(0)?;
     let hash = self_val.as_hash().unwrap();
-    let mut vec = vec![];
-    for (k, v) in hash.iter() {
-        let ary = vec![k, v];
-        vec.push(Value::array_from(ary));
-    }
-    vm.sort_array(&mut vec)?;
-    Ok(Value::array_from(vec))
+    Ok(Value::bool(matches!(hash, HashInfo::IdentMap(_))))
 }
 
 fn invert(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
@@ -287,6 +855,11 @@ fn invert(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     Ok(Value::hash_from_map(new_hash))
 }
 
+fn deconstruct_keys(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    Ok(self_val)
+}
+
 #[cfg(test)]
 mod test {
     use crate::tests::*;
@@ -549,4 +1122,118 @@ mod test {
         "##;
         assert_script(program);
     }
+
+    #[test]
+    fn hash_key_error() {
+        let program = r##"
+            h = {one: 1}
+            begin
+              h.fetch(:two)
+            rescue KeyError => e
+              assert(KeyError, e.class)
+            end
+            assert(true, KeyError.ancestors.include?(IndexError))
+            assert([1, 0], h.fetch_values(:one, :two) { 0 })
+            assert_error { h.fetch_values(:one, :two) }
+        "##;
+        assert_script(program);
+    }
+
+    #[test]
+    fn hash_default_proc() {
+        let program = r##"
+            h = Hash.new { |hash, key| hash[key] = key * 2 }
+            assert(6, h[3])
+            assert({3=>6}, h)
+            assert(nil, h.default)
+            assert(8, h.default(4))
+            assert(Proc, h.default_proc.class)
+            h.default = 5
+            assert(5, h[100])
+            assert(nil, h.default_proc)
+            h.default_proc = Proc.new { |hash, key| key.to_s }
+            assert("7", h[7])
+            assert(nil, h.default)
+            assert_error { Hash.new(1) { } }
+            assert({1=>2, 3=>4}, Hash[[[1, 2], [3, 4]]])
+            assert({1=>2, 3=>4}, Hash[1, 2, 3, 4])
+            assert({a: 1}, Hash[{a: 1}])
+            assert_error { Hash[1, 2, 3] }
+        "##;
+        assert_script(program);
+    }
+
+    #[test]
+    fn hash_query() {
+        let program = r##"
+            h = {a: 1, b: 2, c: 3}
+            assert(3, {a: {b: {c: 3}}}.dig(:a, :b, :c))
+            assert(nil, {a: {}}.dig(:x, :y))
+            assert(:b, h.key(2))
+            assert(nil, h.key(5))
+            assert([1, nil], h.values_at(:a, :z))
+            assert({a: 1, c: 3}, h.slice(:a, :c, :z))
+            assert({a: 1, c: 3}, h.except(:b))
+            assert([:b, 2], h.assoc(:b))
+            assert([:c, 3], h.rassoc(3))
+            assert(nil, h.rassoc(4))
+            assert([[:a, 1], [:b, 2], [:c, 3]], h.to_a)
+            assert(false, h.compare_by_identity?)
+            assert(true, h.dup.compare_by_identity.compare_by_identity?)
+            assert([[:a, 1], [:b, 2], [:c, 3]], h.each.to_a)
+        "##;
+        assert_script(program);
+    }
+
+    #[test]
+    fn hash_transform() {
+        let program = r##"
+            h = {a: 1, b: 2}
+            assert({a: 10, b: 20}, h.transform_values { |v| v * 10 })
+            assert({"a"=>1, "b"=>2}, h.transform_keys(&:to_s))
+            assert({x: 1, b: 2}, h.transform_keys(a: :x))
+            assert({x: 1, b: 2}, h.transform_keys({a: :x}))
+            h.transform_values!(&:to_s)
+            h.transform_keys!(&:to_s)
+            assert({"a"=>"1", "b"=>"2"}, h)
+        "##;
+        assert_script(program);
+    }
+
+    #[test]
+    fn hash_merge_block() {
+        let program = r##"
+            h = {a: 1, b: 2}
+            assert({a: 11, b: 2, c: 3}, h.merge({a: 10, c: 3}) { |k, old, new| old + new })
+            assert({a: 1, b: 2}, h)
+            h.update({b: 5}) { |k, old, new| old * new }
+            assert({a: 1, b: 10}, h)
+            h.merge!(c: 0)
+            assert({a: 1, b: 10, c: 0}, h)
+        "##;
+        assert_script(program);
+    }
+
+    #[test]
+    fn hash_destructive() {
+        let program = r##"
+            h = {a: 1, b: 2, c: 3, d: 4}
+            assert(nil, h.select! { true })
+            assert({a: 1, b: 2, c: 3}, h.filter! { |k, v| v < 4 })
+            assert(nil, h.reject! { false })
+            assert({a: 1, c: 3}, h.reject! { |k, v| v == 2 })
+            assert({a: 1, c: 3}, h.keep_if { true })
+            assert({c: 3}, h.delete_if { |k, v| k == :a })
+            h = {a: 1, b: 2, c: 3}
+            h.delete(:a)
+            h[:a] = 4
+            assert([:b, :c, :a], h.keys)
+            assert("none", h.delete(:z) { "none" })
+            assert([:b, 2], h.shift)
+            assert({c: 3, a: 4}, h)
+            assert(nil, {}.shift)
+            assert_error { {a: 1}.freeze.delete_if { true } }
+        "##;
+        assert_script(program);
+    }
 }
@@ -0,0 +1,449 @@
+///
+/// RubyVM::InstructionSequence class
+///
+/// An instance holds the FnId of a Ruby function in the hidden instance variable `/fnid`.
+/// Instances created by `compile` or `compile_file` also have `/toplevel`, and only they can be `eval`ed.
+///
+use crate::*;
+use std::path::PathBuf;
+
+pub(crate) fn init(globals: &mut Globals) {
+    let mut rubyvm = Module::class_under_object();
+    globals.set_toplevel_constant("RubyVM", rubyvm);
+    let class = Module::class_under_object();
+    rubyvm.set_const_by_str("InstructionSequence", class.into());
+    class.add_builtin_class_method(globals, "compile", compile);
+    class.add_builtin_class_method(globals, "new", compile);
+    class.add_builtin_class_method(globals, "compile_file", compile_file);
+    class.add_builtin_class_method(globals, "of", of);
+    class.add_builtin_method_by_str(globals, "disasm", disasm);
+    class.add_builtin_method_by_str(globals, "disassemble", disasm);
+    class.add_builtin_method_by_str(globals, "to_a", to_a);
+    class.add_builtin_method_by_str(globals, "eval", eval);
+    class.add_builtin_method_by_str(globals, "label", label);
+    class.add_builtin_method_by_str(globals, "path", path);
+    class.add_builtin_method_by_str(globals, "first_lineno", first_lineno);
+    class.add_builtin_method_by_str(globals, "inspect", inspect);
+    class.add_builtin_method_by_str(globals, "to_s", inspect);
+}
+
+fn new_iseq(globals: &Globals, method: FnId, toplevel: bool) -> Value {
+    let class = globals
+        .get_toplevel_constant("RubyVM")
+        .into_module()
+        .get_const_noautoload(IdentId::get_id("InstructionSequence"))
+        .unwrap()
+        .into_module();
+    let val = Value::ordinary_object(class);
+    val.set_var_by_str("/fnid", Value::integer(u32::from(method) as i64));
+    if toplevel {
+        val.set_var_by_str("/toplevel", Value::true_val());
+    }
+    val
+}
+
+/// Get ISeqRef of the Ruby function `method`, or None if `method` is not a Ruby function.
+fn ruby_func(globals: &Globals, method: FnId) -> Option<ISeqRef> {
+    match globals.methods[method] {
+        MethodInfo::RubyFunc { iseq } => Some(iseq),
+        _ => None,
+    }
+}
+
+fn fnid(self_val: Value) -> FnId {
+    let id = self_val
+        .get_var(IdentId::get_id("/fnid"))
+        .and_then(|v| v.as_fixnum())
+        .unwrap();
+    FnId::from(id as u32)
+}
+
+fn iseq_ref(globals: &Globals, self_val: Value) -> ISeqRef {
+    ruby_func(globals, fnid(self_val)).unwrap()
+}
+
+/// Line number of the source location `loc` in `iseq`.
+fn line_no(iseq: ISeqRef, loc: Loc) -> usize {
+    let code = iseq.source_info.code.as_bytes();
+    let pos = std::cmp::min(loc.0, code.len());
+    code[..pos].iter().filter(|b| **b == b'\n').count() + 1
+}
+
+/// Line number of the instruction at `pc` in `iseq`.
+///
+/// An entry of the sourcemap is keyed by the position just after the instruction,
+/// and every statement has an entry after its last instruction.
+/// So the first entry at or after the end of the instruction gives its location.
+/// Instructions after the last statement (e.g. RETURN) belong to the last entry.
+fn inst_line_no(iseq: ISeqRef, pc: ISeqPos) -> usize {
+    let end = pc + Inst::inst_size(iseq.iseq[pc]);
+    let loc = iseq
+        .iseq_sourcemap
+        .iter()
+        .find(|(pos, _)| *pos >= end)
+        .or_else(|| iseq.iseq_sourcemap.last())
+        .map_or(iseq.loc, |(_, loc)| *loc);
+    line_no(iseq, loc)
+}
+
+fn iseq_label(iseq: ISeqRef) -> String {
+    match iseq.kind {
+        ISeqKind::Method(Some(id)) => format!("{:?}", id),
+        ISeqKind::Method(None) => "<main>".to_string(),
+        ISeqKind::Class(id) => format!("<class:{:?}>", id),
+        ISeqKind::Block => "block".to_string(),
+        ISeqKind::Other => "<eval>".to_string(),
+    }
+}
+
+fn iseq_name(iseq: ISeqRef) -> String {
+    format!(
+        "{}@{}:{}",
+        iseq_label(iseq),
+        iseq.source_info.get_file_name(),
+        line_no(iseq, iseq.loc)
+    )
+}
+
+/// FnId of a Ruby function (a block, a method or a class body) which is referred by the instruction at `pc`.
+fn child_method(iseq: &ISeq, pc: ISeqPos) -> Option<FnId> {
+    match iseq.read32(iseq.method_operand(pc)?) {
+        0 => None,
+        id => Some(FnId::from(id)),
+    }
+}
+
+/// Disassemble `iseq` and Ruby functions defined in `iseq` recursively.
+fn disasm_iseq(globals: &Globals, iseq: ISeqRef, res: &mut String) {
+    *res += &format!("== disasm: #<ISeq:{}>\n", iseq_name(iseq));
+    let mut children = vec![];
+    let mut pc = ISeqPos::from(0);
+    while pc.into_usize() < iseq.iseq.len() {
+        let line = inst_line_no(iseq, pc);
+        let info = globals.inst_info(iseq, pc);
+        *res += &format!("{:05x} {:<48}({:>4})\n", pc.into_usize(), info, line);
+        if let Some(child) = child_method(&iseq.iseq, pc).and_then(|m| ruby_func(globals, m)) {
+            children.push(child);
+        }
+        pc += Inst::inst_size(iseq.iseq[pc]);
+    }
+    for child in children {
+        *res += "\n";
+        disasm_iseq(globals, child, res);
+    }
+}
+
+/// Operands of the instruction at `pc` as Ruby objects.
+fn operands(globals: &Globals, iseq_ref: ISeqRef, pc: ISeqPos) -> Vec<Value> {
+    let iseq = &iseq_ref.iseq;
+    let ident = |pc: ISeqPos| Value::symbol(iseq.read32(pc).into());
+    let int = |pc: ISeqPos| Value::integer(iseq.read32(pc) as i32 as i64);
+    let dest = |pc: ISeqPos, size: usize| {
+        Value::integer((pc + size + iseq.read_disp(pc + size - 4)).into_usize() as i64)
+    };
+    let method = |pc: ISeqPos| match iseq.read32(pc) {
+        0 => Value::nil(),
+        id => new_iseq(globals, FnId::from(id), false),
+    };
+    let lvar = |pc: ISeqPos| match iseq_ref.lvar.get_name_id(iseq.read32(pc).into()) {
+        Some(id) => Value::symbol(id),
+        None => Value::nil(),
+    };
+    match iseq[pc] {
+        Inst::ADDI
+        | Inst::SUBI
+        | Inst::EQI
+        | Inst::NEI
+        | Inst::GTI
+        | Inst::GEI
+        | Inst::LTI
+        | Inst::LEI
+        | Inst::GET_IDX_I
+        | Inst::SET_IDX_I
+        | Inst::GET_SVAR
+        | Inst::SET_SVAR
+        | Inst::CREATE_ARRAY
+        | Inst::CREATE_HASH
+        | Inst::DUP
+        | Inst::TAKE
+        | Inst::SINKN
+        | Inst::TOPN
+        | Inst::CONCAT_STRING
+        | Inst::YIELD
+        | Inst::RESCUE => vec![int(pc + 1)],
+        Inst::PUSH_VAL => vec![Value::from(iseq.read64(pc + 1))],
+        Inst::CONST_VAL | Inst::NO_MATCH => {
+            vec![globals.const_values.get(iseq.read32(pc + 1) as usize)]
+        }
+        Inst::JMP
+        | Inst::JMP_BACK
+        | Inst::JMP_F
+        | Inst::JMP_T
+        | Inst::JMP_F_EQ
+        | Inst::JMP_F_NE
+        | Inst::JMP_F_GT
+        | Inst::JMP_F_GE
+        | Inst::JMP_F_LT
+        | Inst::JMP_F_LE => vec![dest(pc, 5)],
+        Inst::JMP_F_EQI
+        | Inst::JMP_F_NEI
+        | Inst::JMP_F_GTI
+        | Inst::JMP_F_GEI
+        | Inst::JMP_F_LTI
+        | Inst::JMP_F_LEI => vec![int(pc + 1), dest(pc, 9)],
+        Inst::OPT_CASE | Inst::OPT_CASE2 => vec![dest(pc, 9)],
+        Inst::SET_LOCAL | Inst::GET_LOCAL | Inst::TEE_LOCAL => vec![lvar(pc + 1)],
+        Inst::SET_DYNLOCAL | Inst::GET_DYNLOCAL => vec![int(pc + 1), int(pc + 5)],
+        Inst::CHECK_LOCAL => vec![lvar(pc + 1), int(pc + 5)],
+        Inst::GET_CONST
+        | Inst::GET_CONST_TOP
+        | Inst::SET_CONST
+        | Inst::CHECK_CONST
+        | Inst::CHECK_METHOD
+        | Inst::GET_SCOPE
+        | Inst::GET_IVAR
+        | Inst::SET_IVAR
+        | Inst::CHECK_IVAR
+        | Inst::GET_CVAR
+        | Inst::SET_CVAR
+        | Inst::GET_GVAR
+        | Inst::SET_GVAR
+        | Inst::CHECK_GVAR => vec![ident(pc + 1)],
+        Inst::SEND => vec![
+            ident(pc + 1),
+            Value::integer(iseq.read16(pc + 5) as i64),
+            method(pc + 8),
+        ],
+        Inst::OPT_SEND | Inst::OPT_SEND_N => vec![
+            ident(pc + 1),
+            Value::integer(iseq.read16(pc + 5) as i64),
+            method(pc + 7),
+        ],
+        Inst::SUPER => vec![
+            Value::integer(iseq.read16(pc + 1) as i64),
+            method(pc + 3),
+            Value::bool(iseq.read8(pc + 7) == 1),
+        ],
+        Inst::DEF_CLASS => vec![
+            Value::symbol_from_str(if iseq.read8(pc + 1) == 1 {
+                "module"
+            } else {
+                "class"
+            }),
+            ident(pc + 2),
+            method(pc + 6),
+        ],
+        Inst::DEF_METHOD | Inst::DEF_SMETHOD => vec![ident(pc + 1), method(pc + 5)],
+        Inst::CREATE_PROC | Inst::DEF_SCLASS => vec![method(pc + 1)],
+        _ => vec![],
+    }
+}
+
+// Class methods
+
+/// RubyVM::InstructionSequence.compile(source, file = "<compiled>") -> RubyVM::InstructionSequence
+///
+/// https://docs.ruby-lang.org/ja/latest/method/RubyVM=3a=3aInstructionSequence/s/compile.html
+fn compile(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_range(1, 2)?;
+    let mut arg0 = vm[0];
+    let code = arg0.expect_string("1st arg")?.to_string();
+    let path = if args.len() == 2 {
+        let mut arg1 = vm[1];
+        arg1.expect_string("2nd arg")?.to_string()
+    } else {
+        "<compiled>".to_string()
+    };
+    let method = vm.parse_program(path, code)?;
+    Ok(new_iseq(&vm.globals, method, true))
+}
+
+/// RubyVM::InstructionSequence.compile_file(file) -> RubyVM::InstructionSequence
+///
+/// https://docs.ruby-lang.org/ja/latest/method/RubyVM=3a=3aInstructionSequence/s/compile_file.html
+fn compile_file(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let mut arg0 = vm[0];
+    let path = PathBuf::from(arg0.expect_string("1st arg")?);
+    let code = match load_file(&path) {
+        Ok(code) => code,
+        Err(err) => {
+            return Err(RubyError::load(format!(
+                "Cannot open file. '{:?}'\n{}",
+                path, err
+            )))
+        }
+    };
+    let method = vm.parse_program(path, code)?;
+    Ok(new_iseq(&vm.globals, method, true))
+}
+
+/// RubyVM::InstructionSequence.of(body) -> RubyVM::InstructionSequence | nil
+///
+/// `body` is a Proc, Method or UnboundMethod.
+/// Returns nil if `body` is not defined in Ruby.
+///
+/// https://docs.ruby-lang.org/ja/latest/method/RubyVM=3a=3aInstructionSequence/s/of.html
+fn of(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let body = vm[0];
+    let method = if let Some(pinfo) = body.as_proc() {
+        pinfo.method
+    } else if let Some(minfo) = body.as_method() {
+        minfo.method
+    } else if let Some(minfo) = body.as_unbound_method() {
+        minfo.method
+    } else {
+        return Err(RubyError::typeerr(format!(
+            "Wrong argument type {} (expected Proc or Method)",
+            body.get_class_name()
+        )));
+    };
+    match ruby_func(&vm.globals, method) {
+        Some(_) => Ok(new_iseq(&vm.globals, method, false)),
+        None => Ok(Value::nil()),
+    }
+}
+
+// Instance methods
+
+/// RubyVM::InstructionSequence#disasm -> String
+///
+/// https://docs.ruby-lang.org/ja/latest/method/RubyVM=3a=3aInstructionSequence/i/disasm.html
+fn disasm(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let mut res = String::new();
+    disasm_iseq(&vm.globals, iseq_ref(&vm.globals, self_val), &mut res);
+    Ok(Value::string(res))
+}
+
+/// RubyVM::InstructionSequence#to_a -> [[Symbol, [Object], Integer]]
+///
+/// Returns an Array of instructions, each of which is an Array of
+/// the opcode name, the operands and the line number.
+///
+/// https://docs.ruby-lang.org/ja/latest/method/RubyVM=3a=3aInstructionSequence/i/to_a.html
+fn to_a(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let iseq = iseq_ref(&vm.globals, self_val);
+    let mut res = vec![];
+    let mut pc = ISeqPos::from(0);
+    while pc.into_usize() < iseq.iseq.len() {
+        let inst = iseq.iseq[pc];
+        let line = inst_line_no(iseq, pc);
+        res.push(Value::array_from(vec![
+            Value::symbol_from_str(&Inst::inst_name(inst)),
+            Value::array_from(operands(&vm.globals, iseq, pc)),
+            Value::integer(line as i64),
+        ]));
+        pc += Inst::inst_size(inst);
+    }
+    Ok(Value::array_from(res))
+}
+
+/// RubyVM::InstructionSequence#eval -> object
+///
+/// https://docs.ruby-lang.org/ja/latest/method/RubyVM=3a=3aInstructionSequence/i/eval.html
+fn eval(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    if self_val.get_var(IdentId::get_id("/toplevel")).is_none() {
+        return Err(RubyError::typeerr(
+            "Only InstructionSequences created by compile can be evaluated.",
+        ));
+    }
+    let main = vm.globals.main_object;
+    vm.eval_method0(fnid(self_val), main)
+}
+
+fn label(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::string(iseq_label(iseq_ref(&vm.globals, self_val))))
+}
+
+fn path(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let iseq = iseq_ref(&vm.globals, self_val);
+    Ok(Value::string(iseq.source_info.get_file_name()))
+}
+
+fn first_lineno(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let iseq = iseq_ref(&vm.globals, self_val);
+    Ok(Value::integer(line_no(iseq, iseq.loc) as i64))
+}
+
+fn inspect(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let iseq = iseq_ref(&vm.globals, self_val);
+    Ok(Value::string(format!(
+        "#<RubyVM::InstructionSequence:{}>",
+        iseq_name(iseq)
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::*;
+
+    #[test]
+    fn iseq_compile() {
+        let program = r##"
+        iseq = RubyVM::InstructionSequence.compile("a = 3\nb = a + 4\nb * 2")
+        assert "#<RubyVM::InstructionSequence:<main>@<compiled>:1>", iseq.inspect
+        assert "<main>", iseq.label
+        assert "<compiled>", iseq.path
+        assert 1, iseq.first_lineno
+        assert 14, iseq.eval
+        assert 14, iseq.eval
+
+        ary = iseq.to_a
+        assert [:TEE_LOCAL, [:a], 1], ary.find { |inst| inst[0] == :TEE_LOCAL }
+        assert [:ADDI, [4], 2], ary.find { |inst| inst[0] == :ADDI }
+        assert [1, 2, 3], ary.map { |inst| inst[2] }.uniq
+
+        disasm = iseq.disasm
+        assert "== disasm: #<ISeq:<main>@<compiled>:1>\n", disasm.lines.first
+        assert true, disasm.include?("TEE_LOCAL 'a'")
+        assert disasm, iseq.disassemble
+
+        assert "foo.rb", RubyVM::InstructionSequence.compile("1", "foo.rb").path
+        assert_error { RubyVM::InstructionSequence.compile("1 +") }
+        "##;
+        assert_script(program);
+    }
+
+    #[test]
+    fn iseq_of() {
+        let program = r#"
+        def foo(x)
+          [1, 2].map { |y| x + y }
+        end
+        iseq = RubyVM::InstructionSequence.of(method(:foo))
+        assert "foo", iseq.label
+        assert 2, iseq.first_lineno
+        send = iseq.to_a.find { |inst| inst[0] == :SEND || inst[0] == :O_SEND }
+        assert :map, send[1][0]
+        assert "block", send[1][2].label
+        assert 1, iseq.disasm.scan("== disasm:").size - 1
+
+        assert "foo", RubyVM::InstructionSequence.of(Object.instance_method(:foo)).label
+        assert "block", RubyVM::InstructionSequence.of(Proc.new { 1 }).label
+        assert nil, RubyVM::InstructionSequence.of(1.method(:+))
+        assert_error { RubyVM::InstructionSequence.of(1) }
+        assert_error { iseq.eval }
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn iseq_compile_file() {
+        let program = r#"
+        File.write("/tmp/ruruby_iseq_compile_file.rb", "x = 6\nx * 7\n")
+        iseq = RubyVM::InstructionSequence.compile_file("/tmp/ruruby_iseq_compile_file.rb")
+        assert "/tmp/ruruby_iseq_compile_file.rb", iseq.path
+        assert 42, iseq.eval
+        assert_error { RubyVM::InstructionSequence.compile_file("/tmp/ruruby_no_such_file.rb") }
+        "#;
+        assert_script(program);
+    }
+}
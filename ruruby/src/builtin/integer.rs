@@ -17,6 +17,7 @@ pub(crate) fn init(globals: &mut Globals) -> Value {
     class.add_builtin_method_by_str(globals, "-@", minus);
     class.add_builtin_method_by_str(globals, "div", quotient);
     class.add_builtin_method_by_str(globals, "fdiv", fdiv);
+    class.add_builtin_method_by_str(globals, "quo", quo);
     class.add_builtin_method_by_str(globals, "==", eq);
     class.add_builtin_method_by_str(globals, "===", eq);
     class.add_builtin_method_by_str(globals, "!=", neq);
@@ -46,6 +47,8 @@ pub(crate) fn init(globals: &mut Globals) -> Value {
     class.add_builtin_method_by_str(globals, "to_f", tof);
     class.add_builtin_method_by_str(globals, "to_i", toi);
     class.add_builtin_method_by_str(globals, "to_int", toi);
+    class.add_builtin_method_by_str(globals, "to_r", tor);
+    class.add_builtin_method_by_str(globals, "rationalize", rationalize);
     class.add_builtin_method_by_str(globals, "size", size);
     class.add_builtin_method_by_str(globals, "next", next);
     class.add_builtin_method_by_str(globals, "succ", next);
@@ -114,6 +117,24 @@ fn fdiv(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     }
 }
 
+/// quo(other) -> Rational | Float
+fn quo(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let lhs = self_val.to_real().unwrap();
+    match vm[0].to_real() {
+        Some(rhs) => {
+            if rhs.is_zero() {
+                return Err(RubyError::zero_div("Divided by zero."));
+            }
+            match rhs {
+                Real::Float(_) => Ok(lhs.divide(rhs).into_val()),
+                rhs => Ok(Value::rational(lhs.into_rational() / rhs.into_rational())),
+            }
+        }
+        None => Err(VMError::cant_coerse(vm[0], "Numeric")),
+    }
+}
+
 fn quotient(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(1)?;
     let lhs = self_val.to_real().unwrap();
@@ -577,6 +598,21 @@ fn toi(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     Ok(self_val)
 }
 
+fn tor(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let num = self_val.to_real().unwrap();
+    Ok(Value::rational(num.into_rational()))
+}
+
+/// rationalize([eps]) -> Rational
+///
+/// The optional argument is ignored, as an Integer is always exact.
+fn rationalize(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
+    let num = self_val.to_real().unwrap();
+    Ok(Value::rational(num.into_rational()))
+}
+
 /// even? -> bool
 ///
 /// https://docs.ruby-lang.org/ja/latest/method/Integer/i/even=3f.html
@@ -1,43 +1,1135 @@
+///
+/// IO class
+///
+/// An IO object wraps a standard stream or an opened file, with its own read and write buffers.
+///
 use crate::*;
+use std::fs::{File, OpenOptions};
+use std::io::{self, IsTerminal, Read, Seek, SeekFrom, Write};
+use std::process::{Child, ExitStatus, Stdio};
+
+const BUF_SIZE: usize = 8192;
 
 pub(crate) fn init(globals: &mut Globals) -> Value {
-    let io_class = Module::class_under_object();
+    let mut io_class = Module::class_under_object();
+    globals.set_toplevel_constant("IO", io_class);
+    io_class.set_const_by_str("SEEK_SET", Value::integer(0));
+    io_class.set_const_by_str("SEEK_CUR", Value::integer(1));
+    io_class.set_const_by_str("SEEK_END", Value::integer(2));
+    io_class.add_builtin_method_by_str(globals, "gets", gets);
+    io_class.add_builtin_method_by_str(globals, "readline", readline);
+    io_class.add_builtin_method_by_str(globals, "readlines", readlines);
+    io_class.add_builtin_method_by_str(globals, "each_line", each_line);
+    io_class.add_builtin_method_by_str(globals, "each", each_line);
+    io_class.add_builtin_method_by_str(globals, "read", read);
+    io_class.add_builtin_method_by_str(globals, "readpartial", readpartial);
+    io_class.add_builtin_method_by_str(globals, "write", write);
+    io_class.add_builtin_method_by_str(globals, "print", print);
+    io_class.add_builtin_method_by_str(globals, "puts", puts);
     io_class.add_builtin_method_by_str(globals, "<<", output);
+    io_class.add_builtin_method_by_str(globals, "flush", flush);
+    io_class.add_builtin_method_by_str(globals, "sync", sync);
+    io_class.add_builtin_method_by_str(globals, "sync=", set_sync);
+    io_class.add_builtin_method_by_str(globals, "seek", seek);
+    io_class.add_builtin_method_by_str(globals, "pos", pos);
+    io_class.add_builtin_method_by_str(globals, "tell", pos);
+    io_class.add_builtin_method_by_str(globals, "pos=", set_pos);
+    io_class.add_builtin_method_by_str(globals, "rewind", rewind);
+    io_class.add_builtin_method_by_str(globals, "eof?", eof);
+    io_class.add_builtin_method_by_str(globals, "eof", eof);
+    io_class.add_builtin_method_by_str(globals, "lineno", lineno);
+    io_class.add_builtin_method_by_str(globals, "close", close);
+    io_class.add_builtin_method_by_str(globals, "closed?", closed);
+    io_class.add_builtin_method_by_str(globals, "fileno", fileno);
+    io_class.add_builtin_method_by_str(globals, "to_i", fileno);
     io_class.add_builtin_method_by_str(globals, "isatty", isatty);
     io_class.add_builtin_method_by_str(globals, "tty?", isatty);
-    io_class.add_builtin_method_by_str(globals, "flush", flush);
-    globals.set_toplevel_constant("IO", io_class);
-    let stdout = Value::ordinary_object(io_class);
+    io_class.add_builtin_method_by_str(globals, "inspect", inspect);
+    io_class.add_builtin_method_by_str(globals, "pid", pid);
+    io_class.add_builtin_method_by_str(globals, "close_write", close_write);
+    io_class.add_builtin_class_method(globals, "popen", popen);
+
+    let stdin = Value::io(io_class, IoInfo::new(Stream::Stdin, None, true, false));
+    let stdout = Value::io(io_class, IoInfo::new(Stream::Stdout, None, false, true));
+    let mut stderr = IoInfo::new(Stream::Stderr, None, false, true);
+    stderr.sync = true;
+    let stderr = Value::io(io_class, stderr);
+    globals.set_toplevel_constant("STDIN", stdin);
     globals.set_toplevel_constant("STDOUT", stdout);
-    globals.set_global_var_by_str("$>", stdout);
+    globals.set_toplevel_constant("STDERR", stderr);
+    globals.set_global_var_by_str("$stdin", stdin);
     globals.set_global_var_by_str("$stdout", stdout);
+    globals.set_global_var_by_str("$stderr", stderr);
 
     io_class.into()
 }
 
-use std::io::{self, Write};
+#[derive(Debug)]
+enum Stream {
+    Stdin,
+    Stdout,
+    Stderr,
+    File(File),
+    /// A child process started by IO.popen. Its output is read from the pipe.
+    Process(Child, Option<io::PipeReader>),
+    Closed,
+}
 
-fn output(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
-    args.check_args_num(1)?;
-    let arg0 = vm[0];
-    match arg0.as_string() {
-        Some(s) => print!("{}", s),
+#[derive(Debug)]
+pub struct IoInfo {
+    stream: Stream,
+    path: Option<String>,
+    readable: bool,
+    writable: bool,
+    /// Read buffer. Bytes in `rbuf[rpos..]` are not consumed yet.
+    rbuf: Vec<u8>,
+    rpos: usize,
+    /// Write buffer for files. Standard streams are buffered by Rust's std.
+    wbuf: Vec<u8>,
+    sync: bool,
+    lineno: i64,
+    fileno: i64,
+}
+
+impl Drop for IoInfo {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+impl IoInfo {
+    fn new(stream: Stream, path: Option<String>, readable: bool, writable: bool) -> Self {
+        let fileno = match &stream {
+            Stream::Stdin => 0,
+            Stream::Stdout => 1,
+            Stream::Stderr => 2,
+            #[cfg(unix)]
+            Stream::File(file) => {
+                use std::os::unix::io::AsRawFd;
+                file.as_raw_fd() as i64
+            }
+            #[cfg(unix)]
+            Stream::Process(child, reader) => {
+                use std::os::unix::io::AsRawFd;
+                match (reader, &child.stdin) {
+                    (Some(reader), _) => reader.as_raw_fd() as i64,
+                    (None, Some(stdin)) => stdin.as_raw_fd() as i64,
+                    _ => -1,
+                }
+            }
+            _ => -1,
+        };
+        IoInfo {
+            stream,
+            path,
+            readable,
+            writable,
+            rbuf: vec![],
+            rpos: 0,
+            wbuf: vec![],
+            sync: false,
+            lineno: 0,
+            fileno,
+        }
+    }
+
+    /// Open the file `path` with `mode` and `perm`.
+    pub(crate) fn open(path: &str, mode: Value, perm: Option<u32>) -> Result<Self, RubyError> {
+        let (mut options, readable, writable) = parse_mode(mode)?;
+        #[cfg(unix)]
+        if let Some(perm) = perm {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(perm);
+        }
+        #[cfg(not(unix))]
+        let _ = perm;
+        let file = options
+            .open(path)
+            .map_err(|err| io_error(err, Some(path)))?;
+        Ok(IoInfo::new(
+            Stream::File(file),
+            Some(path.to_string()),
+            readable,
+            writable,
+        ))
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        matches!(self.stream, Stream::Closed)
+    }
+
+    fn check_readable(&self) -> Result<(), RubyError> {
+        if self.is_closed() {
+            Err(RubyError::io("closed stream"))
+        } else if !self.readable {
+            Err(RubyError::io("not opened for reading"))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn check_writable(&self) -> Result<(), RubyError> {
+        if self.is_closed() {
+            Err(RubyError::io("closed stream"))
+        } else if !self.writable {
+            Err(RubyError::io("not opened for writing"))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn err(&self, err: io::Error) -> RubyError {
+        io_error(err, self.path.as_deref())
+    }
+
+    /// Fill the read buffer if all bytes in it were consumed.
+    /// Returns false on EOF.
+    fn fill_buf(&mut self) -> io::Result<bool> {
+        if self.rpos < self.rbuf.len() {
+            return Ok(true);
+        }
+        self.flush()?;
+        self.rbuf.resize(BUF_SIZE, 0);
+        let len = match &mut self.stream {
+            Stream::Stdin => {
+                io::stdout().flush()?;
+                io::stdin().read(&mut self.rbuf)
+            }
+            Stream::File(file) => file.read(&mut self.rbuf),
+            Stream::Process(_, Some(reader)) => reader.read(&mut self.rbuf),
+            _ => Ok(0),
+        };
+        let len = len.inspect_err(|_| self.rbuf.clear())?;
+        self.rbuf.truncate(len);
+        self.rpos = 0;
+        Ok(len != 0)
+    }
+
+    /// Discard the unread bytes in the read buffer, moving back the file position.
+    fn discard_rbuf(&mut self) -> io::Result<()> {
+        let unread = self.rbuf.len() - self.rpos;
+        if unread != 0 {
+            if let Stream::File(file) = &mut self.stream {
+                file.seek(SeekFrom::Current(-(unread as i64)))?;
+            }
+        }
+        self.rbuf.clear();
+        self.rpos = 0;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.stream {
+            Stream::Stdout => io::stdout().flush(),
+            Stream::Stderr => io::stderr().flush(),
+            Stream::File(file) => {
+                if !self.wbuf.is_empty() {
+                    file.write_all(&self.wbuf)?;
+                    self.wbuf.clear();
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        match self.stream {
+            Stream::Stdout => {
+                let mut stdout = io::stdout();
+                stdout.write_all(bytes)?;
+                if self.sync {
+                    stdout.flush()?;
+                }
+            }
+            Stream::Stderr => io::stderr().write_all(bytes)?,
+            Stream::Process(ref mut child, _) => match &mut child.stdin {
+                Some(stdin) => stdin.write_all(bytes)?,
+                None => return Err(io::ErrorKind::BrokenPipe.into()),
+            },
+            Stream::File(_) => {
+                self.discard_rbuf()?;
+                self.wbuf.extend_from_slice(bytes);
+                if self.sync || self.wbuf.len() >= BUF_SIZE {
+                    self.flush()?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Read bytes until `sep`, or until EOF if `sep` is None.
+    /// An empty `sep` means the paragraph mode.
+    fn read_line(&mut self, sep: Option<&[u8]>) -> io::Result<Option<Vec<u8>>> {
+        let sep = match sep {
+            None => {
+                let all = self.read_all()?;
+                return Ok(if all.is_empty() { None } else { Some(all) });
+            }
+            Some([]) => b"\n\n",
+            Some(sep) => sep,
+        };
+        let mut line = vec![];
+        while self.fill_buf()? {
+            let start = line.len().saturating_sub(sep.len() - 1);
+            line.extend_from_slice(&self.rbuf[self.rpos..]);
+            self.rpos = self.rbuf.len();
+            if let Some(i) = line[start..].windows(sep.len()).position(|w| w == sep) {
+                let end = start + i + sep.len();
+                self.rpos -= line.len() - end;
+                line.truncate(end);
+                break;
+            }
+        }
+        if line.is_empty() {
+            Ok(None)
+        } else {
+            self.lineno += 1;
+            Ok(Some(line))
+        }
+    }
+
+    fn read_all(&mut self) -> io::Result<Vec<u8>> {
+        let mut buf = self.rbuf[self.rpos..].to_vec();
+        self.rbuf.clear();
+        self.rpos = 0;
+        self.flush()?;
+        match &mut self.stream {
+            Stream::Stdin => io::stdin().read_to_end(&mut buf)?,
+            Stream::File(file) => file.read_to_end(&mut buf)?,
+            Stream::Process(_, Some(reader)) => reader.read_to_end(&mut buf)?,
+            _ => 0,
+        };
+        Ok(buf)
+    }
+
+    /// Read at most `len` bytes. Read until `len` bytes are available unless `partial` is true.
+    fn read_bytes(&mut self, len: usize, partial: bool) -> io::Result<Vec<u8>> {
+        let mut buf = vec![];
+        while buf.len() < len && self.fill_buf()? {
+            let end = std::cmp::min(self.rbuf.len(), self.rpos + len - buf.len());
+            buf.extend_from_slice(&self.rbuf[self.rpos..end]);
+            self.rpos = end;
+            if partial {
+                break;
+            }
+        }
+        Ok(buf)
+    }
+
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.flush()?;
+        self.discard_rbuf()?;
+        match &mut self.stream {
+            Stream::File(file) => file.seek(pos),
+            _ => Err(io::ErrorKind::NotSeekable.into()),
+        }
+    }
+
+    fn pos(&mut self) -> io::Result<u64> {
+        let unread = (self.rbuf.len() - self.rpos) as u64;
+        let unwritten = self.wbuf.len() as u64;
+        match &mut self.stream {
+            Stream::File(file) => Ok(file.stream_position()? - unread + unwritten),
+            _ => Err(io::ErrorKind::NotSeekable.into()),
+        }
+    }
+
+    /// Close the stream. Returns the pid and the exit status if a child process was waited.
+    fn close(&mut self) -> io::Result<Option<(u32, ExitStatus)>> {
+        let res = self.flush();
+        let stream = std::mem::replace(&mut self.stream, Stream::Closed);
+        self.rbuf.clear();
+        self.rpos = 0;
+        self.wbuf.clear();
+        res?;
+        match stream {
+            Stream::Process(mut child, reader) => {
+                drop(child.stdin.take());
+                drop(reader);
+                Ok(Some((child.id(), child.wait()?)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// The file descriptor number of a standard stream.
+    pub(crate) fn std_fileno(&self) -> Option<i64> {
+        match self.stream {
+            Stream::Stdin => Some(0),
+            Stream::Stdout => Some(1),
+            Stream::Stderr => Some(2),
+            _ => None,
+        }
+    }
+
+    /// Duplicate the file handle to pass it to a child process.
+    pub(crate) fn try_clone_file(&mut self) -> Result<File, RubyError> {
+        self.flush().map_err(|err| self.err(err))?;
+        match &self.stream {
+            Stream::File(file) => file.try_clone().map_err(|err| self.err(err)),
+            Stream::Closed => Err(RubyError::io("closed stream")),
+            _ => Err(RubyError::argument("wrong exec redirect")),
+        }
+    }
+
+    fn isatty(&self) -> bool {
+        match &self.stream {
+            Stream::Stdin => io::stdin().is_terminal(),
+            Stream::Stdout => io::stdout().is_terminal(),
+            Stream::Stderr => io::stderr().is_terminal(),
+            Stream::File(file) => file.is_terminal(),
+            Stream::Process(..) | Stream::Closed => false,
+        }
+    }
+}
+
+/// Parse the open mode given as a String (e.g. "r", "w+", "ab") or an Integer (File::RDONLY etc.).
+fn parse_mode(mode: Value) -> Result<(OpenOptions, bool, bool), RubyError> {
+    let mut options = OpenOptions::new();
+    if let Some(flags) = mode.as_fixnum() {
+        let (readable, writable) = match flags & 0b11 {
+            0 => (true, false),
+            1 => (false, true),
+            _ => (true, true),
+        };
+        options
+            .read(readable)
+            .write(writable)
+            .append(flags & APPEND != 0)
+            .truncate(flags & TRUNC != 0);
+        if flags & EXCL != 0 && flags & CREAT != 0 {
+            options.create_new(true);
+        } else {
+            options.create(flags & CREAT != 0);
+        }
+        return Ok((options, readable, writable));
+    }
+    let mode_str = match mode.as_string() {
+        Some(s) => s,
+        None if mode.is_nil() => "r",
+        None => return Err(VMError::wrong_type("mode", "String or Integer", mode)),
+    };
+    let invalid = || RubyError::argument(format!("invalid access mode {}", mode_str));
+    // The encoding part (e.g. "r:utf-8") is ignored.
+    let mut chars = mode_str.split(':').next().unwrap().chars();
+    let kind = chars.next().ok_or_else(invalid)?;
+    let mut plus = false;
+    let mut excl = false;
+    for c in chars {
+        match c {
+            '+' => plus = true,
+            'x' if kind == 'w' => excl = true,
+            'b' | 't' => {}
+            _ => return Err(invalid()),
+        }
+    }
+    let (readable, writable) = match kind {
+        'r' => {
+            options.read(true).write(plus);
+            (true, plus)
+        }
+        'w' => {
+            options.read(plus).write(true).truncate(true);
+            if excl {
+                options.create_new(true);
+            } else {
+                options.create(true);
+            }
+            (plus, true)
+        }
+        'a' => {
+            options.read(plus).append(true).create(true);
+            (plus, true)
+        }
+        _ => return Err(invalid()),
+    };
+    Ok((options, readable, writable))
+}
+
+pub(crate) const RDONLY: i64 = 0;
+pub(crate) const WRONLY: i64 = 1;
+pub(crate) const RDWR: i64 = 2;
+pub(crate) const CREAT: i64 = 0o100;
+pub(crate) const EXCL: i64 = 0o200;
+pub(crate) const TRUNC: i64 = 0o1000;
+pub(crate) const APPEND: i64 = 0o2000;
+
+/// Convert std::io::Error to Errno::EXXX or IOError.
+pub(crate) fn io_error(err: io::Error, path: Option<&str>) -> RubyError {
+    let (name, msg) = match err.kind() {
+        io::ErrorKind::NotFound => ("ENOENT", "No such file or directory"),
+        io::ErrorKind::PermissionDenied => ("EACCES", "Permission denied"),
+        io::ErrorKind::AlreadyExists => ("EEXIST", "File exists"),
+        io::ErrorKind::IsADirectory => ("EISDIR", "Is a directory"),
+        io::ErrorKind::NotADirectory => ("ENOTDIR", "Not a directory"),
+        io::ErrorKind::NotSeekable => ("ESPIPE", "Illegal seek"),
+        io::ErrorKind::BrokenPipe => ("EPIPE", "Broken pipe"),
+        io::ErrorKind::InvalidInput => ("EINVAL", "Invalid argument"),
+        #[cfg(unix)]
+        _ if err.raw_os_error() == Some(libc::ECHILD) => ("ECHILD", "No child processes"),
+        #[cfg(unix)]
+        _ if err.raw_os_error() == Some(libc::ESRCH) => ("ESRCH", "No such process"),
+        _ => return RubyError::io(err.to_string()),
+    };
+    match path {
+        Some(path) => RubyError::errno(name, format!("{} - {}", msg, path)),
+        None => RubyError::errno(name, msg),
+    }
+}
+
+/// `$>` is an alias of `$stdout`.
+pub(crate) fn global_var_alias(id: IdentId) -> IdentId {
+    if id == IdentId::get_id("$>") {
+        IdentId::get_id("$stdout")
+    } else {
+        id
+    }
+}
+
+/// `$stdout` and `$stderr` accept only objects which have `write` method.
+pub(crate) fn check_output_var(vm: &mut VM, id: IdentId, val: Value) -> Result<(), RubyError> {
+    if id != IdentId::get_id("$stdout") && id != IdentId::get_id("$stderr") {
+        return Ok(());
+    }
+    if vm
+        .globals
+        .find_method_from_receiver(val, IdentId::get_id("write"))
+        .is_none()
+    {
+        return Err(RubyError::typeerr(format!(
+            "{:?} must have write method, {} given",
+            id,
+            val.get_class_name()
+        )));
+    }
+    Ok(())
+}
+
+fn to_s_bytes(vm: &mut VM, val: Value) -> Result<Vec<u8>, RubyError> {
+    match val.as_bytes() {
+        Some(bytes) => Ok(bytes.to_vec()),
+        None => Ok(val.val_to_s(vm)?.as_bytes().to_vec()),
+    }
+}
+
+/// Build the output of `puts` for the arguments.
+pub(crate) fn puts_bytes(vm: &mut VM) -> Result<Vec<u8>, RubyError> {
+    fn flatten(vm: &mut VM, buf: &mut Vec<u8>, val: Value) -> Result<(), RubyError> {
+        match val.as_array() {
+            Some(ary) => {
+                for v in &**ary {
+                    flatten(vm, buf, *v)?;
+                }
+            }
+            None => {
+                let bytes = to_s_bytes(vm, val)?;
+                buf.extend_from_slice(&bytes);
+                if bytes.last() != Some(&b'\n') {
+                    buf.push(b'\n');
+                }
+            }
+        }
+        Ok(())
+    }
+    let args = vm.args().to_vec();
+    let mut buf = vec![];
+    for val in &args {
+        match val.as_array() {
+            Some(ary) if ary.is_empty() => buf.push(b'\n'),
+            _ => flatten(vm, &mut buf, *val)?,
+        }
+    }
+    if args.is_empty() {
+        buf.push(b'\n');
+    }
+    Ok(buf)
+}
+
+/// Build the output of `print` for the arguments.
+pub(crate) fn print_bytes(vm: &mut VM) -> Result<Vec<u8>, RubyError> {
+    let args = vm.args().to_vec();
+    let mut buf = vec![];
+    for val in &args {
+        buf.extend_from_slice(&to_s_bytes(vm, *val)?);
+    }
+    Ok(buf)
+}
+
+impl VM {
+    /// Write `bytes` to `$stdout`.
+    pub(crate) fn write_stdout(&mut self, bytes: &[u8]) -> Result<(), RubyError> {
+        let mut stdout = self
+            .get_global_var(IdentId::get_id("$stdout"))
+            .unwrap_or_default();
+        match stdout.as_io() {
+            Some(info) => {
+                info.check_writable()?;
+                info.write(bytes).map_err(|err| info.err(err))
+            }
+            None => {
+                let id = IdentId::get_id("write");
+                self.eval_send1(id, stdout, Value::bytes(bytes.to_vec()))?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Get the line separator and `chomp` option for gets, each_line and readlines.
+fn line_args(vm: &mut VM, args: &Args2) -> Result<(Option<Vec<u8>>, bool), RubyError> {
+    args.check_args_range(0, 1)?;
+    let sep = if args.len() == 0 {
+        vm.get_global_var(IdentId::get_id("$/")).unwrap_or_default()
+    } else {
+        vm[0]
+    };
+    let sep = if sep.is_nil() {
+        None
+    } else {
+        match sep.as_bytes() {
+            Some(bytes) => Some(bytes.to_vec()),
+            None => return Err(VMError::wrong_type("1st arg", "String", sep)),
+        }
+    };
+    let chomp = match args.kw_arg.as_hash() {
+        Some(hash) => hash
+            .get(&Value::symbol_from_str("chomp"))
+            .is_some_and(|v| v.to_bool()),
+        None => false,
+    };
+    Ok((sep, chomp))
+}
+
+/// Read a line from `io`, letting other threads run if `io` was the standard input.
+fn read_line(
+    vm: &mut VM,
+    mut io: Value,
+    sep: &Option<Vec<u8>>,
+    chomp: bool,
+) -> Result<Option<Value>, RubyError> {
+    let info = io.expect_io("Receiver")?;
+    info.check_readable()?;
+    if matches!(info.stream, Stream::Stdin) {
+        vm.thread_before_io()?;
+    }
+    let info = io.expect_io("Receiver")?;
+    let line = info
+        .read_line(sep.as_deref())
+        .map_err(|err| info.err(err))?;
+    Ok(line.map(|mut line| {
+        if let Some(sep) = sep {
+            let sep: &[u8] = if sep.is_empty() { b"\n\n" } else { sep };
+            if chomp && line.ends_with(sep) {
+                line.truncate(line.len() - sep.len());
+            }
+        }
+        Value::bytes(line)
+    }))
+}
+
+// Instance methods
+
+/// IO#gets(rs = $/, chomp: false) -> String | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/IO/i/gets.html
+fn gets(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    let (sep, chomp) = line_args(vm, args)?;
+    Ok(read_line(vm, self_val, &sep, chomp)?.unwrap_or_default())
+}
+
+/// IO#readline(rs = $/, chomp: false) -> String
+fn readline(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    let (sep, chomp) = line_args(vm, args)?;
+    read_line(vm, self_val, &sep, chomp)?.ok_or_else(|| RubyError::eof("end of file reached"))
+}
+
+/// IO#readlines(rs = $/, chomp: false) -> [String]
+fn readlines(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    let (sep, chomp) = line_args(vm, args)?;
+    let mut lines = vec![];
+    while let Some(line) = read_line(vm, self_val, &sep, chomp)? {
+        lines.push(line);
+    }
+    Ok(Value::array_from(lines))
+}
+
+/// IO#each_line(rs = $/, chomp: false) {|line| ... } -> self
+/// IO#each_line(rs = $/, chomp: false) -> Enumerator
+///
+/// https://docs.ruby-lang.org/ja/latest/method/IO/i/each_line.html
+fn each_line(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    let (sep, chomp) = line_args(vm, args)?;
+    let block = match &args.block {
         None => {
-            let s = arg0.val_to_s(vm)?;
-            print!("{}", s)
+            let id = IdentId::get_id("each_line");
+            return vm.create_enumerator(id, self_val, args.into(vm));
         }
+        Some(block) => block,
     };
-    io::stdout().flush().unwrap();
+    while let Some(line) = read_line(vm, self_val, &sep, chomp)? {
+        vm.eval_block1(block, line)?;
+    }
     Ok(self_val)
 }
 
-fn isatty(_: &mut VM, _: Value, args: &Args2) -> VMResult {
-    args.check_args_num(0)?;
-    Ok(Value::true_val())
+/// IO#read(length = nil) -> String | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/IO/i/read.html
+fn read(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
+    let len = if args.len() == 0 || vm[0].is_nil() {
+        None
+    } else {
+        let len = vm[0].coerce_to_fixnum("length")?;
+        if len < 0 {
+            return Err(RubyError::argument(format!(
+                "negative length {} given",
+                len
+            )));
+        }
+        Some(len as usize)
+    };
+    let info = self_val.expect_io("Receiver")?;
+    info.check_readable()?;
+    match len {
+        None => {
+            let buf = info.read_all().map_err(|err| info.err(err))?;
+            Ok(Value::bytes(buf))
+        }
+        Some(len) => {
+            let buf = info.read_bytes(len, false).map_err(|err| info.err(err))?;
+            if buf.is_empty() && len != 0 {
+                Ok(Value::nil())
+            } else {
+                Ok(Value::bytes(buf))
+            }
+        }
+    }
+}
+
+/// IO#readpartial(maxlen) -> String
+///
+/// https://docs.ruby-lang.org/ja/latest/method/IO/i/readpartial.html
+fn readpartial(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let len = vm[0].coerce_to_fixnum("maxlen")?;
+    if len < 0 {
+        return Err(RubyError::argument(format!(
+            "negative length {} given",
+            len
+        )));
+    }
+    let info = self_val.expect_io("Receiver")?;
+    info.check_readable()?;
+    let buf = info
+        .read_bytes(len as usize, true)
+        .map_err(|err| info.err(err))?;
+    if buf.is_empty() && len != 0 {
+        return Err(RubyError::eof("end of file reached"));
+    }
+    Ok(Value::bytes(buf))
+}
+
+fn write_bytes(mut io: Value, bytes: &[u8]) -> Result<(), RubyError> {
+    let info = io.expect_io("Receiver")?;
+    info.check_writable()?;
+    info.write(bytes).map_err(|err| info.err(err))
+}
+
+/// IO#write(*str) -> Integer
+fn write(vm: &mut VM, self_val: Value, _: &Args2) -> VMResult {
+    let buf = print_bytes(vm)?;
+    write_bytes(self_val, &buf)?;
+    Ok(Value::integer(buf.len() as i64))
+}
+
+/// IO#print(*arg) -> nil
+fn print(vm: &mut VM, self_val: Value, _: &Args2) -> VMResult {
+    let buf = print_bytes(vm)?;
+    write_bytes(self_val, &buf)?;
+    Ok(Value::nil())
+}
+
+/// IO#puts(*obj) -> nil
+fn puts(vm: &mut VM, self_val: Value, _: &Args2) -> VMResult {
+    let buf = puts_bytes(vm)?;
+    write_bytes(self_val, &buf)?;
+    Ok(Value::nil())
+}
+
+/// IO#<<(object) -> self
+fn output(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let buf = to_s_bytes(vm, vm[0])?;
+    write_bytes(self_val, &buf)?;
+    Ok(self_val)
 }
 
-fn flush(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+/// IO#flush -> self
+fn flush(_: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(0)?;
-    io::stdout().flush().unwrap();
+    let info = self_val.expect_io("Receiver")?;
+    info.check_writable()?;
+    info.flush().map_err(|err| info.err(err))?;
     Ok(self_val)
 }
+
+/// IO#sync -> bool
+fn sync(_: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let info = self_val.expect_io("Receiver")?;
+    if info.is_closed() {
+        return Err(RubyError::io("closed stream"));
+    }
+    Ok(Value::bool(info.sync))
+}
+
+/// IO#sync=(newstate)
+fn set_sync(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let sync = vm[0].to_bool();
+    let info = self_val.expect_io("Receiver")?;
+    if info.is_closed() {
+        return Err(RubyError::io("closed stream"));
+    }
+    info.sync = sync;
+    if sync {
+        info.flush().map_err(|err| info.err(err))?;
+    }
+    Ok(vm[0])
+}
+
+/// IO#seek(offset, whence = IO::SEEK_SET) -> 0
+///
+/// https://docs.ruby-lang.org/ja/latest/method/IO/i/seek.html
+fn seek(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(1, 2)?;
+    let offset = vm[0].coerce_to_fixnum("offset")?;
+    let whence = if args.len() == 2 {
+        vm[1]
+    } else {
+        Value::integer(0)
+    };
+    let pos = match (whence.as_fixnum(), whence.as_symbol()) {
+        (Some(0), _) => SeekFrom::Start(offset as u64),
+        (Some(1), _) => SeekFrom::Current(offset),
+        (Some(2), _) => SeekFrom::End(offset),
+        (_, Some(id)) if id == IdentId::get_id("SET") => SeekFrom::Start(offset as u64),
+        (_, Some(id)) if id == IdentId::get_id("CUR") => SeekFrom::Current(offset),
+        (_, Some(id)) if id == IdentId::get_id("END") => SeekFrom::End(offset),
+        _ => return Err(RubyError::argument(format!("unknown whence: {:?}", whence))),
+    };
+    if matches!(pos, SeekFrom::Start(_)) && offset < 0 {
+        return Err(RubyError::errno("EINVAL", "Invalid argument"));
+    }
+    let info = self_val.expect_io("Receiver")?;
+    if info.is_closed() {
+        return Err(RubyError::io("closed stream"));
+    }
+    info.seek(pos).map_err(|err| info.err(err))?;
+    Ok(Value::integer(0))
+}
+
+/// IO#pos -> Integer
+fn pos(_: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let info = self_val.expect_io("Receiver")?;
+    if info.is_closed() {
+        return Err(RubyError::io("closed stream"));
+    }
+    let pos = info.pos().map_err(|err| info.err(err))?;
+    Ok(Value::integer(pos as i64))
+}
+
+/// IO#pos=(n)
+fn set_pos(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let pos = vm[0].coerce_to_fixnum("pos")?;
+    if pos < 0 {
+        return Err(RubyError::errno("EINVAL", "Invalid argument"));
+    }
+    let info = self_val.expect_io("Receiver")?;
+    if info.is_closed() {
+        return Err(RubyError::io("closed stream"));
+    }
+    info.seek(SeekFrom::Start(pos as u64))
+        .map_err(|err| info.err(err))?;
+    Ok(vm[0])
+}
+
+/// IO#rewind -> 0
+fn rewind(_: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let info = self_val.expect_io("Receiver")?;
+    if info.is_closed() {
+        return Err(RubyError::io("closed stream"));
+    }
+    info.seek(SeekFrom::Start(0)).map_err(|err| info.err(err))?;
+    info.lineno = 0;
+    Ok(Value::integer(0))
+}
+
+/// IO#eof? -> bool
+fn eof(_: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let info = self_val.expect_io("Receiver")?;
+    info.check_readable()?;
+    let eof = !info.fill_buf().map_err(|err| info.err(err))?;
+    Ok(Value::bool(eof))
+}
+
+/// IO#lineno -> Integer
+fn lineno(_: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let info = self_val.expect_io("Receiver")?;
+    info.check_readable()?;
+    Ok(Value::integer(info.lineno))
+}
+
+/// Close `io`. Closing a closed IO is not an error.
+/// `$?` is set if `io` was opened by IO.popen.
+pub(crate) fn close_io(vm: &mut VM, mut io: Value) -> Result<(), RubyError> {
+    let info = io.expect_io("Receiver")?;
+    if let Some((pid, status)) = info.close().map_err(|err| info.err(err))? {
+        process::set_last_status(vm, pid, status);
+    }
+    Ok(())
+}
+
+/// IO#close -> nil
+fn close(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    close_io(vm, self_val)?;
+    Ok(Value::nil())
+}
+
+/// IO#close_write -> nil
+///
+/// Close the standard input of the child process opened by IO.popen.
+fn close_write(_: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let info = self_val.expect_io("Receiver")?;
+    match &mut info.stream {
+        Stream::Process(child, _) => {
+            drop(child.stdin.take());
+            info.writable = false;
+        }
+        Stream::Closed => return Err(RubyError::io("closed stream")),
+        _ if info.readable => info.writable = false,
+        _ => {
+            info.close().map_err(|err| info.err(err))?;
+        }
+    }
+    Ok(Value::nil())
+}
+
+/// IO#pid -> Integer | nil
+fn pid(_: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let info = self_val.expect_io("Receiver")?;
+    Ok(match &info.stream {
+        Stream::Process(child, _) => Value::integer(child.id() as i64),
+        _ => Value::nil(),
+    })
+}
+
+/// IO.popen([env,] command, mode = "r", opt = {}) -> IO
+/// IO.popen([env,] command, mode = "r", opt = {}) {|io| ... } -> object
+///
+/// `command` is a command line String or an Array of the program and its arguments.
+/// The IO is closed and `$?` is set when the block exits.
+///
+/// https://docs.ruby-lang.org/ja/latest/method/IO/s/popen.html
+fn popen(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(1, 3)?;
+    let mut cmd_args = vec![];
+    let mut rest = vm.args();
+    if rest[0].as_hash().is_some() {
+        cmd_args.push(rest[0]);
+        rest = &rest[1..];
+    }
+    let (cmd, mode) = match rest {
+        [cmd] => (*cmd, Value::nil()),
+        [cmd, mode] => (*cmd, *mode),
+        _ => return Err(RubyError::argument_wrong(args.len(), 2)),
+    };
+    match cmd.as_array() {
+        Some(ary) => cmd_args.extend(ary.iter()),
+        None => cmd_args.push(cmd),
+    }
+    if cmd_args.last().and_then(|v| v.as_string()) == Some("-") && cmd_args.len() == 1 {
+        return Err(RubyError::runtime(
+            "fork() function is unimplemented on this machine",
+        ));
+    }
+    let mode_str = match mode.as_string() {
+        Some(s) => s.to_string(),
+        None if mode.is_nil() => "r".to_string(),
+        None => return Err(VMError::wrong_type("mode", "String", mode)),
+    };
+    let (readable, writable) = match mode_str
+        .split(':')
+        .next()
+        .unwrap()
+        .trim_end_matches(['b', 't'])
+    {
+        "r" => (true, false),
+        "w" => (false, true),
+        "r+" | "w+" => (true, true),
+        _ => {
+            return Err(RubyError::argument(format!(
+                "invalid access mode {}",
+                mode_str
+            )))
+        }
+    };
+    let mut spawn = process::Spawn::from_args(vm, &cmd_args, args.kw_arg, &[])?;
+    let reader = if readable {
+        Some(spawn.pipe_stdout()?)
+    } else {
+        None
+    };
+    if writable {
+        spawn.command.stdin(Stdio::piped());
+    }
+    let child = spawn.spawn()?;
+    let info = IoInfo::new(Stream::Process(child, reader), None, readable, writable);
+    let io = Value::io(Module::new(self_val), info);
+    match &args.block {
+        None => Ok(io),
+        Some(block) => {
+            let res = vm.eval_block1(block, io);
+            close_io(vm, io)?;
+            res
+        }
+    }
+}
+
+/// IO#closed? -> bool
+fn closed(_: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let info = self_val.expect_io("Receiver")?;
+    Ok(Value::bool(info.is_closed()))
+}
+
+/// IO#fileno -> Integer
+fn fileno(_: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let info = self_val.expect_io("Receiver")?;
+    if info.is_closed() {
+        return Err(RubyError::io("closed stream"));
+    }
+    if info.fileno < 0 {
+        return Ok(Value::nil());
+    }
+    Ok(Value::integer(info.fileno))
+}
+
+/// IO#isatty -> bool
+fn isatty(_: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let info = self_val.expect_io("Receiver")?;
+    if info.is_closed() {
+        return Err(RubyError::io("closed stream"));
+    }
+    Ok(Value::bool(info.isatty()))
+}
+
+/// File#path -> String
+pub(crate) fn path(_: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let info = self_val.expect_io("Receiver")?;
+    Ok(match &info.path {
+        Some(path) => Value::string(path),
+        None => Value::nil(),
+    })
+}
+
+fn inspect(_: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let class_name = self_val.get_class_name();
+    let info = self_val.expect_io("Receiver")?;
+    let name = match (&info.path, &info.stream) {
+        (Some(path), _) => path.to_string(),
+        (None, Stream::Stdin) => "<STDIN>".to_string(),
+        (None, Stream::Stdout) => "<STDOUT>".to_string(),
+        (None, Stream::Stderr) => "<STDERR>".to_string(),
+        (None, _) => format!("fd {}", info.fileno),
+    };
+    let closed = if info.is_closed() { " (closed)" } else { "" };
+    Ok(Value::string(format!(
+        "#<{}:{}{}>",
+        class_name, name, closed
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::*;
+
+    #[test]
+    fn io() {
+        let program = r##"
+        assert IO, STDOUT.class
+        assert STDOUT, $stdout
+        assert STDOUT, $>
+        assert STDERR, $stderr
+        assert STDIN, $stdin
+        assert 0, STDIN.fileno
+        assert 1, STDOUT.fileno
+        assert 2, STDERR.fileno
+        assert "#<IO:<STDOUT>>", STDOUT.inspect
+        assert true, STDERR.sync
+        assert STDOUT, STDOUT << "hello\n"
+        assert 6, STDOUT.write("world", "\n")
+        assert STDOUT, $stdout.flush
+        assert_error { STDOUT.gets }
+        assert_error { STDIN.puts "x" }
+        assert_error { $stdout = 1 }
+
+        class Buf
+          attr_reader :buf
+          def initialize; @buf = ""; end
+          def write(s); @buf += s; s.size; end
+        end
+        out = Buf.new
+        $stdout = out
+        puts "a", ["b", ["c"]], nil
+        print "d", 1
+        p :e
+        $stdout = STDOUT
+        assert "a\nb\nc\n\nd1:e\n", out.buf
+        assert STDOUT, $>
+        "##;
+        assert_script(program);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn io_popen() {
+        let program = r##"
+        assert "hello\n", IO.popen("echo hello") { |io| io.read }
+        assert 0, $?.exitstatus
+        assert ["a\n", "b b\n"], IO.popen(["printf", "a\\nb b\\n"]).readlines
+        io = IO.popen("cat", "r+")
+        pid = io.pid
+        assert Integer, pid.class
+        io.write "abc"
+        io.close_write
+        assert "abc", io.read
+        io.close
+        assert true, io.closed?
+        assert pid, $?.pid
+        assert "err\n", IO.popen("echo err >&2", err: [:child, :out]) { |io| io.read }
+        IO.popen("exit 3") { |io| io.read }
+        assert 3, $?.exitstatus
+        IO.popen("cat > /dev/null", "w") { |io| io.puts "x" }
+        assert true, $?.success?
+        assert_error { IO.popen("true", "q") }
+        assert_error { IO.popen("no_such_command_xyz") }
+        "##;
+        assert_script(program);
+    }
+}
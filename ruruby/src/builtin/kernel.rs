@@ -9,6 +9,9 @@ pub(crate) fn init(globals: &mut Globals) -> Module {
     class.add_builtin_module_func(globals, "gets", gets);
     class.add_builtin_module_func(globals, "p", p);
     class.add_builtin_module_func(globals, "print", print);
+    class.add_builtin_module_func(globals, "printf", printf);
+    class.add_builtin_module_func(globals, "format", sprintf);
+    class.add_builtin_module_func(globals, "sprintf", sprintf);
     class.add_builtin_module_func(globals, "assert", assert);
     class.add_builtin_module_func(globals, "assert_error", assert_error);
     class.add_builtin_module_func(globals, "require", require);
@@ -19,8 +22,12 @@ pub(crate) fn init(globals: &mut Globals) -> Module {
     class.add_builtin_module_func(globals, "kind_of?", isa);
     class.add_builtin_module_func(globals, "__dir__", dir);
     class.add_builtin_module_func(globals, "raise", raise);
+    class.add_builtin_module_func(globals, "caller", caller);
+    class.add_builtin_module_func(globals, "caller_locations", caller_locations);
     class.add_builtin_module_func(globals, "rand", rand_);
     class.add_builtin_module_func(globals, "loop", loop_);
+    class.add_builtin_module_func(globals, "catch", catch);
+    class.add_builtin_module_func(globals, "throw", throw);
     class.add_builtin_module_func(globals, "exit", exit);
     class.add_builtin_module_func(globals, "abort", abort);
     class.add_builtin_module_func(globals, "sleep", sleep);
@@ -28,48 +35,40 @@ pub(crate) fn init(globals: &mut Globals) -> Module {
     class.add_builtin_module_func(globals, "lambda", lambda);
     class.add_builtin_module_func(globals, "Integer", kernel_integer);
     class.add_builtin_module_func(globals, "Complex", kernel_complex);
+    class.add_builtin_module_func(globals, "Rational", kernel_rational);
     class.add_builtin_module_func(globals, "Array", kernel_array);
     class.add_builtin_module_func(globals, "at_exit", at_exit);
     class.add_builtin_module_func(globals, "`", command);
+    class.add_builtin_module_func(globals, "system", process::system);
+    class.add_builtin_module_func(globals, "spawn", process::spawn);
+    class.add_builtin_module_func(globals, "exec", process::exec);
     class.add_builtin_module_func(globals, "eval", eval);
     class.add_builtin_module_func(globals, "binding", binding);
     class
 }
 /// Built-in function "puts".
-fn puts(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
-    fn flatten(vm: &mut VM, val: Value) -> Result<(), RubyError> {
-        match val.as_array() {
-            None => println!("{}", val.val_to_s(vm)?),
-            Some(aref) => {
-                for val in &**aref {
-                    flatten(vm, val.clone())?;
-                }
-            }
-        }
-        Ok(())
-    }
-    if args.len() == 0 {
-        println!();
-    }
-    for i in 0..vm.args().len() {
-        flatten(vm, vm[i])?;
-    }
+fn puts(vm: &mut VM, _: Value, _: &Args2) -> VMResult {
+    let buf = io::puts_bytes(vm)?;
+    vm.write_stdout(&buf)?;
     Ok(Value::nil())
 }
 
-fn gets(_: &mut VM, _: Value, args: &Args2) -> VMResult {
+/// Built-in function "gets". Read a line from `$stdin`.
+fn gets(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
     args.check_args_num(0)?;
-    let mut buf = String::new();
-    std::io::stdin()
-        .read_line(&mut buf)
-        .or_else(|err| Err(RubyError::runtime(err.to_string())))?;
-    Ok(Value::string(buf))
+    let stdin = vm
+        .get_global_var(IdentId::get_id("$stdin"))
+        .unwrap_or_default();
+    vm.eval_send0(IdentId::get_id("gets"), stdin)
 }
 
 fn p(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    let mut buf = String::new();
     for i in 0..args.len() {
-        println!("{}", vm.val_inspect(vm[i])?);
+        buf += &vm.val_inspect(vm[i])?;
+        buf.push('\n');
     }
+    vm.write_stdout(buf.as_bytes())?;
     match args.len() {
         0 => Ok(Value::nil()),
         1 => Ok(vm[0]),
@@ -78,20 +77,38 @@ fn p(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
 }
 
 /// Built-in function "print".
-fn print(vm: &mut VM, _: Value, _args: &Args2) -> VMResult {
-    for i in 0..vm.args().len() {
-        let arg = vm[i];
-        match arg.as_bytes() {
-            Some(bytes) => {
-                use std::io::{self, Write};
-                io::stdout().write(bytes).unwrap();
-            }
-            None => print!("{}", arg.val_to_s(vm)?),
-        }
+fn print(vm: &mut VM, _: Value, _: &Args2) -> VMResult {
+    let buf = io::print_bytes(vm)?;
+    vm.write_stdout(&buf)?;
+    Ok(Value::nil())
+}
+
+/// Built-in function "printf".
+fn printf(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    if args.len() == 0 {
+        return Ok(Value::nil());
     }
+    let res = sprintf(vm, Value::nil(), args)?;
+    vm.write_stdout(res.as_rstring().unwrap().as_bytes())?;
     Ok(Value::nil())
 }
 
+/// Built-in function "format" and "sprintf".
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Kernel/m/format.html
+fn sprintf(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_min(1)?;
+    let mut fmt = vm[0];
+    let fmt = fmt.expect_string("format string")?.to_string();
+    let mut arguments = vm.args()[1..].to_vec();
+    // format("%{x}", x: 1)
+    if args.kw_arg.as_hash().is_some() {
+        arguments.push(args.kw_arg);
+    }
+    let res = format::sprintf(vm, &fmt, &arguments)?;
+    Ok(Value::string(res))
+}
+
 /// Built-in function "assert".
 fn assert(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
     args.check_args_num(2)?;
@@ -212,30 +229,123 @@ fn dir(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
 /// raise(error_type, message = nil, backtrace = caller(0), cause: $!) -> ()
 /// fail(error_type, message = nil, backtrace = caller(0), cause: $!) -> ()
 fn raise(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 3)?;
+    let cause = match args.kw_arg.as_hash() {
+        Some(hash) => hash.get(&Value::symbol_from_str("cause")).cloned(),
+        None => None,
+    };
+    let len = args.len();
+    if len == 0 {
+        let errinfo = vm.get_global_var(IdentId::get_id("$!")).unwrap_or_default();
+        if errinfo.if_exception().is_some() {
+            vm.globals.val = errinfo;
+            return Err(RubyError::value());
+        }
+        return Err(RubyError::runtime("unhandled exception"));
+    }
+    let arg0 = vm[0];
+    let ex = if let Some(s) = arg0.as_string() {
+        if len > 1 {
+            return Err(RubyError::typeerr("exception class/object expected"));
+        }
+        match cause {
+            None => return Err(RubyError::none(s)),
+            Some(_) => vm.globals.from_exception(&RubyError::none(s)).unwrap(),
+        }
+    } else if arg0.is_class() {
+        if !arg0.is_exception_class() {
+            return Err(RubyError::typeerr("exception class/object expected"));
+        }
+        let args = if len > 1 {
+            Args::new1(vm[1])
+        } else {
+            Args::new0()
+        };
+        vm.eval_send(IdentId::NEW, arg0, &args)?
+    } else if arg0.if_exception().is_some() {
+        if len > 1 {
+            let class = vm.globals.get_class(arg0);
+            vm.eval_send1(IdentId::NEW, class.into(), vm[1])?
+        } else {
+            arg0
+        }
+    } else {
+        return Err(RubyError::typeerr("exception class/object expected"));
+    };
+    if ex.if_exception().is_none() {
+        return Err(RubyError::typeerr("exception object expected"));
+    }
+    if len == 3 {
+        let backtrace = vm[2];
+        vm.eval_send1(IdentId::get_id("set_backtrace"), ex, backtrace)?;
+    }
+    if let Some(cause) = cause {
+        if !cause.is_nil() && cause.if_exception().is_none() {
+            return Err(RubyError::typeerr("exception object expected"));
+        }
+        ex.set_var_by_str("/cause", cause);
+    }
+    vm.globals.val = ex;
+    Err(RubyError::value())
+}
+
+/// caller(start = 1, length = nil) -> [String] | nil
+/// https://docs.ruby-lang.org/ja/latest/method/Kernel/m/caller.html
+fn caller(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    let locs = match caller_backtrace(vm, args)? {
+        Some(locs) => locs,
+        None => return Ok(Value::nil()),
+    };
+    let ary = locs
+        .iter()
+        .map(|loc| Value::string(loc.to_string()))
+        .collect();
+    Ok(Value::array_from(ary))
+}
+
+/// caller_locations(start = 1, length = nil) -> [Thread::Backtrace::Location] | nil
+/// https://docs.ruby-lang.org/ja/latest/method/Kernel/m/caller_locations.html
+fn caller_locations(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    let locs = match caller_backtrace(vm, args)? {
+        Some(locs) => locs,
+        None => return Ok(Value::nil()),
+    };
+    let ary = locs
+        .iter()
+        .map(|loc| thread::backtrace_location(&vm.globals, loc))
+        .collect();
+    Ok(Value::array_from(ary))
+}
+
+fn caller_backtrace(vm: &mut VM, args: &Args2) -> Result<Option<Vec<BacktraceLoc>>, RubyError> {
     args.check_args_range(0, 2)?;
-    match args.len() {
-        0 => Err(RubyError::none("")),
-        1 => {
-            let arg0 = vm[0];
-            if let Some(s) = arg0.as_string() {
-                Err(RubyError::none(s))
-            } else if arg0.is_class() {
-                if arg0.is_exception_class() {
-                    let method = arg0.get_method_or_nomethod(&mut vm.globals, IdentId::NEW)?;
-                    vm.globals.val = vm.eval_method0(method, arg0)?;
-                    Err(RubyError::value())
-                } else {
-                    Err(RubyError::typeerr("Exception class/object expected."))
-                }
-            } else if arg0.if_exception().is_some() {
-                vm.globals.val = arg0;
-                Err(RubyError::value())
-            } else {
-                Err(RubyError::typeerr("Exception class/object expected."))
-            }
+    let start = if args.len() > 0 {
+        vm[0].coerce_to_fixnum("1st arg")?
+    } else {
+        1
+    };
+    if start < 0 {
+        return Err(RubyError::argument(format!("negative level ({})", start)));
+    }
+    let length = if args.len() > 1 && !vm[1].is_nil() {
+        let length = vm[1].coerce_to_fixnum("2nd arg")?;
+        if length < 0 {
+            return Err(RubyError::argument(format!("negative size ({})", length)));
         }
-        _ => Err(RubyError::none(vm[1].clone().expect_string("2nd arg")?)),
+        Some(length as usize)
+    } else {
+        None
+    };
+    let backtrace = vm.backtrace();
+    let start = start as usize;
+    if start > backtrace.len() {
+        return Ok(None);
     }
+    let end = match length {
+        Some(length) => backtrace.len().min(start + length),
+        None => backtrace.len(),
+    };
+    Ok(Some(backtrace[start..end].to_vec()))
 }
 
 /// rand(max = 0) -> Integer | Float
@@ -247,6 +357,82 @@ fn rand_(_: &mut VM, _: Value, args: &Args2) -> VMResult {
     Ok(Value::float(num))
 }
 
+/// catch(tag = Object.new) {|tag| ... } -> object
+fn catch(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
+    let block = args.expect_block()?;
+    let tag = if args.len() == 0 {
+        Value::ordinary_object(BuiltinClass::object())
+    } else {
+        vm[0]
+    };
+    vm.catch_tags.push(tag);
+    let res = vm.eval_block1(block, tag);
+    vm.catch_tags.pop();
+    match res {
+        Err(err) => match thrown_value(vm, &err, tag) {
+            Some(val) => Ok(val),
+            None => Err(err),
+        },
+        res => res,
+    }
+}
+
+/// throw(tag, obj = nil) -> ()
+fn throw(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_range(1, 2)?;
+    let tag = vm[0];
+    let val = if args.len() == 1 { Value::nil() } else { vm[1] };
+    if vm.catch_tags.iter().any(|t| t.id() == tag.id()) {
+        Err(throw_to(vm, tag, val))
+    } else {
+        let err = RubyError::none(format!("uncaught throw {}", vm.val_inspect(tag)?));
+        set_throw_exception(vm, tag, val, err);
+        Err(RubyError::value())
+    }
+}
+
+/// Set an exception object which carries `tag` and `val` of `throw` to vm.globals.val.
+fn set_throw_exception(vm: &mut VM, tag: Value, val: Value, err: RubyError) {
+    let class = vm
+        .globals
+        .get_toplevel_constant("UncaughtThrowError")
+        .into_module();
+    let ex = Value::exception(class, err);
+    ex.set_var_by_str("@tag", tag);
+    ex.set_var_by_str("@value", val);
+    vm.globals.val = ex;
+}
+
+/// Throw `val` to `tag`, which the caller must catch by `thrown_value`.
+///
+/// `throw` can not be rescued, and `ensure` clauses are executed on the way.
+pub(crate) fn throw_to(vm: &mut VM, tag: Value, val: Value) -> RubyError {
+    set_throw_exception(vm, tag, val, RubyError::throw());
+    RubyError::throw()
+}
+
+/// Get the value if `err` is `throw` to `tag`.
+pub(crate) fn thrown_value(vm: &VM, err: &RubyError, tag: Value) -> Option<Value> {
+    if !err.is_throw() {
+        return None;
+    }
+    let ex = vm.globals.val;
+    if ex.get_var(IdentId::get_id("@tag")) == Some(tag) {
+        Some(ex.get_var(IdentId::get_id("@value")).unwrap_or_default())
+    } else {
+        None
+    }
+}
+
+/// Check whether `val` is an exception object which carries `throw` to an active `catch`.
+pub(crate) fn is_pending_throw(val: Value) -> bool {
+    match val.if_exception() {
+        Some(err) => err.is_throw(),
+        None => false,
+    }
+}
+
 fn loop_(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
     let block = args.expect_block()?;
     loop {
@@ -290,7 +476,7 @@ fn abort(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
 fn sleep(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
     args.check_args_range(0, 1)?;
     let secs = if args.len() == 0 {
-        0.0
+        None
     } else {
         let secs = match vm[0].unpack() {
             RV::Integer(i) => i as f64,
@@ -300,10 +486,10 @@ fn sleep(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
         if secs < 0.0 {
             return Err(RubyError::argument("Negative number."));
         }
-        secs
+        Some(secs)
     };
     let start = std::time::Instant::now();
-    std::thread::sleep(std::time::Duration::from_secs_f64(secs));
+    vm.thread_sleep(secs, false)?;
     let duration = start.elapsed().as_secs() as u64 as i64;
     Ok(Value::integer(duration))
 }
@@ -368,6 +554,20 @@ fn kernel_complex(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
     Ok(Value::complex(r, i))
 }
 
+/// Rational(x, y = 1) -> Rational
+fn kernel_rational(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_range(1, 2)?;
+    let numer = rational::to_rational(vm, vm[0])?;
+    if args.len() == 1 {
+        return Ok(Value::rational(numer));
+    }
+    let denom = rational::to_rational(vm, vm[1])?;
+    if num::Zero::is_zero(&denom) {
+        return Err(RubyError::zero_div("divided by 0"));
+    }
+    Ok(Value::rational(numer / denom))
+}
+
 /// Array(arg) -> Array
 fn kernel_array(vm: &mut VM, _self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(1)?;
@@ -397,34 +597,25 @@ fn at_exit(_vm: &mut VM, _self_val: Value, _args: &Args2) -> VMResult {
     Ok(_self_val)
 }
 
+/// `command` -> String
+///
+/// Run the command and return its standard output. `$?` is set to the exit status.
 fn command(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
-    use std::process::Command;
+    use std::process::Stdio;
     args.check_args_num(1)?;
     let mut arg = vm[0];
-    let opt = if cfg!(windows) { "/C" } else { "-c" };
     let input = arg.expect_string("Arg")?;
-    let command = if cfg!(windows) { "cmd" } else { "sh" };
-    let output = match Command::new(command).args(&[opt, input]).output() {
-        Ok(ok) => ok,
-        Err(err) => {
-            return Err(RubyError::runtime(format!(
-                "Command failed. {} {:?}",
-                command,
-                err.kind()
-            )))
-        }
-    };
-    let status = output.status;
-    if status.success() {
-        Ok(Value::bytes(output.stdout))
-    } else {
-        let err = format!(
-            "{} exit status:{:?}",
-            String::from_utf8_lossy(&output.stderr),
-            status.code()
-        );
-        Err(RubyError::runtime(err))
-    }
+    let mut command = process::shell_command(input);
+    let child = command
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|err| io::io_error(err, Some(input)))?;
+    let pid = child.id();
+    let output = child
+        .wait_with_output()
+        .map_err(|err| io::io_error(err, None))?;
+    process::set_last_status(vm, pid, output.status);
+    Ok(Value::bytes(output.stdout))
 }
 
 fn eval(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
@@ -631,6 +822,75 @@ mod test {
         assert_script(program);
     }
 
+    #[test]
+    fn kernel_caller() {
+        let program = r##"
+        def foo
+          [1].map { bar }[0]
+        end
+        def bar
+          caller_locations(0)
+        end
+        locs = foo
+        assert ["bar", "block in foo", "foo", "<main>"], locs.map(&:label)
+        assert [6, 3, 3, 8], locs.map(&:lineno)
+        def baz
+          caller
+        end
+        assert true, baz[0].end_with?(":14:in `<main>'")
+        assert 1, caller(0, 1).size
+        assert nil, caller(100)
+        "##;
+        assert_script(program);
+    }
+
+    #[test]
+    fn kernel_catch_throw() {
+        let program = r#"
+        r = catch(:done) do
+          10.times { |i| throw :done, i * 2 if i == 3 }
+          :never
+        end
+        assert 6, r
+        assert 42, catch(:x) { 42 }
+        assert nil, catch(:x) { throw :x }
+        assert 7, catch { |tag| throw tag, 7 }
+        assert :outer, catch(:a) { catch(:b) { throw :a, :outer }; :no }
+        def find_two
+          [1, 2, 3].each { |x| throw :found, x if x == 2 }
+        end
+        assert 2, catch(:found) { find_two }
+
+        log = []
+        r = catch(:a) do
+          begin
+            begin
+              throw :a, 1
+            rescue Exception
+              log << :rescued
+            ensure
+              log << :inner
+            end
+          ensure
+            log << :outer
+          end
+          :no
+        end
+        assert 1, r
+        assert [:inner, :outer], log
+
+        begin
+          throw :nope, 3
+        rescue UncaughtThrowError => e
+          assert "uncaught throw :nope", e.message
+          assert [:nope, 3], [e.tag, e.value]
+        end
+        assert ArgumentError, UncaughtThrowError.superclass
+        assert_error { catch(:a) { throw :b } }
+        "#;
+        assert_script(program);
+    }
+
     #[test]
     fn kernel_complex() {
         let program = r#"
@@ -0,0 +1,1168 @@
+///
+/// Marshal module
+///
+/// Serializes objects into the marshal format 4.8 of CRuby, and restores them.
+/// Streams are compatible byte-for-byte with those of CRuby.
+///
+use crate::*;
+use fxhash::FxHashMap;
+use num::bigint::Sign;
+use num::BigInt;
+
+const MAJOR_VERSION: u8 = 4;
+const MINOR_VERSION: u8 = 8;
+
+pub(crate) fn init(globals: &mut Globals) -> Value {
+    let mut class = Module::module();
+    globals.set_toplevel_constant("Marshal", class);
+    class.add_builtin_module_func(globals, "dump", dump);
+    class.add_builtin_module_func(globals, "load", load);
+    class.add_builtin_module_func(globals, "restore", load);
+    class.set_const_by_str("MAJOR_VERSION", Value::integer(MAJOR_VERSION as i64));
+    class.set_const_by_str("MINOR_VERSION", Value::integer(MINOR_VERSION as i64));
+    class.into()
+}
+
+// Module functions
+
+/// Marshal.dump(obj, limit = -1) -> String
+/// Marshal.dump(obj, port, limit = -1) -> port
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Marshal/m/dump.html
+fn dump(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_range(1, 3)?;
+    let obj = vm[0];
+    let (port, limit) = match args.len() {
+        1 => (None, -1),
+        2 => match vm[1].as_fixnum() {
+            Some(limit) => (None, limit),
+            None => (Some(vm[1]), -1),
+        },
+        _ => (Some(vm[1]), vm[2].coerce_to_fixnum("limit")?),
+    };
+    let res = Value::bytes(dump_value(vm, obj, limit)?);
+    match port {
+        None => Ok(res),
+        Some(port) => {
+            let write = IdentId::get_id("write");
+            if vm.globals.find_method_from_receiver(port, write).is_none() {
+                return Err(RubyError::typeerr("instance of IO needed"));
+            }
+            vm.eval_send1(write, port, res)?;
+            Ok(port)
+        }
+    }
+}
+
+/// Marshal.load(port, proc = nil) -> object
+/// Marshal.restore(port, proc = nil) -> object
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Marshal/m/load.html
+fn load(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_range(1, 2)?;
+    let port = vm[0];
+    let proc = if args.len() == 2 && !vm[1].is_nil() {
+        Some(vm[1])
+    } else {
+        None
+    };
+    let source = match port.as_rstring() {
+        Some(s) => s.as_bytes().to_vec(),
+        None => {
+            let read = IdentId::get_id("read");
+            if vm.globals.find_method_from_receiver(port, read).is_none() {
+                return Err(RubyError::typeerr("instance of IO needed"));
+            }
+            let s = vm.eval_send0(read, port)?;
+            match s.as_rstring() {
+                Some(s) => s.as_bytes().to_vec(),
+                None => return Err(RubyError::argument("marshal data too short")),
+            }
+        }
+    };
+    load_value(vm, &source, proc)
+}
+
+/// Serialize `obj` into a marshal stream.
+pub(crate) fn dump_value(vm: &mut VM, obj: Value, limit: i64) -> Result<Vec<u8>, RubyError> {
+    let mut dumper = Dumper::new();
+    dumper.buf.push(MAJOR_VERSION);
+    dumper.buf.push(MINOR_VERSION);
+    dumper.dump(vm, obj, limit)?;
+    Ok(dumper.buf)
+}
+
+/// Restore an object from a marshal stream `source`.
+pub(crate) fn load_value(vm: &mut VM, source: &[u8], proc: Option<Value>) -> VMResult {
+    let mut loader = Loader::new(source, proc);
+    let major = loader.read_byte()?;
+    let minor = loader.read_byte()?;
+    if major != MAJOR_VERSION || minor > MINOR_VERSION {
+        return Err(RubyError::typeerr(format!(
+            "incompatible marshal file format (can't be read)\n\tformat version {}.{} required; {}.{} given",
+            MAJOR_VERSION, MINOR_VERSION, major, minor
+        )));
+    }
+    loader.load(vm)
+}
+
+fn class_path(class: Module) -> Result<String, RubyError> {
+    if class.is_singleton() {
+        return Err(RubyError::typeerr("singleton class can't be dumped"));
+    }
+    match class.op_name() {
+        Some(name) => Ok(name),
+        None => Err(RubyError::typeerr(format!(
+            "can't dump anonymous class {}",
+            class.name()
+        ))),
+    }
+}
+
+fn path_to_class(vm: &mut VM, path: IdentId) -> Result<Module, RubyError> {
+    let path = path.get_name();
+    let mut class = BuiltinClass::object();
+    for name in path.split("::") {
+        let val = match vm.get_super_const(class, IdentId::get_id(name)) {
+            Ok(val) => val,
+            Err(_) => {
+                return Err(RubyError::argument(format!(
+                    "undefined class/module {}",
+                    path
+                )))
+            }
+        };
+        class = match val.if_mod_class() {
+            Some(class) => class,
+            None => {
+                return Err(RubyError::argument(format!(
+                    "{} does not refer to class/module",
+                    path
+                )))
+            }
+        };
+    }
+    Ok(class)
+}
+
+/// Split the source string of `RegexpInfo` into Ruby's source and options.
+///
+/// Regexp literals carry their options as a leading group like "(?mi)".
+fn regexp_source(re: &RegexpInfo) -> (&str, u8) {
+    let source = re.as_str();
+    if let Some(rest) = source.strip_prefix("(?m") {
+        if let Some(end) = rest.find(')') {
+            let flags = &rest[..end];
+            if flags.chars().all(|c| "isxo".contains(c)) {
+                let mut opt = 0;
+                for c in flags.chars() {
+                    match c {
+                        'i' => opt |= 1,
+                        'x' => opt |= 2,
+                        's' => opt |= 4,
+                        _ => {}
+                    }
+                }
+                return (&rest[end + 1..], opt);
+            }
+        }
+    }
+    (source, 0)
+}
+
+struct Dumper {
+    buf: Vec<u8>,
+    symbols: FxHashMap<IdentId, usize>,
+    objects: FxHashMap<u64, usize>,
+    objects_len: usize,
+}
+
+impl Dumper {
+    fn new() -> Self {
+        Dumper {
+            buf: vec![],
+            symbols: FxHashMap::default(),
+            objects: FxHashMap::default(),
+            objects_len: 0,
+        }
+    }
+
+    /// Register an object entry for links.
+    /// Temporary objects which can not be referred again are given as None.
+    fn remember(&mut self, val: impl Into<Option<Value>>) {
+        if let Some(val) = val.into() {
+            self.objects.insert(val.id(), self.objects_len);
+        }
+        self.objects_len += 1;
+    }
+
+    fn write_long(&mut self, x: i64) {
+        if x == 0 {
+            self.buf.push(0);
+        } else if 0 < x && x < 123 {
+            self.buf.push((x + 5) as u8);
+        } else if -124 < x && x < 0 {
+            self.buf.push(((x - 5) & 0xff) as u8);
+        } else {
+            let mut x = x;
+            let mut bytes = vec![];
+            for i in 1..=8 {
+                bytes.push((x & 0xff) as u8);
+                x >>= 8;
+                if x == 0 {
+                    self.buf.push(i);
+                    break;
+                }
+                if x == -1 {
+                    self.buf.push((-(i as i8)) as u8);
+                    break;
+                }
+            }
+            self.buf.extend_from_slice(&bytes);
+        }
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.write_long(bytes.len() as i64);
+        self.buf.extend_from_slice(bytes);
+    }
+
+    fn write_symbol(&mut self, id: IdentId) {
+        if let Some(idx) = self.symbols.get(&id) {
+            let idx = *idx as i64;
+            self.buf.push(b';');
+            self.write_long(idx);
+            return;
+        }
+        self.symbols.insert(id, self.symbols.len());
+        let name = id.get_name();
+        if name.is_ascii() {
+            self.buf.push(b':');
+            self.write_bytes(name.as_bytes());
+        } else {
+            self.buf.extend_from_slice(b"I:");
+            self.write_bytes(name.as_bytes());
+            self.write_long(1);
+            self.write_encoding(true);
+        }
+    }
+
+    /// Write the encoding ivar. (`E: true` for UTF-8, `E: false` for US-ASCII)
+    fn write_encoding(&mut self, utf8: bool) {
+        self.write_symbol(IdentId::get_id("E"));
+        self.buf.push(if utf8 { b'T' } else { b'F' });
+    }
+
+    fn write_class(&mut self, ty: u8, class: Module) -> Result<(), RubyError> {
+        let path = class_path(class)?;
+        self.buf.push(ty);
+        self.write_symbol(IdentId::get_id_from_string(path));
+        Ok(())
+    }
+
+    /// Write the class of an instance of a subclass of String, Array, Hash or Regexp.
+    fn write_uclass(&mut self, class: Module, base: Module) -> Result<(), RubyError> {
+        if class.id() != base.id() {
+            self.write_class(b'C', class)?;
+        }
+        Ok(())
+    }
+
+    fn write_float(&mut self, f: f64) {
+        let s = if f.is_nan() {
+            "nan".to_string()
+        } else if f.is_infinite() {
+            if f > 0.0 { "inf" } else { "-inf" }.to_string()
+        } else if f == 0.0 {
+            if f.is_sign_negative() { "-0" } else { "0" }.to_string()
+        } else {
+            // Shortest representation formatted the same way as CRuby.
+            let e = format!("{:e}", f.abs());
+            let (mantissa, exp) = e.split_at(e.find('e').unwrap());
+            let digits = mantissa.replace('.', "");
+            let decpt = exp[1..].parse::<i32>().unwrap() + 1;
+            let digs = digits.len() as i32;
+            let mut s = if f < 0.0 {
+                "-".to_string()
+            } else {
+                String::new()
+            };
+            if decpt < -3 || decpt > digs {
+                s += &digits[..1];
+                if digs > 1 {
+                    s.push('.');
+                    s += &digits[1..];
+                }
+                s += &format!("e{}", decpt - 1);
+            } else if decpt > 0 {
+                s += &digits[..decpt as usize];
+                if digs > decpt {
+                    s.push('.');
+                    s += &digits[decpt as usize..];
+                }
+            } else {
+                s += "0.";
+                s += &"0".repeat(-decpt as usize);
+                s += &digits;
+            }
+            s
+        };
+        self.buf.push(b'f');
+        self.write_bytes(s.as_bytes());
+    }
+
+    fn write_bignum(&mut self, b: &BigInt) {
+        let (sign, mut bytes) = b.to_bytes_le();
+        if bytes.len() % 2 == 1 {
+            bytes.push(0);
+        }
+        self.buf.push(b'l');
+        self.buf.push(if sign == Sign::Minus { b'-' } else { b'+' });
+        self.write_long((bytes.len() / 2) as i64);
+        self.buf.extend_from_slice(&bytes);
+    }
+
+    fn dump(&mut self, vm: &mut VM, val: Value, limit: i64) -> Result<(), RubyError> {
+        if limit == 0 {
+            return Err(RubyError::argument("exceed depth limit"));
+        }
+        let limit = limit - 1;
+        match val.unpack() {
+            RV::Nil => self.buf.push(b'0'),
+            RV::True => self.buf.push(b'T'),
+            RV::False => self.buf.push(b'F'),
+            RV::Integer(i) => {
+                if (-(1 << 30)..(1 << 30)).contains(&i) {
+                    self.buf.push(b'i');
+                    self.write_long(i);
+                } else {
+                    // Out of the range of Fixnum in the marshal format.
+                    self.remember(None);
+                    self.write_bignum(&BigInt::from(i));
+                }
+            }
+            RV::Symbol(id) => self.write_symbol(id),
+            RV::Float(f) => {
+                if val.as_flonum().is_some() {
+                    // Immediate floats take an object entry, but are never linked.
+                    self.remember(None);
+                    self.write_float(f);
+                } else if !self.write_link(val) {
+                    self.remember(val);
+                    self.write_float(f);
+                }
+            }
+            RV::Object(_) => {
+                if !self.write_link(val) {
+                    self.dump_object(vm, val, limit)?;
+                }
+            }
+            RV::Uninitialized => return Err(RubyError::internal("Uninitialized value.")),
+        }
+        Ok(())
+    }
+
+    fn write_link(&mut self, val: Value) -> bool {
+        match self.objects.get(&val.id()) {
+            Some(idx) => {
+                let idx = *idx as i64;
+                self.buf.push(b'@');
+                self.write_long(idx);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn dump_object(&mut self, vm: &mut VM, val: Value, limit: i64) -> Result<(), RubyError> {
+        let class = vm.globals.get_class(val);
+        let oref = val.rvalue();
+        if !matches!(oref.kind(), ObjKind::MODULE | ObjKind::CLASS) {
+            let marshal_dump = IdentId::get_id("marshal_dump");
+            if vm
+                .globals
+                .find_method_from_receiver(val, marshal_dump)
+                .is_some()
+            {
+                self.remember(val);
+                let data = vm.eval_send0(marshal_dump, val)?;
+                vm.temp_push(data);
+                self.write_class(b'U', class)?;
+                return self.dump(vm, data, limit);
+            }
+            let _dump = IdentId::get_id("_dump");
+            if vm.globals.find_method_from_receiver(val, _dump).is_some() {
+                let data = vm.eval_send1(_dump, val, Value::integer(limit))?;
+                vm.temp_push(data);
+                let rstring = match data.as_rstring() {
+                    Some(rstring) => rstring,
+                    None => return Err(RubyError::typeerr("_dump() must return string")),
+                };
+                let utf8 = !matches!(rstring, RString::Bytes(_));
+                if utf8 {
+                    self.buf.push(b'I');
+                }
+                self.write_class(b'u', class)?;
+                self.write_bytes(rstring.as_bytes());
+                if utf8 {
+                    self.write_long(1);
+                    self.write_encoding(true);
+                }
+                self.remember(val);
+                return Ok(());
+            }
+        }
+        self.remember(val);
+        match oref.kind() {
+            ObjKind::STRING => {
+                let rstring = oref.string();
+                let utf8 = !matches!(rstring, RString::Bytes(_))
+                    && Encoding::of(val) != Encoding::Ascii8Bit;
+                if utf8 {
+                    self.buf.push(b'I');
+                }
+                self.write_uclass(class, BuiltinClass::string())?;
+                self.buf.push(b'"');
+                self.write_bytes(rstring.as_bytes());
+                if utf8 {
+                    self.write_long(1);
+                    self.write_encoding(true);
+                }
+            }
+            ObjKind::ARRAY => {
+                self.write_uclass(class, BuiltinClass::array())?;
+                self.buf.push(b'[');
+                let elems = oref.array().to_vec();
+                self.write_long(elems.len() as i64);
+                for elem in elems {
+                    self.dump(vm, elem, limit)?;
+                }
+            }
+            ObjKind::HASH => {
+                let hash = oref.rhash();
+                self.write_uclass(class, BuiltinClass::hash())?;
+                if let HashInfo::IdentMap(_) = hash {
+                    self.write_class(b'C', BuiltinClass::hash())?;
+                }
+                let default = val.get_var(IdentId::_DEFAULT);
+                self.buf.push(if default.is_some() { b'}' } else { b'{' });
+                let pairs: Vec<_> = hash.iter().collect();
+                self.write_long(pairs.len() as i64);
+                for (k, v) in pairs {
+                    self.dump(vm, k, limit)?;
+                    self.dump(vm, v, limit)?;
+                }
+                if let Some(default) = default {
+                    self.dump(vm, default, limit)?;
+                }
+            }
+            ObjKind::RANGE => {
+                let range = oref.range().clone();
+                self.write_class(b'o', class)?;
+                self.write_long(3);
+                self.write_symbol(IdentId::get_id("excl"));
+                self.dump(vm, Value::bool(range.exclude), limit)?;
+                self.write_symbol(IdentId::get_id("begin"));
+                self.dump(vm, range.start, limit)?;
+                self.write_symbol(IdentId::get_id("end"));
+                self.dump(vm, range.end, limit)?;
+            }
+            ObjKind::REGEXP => {
+                let re = oref.regexp();
+                let (source, mut opt) = regexp_source(re);
+                let ascii = source.is_ascii();
+                if !ascii {
+                    // ARG_ENCODING_FIXED
+                    opt |= 16;
+                }
+                self.buf.push(b'I');
+                self.write_uclass(class, BuiltinClass::regexp())?;
+                self.buf.push(b'/');
+                self.write_bytes(source.as_bytes());
+                self.buf.push(opt);
+                self.write_long(1);
+                self.write_encoding(!ascii);
+            }
+            ObjKind::BIGNUM => self.write_bignum(oref.bignum()),
+            ObjKind::FLOAT => self.write_float(oref.float()),
+            ObjKind::COMPLEX => {
+                // Complex is dumped by `marshal_dump` which returns [real, imag].
+                let (r, i) = (oref.complex().r, oref.complex().i);
+                self.write_class(b'U', class)?;
+                self.remember(None);
+                self.buf.push(b'[');
+                self.write_long(2);
+                self.dump(vm, r, limit)?;
+                self.dump(vm, i, limit)?;
+            }
+            ObjKind::RATIONAL => {
+                // Rational is dumped by `marshal_dump` which returns [numerator, denominator].
+                let r = oref.rational();
+                let (numer, denom) = (r.numer().clone(), r.denom().clone());
+                self.write_class(b'U', class)?;
+                self.remember(None);
+                self.buf.push(b'[');
+                self.write_long(2);
+                self.dump(vm, Value::bignum(numer), limit)?;
+                self.dump(vm, Value::bignum(denom), limit)?;
+            }
+            ObjKind::MODULE | ObjKind::CLASS => {
+                let module = val.into_module();
+                let path = class_path(module)?;
+                self.buf.push(if module.is_module() { b'm' } else { b'c' });
+                self.write_bytes(path.as_bytes());
+            }
+            ObjKind::ORDINARY => match structobj::member_names(class) {
+                Some(_) => {
+                    let members = structobj::members(vm, val)?;
+                    self.write_class(b'S', class)?;
+                    self.write_long(members.len() as i64);
+                    for (name, v) in members {
+                        self.write_symbol(name.as_symbol().unwrap());
+                        self.dump(vm, v, limit)?;
+                    }
+                }
+                None => {
+                    let ivars: Vec<_> = match oref.ivars() {
+                        Some(table) => table.iter().filter(|(k, _)| k.starts_with("@")).collect(),
+                        None => vec![],
+                    };
+                    self.write_class(b'o', class)?;
+                    self.write_long(ivars.len() as i64);
+                    for (k, v) in ivars {
+                        self.write_symbol(k);
+                        self.dump(vm, v, limit)?;
+                    }
+                }
+            },
+            _ => {
+                return Err(RubyError::typeerr(format!(
+                    "no _dump_data is defined for class {}",
+                    class.name()
+                )))
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Loader<'a> {
+    source: &'a [u8],
+    pos: usize,
+    symbols: Vec<IdentId>,
+    objects: Vec<Value>,
+    proc: Option<Value>,
+}
+
+impl<'a> Loader<'a> {
+    fn new(source: &'a [u8], proc: Option<Value>) -> Self {
+        Loader {
+            source,
+            pos: 0,
+            symbols: vec![],
+            objects: vec![],
+            proc,
+        }
+    }
+
+    fn read_byte(&mut self) -> Result<u8, RubyError> {
+        match self.source.get(self.pos) {
+            Some(b) => {
+                self.pos += 1;
+                Ok(*b)
+            }
+            None => Err(RubyError::argument("marshal data too short")),
+        }
+    }
+
+    fn read_long(&mut self) -> Result<i64, RubyError> {
+        let c = self.read_byte()? as i8 as i64;
+        if c == 0 {
+            return Ok(0);
+        }
+        if c > 0 {
+            if 4 < c {
+                return Ok(c - 5);
+            }
+            let mut x = 0;
+            for i in 0..c {
+                x |= (self.read_byte()? as i64) << (8 * i);
+            }
+            Ok(x)
+        } else {
+            if c < -4 {
+                return Ok(c + 5);
+            }
+            let mut x = -1;
+            for i in 0..-c {
+                x &= !(0xff << (8 * i));
+                x |= (self.read_byte()? as i64) << (8 * i);
+            }
+            Ok(x)
+        }
+    }
+
+    /// Read a length of a collection. Negative lengths are rejected.
+    fn read_len(&mut self) -> Result<usize, RubyError> {
+        match self.read_long()? {
+            len if len < 0 => Err(RubyError::argument("marshal data too short")),
+            len => Ok(len as usize),
+        }
+    }
+
+    /// Read `len` raw bytes.
+    fn read_raw(&mut self, len: usize) -> Result<&'a [u8], RubyError> {
+        match self.pos.checked_add(len) {
+            Some(end) if end <= self.source.len() => {
+                let bytes = &self.source[self.pos..end];
+                self.pos = end;
+                Ok(bytes)
+            }
+            _ => Err(RubyError::argument("marshal data too short")),
+        }
+    }
+
+    fn read_bytes(&mut self) -> Result<&'a [u8], RubyError> {
+        let len = self.read_len()?;
+        self.read_raw(len)
+    }
+
+    fn read_symbol(&mut self) -> Result<IdentId, RubyError> {
+        match self.read_byte()? {
+            b':' => self.read_symbol_body(false),
+            b';' => {
+                let idx = self.read_long()?;
+                match self.symbols.get(idx as usize) {
+                    Some(id) => Ok(*id),
+                    None => Err(RubyError::argument("bad symbol")),
+                }
+            }
+            b'I' => match self.read_byte()? {
+                b':' => self.read_symbol_body(true),
+                c => Err(RubyError::argument(format!(
+                    "dump format error for symbol(0x{:x})",
+                    c
+                ))),
+            },
+            c => Err(RubyError::argument(format!(
+                "dump format error for symbol(0x{:x})",
+                c
+            ))),
+        }
+    }
+
+    fn read_symbol_body(&mut self, ivar: bool) -> Result<IdentId, RubyError> {
+        let name = String::from_utf8_lossy(self.read_bytes()?).into_owned();
+        let id = IdentId::get_id_from_string(name);
+        self.symbols.push(id);
+        if ivar {
+            // Encoding of the symbol. Symbols are always UTF-8 in ruruby.
+            let len = self.read_len()?;
+            for _ in 0..len {
+                self.read_symbol()?;
+                self.skip_value()?;
+            }
+        }
+        Ok(id)
+    }
+
+    /// Skip an encoding value (true / false / String) of a symbol.
+    fn skip_value(&mut self) -> Result<(), RubyError> {
+        match self.read_byte()? {
+            b'T' | b'F' | b'0' => Ok(()),
+            b'"' => self.read_bytes().map(|_| ()),
+            b'I' => {
+                self.skip_value()?;
+                let len = self.read_len()?;
+                for _ in 0..len {
+                    self.read_symbol()?;
+                    self.skip_value()?;
+                }
+                Ok(())
+            }
+            c => Err(RubyError::argument(format!("dump format error(0x{:x})", c))),
+        }
+    }
+
+    fn read_class(&mut self, vm: &mut VM) -> Result<Module, RubyError> {
+        let path = self.read_symbol()?;
+        path_to_class(vm, path)
+    }
+
+    /// Register an object entry for links, and returns the index.
+    fn entry(&mut self, vm: &mut VM, val: Value) -> usize {
+        vm.temp_push(val);
+        self.objects.push(val);
+        self.objects.len() - 1
+    }
+
+    fn replace_entry(&mut self, vm: &mut VM, idx: usize, val: Value) {
+        vm.temp_push(val);
+        self.objects[idx] = val;
+    }
+
+    fn load(&mut self, vm: &mut VM) -> VMResult {
+        self.read_object(vm, false, &mut false)
+    }
+
+    fn post_proc(&mut self, vm: &mut VM, val: Value) -> VMResult {
+        match self.proc {
+            Some(proc) => vm.eval_send1(IdentId::get_id("call"), proc, val),
+            None => Ok(val),
+        }
+    }
+
+    /// Read an object.
+    ///
+    /// `ivar` is true when the object is followed by its instance variables.
+    /// If the reader consumed them by itself, `ivar` is set to false.
+    fn read_object(&mut self, vm: &mut VM, partial: bool, ivar: &mut bool) -> VMResult {
+        let val = match self.read_byte()? {
+            b'@' => {
+                let idx = self.read_long()?;
+                let val = match self.objects.get(idx as usize) {
+                    Some(val) => *val,
+                    None => return Err(RubyError::argument("dump format error (unlinked)")),
+                };
+                return self.post_proc(vm, val);
+            }
+            b'I' => {
+                let mut ivar = true;
+                let val = self.read_object(vm, true, &mut ivar)?;
+                if ivar {
+                    self.read_ivars(vm, val)?;
+                }
+                val
+            }
+            b'0' => Value::nil(),
+            b'T' => Value::true_val(),
+            b'F' => Value::false_val(),
+            b'i' => Value::integer(self.read_long()?),
+            b':' => {
+                let id = self.read_symbol_body(*ivar)?;
+                *ivar = false;
+                Value::symbol(id)
+            }
+            b';' => {
+                self.pos -= 1;
+                Value::symbol(self.read_symbol()?)
+            }
+            b'f' => {
+                let bytes = self.read_bytes()?;
+                let s = String::from_utf8_lossy(bytes);
+                let s = s.split('\0').next().unwrap();
+                let f = match s {
+                    "nan" => f64::NAN,
+                    "inf" => f64::INFINITY,
+                    "-inf" => f64::NEG_INFINITY,
+                    s => match s.parse() {
+                        Ok(f) => f,
+                        Err(_) => {
+                            return Err(RubyError::argument(format!("invalid float format: {}", s)))
+                        }
+                    },
+                };
+                let val = Value::float(f);
+                self.entry(vm, val);
+                val
+            }
+            b'l' => {
+                let sign = match self.read_byte()? {
+                    b'-' => Sign::Minus,
+                    _ => Sign::Plus,
+                };
+                let len = match self.read_len()?.checked_mul(2) {
+                    Some(len) => len,
+                    None => return Err(RubyError::argument("marshal data too short")),
+                };
+                let b = BigInt::from_bytes_le(sign, self.read_raw(len)?);
+                let val = Value::bignum(b);
+                self.entry(vm, val);
+                val
+            }
+            b'"' => {
+                let bytes = self.read_bytes()?.to_vec();
+                let val = Value::string_from_rstring(RString::Bytes(bytes));
+                // A String without the encoding ivar is binary.
+                if !*ivar {
+                    Encoding::Ascii8Bit.set(val);
+                }
+                self.entry(vm, val);
+                val
+            }
+            b'/' => {
+                let source = String::from_utf8_lossy(self.read_bytes()?).into_owned();
+                let opt = self.read_byte()?;
+                let mut flags = String::new();
+                if opt & 1 != 0 {
+                    flags.push('i');
+                }
+                if opt & 2 != 0 {
+                    flags.push('x');
+                }
+                if opt & 4 != 0 {
+                    flags.push('s');
+                }
+                let val = Value::regexp_from(vm, &format!("(?m{}){}", flags, source))?;
+                self.entry(vm, val);
+                val
+            }
+            b'[' => {
+                let len = self.read_len()?;
+                let val = Value::array_empty();
+                self.entry(vm, val);
+                for _ in 0..len {
+                    let elem = self.load(vm)?;
+                    val.into_array().push(elem);
+                }
+                val
+            }
+            c @ (b'{' | b'}') => {
+                let len = self.read_len()?;
+                let mut val = Value::hash_from_map(FxIndexMap::default());
+                self.entry(vm, val);
+                for _ in 0..len {
+                    let k = self.load(vm)?;
+                    let v = self.load(vm)?;
+                    val.as_mut_hash().unwrap().insert(k, v);
+                }
+                if c == b'}' {
+                    let default = self.load(vm)?;
+                    val.set_var(IdentId::_DEFAULT, default);
+                }
+                val
+            }
+            c @ (b'c' | b'm') => {
+                let path = String::from_utf8_lossy(self.read_bytes()?).into_owned();
+                let class = path_to_class(vm, IdentId::get_id_from_string(path.clone()))?;
+                if class.is_module() != (c == b'm') {
+                    return Err(RubyError::argument(format!(
+                        "{} does not refer to {}",
+                        path,
+                        if c == b'm' { "module" } else { "class" }
+                    )));
+                }
+                let val = class.into();
+                self.entry(vm, val);
+                val
+            }
+            b'o' => {
+                let class = self.read_class(vm)?;
+                if class.id() == BuiltinClass::range().id() {
+                    let idx = self.entry(vm, Value::nil());
+                    let (mut start, mut end, mut exclude) = (Value::nil(), Value::nil(), false);
+                    let len = self.read_len()?;
+                    for _ in 0..len {
+                        let name = self.read_symbol()?;
+                        let v = self.load(vm)?;
+                        match name.get_name().as_str() {
+                            "begin" => start = v,
+                            "end" => end = v,
+                            "excl" => exclude = v.to_bool(),
+                            _ => {}
+                        }
+                    }
+                    let val = Value::range(start, end, exclude);
+                    self.replace_entry(vm, idx, val);
+                    val
+                } else {
+                    let val = Value::ordinary_object(class);
+                    self.entry(vm, val);
+                    self.read_ivars(vm, val)?;
+                    val
+                }
+            }
+            b'S' => {
+                let class = self.read_class(vm)?;
+                let names = match structobj::member_names(class) {
+                    Some(names) => names,
+                    None => {
+                        return Err(RubyError::typeerr(format!(
+                            "class {} not a struct",
+                            class.name()
+                        )))
+                    }
+                };
+                let val = Value::ordinary_object(class);
+                self.entry(vm, val);
+                let len = self.read_len()?;
+                for i in 0..len {
+                    let name = self.read_symbol()?;
+                    let expected = names.into_array().get(i).cloned();
+                    if expected != Some(Value::symbol(name)) {
+                        return Err(RubyError::typeerr(format!(
+                            "struct {} not compatible (:{:?} for {})",
+                            class.name(),
+                            name,
+                            expected.map_or("nil".to_string(), |v| format!(":{:?}", v)),
+                        )));
+                    }
+                    let v = self.load(vm)?;
+                    structobj::set_member(val, name, v);
+                }
+                val
+            }
+            b'C' => {
+                let class = self.read_class(vm)?;
+                let val = self.read_object(vm, true, ivar)?;
+                if val.as_hash().is_some() && class.id() == BuiltinClass::hash().id() {
+                    vm.eval_send0(IdentId::get_id("compare_by_identity"), val)?;
+                } else if val.is_packed_value() {
+                    return Err(RubyError::argument("dump format error (user class)"));
+                } else {
+                    val.set_class(class);
+                }
+                val
+            }
+            b'u' => {
+                let class = self.read_class(vm)?;
+                let bytes = self.read_bytes()?.to_vec();
+                let data = Value::string_from_rstring(RString::Bytes(bytes));
+                vm.temp_push(data);
+                if *ivar {
+                    self.read_ivars(vm, data)?;
+                    *ivar = false;
+                }
+                let _load = IdentId::get_id("_load");
+                if vm
+                    .globals
+                    .find_method_from_receiver(class.into(), _load)
+                    .is_none()
+                {
+                    return Err(RubyError::typeerr(format!(
+                        "class {} needs to have method `_load'",
+                        class.name()
+                    )));
+                }
+                let val = vm.eval_send1(_load, class.into(), data)?;
+                self.entry(vm, val);
+                val
+            }
+            b'U' => {
+                let class = self.read_class(vm)?;
+                if class.id() == BuiltinClass::complex().id() {
+                    let idx = self.entry(vm, Value::nil());
+                    let data = self.load(vm)?;
+                    let (r, i) = match data.as_array() {
+                        Some(ary) if ary.len() == 2 => (ary[0], ary[1]),
+                        _ => {
+                            return Err(RubyError::argument(
+                                "marshaled complex must have an array whose length is 2 but 0",
+                            ))
+                        }
+                    };
+                    let val = Value::complex(r, i);
+                    self.replace_entry(vm, idx, val);
+                    val
+                } else if class.id() == BuiltinClass::rational().id() {
+                    let idx = self.entry(vm, Value::nil());
+                    let data = self.load(vm)?;
+                    let val = load_rational(data)?;
+                    self.replace_entry(vm, idx, val);
+                    val
+                } else {
+                    let val = Value::ordinary_object(class);
+                    let marshal_load = IdentId::get_id("marshal_load");
+                    if vm
+                        .globals
+                        .find_method_from_receiver(val, marshal_load)
+                        .is_none()
+                    {
+                        return Err(RubyError::typeerr(format!(
+                            "instance of {} needs to have method `marshal_load'",
+                            class.name()
+                        )));
+                    }
+                    self.entry(vm, val);
+                    let data = self.load(vm)?;
+                    vm.eval_send1(marshal_load, val, data)?;
+                    val
+                }
+            }
+            b'e' => {
+                let module = self.read_class(vm)?;
+                let val = self.read_object(vm, true, ivar)?;
+                vm.eval_send1(IdentId::get_id("extend"), val, module.into())?;
+                val
+            }
+            c => return Err(RubyError::argument(format!("dump format error(0x{:x})", c))),
+        };
+        if partial {
+            Ok(val)
+        } else {
+            self.post_proc(vm, val)
+        }
+    }
+
+    /// Read instance variables of `val`.
+    ///
+    /// The encoding of a String is given as `E` or `encoding`.
+    fn read_ivars(&mut self, vm: &mut VM, mut val: Value) -> Result<(), RubyError> {
+        let len = self.read_len()?;
+        for _ in 0..len {
+            let name = self.read_symbol()?;
+            let v = self.load(vm)?;
+            match name.get_name().as_str() {
+                "E" | "encoding" => {
+                    let binary = v.as_rstring().is_some_and(|enc| {
+                        let enc = enc.as_bytes();
+                        enc == b"ASCII-8BIT" || enc == b"BINARY"
+                    });
+                    if let Some(rstring) = val.as_mut_rstring() {
+                        if !binary {
+                            let bytes = rstring.as_bytes().to_vec();
+                            *rstring = RString::from_bytes(bytes);
+                        }
+                    }
+                }
+                _ => {
+                    if !val.is_packed_value() {
+                        val.set_var(name, v);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Build a Rational from the [numerator, denominator] array of a marshaled rational.
+fn load_rational(data: Value) -> VMResult {
+    let (numer, denom) = match data.as_array() {
+        Some(ary) if ary.len() == 2 => (ary[0].to_real(), ary[1].to_real()),
+        ary => {
+            return Err(RubyError::argument(format!(
+                "marshaled rational must have an array whose length is 2 but {}",
+                ary.map_or(0, |ary| ary.len())
+            )))
+        }
+    };
+    match (numer, denom) {
+        (
+            Some(numer @ (Real::Integer(_) | Real::Bignum(_))),
+            Some(denom @ (Real::Integer(_) | Real::Bignum(_))),
+        ) => {
+            if denom.is_zero() {
+                return Err(RubyError::zero_div("divided by 0"));
+            }
+            Ok(Value::rational(
+                numer.into_rational() / denom.into_rational(),
+            ))
+        }
+        _ => Err(RubyError::typeerr("not an integer")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::*;
+
+    #[test]
+    fn marshal_dump() {
+        let program = r#"
+        assert [4, 8, 48], Marshal.dump(nil).bytes
+        assert [4, 8, 84], Marshal.dump(true).bytes
+        assert [4, 8, 70], Marshal.dump(false).bytes
+        assert [4, 8, 105, 6], Marshal.dump(1).bytes
+        assert [4, 8, 105, 250], Marshal.dump(-1).bytes
+        assert [4, 8, 105, 2, 44, 1], Marshal.dump(300).bytes
+        assert [4, 8, 108, 43, 7, 0, 0, 0, 64], Marshal.dump(2**30).bytes
+        assert [4, 8, 108, 43, 10, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0], Marshal.dump(2**64).bytes
+        assert [4, 8, 102, 8, 49, 46, 53], Marshal.dump(1.5).bytes
+        assert "\x04\bf\b1e2", Marshal.dump(100.0)
+        assert "\x04\bf\t1e-5", Marshal.dump(0.00001)
+        assert "\x04\b[\af\b1.5f\b1.5", Marshal.dump([1.5, 1.5])
+        f = 1e300
+        assert "\x04\b[\af\n1e300@\x06", Marshal.dump([f, f])
+        assert "\x04\bI\"\babc\x06:\x06ET", Marshal.dump("abc")
+        assert "\x04\b[\a:\x06a;\x00", Marshal.dump([:a, :a])
+        assert "\x04\b{\x06:\x06ai\x06", Marshal.dump({a: 1})
+        assert "\x04\bo:\nRange\b:\texclF:\nbegini\x06:\bendi\a", Marshal.dump(1..2)
+        assert "\x04\bI/\babc\x01\x06:\x06EF", Marshal.dump(/abc/i)
+        assert "\x04\bU:\rRational[\ai\x06i\b", Marshal.dump(Rational(1, 3))
+        a = "x"
+        assert "\x04\b[\aI\"\x06x\x06:\x06ET@\x06", Marshal.dump([a, a])
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn marshal_round_trip() {
+        let program = r#"
+        class Foo
+          attr_accessor :a, :b
+          def initialize(a, b)
+            @a = a
+            @b = b
+          end
+        end
+        S = Struct.new(:x, :y)
+        obj = [nil, true, 1, -300, 2**70, 3.25, "str", :sym, [1, [2]], {a: 1, "b" => 2}, 1...3]
+        assert obj, Marshal.load(Marshal.dump(obj))
+        assert "\x04\bS:\x06S\a:\x06xi\x06:\x06yi\a", Marshal.dump(S.new(1, 2))
+        s = Marshal.load(Marshal.dump(S.new(1, "y")))
+        assert [S, 1, "y"], [s.class, s.x, s.y]
+        f = Marshal.load(Marshal.dump(Foo.new(1, [2])))
+        assert Foo, f.class
+        assert [1, [2]], [f.a, f.b]
+        assert "\x04\bo:\bFoo\a:\a@ai\x06:\a@b0", Marshal.dump(Foo.new(1, nil))
+        h = Marshal.load(Marshal.dump(Hash.new(5)))
+        assert 5, h[:none]
+        s = "shared"
+        a = Marshal.load(Marshal.dump([s, s]))
+        assert true, a[0].equal?(a[1])
+        assert 1, Marshal.load(Marshal.dump(/abc/i)) =~ "xABC"
+        assert [String, Array], Marshal.load(Marshal.dump([String, Array]))
+        r = [Rational(-2, 3), 2**70/3r]
+        assert r, Marshal.load(Marshal.dump(r))
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn marshal_hooks() {
+        let program = r#"
+        class UDump
+          attr_reader :v
+          def initialize(v); @v = v; end
+          def marshal_dump; [@v]; end
+          def marshal_load(a); @v = a[0]; end
+        end
+        class UDef
+          attr_reader :v
+          def initialize(v); @v = v; end
+          def _dump(lv); @v.to_s; end
+          def self._load(s); new(s.to_i); end
+        end
+        assert "\x04\bU:\nUDump[\x06i\a", Marshal.dump(UDump.new(2))
+        assert 2, Marshal.load(Marshal.dump(UDump.new(2))).v
+        assert "\x04\bIu:\tUDef\x067\x06:\x06ET", Marshal.dump(UDef.new(7))
+        assert 7, Marshal.load(Marshal.dump(UDef.new(7))).v
+        assert_error { Marshal.dump(Class.new.new) }
+        assert_error { Marshal.load("\x04\x09[") }
+        assert_error { Marshal.load("\x04\b[\x06") }
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn marshal_malformed() {
+        let program = r#"
+        def load_error(s)
+          Marshal.load(s.b)
+        rescue => e
+          e.class
+        end
+        assert ArgumentError, load_error("\x04\bl+\xfa")
+        assert ArgumentError, load_error("\x04\bl+\x0a\x00\x01")
+        assert ArgumentError, load_error("\x04\b[\xfa")
+        assert ArgumentError, load_error("\x04\b{\xfa")
+        assert ArgumentError, load_error("\x04\b\"\xfa")
+        assert ArgumentError, load_error("\x04\b\"\x0aabc")
+        assert ArgumentError, load_error("\x04\b[\x07i\x06")
+        assert ArgumentError, load_error("\x04\bf\x06x")
+        assert 1.5, Marshal.load("\x04\bf\b1.5")
+        "#;
+        assert_script(program);
+    }
+}
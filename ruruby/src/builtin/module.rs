@@ -32,6 +32,9 @@ pub(crate) fn init(globals: &mut Globals) {
     class.add_builtin_method_by_str(globals, "module_eval", module_eval);
     class.add_builtin_method_by_str(globals, "class_eval", module_eval);
     class.add_builtin_method_by_str(globals, "alias_method", module_alias_method);
+    class.add_builtin_method_by_str(globals, "undef_method", undef_method);
+    class.add_builtin_method_by_str(globals, "remove_method", remove_method);
+    class.add_builtin_method_by_str(globals, "define_method", define_method);
     class.add_builtin_method_by_str(globals, "public", public);
     class.add_builtin_method_by_str(globals, "private", private);
     class.add_builtin_method_by_str(globals, "protected", protected);
@@ -205,11 +208,11 @@ fn class_variables(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     let inherit = vm[0].to_bool();
     assert_eq!(inherit, false);
     let receiver = self_val.rvalue();
-    let res = match receiver.var_table() {
+    let res = match receiver.ivars() {
         Some(table) => table
-            .keys()
-            .filter(|x| x.is_class_var())
-            .map(|x| Value::symbol(*x))
+            .iter()
+            .filter(|(x, _)| x.is_class_var())
+            .map(|(x, _)| Value::symbol(x))
             .collect(),
         None => vec![],
     };
@@ -330,7 +333,7 @@ fn attr_writer(vm: &mut VM, self_val: Value, _args: &Args2) -> VMResult {
     Ok(Value::nil())
 }
 
-fn define_reader(globals: &mut Globals, mut class: Module, id: IdentId) {
+pub(crate) fn define_reader(globals: &mut Globals, mut class: Module, id: IdentId) {
     let instance_var_id = id.add_prefix("@");
     let info = MethodInfo::AttrReader {
         id: instance_var_id,
@@ -468,6 +471,112 @@ fn module_alias_method(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     Ok(self_val)
 }
 
+/// ## instance method Module#undef_method
+/// - undef_method(*name) -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Module/i/undef_method.html
+fn undef_method(vm: &mut VM, self_val: Value, _args: &Args2) -> VMResult {
+    undef_methods(vm, self_val.into_module())?;
+    Ok(self_val)
+}
+
+/// Undefine methods given as the arguments in `module`.
+///
+/// This is also used for an undef statement.
+pub(crate) fn undef_methods(vm: &mut VM, mut module: Module) -> Result<(), RubyError> {
+    for arg in vm.args().to_owned() {
+        let name = arg.expect_string_or_symbol("Args")?;
+        if module.search_method(name).is_none() {
+            return Err(RubyError::name(format!(
+                "undefined method `{:?}' for class `{}'",
+                name,
+                module.name()
+            )));
+        }
+        module.undef_method(&mut vm.globals, name);
+    }
+    Ok(())
+}
+
+/// ## instance method Module#remove_method
+/// - remove_method(*name) -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Module/i/remove_method.html
+fn remove_method(vm: &mut VM, self_val: Value, _args: &Args2) -> VMResult {
+    let mut module = self_val.into_module();
+    for arg in vm.args().to_owned() {
+        let name = arg.expect_string_or_symbol("Args")?;
+        if module.remove_method(&mut vm.globals, name).is_none() {
+            return Err(RubyError::name(format!(
+                "method `{:?}' not defined in {}",
+                name,
+                module.name()
+            )));
+        }
+    }
+    Ok(self_val)
+}
+
+/// ## instance method Module#define_method
+/// - define_method(name, method) -> Symbol
+/// - define_method(name) { ... } -> Symbol
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Module/i/define_method.html
+fn define_method(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(1, 2)?;
+    let name = vm[0].expect_string_or_symbol("1st arg")?;
+    let fid = if args.len() == 2 {
+        let body = vm[1];
+        if let Some(minfo) = body.as_method().or_else(|| body.as_unbound_method()) {
+            minfo.method
+        } else if body.as_proc().is_some() {
+            proc_method(vm, name, body)
+        } else {
+            return Err(RubyError::typeerr(format!(
+                "wrong argument type {} (expected Proc/Method/UnboundMethod)",
+                body.get_class_name()
+            )));
+        }
+    } else {
+        match &args.block {
+            Some(block) => {
+                let proc = vm.create_proc(block);
+                proc_method(vm, name, proc)
+            }
+            None => {
+                return Err(RubyError::argument(
+                    "tried to create Proc object without a block",
+                ))
+            }
+        }
+    };
+    self_val
+        .into_module()
+        .add_method(&mut vm.globals, name, fid);
+    Ok(Value::symbol(name))
+}
+
+/// Register a method whose body is `proc`.
+///
+/// The ISeq of `proc` is duplicated as a lambda so that `proc` itself keeps its semantics.
+fn proc_method(vm: &mut VM, name: IdentId, proc: Value) -> FnId {
+    let pinfo = proc.as_proc().unwrap();
+    let mut info = (*vm.globals.methods[pinfo.method].as_iseq()).clone();
+    info.kind = ISeqKind::Method(Some(name));
+    let lambda_id = vm.globals.methods.add(MethodInfo::default());
+    info.method = lambda_id;
+    vm.globals.methods.update(
+        lambda_id,
+        MethodInfo::RubyFunc {
+            iseq: ISeqRef::new(info),
+        },
+    );
+    let lambda = RValue::new_proc(ProcInfo::new(pinfo.self_val, lambda_id, pinfo.outer)).pack();
+    vm.globals
+        .methods
+        .add(MethodInfo::ProcFunc { proc: lambda })
+}
+
 fn public(_vm: &mut VM, self_val: Value, _args: &Args2) -> VMResult {
     Ok(self_val)
 }
@@ -932,6 +1041,86 @@ mod test {
         assert_script(program);
     }
 
+    #[test]
+    fn undef_method() {
+        let program = r##"
+        class Foo
+          def foo; 1; end
+          def bar; 2; end
+        end
+        class Bar < Foo
+          def foo; 3; end
+          undef_method :foo, "bar"
+          assert_error { undef_method :baz }
+        end
+        b = Bar.new
+        assert 3, (Foo.new.foo + Foo.new.bar)
+        assert_error { b.foo }
+        assert_error { b.bar }
+        assert false, b.respond_to?(:foo)
+        assert [], Bar.instance_methods(false)
+        class Foo
+          undef foo
+        end
+        assert_error { Foo.new.foo }
+        def baz; 4; end
+        undef :baz
+        assert_error { baz }
+        "##;
+        assert_script(program);
+    }
+
+    #[test]
+    fn remove_method() {
+        let program = r##"
+        class Foo
+          def foo; 1; end
+        end
+        class Bar < Foo
+          def foo; 2; end
+        end
+        b = Bar.new
+        a = []
+        3.times do |i|
+          Bar.send(:remove_method, :foo) if i == 1
+          a << b.foo
+        end
+        assert [2, 1, 1], a
+        assert_error { Bar.send(:remove_method, :foo) }
+        class Bar
+          undef_method :foo
+          assert_error { remove_method :foo }
+        end
+        "##;
+        assert_script(program);
+    }
+
+    #[test]
+    fn define_method() {
+        let program = r##"
+        class Foo
+          x = 10
+          assert :add, define_method(:add) { |y| x + y + @v }
+          define_method("ret") { return 5; 6 }
+          define_method(:add2, instance_method(:add))
+          pr = proc { |a, b| [self.class, a, b] }
+          define_method(:pr, pr)
+          def initialize; @v = 1; end
+          assert_error { define_method(:foo) }
+          assert_error { define_method(:foo, 3) }
+        end
+        f = Foo.new
+        assert 16, f.add(5)
+        assert 5, f.ret
+        assert 12, f.add2(1)
+        assert [Foo, 1, 2], f.pr(1, 2)
+        assert_error { f.add }
+        assert_error { f.pr(1) }
+        assert [12, 13], [1, 2].map { |i| f.add(i) }
+        "##;
+        assert_script(program);
+    }
+
     #[test]
     fn const_defined() {
         let program = r#"
@@ -0,0 +1,268 @@
+///
+/// Mutex and ConditionVariable
+///
+/// The owner of a Mutex and the threads waiting on a ConditionVariable are held in hidden instance variables.
+///
+use crate::*;
+
+pub(crate) fn init(globals: &mut Globals) {
+    let mut thread = globals.get_toplevel_constant("Thread").into_module();
+
+    let class = Module::class_under_object();
+    globals.set_toplevel_constant("Mutex", class);
+    thread.set_const_by_str("Mutex", class.into());
+    class.add_builtin_method_by_str(globals, "lock", lock);
+    class.add_builtin_method_by_str(globals, "unlock", unlock);
+    class.add_builtin_method_by_str(globals, "try_lock", try_lock);
+    class.add_builtin_method_by_str(globals, "locked?", locked);
+    class.add_builtin_method_by_str(globals, "owned?", owned);
+    class.add_builtin_method_by_str(globals, "synchronize", synchronize);
+    class.add_builtin_method_by_str(globals, "sleep", sleep);
+
+    let class = Module::class_under_object();
+    globals.set_toplevel_constant("ConditionVariable", class);
+    thread.set_const_by_str("ConditionVariable", class.into());
+    class.add_builtin_method_by_str(globals, "wait", wait);
+    class.add_builtin_method_by_str(globals, "signal", signal);
+    class.add_builtin_method_by_str(globals, "broadcast", broadcast);
+}
+
+fn owner(mutex: Value) -> Value {
+    mutex.get_var(IdentId::get_id("/owner")).unwrap_or_default()
+}
+
+pub(crate) fn lock_mutex(vm: &mut VM, mutex: Value) -> Result<(), RubyError> {
+    let current = vm.globals.scheduler.current;
+    if owner(mutex).id() == current.id() {
+        return Err(RubyError::thread("deadlock; recursive locking"));
+    }
+    vm.thread_wait(None, |_| owner(mutex).is_nil())?;
+    mutex.set_var_by_str("/owner", current);
+    Ok(())
+}
+
+pub(crate) fn unlock_mutex(vm: &mut VM, mutex: Value) -> Result<(), RubyError> {
+    let owner = owner(mutex);
+    if owner.is_nil() {
+        return Err(RubyError::thread(
+            "Attempt to unlock a mutex which is not locked",
+        ));
+    }
+    if owner.id() != vm.globals.scheduler.current.id() {
+        return Err(RubyError::thread(
+            "Attempt to unlock a mutex which is locked by another thread",
+        ));
+    }
+    mutex.set_var_by_str("/owner", Value::nil());
+    vm.globals.scheduler.event += 1;
+    Ok(())
+}
+
+// Mutex instance methods
+
+/// Mutex#lock -> self
+fn lock(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    lock_mutex(vm, self_val)?;
+    Ok(self_val)
+}
+
+/// Mutex#unlock -> self
+fn unlock(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    unlock_mutex(vm, self_val)?;
+    Ok(self_val)
+}
+
+/// Mutex#try_lock -> bool
+fn try_lock(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    if owner(self_val).is_nil() {
+        self_val.set_var_by_str("/owner", vm.globals.scheduler.current);
+        Ok(Value::true_val())
+    } else {
+        Ok(Value::false_val())
+    }
+}
+
+/// Mutex#locked? -> bool
+fn locked(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::bool(!owner(self_val).is_nil()))
+}
+
+/// Mutex#owned? -> bool
+fn owned(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::bool(
+        owner(self_val).id() == vm.globals.scheduler.current.id(),
+    ))
+}
+
+/// Mutex#synchronize { ... } -> object
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Thread=3a=3aMutex/i/synchronize.html
+fn synchronize(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let block = args.expect_block()?;
+    lock_mutex(vm, self_val)?;
+    let res = vm.eval_block0(block);
+    unlock_mutex(vm, self_val)?;
+    res
+}
+
+/// Mutex#sleep(timeout = nil) -> Integer
+///
+/// Release the lock, sleep, and reacquire the lock after woken up.
+fn sleep(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
+    let secs = if args.len() == 0 || vm[0].is_nil() {
+        None
+    } else {
+        match vm[0].unpack() {
+            RV::Integer(i) => Some(i as f64),
+            RV::Float(f) => Some(f),
+            _ => return Err(RubyError::argument("Arg must be Integer or Float.")),
+        }
+    };
+    unlock_mutex(vm, self_val)?;
+    let start = std::time::Instant::now();
+    let res = vm.thread_sleep(secs, false);
+    lock_mutex(vm, self_val)?;
+    res?;
+    Ok(Value::integer(start.elapsed().as_secs() as i64))
+}
+
+// ConditionVariable instance methods
+
+fn waiters(cv: Value) -> Array {
+    match cv.get_var(IdentId::get_id("/waiters")) {
+        Some(waiters) => waiters.into_array(),
+        None => {
+            let waiters = Value::array_empty();
+            cv.set_var_by_str("/waiters", waiters);
+            waiters.into_array()
+        }
+    }
+}
+
+/// ConditionVariable#wait(mutex, timeout = nil) -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Thread=3a=3aConditionVariable/i/wait.html
+fn wait(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(1, 2)?;
+    let mutex = vm[0];
+    let deadline = if args.len() == 2 {
+        builtin::thread::timeout_to_deadline(vm[1])?
+    } else {
+        None
+    };
+    let mut current = vm.globals.scheduler.current;
+    current.as_thread().unwrap().wakeup = false;
+    waiters(self_val).push(current);
+    unlock_mutex(vm, mutex)?;
+    let res = vm.thread_wait(deadline, |_| {
+        std::mem::take(&mut current.as_thread().unwrap().wakeup)
+    });
+    let mut waiters = waiters(self_val);
+    if let Some(i) = waiters.iter().position(|t| t.id() == current.id()) {
+        waiters.drain(i..i + 1);
+    }
+    lock_mutex(vm, mutex)?;
+    res?;
+    Ok(self_val)
+}
+
+fn wake_waiter(vm: &mut VM, mut thread: Value) {
+    if let Some(info) = thread.as_thread() {
+        info.wakeup = true;
+        vm.globals.scheduler.event += 1;
+    }
+}
+
+/// ConditionVariable#signal -> self
+fn signal(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let mut waiters = waiters(self_val);
+    if !waiters.is_empty() {
+        let thread = waiters.drain(0..1)[0];
+        wake_waiter(vm, thread);
+    }
+    Ok(self_val)
+}
+
+/// ConditionVariable#broadcast -> self
+fn broadcast(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let mut waiters = waiters(self_val);
+    let len = waiters.len();
+    for thread in waiters.drain(0..len) {
+        wake_waiter(vm, thread);
+    }
+    Ok(self_val)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::*;
+
+    #[test]
+    fn mutex() {
+        let program = r#"
+        m = Mutex.new
+        assert false, m.locked?
+        assert m, m.lock
+        assert true, m.locked?
+        assert true, m.owned?
+        assert_error { m.lock }
+        assert m, m.unlock
+        assert_error { m.unlock }
+        assert true, m.try_lock
+        assert false, m.try_lock
+        m.unlock
+        assert Mutex, Thread::Mutex
+
+        count = 0
+        threads = 5.times.map do
+          Thread.new do
+            100.times do
+              m.synchronize do
+                c = count
+                Thread.pass
+                count = c + 1
+              end
+            end
+          end
+        end
+        threads.each(&:join)
+        assert 500, count
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn condition_variable() {
+        let program = r#"
+        m = Mutex.new
+        cv = ConditionVariable.new
+        ready = false
+        res = []
+        t = Thread.new do
+          m.synchronize do
+            cv.wait(m) until ready
+            res << :waked
+          end
+        end
+        Thread.pass
+        m.synchronize do
+          res << :signal
+          ready = true
+          cv.signal
+        end
+        t.join
+        assert [:signal, :waked], res
+        m.synchronize { assert cv, cv.wait(m, 0.01) }
+        "#;
+        assert_script(program);
+    }
+}
@@ -27,6 +27,7 @@ fn inspect(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
             Real::Bignum(n) => n.to_string(),
             Real::Integer(i) => i.to_string(),
             Real::Float(f) => format!("{:?}", f),
+            Real::Rational(r) => format!("({}/{})", r.numer(), r.denom()),
         },
         None => match self_val.as_complex() {
             Some((r, i)) => {
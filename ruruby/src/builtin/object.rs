@@ -18,7 +18,7 @@ pub(crate) fn init(globals: &mut Globals) {
     object.add_builtin_method_by_str(globals, "eql?", eql);
     object.add_builtin_method_by_str(globals, "singleton_class", singleton_class);
     object.add_builtin_method_by_str(globals, "extend", extend);
-    object.add_builtin_method_by_str(globals, "clone", dup);
+    object.add_builtin_method_by_str(globals, "clone", clone);
     object.add_builtin_method_by_str(globals, "dup", dup);
     object.add_builtin_method_by_str(globals, "nil?", nil_);
     object.add_builtin_method_by_str(globals, "method", method);
@@ -95,6 +95,18 @@ fn dup(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     Ok(val)
 }
 
+/// clone -> object
+/// https://docs.ruby-lang.org/ja/latest/method/Object/i/clone.html
+fn clone(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let val = self_val.shallow_dup();
+    if self_val.is_frozen() {
+        Ok(val.freeze())
+    } else {
+        Ok(val)
+    }
+}
+
 /// eql?(other) -> bool
 /// https://docs.ruby-lang.org/ja/latest/method/Object/i/eql=3f.html
 fn eql(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
@@ -133,6 +145,7 @@ fn instance_variable_set(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult
     let name = vm[0];
     let val = vm[1];
     let var_id = name.expect_symbol_or_string("1st arg")?;
+    self_val.check_frozen()?;
     self_val.set_var(var_id, val);
     Ok(val)
 }
@@ -152,11 +165,11 @@ fn instance_variable_get(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult
 fn instance_variables(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(0)?;
     let receiver = self_val.rvalue();
-    let res = match receiver.var_table() {
+    let res = match receiver.ivars() {
         Some(table) => table
-            .keys()
-            .filter(|x| x.starts_with("@"))
-            .map(|x| Value::symbol(*x))
+            .iter()
+            .filter(|(x, _)| x.starts_with("@"))
+            .map(|(x, _)| Value::symbol(x))
             .collect(),
         None => vec![],
     };
@@ -170,9 +183,11 @@ fn instance_of(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     ))
 }
 
+/// freeze -> self
+/// https://docs.ruby-lang.org/ja/latest/method/Object/i/freeze.html
 fn freeze(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(0)?;
-    Ok(self_val)
+    Ok(self_val.freeze())
 }
 
 fn equal(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
@@ -295,9 +310,11 @@ fn cmp(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     Ok(res)
 }
 
-fn frozen_(_: &mut VM, _: Value, args: &Args2) -> VMResult {
+/// frozen? -> bool
+/// https://docs.ruby-lang.org/ja/latest/method/Object/i/frozen=3f.html
+fn frozen_(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(0)?;
-    Ok(Value::false_val())
+    Ok(Value::bool(self_val.is_frozen()))
 }
 
 #[cfg(test)]
@@ -679,4 +696,49 @@ mod test {
         "#;
         assert_script(program);
     }
+
+    #[test]
+    fn object_freeze() {
+        let program = r#"
+        class Foo
+          attr_accessor :x
+          def set
+            @y = 1
+          end
+        end
+        s = "abc".freeze
+        assert true, s.frozen?
+        assert false, s.dup.frozen?
+        assert true, s.clone.frozen?
+        assert true, 1.frozen?
+        assert true, :a.frozen?
+        assert true, nil.frozen?
+        assert true, 1.5.frozen?
+        begin
+          s << "d"
+        rescue FrozenError => e
+          res = e.class
+        end
+        assert FrozenError, res
+        assert "abc", s
+        a = [1, 2].freeze
+        assert_error { a << 3 }
+        assert_error { a[0] = 3 }
+        assert_error { a.push(3) }
+        assert [1, 2], a
+        assert false, a.dup.frozen?
+        assert true, a.clone.frozen?
+        h = { a: 1 }.freeze
+        assert_error { h[:b] = 2 }
+        assert_error { h.delete(:a) }
+        assert({ a: 1 }, h)
+        assert false, h.dup.frozen?
+        f = Foo.new.freeze
+        assert true, f.frozen?
+        assert_error { f.x = 3 }
+        assert_error { f.set }
+        assert_error { f.instance_variable_set(:@z, 1) }
+        "#;
+        assert_script(program);
+    }
 }
@@ -0,0 +1,293 @@
+///
+/// ObjectSpace module
+///
+/// Objects are enumerated by scanning the heap pages of the allocator.
+///
+use crate::*;
+
+pub(crate) fn init(globals: &mut Globals) -> Value {
+    let class = Module::module();
+    globals.set_toplevel_constant("ObjectSpace", class);
+    class.add_builtin_module_func(globals, "each_object", each_object);
+    class.add_builtin_module_func(globals, "count_objects", count_objects);
+    class.add_builtin_module_func(globals, "garbage_collect", garbage_collect);
+    class.add_builtin_module_func(globals, "define_finalizer", define_finalizer);
+    class.add_builtin_module_func(globals, "undefine_finalizer", undefine_finalizer);
+    class.add_builtin_module_func(globals, "_id2ref", id2ref);
+    class.into()
+}
+
+/// Collect all live objects visible from Ruby.
+///
+/// Internal objects, singleton classes and included modules are excluded.
+fn live_objects() -> Vec<Value> {
+    let mut v = vec![];
+    ALLOC.with(|m| {
+        m.borrow()
+            .for_each_slot(|rvalue| match rvalue.kind_or_none() {
+                None | Some(ObjKind::INVALID) | Some(ObjKind::SPLAT) => {}
+                Some(ObjKind::MODULE) | Some(ObjKind::CLASS) => {
+                    let module =
+                        Module::new(Value::from_ptr(rvalue as *const RValue as *mut RValue));
+                    if !module.is_singleton() && !module.is_included() {
+                        v.push(module.into());
+                    }
+                }
+                Some(_) => v.push(Value::from_ptr(rvalue as *const RValue as *mut RValue)),
+            })
+    });
+    v
+}
+
+/// Name of the type of objects for ObjectSpace.count_objects.
+fn type_name(rvalue: &RValue) -> &'static str {
+    match rvalue.kind() {
+        ObjKind::ORDINARY | ObjKind::EXCEPTION => "T_OBJECT",
+        ObjKind::CLASS => "T_CLASS",
+        ObjKind::MODULE => {
+            if rvalue.module().is_included() {
+                "T_ICLASS"
+            } else {
+                "T_MODULE"
+            }
+        }
+        ObjKind::FLOAT => "T_FLOAT",
+        ObjKind::STRING => "T_STRING",
+        ObjKind::REGEXP => "T_REGEXP",
+        ObjKind::ARRAY => "T_ARRAY",
+        ObjKind::HASH => "T_HASH",
+        ObjKind::RANGE => "T_STRUCT",
+        ObjKind::BIGNUM => "T_BIGNUM",
+        ObjKind::COMPLEX => "T_COMPLEX",
+        ObjKind::RATIONAL => "T_RATIONAL",
+        ObjKind::SPLAT => "T_IMEMO",
+        ObjKind::IO => "T_FILE",
+        ObjKind::SET => "T_OBJECT",
+        _ => "T_DATA",
+    }
+}
+
+// Module functions
+
+/// ObjectSpace.each_object(klass = nil) {|object| ...} -> Integer
+/// ObjectSpace.each_object(klass = nil) -> Enumerator
+///
+/// https://docs.ruby-lang.org/ja/latest/method/ObjectSpace/m/each_object.html
+fn each_object(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
+    let class = if args.len() == 1 && !vm[0].is_nil() {
+        Some(vm[0].expect_mod_class()?)
+    } else {
+        None
+    };
+    let block = match &args.block {
+        None => {
+            let id = IdentId::get_id("each_object");
+            return vm.create_enumerator(id, self_val, args.into(vm));
+        }
+        Some(block) => block,
+    };
+    let objects: Vec<_> = live_objects()
+        .into_iter()
+        .filter(|obj| match class {
+            Some(class) => vm.globals.kind_of(*obj, class.into()),
+            None => true,
+        })
+        .collect();
+    vm.temp_extend_from_slice(&objects);
+    for obj in &objects {
+        vm.eval_block1(block, *obj)?;
+    }
+    Ok(Value::integer(objects.len() as i64))
+}
+
+/// ObjectSpace.count_objects(result_hash = {}) -> Hash
+///
+/// https://docs.ruby-lang.org/ja/latest/method/ObjectSpace/m/count_objects.html
+fn count_objects(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
+    let mut counts: FxIndexMap<&'static str, usize> = FxIndexMap::default();
+    for name in &[
+        "T_OBJECT",
+        "T_CLASS",
+        "T_MODULE",
+        "T_FLOAT",
+        "T_STRING",
+        "T_REGEXP",
+        "T_ARRAY",
+        "T_HASH",
+        "T_STRUCT",
+        "T_BIGNUM",
+        "T_DATA",
+        "T_FILE",
+        "T_COMPLEX",
+        "T_RATIONAL",
+        "T_IMEMO",
+        "T_ICLASS",
+    ] {
+        counts.insert(name, 0);
+    }
+    let (total, live) = ALLOC.with(|m| {
+        let alloc = m.borrow();
+        let mut live = 0;
+        alloc.for_each_slot(|rvalue| {
+            if let Some(kind) = rvalue.kind_or_none() {
+                if kind != ObjKind::INVALID {
+                    live += 1;
+                    *counts.get_mut(type_name(rvalue)).unwrap() += 1;
+                }
+            }
+        });
+        (alloc.total_slots(), live)
+    });
+    let mut hash = if args.len() == 1 {
+        let mut hash = vm[0];
+        hash.expect_hash("1st arg")?;
+        hash.as_mut_hash().unwrap().clear();
+        hash
+    } else {
+        Value::hash_from_map(FxIndexMap::default())
+    };
+    let map = hash.as_mut_hash().unwrap();
+    map.insert(
+        Value::symbol_from_str("TOTAL"),
+        Value::integer(total as i64),
+    );
+    map.insert(
+        Value::symbol_from_str("FREE"),
+        Value::integer((total - live) as i64),
+    );
+    for (name, count) in counts {
+        if count != 0 {
+            map.insert(Value::symbol_from_str(name), Value::integer(count as i64));
+        }
+    }
+    Ok(hash)
+}
+
+/// ObjectSpace.garbage_collect -> nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/ObjectSpace/m/garbage_collect.html
+fn garbage_collect(vm: &mut VM, _: Value, _: &Args2) -> VMResult {
+    vm.gc();
+    Ok(Value::nil())
+}
+
+/// ObjectSpace.define_finalizer(obj) {|id| ...} -> Array
+/// ObjectSpace.define_finalizer(obj, callable) -> Array
+///
+/// https://docs.ruby-lang.org/ja/latest/method/ObjectSpace/m/define_finalizer.html
+fn define_finalizer(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_range(1, 2)?;
+    let obj = vm[0];
+    let proc = if args.len() == 2 {
+        vm[1]
+    } else {
+        match &args.block {
+            Some(block) => vm.create_proc(block),
+            None => {
+                return Err(RubyError::argument(
+                    "tried to create Proc object without a block",
+                ))
+            }
+        }
+    };
+    if obj.is_packed_value() {
+        return Err(RubyError::argument(format!(
+            "cannot define finalizer for {}",
+            obj.get_class_name()
+        )));
+    }
+    if vm
+        .globals
+        .find_method_from_receiver(proc, IdentId::get_id("call"))
+        .is_none()
+    {
+        return Err(RubyError::argument(format!(
+            "wrong type argument {} (should be callable)",
+            proc.get_class_name()
+        )));
+    }
+    vm.globals
+        .finalizers
+        .entry(obj.id())
+        .or_default()
+        .push(proc);
+    Ok(Value::array_from(vec![Value::integer(0), proc]))
+}
+
+/// ObjectSpace.undefine_finalizer(obj) -> object
+///
+/// https://docs.ruby-lang.org/ja/latest/method/ObjectSpace/m/undefine_finalizer.html
+fn undefine_finalizer(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let obj = vm[0];
+    vm.globals.finalizers.shift_remove(&obj.id());
+    Ok(obj)
+}
+
+/// ObjectSpace._id2ref(id) -> object
+///
+/// https://docs.ruby-lang.org/ja/latest/method/ObjectSpace/m/_id2ref.html
+fn id2ref(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let id = vm[0].coerce_to_fixnum("1st arg")? as u64;
+    if id != 0 && Value::from(id).is_packed_value() {
+        return Ok(Value::from(id));
+    }
+    match live_objects().into_iter().find(|obj| obj.id() == id) {
+        Some(obj) => Ok(obj),
+        None => Err(RubyError::range(format!("0x{:016x} is not id value", id))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::*;
+
+    #[test]
+    fn object_space() {
+        let program = r#"
+        class Foo; end
+        class Bar < Foo; end
+        a = [Foo.new, Bar.new, Bar.new]
+        count = 0
+        res = ObjectSpace.each_object(Foo) { |o| count += 1 }
+        assert 3, res
+        assert 3, count
+        assert 2, ObjectSpace.each_object(Bar).to_a.size
+        assert true, ObjectSpace.each_object(Class).to_a.include?(Bar)
+        h = ObjectSpace.count_objects
+        assert true, h[:TOTAL] >= h[:FREE] + h[:T_OBJECT]
+        assert true, h[:T_ARRAY] > 0
+        s = "id2ref"
+        assert true, s.equal?(ObjectSpace._id2ref(s.object_id))
+        assert 100, ObjectSpace._id2ref(100.object_id)
+        assert_error { ObjectSpace.define_finalizer(1) {} }
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn object_space_finalizer() {
+        let program = r#"
+        $fin = []
+        FIN = proc { |id| $fin << id }
+        def make
+          o = Object.new
+          ObjectSpace.define_finalizer(o, FIN)
+          o.object_id
+        end
+        def make2
+          o = Object.new
+          ObjectSpace.define_finalizer(o, proc { |id| $fin << :never })
+          ObjectSpace.undefine_finalizer(o)
+        end
+        id = make
+        make2
+        ObjectSpace.garbage_collect
+        assert [id], $fin
+        "#;
+        assert_script(program);
+    }
+}
@@ -1,14 +1,630 @@
+use super::io::io_error;
 use crate::*;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+#[cfg(unix)]
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::process::{Child, Command, ExitStatus, Stdio};
 
 pub(crate) fn init(globals: &mut Globals) -> Value {
     let mut class = Module::class_under_object();
     class.set_const_by_str("CLOCK_MONOTONIC", Value::integer(0));
+    class.set_const_by_str("WNOHANG", Value::integer(WNOHANG));
+    class.set_const_by_str("WUNTRACED", Value::integer(WUNTRACED));
     globals.set_toplevel_constant("Process", class);
     class.add_builtin_class_method(globals, "clock_gettime", clock_gettime);
     class.add_builtin_class_method(globals, "pid", pid);
+    class.add_builtin_class_method(globals, "spawn", spawn);
+    class.add_builtin_class_method(globals, "wait", wait);
+    class.add_builtin_class_method(globals, "waitpid", wait);
+    class.add_builtin_class_method(globals, "wait2", wait2);
+    class.add_builtin_class_method(globals, "waitpid2", wait2);
+    class.add_builtin_class_method(globals, "waitall", waitall);
+    class.add_builtin_class_method(globals, "kill", kill);
+    class.add_builtin_class_method(globals, "last_status", last_status);
+
+    let status = Module::class_under_object();
+    class.set_const_by_str("Status", status.into());
+    status.add_builtin_method_by_str(globals, "pid", status_pid);
+    status.add_builtin_method_by_str(globals, "exitstatus", exitstatus);
+    status.add_builtin_method_by_str(globals, "success?", success);
+    status.add_builtin_method_by_str(globals, "exited?", exited);
+    status.add_builtin_method_by_str(globals, "signaled?", signaled);
+    status.add_builtin_method_by_str(globals, "termsig", termsig);
+    status.add_builtin_method_by_str(globals, "stopped?", stopped);
+    status.add_builtin_method_by_str(globals, "to_i", status_to_i);
+    status.add_builtin_method_by_str(globals, "==", status_eq);
+    status.add_builtin_method_by_str(globals, "to_s", status_to_s);
+    status.add_builtin_method_by_str(globals, "inspect", status_inspect);
+
+    let open3 = Module::module();
+    globals.set_toplevel_constant("Open3", open3);
+    open3.add_builtin_module_func(globals, "capture2", capture2);
+    open3.add_builtin_module_func(globals, "capture2e", capture2e);
+    open3.add_builtin_module_func(globals, "capture3", capture3);
     class.into()
 }
 
+#[cfg(unix)]
+const WNOHANG: i64 = libc::WNOHANG as i64;
+#[cfg(unix)]
+const WUNTRACED: i64 = libc::WUNTRACED as i64;
+#[cfg(not(unix))]
+const WNOHANG: i64 = 1;
+#[cfg(not(unix))]
+const WUNTRACED: i64 = 2;
+
+/// Flush the standard streams so that the outputs of a child process come after them.
+fn flush_std() {
+    let _ = io::stdout().flush();
+    let _ = io::stderr().flush();
+}
+
+/// Where a standard stream of a child process goes.
+enum Redirect {
+    File(File),
+    Null,
+    /// The standard output of this process.
+    Stdout,
+    /// The standard error of this process.
+    Stderr,
+    /// The standard output of the child.
+    ChildOut,
+}
+
+/// A command line given to `system`, `spawn`, `exec`, `IO.popen` and Open3.
+pub(crate) struct Spawn {
+    pub(crate) command: Command,
+    /// The program name shown in error messages.
+    name: String,
+    /// True if the standard error goes to the standard output of the child.
+    err_to_out: bool,
+}
+
+impl Spawn {
+    /// Build a command from the arguments `[env,] command... [,options]`.
+    ///
+    /// A single command string is run by the shell if it contains meta characters,
+    /// otherwise it is split into words and executed directly.
+    /// Keys in `extra` are options of the caller and ignored here.
+    pub(crate) fn from_args(
+        vm: &mut VM,
+        args: &[Value],
+        opts: Value,
+        extra: &[&str],
+    ) -> Result<Self, RubyError> {
+        let mut args = args;
+        let env = match args.first().map(|v| v.as_hash().is_some()) {
+            Some(true) => {
+                let env = args[0];
+                args = &args[1..];
+                Some(env)
+            }
+            _ => None,
+        };
+        let (mut command, name) = match args {
+            [] => return Err(RubyError::argument_wrong(0, 1)),
+            [cmd] if cmd.as_array().is_none() => {
+                let mut cmd = *cmd;
+                let cmd = cmd.expect_string("command")?;
+                (shell_command(cmd), cmd.to_string())
+            }
+            [cmd, rest @ ..] => {
+                // [program, argv0] sets the program name shown to the child, which is ignored here.
+                let mut program = match cmd.as_array() {
+                    Some(ary) if ary.len() == 2 => ary[0],
+                    Some(_) => return Err(RubyError::argument("wrong first argument")),
+                    None => *cmd,
+                };
+                let name = program.expect_string("command")?.to_string();
+                let mut command = Command::new(&name);
+                for arg in rest {
+                    let mut arg = *arg;
+                    command.arg(arg.expect_string("argument")?);
+                }
+                (command, name)
+            }
+        };
+        if let Some(env) = env {
+            for (k, v) in env.as_hash().unwrap().iter() {
+                let key = k.val_to_s(vm)?.into_owned();
+                if v.is_nil() {
+                    command.env_remove(key);
+                } else {
+                    command.env(key, v.val_to_s(vm)?.as_ref());
+                }
+            }
+        }
+        let mut spawn = Spawn {
+            command,
+            name,
+            err_to_out: false,
+        };
+        if let Some(opts) = opts.as_hash() {
+            let opts: Vec<_> = opts.iter().collect();
+            spawn.set_options(vm, &opts, extra)?;
+        }
+        Ok(spawn)
+    }
+
+    fn set_options(
+        &mut self,
+        vm: &mut VM,
+        opts: &[(Value, Value)],
+        extra: &[&str],
+    ) -> Result<(), RubyError> {
+        let mut out = None;
+        let mut err = None;
+        for (key, val) in opts {
+            match key.as_symbol().map(|id| id.get_name()).as_deref() {
+                Some(name) if extra.contains(&name) => continue,
+                Some("chdir") => {
+                    self.command.current_dir(val.val_to_s(vm)?.as_ref());
+                    continue;
+                }
+                Some("unsetenv_others") => {
+                    if val.to_bool() {
+                        self.command.env_clear();
+                    }
+                    continue;
+                }
+                Some("umask") | Some("pgroup") | Some("close_others") => continue,
+                _ => {}
+            }
+            let fds = match key.as_array() {
+                Some(ary) => ary.iter().cloned().collect(),
+                None => vec![*key],
+            };
+            for fd in fds {
+                match std_fd(fd) {
+                    Some(0) => {
+                        let stdin = match redirect_target(vm, *val, true)? {
+                            Redirect::File(file) => Stdio::from(file),
+                            Redirect::Null => Stdio::null(),
+                            _ => Stdio::inherit(),
+                        };
+                        self.command.stdin(stdin);
+                    }
+                    Some(1) => out = Some(redirect_target(vm, *val, false)?),
+                    Some(2) => err = Some(redirect_target(vm, *val, false)?),
+                    _ => {
+                        return Err(RubyError::argument(format!(
+                            "wrong exec option: {}",
+                            vm.val_inspect(*key)?
+                        )))
+                    }
+                }
+            }
+        }
+        if let Some(Redirect::ChildOut) = out {
+            out = None;
+        }
+        let err = match err {
+            Some(Redirect::ChildOut) => {
+                self.err_to_out = true;
+                match &out {
+                    Some(Redirect::File(file)) => Some(Redirect::File(
+                        file.try_clone().map_err(|err| io_error(err, None))?,
+                    )),
+                    Some(Redirect::Null) => Some(Redirect::Null),
+                    Some(Redirect::Stderr) => Some(Redirect::Stderr),
+                    _ => Some(Redirect::Stdout),
+                }
+            }
+            err => err,
+        };
+        if let Some(out) = out {
+            self.command.stdout(out.into_stdio());
+        }
+        if let Some(err) = err {
+            self.command.stderr(err.into_stdio());
+        }
+        Ok(())
+    }
+
+    /// Send the standard output (and the standard error if it was redirected to the
+    /// standard output) of the child to a pipe, and return the reading end.
+    pub(crate) fn pipe_stdout(&mut self) -> Result<io::PipeReader, RubyError> {
+        let (reader, writer) = io::pipe().map_err(|err| io_error(err, None))?;
+        if self.err_to_out {
+            let writer = writer.try_clone().map_err(|err| io_error(err, None))?;
+            self.command.stderr(writer);
+        }
+        self.command.stdout(writer);
+        Ok(reader)
+    }
+
+    /// Start the child process.
+    ///
+    /// The command is dropped here so that the writing ends of pipes given to the child
+    /// are closed in this process.
+    pub(crate) fn spawn(mut self) -> Result<Child, RubyError> {
+        flush_std();
+        self.command
+            .spawn()
+            .map_err(|err| io_error(err, Some(&self.name)))
+    }
+}
+
+impl Redirect {
+    fn into_stdio(self) -> Stdio {
+        match self {
+            Redirect::File(file) => Stdio::from(file),
+            Redirect::Null => Stdio::null(),
+            Redirect::Stdout => Stdio::from(io::stdout()),
+            Redirect::Stderr => Stdio::from(io::stderr()),
+            Redirect::ChildOut => Stdio::inherit(),
+        }
+    }
+}
+
+/// The file descriptor number of a redirection key such as `:out`, `2` and `STDERR`.
+fn std_fd(mut val: Value) -> Option<i64> {
+    if let Some(i) = val.as_fixnum() {
+        return Some(i);
+    }
+    if let Some(id) = val.as_symbol() {
+        return match id.get_name().as_str() {
+            "in" => Some(0),
+            "out" => Some(1),
+            "err" => Some(2),
+            _ => None,
+        };
+    }
+    val.as_io().and_then(|info| info.std_fileno())
+}
+
+/// Convert the target of a redirection, which is a path, `[path, mode, perm]`,
+/// an IO, `:close`, or a standard stream given as `:out`, `[:child, :out]` etc.
+fn redirect_target(vm: &mut VM, mut val: Value, input: bool) -> Result<Redirect, RubyError> {
+    if let Some(id) = val.as_symbol() {
+        if id == IdentId::get_id("close") {
+            return Ok(Redirect::Null);
+        }
+    }
+    if let Some(ary) = val.as_array() {
+        if ary.len() == 2 && ary[0].as_symbol() == Some(IdentId::get_id("child")) {
+            return match std_fd(ary[1]) {
+                Some(1) => Ok(Redirect::ChildOut),
+                _ => Err(RubyError::argument(format!(
+                    "wrong exec redirect: {}",
+                    vm.val_inspect(val)?
+                ))),
+            };
+        }
+    }
+    if let Some(info) = val.as_io() {
+        if let Some(fd) = info.std_fileno() {
+            return Ok(match fd {
+                1 => Redirect::Stdout,
+                2 => Redirect::Stderr,
+                _ => Redirect::ChildOut,
+            });
+        }
+        return Ok(Redirect::File(info.try_clone_file()?));
+    }
+    match std_fd(val) {
+        Some(1) => return Ok(Redirect::Stdout),
+        Some(2) => return Ok(Redirect::Stderr),
+        _ => {}
+    }
+    let (path, mode) = match val.as_array() {
+        Some(ary) if !ary.is_empty() => (ary[0], ary.get(1).cloned()),
+        _ => (val, None),
+    };
+    let path = path.val_to_s(vm)?.into_owned();
+    let mut options = OpenOptions::new();
+    let mode = match mode {
+        Some(mode) => mode.val_to_s(vm)?.into_owned(),
+        None if input => "r".to_string(),
+        None => "w".to_string(),
+    };
+    match mode.chars().next() {
+        Some('a') => options.append(true).create(true),
+        Some('w') => options.write(true).truncate(true).create(true),
+        _ => options.read(true),
+    };
+    let file = options
+        .open(&path)
+        .map_err(|err| io_error(err, Some(&path)))?;
+    Ok(Redirect::File(file))
+}
+
+/// Build a command from a command line given as a single string.
+pub(crate) fn shell_command(cmd: &str) -> Command {
+    const META: &[char] = &[
+        '*', '?', '{', '}', '[', ']', '<', '>', '(', ')', '~', '&', '|', '\\', '$', ';', '\'', '`',
+        '"', '\n', '#', '=', '%',
+    ];
+    const RESERVED: &[&str] = &[
+        "case", "do", "done", "elif", "else", "esac", "fi", "for", "function", "if", "in",
+        "select", "then", "until", "while", "!", ".", ":", "break", "cd", "continue", "eval",
+        "exec", "exit", "export", "readonly", "return", "set", "shift", "times", "trap", "unset",
+    ];
+    let mut words = cmd.split_whitespace();
+    match words.next() {
+        Some(program) if !cmd.contains(META) && !RESERVED.contains(&program) => {
+            let mut command = Command::new(program);
+            command.args(words);
+            command
+        }
+        _ => {
+            let (shell, opt) = if cfg!(windows) {
+                ("cmd", "/C")
+            } else {
+                ("/bin/sh", "-c")
+            };
+            let mut command = Command::new(shell);
+            command.args([opt, cmd]);
+            command
+        }
+    }
+}
+
+/// Get a Hash value from the keyword arguments.
+fn kw_value(args: &Args2, name: &str) -> Option<Value> {
+    args.kw_arg
+        .as_hash()
+        .and_then(|hash| hash.get(&Value::symbol_from_str(name)).cloned())
+}
+
+// Process::Status
+
+/// The raw wait status of a terminated child.
+#[cfg(unix)]
+fn raw_status(status: ExitStatus) -> i32 {
+    status.into_raw()
+}
+
+#[cfg(not(unix))]
+fn raw_status(status: ExitStatus) -> i32 {
+    status.code().unwrap_or(1) << 8
+}
+
+/// Create a Process::Status object of the process `pid` which finished with the wait status `status`.
+pub(crate) fn new_status(globals: &Globals, pid: u32, status: i32) -> Value {
+    let class = globals
+        .get_toplevel_constant("Process")
+        .into_module()
+        .get_const_noautoload(IdentId::get_id("Status"))
+        .unwrap()
+        .into_module();
+    let val = Value::ordinary_object(class);
+    val.set_var_by_str("/pid", Value::integer(pid as i64));
+    val.set_var_by_str("/status", Value::integer(status as i64));
+    val
+}
+
+/// Set `$?` to the status of the process which terminated last, and return the status.
+pub(crate) fn set_last_status(vm: &mut VM, pid: u32, status: ExitStatus) -> Value {
+    let status = new_status(&vm.globals, pid, raw_status(status));
+    vm.globals.set_global_var_by_str("$?", status);
+    status
+}
+
+/// Get (pid, wait status) of Process::Status.
+fn status_info(val: Value) -> (i64, i32) {
+    let get = |name: &str| {
+        val.get_var(IdentId::get_id(name))
+            .and_then(|v| v.as_fixnum())
+            .unwrap_or(0)
+    };
+    (get("/pid"), get("/status") as i32)
+}
+
+#[cfg(unix)]
+fn exit_code(status: i32) -> Option<i32> {
+    if libc::WIFEXITED(status) {
+        Some(libc::WEXITSTATUS(status))
+    } else {
+        None
+    }
+}
+
+#[cfg(not(unix))]
+fn exit_code(status: i32) -> Option<i32> {
+    Some(status >> 8)
+}
+
+#[cfg(unix)]
+fn term_signal(status: i32) -> Option<i32> {
+    if libc::WIFSIGNALED(status) {
+        Some(libc::WTERMSIG(status))
+    } else {
+        None
+    }
+}
+
+#[cfg(not(unix))]
+fn term_signal(_: i32) -> Option<i32> {
+    None
+}
+
+#[cfg(unix)]
+fn is_stopped(status: i32) -> bool {
+    libc::WIFSTOPPED(status)
+}
+
+#[cfg(not(unix))]
+fn is_stopped(_: i32) -> bool {
+    false
+}
+
+/// Process::Status#pid -> Integer
+fn status_pid(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::integer(status_info(self_val).0))
+}
+
+/// Process::Status#exitstatus -> Integer | nil
+fn exitstatus(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(match exit_code(status_info(self_val).1) {
+        Some(code) => Value::integer(code as i64),
+        None => Value::nil(),
+    })
+}
+
+/// Process::Status#success? -> bool | nil
+fn success(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(match exit_code(status_info(self_val).1) {
+        Some(code) => Value::bool(code == 0),
+        None => Value::nil(),
+    })
+}
+
+/// Process::Status#exited? -> bool
+fn exited(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::bool(exit_code(status_info(self_val).1).is_some()))
+}
+
+/// Process::Status#signaled? -> bool
+fn signaled(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::bool(term_signal(status_info(self_val).1).is_some()))
+}
+
+/// Process::Status#termsig -> Integer | nil
+fn termsig(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(match term_signal(status_info(self_val).1) {
+        Some(sig) => Value::integer(sig as i64),
+        None => Value::nil(),
+    })
+}
+
+/// Process::Status#stopped? -> bool
+fn stopped(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::bool(is_stopped(status_info(self_val).1)))
+}
+
+/// Process::Status#to_i -> Integer
+fn status_to_i(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::integer(status_info(self_val).1 as i64))
+}
+
+/// Process::Status#==(other) -> bool
+fn status_eq(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let status = status_info(self_val).1 as i64;
+    let other = vm[0];
+    let b = match other.as_fixnum() {
+        Some(i) => i == status,
+        None => {
+            other.get_class_name() == "Process::Status" && status_info(other).1 as i64 == status
+        }
+    };
+    Ok(Value::bool(b))
+}
+
+fn status_string(val: Value) -> String {
+    let (pid, status) = status_info(val);
+    match (exit_code(status), term_signal(status)) {
+        (Some(code), _) => format!("pid {} exit {}", pid, code),
+        (_, Some(sig)) => match signal_name(sig) {
+            Some(name) => format!("pid {} SIG{} (signal {})", pid, name, sig),
+            None => format!("pid {} signal {}", pid, sig),
+        },
+        _ => format!("pid {} status {}", pid, status),
+    }
+}
+
+/// Process::Status#to_s -> String
+fn status_to_s(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::string(status_string(self_val)))
+}
+
+/// Process::Status#inspect -> String
+fn status_inspect(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::string(format!(
+        "#<Process::Status: {}>",
+        status_string(self_val)
+    )))
+}
+
+// Signals
+
+#[cfg(unix)]
+const SIGNALS: &[(&str, i32)] = &[
+    ("HUP", libc::SIGHUP),
+    ("INT", libc::SIGINT),
+    ("QUIT", libc::SIGQUIT),
+    ("ILL", libc::SIGILL),
+    ("TRAP", libc::SIGTRAP),
+    ("ABRT", libc::SIGABRT),
+    ("IOT", libc::SIGIOT),
+    ("BUS", libc::SIGBUS),
+    ("FPE", libc::SIGFPE),
+    ("KILL", libc::SIGKILL),
+    ("USR1", libc::SIGUSR1),
+    ("SEGV", libc::SIGSEGV),
+    ("USR2", libc::SIGUSR2),
+    ("PIPE", libc::SIGPIPE),
+    ("ALRM", libc::SIGALRM),
+    ("TERM", libc::SIGTERM),
+    ("CHLD", libc::SIGCHLD),
+    ("CONT", libc::SIGCONT),
+    ("STOP", libc::SIGSTOP),
+    ("TSTP", libc::SIGTSTP),
+    ("TTIN", libc::SIGTTIN),
+    ("TTOU", libc::SIGTTOU),
+    ("URG", libc::SIGURG),
+    ("XCPU", libc::SIGXCPU),
+    ("XFSZ", libc::SIGXFSZ),
+    ("VTALRM", libc::SIGVTALRM),
+    ("PROF", libc::SIGPROF),
+    ("WINCH", libc::SIGWINCH),
+    ("IO", libc::SIGIO),
+    ("SYS", libc::SIGSYS),
+];
+
+#[cfg(not(unix))]
+const SIGNALS: &[(&str, i32)] = &[("INT", 2), ("KILL", 9), ("TERM", 15)];
+
+fn signal_name(sig: i32) -> Option<&'static str> {
+    SIGNALS
+        .iter()
+        .find(|(_, num)| *num == sig)
+        .map(|(name, _)| *name)
+}
+
+/// Convert a signal given as an Integer, or a name such as "TERM", "SIGTERM" and :TERM.
+fn signal_number(val: Value) -> Result<i32, RubyError> {
+    if let Some(i) = val.as_fixnum() {
+        return Ok(i as i32);
+    }
+    let name = match val.as_symbol() {
+        Some(id) => id.get_name(),
+        None => match val.as_string() {
+            Some(s) => s.to_string(),
+            None => {
+                return Err(RubyError::argument(format!(
+                    "bad signal type {}",
+                    val.get_class_name()
+                )))
+            }
+        },
+    };
+    let (neg, name) = match name.strip_prefix('-') {
+        Some(name) => (true, name),
+        None => (false, name.as_str()),
+    };
+    let short = name.strip_prefix("SIG").unwrap_or(name);
+    match SIGNALS.iter().find(|(n, _)| *n == short) {
+        Some((_, num)) => Ok(if neg { -num } else { *num }),
+        None => Err(RubyError::argument(format!(
+            "unsupported signal 'SIG{}'",
+            short
+        ))),
+    }
+}
+
 // Class methods
 
 fn clock_gettime(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
@@ -19,7 +635,252 @@ fn clock_gettime(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
 
 fn pid(_: &mut VM, _: Value, args: &Args2) -> VMResult {
     args.check_args_num(0)?;
-    Ok(Value::integer(std::process::id as i64))
+    Ok(Value::integer(std::process::id() as i64))
+}
+
+/// Process.spawn([env,] command... [,options]) -> Integer
+/// Kernel#spawn([env,] command... [,options]) -> Integer
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Kernel/m/spawn.html
+pub(crate) fn spawn(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_min(1)?;
+    let cmd_args = vm.args().to_vec();
+    let child = Spawn::from_args(vm, &cmd_args, args.kw_arg, &[])?.spawn()?;
+    Ok(Value::integer(child.id() as i64))
+}
+
+/// Kernel#system([env,] command... [,options], exception: false) -> true | false | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Kernel/m/system.html
+pub(crate) fn system(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_min(1)?;
+    let exception = kw_value(args, "exception").is_some_and(|v| v.to_bool());
+    let cmd_args = vm.args().to_vec();
+    let spawn = Spawn::from_args(vm, &cmd_args, args.kw_arg, &["exception"])?;
+    let name = spawn.name.clone();
+    let mut child = match spawn.spawn() {
+        Ok(child) => child,
+        Err(err) if exception => return Err(err),
+        Err(_) => {
+            #[cfg(unix)]
+            set_last_status(vm, 0, ExitStatus::from_raw(127 << 8));
+            return Ok(Value::nil());
+        }
+    };
+    let status = child.wait().map_err(|err| io_error(err, None))?;
+    set_last_status(vm, child.id(), status);
+    if status.success() {
+        Ok(Value::true_val())
+    } else if exception {
+        let status = match (status.code(), term_signal(raw_status(status))) {
+            (Some(code), _) => format!("exit {}", code),
+            (_, Some(sig)) => format!("signal {}", sig),
+            _ => "unknown status".to_string(),
+        };
+        Err(RubyError::runtime(format!(
+            "Command failed with {}: {}",
+            status, name
+        )))
+    } else {
+        Ok(Value::false_val())
+    }
+}
+
+/// Kernel#exec([env,] command... [,options]) -> ()
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Kernel/m/exec.html
+pub(crate) fn exec(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_min(1)?;
+    let cmd_args = vm.args().to_vec();
+    let mut spawn = Spawn::from_args(vm, &cmd_args, args.kw_arg, &[])?;
+    flush_std();
+    #[cfg(unix)]
+    let err = spawn.command.exec();
+    #[cfg(not(unix))]
+    let err = match spawn.command.status() {
+        Ok(status) => std::process::exit(status.code().unwrap_or(1)),
+        Err(err) => err,
+    };
+    Err(io_error(err, Some(&spawn.name)))
+}
+
+/// Wait for the child `pid` and return (pid, wait status), or None if WNOHANG is given
+/// and no child has exited.
+#[cfg(unix)]
+fn waitpid(pid: i64, flags: i64) -> Result<Option<(u32, i32)>, RubyError> {
+    let mut status = 0;
+    loop {
+        let res = unsafe { libc::waitpid(pid as libc::pid_t, &mut status, flags as libc::c_int) };
+        if res > 0 {
+            return Ok(Some((res as u32, status)));
+        } else if res == 0 {
+            return Ok(None);
+        }
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::Interrupted {
+            return Err(io_error(err, None));
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn waitpid(_: i64, _: i64) -> Result<Option<(u32, i32)>, RubyError> {
+    Err(RubyError::errno("ENOSYS", "Function not implemented"))
+}
+
+/// Wait for a child process with the arguments `(pid = -1, flags = 0)`, and set `$?`.
+fn wait_child(vm: &mut VM, args: &Args2) -> Result<Option<(u32, Value)>, RubyError> {
+    args.check_args_range(0, 2)?;
+    let pid = match args.len() {
+        0 => -1,
+        _ if vm[0].is_nil() => -1,
+        _ => vm[0].coerce_to_fixnum("pid")?,
+    };
+    let flags = match args.len() {
+        2 if !vm[1].is_nil() => vm[1].coerce_to_fixnum("flags")?,
+        _ => 0,
+    };
+    match waitpid(pid, flags)? {
+        Some((pid, status)) => {
+            let status = new_status(&vm.globals, pid, status);
+            vm.globals.set_global_var_by_str("$?", status);
+            Ok(Some((pid, status)))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Process.wait(pid = -1, flags = 0) -> Integer | nil
+/// Process.waitpid(pid = -1, flags = 0) -> Integer | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Process/m/wait.html
+fn wait(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    Ok(match wait_child(vm, args)? {
+        Some((pid, _)) => Value::integer(pid as i64),
+        None => Value::nil(),
+    })
+}
+
+/// Process.wait2(pid = -1, flags = 0) -> [Integer, Process::Status] | nil
+/// Process.waitpid2(pid = -1, flags = 0) -> [Integer, Process::Status] | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Process/m/wait2.html
+fn wait2(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    Ok(match wait_child(vm, args)? {
+        Some((pid, status)) => Value::array_from(vec![Value::integer(pid as i64), status]),
+        None => Value::nil(),
+    })
+}
+
+/// Process.waitall -> [[Integer, Process::Status]]
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Process/m/waitall.html
+fn waitall(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let mut res = vec![];
+    // Stops at ECHILD: no more children.
+    while let Ok(Some((pid, status))) = waitpid(-1, 0) {
+        let status = new_status(&vm.globals, pid, status);
+        vm.globals.set_global_var_by_str("$?", status);
+        res.push(Value::array_from(vec![Value::integer(pid as i64), status]));
+    }
+    Ok(Value::array_from(res))
+}
+
+/// Process.kill(signal, pid, *pids) -> Integer
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Process/m/kill.html
+fn kill(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_min(2)?;
+    let sig = signal_number(vm[0])?;
+    for i in 1..args.len() {
+        let pid = vm[i].coerce_to_fixnum("pid")?;
+        send_signal(pid, sig)?;
+    }
+    Ok(Value::integer(args.len() as i64 - 1))
+}
+
+#[cfg(unix)]
+fn send_signal(pid: i64, sig: i32) -> Result<(), RubyError> {
+    // A negative signal is sent to the process group.
+    let (pid, sig) = if sig < 0 { (-pid, -sig) } else { (pid, sig) };
+    if unsafe { libc::kill(pid as libc::pid_t, sig) } < 0 {
+        return Err(io_error(io::Error::last_os_error(), None));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn send_signal(_: i64, _: i32) -> Result<(), RubyError> {
+    Err(RubyError::errno("ENOSYS", "Function not implemented"))
+}
+
+/// Process.last_status -> Process::Status | nil
+fn last_status(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(vm.get_global_var(IdentId::get_id("$?")).unwrap_or_default())
+}
+
+// Open3
+
+/// Run a command with `stdin_data:` as its standard input, and collect its outputs.
+/// Returns (stdout, stderr, status). The standard error is merged into stdout if `merge` is true.
+fn capture(vm: &mut VM, args: &Args2, merge: bool) -> Result<(Value, Value, Value), RubyError> {
+    args.check_args_min(1)?;
+    let stdin_data = match kw_value(args, "stdin_data") {
+        Some(data) if !data.is_nil() => data.val_to_s(vm)?.as_bytes().to_vec(),
+        _ => vec![],
+    };
+    let cmd_args = vm.args().to_vec();
+    let mut spawn = Spawn::from_args(vm, &cmd_args, args.kw_arg, &["stdin_data", "binmode"])?;
+    spawn.command.stdin(Stdio::piped());
+    let reader = if merge {
+        spawn.err_to_out = true;
+        Some(spawn.pipe_stdout()?)
+    } else {
+        spawn.command.stdout(Stdio::piped()).stderr(Stdio::piped());
+        None
+    };
+    let mut child = spawn.spawn()?;
+    let mut stdin = child.stdin.take().unwrap();
+    let writer = std::thread::spawn(move || {
+        // The child may exit without reading its input.
+        let _ = stdin.write_all(&stdin_data);
+    });
+    let pid = child.id();
+    let err = |err| io_error(err, None);
+    let (out, errout, status) = match reader {
+        Some(mut reader) => {
+            let mut out = vec![];
+            reader.read_to_end(&mut out).map_err(err)?;
+            (out, vec![], child.wait().map_err(err)?)
+        }
+        None => {
+            let output = child.wait_with_output().map_err(err)?;
+            (output.stdout, output.stderr, output.status)
+        }
+    };
+    let _ = writer.join();
+    let status = set_last_status(vm, pid, status);
+    Ok((Value::bytes(out), Value::bytes(errout), status))
+}
+
+/// Open3.capture2([env,] command... [,options]) -> [String, Process::Status]
+fn capture2(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    let (out, _, status) = capture(vm, args, false)?;
+    Ok(Value::array_from(vec![out, status]))
+}
+
+/// Open3.capture2e([env,] command... [,options]) -> [String, Process::Status]
+fn capture2e(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    let (out, _, status) = capture(vm, args, true)?;
+    Ok(Value::array_from(vec![out, status]))
+}
+
+/// Open3.capture3([env,] command... [,options]) -> [String, String, Process::Status]
+fn capture3(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    let (out, err, status) = capture(vm, args, false)?;
+    Ok(Value::array_from(vec![out, err, status]))
 }
 
 #[cfg(test)]
@@ -35,4 +896,81 @@ mod tests {
         "#;
         assert_script(program);
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn process_system() {
+        let program = r##"
+        assert true, system("true")
+        assert true, $?.success?
+        assert false, system("exit 3")
+        assert 3, $?.exitstatus
+        assert false, $?.success?
+        assert false, $?.signaled?
+        assert Process::Status, $?.class
+        assert nil, system("no_such_command_xyz")
+        assert_error { system("no_such_command_xyz", exception: true) }
+        assert_error { system("exit 1", exception: true) }
+        assert true, system({"FOO" => "bar"}, "test \"$FOO\" = bar")
+        assert true, system("test", "-d", ".")
+        assert true, system("pwd", chdir: "/", out: File::NULL)
+
+        assert "hello\n", `echo hello`
+        assert 0, $?.exitstatus
+        `exit 5`
+        assert 5, $?.exitstatus
+        assert $?.pid, $?.pid.to_i
+        assert "pid #{$?.pid} exit 5", $?.to_s
+        assert "#<Process::Status: pid #{$?.pid} exit 5>", $?.inspect
+        "##;
+        assert_script(program);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn process_spawn_wait_kill() {
+        let program = r#"
+        pid = spawn("exit 7")
+        assert pid, Process.wait(pid)
+        assert 7, $?.exitstatus
+        assert pid, $?.pid
+        assert Process.last_status, $?
+
+        pid = Process.spawn("sleep", "10")
+        assert 1, Process.kill(:TERM, pid)
+        wpid, status = Process.wait2(pid)
+        assert pid, wpid
+        assert true, status.signaled?
+        assert 15, status.termsig
+        assert nil, status.exitstatus
+        assert nil, status.success?
+        assert "pid #{pid} SIGTERM (signal 15)", status.to_s
+
+        pid = spawn("sleep 10")
+        assert nil, Process.wait(pid, Process::WNOHANG)
+        Process.kill(:KILL, pid)
+        assert pid, Process.waitpid(pid)
+        assert_error { Process.wait(pid) }
+        assert_error { Process.kill("NOSUCHSIG", Process.pid) }
+        "#;
+        assert_script(program);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn open3() {
+        let program = r#"
+        require "open3"
+        out, status = Open3.capture2("tr a-z A-Z", stdin_data: "hello")
+        assert "HELLO", out
+        assert true, status.success?
+        out, err, status = Open3.capture3("echo out; echo err >&2; exit 2")
+        assert ["out\n", "err\n", 2], [out, err, status.exitstatus]
+        out, status = Open3.capture2e("echo out; echo err >&2")
+        assert "out\nerr\n", out
+        out, status = Open3.capture2("cat", "-")
+        assert "", out
+        "#;
+        assert_script(program);
+    }
 }
@@ -39,6 +39,7 @@ pub(crate) fn init(globals: &mut Globals) -> Value {
     class.add_builtin_method_by_str(globals, "inspect", inspect);
     class.add_builtin_method_by_str(globals, "call", proc_call);
     class.add_builtin_method_by_str(globals, "[]", proc_call);
+    class.add_builtin_method_by_str(globals, "===", proc_call);
 
     class.add_builtin_class_method(globals, "new", proc_new);
     class.into()
@@ -0,0 +1,268 @@
+///
+/// Queue and SizedQueue
+///
+/// Elements are held in a hidden Array instance variable.
+///
+use crate::*;
+
+pub(crate) fn init(globals: &mut Globals) {
+    let mut thread = globals.get_toplevel_constant("Thread").into_module();
+
+    let queue = Module::class_under_object();
+    globals.set_toplevel_constant("Queue", queue);
+    thread.set_const_by_str("Queue", queue.into());
+    queue.add_builtin_method_by_str(globals, "initialize", initialize);
+    queue.add_builtin_method_by_str(globals, "push", push);
+    queue.add_builtin_method_by_str(globals, "<<", push);
+    queue.add_builtin_method_by_str(globals, "enq", push);
+    queue.add_builtin_method_by_str(globals, "pop", pop);
+    queue.add_builtin_method_by_str(globals, "shift", pop);
+    queue.add_builtin_method_by_str(globals, "deq", pop);
+    queue.add_builtin_method_by_str(globals, "close", close);
+    queue.add_builtin_method_by_str(globals, "closed?", closed);
+    queue.add_builtin_method_by_str(globals, "empty?", empty);
+    queue.add_builtin_method_by_str(globals, "size", size);
+    queue.add_builtin_method_by_str(globals, "length", size);
+    queue.add_builtin_method_by_str(globals, "clear", clear);
+    queue.add_builtin_method_by_str(globals, "num_waiting", num_waiting);
+
+    let class = Module::class_under(queue);
+    globals.set_toplevel_constant("SizedQueue", class);
+    thread.set_const_by_str("SizedQueue", class.into());
+    class.add_builtin_method_by_str(globals, "initialize", sized_initialize);
+    class.add_builtin_method_by_str(globals, "max", max);
+    class.add_builtin_method_by_str(globals, "max=", set_max);
+}
+
+fn elements(queue: Value) -> Array {
+    match queue.get_var(IdentId::get_id("/queue")) {
+        Some(ary) => ary.into_array(),
+        None => {
+            let ary = Value::array_empty();
+            queue.set_var_by_str("/queue", ary);
+            ary.into_array()
+        }
+    }
+}
+
+fn is_closed(queue: Value) -> bool {
+    queue
+        .get_var(IdentId::get_id("/closed"))
+        .is_some_and(|v| v.to_bool())
+}
+
+/// The capacity of SizedQueue. None for Queue.
+fn capacity(queue: Value) -> Option<usize> {
+    queue
+        .get_var(IdentId::get_id("/max"))
+        .and_then(|v| v.as_fixnum())
+        .map(|i| i as usize)
+}
+
+/// Block the current thread until `cond` is satisfied, counting the waiting threads.
+fn wait(vm: &mut VM, queue: Value, cond: impl FnMut(&mut VM) -> bool) -> Result<(), RubyError> {
+    let id = IdentId::get_id("/waiting");
+    let count = |queue: Value| queue.get_var(id).and_then(|v| v.as_fixnum()).unwrap_or(0);
+    queue.set_var(id, Value::integer(count(queue) + 1));
+    let res = vm.thread_wait(None, cond);
+    queue.set_var(id, Value::integer(count(queue) - 1));
+    res.map(|_| ())
+}
+
+// Queue instance methods
+
+/// Queue.new(items = nil) -> Queue
+fn initialize(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
+    let ary = if args.len() == 1 {
+        let items = vm.eval_send0(IdentId::get_id("to_a"), vm[0])?;
+        items.expect_array("1st arg")?.to_vec()
+    } else {
+        vec![]
+    };
+    self_val.set_var_by_str("/queue", Value::array_from(ary));
+    Ok(Value::nil())
+}
+
+/// Queue#push(object) -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Thread=3a=3aQueue/i/push.html
+fn push(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(1, 2)?;
+    let val = vm[0];
+    let non_block = args.len() == 2 && vm[1].to_bool();
+    if let Some(max) = capacity(self_val) {
+        let full = |queue: Value| elements(queue).len() >= max && !is_closed(queue);
+        if full(self_val) {
+            if non_block {
+                return Err(RubyError::thread("queue full"));
+            }
+            wait(vm, self_val, |_| !full(self_val))?;
+        }
+    }
+    if is_closed(self_val) {
+        return Err(RubyError::closed_queue("queue closed"));
+    }
+    elements(self_val).push(val);
+    vm.globals.scheduler.event += 1;
+    Ok(self_val)
+}
+
+/// Queue#pop(non_block = false) -> object
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Thread=3a=3aQueue/i/pop.html
+fn pop(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
+    let non_block = args.len() == 1 && vm[0].to_bool();
+    let empty = |queue: Value| elements(queue).is_empty() && !is_closed(queue);
+    if empty(self_val) {
+        if non_block {
+            return Err(RubyError::thread("queue empty"));
+        }
+        wait(vm, self_val, |_| !empty(self_val))?;
+    }
+    let mut ary = elements(self_val);
+    if ary.is_empty() {
+        // The queue was closed.
+        return Ok(Value::nil());
+    }
+    let val = ary.drain(0..1)[0];
+    vm.globals.scheduler.event += 1;
+    Ok(val)
+}
+
+/// Queue#close -> self
+fn close(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    self_val.set_var_by_str("/closed", Value::true_val());
+    vm.globals.scheduler.event += 1;
+    Ok(self_val)
+}
+
+/// Queue#closed? -> bool
+fn closed(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::bool(is_closed(self_val)))
+}
+
+/// Queue#empty? -> bool
+fn empty(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::bool(elements(self_val).is_empty()))
+}
+
+/// Queue#size -> Integer
+fn size(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::integer(elements(self_val).len() as i64))
+}
+
+/// Queue#clear -> self
+fn clear(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    elements(self_val).clear();
+    vm.globals.scheduler.event += 1;
+    Ok(self_val)
+}
+
+/// Queue#num_waiting -> Integer
+fn num_waiting(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let count = self_val
+        .get_var(IdentId::get_id("/waiting"))
+        .unwrap_or(Value::integer(0));
+    Ok(count)
+}
+
+// SizedQueue instance methods
+
+fn expect_max(val: Value) -> Result<i64, RubyError> {
+    let max = val.coerce_to_fixnum("max")?;
+    if max <= 0 {
+        return Err(RubyError::argument("queue size must be positive"));
+    }
+    Ok(max)
+}
+
+/// SizedQueue.new(max) -> SizedQueue
+fn sized_initialize(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let max = expect_max(vm[0])?;
+    self_val.set_var_by_str("/queue", Value::array_empty());
+    self_val.set_var_by_str("/max", Value::integer(max));
+    Ok(Value::nil())
+}
+
+/// SizedQueue#max -> Integer
+fn max(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::integer(capacity(self_val).unwrap_or(0) as i64))
+}
+
+/// SizedQueue#max=(n)
+fn set_max(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let max = expect_max(vm[0])?;
+    self_val.set_var_by_str("/max", Value::integer(max));
+    vm.globals.scheduler.event += 1;
+    Ok(vm[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::*;
+
+    #[test]
+    fn queue() {
+        let program = r#"
+        q = Queue.new([1, 2])
+        assert 2, q.size
+        q << 3
+        assert 1, q.pop
+        assert [2, 3], [q.shift, q.deq]
+        assert true, q.empty?
+        assert_error { q.pop(true) }
+
+        producer = Thread.new { 5.times { |i| q.push(i); Thread.pass } ; q.close }
+        res = []
+        while v = q.pop
+          res << v
+        end
+        assert [0, 1, 2, 3, 4], res
+        assert true, q.closed?
+        assert_error { q.push(1) }
+        assert Queue, Thread::Queue
+
+        q = Queue.new
+        t = Thread.new { q.pop }
+        Thread.pass
+        assert 1, q.num_waiting
+        q << :done
+        assert :done, t.value
+        assert_error { Queue.new.pop }
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn sized_queue() {
+        let program = r#"
+        q = SizedQueue.new(2)
+        assert 2, q.max
+        log = []
+        t = Thread.new { 4.times { |i| q << i; log << "push#{i}" } }
+        Thread.pass
+        assert ["push0", "push1"], log
+        assert 0, q.pop
+        assert 1, q.pop
+        t.join
+        assert ["push0", "push1", "push2", "push3"], log
+        assert [2, 3], [q.pop, q.pop]
+        q << 1
+        q << 2
+        assert_error { q.push(3, true) }
+        assert_error { SizedQueue.new(0) }
+        "#;
+        assert_script(program);
+    }
+}
@@ -36,14 +36,12 @@ impl RangeInfo {
 }
 
 pub(crate) fn init(globals: &mut Globals) -> Value {
-    let class = Module::class_under_object();
+    let mut class = Module::class_under_object();
     globals.set_toplevel_constant("Range", class);
+    class.append_include_without_increment_version(BuiltinClass::enumerable());
     class.add_builtin_method_by_str(globals, "to_s", to_s);
     class.add_builtin_method_by_str(globals, "inspect", inspect);
-    class.add_builtin_method_by_str(globals, "map", map);
-    class.add_builtin_method_by_str(globals, "flat_map", flat_map);
     class.add_builtin_method_by_str(globals, "each", each);
-    class.add_builtin_method_by_str(globals, "all?", all);
     class.add_builtin_method_by_str(globals, "begin", begin);
     class.add_builtin_method_by_str(globals, "first", first);
     class.add_builtin_method_by_str(globals, "end", end);
@@ -51,6 +49,7 @@ pub(crate) fn init(globals: &mut Globals) -> Value {
     class.add_builtin_method_by_str(globals, "to_a", to_a);
     class.add_builtin_method_by_str(globals, "exclude_end?", exclude_end);
     class.add_builtin_method_by_str(globals, "include?", include);
+    class.add_builtin_method_by_str(globals, "===", include);
 
     class.add_builtin_class_method(globals, "new", range_new);
     class.into()
@@ -134,37 +133,6 @@ fn last(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     Ok(Value::array_from(v))
 }
 
-fn map(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
-    args.check_args_num(0)?;
-    let range = self_val.as_range().unwrap();
-    let block = args.expect_block()?;
-    let start = range.start.coerce_to_fixnum("Start")?;
-    let end = range.end.coerce_to_fixnum("End")? + if range.exclude { 0 } else { 1 };
-    let res = vm.eval_block_map1_iter(block, (start..end).map(|i| Value::integer(i)))?;
-    Ok(res)
-}
-
-fn flat_map(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
-    args.check_args_num(0)?;
-    let range = self_val.as_range().unwrap();
-    let block = args.expect_block()?;
-    let start = range.start.coerce_to_fixnum("Start")?;
-    let end = range.end.coerce_to_fixnum("End")? + if range.exclude { 0 } else { 1 };
-    let len = vm.temp_len();
-    let f = vm.eval_block_map1(block);
-    for i in start..end {
-        let val = f(vm, Value::integer(i))?;
-        match val.as_array() {
-            Some(aref) => {
-                vm.temp_extend_from_slice(&**aref);
-            }
-            None => vm.temp_push(val),
-        };
-    }
-    let res = Value::array_from(vm.temp_pop_vec(len));
-    Ok(res)
-}
-
 fn each(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(0)?;
     let range = self_val.as_range().unwrap();
@@ -184,22 +152,6 @@ fn each(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     vm.eval_block_each1_iter(block, iter, self_val)
 }
 
-fn all(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
-    args.check_args_num(0)?;
-    let range = self_val.as_range().unwrap();
-    let block = args.expect_block()?;
-    let start = range.start.coerce_to_fixnum("Start")?;
-    let end = range.end.coerce_to_fixnum("End")? + if range.exclude { 0 } else { 1 };
-    let f = vm.eval_block_map1(block);
-    for i in start..end {
-        let res = f(vm, Value::integer(i))?;
-        if !res.to_bool() {
-            return Ok(Value::false_val());
-        }
-    }
-    Ok(Value::true_val())
-}
-
 fn to_a(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(0)?;
     let RangeInfo {
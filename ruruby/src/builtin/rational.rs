@@ -0,0 +1,448 @@
+use crate::*;
+use num::{BigInt, BigRational, One, Signed, ToPrimitive, Zero};
+
+pub(crate) fn init(globals: &mut Globals) -> Value {
+    let class = Module::class_under(BuiltinClass::numeric());
+    globals.set_toplevel_constant("Rational", class);
+    class.add_builtin_method_by_str(globals, "numerator", numerator);
+    class.add_builtin_method_by_str(globals, "denominator", denominator);
+    class.add_builtin_method_by_str(globals, "quo", quo);
+    class.add_builtin_method_by_str(globals, "%", rem);
+    class.add_builtin_method_by_str(globals, "modulo", rem);
+    class.add_builtin_method_by_str(globals, "**", exp);
+    class.add_builtin_method_by_str(globals, "==", eq);
+    class.add_builtin_method_by_str(globals, "<=>", cmp);
+    class.add_builtin_method_by_str(globals, "+@", plus);
+    class.add_builtin_method_by_str(globals, "-@", minus);
+    class.add_builtin_method_by_str(globals, "abs", abs);
+    class.add_builtin_method_by_str(globals, "magnitude", abs);
+    class.add_builtin_method_by_str(globals, "zero?", zero);
+    class.add_builtin_method_by_str(globals, "positive?", positive);
+    class.add_builtin_method_by_str(globals, "negative?", negative);
+    class.add_builtin_method_by_str(globals, "integer?", integer);
+    class.add_builtin_method_by_str(globals, "to_s", to_s);
+    class.add_builtin_method_by_str(globals, "to_f", to_f);
+    class.add_builtin_method_by_str(globals, "to_i", truncate);
+    class.add_builtin_method_by_str(globals, "to_r", to_r);
+    class.add_builtin_method_by_str(globals, "truncate", truncate);
+    class.add_builtin_method_by_str(globals, "floor", floor);
+    class.add_builtin_method_by_str(globals, "ceil", ceil);
+    class.add_builtin_method_by_str(globals, "round", round);
+    class.add_builtin_method_by_str(globals, "rationalize", rationalize);
+    class.into()
+}
+
+/// Convert `val` to an exact rational.
+///
+/// Floats are converted without rounding, and strings are parsed as rational literals.
+pub(crate) fn to_rational(vm: &mut VM, val: Value) -> Result<BigRational, RubyError> {
+    if let Some(f) = val.as_float() {
+        return float_to_rational(f);
+    }
+    if let Some(r) = val.to_real() {
+        return Ok(r.into_rational());
+    }
+    if let Some(s) = val.as_string() {
+        return match parse_rational(s) {
+            Some(r) => Ok(r),
+            None => Err(RubyError::argument(format!(
+                "invalid value for convert(): {}",
+                vm.val_inspect(val)?
+            ))),
+        };
+    }
+    if val.is_nil() {
+        return Err(RubyError::typeerr("can't convert nil into Rational"));
+    }
+    Err(VMError::no_implicit_conv(val, "Rational"))
+}
+
+pub(crate) fn float_to_rational(f: f64) -> Result<BigRational, RubyError> {
+    match BigRational::from_float(f) {
+        Some(r) => Ok(r),
+        None => Err(RubyError::range(format!("{:?}", f))),
+    }
+}
+
+/// Parse a string such as "1/3", "-0.75" or "1e3" into a rational.
+fn parse_rational(s: &str) -> Option<BigRational> {
+    let s = s.trim();
+    match s.split_once('/') {
+        Some((numer, denom)) => {
+            let denom = parse_decimal(denom.trim())?;
+            if denom.is_zero() {
+                return None;
+            }
+            Some(parse_decimal(numer.trim())? / denom)
+        }
+        None => parse_decimal(s),
+    }
+}
+
+fn parse_decimal(s: &str) -> Option<BigRational> {
+    let s = s.replace('_', "");
+    let (s, negative) = match s.strip_prefix('-') {
+        Some(s) => (s, true),
+        None => (s.strip_prefix('+').unwrap_or(&s), false),
+    };
+    let (mantissa, exp) = match s.split_once(['e', 'E']) {
+        Some((m, e)) => (m, e.parse::<i32>().ok()?),
+        None => (s, 0),
+    };
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if int.is_empty() || !int.bytes().chain(frac.bytes()).all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let numer = BigInt::parse_bytes(format!("{}{}", int, frac).as_bytes(), 10)?;
+    let exp = exp - frac.len() as i32;
+    let scale = num::pow(BigInt::from(10), exp.unsigned_abs() as usize);
+    let r = if exp >= 0 {
+        BigRational::from_integer(numer * scale)
+    } else {
+        BigRational::new(numer, scale)
+    };
+    Some(if negative { -r } else { r })
+}
+
+/// Find the simplest rational in the closed interval [`a`, `b`].
+fn simplest_between(mut a: BigRational, mut b: BigRational) -> BigRational {
+    let (mut p0, mut p1) = (BigInt::zero(), BigInt::one());
+    let (mut q0, mut q1) = (BigInt::one(), BigInt::zero());
+    let c = loop {
+        let c = a.ceil();
+        if c < b {
+            break c.to_integer();
+        }
+        let k = c.to_integer() - 1;
+        let p2 = &k * &p1 + &p0;
+        let q2 = &k * &q1 + &q0;
+        let k = BigRational::from_integer(k);
+        let t = (&b - &k).recip();
+        b = (&a - &k).recip();
+        a = t;
+        p0 = std::mem::replace(&mut p1, p2);
+        q0 = std::mem::replace(&mut q1, q2);
+    };
+    BigRational::new(&c * p1 + p0, c * q1 + q0)
+}
+
+/// Find the simplest rational within `eps` of `r`.
+pub(crate) fn rationalize_with(r: &BigRational, eps: &BigRational) -> BigRational {
+    let eps = eps.abs();
+    let (a, b) = (r - &eps, r + &eps);
+    if a == b {
+        return r.clone();
+    }
+    simplest_between(a, b)
+}
+
+/// Find the simplest rational which rounds to the float `f`.
+pub(crate) fn rationalize_float(f: f64) -> Result<BigRational, RubyError> {
+    if !f.is_finite() {
+        return Err(RubyError::range(format!("{:?}", f)));
+    }
+    if f == 0.0 {
+        return Ok(BigRational::zero());
+    }
+    // Decompose `f` into `m * 2 ** n` with a 53-bit integer `m`.
+    let bits = f.to_bits();
+    let raw_exp = ((bits >> 52) & 0x7ff) as i32;
+    let mut mantissa = bits & 0xf_ffff_ffff_ffff;
+    let mut n = if raw_exp == 0 {
+        -1074
+    } else {
+        mantissa |= 1 << 52;
+        raw_exp - 1075
+    };
+    while mantissa & (1 << 52) == 0 {
+        mantissa <<= 1;
+        n -= 1;
+    }
+    let m = if f.is_sign_negative() {
+        -BigInt::from(mantissa)
+    } else {
+        BigInt::from(mantissa)
+    };
+    if n >= 0 {
+        return Ok(BigRational::from_integer(m << n as usize));
+    }
+    let den = BigInt::one() << (1 - n) as usize;
+    let a = BigRational::new(&m * 2 - 1, den.clone());
+    let b = BigRational::new(&m * 2 + 1, den);
+    Ok(simplest_between(a, b))
+}
+
+fn self_rational(self_val: Value) -> BigRational {
+    self_val.as_rational().unwrap().clone()
+}
+
+/// Apply rounding function `f` to `r` at `ndigits` decimal digits.
+///
+/// Returns an Integer when `ndigits` is omitted or not positive, otherwise a Rational.
+fn round_common(
+    vm: &mut VM,
+    r: &BigRational,
+    args: &Args2,
+    f: fn(&BigRational) -> BigRational,
+) -> VMResult {
+    args.check_args_range(0, 1)?;
+    let ndigits = if args.len() == 0 {
+        None
+    } else {
+        Some(vm[0].coerce_to_fixnum("ndigits")?)
+    };
+    match ndigits {
+        None => Ok(Real::integer(f(r).to_integer()).into_val()),
+        Some(n) => {
+            let scale =
+                BigRational::from_integer(num::pow(BigInt::from(10), n.unsigned_abs() as usize));
+            let scale = if n < 0 { scale.recip() } else { scale };
+            let res = f(&(r * &scale)) / scale;
+            if n > 0 {
+                Ok(Value::rational(res))
+            } else {
+                Ok(Real::integer(res.to_integer()).into_val())
+            }
+        }
+    }
+}
+
+// Instance methods
+
+fn numerator(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let r = self_val.as_rational().unwrap();
+    Ok(Value::bignum(r.numer().clone()))
+}
+
+fn denominator(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let r = self_val.as_rational().unwrap();
+    Ok(Value::bignum(r.denom().clone()))
+}
+
+fn quo(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let arg0 = vm[0];
+    match arg0.to_real() {
+        Some(rhs) => {
+            if rhs.is_zero() {
+                return Err(RubyError::zero_div("Divided by zero."));
+            }
+            Ok((Real::Rational(self_rational(self_val)) / rhs).into_val())
+        }
+        None => Err(VMError::cant_coerse(arg0, "Rational")),
+    }
+}
+
+fn rem(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    arith::rem_rational(self_val.as_rational().unwrap(), vm[0])
+}
+
+fn exp(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    arith::exp_rational(self_val.as_rational().unwrap(), vm[0])
+}
+
+fn eq(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let lhs = Real::Rational(self_rational(self_val));
+    match vm[0].to_real() {
+        Some(rhs) => Ok(Value::bool(lhs == rhs)),
+        None => Ok(Value::bool(false)),
+    }
+}
+
+fn cmp(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let res = arith::cmp_rational(self_val.as_rational().unwrap(), vm[0]);
+    Ok(Value::from_ord(res))
+}
+
+fn plus(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(self_val)
+}
+
+fn minus(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::rational(-self_rational(self_val)))
+}
+
+fn abs(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::rational(self_val.as_rational().unwrap().abs()))
+}
+
+fn zero(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::bool(self_val.as_rational().unwrap().is_zero()))
+}
+
+fn positive(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::bool(self_val.as_rational().unwrap().is_positive()))
+}
+
+fn negative(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::bool(self_val.as_rational().unwrap().is_negative()))
+}
+
+fn integer(_: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::false_val())
+}
+
+fn to_s(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let r = self_val.as_rational().unwrap();
+    Ok(Value::string(format!("{}/{}", r.numer(), r.denom())))
+}
+
+fn to_f(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let r = self_val.as_rational().unwrap();
+    Ok(Value::float(r.to_f64().unwrap()))
+}
+
+fn to_r(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(self_val)
+}
+
+fn truncate(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    round_common(vm, &self_rational(self_val), args, BigRational::trunc)
+}
+
+fn floor(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    round_common(vm, &self_rational(self_val), args, BigRational::floor)
+}
+
+fn ceil(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    round_common(vm, &self_rational(self_val), args, BigRational::ceil)
+}
+
+fn round(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    round_common(vm, &self_rational(self_val), args, BigRational::round)
+}
+
+/// rationalize([eps]) -> Rational
+fn rationalize(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
+    if args.len() == 0 {
+        return Ok(self_val);
+    }
+    let eps = to_rational(vm, vm[0])?;
+    let r = self_val.as_rational().unwrap();
+    Ok(Value::rational(rationalize_with(r, &eps)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::*;
+
+    #[test]
+    fn rational_literal() {
+        let program = r#"
+        assert(Rational, 3r.class)
+        assert("(3/1)", 3r.inspect)
+        assert("(3/2)", 1.5r.inspect)
+        assert("(-1/4)", -0.25r.inspect)
+        assert("1/3", (1/3r).to_s)
+        assert(1, 3r.numerator / 3)
+        assert(10, 0.3r.denominator)
+        assert(Rational(1, 3), 1/3r)
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn rational_arith() {
+        let program = r#"
+        assert(Rational(5, 6), 1/2r + 1/3r)
+        assert(Rational(1, 6), 1/2r - 1/3r)
+        assert(Rational(1, 6), 1/2r * 1/3r)
+        assert(Rational(3, 2), (1/2r) / (1/3r))
+        assert(Rational(7, 2), 3 + 1/2r)
+        assert(Rational(7, 2), 1/2r + 3)
+        assert(3.5, 3.0 + 1/2r)
+        assert(3.5, 1/2r + 3.0)
+        assert(Rational(2, 3), 2.quo(3r))
+        assert(Rational(1, 4), (1/2r) ** 2)
+        assert(Rational(4, 1), (1/2r) ** -2)
+        assert(Rational(1, 8), 2 ** -3)
+        assert(2.0, 4 ** (1/2r))
+        assert(Rational(1, 6), (7/6r) % 1)
+        assert(Complex.rect(Rational(3, 2), 1), 1/2r + (1+1i))
+        assert_error { 1/2r / 0 }
+        assert_error { 1/2r + :a }
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn rational_cmp() {
+        let program = r#"
+        assert(true, 1/2r == 0.5)
+        assert(true, 2r == 2)
+        assert(true, 2 == 2r)
+        assert(false, 1/2r == :a)
+        assert(true, 1/3r < 1/2r)
+        assert(true, 1/3r > 0.3)
+        assert(true, 0 < 1/3r)
+        assert(-1, 1/3r <=> 1/2r)
+        assert(1, 1 <=> 1/2r)
+        assert(-1, 0.3 <=> 1/2r)
+        assert(nil, 1/3r <=> :a)
+        assert(1, {1/2r => 1}[Rational(2, 4)])
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn rational_conversion() {
+        let program = r#"
+        assert(0.5, (1/2r).to_f)
+        assert(3, (7/2r).to_i)
+        assert(-3, (-7/2r).truncate)
+        assert(-4, (-7/2r).floor)
+        assert(4, (7/2r).ceil)
+        assert(4, (7/2r).round)
+        assert(-4, (-7/2r).round)
+        assert(Rational(157, 50), Rational(22, 7).round(2))
+        assert(Rational(1, 3), Rational(1, 3).abs)
+        assert(Rational(1, 3), Rational(-1, 3).abs)
+        assert(true, 0r.zero?)
+        assert(true, (-1/3r).negative?)
+        assert(Rational(3, 1), 3.to_r)
+        assert(Rational(1, 2), 0.5.to_r)
+        assert(Rational(3602879701896397, 36028797018963968), 0.1.to_r)
+        assert(Rational(1, 10), 0.1.rationalize)
+        assert(Rational(1, 3), 0.333.rationalize(Rational(1, 100)))
+        assert(Rational(-1, 3), -0.333.rationalize(Rational(1, 100)))
+        assert(Rational(1, 3), Rational(333, 1000).rationalize(Rational(1, 100)))
+        assert(Rational(5, 1), 5.rationalize)
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn kernel_rational() {
+        let program = r#"
+        assert(Rational(1, 2), Rational(2, 4))
+        assert(Rational(3, 1), Rational(3))
+        assert(Rational(1, 2), Rational(0.5))
+        assert(Rational(1, 3), Rational("1/3"))
+        assert(Rational(-3, 4), Rational(" -0.75 "))
+        assert(Rational(1000, 1), Rational("1e3"))
+        assert(Rational(3, 4), Rational(1/2r, 2/3r))
+        assert("(-1/2)", Rational(1, -2).inspect)
+        assert_error { Rational(1, 0) }
+        assert_error { Rational("x") }
+        assert_error { Rational(nil) }
+        "#;
+        assert_script(program);
+    }
+}
@@ -0,0 +1,584 @@
+///
+/// Set class
+///
+/// Elements are held in `SetInfo`, which uses the same hashing and equality
+/// as keys of Hash (`eql?` and `hash`), and keeps insertion order.
+///
+use crate::*;
+
+pub(crate) fn init(globals: &mut Globals) {
+    let mut class = Module::class_under_object();
+    globals.set_toplevel_constant("Set", class);
+    class.append_include_without_increment_version(BuiltinClass::enumerable());
+    class.add_builtin_class_method(globals, "new", set_new);
+    class.add_builtin_class_method(globals, "[]", set_elems);
+    class.add_builtin_method_by_str(globals, "initialize", initialize);
+    class.add_builtin_method_by_str(globals, "add", add);
+    class.add_builtin_method_by_str(globals, "<<", add);
+    class.add_builtin_method_by_str(globals, "add?", add_);
+    class.add_builtin_method_by_str(globals, "delete", delete);
+    class.add_builtin_method_by_str(globals, "delete?", delete_);
+    class.add_builtin_method_by_str(globals, "merge", merge);
+    class.add_builtin_method_by_str(globals, "clear", clear);
+    class.add_builtin_method_by_str(globals, "include?", include);
+    class.add_builtin_method_by_str(globals, "member?", include);
+    class.add_builtin_method_by_str(globals, "===", include);
+    class.add_builtin_method_by_str(globals, "size", size);
+    class.add_builtin_method_by_str(globals, "length", size);
+    class.add_builtin_method_by_str(globals, "empty?", empty);
+    class.add_builtin_method_by_str(globals, "|", union);
+    class.add_builtin_method_by_str(globals, "union", union);
+    class.add_builtin_method_by_str(globals, "+", union);
+    class.add_builtin_method_by_str(globals, "&", intersection);
+    class.add_builtin_method_by_str(globals, "intersection", intersection);
+    class.add_builtin_method_by_str(globals, "-", difference);
+    class.add_builtin_method_by_str(globals, "difference", difference);
+    class.add_builtin_method_by_str(globals, "^", xor);
+    class.add_builtin_method_by_str(globals, "subset?", subset);
+    class.add_builtin_method_by_str(globals, "<=", subset);
+    class.add_builtin_method_by_str(globals, "proper_subset?", proper_subset);
+    class.add_builtin_method_by_str(globals, "<", proper_subset);
+    class.add_builtin_method_by_str(globals, "superset?", superset);
+    class.add_builtin_method_by_str(globals, ">=", superset);
+    class.add_builtin_method_by_str(globals, "proper_superset?", proper_superset);
+    class.add_builtin_method_by_str(globals, ">", proper_superset);
+    class.add_builtin_method_by_str(globals, "disjoint?", disjoint);
+    class.add_builtin_method_by_str(globals, "intersect?", intersect);
+    class.add_builtin_method_by_str(globals, "each", each);
+    class.add_builtin_method_by_str(globals, "map", map);
+    class.add_builtin_method_by_str(globals, "collect", map);
+    class.add_builtin_method_by_str(globals, "to_a", to_a);
+    class.add_builtin_method_by_str(globals, "to_set", to_set);
+    class.add_builtin_method_by_str(globals, "==", eq);
+    class.add_builtin_method_by_str(globals, "hash", hash);
+    class.add_builtin_method_by_str(globals, "inspect", inspect);
+    class.add_builtin_method_by_str(globals, "to_s", inspect);
+
+    BuiltinClass::enumerable().add_builtin_method_by_str(globals, "to_set", enum_to_set);
+}
+
+/// Collect elements of `val`, which must be a Set or an Enumerable.
+fn elements_of(vm: &mut VM, val: Value) -> Result<Vec<Value>, RubyError> {
+    if let Some(set) = val.as_set() {
+        return Ok(set.to_vec());
+    }
+    if let Some(ary) = val.as_array() {
+        return Ok(ary.to_vec());
+    }
+    if vm
+        .globals
+        .find_method_from_receiver(val, IdentId::EACH)
+        .is_none()
+    {
+        return Err(RubyError::argument("value must be enumerable"));
+    }
+    let ary = vm.eval_send0(IdentId::get_id("to_a"), val)?;
+    Ok(ary.expect_array("to_a")?.to_vec())
+}
+
+/// Build SetInfo from the elements of `val`, converting each element by `block` if given.
+fn collect_set(vm: &mut VM, val: Value, block: &Option<Block>) -> Result<SetInfo, RubyError> {
+    let elems = elements_of(vm, val)?;
+    let block = match block {
+        None => return Ok(SetInfo::from_iter(elems.into_iter())),
+        Some(block) => block,
+    };
+    let temp_len = vm.temp_len();
+    vm.temp_extend_from_slice(&elems);
+    let mut info = SetInfo::new();
+    for elem in elems {
+        let res = vm.eval_block1(block, elem);
+        let v = match res {
+            Ok(v) => v,
+            Err(err) => {
+                vm.temp_pop_vec(temp_len);
+                return Err(err);
+            }
+        };
+        vm.temp_push(v);
+        info.insert(v);
+    }
+    vm.temp_pop_vec(temp_len);
+    Ok(info)
+}
+
+/// Get SetInfo of `val`, or raise ArgumentError.
+fn expect_set(val: &Value) -> Result<&SetInfo, RubyError> {
+    val.as_set()
+        .ok_or_else(|| RubyError::argument("value must be a set"))
+}
+
+/// Create a new Set of the same class as `self_val`.
+fn new_set_like(vm: &VM, self_val: Value, info: SetInfo) -> Value {
+    Value::set(vm.globals.get_class(self_val), info)
+}
+
+/// ## class method Set.new
+/// - new(enum = nil) -> Set
+/// - new(enum = nil) {|o| ... } -> Set
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Set/s/new.html
+fn set_new(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    let class = self_val.into_module();
+    let set = Value::set(class, SetInfo::new());
+    vm.eval_initialize(class, set, args)?;
+    Ok(set)
+}
+
+/// ## class method Set.[]
+/// - self[*ary] -> Set
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Set/s/=5b=5d.html
+fn set_elems(vm: &mut VM, self_val: Value, _: &Args2) -> VMResult {
+    let info = SetInfo::from_iter(vm.args().iter().cloned());
+    Ok(Value::set(self_val.into_module(), info))
+}
+
+fn initialize(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
+    if args.len() == 0 || vm[0].is_nil() {
+        return Ok(Value::nil());
+    }
+    let info = collect_set(vm, vm[0], &args.block)?;
+    *self_val.as_mut_set().unwrap() = info;
+    Ok(Value::nil())
+}
+
+/// ## instance method Set#add
+/// - add(o) -> self
+/// - self << o -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Set/i/add.html
+fn add(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    self_val.check_frozen()?;
+    self_val.as_mut_set().unwrap().insert(vm[0]);
+    Ok(self_val)
+}
+
+/// ## instance method Set#add?
+/// - add?(o) -> self | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Set/i/add=3f.html
+fn add_(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    self_val.check_frozen()?;
+    if self_val.as_mut_set().unwrap().insert(vm[0]) {
+        Ok(self_val)
+    } else {
+        Ok(Value::nil())
+    }
+}
+
+/// ## instance method Set#delete
+/// - delete(o) -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Set/i/delete.html
+fn delete(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    self_val.check_frozen()?;
+    self_val.as_mut_set().unwrap().remove(vm[0]);
+    Ok(self_val)
+}
+
+/// ## instance method Set#delete?
+/// - delete?(o) -> self | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Set/i/delete=3f.html
+fn delete_(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    self_val.check_frozen()?;
+    if self_val.as_mut_set().unwrap().remove(vm[0]) {
+        Ok(self_val)
+    } else {
+        Ok(Value::nil())
+    }
+}
+
+/// ## instance method Set#merge
+/// - merge(*enums) -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Set/i/merge.html
+fn merge(vm: &mut VM, mut self_val: Value, _: &Args2) -> VMResult {
+    self_val.check_frozen()?;
+    for arg in vm.args().to_owned() {
+        let elems = elements_of(vm, arg)?;
+        let set = self_val.as_mut_set().unwrap();
+        for elem in elems {
+            set.insert(elem);
+        }
+    }
+    Ok(self_val)
+}
+
+/// ## instance method Set#clear
+/// - clear -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Set/i/clear.html
+fn clear(_: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    self_val.check_frozen()?;
+    self_val.as_mut_set().unwrap().clear();
+    Ok(self_val)
+}
+
+/// ## instance method Set#include?
+/// - include?(o) -> bool
+/// - member?(o) -> bool
+/// - self === o -> bool
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Set/i/include=3f.html
+fn include(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let set = self_val.as_set().unwrap();
+    Ok(Value::bool(set.contains(vm[0])))
+}
+
+/// ## instance method Set#size
+/// - size -> Integer
+/// - length -> Integer
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Set/i/length.html
+fn size(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::integer(self_val.as_set().unwrap().len() as i64))
+}
+
+/// ## instance method Set#empty?
+/// - empty? -> bool
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Set/i/empty=3f.html
+fn empty(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::bool(self_val.as_set().unwrap().is_empty()))
+}
+
+/// ## instance method Set#|
+/// - self | enum -> Set
+/// - union(enum) -> Set
+/// - self + enum -> Set
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Set/i/=2b.html
+fn union(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let elems = elements_of(vm, vm[0])?;
+    let mut info = self_val.as_set().unwrap().clone();
+    for elem in elems {
+        info.insert(elem);
+    }
+    Ok(new_set_like(vm, self_val, info))
+}
+
+/// ## instance method Set#&
+/// - self & enum -> Set
+/// - intersection(enum) -> Set
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Set/i/=26.html
+fn intersection(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let elems = elements_of(vm, vm[0])?;
+    let set = self_val.as_set().unwrap();
+    let info = SetInfo::from_iter(elems.into_iter().filter(|elem| set.contains(*elem)));
+    Ok(new_set_like(vm, self_val, info))
+}
+
+/// ## instance method Set#-
+/// - self - enum -> Set
+/// - difference(enum) -> Set
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Set/i/=2d.html
+fn difference(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let elems = elements_of(vm, vm[0])?;
+    let mut info = self_val.as_set().unwrap().clone();
+    for elem in elems {
+        info.remove(elem);
+    }
+    Ok(new_set_like(vm, self_val, info))
+}
+
+/// ## instance method Set#^
+/// - self ^ enum -> Set
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Set/i/=5e.html
+fn xor(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let elems = elements_of(vm, vm[0])?;
+    let set = self_val.as_set().unwrap();
+    let mut info = SetInfo::from_iter(elems.into_iter());
+    for elem in set.iter() {
+        if !info.remove(elem) {
+            info.insert(elem);
+        }
+    }
+    Ok(new_set_like(vm, self_val, info))
+}
+
+/// ## instance method Set#subset?
+/// - subset?(set) -> bool
+/// - self <= set -> bool
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Set/i/=3c=3d.html
+fn subset(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let other = vm[0];
+    let other = expect_set(&other)?;
+    Ok(Value::bool(self_val.as_set().unwrap().is_subset(other)))
+}
+
+/// ## instance method Set#proper_subset?
+/// - proper_subset?(set) -> bool
+/// - self < set -> bool
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Set/i/=3c.html
+fn proper_subset(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let other = vm[0];
+    let other = expect_set(&other)?;
+    let set = self_val.as_set().unwrap();
+    Ok(Value::bool(set.len() < other.len() && set.is_subset(other)))
+}
+
+/// ## instance method Set#superset?
+/// - superset?(set) -> bool
+/// - self >= set -> bool
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Set/i/=3e=3d.html
+fn superset(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let other = vm[0];
+    let other = expect_set(&other)?;
+    Ok(Value::bool(other.is_subset(self_val.as_set().unwrap())))
+}
+
+/// ## instance method Set#proper_superset?
+/// - proper_superset?(set) -> bool
+/// - self > set -> bool
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Set/i/=3e.html
+fn proper_superset(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let other = vm[0];
+    let other = expect_set(&other)?;
+    let set = self_val.as_set().unwrap();
+    Ok(Value::bool(other.len() < set.len() && other.is_subset(set)))
+}
+
+/// ## instance method Set#disjoint?
+/// - disjoint?(set) -> bool
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Set/i/disjoint=3f.html
+fn disjoint(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let other = vm[0];
+    if let Some(other) = other.as_set() {
+        return Ok(Value::bool(self_val.as_set().unwrap().is_disjoint(other)));
+    }
+    let elems = elements_of(vm, other)?;
+    let set = self_val.as_set().unwrap();
+    Ok(Value::bool(
+        !elems.into_iter().any(|elem| set.contains(elem)),
+    ))
+}
+
+/// ## instance method Set#intersect?
+/// - intersect?(set) -> bool
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Set/i/intersect=3f.html
+fn intersect(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    let res = disjoint(vm, self_val, args)?;
+    Ok(Value::bool(!res.to_bool()))
+}
+
+/// ## instance method Set#each
+/// - each {|o| ... } -> self
+/// - each -> Enumerator
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Set/i/each.html
+fn each(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let block = match &args.block {
+        None => {
+            let val = vm.create_enumerator(IdentId::EACH, self_val, args.into(vm))?;
+            return Ok(val);
+        }
+        Some(block) => block,
+    };
+    let elems = Value::array_from(self_val.as_set().unwrap().to_vec());
+    vm.temp_push(elems);
+    vm.eval_block_each1_iter(block, elems.into_array().iter().cloned(), self_val)
+}
+
+/// ## instance method Set#map
+/// - map {|o| ... } -> [object]
+/// - collect {|o| ... } -> [object]
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/collect.html
+fn map(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let block = match &args.block {
+        None => {
+            let val = vm.create_enumerator(IdentId::MAP, self_val, args.into(vm))?;
+            return Ok(val);
+        }
+        Some(block) => block,
+    };
+    let elems = Value::array_from(self_val.as_set().unwrap().to_vec());
+    vm.temp_push(elems);
+    vm.eval_block_map1_iter(block, elems.into_array().iter().cloned())
+}
+
+/// ## instance method Set#to_a
+/// - to_a -> Array
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Set/i/to_a.html
+fn to_a(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::array_from(self_val.as_set().unwrap().to_vec()))
+}
+
+/// ## instance method Set#to_set
+/// - to_set -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Set/i/to_set.html
+fn to_set(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(self_val)
+}
+
+/// ## instance method Set#==
+/// - self == set -> bool
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Set/i/=3d=3d.html
+fn eq(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let res = match vm[0].as_set() {
+        Some(other) => *self_val.as_set().unwrap() == *other,
+        None => false,
+    };
+    Ok(Value::bool(res))
+}
+
+/// ## instance method Set#hash
+/// - hash -> Integer
+fn hash(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let hash = fxhash::hash64(&HashKey(self_val));
+    Ok(Value::integer(hash as i64 >> 2))
+}
+
+/// ## instance method Set#inspect
+/// - inspect -> String
+/// - to_s -> String
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Set/i/inspect.html
+fn inspect(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let mut s = vec![];
+    for elem in self_val.as_set().unwrap().to_vec() {
+        if elem.id() == self_val.id() {
+            s.push("#<Set: {...}>".to_string());
+        } else {
+            s.push(vm.val_inspect(elem)?);
+        }
+    }
+    Ok(Value::string(format!(
+        "#<{}: {{{}}}>",
+        self_val.get_class_name(),
+        s.join(", ")
+    )))
+}
+
+/// ## instance method Enumerable#to_set
+/// - to_set -> Set
+/// - to_set {|o| ... } -> Set
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Enumerable/i/to_set.html
+fn enum_to_set(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let class = vm.globals.get_toplevel_constant("Set").into_module();
+    let info = collect_set(vm, self_val, &args.block)?;
+    Ok(Value::set(class, info))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::*;
+
+    #[test]
+    fn set() {
+        let program = r##"
+        assert false, require('set')
+        s = Set.new([1, 2, 3])
+        assert "#<Set: {1, 2, 3}>", s.inspect
+        assert s, s << 2 << 4
+        assert 4, s.size
+        assert nil, s.add?(4)
+        assert s, s.add?(5)
+        assert [2, 3, 4, 5], s.delete(1).to_a
+        assert nil, s.delete?(100)
+        assert true, s.include?(3)
+        assert false, s.member?(9)
+        assert true, s === 3
+        r = case 4
+            when Set[1, 2] then :small
+            when Set[3, 4] then :large
+            end
+        assert :large, r
+        assert Set.new, Set[]
+        assert [10, 20], Set.new([1, 2, 1]) { |x| x * 10 }.to_a
+        assert [1, 2, 3], (1..3).to_set.to_a
+        assert true, Set[].empty?
+        f = Set[1].freeze
+        assert_error { f << 2 }
+        "##;
+        assert_script(program);
+    }
+
+    #[test]
+    fn set_operations() {
+        let program = r##"
+        a = Set[1, 2, 3]
+        b = Set[3, 4]
+        assert Set[1, 2, 3, 4], a | b
+        assert Set[1, 2, 3, 4], a + [4]
+        assert Set[3], a & b
+        assert Set[1, 2], a - b
+        assert Set[4, 1, 2], a ^ b
+        assert true, Set[1, 2].subset?(a)
+        assert true, Set[1, 2] < a
+        assert true, a <= a
+        assert false, a < a
+        assert true, a.superset?(Set[1])
+        assert true, a > Set[1]
+        assert true, a.disjoint?(Set[9])
+        assert true, a.intersect?([3])
+        assert [2, 4, 6], a.map { |x| x * 2 }
+        assert [2, 3], a.select { |x| x > 1 }
+        res = []
+        assert a, a.each { |x| res << x }
+        assert [1, 2, 3], res
+        assert [1, 2, 3], a.each.to_a
+        class MySet < Set; end
+        assert MySet, (MySet[1] | [2]).class
+        assert "#<MySet: {1}>", MySet[1].inspect
+        "##;
+        assert_script(program);
+    }
+
+    #[test]
+    fn set_eq_hash() {
+        let program = r##"
+        assert Set[3, 1], [3, 1, 3].to_set
+        assert true, Set[1, 2] == Set[2, 1]
+        assert false, Set[1, 2] == [1, 2]
+        assert true, Set[1, 2].eql?(Set[2, 1])
+        assert Set[1, 2].hash, Set[2, 1].hash
+        h = { Set[1, 2] => :a }
+        assert :a, h[Set[2, 1]]
+        assert true, [Set["a"]].include?(Set["a"])
+        assert [1, 3], Set[1, 2, 3].delete(2).to_a
+        "##;
+        assert_script(program);
+    }
+}
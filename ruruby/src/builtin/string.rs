@@ -60,6 +60,19 @@ pub(crate) fn init(globals: &mut Globals) -> Value {
     class.add_builtin_method_by_str(globals, "empty?", empty);
     class.add_builtin_method_by_str(globals, "codepoints", codepoints);
     class.add_builtin_method_by_str(globals, "lines", lines);
+    class.add_builtin_method_by_str(globals, "bytesize", bytesize);
+    class.add_builtin_method_by_str(globals, "encoding", encoding);
+    class.add_builtin_method_by_str(globals, "force_encoding", force_encoding);
+    class.add_builtin_method_by_str(globals, "valid_encoding?", valid_encoding);
+    class.add_builtin_method_by_str(globals, "ascii_only?", ascii_only);
+    class.add_builtin_method_by_str(globals, "b", b);
+    class.add_builtin_method_by_str(globals, "encode", encode);
+    class.add_builtin_method_by_str(globals, "encode!", encode_);
+    class.add_builtin_method_by_str(globals, "scrub", scrub);
+    class.add_builtin_method_by_str(globals, "scrub!", scrub_);
+    class.add_builtin_method_by_str(globals, "unicode_normalize", unicode_normalize);
+    class.add_builtin_method_by_str(globals, "unicode_normalize!", unicode_normalize_);
+    class.add_builtin_method_by_str(globals, "unicode_normalized?", unicode_normalized);
     class.into()
 }
 
@@ -86,8 +99,7 @@ fn to_s(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
 
 fn inspect(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(0)?;
-    let self_ = self_val.as_rstring().unwrap();
-    Ok(Value::string(self_.inspect()))
+    Ok(Value::string(Encoding::inspect_string(self_val)))
 }
 
 /// String#dump
@@ -223,6 +235,7 @@ fn index(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
 
 fn index_assign(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
     args.check_args_range(2, 3)?;
+    self_val.check_frozen()?;
     let string = self_val.as_mut_rstring().unwrap();
     let str_len = string.chars().count();
     let start_pos = vm[0].coerce_to_fixnum("1st arg")? as usize;
@@ -259,6 +272,7 @@ fn cmp(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
 
 fn concat(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(1)?;
+    self_val.check_frozen()?;
     let lhs = self_val.as_mut_rstring().unwrap();
     match vm[0].as_rstring() {
         Some(rhs) => lhs.append(rhs),
@@ -273,149 +287,18 @@ fn concat(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
     Ok(self_val)
 }
 
-fn expect_char(_: &mut VM, chars: &mut std::str::Chars) -> Result<char, RubyError> {
-    let ch = match chars.next() {
-        Some(ch) => ch,
-        None => return Err(RubyError::argument("Invalid termination of format string.")),
-    };
-    Ok(ch)
-}
-
-macro_rules! next_char {
-    ($ch:ident, $chars:ident) => {
-        $ch = match $chars.next() {
-            Some(c) => c,
-            None => break,
-        };
-    };
-}
-
+/// String#%(args) -> String
+///
+/// https://docs.ruby-lang.org/ja/latest/method/String/i/=25.html
 fn rem(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(1)?;
     let arguments = match vm[0].as_array() {
         Some(ary) => ary.to_vec(),
         None => vec![vm[0]],
     };
-    let mut arg_no = 0;
-    let mut format_str = vec![];
-    let mut chars = self_val.as_string().unwrap().chars();
-    let mut ch = match chars.next() {
-        Some(ch) => ch,
-        None => {
-            let res = Value::string("");
-            return Ok(res);
-        }
-    };
-    loop {
-        if ch != '%' {
-            format_str.push(ch);
-            next_char!(ch, chars);
-            continue;
-        }
-        match chars.next() {
-            Some(c) if c == '%' => {
-                format_str.push('%');
-                next_char!(ch, chars);
-                continue;
-            }
-            Some(c) => ch = c,
-            None => {
-                return Err(RubyError::argument(
-                    "Incomplete format specifier. use '%%' instead.",
-                ))
-            }
-        };
-        let mut zero_flag = false;
-        // Zero-fill
-        if ch == '0' {
-            zero_flag = true;
-            ch = expect_char(vm, &mut chars)?;
-        }
-        // Width
-        let mut width = 0usize;
-        while '0' <= ch && ch <= '9' {
-            width = width * 10 + ch as usize - '0' as usize;
-            ch = expect_char(vm, &mut chars)?;
-        }
-        // Precision
-        let mut precision = 0usize;
-        if ch == '.' {
-            ch = expect_char(vm, &mut chars)?;
-            while '0' <= ch && ch <= '9' {
-                precision = precision * 10 + ch as usize - '0' as usize;
-                ch = expect_char(vm, &mut chars)?;
-            }
-        };
-        if arguments.len() <= arg_no {
-            return Err(RubyError::argument("Too few arguments"));
-        };
-        // Specifier
-        let mut val = arguments[arg_no];
-        arg_no += 1;
-        let format = match ch {
-            'd' => {
-                let val = val.coerce_to_fixnum("Invalid value for placeholder of Integer.")?;
-                if zero_flag {
-                    format!("{:0w$.p$}", val, w = width, p = precision)
-                } else {
-                    format!("{:w$.p$}", val, w = width, p = precision)
-                }
-            }
-            'b' => {
-                let val = val.coerce_to_fixnum("Invalid value for placeholder of Integer.")?;
-                if zero_flag {
-                    format!("{:0w$b}", val, w = width)
-                } else {
-                    format!("{:w$b}", val, w = width)
-                }
-            }
-            'x' => {
-                let val = val.coerce_to_fixnum("Invalid value for placeholder of Integer.")?;
-                if zero_flag {
-                    format!("{:0w$x}", val, w = width)
-                } else {
-                    format!("{:w$x}", val, w = width)
-                }
-            }
-            'X' => {
-                let val = val.coerce_to_fixnum("Value for the placeholder")?;
-                if zero_flag {
-                    format!("{:0w$X}", val, w = width)
-                } else {
-                    format!("{:w$X}", val, w = width)
-                }
-            }
-            'f' => {
-                let f = match val.as_float() {
-                    Some(f) => f,
-                    None => val.coerce_to_fixnum("Value for the placeholder")? as f64,
-                };
-                if zero_flag {
-                    format!("{:0w$.p$}", f, w = width, p = precision)
-                } else {
-                    format!("{:w$.p$}", f, w = width, p = precision)
-                }
-            }
-            'c' => {
-                let val = val.coerce_to_fixnum("Value for the placeholder")?;
-                let ch = char::from_u32(val as u32)
-                    .ok_or_else(|| RubyError::argument("Invalid value for placeholder."))?;
-                format!("{}", ch)
-            }
-            's' => val.expect_string("Value for placeholder")?.to_string(),
-            _ => {
-                return Err(RubyError::argument(format!(
-                    "Invalid format character. {}",
-                    ch
-                )))
-            }
-        };
-        format_str.append(&mut format.chars().collect());
-        next_char!(ch, chars);
-    }
-
-    let res = Value::string(format_str.into_iter().collect::<String>());
-    Ok(res)
+    let fmt = self_val.as_rstring().unwrap().to_s().into_owned();
+    let res = format::sprintf(vm, &fmt, &arguments)?;
+    Ok(Value::string(res))
 }
 
 fn start_with(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
@@ -584,6 +467,7 @@ fn gsub(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
 }
 
 fn gsub_(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    self_val.check_frozen()?;
     let (res, changed) = gsub_main(vm, self_val, args)?;
     *self_val.rvalue_mut() = RValue::new_string(&res);
     let res = if changed { self_val } else { Value::nil() };
@@ -651,6 +535,7 @@ fn slice_(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     }
     let mut self_val2 = self_val;
     args.check_args_range(1, 2)?;
+    self_val.check_frozen()?;
     let target = self_val2.as_mut_rstring().unwrap();
     let arg0 = vm[0];
     match arg0.unpack() {
@@ -779,6 +664,9 @@ fn tr(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
 
 fn size(_: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(0)?;
+    if let Encoding::Ascii8Bit | Encoding::UsAscii = Encoding::of(self_val) {
+        return Ok(Value::integer(self_val.as_rstring().unwrap().len() as i64));
+    }
     let rec = self_val.expect_string("Receiver")?;
     Ok(Value::integer(rec.chars().count() as i64))
 }
@@ -877,6 +765,7 @@ fn downcase(_: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
 /// https://docs.ruby-lang.org/ja/latest/method/String/i/replace.html
 fn replace(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(1)?;
+    self_val.check_frozen()?;
     let self_ = self_val.as_mut_rstring().unwrap();
     let mut arg0 = vm[0];
     *self_ = RString::from(arg0.expect_string("1st arg")?);
@@ -936,6 +825,7 @@ fn chomp(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
 /// https://docs.ruby-lang.org/ja/latest/method/String/i/chomp=21.html
 fn chomp_(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
     args.check_args_range(0, 1)?;
+    self_val.check_frozen()?;
     let rs = if args.len() == 0 {
         "\n".to_string()
     } else {
@@ -1230,6 +1120,211 @@ fn lines(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     Ok(Value::array_from(ary))
 }
 
+/// String#bytesize -> Integer
+///
+/// https://docs.ruby-lang.org/ja/latest/method/String/i/bytesize.html
+fn bytesize(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let len = self_val.as_rstring().unwrap().len();
+    Ok(Value::integer(len as i64))
+}
+
+/// String#encoding -> Encoding
+///
+/// https://docs.ruby-lang.org/ja/latest/method/String/i/encoding.html
+fn encoding(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Encoding::of(self_val).to_value())
+}
+
+/// String#force_encoding(encoding) -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/String/i/force_encoding.html
+fn force_encoding(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    self_val.check_frozen()?;
+    Encoding::from_value(vm[0])?.set(self_val);
+    Ok(self_val)
+}
+
+/// String#valid_encoding? -> bool
+///
+/// https://docs.ruby-lang.org/ja/latest/method/String/i/valid_encoding=3f.html
+fn valid_encoding(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let bytes = self_val.as_rstring().unwrap().as_bytes();
+    Ok(Value::bool(Encoding::of(self_val).is_valid(bytes)))
+}
+
+/// String#ascii_only? -> bool
+///
+/// https://docs.ruby-lang.org/ja/latest/method/String/i/ascii_only=3f.html
+fn ascii_only(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let bytes = self_val.as_rstring().unwrap().as_bytes();
+    Ok(Value::bool(
+        Encoding::of(self_val).is_ascii_compatible() && bytes.is_ascii(),
+    ))
+}
+
+/// String#b -> String
+///
+/// https://docs.ruby-lang.org/ja/latest/method/String/i/b.html
+fn b(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let bytes = self_val.as_rstring().unwrap().as_bytes().to_vec();
+    let res = Value::bytes(bytes);
+    Encoding::Ascii8Bit.set(res);
+    Ok(res)
+}
+
+/// Convert the encoding of `self_val` and return the converted bytes and the new encoding.
+fn transcode_str(
+    vm: &mut VM,
+    self_val: Value,
+    args: &Args2,
+) -> Result<(Vec<u8>, Encoding), RubyError> {
+    args.check_args_range(0, 2)?;
+    let opt = TranscodeOptions::from_hash(args.kw_arg)?;
+    let dst = match args.len() {
+        0 => Encoding::default_internal().unwrap_or(Encoding::Utf8),
+        _ => Encoding::from_value(vm[0])?,
+    };
+    let src = match args.len() {
+        2 => Encoding::from_value(vm[1])?,
+        _ => Encoding::of(self_val),
+    };
+    let bytes = self_val.as_rstring().unwrap().as_bytes();
+    Ok((transcode(bytes, src, dst, &opt)?, dst))
+}
+
+/// String#encode(encoding, from_encoding = self.encoding, **option) -> String
+///
+/// Options: `invalid: :replace`, `undef: :replace` and `replace: String`.
+///
+/// https://docs.ruby-lang.org/ja/latest/method/String/i/encode.html
+fn encode(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    let (bytes, dst) = transcode_str(vm, self_val, args)?;
+    let res = Value::bytes(bytes);
+    dst.set(res);
+    Ok(res)
+}
+
+/// String#encode!(encoding, from_encoding = self.encoding, **option) -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/String/i/encode=21.html
+fn encode_(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    self_val.check_frozen()?;
+    let (bytes, dst) = transcode_str(vm, self_val, args)?;
+    *self_val.as_mut_rstring().unwrap() = RString::from_bytes(bytes);
+    dst.set(self_val);
+    Ok(self_val)
+}
+
+/// Replace invalid byte sequences of `self_val` and return the result.
+fn scrub_str(vm: &mut VM, self_val: Value, args: &Args2) -> Result<Vec<u8>, RubyError> {
+    args.check_args_range(0, 1)?;
+    let enc = Encoding::of(self_val);
+    let bytes = self_val.as_rstring().unwrap().as_bytes().to_vec();
+    if args.len() == 1 && !vm[0].is_nil() {
+        let mut repl = vm[0];
+        let repl = enc.encode_replacement(repl.expect_string("Replacement")?)?;
+        return enc.scrub(&bytes, &mut |_| Ok(repl.clone()));
+    }
+    match &args.block {
+        Some(block) => enc.scrub(&bytes, &mut |invalid| {
+            let repl = vm.eval_block1(block, Value::bytes(invalid.to_vec()))?;
+            Ok(repl.expect_bytes("Replacement")?.to_vec())
+        }),
+        None => {
+            let repl = enc.encode_replacement(enc.replacement())?;
+            enc.scrub(&bytes, &mut |_| Ok(repl.clone()))
+        }
+    }
+}
+
+/// String#scrub(repl = "�") -> String
+/// String#scrub {|bytes| ... } -> String
+///
+/// https://docs.ruby-lang.org/ja/latest/method/String/i/scrub.html
+fn scrub(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    let res = Value::bytes(scrub_str(vm, self_val, args)?);
+    Encoding::of(self_val).set(res);
+    Ok(res)
+}
+
+/// String#scrub!(repl = "�") -> self
+/// String#scrub! {|bytes| ... } -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/String/i/scrub=21.html
+fn scrub_(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    self_val.check_frozen()?;
+    let bytes = scrub_str(vm, self_val, args)?;
+    *self_val.as_mut_rstring().unwrap() = RString::from_bytes(bytes);
+    Ok(self_val)
+}
+
+/// Normalize `self_val` in the form given by the optional argument. (default: :nfc)
+fn normalize_str(vm: &mut VM, mut self_val: Value, args: &Args2) -> Result<String, RubyError> {
+    use unicode_normalization::UnicodeNormalization;
+    args.check_args_range(0, 1)?;
+    let form = if args.len() == 0 {
+        IdentId::get_id("nfc")
+    } else {
+        vm[0].expect_symbol_or_string("1st arg")?
+    };
+    if !Encoding::of(self_val).is_unicode() {
+        return Err(RubyError::encoding(
+            "CompatibilityError",
+            format!(
+                "Unicode Normalization not appropriate for {}",
+                Encoding::of(self_val).name()
+            ),
+        ));
+    }
+    let s = self_val.expect_string("Receiver")?;
+    let res = match form.get_name().as_str() {
+        "nfc" => s.nfc().collect(),
+        "nfd" => s.nfd().collect(),
+        "nfkc" => s.nfkc().collect(),
+        "nfkd" => s.nfkd().collect(),
+        _ => {
+            return Err(RubyError::argument(format!(
+                "Invalid normalization form {:?}.",
+                form
+            )))
+        }
+    };
+    Ok(res)
+}
+
+/// String#unicode_normalize(form = :nfc) -> String
+///
+/// https://docs.ruby-lang.org/ja/latest/method/String/i/unicode_normalize.html
+fn unicode_normalize(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    Ok(Value::string(normalize_str(vm, self_val, args)?))
+}
+
+/// String#unicode_normalize!(form = :nfc) -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/String/i/unicode_normalize=21.html
+fn unicode_normalize_(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    self_val.check_frozen()?;
+    let res = normalize_str(vm, self_val, args)?;
+    *self_val.as_mut_rstring().unwrap() = RString::from(res);
+    Ok(self_val)
+}
+
+/// String#unicode_normalized?(form = :nfc) -> bool
+///
+/// https://docs.ruby-lang.org/ja/latest/method/String/i/unicode_normalized=3f.html
+fn unicode_normalized(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    let res = normalize_str(vm, self_val, args)?;
+    Ok(Value::bool(
+        self_val.as_rstring().unwrap().as_bytes() == res.as_bytes(),
+    ))
+}
+
 #[cfg(test)]
 mod test {
     use crate::tests::*;
@@ -1748,4 +1843,91 @@ mod test {
         "#;
         assert_script(program);
     }
+
+    #[test]
+    fn string_encoding() {
+        let program = r#"
+        assert Encoding::UTF_8, "abc".encoding
+        assert 6, "あい".bytesize
+        b = "あい".b
+        assert Encoding::ASCII_8BIT, b.encoding
+        assert 6, b.size
+        assert false, b.ascii_only?
+        assert true, "abc".ascii_only?
+        assert Encoding::UTF_8, b.force_encoding("UTF-8").encoding
+        assert 2, b.size
+        x = [97, 255, 98].pack("C*")
+        assert Encoding::BINARY, x.encoding
+        assert true, x.valid_encoding?
+        x.force_encoding(Encoding::UTF_8)
+        assert false, x.valid_encoding?
+        assert "a\uFFFDb", x.scrub
+        assert "a?b", x.scrub("?")
+        assert "a<255>b", x.scrub { |bytes| "<#{bytes.bytes[0]}>" }
+        x.scrub!("")
+        assert "ab", x
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn string_byte_escape() {
+        let program = r#"
+        assert [255], "\xff".bytes
+        assert false, "\xff".valid_encoding?
+        assert [97, 255, 65], "a\377\101".bytes
+        assert "あ", "\xe3\x81\x82"
+        x = 1
+        assert [255, 49, 254], "\xff#{x}\xfe".bytes
+        assert '"h\xC3\xA9llo"', "héllo".b.inspect
+        assert '"\xFF"', "\xff".inspect
+        assert '["\x00\xFF"]', ["\x00\xff".b].inspect
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn string_encode() {
+        let program = r#"
+        s = "あいう".encode("Shift_JIS")
+        assert Encoding::Shift_JIS, s.encoding
+        assert [130, 160, 130, 162, 130, 164], s.bytes
+        assert "あいう", s.encode("UTF-8")
+        assert [66, 48], "あ".encode(Encoding::UTF_16LE).bytes
+        assert [48, 66], "あ".encode("UTF-16BE").bytes
+        assert "あ", "あ".encode("UTF-16BE").encode("UTF-8")
+        assert_error { "aあ".encode("US-ASCII") }
+        assert "a?", "aあ".encode("US-ASCII", undef: :replace)
+        assert "a*", "aあ".encode("US-ASCII", undef: :replace, replace: "*")
+        x = [97, 255].pack("C*").force_encoding("UTF-8")
+        assert_error { x.encode("UTF-16LE") }
+        assert "a\uFFFD", x.encode("UTF-16LE", invalid: :replace).encode("UTF-8")
+        assert "a?", x.encode("UTF-8", invalid: :replace, replace: "?")
+        begin
+          "\u00FF".b.encode("UTF-8")
+        rescue Encoding::UndefinedConversionError => e
+          assert "Encoding::UndefinedConversionError (\"\\xC3\" from ASCII-8BIT to UTF-8)", e.message
+        end
+        s = "abc".b
+        s.encode!("UTF-8")
+        assert Encoding::UTF_8, s.encoding
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn string_unicode_normalize() {
+        let program = r#"
+        assert "e\u0301", "\u00e9".unicode_normalize(:nfd)
+        assert "\u00e9", "e\u0301".unicode_normalize
+        assert "1", "\u2460".unicode_normalize(:nfkc)
+        assert true, "\u00e9".unicode_normalized?
+        assert false, "e\u0301".unicode_normalized?
+        s = "e\u0301"
+        s.unicode_normalize!
+        assert "\u00e9", s
+        assert_error { "a".unicode_normalize(:foo) }
+        "#;
+        assert_script(program);
+    }
 }
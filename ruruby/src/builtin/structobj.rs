@@ -1,12 +1,35 @@
 use crate::*;
 
 pub(crate) fn init(globals: &mut Globals) -> Value {
-    let class = Module::class_under_object();
+    let mut class = Module::class_under_object();
     globals.set_toplevel_constant("Struct", class);
+    class.append_include_without_increment_version(BuiltinClass::enumerable());
+    class.add_builtin_method_by_str(globals, "==", eq);
+    class.add_builtin_method_by_str(globals, "eql?", eql);
+    class.add_builtin_method_by_str(globals, "hash", hash);
+    class.add_builtin_method_by_str(globals, "[]", index);
+    class.add_builtin_method_by_str(globals, "[]=", index_assign);
+    class.add_builtin_method_by_str(globals, "members", members_);
+    class.add_builtin_method_by_str(globals, "size", size);
+    class.add_builtin_method_by_str(globals, "length", size);
+    class.add_builtin_method_by_str(globals, "each", each);
+    class.add_builtin_method_by_str(globals, "each_pair", each_pair);
+    class.add_builtin_method_by_str(globals, "to_a", deconstruct);
+    class.add_builtin_method_by_str(globals, "values", deconstruct);
+    class.add_builtin_method_by_str(globals, "deconstruct", deconstruct);
+    class.add_builtin_method_by_str(globals, "to_h", to_h);
+    class.add_builtin_method_by_str(globals, "deconstruct_keys", deconstruct_keys);
+    class.add_builtin_method_by_str(globals, "values_at", values_at);
+    class.add_builtin_method_by_str(globals, "dig", dig);
     class.add_builtin_class_method(globals, "new", struct_new);
     class.into()
 }
 
+/// ## class method Struct.new
+/// - new(*args, keyword_init: nil) -> Class
+/// - new(*args, keyword_init: nil) {|subclass| block } -> Class
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Struct/s/new.html
 fn struct_new(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     let self_val = self_val.into_module();
     args.check_args_min(1)?;
@@ -30,10 +53,19 @@ fn struct_new(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
             class.set_name(format!("Struct::{}", s))
         }
     };
+    let keyword_init = match args.kw_arg.as_hash() {
+        Some(hash) => hash
+            .get(&Value::symbol_from_str("keyword_init"))
+            .cloned()
+            .unwrap_or_default(),
+        None => Value::nil(),
+    };
     class.add_builtin_method_by_str(&mut vm.globals, "initialize", initialize);
     class.add_builtin_method_by_str(&mut vm.globals, "inspect", inspect);
     class.add_builtin_class_method(&mut vm.globals, "[]", builtin::class::new);
     class.add_builtin_class_method(&mut vm.globals, "new", builtin::class::new);
+    class.add_builtin_class_method(&mut vm.globals, "members", class_members);
+    class.add_builtin_class_method(&mut vm.globals, "keyword_init?", keyword_init_);
 
     let mut attr_args = Args::new(args.len() - i);
     let mut vec = vec![];
@@ -45,10 +77,14 @@ fn struct_new(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
                 vm[index]
             )));
         };
+        if vec.iter().any(|member: &Value| member.id() == v.id()) {
+            return Err(RubyError::argument(format!("duplicate member: {:?}", v)));
+        }
         vec.push(v);
         attr_args[index - i] = v;
     }
     class.set_var_by_str("/members", Value::array_from(vec));
+    class.set_var_by_str("/keyword_init", keyword_init);
     builtin::module::set_attr_accessor(&mut vm.globals, class, &attr_args)?;
 
     match &args.block {
@@ -62,21 +98,424 @@ fn struct_new(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     Ok(class.into())
 }
 
+/// ## class method Struct#members
+/// - members -> [Symbol]
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Struct/s/members.html
+fn class_members(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let members = member_names(self_val.into_module()).unwrap();
+    Ok(Value::array_from(members.into_array().to_vec()))
+}
+
+fn keyword_init_(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(keyword_init(self_val.into_module()))
+}
+
 fn initialize(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     let class = vm.globals.get_class(self_val);
-    let name = class.get_var(IdentId::get_id("/members")).unwrap();
+    let name = member_names(class).unwrap();
     let members = name.into_array();
-    if members.len() < args.len() {
-        return Err(RubyError::argument("Struct size differs."));
+    let keyword_init = keyword_init(class);
+    let kw_arg = args.kw_arg;
+    // Since Ruby 3.2, keyword arguments are accepted unless `keyword_init: false`.
+    let use_keyword = if keyword_init.is_nil() {
+        args.len() == 0 && kw_arg.as_hash().is_some()
+    } else {
+        keyword_init.to_bool()
+    };
+    if use_keyword {
+        args.check_args_num(0)?;
+        if let Some(hash) = kw_arg.as_hash() {
+            let mut unknown = vec![];
+            for (k, v) in hash.iter() {
+                match k.as_symbol() {
+                    Some(id) if members.iter().any(|m| m.id() == k.id()) => {
+                        set_member(self_val, id, v)
+                    }
+                    _ => unknown.push(format!("{:?}", k)),
+                }
+            }
+            if !unknown.is_empty() {
+                return Err(RubyError::argument(format!(
+                    "unknown keywords: {}",
+                    unknown.join(", ")
+                )));
+            }
+        }
+        return Ok(Value::nil());
+    }
+    let mut values = vm.args().to_vec();
+    if !kw_arg.is_nil() {
+        values.push(kw_arg);
+    }
+    if members.len() < values.len() {
+        return Err(RubyError::argument("struct size differs"));
     };
-    for (i, arg) in vm.args().iter().enumerate() {
+    for (i, arg) in values.into_iter().enumerate() {
         let id = members[i].as_symbol().unwrap();
-        let var = format!("@{:?}", id);
-        self_val.set_var_by_str(&var, *arg);
+        set_member(self_val, id, arg);
     }
     Ok(Value::nil())
 }
 
+/// Member names (Array of Symbol) of `class`, or None if `class` was not generated by Struct.new.
+pub(crate) fn member_names(class: Module) -> Option<Value> {
+    let id = IdentId::get_id("/members");
+    let mut class = Some(class);
+    while let Some(c) = class {
+        if let Some(members) = c.get_var(id) {
+            return Some(members);
+        }
+        class = c.superclass();
+    }
+    None
+}
+
+/// `keyword_init` option given to Struct.new (true, false or nil).
+fn keyword_init(class: Module) -> Value {
+    let id = IdentId::get_id("/keyword_init");
+    let mut class = Some(class);
+    while let Some(c) = class {
+        if let Some(val) = c.get_var(id) {
+            return val;
+        }
+        class = c.superclass();
+    }
+    Value::nil()
+}
+
+/// Set the value of the member `id`.
+pub(crate) fn set_member(self_val: Value, id: IdentId, val: Value) {
+    let var = format!("@{:?}", id);
+    self_val.set_var_by_str(&var, val);
+}
+
+/// Get the value of the member `id`.
+fn get_member(self_val: Value, id: IdentId) -> Value {
+    self_val.get_var(id.add_prefix("@")).unwrap_or_default()
+}
+
+/// Pairs of the member name (Symbol) and its value.
+pub(crate) fn members(vm: &VM, self_val: Value) -> Result<Vec<(Value, Value)>, RubyError> {
+    let members = match member_names(vm.globals.get_class(self_val)) {
+        Some(members) => members,
+        None => return Err(RubyError::internal("No /members.")),
+    };
+    let members = members
+        .into_array()
+        .iter()
+        .map(|member| (*member, get_member(self_val, member.as_symbol().unwrap())))
+        .collect();
+    Ok(members)
+}
+
+/// Member values of `rval` if it is an instance of a Struct class.
+///
+/// This is used for hashing and eql? comparison of Struct instances as Hash keys.
+pub(crate) fn struct_values(rval: &RValue) -> Option<Vec<Value>> {
+    let names = member_names(rval.real_class())?;
+    let values = names
+        .into_array()
+        .iter()
+        .map(|member| {
+            let id = member.as_symbol().unwrap().add_prefix("@");
+            rval.get_var(id).unwrap_or_default()
+        })
+        .collect();
+    Some(values)
+}
+
+/// Resolve `idx` (Integer, Symbol or String) into the member name.
+fn member_id(vm: &VM, self_val: Value, idx: Value) -> Result<IdentId, RubyError> {
+    let names = member_names(vm.globals.get_class(self_val)).unwrap();
+    let names = names.into_array();
+    if let Some(i) = idx.as_fixnum() {
+        let len = names.len() as i64;
+        let index = if i < 0 { len + i } else { i };
+        if index < 0 {
+            return Err(RubyError::index(format!(
+                "offset {} too small for struct(size:{})",
+                i, len
+            )));
+        } else if index >= len {
+            return Err(RubyError::index(format!(
+                "offset {} too large for struct(size:{})",
+                i, len
+            )));
+        }
+        return Ok(names[index as usize].as_symbol().unwrap());
+    }
+    let id = match idx.as_symbol() {
+        Some(id) => id,
+        None => match idx.as_string() {
+            Some(s) => IdentId::get_id(s),
+            None => return Err(VMError::no_implicit_conv(idx, "Integer")),
+        },
+    };
+    if names.iter().any(|name| name.as_symbol() == Some(id)) {
+        Ok(id)
+    } else {
+        Err(RubyError::name(format!("no member '{:?}' in struct", id)))
+    }
+}
+
+/// ## instance method Struct#==
+/// - self == other -> bool
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Struct/i/=3d=3d.html
+fn eq(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let other = vm[0];
+    if self_val.id() == other.id() {
+        return Ok(Value::true_val());
+    }
+    if other.is_packed_value() || vm.globals.get_class(self_val) != vm.globals.get_class(other) {
+        return Ok(Value::false_val());
+    }
+    let lhs = members(vm, self_val)?;
+    let rhs = members(vm, other)?;
+    for ((_, l), (_, r)) in lhs.into_iter().zip(rhs) {
+        if !vm.eval_eq2(r, l)? {
+            return Ok(Value::false_val());
+        }
+    }
+    Ok(Value::true_val())
+}
+
+/// ## instance method Struct#eql?
+/// - eql?(other) -> bool
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Struct/i/eql=3f.html
+fn eql(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    Ok(Value::bool(self_val.eql(&vm[0])))
+}
+
+/// ## instance method Struct#hash
+/// - hash -> Integer
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Struct/i/hash.html
+fn hash(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let hash = fxhash::hash64(&HashKey(self_val));
+    Ok(Value::integer(hash as i64 >> 2))
+}
+
+/// ## instance method Struct#[]
+/// - self[member] -> object
+/// - self[index] -> object
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Struct/i/=5b=5d.html
+fn index(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let id = member_id(vm, self_val, vm[0])?;
+    Ok(get_member(self_val, id))
+}
+
+/// ## instance method Struct#[]=
+/// - self[member] = value
+/// - self[index] = value
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Struct/i/=5b=5d=3d.html
+fn index_assign(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(2)?;
+    self_val.check_frozen()?;
+    let id = member_id(vm, self_val, vm[0])?;
+    set_member(self_val, id, vm[1]);
+    Ok(vm[1])
+}
+
+/// ## instance method Struct#members
+/// - members -> [Symbol]
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Struct/i/members.html
+fn members_(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let names = members(vm, self_val)?
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    Ok(Value::array_from(names))
+}
+
+/// ## instance method Struct#size
+/// - size -> Integer
+/// - length -> Integer
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Struct/i/length.html
+fn size(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let names = member_names(vm.globals.get_class(self_val)).unwrap();
+    Ok(Value::integer(names.into_array().len() as i64))
+}
+
+/// ## instance method Struct#each
+/// - each {|value| ... } -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Struct/i/each.html
+fn each(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let block = match &args.block {
+        None => return vm.create_enumerator(IdentId::EACH, self_val, args.into(vm)),
+        Some(block) => block,
+    };
+    for (_, val) in members(vm, self_val)? {
+        vm.eval_block1(block, val)?;
+    }
+    Ok(self_val)
+}
+
+/// ## instance method Struct#each_pair
+/// - each_pair {|member, value| ... } -> self
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Struct/i/each_pair.html
+fn each_pair(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let block = match &args.block {
+        None => return vm.create_enumerator(IdentId::get_id("each_pair"), self_val, args.into(vm)),
+        Some(block) => block,
+    };
+    for (name, val) in members(vm, self_val)? {
+        vm.eval_block2(block, name, val)?;
+    }
+    Ok(self_val)
+}
+
+/// ## instance method Struct#to_a
+/// - to_a -> [object]
+/// - values -> [object]
+/// ## instance method Struct#deconstruct
+/// - deconstruct -> [object]
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Struct/i/deconstruct.html
+fn deconstruct(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let values = members(vm, self_val)?
+        .into_iter()
+        .map(|(_, val)| val)
+        .collect();
+    Ok(Value::array_from(values))
+}
+
+/// ## instance method Struct#to_h
+/// - to_h -> Hash
+/// - to_h {|member, value| block } -> Hash
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Struct/i/to_h.html
+fn to_h(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let mut map = FxIndexMap::default();
+    for (name, val) in members(vm, self_val)? {
+        let (k, v) = match &args.block {
+            None => (name, val),
+            Some(block) => {
+                let pair = vm.eval_block2(block, name, val)?;
+                match pair.as_array() {
+                    Some(ary) if ary.len() == 2 => (ary[0], ary[1]),
+                    _ => {
+                        return Err(RubyError::typeerr(format!(
+                            "wrong element type {} (expected array)",
+                            pair.get_class_name()
+                        )))
+                    }
+                }
+            }
+        };
+        map.insert(HashKey(k), v);
+    }
+    Ok(Value::hash_from_map(map))
+}
+
+/// ## instance method Struct#deconstruct_keys
+/// - deconstruct_keys(array_of_names) -> Hash
+/// - deconstruct_keys(nil) -> Hash
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Struct/i/deconstruct_keys.html
+fn deconstruct_keys(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let keys = vm[0];
+    if !keys.is_nil() && keys.as_array().is_none() {
+        return Err(RubyError::typeerr(format!(
+            "Wrong argument type {} (expected Array or nil)",
+            keys.get_class_name()
+        )));
+    }
+    let mut hash = Value::hash_from_map(FxIndexMap::default());
+    for (member, val) in members(vm, self_val)? {
+        if let Some(keys) = keys.as_array() {
+            if !keys.iter().any(|key| key.id() == member.id()) {
+                continue;
+            }
+        }
+        hash.as_mut_hash().unwrap().insert(member, val);
+    }
+    Ok(hash)
+}
+
+/// ## instance method Struct#values_at
+/// - values_at(*members) -> [object]
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Struct/i/values_at.html
+fn values_at(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    let values: Vec<_> = members(vm, self_val)?.into_iter().map(|(_, v)| v).collect();
+    let len = values.len() as i64;
+    let mut res = vec![];
+    for i in 0..args.len() {
+        let idx = vm[i];
+        if let Some(range) = idx.as_range() {
+            // Indexes beyond the members are filled with nil, as Array#values_at.
+            let bound = |v: Value| match v.as_fixnum() {
+                Some(i) if i < 0 => Ok(i + len),
+                Some(i) => Ok(i),
+                None => Err(VMError::no_implicit_conv(v, "Integer")),
+            };
+            let start = bound(range.start)?;
+            let end = bound(range.end)? + if range.exclude { 0 } else { 1 };
+            if start < 0 {
+                let range = range.inspect(vm)?;
+                return Err(RubyError::range(format!("{} out of range", range)));
+            }
+            for i in start..end {
+                res.push(values.get(i as usize).cloned().unwrap_or_default());
+            }
+        } else if idx.as_fixnum().is_some() {
+            let id = member_id(vm, self_val, idx)?;
+            res.push(get_member(self_val, id));
+        } else {
+            return Err(VMError::no_implicit_conv(idx, "Integer"));
+        }
+    }
+    Ok(Value::array_from(res))
+}
+
+/// ## instance method Struct#dig
+/// - dig(key, ...) -> object | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Struct/i/dig.html
+fn dig(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_min(1)?;
+    let keys = vm.args().to_vec();
+    let mut val = match member_id(vm, self_val, keys[0]) {
+        Ok(id) => get_member(self_val, id),
+        Err(_) => return Ok(Value::nil()),
+    };
+    for key in &keys[1..] {
+        if val.is_nil() {
+            break;
+        }
+        val = if let Some(ary) = val.as_array() {
+            ary.get_elem1(*key)?
+        } else if let Some(hash) = val.as_hash() {
+            hash.get(key).cloned().unwrap_or_default()
+        } else {
+            vm.eval_send1(IdentId::get_id("dig"), val, *key)?
+        };
+    }
+    Ok(val)
+}
+
 use std::borrow::Cow;
 fn inspect(vm: &mut VM, self_val: Value, _args: &Args2) -> VMResult {
     let mut inspect = format!("#<struct ");
@@ -84,11 +523,7 @@ fn inspect(vm: &mut VM, self_val: Value, _args: &Args2) -> VMResult {
         Some(name) => inspect += &name,
         None => {}
     };
-    let name = match vm
-        .globals
-        .get_class(self_val)
-        .get_var(IdentId::get_id("/members"))
-    {
+    let name = match member_names(vm.globals.get_class(self_val)) {
         Some(name) => name,
         None => return Err(RubyError::internal("No /members.")),
     };
@@ -145,4 +580,87 @@ mod tests {
         "###;
         assert_script(program);
     }
+
+    #[test]
+    fn struct_methods() {
+        let program = r###"
+        Point = Struct.new(:x, :y)
+        a = Point.new(1, 2)
+        b = Point.new(1, 2)
+        assert true, a == b
+        assert true, a.eql?(b)
+        assert true, a.hash == b.hash
+        assert false, a == Point.new(1, 3)
+        assert [1, 2], a.to_a
+        assert [1, 2], a.deconstruct
+        assert({ x: 1, y: 2 }, a.to_h)
+        assert({ "x" => 2, "y" => 4 }, a.to_h { |k, v| [k.to_s, v * 2] })
+        assert [:x, :y], a.members
+        assert [:x, :y], Point.members
+        assert 2, a.size
+        r = []
+        a.each { |v| r << v }
+        a.each_pair { |k, v| r << [k, v] }
+        assert [1, 2, [:x, 1], [:y, 2]], r
+        assert 1, a[0]
+        assert 2, a[-1]
+        assert 2, a[:y]
+        assert 1, a["x"]
+        a[:x] = 10
+        a[1] = 20
+        assert [10, 20], a.values_at(0, 1)
+        assert [10, 20, 20], a.values_at(0..1, -1)
+        assert [20, nil, nil], a.values_at(1..3)
+        assert [10], a.values_at(0...-1)
+        assert_error { a.values_at(-3..1) }
+        assert_error { a.values_at(2) }
+        assert_error { a.values_at(:x) }
+        assert_error { a[2] }
+        assert_error { a[:z] }
+        assert_error { Point.new(1, 2).freeze[:x] = 3 }
+        h = { b => "found" }
+        assert "found", h[Point.new(1, 2)]
+        "###;
+        assert_script(program);
+    }
+
+    #[test]
+    fn struct_keyword_init() {
+        let program = r###"
+        K = Struct.new(:name, :age, keyword_init: true)
+        k = K.new(name: "bob", age: 3)
+        assert "bob", k.name
+        assert 3, k.age
+        assert true, K.keyword_init?
+        assert_error { K.new("bob", 3) }
+        assert_error { K.new(foo: 1) }
+        S = Struct.new(:a, :b)
+        s = S.new(a: 5, b: 6)
+        assert [5, 6], s.to_a
+        assert_error { S.new(1, 2, 3) }
+        "###;
+        assert_script(program);
+    }
+
+    #[test]
+    fn struct_dig() {
+        let program = r###"
+        P = Struct.new(:x, :y)
+        N = Struct.new(:inner, :h)
+        n = N.new(P.new(1, 2), { k: [1, 2, 3] })
+        assert 1, n.dig(:inner, :x)
+        assert 3, n.dig(:h, :k, 2)
+        assert nil, n.dig(:foo)
+        class P3 < Struct.new(:x, :y, :z)
+          def sum
+            x + y + z
+          end
+        end
+        assert 6, P3.new(1, 2, 3).sum
+        assert [:x, :y, :z], P3.new(1, 2, 3).members
+        assert "#<struct P3 @x=1 @y=2 @z=3>", P3.new(1, 2, 3).inspect
+        p P3.new(1, 2, 3)
+        "###;
+        assert_script(program);
+    }
 }
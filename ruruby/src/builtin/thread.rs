@@ -0,0 +1,861 @@
+use crate::coroutine::*;
+use crate::*;
+use std::time::{Duration, Instant};
+
+///
+/// Green thread.
+///
+/// Each Thread other than the main thread runs on its own fiber,
+/// and is resumed by the `Scheduler`.
+///
+#[derive(Debug)]
+pub struct ThreadInfo {
+    /// The root fiber of the thread. `None` for the main thread.
+    fiber: Option<Box<FiberContext>>,
+    pub status: ThreadStatus,
+    /// The value of the block, or the exception object which terminated the thread.
+    pub value: Value,
+    /// Thread local variables. (Thread#[])
+    pub locals: FxIndexMap<IdentId, Value>,
+    /// Set by Thread#wakeup or ConditionVariable#signal.
+    pub wakeup: bool,
+    /// Set by Thread#kill. The thread is unwound by the kill error when it is resumed next.
+    killed: bool,
+    /// The time when the sleeping thread should wake up.
+    pub deadline: Option<Instant>,
+    /// True while the thread is resumed and on the machine stack.
+    running: bool,
+    pub report_on_exception: bool,
+    pub name: Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadStatus {
+    Run,
+    Sleep,
+    /// Terminated normally, or killed.
+    Dead,
+    /// Terminated by an exception.
+    Aborted,
+}
+
+impl GC<RValue> for ThreadInfo {
+    fn mark(&self, alloc: &mut Allocator<RValue>) {
+        if let Some(fiber) = &self.fiber {
+            fiber.mark(alloc);
+        }
+        self.value.mark(alloc);
+        self.locals.values().for_each(|v| v.mark(alloc));
+        self.name.mark(alloc);
+    }
+}
+
+impl ThreadInfo {
+    fn new(fiber: Option<FiberContext>) -> Self {
+        ThreadInfo {
+            fiber: fiber.map(Box::new),
+            status: ThreadStatus::Run,
+            value: Value::nil(),
+            locals: FxIndexMap::default(),
+            wakeup: false,
+            killed: false,
+            deadline: None,
+            running: false,
+            report_on_exception: true,
+            name: Value::nil(),
+        }
+    }
+
+    pub(crate) fn is_alive(&self) -> bool {
+        matches!(self.status, ThreadStatus::Run | ThreadStatus::Sleep)
+    }
+
+    pub(crate) fn is_main(&self) -> bool {
+        self.fiber.is_none()
+    }
+}
+
+///
+/// Scheduler for green threads.
+///
+/// Threads are switched when the timeslice of the running thread expired (checked at
+/// backward jumps and method calls), or when the running thread is blocked.
+///
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    pub main: Value,
+    pub current: Value,
+    /// Living threads except the main thread, in creation order.
+    pub threads: Vec<Value>,
+    /// Incremented whenever some blocked thread may be able to proceed.
+    pub event: usize,
+    /// The time when the current thread got control.
+    pub switched_at: Instant,
+}
+
+impl GC<RValue> for Scheduler {
+    fn mark(&self, alloc: &mut Allocator<RValue>) {
+        self.main.mark(alloc);
+        self.current.mark(alloc);
+        self.threads.iter().for_each(|v| v.mark(alloc));
+    }
+}
+
+impl Scheduler {
+    pub const TIMESLICE: Duration = Duration::from_millis(50);
+
+    pub(crate) fn new() -> Self {
+        Scheduler {
+            main: Value::nil(),
+            current: Value::nil(),
+            threads: vec![],
+            event: 0,
+            switched_at: Instant::now(),
+        }
+    }
+
+    /// Returns true if any thread other than the current one can proceed.
+    pub(crate) fn has_runnable(&self) -> bool {
+        std::iter::once(&self.main)
+            .chain(self.threads.iter())
+            .filter(|t| t.id() != self.current.id())
+            .any(|t| t.rvalue().thread().status == ThreadStatus::Run)
+    }
+
+    /// Returns the earliest time when some sleeping thread wakes up.
+    pub(crate) fn earliest_deadline(&self) -> Option<Instant> {
+        std::iter::once(&self.main)
+            .chain(self.threads.iter())
+            .filter_map(|t| {
+                let info = t.rvalue().thread();
+                match info.status {
+                    ThreadStatus::Sleep => info.deadline,
+                    _ => None,
+                }
+            })
+            .min()
+    }
+}
+
+impl VM {
+    /// Pass control to other threads if the timeslice of the current thread has expired.
+    #[inline(always)]
+    pub(crate) fn check_timeslice(&mut self) -> Result<(), RubyError> {
+        if !self.globals.scheduler.threads.is_empty()
+            && self.globals.scheduler.switched_at.elapsed() >= Scheduler::TIMESLICE
+        {
+            self.thread_pass()?;
+        }
+        Ok(())
+    }
+
+    fn is_thread_root(&self) -> bool {
+        self.handle.is_some_and(|h| h.is_thread())
+    }
+
+    /// Pass control to other threads.
+    ///
+    /// On the root fiber of a thread, control is given back to the scheduler.
+    /// Otherwise, every runnable thread is resumed once.
+    /// Returns the kill error if the current thread was killed in the meantime.
+    pub(crate) fn thread_pass(&mut self) -> Result<(), RubyError> {
+        // An exception object on the way of propagation must survive other threads.
+        let val = self.globals.val;
+        if self.is_thread_root() {
+            FiberHandle::thread_yield(self);
+        } else {
+            self.run_threads();
+        }
+        self.globals.val = val;
+        let mut current = self.globals.scheduler.current;
+        if std::mem::take(&mut current.as_thread().unwrap().killed) {
+            return Err(RubyError::thread_kill());
+        }
+        Ok(())
+    }
+
+    fn run_threads(&mut self) {
+        let threads = self.globals.scheduler.threads.clone();
+        let temp_len = self.temp_len();
+        self.temp_extend_from_slice(&threads);
+        for thread in threads {
+            self.resume_thread(thread);
+        }
+        self.temp_pop_vec(temp_len);
+        self.globals
+            .scheduler
+            .threads
+            .retain(|t| t.rvalue().thread().is_alive());
+    }
+
+    /// Resume `thread` until it passes control back or terminates.
+    fn resume_thread(&mut self, mut thread: Value) {
+        let info = thread.as_thread().unwrap();
+        if !info.is_alive() || info.running {
+            return;
+        }
+        let current = self.globals.scheduler.current;
+        info.running = true;
+        let fiber = info.fiber.as_deref_mut().unwrap() as *mut FiberContext;
+        self.globals.scheduler.current = thread;
+        self.globals.scheduler.switched_at = Instant::now();
+        let res = unsafe { (*fiber).resume(Value::nil()) };
+        let info = thread.as_thread().unwrap();
+        info.running = false;
+        if info.fiber.as_ref().unwrap().state == FiberState::Dead {
+            self.finish_thread(thread, res);
+        }
+        self.globals.scheduler.current = current;
+        self.globals.scheduler.switched_at = Instant::now();
+    }
+
+    fn finish_thread(&mut self, mut thread: Value, res: VMResult) {
+        let (status, value) = match res {
+            Ok(val) => (ThreadStatus::Dead, val),
+            Err(err) => match err.kind {
+                RubyErrorKind::SystemExit(_) | RubyErrorKind::ThreadKill => {
+                    (ThreadStatus::Dead, Value::nil())
+                }
+                _ => {
+                    let exception = self
+                        .globals
+                        .from_exception(&err)
+                        .unwrap_or(self.globals.val);
+                    if thread.as_thread().unwrap().report_on_exception {
+                        eprintln!(
+                            "{} terminated with exception (report_on_exception is true):",
+                            inspect_thread(thread)
+                        );
+                        self.show_err(&err);
+                    }
+                    (ThreadStatus::Aborted, exception)
+                }
+            },
+        };
+        let info = thread.as_thread().unwrap();
+        info.status = status;
+        info.value = value;
+        self.globals.scheduler.event += 1;
+    }
+
+    /// Block the current thread until `cond` is satisfied, running other threads in the meantime.
+    ///
+    /// Returns Ok(false) if `deadline` passed before `cond` was satisfied.
+    pub(crate) fn thread_wait(
+        &mut self,
+        deadline: Option<Instant>,
+        mut cond: impl FnMut(&mut VM) -> bool,
+    ) -> Result<bool, RubyError> {
+        let mut current = self.globals.scheduler.current;
+        let res = loop {
+            if cond(self) {
+                break Ok(true);
+            }
+            if deadline.is_some_and(|d| Instant::now() >= d) {
+                break Ok(false);
+            }
+            let info = current.as_thread().unwrap();
+            info.status = ThreadStatus::Sleep;
+            info.deadline = deadline;
+            if self.is_thread_root() {
+                if let Err(err) = self.thread_pass() {
+                    break Err(err);
+                }
+                continue;
+            }
+            let event = self.globals.scheduler.event;
+            if let Err(err) = self.thread_pass() {
+                break Err(err);
+            }
+            if self.globals.scheduler.event != event || self.globals.scheduler.has_runnable() {
+                continue;
+            }
+            // No thread can proceed until some sleeping thread wakes up.
+            match self.globals.scheduler.earliest_deadline() {
+                Some(wake) => {
+                    let duration = wake.saturating_duration_since(Instant::now());
+                    std::thread::sleep(duration.min(Duration::from_millis(10)));
+                }
+                None => break Err(RubyError::fatal("No live threads left. Deadlock?")),
+            }
+        };
+        let info = current.as_thread().unwrap();
+        info.status = ThreadStatus::Run;
+        info.deadline = None;
+        res
+    }
+
+    /// Let other threads run before a blocking IO operation.
+    pub(crate) fn thread_before_io(&mut self) -> Result<(), RubyError> {
+        if self.globals.scheduler.threads.is_empty() {
+            return Ok(());
+        }
+        if self.is_thread_root() {
+            return self.thread_pass();
+        }
+        loop {
+            let event = self.globals.scheduler.event;
+            self.thread_pass()?;
+            if self.globals.scheduler.event == event && !self.globals.scheduler.has_runnable() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Sleep the current thread for `secs` seconds, or until woken up if `secs` is None.
+    ///
+    /// If `deadlockable` is false, sleeping forever does not raise the deadlock error.
+    /// Returns Ok(true) if woken up by Thread#wakeup.
+    pub(crate) fn thread_sleep(
+        &mut self,
+        secs: Option<f64>,
+        deadlockable: bool,
+    ) -> Result<bool, RubyError> {
+        let deadline = match secs {
+            Some(secs) => Some(Instant::now() + Duration::from_secs_f64(secs)),
+            None if deadlockable => None,
+            None => Some(Instant::now() + Duration::from_secs(u32::MAX as u64)),
+        };
+        let mut current = self.globals.scheduler.current;
+        current.as_thread().unwrap().wakeup = false;
+        if self.globals.scheduler.threads.is_empty() {
+            if deadlockable {
+                return Err(RubyError::thread("stopping only thread"));
+            }
+            std::thread::sleep(deadline.unwrap().saturating_duration_since(Instant::now()));
+            return Ok(false);
+        }
+        self.thread_wait(deadline, |_| {
+            std::mem::take(&mut current.as_thread().unwrap().wakeup)
+        })
+    }
+}
+
+/// Convert a timeout value in seconds into a deadline.
+pub(crate) fn timeout_to_deadline(timeout: Value) -> Result<Option<Instant>, RubyError> {
+    if timeout.is_nil() {
+        return Ok(None);
+    }
+    let secs = match timeout.unpack() {
+        RV::Integer(i) => i as f64,
+        RV::Float(f) => f,
+        _ => return Err(RubyError::typeerr("Timeout must be Integer or Float.")),
+    };
+    Ok(Some(
+        Instant::now() + Duration::from_secs_f64(secs.max(0.0)),
+    ))
+}
+
+pub(crate) fn init(globals: &mut Globals) -> Value {
+    let mut class = Module::class_under_object();
+    globals.set_toplevel_constant("Thread", class);
+    class.add_builtin_class_method(globals, "new", thread_new);
+    class.add_builtin_class_method(globals, "start", thread_new);
+    class.add_builtin_class_method(globals, "fork", thread_new);
+    class.add_builtin_class_method(globals, "current", current);
+    class.add_builtin_class_method(globals, "main", main);
+    class.add_builtin_class_method(globals, "list", list);
+    class.add_builtin_class_method(globals, "pass", pass);
+    class.add_builtin_class_method(globals, "stop", stop);
+    class.add_builtin_class_method(globals, "exit", exit);
+    class.add_builtin_class_method(globals, "kill", kill_thread);
+
+    class.add_builtin_method_by_str(globals, "join", join);
+    class.add_builtin_method_by_str(globals, "value", value);
+    class.add_builtin_method_by_str(globals, "alive?", alive);
+    class.add_builtin_method_by_str(globals, "stop?", stop_);
+    class.add_builtin_method_by_str(globals, "status", status);
+    class.add_builtin_method_by_str(globals, "kill", kill);
+    class.add_builtin_method_by_str(globals, "exit", kill);
+    class.add_builtin_method_by_str(globals, "terminate", kill);
+    class.add_builtin_method_by_str(globals, "wakeup", wakeup);
+    class.add_builtin_method_by_str(globals, "run", run);
+    class.add_builtin_method_by_str(globals, "[]", get_local);
+    class.add_builtin_method_by_str(globals, "[]=", set_local);
+    class.add_builtin_method_by_str(globals, "key?", key);
+    class.add_builtin_method_by_str(globals, "keys", keys);
+    class.add_builtin_method_by_str(globals, "name", name);
+    class.add_builtin_method_by_str(globals, "name=", set_name);
+    class.add_builtin_method_by_str(globals, "report_on_exception", report_on_exception);
+    class.add_builtin_method_by_str(globals, "report_on_exception=", set_report_on_exception);
+    class.add_builtin_method_by_str(globals, "inspect", inspect);
+    class.add_builtin_method_by_str(globals, "to_s", inspect);
+
+    // Thread::Backtrace::Location
+    let mut backtrace = Module::module();
+    class.set_const_by_str("Backtrace", backtrace.into());
+    let location = Module::class_under_object();
+    backtrace.set_const_by_str("Location", location.into());
+    for name in &["path", "lineno", "label"] {
+        builtin::module::define_reader(globals, location, IdentId::get_id(name));
+    }
+    location.add_builtin_method_by_str(globals, "absolute_path", location_path);
+    location.add_builtin_method_by_str(globals, "base_label", location_base_label);
+    location.add_builtin_method_by_str(globals, "to_s", location_to_s);
+    location.add_builtin_method_by_str(globals, "inspect", location_inspect);
+
+    let main = Value::thread(class, ThreadInfo::new(None));
+    globals.scheduler.main = main;
+    globals.scheduler.current = main;
+    class.into()
+}
+
+/// Create a Thread::Backtrace::Location object from `loc`.
+pub(crate) fn backtrace_location(globals: &Globals, loc: &BacktraceLoc) -> Value {
+    let location = globals
+        .get_toplevel_constant("Thread")
+        .into_module()
+        .get_const_noautoload(IdentId::get_id("Backtrace"))
+        .unwrap()
+        .into_module()
+        .get_const_noautoload(IdentId::get_id("Location"))
+        .unwrap()
+        .into_module();
+    let val = Value::ordinary_object(location);
+    val.set_var_by_str("@path", Value::string(loc.path()));
+    val.set_var_by_str("@lineno", Value::integer(loc.lineno() as i64));
+    val.set_var_by_str("@label", Value::string(&loc.label));
+    val
+}
+
+fn location_var(self_val: Value, name: &str) -> Value {
+    self_val.get_var(IdentId::get_id(name)).unwrap_or_default()
+}
+
+/// ## instance method Thread::Backtrace::Location#absolute_path
+/// - absolute_path -> String
+fn location_path(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(location_var(self_val, "@path"))
+}
+
+/// ## instance method Thread::Backtrace::Location#base_label
+/// - base_label -> String
+fn location_base_label(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let mut label = location_var(self_val, "@label");
+    let label = label.expect_string("label")?;
+    let base = match label.find(" in ") {
+        Some(pos) if label.starts_with("block") => &label[pos + 4..],
+        _ => label,
+    };
+    Ok(Value::string(base))
+}
+
+/// ## instance method Thread::Backtrace::Location#to_s
+/// - to_s -> String
+fn location_to_s(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let path = location_var(self_val, "@path");
+    let lineno = location_var(self_val, "@lineno");
+    let label = location_var(self_val, "@label");
+    Ok(Value::string(format!(
+        "{}:{}:in `{}'",
+        path.val_to_s(vm)?,
+        lineno.val_to_s(vm)?,
+        label.val_to_s(vm)?
+    )))
+}
+
+/// ## instance method Thread::Backtrace::Location#inspect
+/// - inspect -> String
+fn location_inspect(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    let s = location_to_s(vm, self_val, args)?;
+    Ok(Value::string(vm.val_inspect(s)?))
+}
+
+fn inspect_thread(thread: Value) -> String {
+    let info = thread.rvalue().thread();
+    let status = match info.status {
+        ThreadStatus::Run => "run",
+        ThreadStatus::Sleep => "sleep",
+        ThreadStatus::Dead | ThreadStatus::Aborted => "dead",
+    };
+    match info.name.as_string() {
+        Some(name) => format!("#<Thread:0x{:016x}@{} {}>", thread.id(), name, status),
+        None => format!("#<Thread:0x{:016x} {}>", thread.id(), status),
+    }
+}
+
+// Class methods
+
+/// Thread.new(*arg) {|*arg| ... } -> Thread
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Thread/s/new.html
+fn thread_new(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    let block = match &args.block {
+        Some(block) => block,
+        None => return Err(RubyError::thread("must be called with a block")),
+    };
+    let start = ThreadStart {
+        block: vm.create_proc(block),
+        args: vm.args().to_vec(),
+    };
+    let fiber = FiberContext::new_thread(vm.create_fiber(), start);
+    let thread = Value::thread(Module::new(self_val), ThreadInfo::new(Some(fiber)));
+    vm.globals.scheduler.threads.push(thread);
+    Ok(thread)
+}
+
+/// Thread.current -> Thread
+fn current(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(vm.globals.scheduler.current)
+}
+
+/// Thread.main -> Thread
+fn main(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(vm.globals.scheduler.main)
+}
+
+/// Thread.list -> [Thread]
+fn list(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let scheduler = &vm.globals.scheduler;
+    let threads = std::iter::once(scheduler.main)
+        .chain(scheduler.threads.iter().cloned())
+        .filter(|t| t.rvalue().thread().is_alive())
+        .collect();
+    Ok(Value::array_from(threads))
+}
+
+/// Thread.pass -> nil
+fn pass(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    vm.thread_pass()?;
+    Ok(Value::nil())
+}
+
+/// Thread.stop -> nil
+fn stop(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    vm.thread_sleep(None, true)?;
+    Ok(Value::nil())
+}
+
+/// Thread.exit -> ()
+fn exit(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let current = vm.globals.scheduler.current;
+    kill_(vm, current)
+}
+
+/// Thread.kill(thread) -> Thread
+fn kill_thread(vm: &mut VM, _: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let mut thread = vm[0];
+    thread.expect_thread("1st arg")?;
+    kill_(vm, thread)
+}
+
+// Instance methods
+
+/// Thread#join(limit = nil) -> Thread | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Thread/i/join.html
+fn join(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
+    let deadline = if args.len() == 1 {
+        timeout_to_deadline(vm[0])?
+    } else {
+        None
+    };
+    self_val.expect_thread("Receiver")?;
+    if self_val.id() == vm.globals.scheduler.current.id() {
+        return Err(RubyError::thread(
+            "Target thread must not be current thread",
+        ));
+    }
+    if self_val.rvalue().thread().is_main() {
+        return Err(RubyError::thread("Target thread must not be main thread"));
+    }
+    if !vm.thread_wait(deadline, |_| !self_val.rvalue().thread().is_alive())? {
+        return Ok(Value::nil());
+    }
+    let info = self_val.as_thread().unwrap();
+    if info.status == ThreadStatus::Aborted {
+        vm.globals.val = info.value;
+        return Err(RubyError::value());
+    }
+    Ok(self_val)
+}
+
+/// Thread#value -> object
+fn value(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    join(vm, self_val, args)?;
+    Ok(self_val.as_thread().unwrap().value)
+}
+
+/// Thread#alive? -> bool
+fn alive(_: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let info = self_val.expect_thread("Receiver")?;
+    Ok(Value::bool(info.is_alive()))
+}
+
+/// Thread#stop? -> bool
+fn stop_(_: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let info = self_val.expect_thread("Receiver")?;
+    Ok(Value::bool(info.status != ThreadStatus::Run))
+}
+
+/// Thread#status -> String | false | nil
+///
+/// https://docs.ruby-lang.org/ja/latest/method/Thread/i/status.html
+fn status(_: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let info = self_val.expect_thread("Receiver")?;
+    let status = match info.status {
+        ThreadStatus::Run => Value::string("run"),
+        ThreadStatus::Sleep => Value::string("sleep"),
+        ThreadStatus::Dead => Value::false_val(),
+        ThreadStatus::Aborted => Value::nil(),
+    };
+    Ok(status)
+}
+
+/// Thread#kill -> Thread
+fn kill(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    self_val.expect_thread("Receiver")?;
+    kill_(vm, self_val)
+}
+
+fn kill_(vm: &mut VM, mut thread: Value) -> VMResult {
+    let is_current = thread.id() == vm.globals.scheduler.current.id();
+    let info = thread.as_thread().unwrap();
+    if !info.is_alive() {
+        return Ok(thread);
+    }
+    if info.is_main() {
+        return Err(RubyError::system_exit(0));
+    }
+    if is_current {
+        return Err(RubyError::thread_kill());
+    }
+    vm.globals.scheduler.event += 1;
+    if info.fiber.as_ref().unwrap().state == FiberState::Created {
+        // The thread has not started yet.
+        info.status = ThreadStatus::Dead;
+        info.value = Value::nil();
+    } else {
+        // Resume the thread to unwind it, running its ensure clauses.
+        info.killed = true;
+        vm.resume_thread(thread);
+    }
+    vm.globals
+        .scheduler
+        .threads
+        .retain(|t| t.rvalue().thread().is_alive());
+    Ok(thread)
+}
+
+/// Thread#wakeup -> Thread
+fn wakeup(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let info = self_val.expect_thread("Receiver")?;
+    if !info.is_alive() {
+        return Err(RubyError::thread("killed thread"));
+    }
+    info.wakeup = true;
+    vm.globals.scheduler.event += 1;
+    Ok(self_val)
+}
+
+/// Thread#run -> Thread
+fn run(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    wakeup(vm, self_val, args)?;
+    vm.thread_pass()?;
+    Ok(self_val)
+}
+
+/// Thread#[](name) -> object | nil
+fn get_local(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let id = vm[0].expect_symbol_or_string("1st arg")?;
+    let info = self_val.expect_thread("Receiver")?;
+    Ok(info.locals.get(&id).cloned().unwrap_or_default())
+}
+
+/// Thread#[]=(name, val)
+fn set_local(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(2)?;
+    let id = vm[0].expect_symbol_or_string("1st arg")?;
+    let val = vm[1];
+    let info = self_val.expect_thread("Receiver")?;
+    if val.is_nil() {
+        info.locals.shift_remove(&id);
+    } else {
+        info.locals.insert(id, val);
+    }
+    Ok(val)
+}
+
+/// Thread#key?(name) -> bool
+fn key(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let id = vm[0].expect_symbol_or_string("1st arg")?;
+    let info = self_val.expect_thread("Receiver")?;
+    Ok(Value::bool(info.locals.contains_key(&id)))
+}
+
+/// Thread#keys -> [Symbol]
+fn keys(_: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let info = self_val.expect_thread("Receiver")?;
+    let keys = info.locals.keys().map(|id| Value::symbol(*id)).collect();
+    Ok(Value::array_from(keys))
+}
+
+/// Thread#name -> String | nil
+fn name(_: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let info = self_val.expect_thread("Receiver")?;
+    Ok(info.name)
+}
+
+/// Thread#name=(name)
+fn set_name(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let name = vm[0];
+    if !name.is_nil() {
+        name.clone().expect_string("name")?;
+    }
+    self_val.expect_thread("Receiver")?.name = name;
+    Ok(name)
+}
+
+/// Thread#report_on_exception -> bool
+fn report_on_exception(_: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let info = self_val.expect_thread("Receiver")?;
+    Ok(Value::bool(info.report_on_exception))
+}
+
+/// Thread#report_on_exception=(bool)
+fn set_report_on_exception(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let flag = vm[0];
+    self_val.expect_thread("Receiver")?.report_on_exception = flag.to_bool();
+    Ok(flag)
+}
+
+/// Thread#inspect -> String
+fn inspect(_: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    self_val.expect_thread("Receiver")?;
+    Ok(Value::string(inspect_thread(self_val)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::*;
+
+    #[test]
+    fn thread() {
+        let program = r#"
+        t = Thread.new(3, 4) { |a, b| a * b }
+        assert 12, t.value
+        assert false, t.alive?
+        assert false, t.status
+        assert t, t.join
+        assert Thread.main, Thread.current
+        assert "run", Thread.current.status
+
+        res = []
+        t1 = Thread.new { 3.times { |i| res << [:a, i]; Thread.pass } }
+        t2 = Thread.new { 3.times { |i| res << [:b, i]; Thread.pass } }
+        [t1, t2].each(&:join)
+        assert [[:a, 0], [:b, 0], [:a, 1], [:b, 1], [:a, 2], [:b, 2]], res
+
+        t = Thread.new { Thread.current[:x] = 10; Thread.current[:x] }
+        assert 10, t.value
+        assert nil, Thread.current[:x]
+        assert [t.inspect], [t.to_s]
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn thread_preemption() {
+        let program = r#"
+        flag = false
+        t = Thread.new { i = 0; i += 1 until flag; i }
+        sleep 0.1
+        flag = true
+        assert true, t.value > 0
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn thread_exception() {
+        let program = r#"
+        t = Thread.new { raise ArgumentError.new("boo") }
+        t.report_on_exception = false
+        begin
+          t.join
+        rescue ArgumentError => e
+          assert "boo", e.message
+        end
+        assert nil, t.status
+        assert_error { t.value }
+
+        t = Thread.new { sleep }
+        Thread.pass
+        assert "sleep", t.status
+        t.kill
+        assert false, t.alive?
+        assert_error { Thread.current.join }
+        assert_error { Thread.new }
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn thread_kill() {
+        let program = r#"
+        m = Mutex.new
+        log = []
+        t = Thread.new do
+          begin
+            m.synchronize { sleep }
+          ensure
+            log << :ensure
+          end
+        end
+        Thread.pass
+        assert true, m.locked?
+        t.kill
+        assert [false, false, [:ensure]], [m.locked?, t.alive?, log]
+        m.synchronize { log << :locked }
+
+        t = Thread.new do
+          begin
+            loop {}
+          rescue Exception
+            log << :rescued
+          ensure
+            log << :busy
+          end
+        end
+        sleep 0.1
+        t.kill
+        assert [false, nil], [t.alive?, t.value]
+
+        t = Thread.new do
+          begin
+            Thread.exit
+          ensure
+            log << :exit
+          end
+        end
+        t.join
+        assert [:ensure, :locked, :busy, :exit], log
+        "#;
+        assert_script(program);
+    }
+}
@@ -1,17 +1,38 @@
 use crate::*;
-use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, Utc};
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone, Timelike,
+    Utc,
+};
+use num::{BigInt, BigRational, ToPrimitive};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use std::convert::TryFrom;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
 pub enum TimeInfo {
     Local(DateTime<FixedOffset>),
     UTC(DateTime<Utc>),
 }
 
+/// Two Time objects are equal when they denote the same instant, whatever their zones are.
+impl PartialEq for TimeInfo {
+    fn eq(&self, other: &Self) -> bool {
+        self.nanos() == other.nanos()
+    }
+}
+
 impl std::fmt::Display for TimeInfo {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let t = self.fixed();
+        write!(f, "{}", t.format("%Y-%m-%d %H:%M:%S"))?;
+        let subsec = format!("{:09}", t.nanosecond());
+        let subsec = subsec.trim_end_matches('0');
+        if !subsec.is_empty() {
+            write!(f, ".{}", subsec)?;
+        }
         match self {
-            TimeInfo::Local(t) => write!(f, "{}", t),
-            TimeInfo::UTC(t) => write!(f, "{}", t),
+            TimeInfo::Local(t) => write!(f, " {}", t.format("%z")),
+            TimeInfo::UTC(_) => write!(f, " UTC"),
         }
     }
 }
@@ -48,106 +69,777 @@ impl std::ops::Add<Duration> for TimeInfo {
     }
 }
 
+const NANOS_PER_SEC: i128 = 1_000_000_000;
+
+impl TimeInfo {
+    /// The wall clock time of `self` with its UTC offset.
+    fn fixed(&self) -> DateTime<FixedOffset> {
+        match self {
+            TimeInfo::Local(t) => *t,
+            TimeInfo::UTC(t) => t.with_timezone(&FixedOffset::east_opt(0).unwrap()),
+        }
+    }
+
+    fn utc(&self) -> DateTime<Utc> {
+        match self {
+            TimeInfo::Local(t) => t.with_timezone(&Utc),
+            TimeInfo::UTC(t) => *t,
+        }
+    }
+
+    fn is_utc(&self) -> bool {
+        matches!(self, TimeInfo::UTC(_))
+    }
+
+    fn utc_offset(&self) -> i32 {
+        self.fixed().offset().local_minus_utc()
+    }
+
+    /// Nanoseconds since the Unix epoch.
+    fn nanos(&self) -> i128 {
+        let t = self.utc();
+        t.timestamp() as i128 * NANOS_PER_SEC + t.timestamp_subsec_nanos() as i128
+    }
+
+    fn from_nanos(nanos: i128, zone: Zone) -> Result<Self, RubyError> {
+        let secs = nanos.div_euclid(NANOS_PER_SEC);
+        let subsec = nanos.rem_euclid(NANOS_PER_SEC) as u32;
+        let utc = i64::try_from(secs)
+            .ok()
+            .and_then(|secs| Utc.timestamp_opt(secs, subsec).single())
+            .ok_or_else(|| RubyError::range("time out of range"))?;
+        Ok(zone.time(utc))
+    }
+
+    /// The same instant as `self` at `nanos` since the Unix epoch, keeping the UTC offset.
+    fn with_nanos(&self, nanos: i128) -> Result<Self, RubyError> {
+        let zone = match self {
+            TimeInfo::Local(t) => Zone::Fixed(*t.offset()),
+            TimeInfo::UTC(_) => Zone::Utc,
+        };
+        Self::from_nanos(nanos, zone)
+    }
+
+    /// The abbreviated name of the timezone, or None for a zone given as a bare UTC offset.
+    fn zone(&self) -> Option<String> {
+        match self {
+            TimeInfo::Local(t) => local_zone_name(t),
+            TimeInfo::UTC(_) => Some("UTC".to_string()),
+        }
+    }
+}
+
+/// Look up the name the system gives to the local timezone at `t`.
+#[cfg(unix)]
+fn local_zone_name(t: &DateTime<FixedOffset>) -> Option<String> {
+    let secs = t.timestamp() as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&secs, &mut tm) }.is_null() || tm.tm_zone.is_null() {
+        return None;
+    }
+    if tm.tm_gmtoff != t.offset().local_minus_utc() as libc::c_long {
+        return None;
+    }
+    let name = unsafe { std::ffi::CStr::from_ptr(tm.tm_zone) };
+    Some(name.to_string_lossy().into_owned())
+}
+
+#[cfg(not(unix))]
+fn local_zone_name(_: &DateTime<FixedOffset>) -> Option<String> {
+    None
+}
+
+/// The timezone a Time object is created in.
+#[derive(Clone, Copy, Debug)]
+enum Zone {
+    Utc,
+    Local,
+    Fixed(FixedOffset),
+}
+
+impl Zone {
+    /// Convert `nil`, a UTC offset in seconds, or a zone string such as "UTC" and "+09:00".
+    fn from_value(val: Value) -> Result<Self, RubyError> {
+        if val.is_nil() {
+            return Ok(Zone::Local);
+        }
+        if let Some(i) = val.as_fixnum() {
+            return Zone::from_offset(i);
+        }
+        if let Some(s) = val.as_rstring() {
+            let s = s.to_s();
+            return Zone::from_str(&s).ok_or_else(|| {
+                RubyError::argument(format!(
+                    "\"+HH:MM\", \"-HH:MM\", \"UTC\" or \"A\"..\"I\",\"K\"..\"Z\" expected for utc_offset: {}",
+                    s
+                ))
+            });
+        }
+        Err(VMError::no_implicit_conv(val, "String"))
+    }
+
+    fn from_offset(secs: i64) -> Result<Self, RubyError> {
+        if secs.abs() >= 86400 {
+            return Err(RubyError::argument("utc_offset out of range"));
+        }
+        Ok(Zone::Fixed(FixedOffset::east_opt(secs as i32).unwrap()))
+    }
+
+    fn from_str(s: &str) -> Option<Self> {
+        static OFFSET: Lazy<Regex> =
+            Lazy::new(|| Regex::new(r"^([+-])(\d{2})(?::?(\d{2})(?::?(\d{2}))?)?$").unwrap());
+        match s {
+            "UTC" | "Z" | "-00:00" => return Some(Zone::Utc),
+            _ => {}
+        }
+        if let Some(offset) = military_zone(s) {
+            return Zone::from_offset(offset).ok();
+        }
+        let cap = OFFSET.captures(s)?;
+        let num = |i: usize| cap.get(i).map_or(0, |m| m.as_str().parse::<i64>().unwrap());
+        let (h, m, s) = (num(2), num(3), num(4));
+        if h > 23 || m > 59 || s > 59 {
+            return None;
+        }
+        let offset = h * 3600 + m * 60 + s;
+        Zone::from_offset(if &cap[1] == "-" { -offset } else { offset }).ok()
+    }
+
+    /// The time at the instant `utc` in this zone.
+    fn time(self, utc: DateTime<Utc>) -> TimeInfo {
+        match self {
+            Zone::Utc => TimeInfo::UTC(utc),
+            Zone::Local => TimeInfo::Local(utc.with_timezone(&Local).into()),
+            Zone::Fixed(offset) => TimeInfo::Local(utc.with_timezone(&offset)),
+        }
+    }
+
+    /// The time whose wall clock in this zone reads `naive`.
+    fn wall_clock(self, naive: NaiveDateTime) -> TimeInfo {
+        match self {
+            Zone::Utc => TimeInfo::UTC(DateTime::<Utc>::from_naive_utc_and_offset(naive, Utc)),
+            Zone::Local => {
+                // A wall clock time skipped by a DST transition is taken as one hour later.
+                let t = Local
+                    .from_local_datetime(&naive)
+                    .earliest()
+                    .or_else(|| {
+                        Local
+                            .from_local_datetime(&(naive + Duration::hours(1)))
+                            .earliest()
+                    })
+                    .unwrap_or_else(|| Local.from_utc_datetime(&naive));
+                TimeInfo::Local(t.into())
+            }
+            Zone::Fixed(offset) => {
+                TimeInfo::Local(offset.from_local_datetime(&naive).single().unwrap())
+            }
+        }
+    }
+}
+
+/// UTC offset of the military timezones "A".."I", "K".."Z".
+fn military_zone(s: &str) -> Option<i64> {
+    let c = match s.as_bytes() {
+        [c] if c.is_ascii_uppercase() && *c != b'J' => *c,
+        _ => return None,
+    };
+    let hour = match c {
+        b'A'..=b'I' => (c - b'A' + 1) as i64,
+        b'K'..=b'M' => (c - b'K' + 10) as i64,
+        b'N'..=b'Y' => -((c - b'N' + 1) as i64),
+        _ => 0,
+    };
+    Some(hour * 3600)
+}
+
 pub(crate) fn init(globals: &mut Globals) -> Value {
-    let class = Module::class_under_object();
+    let mut class = Module::class_under_object();
     globals.set_toplevel_constant("Time", class);
+    class.append_include_without_increment_version(BuiltinClass::comparable());
     class.add_builtin_class_method(globals, "now", time_now);
+    class.add_builtin_class_method(globals, "new", time_new);
+    class.add_builtin_class_method(globals, "at", time_at);
     class.add_builtin_class_method(globals, "utc", time_utc);
     class.add_builtin_class_method(globals, "gm", time_utc);
+    class.add_builtin_class_method(globals, "local", time_local);
+    class.add_builtin_class_method(globals, "mktime", time_local);
+    class.add_builtin_class_method(globals, "iso8601", time_iso8601);
+    class.add_builtin_class_method(globals, "xmlschema", time_iso8601);
+    class.add_builtin_class_method(globals, "parse", time_parse);
 
     class.add_builtin_method_by_str(globals, "inspect", inspect);
     class.add_builtin_method_by_str(globals, "to_s", to_s);
     class.add_builtin_method_by_str(globals, "gmtime", utc);
     class.add_builtin_method_by_str(globals, "utc", utc);
+    class.add_builtin_method_by_str(globals, "localtime", localtime);
+    class.add_builtin_method_by_str(globals, "getlocal", getlocal);
+    class.add_builtin_method_by_str(globals, "getutc", getutc);
+    class.add_builtin_method_by_str(globals, "getgm", getutc);
     class.add_builtin_method_by_str(globals, "-", sub);
     class.add_builtin_method_by_str(globals, "+", add);
+    class.add_builtin_method_by_str(globals, "<=>", cmp);
+    class.add_builtin_method_by_str(globals, "eql?", eql);
+    class.add_builtin_method_by_str(globals, "hash", hash);
     class.add_builtin_method_by_str(globals, "year", year);
     class.add_builtin_method_by_str(globals, "month", month);
     class.add_builtin_method_by_str(globals, "mon", month);
     class.add_builtin_method_by_str(globals, "mday", day);
     class.add_builtin_method_by_str(globals, "day", day);
+    class.add_builtin_method_by_str(globals, "hour", hour);
+    class.add_builtin_method_by_str(globals, "min", min);
+    class.add_builtin_method_by_str(globals, "sec", sec);
+    class.add_builtin_method_by_str(globals, "usec", usec);
+    class.add_builtin_method_by_str(globals, "tv_usec", usec);
+    class.add_builtin_method_by_str(globals, "nsec", nsec);
+    class.add_builtin_method_by_str(globals, "tv_nsec", nsec);
+    class.add_builtin_method_by_str(globals, "wday", wday);
+    class.add_builtin_method_by_str(globals, "yday", yday);
+    class.add_builtin_method_by_str(globals, "zone", zone);
+    class.add_builtin_method_by_str(globals, "utc_offset", utc_offset);
+    class.add_builtin_method_by_str(globals, "gmt_offset", utc_offset);
+    class.add_builtin_method_by_str(globals, "gmtoff", utc_offset);
+    class.add_builtin_method_by_str(globals, "utc?", is_utc);
+    class.add_builtin_method_by_str(globals, "gmt?", is_utc);
+    class.add_builtin_method_by_str(globals, "to_i", to_i);
+    class.add_builtin_method_by_str(globals, "tv_sec", to_i);
+    class.add_builtin_method_by_str(globals, "to_f", to_f);
+    class.add_builtin_method_by_str(globals, "to_r", to_r);
+    class.add_builtin_method_by_str(globals, "round", round);
+    class.add_builtin_method_by_str(globals, "floor", floor);
+    class.add_builtin_method_by_str(globals, "ceil", ceil);
+    class.add_builtin_method_by_str(globals, "strftime", strftime);
+    class.add_builtin_method_by_str(globals, "iso8601", iso8601);
+    class.add_builtin_method_by_str(globals, "xmlschema", iso8601);
     class.into()
 }
 
+/// Get the zone given by the `in:` keyword argument.
+fn zone_kwarg(args: &Args2) -> Result<Option<Zone>, RubyError> {
+    match args.kw_arg.as_hash() {
+        Some(hash) => match hash.get(&Value::symbol_from_str("in")) {
+            Some(v) => Ok(Some(Zone::from_value(*v)?)),
+            None => Ok(None),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Convert a number of seconds (Integer, Float, Rational or Time) to nanoseconds.
+fn to_nanos(val: Value, unit: i128) -> Result<i128, RubyError> {
+    if let Some(i) = val.as_fixnum() {
+        return Ok(i as i128 * unit);
+    }
+    if let Some(b) = val.as_bignum() {
+        return (b * BigInt::from(unit))
+            .to_i128()
+            .ok_or_else(|| RubyError::range("time out of range"));
+    }
+    if let Some(f) = val.as_float() {
+        if !f.is_finite() {
+            return Err(RubyError::range(format!("{} out of Time range", f)));
+        }
+        return Ok((f * unit as f64).floor() as i128);
+    }
+    if let Some(r) = val.as_rational() {
+        return (r * BigInt::from(unit))
+            .floor()
+            .to_integer()
+            .to_i128()
+            .ok_or_else(|| RubyError::range("time out of range"));
+    }
+    if let Some(info) = val.as_rvalue() {
+        if info.kind() == ObjKind::TIME {
+            return Ok(info.time().nanos() * unit / NANOS_PER_SEC);
+        }
+    }
+    Err(RubyError::typeerr(format!(
+        "can't convert {} into an exact number",
+        val.get_class_name()
+    )))
+}
+
+/// Convert a Time argument such as year and hour to Integer. Strings of digits are accepted.
+fn time_arg(val: Option<Value>, default: i64) -> Result<i64, RubyError> {
+    match val {
+        None => Ok(default),
+        Some(v) if v.is_nil() => Ok(default),
+        Some(v) => {
+            match v.as_rstring() {
+                Some(s) => s.to_s().trim().parse().map_err(|_| {
+                    RubyError::argument(format!("invalid value for Integer(): {:?}", s))
+                }),
+                None => v.coerce_to_fixnum("Args"),
+            }
+        }
+    }
+}
+
+/// Convert a month argument, which may be a number or an abbreviated month name.
+fn month_arg(val: Option<Value>) -> Result<i64, RubyError> {
+    if let Some(s) = val.and_then(|v| v.as_rstring().map(|s| s.to_s().into_owned())) {
+        if let Some(m) = month_from_name(&s) {
+            return Ok(m as i64);
+        }
+    }
+    time_arg(val, 1)
+}
+
+/// Convert a second argument that may have a fractional part to (seconds, nanoseconds).
+fn sec_arg(val: Option<Value>) -> Result<(i64, i64), RubyError> {
+    match val {
+        Some(v) if v.as_float().is_some() || v.as_rational().is_some() => {
+            let nanos = to_nanos(v, NANOS_PER_SEC)?;
+            Ok((
+                nanos.div_euclid(NANOS_PER_SEC) as i64,
+                nanos.rem_euclid(NANOS_PER_SEC) as i64,
+            ))
+        }
+        Some(v) if v.as_rstring().is_some() => {
+            let s = v.as_rstring().unwrap().to_s().into_owned();
+            match s.trim().parse::<f64>() {
+                Ok(f) => sec_arg(Some(Value::float(f))),
+                Err(_) => Err(RubyError::argument(format!(
+                    "invalid value for Float(): {:?}",
+                    s
+                ))),
+            }
+        }
+        v => Ok((time_arg(v, 0)?, 0)),
+    }
+}
+
+/// Build the wall clock time from the arguments (year, mon, day, hour, min, sec).
+/// The day overflowing its month is carried to the next month as CRuby does.
+fn naive_from_args(args: &[Value], usec: Option<Value>) -> Result<NaiveDateTime, RubyError> {
+    let arg = |i: usize| args.get(i).cloned();
+    let out_of_range = || RubyError::argument("argument out of range");
+    let year = time_arg(arg(0), 0)?;
+    let mon = month_arg(arg(1))?;
+    let day = time_arg(arg(2), 1)?;
+    let hour = time_arg(arg(3), 0)?;
+    let min = time_arg(arg(4), 0)?;
+    let (sec, mut nanos) = sec_arg(arg(5))?;
+    if let Some(usec) = usec {
+        nanos += to_nanos(usec, 1000)? as i64;
+    }
+    if !(1..=12).contains(&mon)
+        || !(1..=31).contains(&day)
+        || !(0..=24).contains(&hour)
+        || !(0..=59).contains(&min)
+        || !(0..=60).contains(&sec)
+        || (hour == 24 && (min != 0 || sec != 0))
+    {
+        return Err(out_of_range());
+    }
+    let date = i32::try_from(year)
+        .ok()
+        .and_then(|year| NaiveDate::from_ymd_opt(year, mon as u32, 1))
+        .ok_or_else(out_of_range)?;
+    Ok(date.and_hms_opt(0, 0, 0).unwrap()
+        + Duration::days(day - 1)
+        + Duration::hours(hour)
+        + Duration::minutes(min)
+        + Duration::seconds(sec)
+        + Duration::nanoseconds(nanos))
+}
+
+/// Reorder the ten arguments (sec, min, hour, day, mon, year, wday, yday, isdst, zone)
+/// of Time.utc and Time.local to (year, mon, day, hour, min, sec).
+fn reorder_args(args: &[Value]) -> Vec<Value> {
+    if args.len() == 10 {
+        vec![args[5], args[4], args[3], args[2], args[1], args[0]]
+    } else {
+        args.to_vec()
+    }
+}
+
+/// Time.now(in: nil) -> Time
+/// https://docs.ruby-lang.org/ja/latest/method/Time/s/now.html
 fn time_now(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(0)?;
-    let t = Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap());
-    let time_info = TimeInfo::Local(t);
+    let zone = zone_kwarg(args)?.unwrap_or(Zone::Local);
+    let time_info = zone.time(Utc::now());
     let new_obj = Value::time(Module::new(self_val), time_info);
     Ok(new_obj)
 }
 
+/// Time.new(year = nil, mon = nil, day = nil, hour = nil, min = nil, sec = nil, zone = nil, in: nil) -> Time
+/// https://docs.ruby-lang.org/ja/latest/method/Time/s/new.html
+fn time_new(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 7)?;
+    let kw_zone = zone_kwarg(args)?;
+    if args.len() == 0 {
+        let time = kw_zone.unwrap_or(Zone::Local).time(Utc::now());
+        return Ok(Value::time(Module::new(self_val), time));
+    }
+    let zone = match args.len() {
+        7 if kw_zone.is_some() => {
+            return Err(RubyError::argument(
+                "timezone argument given as positional and keyword arguments",
+            ))
+        }
+        7 => Zone::from_value(vm[6])?,
+        _ => kw_zone.unwrap_or(Zone::Local),
+    };
+    let naive = naive_from_args(&vm.args()[0..args.len().min(6)], None)?;
+    Ok(Value::time(Module::new(self_val), zone.wall_clock(naive)))
+}
+
+/// Time.at(time, in: nil) -> Time
+/// Time.at(time, subsec, unit = :microsecond, in: nil) -> Time
+/// https://docs.ruby-lang.org/ja/latest/method/Time/s/at.html
+fn time_at(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(1, 3)?;
+    let mut nanos = to_nanos(vm[0], NANOS_PER_SEC)?;
+    if args.len() >= 2 {
+        let unit = if args.len() == 3 {
+            match vm[2].as_symbol().map(|id| id.get_name()).as_deref() {
+                Some("millisecond") => 1_000_000,
+                Some("usec") | Some("microsecond") => 1_000,
+                Some("nsec") | Some("nanosecond") => 1,
+                _ => {
+                    return Err(RubyError::argument(format!(
+                        "unexpected unit: {}",
+                        vm.val_inspect(vm[2])?
+                    )))
+                }
+            }
+        } else {
+            1_000
+        };
+        nanos += to_nanos(vm[1], unit)?;
+    }
+    let zone = match zone_kwarg(args)? {
+        Some(zone) => zone,
+        None => match vm[0].as_rvalue() {
+            Some(info) if info.kind() == ObjKind::TIME && info.time().is_utc() => Zone::Utc,
+            Some(info) if info.kind() == ObjKind::TIME => {
+                Zone::Fixed(FixedOffset::east_opt(info.time().utc_offset()).unwrap())
+            }
+            _ => Zone::Local,
+        },
+    };
+    let time = TimeInfo::from_nanos(nanos, zone)?;
+    Ok(Value::time(Module::new(self_val), time))
+}
+
 /// Time.gm(year, mon = 1, day = 1, hour = 0, min = 0, sec = 0, usec = 0) -> time
 /// Time.utc(year, mon = 1, day = 1, hour = 0, min = 0, sec = 0, usec = 0) -> time
+/// Time.gm(sec, min, hour, mday, mon, year, wday, yday, isdst, zone) -> Time
 /// https://docs.ruby-lang.org/ja/latest/method/Time/s/gm.html
 fn time_utc(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
-    args.check_args_range(1, 8)?;
-    let (y, m, d, h, min, sec, usec) = match args.len() {
-        1 => (vm[0].coerce_to_fixnum("Args")?, 1, 1, 0, 0, 0, 0),
-        2 => (
-            vm[0].coerce_to_fixnum("Args")?,
-            vm[1].coerce_to_fixnum("Args")?,
-            1,
-            0,
-            0,
-            0,
-            0,
-        ),
-        3 => (
-            vm[0].coerce_to_fixnum("Args")?,
-            vm[1].coerce_to_fixnum("Args")?,
-            vm[2].coerce_to_fixnum("Args")?,
-            0,
-            0,
-            0,
-            0,
-        ),
-        4 => (
-            vm[0].coerce_to_fixnum("Args")?,
-            vm[1].coerce_to_fixnum("Args")?,
-            vm[2].coerce_to_fixnum("Args")?,
-            vm[3].coerce_to_fixnum("Args")?,
-            0,
-            0,
-            0,
-        ),
-        5 => (
-            vm[0].coerce_to_fixnum("Args")?,
-            vm[1].coerce_to_fixnum("Args")?,
-            vm[2].coerce_to_fixnum("Args")?,
-            vm[3].coerce_to_fixnum("Args")?,
-            vm[4].coerce_to_fixnum("Args")?,
-            0,
-            0,
-        ),
-        6 => (
-            vm[0].coerce_to_fixnum("Args")?,
-            vm[1].coerce_to_fixnum("Args")?,
-            vm[2].coerce_to_fixnum("Args")?,
-            vm[3].coerce_to_fixnum("Args")?,
-            vm[4].coerce_to_fixnum("Args")?,
-            vm[5].coerce_to_fixnum("Args")?,
-            0,
-        ),
-        7 => (
-            vm[0].coerce_to_fixnum("Args")?,
-            vm[1].coerce_to_fixnum("Args")?,
-            vm[2].coerce_to_fixnum("Args")?,
-            vm[3].coerce_to_fixnum("Args")?,
-            vm[4].coerce_to_fixnum("Args")?,
-            vm[5].coerce_to_fixnum("Args")?,
-            vm[6].coerce_to_fixnum("Args")?,
-        ),
-        _ => unreachable!(),
-    };
-    let native_dt = NaiveDate::from_ymd_opt(y as i32, m as u32, d as u32)
-        .ok_or_else(|| RubyError::argument("Out of range."))?
-        .and_hms_micro_opt(h as u32, min as u32, sec as u32, usec as u32)
-        .ok_or_else(|| RubyError::argument("Out of range."))?;
-    let time = TimeInfo::UTC(DateTime::<Utc>::from_naive_utc_and_offset(native_dt, Utc));
+    args.check_args_range(1, 10)?;
+    let args = vm.args();
+    let usec = if args.len() == 7 { Some(args[6]) } else { None };
+    let fields = reorder_args(args);
+    let native_dt = naive_from_args(&fields[0..fields.len().min(6)], usec)?;
+    let time = Zone::Utc.wall_clock(native_dt);
     Ok(Value::time(Module::new(self_val), time))
 }
 
+/// Time.local(year, mon = 1, day = 1, hour = 0, min = 0, sec = 0, usec = 0) -> Time
+/// Time.mktime(year, mon = 1, day = 1, hour = 0, min = 0, sec = 0, usec = 0) -> Time
+/// Time.local(sec, min, hour, mday, mon, year, wday, yday, isdst, zone) -> Time
+/// https://docs.ruby-lang.org/ja/latest/method/Time/s/local.html
+fn time_local(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(1, 10)?;
+    let args = vm.args();
+    let usec = if args.len() == 7 { Some(args[6]) } else { None };
+    let fields = reorder_args(args);
+    let native_dt = naive_from_args(&fields[0..fields.len().min(6)], usec)?;
+    let time = Zone::Local.wall_clock(native_dt);
+    Ok(Value::time(Module::new(self_val), time))
+}
+
+/// Time.iso8601(date) -> Time
+/// Time.xmlschema(date) -> Time
+/// https://docs.ruby-lang.org/ja/latest/method/Time/s/iso8601.html
+fn time_iso8601(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    static XMLSCHEMA: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(
+            r"^\s*(-?\d{4,})-(\d\d)-(\d\d)(?:[Tt](\d\d):(\d\d):(\d\d)(?:\.(\d+))?\s*([Zz]|[+-]\d\d(?::?\d\d)?)?)?\s*$",
+        )
+        .unwrap()
+    });
+    args.check_args_num(1)?;
+    let mut arg0 = vm[0];
+    let s = arg0.expect_string("Argument")?.to_string();
+    let invalid = || RubyError::argument(format!("invalid xmlschema format: {:?}", s));
+    let cap = XMLSCHEMA.captures(&s).ok_or_else(invalid)?;
+    let num = |i: usize| cap.get(i).map_or(0, |m| m.as_str().parse::<i64>().unwrap());
+    let date = i32::try_from(num(1))
+        .ok()
+        .and_then(|y| NaiveDate::from_ymd_opt(y, num(2) as u32, num(3) as u32))
+        .ok_or_else(invalid)?;
+    let naive = date
+        .and_hms_nano_opt(
+            num(4) as u32,
+            num(5) as u32,
+            num(6) as u32,
+            cap.get(7).map_or(0, |m| frac_to_nanos(m.as_str())),
+        )
+        .ok_or_else(invalid)?;
+    let zone = match cap.get(8) {
+        Some(m) if m.as_str().eq_ignore_ascii_case("z") => Zone::Utc,
+        Some(m) => Zone::from_str(m.as_str()).ok_or_else(invalid)?,
+        None => Zone::Local,
+    };
+    Ok(Value::time(Module::new(self_val), zone.wall_clock(naive)))
+}
+
+/// Time.parse(date) -> Time
+///
+/// Read a date and a time of day from `date` loosely, filling the missing date with today.
+/// https://docs.ruby-lang.org/ja/latest/method/Time/s/parse.html
+fn time_parse(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let mut arg0 = vm[0];
+    let s = arg0.expect_string("Argument")?.to_string();
+    let (naive, zone) = match parse_time(&s) {
+        Some(res) => res,
+        None => {
+            return Err(RubyError::argument(format!(
+                "no time information in {:?}",
+                s
+            )))
+        }
+    };
+    Ok(Value::time(
+        Module::new(self_val),
+        zone.unwrap_or(Zone::Local).wall_clock(naive),
+    ))
+}
+
+const MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const DAY_NAMES: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+/// Month number of a month name, which may be abbreviated to three letters.
+fn month_from_name(s: &str) -> Option<u32> {
+    if s.len() < 3 || !s.is_char_boundary(3) {
+        return None;
+    }
+    let s = &s[0..3];
+    MONTH_NAMES
+        .iter()
+        .position(|m| m[0..3].eq_ignore_ascii_case(s))
+        .map(|i| i as u32 + 1)
+}
+
+/// Nanoseconds of the digits after a decimal point.
+fn frac_to_nanos(digits: &str) -> u32 {
+    format!("{:0<9}", &digits[0..digits.len().min(9)])
+        .parse()
+        .unwrap()
+}
+
+/// UTC offsets of the zone names which Time.parse knows.
+const ZONE_NAMES: [(&str, i64); 16] = [
+    ("EST", -5),
+    ("EDT", -4),
+    ("CST", -6),
+    ("CDT", -5),
+    ("MST", -7),
+    ("MDT", -6),
+    ("PST", -8),
+    ("PDT", -7),
+    ("AKST", -9),
+    ("AKDT", -8),
+    ("HST", -10),
+    ("BST", 1),
+    ("CET", 1),
+    ("CEST", 2),
+    ("JST", 9),
+    ("KST", 9),
+];
+
+fn parse_time(s: &str) -> Option<(NaiveDateTime, Option<Zone>)> {
+    const MONTH: &str = "(jan|feb|mar|apr|may|jun|jul|aug|sep|oct|nov|dec)[a-z]*\\.?";
+    static YMD: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"\b(-?\d{4})[-/](\d{1,2})[-/](\d{1,2})").unwrap());
+    static DMY: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(&format!(
+            r"(?i)\b(\d{{1,2}})(?:st|nd|rd|th)?[\s/-]+{}(?:[\s/,-]+(-?\d{{4}}))?",
+            MONTH
+        ))
+        .unwrap()
+    });
+    static MDY: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(&format!(
+            r"(?i)\b{}\s*(\d{{1,2}})(?:st|nd|rd|th)?\b(?:,?\s+(-?\d{{4}})\b)?",
+            MONTH
+        ))
+        .unwrap()
+    });
+    static US: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b(\d{1,2})/(\d{1,2})/(\d{4})\b").unwrap());
+    static COMPACT: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"^\s*(\d{4})(\d{2})(\d{2})(?:T?(\d{2})(\d{2})(\d{2})?)?(?:\s|$|[-+Z])").unwrap()
+    });
+    static TIME: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(
+            r"(?i)(?:^|[^\d])(\d{1,2}):(\d{2})(?::(\d{2})(?:[.,](\d+))?)?(?:\s*([ap])\.?m\b\.?)?",
+        )
+        .unwrap()
+    });
+    static HOUR: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"(?i)\b(\d{1,2})\s*([ap])\.?m\b\.?").unwrap());
+    static YEAR: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b(\d{4})\b").unwrap());
+    static ZONE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(
+            r"(?i)^\s*(?:(z|utc|gmt|ut)\b|([+-]\d{1,2}(?::?\d{2}(?::?\d{2})?)?)\b|([a-z]{3,4})\b)",
+        )
+        .unwrap()
+    });
+
+    let num = |cap: &Captures, i: usize| cap.get(i).map(|m| m.as_str().parse::<i64>().unwrap());
+    let mut text = s.to_string();
+    // Blank out the matched part so that the following patterns do not see it.
+    let blank = |text: &mut String, range: std::ops::Range<usize>| {
+        text.replace_range(range.clone(), &" ".repeat(range.len()));
+    };
+
+    let (mut year, mut mon, mut day) = (None, None, None);
+    let mut hms = None;
+    let mut zone = None;
+    if let Some(cap) = COMPACT.captures(s) {
+        year = num(&cap, 1);
+        mon = num(&cap, 2);
+        day = num(&cap, 3);
+        if cap.get(4).is_some() {
+            hms = Some((num(&cap, 4)?, num(&cap, 5)?, num(&cap, 6).unwrap_or(0), 0));
+        }
+        let end = cap
+            .get(6)
+            .or_else(|| cap.get(5))
+            .or_else(|| cap.get(3))?
+            .end();
+        blank(&mut text, 0..end);
+        zone = ZONE
+            .captures(&s[end..])
+            .and_then(|cap| zone_from_captures(&cap));
+    } else {
+        let date = if let Some(cap) = YMD.captures(&text) {
+            Some((
+                num(&cap, 1),
+                num(&cap, 2),
+                num(&cap, 3),
+                cap.get(0)?.range(),
+            ))
+        } else if let Some(cap) = DMY.captures(&text) {
+            let mon = month_from_name(&cap[2]).map(|m| m as i64);
+            Some((num(&cap, 3), mon, num(&cap, 1), cap.get(0)?.range()))
+        } else if let Some(cap) = MDY.captures(&text) {
+            let mon = month_from_name(&cap[1]).map(|m| m as i64);
+            Some((num(&cap, 3), mon, num(&cap, 2), cap.get(0)?.range()))
+        } else if let Some(cap) = US.captures(&text) {
+            Some((
+                num(&cap, 3),
+                num(&cap, 1),
+                num(&cap, 2),
+                cap.get(0)?.range(),
+            ))
+        } else {
+            None
+        };
+        if let Some((y, m, d, range)) = date {
+            year = y;
+            mon = m;
+            day = d;
+            blank(&mut text, range);
+        }
+        let time_end = if let Some(cap) = TIME.captures(&text) {
+            let mut hour = num(&cap, 1)?;
+            if let Some(ampm) = cap.get(5) {
+                hour = hour % 12
+                    + if ampm.as_str().eq_ignore_ascii_case("p") {
+                        12
+                    } else {
+                        0
+                    };
+            }
+            let nanos = cap.get(4).map_or(0, |m| frac_to_nanos(m.as_str()));
+            hms = Some((hour, num(&cap, 2)?, num(&cap, 3).unwrap_or(0), nanos));
+            Some(cap.get(0)?.range())
+        } else if let Some(cap) = HOUR.captures(&text) {
+            let mut hour = num(&cap, 1)? % 12;
+            if cap[2].eq_ignore_ascii_case("p") {
+                hour += 12;
+            }
+            hms = Some((hour, 0, 0, 0));
+            Some(cap.get(0)?.range())
+        } else {
+            None
+        };
+        if let Some(range) = time_end {
+            zone = ZONE
+                .captures(&text[range.end..])
+                .and_then(|cap| zone_from_captures(&cap));
+            blank(&mut text, range);
+        }
+        if year.is_none() && mon.is_some() {
+            year = YEAR.captures(&text).and_then(|cap| num(&cap, 1));
+        }
+    }
+    if mon.is_none() && hms.is_none() {
+        return None;
+    }
+
+    let today = Local::now().date_naive();
+    let date = NaiveDate::from_ymd_opt(
+        year.unwrap_or(today.year() as i64) as i32,
+        mon.unwrap_or(today.month() as i64) as u32,
+        day.unwrap_or(if mon.is_some() { 1 } else { today.day() as i64 }) as u32,
+    )?;
+    let (h, m, sec, nanos) = hms.unwrap_or((0, 0, 0, 0));
+    let naive = date.and_hms_nano_opt(h as u32, m as u32, sec.min(59) as u32, nanos)?;
+    Some((naive, zone))
+}
+
+fn zone_from_captures(cap: &Captures) -> Option<Zone> {
+    if cap.get(1).is_some() {
+        Some(Zone::Utc)
+    } else if let Some(m) = cap.get(2) {
+        let s = m.as_str();
+        // Normalize "+9" and "+0900" to the "+HH:MM" form.
+        let (sign, digits) = s.split_at(1);
+        let digits = digits.replace(':', "");
+        let digits = if digits.len() % 2 == 1 {
+            format!("0{}", digits)
+        } else {
+            digits
+        };
+        Zone::from_str(&format!("{}{}", sign, digits))
+    } else {
+        let name = cap.get(3)?.as_str().to_ascii_uppercase();
+        ZONE_NAMES
+            .iter()
+            .find(|(zone, _)| *zone == name)
+            .and_then(|(_, hour)| Zone::from_offset(hour * 3600).ok())
+    }
+}
+
 /// Time#inspect -> String
 /// https://docs.ruby-lang.org/ja/latest/method/Time/i/inspect.html
 fn inspect(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
@@ -180,6 +872,40 @@ fn utc(_: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
     Ok(self_val)
 }
 
+/// Time#localtime(zone = nil) -> self
+/// https://docs.ruby-lang.org/ja/latest/method/Time/i/localtime.html
+fn localtime(vm: &mut VM, mut self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
+    let zone = match args.len() {
+        0 => Zone::Local,
+        _ => Zone::from_value(vm[0])?,
+    };
+    let time = self_val.as_mut_time();
+    *time = zone.time(time.utc());
+    Ok(self_val)
+}
+
+/// Time#getlocal(zone = nil) -> Time
+/// https://docs.ruby-lang.org/ja/latest/method/Time/i/getlocal.html
+fn getlocal(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
+    let zone = match args.len() {
+        0 => Zone::Local,
+        _ => Zone::from_value(vm[0])?,
+    };
+    let time = zone.time(self_val.as_time().utc());
+    Ok(Value::time(vm.globals.get_class(self_val), time))
+}
+
+/// Time#getutc -> Time
+/// Time#getgm -> Time
+/// https://docs.ruby-lang.org/ja/latest/method/Time/i/getgm.html
+fn getutc(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let time = TimeInfo::UTC(self_val.as_time().utc());
+    Ok(Value::time(vm.globals.get_class(self_val), time))
+}
+
 /// self - time -> Float
 /// self - sec -> Time
 /// https://docs.ruby-lang.org/ja/latest/method/Time/i/=2d.html
@@ -187,25 +913,17 @@ fn sub(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(1)?;
     let time = self_val.as_time().clone();
     let arg0 = vm[0];
-    match arg0.unpack() {
-        RV::Integer(i) => {
-            let res = time - Duration::seconds(i);
-            Ok(Value::time(vm.globals.get_class(self_val), res))
+    match arg0.as_rvalue() {
+        Some(rv) if rv.kind() == ObjKind::TIME => {
+            let res = time.nanos() - rv.time().nanos();
+            Ok(Value::float(res as f64 / NANOS_PER_SEC as f64))
         }
-        RV::Float(f) => {
-            let offset = (f * 1000.0 * 1000.0 * 1000.0) as i64;
-            let res = time - Duration::nanoseconds(offset);
+        _ => {
+            let offset = to_nanos(arg0, NANOS_PER_SEC)
+                .map_err(|_| VMError::undefined_op("-", arg0, self_val))?;
+            let res = time.with_nanos(time.nanos() - offset)?;
             Ok(Value::time(vm.globals.get_class(self_val), res))
         }
-        RV::Object(rv) => match rv.kind() {
-            ObjKind::TIME => {
-                let res = time - rv.time().clone();
-                let offset = (res.num_nanoseconds().unwrap() as f64) / 1000.0 / 1000.0 / 1000.0;
-                Ok(Value::float(offset))
-            }
-            _ => return Err(VMError::undefined_op("-", arg0, self_val)),
-        },
-        _ => return Err(VMError::undefined_op("-", arg0, self_val)),
     }
 }
 
@@ -213,28 +931,51 @@ fn sub(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
 /// https://docs.ruby-lang.org/ja/latest/method/Time/i/=2b.html
 fn add(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     args.check_args_num(1)?;
-    let time = {
-        let rval = self_val.rvalue();
-        match rval.kind() {
-            ObjKind::TIME => rval.time().clone(),
-            _ => unreachable!(),
-        }
-    };
+    let time = self_val.as_time().clone();
     let arg0 = vm[0];
-    match arg0.unpack() {
-        RV::Integer(i) => {
-            let res = time + Duration::seconds(i);
+    match arg0.as_rvalue() {
+        Some(rv) if rv.kind() == ObjKind::TIME => Err(VMError::undefined_op("+", arg0, self_val)),
+        _ => {
+            let offset = to_nanos(arg0, NANOS_PER_SEC)
+                .map_err(|_| VMError::undefined_op("+", arg0, self_val))?;
+            let res = time.with_nanos(time.nanos() + offset)?;
             Ok(Value::time(vm.globals.get_class(self_val), res))
         }
-        RV::Float(f) => {
-            let offset = (f * 1000.0 * 1000.0 * 1000.0) as i64;
-            let res = time + Duration::nanoseconds(offset);
-            Ok(Value::time(vm.globals.get_class(self_val), res))
+    }
+}
+
+/// Time#<=>(other) -> -1 | 0 | 1 | nil
+/// https://docs.ruby-lang.org/ja/latest/method/Time/i/=3c=3d=3e.html
+fn cmp(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    match vm[0].as_rvalue() {
+        Some(rv) if rv.kind() == ObjKind::TIME => {
+            let ord = self_val.as_time().nanos().cmp(&rv.time().nanos());
+            Ok(Value::integer(ord as i64))
         }
-        _ => return Err(VMError::undefined_op("+", arg0, self_val)),
+        _ => Ok(Value::nil()),
     }
 }
 
+/// Time#eql?(other) -> bool
+/// https://docs.ruby-lang.org/ja/latest/method/Time/i/eql=3f.html
+fn eql(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let b = match vm[0].as_rvalue() {
+        Some(rv) if rv.kind() == ObjKind::TIME => self_val.as_time() == rv.time(),
+        _ => false,
+    };
+    Ok(Value::bool(b))
+}
+
+/// Time#hash -> Integer
+/// https://docs.ruby-lang.org/ja/latest/method/Time/i/hash.html
+fn hash(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let nanos = self_val.as_time().nanos();
+    Ok(Value::integer((nanos ^ (nanos >> 64)) as i64))
+}
+
 /// Time#year -> Integer
 /// https://docs.ruby-lang.org/ja/latest/method/Time/i/year.html
 fn year(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
@@ -270,6 +1011,392 @@ fn day(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
     Ok(Value::integer(num as i64))
 }
 
+/// Time#hour -> Integer
+/// https://docs.ruby-lang.org/ja/latest/method/Time/i/hour.html
+fn hour(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::integer(self_val.as_time().fixed().hour() as i64))
+}
+
+/// Time#min -> Integer
+/// https://docs.ruby-lang.org/ja/latest/method/Time/i/min.html
+fn min(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::integer(self_val.as_time().fixed().minute() as i64))
+}
+
+/// Time#sec -> Integer
+/// https://docs.ruby-lang.org/ja/latest/method/Time/i/sec.html
+fn sec(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::integer(self_val.as_time().fixed().second() as i64))
+}
+
+/// Time#usec -> Integer
+/// Time#tv_usec -> Integer
+/// https://docs.ruby-lang.org/ja/latest/method/Time/i/usec.html
+fn usec(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let nanos = self_val.as_time().nanos().rem_euclid(NANOS_PER_SEC);
+    Ok(Value::integer((nanos / 1000) as i64))
+}
+
+/// Time#nsec -> Integer
+/// Time#tv_nsec -> Integer
+/// https://docs.ruby-lang.org/ja/latest/method/Time/i/nsec.html
+fn nsec(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let nanos = self_val.as_time().nanos().rem_euclid(NANOS_PER_SEC);
+    Ok(Value::integer(nanos as i64))
+}
+
+/// Time#wday -> Integer
+/// https://docs.ruby-lang.org/ja/latest/method/Time/i/wday.html
+fn wday(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let wday = self_val.as_time().fixed().weekday().num_days_from_sunday();
+    Ok(Value::integer(wday as i64))
+}
+
+/// Time#yday -> Integer
+/// https://docs.ruby-lang.org/ja/latest/method/Time/i/yday.html
+fn yday(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::integer(self_val.as_time().fixed().ordinal() as i64))
+}
+
+/// Time#zone -> String | nil
+/// https://docs.ruby-lang.org/ja/latest/method/Time/i/zone.html
+fn zone(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(match self_val.as_time().zone() {
+        Some(zone) => Value::string(zone),
+        None => Value::nil(),
+    })
+}
+
+/// Time#utc_offset -> Integer
+/// Time#gmt_offset -> Integer
+/// Time#gmtoff -> Integer
+/// https://docs.ruby-lang.org/ja/latest/method/Time/i/gmt_offset.html
+fn utc_offset(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::integer(self_val.as_time().utc_offset() as i64))
+}
+
+/// Time#utc? -> bool
+/// Time#gmt? -> bool
+/// https://docs.ruby-lang.org/ja/latest/method/Time/i/gmt=3f.html
+fn is_utc(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::bool(self_val.as_time().is_utc()))
+}
+
+/// Time#to_i -> Integer
+/// Time#tv_sec -> Integer
+/// https://docs.ruby-lang.org/ja/latest/method/Time/i/to_i.html
+fn to_i(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    Ok(Value::integer(self_val.as_time().utc().timestamp()))
+}
+
+/// Time#to_f -> Float
+/// https://docs.ruby-lang.org/ja/latest/method/Time/i/to_f.html
+fn to_f(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let t = self_val.as_time().utc();
+    let f = t.timestamp() as f64 + t.timestamp_subsec_nanos() as f64 / NANOS_PER_SEC as f64;
+    Ok(Value::float(f))
+}
+
+/// Time#to_r -> Rational
+/// https://docs.ruby-lang.org/ja/latest/method/Time/i/to_r.html
+fn to_r(_: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(0)?;
+    let nanos = self_val.as_time().nanos();
+    Ok(Value::rational(BigRational::new(
+        BigInt::from(nanos),
+        BigInt::from(NANOS_PER_SEC),
+    )))
+}
+
+/// Adjust the fraction of a second of `self` to `ndigits` digits with `f`,
+/// which takes the nanoseconds and the unit to adjust them to.
+fn adjust_subsec(
+    vm: &mut VM,
+    self_val: Value,
+    args: &Args2,
+    f: fn(i128, i128) -> i128,
+) -> VMResult {
+    args.check_args_range(0, 1)?;
+    let ndigits = match args.len() {
+        0 => 0,
+        _ => vm[0].coerce_to_fixnum("ndigits")?,
+    };
+    if ndigits < 0 {
+        return Err(RubyError::argument("negative ndigits given"));
+    }
+    let time = self_val.as_time();
+    let nanos = time.nanos();
+    let res = if ndigits >= 9 {
+        nanos
+    } else {
+        let unit = 10i128.pow(9 - ndigits as u32);
+        nanos - nanos.rem_euclid(unit) + f(nanos.rem_euclid(unit), unit)
+    };
+    let time = time.with_nanos(res)?;
+    Ok(Value::time(vm.globals.get_class(self_val), time))
+}
+
+/// Time#round(ndigits = 0) -> Time
+/// https://docs.ruby-lang.org/ja/latest/method/Time/i/round.html
+fn round(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    adjust_subsec(
+        vm,
+        self_val,
+        args,
+        |rem, unit| {
+            if rem * 2 >= unit {
+                unit
+            } else {
+                0
+            }
+        },
+    )
+}
+
+/// Time#floor(ndigits = 0) -> Time
+/// https://docs.ruby-lang.org/ja/latest/method/Time/i/floor.html
+fn floor(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    adjust_subsec(vm, self_val, args, |_, _| 0)
+}
+
+/// Time#ceil(ndigits = 0) -> Time
+/// https://docs.ruby-lang.org/ja/latest/method/Time/i/ceil.html
+fn ceil(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    adjust_subsec(
+        vm,
+        self_val,
+        args,
+        |rem, unit| if rem > 0 { unit } else { 0 },
+    )
+}
+
+/// Time#strftime(format) -> String
+/// https://docs.ruby-lang.org/ja/latest/method/Time/i/strftime.html
+fn strftime(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_num(1)?;
+    let mut arg0 = vm[0];
+    let fmt = arg0.expect_string("Format")?.to_string();
+    Ok(Value::string(format_time(self_val.as_time(), &fmt)))
+}
+
+/// Time#iso8601(fraction_digits = 0) -> String
+/// Time#xmlschema(fraction_digits = 0) -> String
+/// https://docs.ruby-lang.org/ja/latest/method/Time/i/iso8601.html
+fn iso8601(vm: &mut VM, self_val: Value, args: &Args2) -> VMResult {
+    args.check_args_range(0, 1)?;
+    let digits = match args.len() {
+        0 => 0,
+        _ => vm[0].coerce_to_fixnum("fraction_digits")?.max(0),
+    };
+    let time = self_val.as_time();
+    let mut fmt = "%Y-%m-%dT%H:%M:%S".to_string();
+    if digits > 0 {
+        fmt += &format!(".%{}N", digits);
+    }
+    fmt += if time.is_utc() { "Z" } else { "%:z" };
+    Ok(Value::string(format_time(time, &fmt)))
+}
+
+/// Flags, width and colons given to a conversion of strftime.
+#[derive(Default)]
+struct Spec {
+    /// '-': Do not pad numbers.
+    no_pad: bool,
+    /// '_' or '0': Pad with spaces or zeros.
+    pad: Option<char>,
+    /// '^': Upcase the result.
+    upcase: bool,
+    /// '#': Change the case of the result.
+    swapcase: bool,
+    width: Option<usize>,
+    colons: usize,
+}
+
+impl Spec {
+    fn num(&self, n: i64, width: usize, pad: char) -> String {
+        if self.no_pad {
+            return n.to_string();
+        }
+        let width = self.width.unwrap_or(width);
+        match self.pad.unwrap_or(pad) {
+            '0' if n < 0 => format!("-{:0>1$}", -n, width.saturating_sub(1)),
+            '0' => format!("{:0>1$}", n, width),
+            _ => format!("{:>1$}", n, width),
+        }
+    }
+
+    fn text(&self, s: &str) -> String {
+        let s = if self.upcase {
+            s.to_uppercase()
+        } else if self.swapcase {
+            if s.chars().any(|c| c.is_lowercase()) {
+                s.to_uppercase()
+            } else {
+                s.to_lowercase()
+            }
+        } else {
+            s.to_string()
+        };
+        match (self.width, self.no_pad) {
+            (Some(width), false) if self.pad == Some('0') => format!("{:0>1$}", s, width),
+            (Some(width), false) => format!("{:>1$}", s, width),
+            _ => s,
+        }
+    }
+
+    /// The UTC offset in the form of "+hhmm", "+hh:mm" or "+hh:mm:ss" for %z.
+    fn offset(&self, offset: i32) -> String {
+        let sign = if offset < 0 { '-' } else { '+' };
+        let offset = offset.abs();
+        let (h, m, s) = (offset / 3600, offset / 60 % 60, offset % 60);
+        let body = match self.colons {
+            0 => format!("{:02}{:02}", h, m),
+            1 => format!("{:02}:{:02}", h, m),
+            _ => format!("{:02}:{:02}:{:02}", h, m, s),
+        };
+        let len = body.len() + 1;
+        match self.width {
+            Some(width) if width > len && !self.no_pad => match self.pad {
+                Some('_') => format!("{:>1$}", format!("{}{}", sign, body), width),
+                _ => format!("{}{:0>2$}", sign, body, width - 1),
+            },
+            _ => format!("{}{}", sign, body),
+        }
+    }
+}
+
+/// Format `time` according to the directives of Time#strftime.
+fn format_time(time: &TimeInfo, fmt: &str) -> String {
+    let t = time.fixed();
+    let mut out = String::new();
+    let mut chars = fmt.char_indices().peekable();
+    while let Some((start, ch)) = chars.next() {
+        if ch != '%' {
+            out.push(ch);
+            continue;
+        }
+        let mut spec = Spec::default();
+        while let Some(&(_, c)) = chars.peek() {
+            match c {
+                '-' => spec.no_pad = true,
+                '_' => spec.pad = Some(' '),
+                '0' => spec.pad = Some('0'),
+                '^' => spec.upcase = true,
+                '#' => spec.swapcase = true,
+                _ => break,
+            }
+            chars.next();
+        }
+        let mut width = None;
+        while let Some(&(_, c)) = chars.peek() {
+            match c.to_digit(10) {
+                Some(d) => width = Some(width.unwrap_or(0) * 10 + d as usize),
+                None => break,
+            }
+            chars.next();
+        }
+        spec.width = width;
+        if let Some(&(_, 'E')) | Some(&(_, 'O')) = chars.peek() {
+            chars.next();
+        }
+        while let Some(&(_, ':')) = chars.peek() {
+            spec.colons += 1;
+            chars.next();
+        }
+        let (end, conv) = match chars.next() {
+            Some((i, c)) => (i + c.len_utf8(), c),
+            None => {
+                out += &fmt[start..];
+                break;
+            }
+        };
+        if spec.colons != 0 && conv != 'z' {
+            out += &fmt[start..end];
+            continue;
+        }
+        let year = t.year() as i64;
+        let hour12 = (t.hour() + 11) % 12 + 1;
+        let s = match conv {
+            'Y' => {
+                if year >= 0 {
+                    spec.num(year, 4, '0')
+                } else {
+                    spec.num(year, 5, '0')
+                }
+            }
+            'C' => spec.num(year.div_euclid(100), 2, '0'),
+            'y' => spec.num(year.rem_euclid(100), 2, '0'),
+            'm' => spec.num(t.month() as i64, 2, '0'),
+            'B' => spec.text(MONTH_NAMES[t.month0() as usize]),
+            'b' | 'h' => spec.text(&MONTH_NAMES[t.month0() as usize][0..3]),
+            'd' => spec.num(t.day() as i64, 2, '0'),
+            'e' => spec.num(t.day() as i64, 2, ' '),
+            'j' => spec.num(t.ordinal() as i64, 3, '0'),
+            'H' => spec.num(t.hour() as i64, 2, '0'),
+            'k' => spec.num(t.hour() as i64, 2, ' '),
+            'I' => spec.num(hour12 as i64, 2, '0'),
+            'l' => spec.num(hour12 as i64, 2, ' '),
+            'P' => spec.text(if t.hour() < 12 { "am" } else { "pm" }),
+            'p' => spec.text(if t.hour() < 12 { "AM" } else { "PM" }),
+            'M' => spec.num(t.minute() as i64, 2, '0'),
+            'S' => spec.num(t.second() as i64, 2, '0'),
+            'L' | 'N' => {
+                let digits = spec.width.unwrap_or(if conv == 'L' { 3 } else { 9 });
+                let nanos = format!("{:09}", t.nanosecond());
+                if digits <= 9 {
+                    nanos[0..digits].to_string()
+                } else {
+                    format!("{:0<1$}", nanos, digits)
+                }
+            }
+            'z' => spec.offset(time.utc_offset()),
+            'Z' => spec.text(&time.zone().unwrap_or_default()),
+            'A' => spec.text(DAY_NAMES[t.weekday().num_days_from_sunday() as usize]),
+            'a' => spec.text(&DAY_NAMES[t.weekday().num_days_from_sunday() as usize][0..3]),
+            'u' => spec.num(t.weekday().number_from_monday() as i64, 1, '0'),
+            'w' => spec.num(t.weekday().num_days_from_sunday() as i64, 1, '0'),
+            'G' => spec.num(t.iso_week().year() as i64, 4, '0'),
+            'g' => spec.num((t.iso_week().year() as i64).rem_euclid(100), 2, '0'),
+            'V' => spec.num(t.iso_week().week() as i64, 2, '0'),
+            'U' => {
+                let week = (t.ordinal0() + 7 - t.weekday().num_days_from_sunday()) / 7;
+                spec.num(week as i64, 2, '0')
+            }
+            'W' => {
+                let week = (t.ordinal0() + 7 - t.weekday().num_days_from_monday()) / 7;
+                spec.num(week as i64, 2, '0')
+            }
+            's' => spec.num(t.timestamp(), 1, '0'),
+            'n' => spec.text("\n"),
+            't' => spec.text("\t"),
+            '%' => spec.text("%"),
+            'c' => spec.text(&format_time(time, "%a %b %e %H:%M:%S %Y")),
+            'D' | 'x' => spec.text(&format_time(time, "%m/%d/%y")),
+            'F' => spec.text(&format_time(time, "%Y-%m-%d")),
+            'T' | 'X' => spec.text(&format_time(time, "%H:%M:%S")),
+            'R' => spec.text(&format_time(time, "%H:%M")),
+            'r' => spec.text(&format_time(time, "%I:%M:%S %p")),
+            'v' => spec.text(&format_time(time, "%e-%^b-%4Y")),
+            '+' => spec.text(&format_time(time, "%a %b %e %H:%M:%S %Z %Y")),
+            _ => fmt[start..end].to_string(),
+        };
+        out += &s;
+    }
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use crate::tests::*;
@@ -304,4 +1431,122 @@ mod tests {
     ";
         assert_script(program);
     }
+
+    #[test]
+    fn time_new_at() {
+        let program = r#"
+        t = Time.new(2024, 2, 30, 13, 4, 5.5, "+09:00")
+        assert "2024-03-01 13:04:05.5 +0900", t.inspect
+        assert [13, 4, 5, 500000, 500000000], [t.hour, t.min, t.sec, t.usec, t.nsec]
+        assert [5, 61, 32400, false], [t.wday, t.yday, t.utc_offset, t.utc?]
+        assert nil, Time.new(2000, 1, 1, 0, 0, 0, "+01:23").zone
+        assert 1709265845, t.to_i
+        assert 1709265845.5, t.to_f
+        assert Rational(3418531691, 2), t.to_r
+        assert Time.new(2000, 1, 1, 0, 0, 0, "Z"), Time.new(2000, 1, 1, 9, 0, 0, 32400)
+        assert "UTC", Time.new(2000, 1, 1, in: "UTC").zone
+        assert "2000-01-01 00:00:00 -0130", Time.new(2000, "jan", "1", in: "-01:30").to_s
+
+        assert "1970-01-01 00:00:01.5 UTC", Time.at(1.5, in: "UTC").inspect
+        assert "1970-01-01 09:00:01.25 +0900", Time.at(Rational(5, 4), in: "+09:00").inspect
+        assert 123456789, Time.at(0, 123456789, :nsec).nsec
+        assert 123000, Time.at(0, 123, :millisecond).usec
+        assert 123, Time.at(0, 123).usec
+        assert Time.at(10), Time.at(Time.at(10))
+        assert_error { Time.at(0, 1, :hour) }
+        assert_error { Time.new(2000, 13) }
+        assert_error { Time.new(2000, 1, 1, 0, 0, 0, "JST") }
+
+        t = Time.utc(2000, 1, 1)
+        assert "2000-01-01 09:00:00 +0900", t.getlocal("+09:00").to_s
+        assert "2000-01-01 00:00:00 UTC", t.to_s
+        t.localtime("-05:00")
+        assert "1999-12-31 19:00:00 -0500", t.to_s
+        assert true, t.getutc.utc?
+        assert Time.local(2000, 1, 2, 3), Time.mktime(0, 0, 3, 2, 1, 2000, nil, nil, false, nil)
+    "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn time_compare() {
+        let program = r#"
+        a = Time.at(100, in: "UTC")
+        b = Time.at(100, in: "+03:00")
+        c = a + Rational(1, 2)
+        assert 0, a <=> b
+        assert -1, a <=> c
+        assert nil, a <=> 100
+        assert true, a == b
+        assert true, a.eql?(b)
+        assert a.hash, b.hash
+        assert true, a < c
+        assert true, c >= a && c <= c + 1
+        assert [a, c], [c, a].sort
+        assert 0.5, c - a
+
+        t = Time.at(0, 123456789, :nsec, in: "UTC")
+        assert 0, t.round.nsec
+        assert 123460000, t.round(5).nsec
+        assert 123450000, t.floor(5).nsec
+        assert 123460000, t.ceil(5).nsec
+        assert 1, Time.at(0, 500, :millisecond, in: "UTC").round.sec
+    "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn time_strftime() {
+        let program = r#"
+        t = Time.new(2024, 1, 7, 15, 4, Rational(5123456789, 1000000000), "+09:00")
+        assert "2024-01-07 15:04:05.123 +0900", t.strftime("%Y-%m-%d %H:%M:%S.%L %z")
+        assert "20 24 01 January Jan Jan 07  7 007", t.strftime("%C %y %m %B %b %h %d %e %j")
+        assert "15 15 03  3 pm PM 04 05", t.strftime("%H %k %I %l %P %p %M %S")
+        assert "123456789 123456 123456789000", t.strftime("%N %6N %12N")
+        assert "+0900 +09:00 +09:00:00", t.strftime("%z %:z %::z")
+        assert "", Time.new(2000, 1, 1, 0, 0, 0, "+01:23").strftime("%Z")
+        assert "Sunday Sun 7 0 2024 24 01 01 01", t.strftime("%A %a %u %w %G %g %V %U %W")
+        assert "1704607445 \n \t %", t.strftime("%s %n %t %%")
+        assert "Sun Jan  7 15:04:05 2024", t.strftime("%c")
+        assert "01/07/24 01/07/24 2024-01-07 15:04:05 15:04:05 15:04", t.strftime("%D %x %F %T %X %R")
+        assert "03:04:05 PM  7-JAN-2024", t.strftime("%r %v")
+        assert "Sun Jan  7 15:04:05 UTC 2024", Time.utc(2024, 1, 7, 15, 4, 5).strftime("%+")
+        assert "7 JANUARY SUN pm 0015   7 3|  3", t.strftime("%-d %^B %#a %#p %4H %_3d %-l|%3l")
+        assert "%Q %:y", t.strftime("%Q %:y")
+        assert "0001", Time.utc(1).strftime("%Y")
+        assert "UTC", Time.utc(2000).strftime("%Z")
+    "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn time_iso8601_parse() {
+        let program = r#"
+        require "time"
+        t = Time.utc(2024, 1, 2, 3, 4, 5.25)
+        assert "2024-01-02T03:04:05Z", t.iso8601
+        assert "2024-01-02T03:04:05.250Z", t.xmlschema(3)
+        assert "2024-01-02T12:04:05+09:00", t.getlocal("+09:00").iso8601
+        assert t, Time.iso8601("2024-01-02T03:04:05.25Z")
+        assert true, Time.iso8601("2024-01-02T03:04:05Z").utc?
+        assert 3600, Time.iso8601("2024-01-02T03:04:05+01:00").utc_offset
+        assert_error { Time.iso8601("2024-01-02 03:04:05") }
+        assert_error { Time.xmlschema("2024-13-02T03:04:05Z") }
+
+        assert "2024-01-02 03:04:05 +0900", Time.parse("2024-01-02 03:04:05 +0900").to_s
+        assert "2024-01-02 03:04:05 UTC", Time.parse("2024-01-02T03:04:05Z").to_s
+        assert "2024-01-02 03:04:05.5 -0500", Time.parse("2024/01/02 03:04:05.5 EST").inspect
+        assert "2024-01-02 03:04:05 +0900", Time.parse("02/Jan/2024:03:04:05 +0900").to_s
+        assert "2024-01-02 03:04:05 UTC", Time.parse("Tue, 02 Jan 2024 03:04:05 GMT").to_s
+        assert "2024-01-02 15:30:00 -0800", Time.parse("Jan 2nd, 2024 3:30 pm PST").to_s
+        assert "2024-01-02 03:04:05 UTC", Time.parse("Tue Jan  2 03:04:05 UTC 2024").to_s
+        assert "2024-01-02 03:04:05 +0100", Time.parse("20240102T030405+0100").to_s
+        t = Time.parse("2024-01-02")
+        assert [2024, 1, 2, 0, 0], [t.year, t.mon, t.day, t.hour, t.min]
+        t = Time.parse("10:20")
+        assert [10, 20], [t.hour, t.min]
+        assert_error { Time.parse("hello") }
+    "#;
+        assert_script(program);
+    }
 }
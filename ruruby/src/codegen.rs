@@ -3,6 +3,8 @@ use std::path::PathBuf;
 use crate::*;
 use ruruby_parse::{BlockInfo, Node};
 mod defined;
+mod optimizer;
+mod pattern;
 mod send;
 
 /// Codegen State
@@ -14,6 +16,8 @@ pub struct Codegen {
     extern_context: Option<EnvFrame>,
     pub loc: Loc,
     pub source_info: SourceInfoRef,
+    frozen_string_literal: bool,
+    source_encoding: Encoding,
 }
 
 // Public APIs
@@ -86,6 +90,8 @@ impl Codegen {
             loop_stack: vec![LoopInfo::new_top()],
             loc: Loc(0, 0),
             source_info,
+            frozen_string_literal: false,
+            source_encoding: Encoding::Utf8,
         }
     }
 
@@ -96,6 +102,13 @@ impl Codegen {
         extern_context: Option<EnvFrame>,
     ) -> Result<FnId, RubyError> {
         let mut codegen = Codegen::new(result.source_info, extern_context);
+        codegen.frozen_string_literal = result.frozen_string_literal;
+        if let Some(enc) = result
+            .source_encoding
+            .and_then(|name| Encoding::from_name(&name))
+        {
+            codegen.source_encoding = enc;
+        }
         let loc = result.node.loc;
         codegen.gen_iseq(
             globals,
@@ -213,13 +226,22 @@ impl Codegen {
             iseq.push32(i as u32);
             self.emit_set_local(&mut iseq, *id);
         });
+        self.context_mut().body_start = iseq.current();
         self.gen(globals, &mut iseq, node, use_value)?;
         let context = self.context_stack.pop().unwrap();
 
-        let iseq_sourcemap = context.iseq_sourcemap;
-        let exception_table = context.exception_table;
+        let mut iseq_sourcemap = context.iseq_sourcemap;
+        let mut exception_table = context.exception_table;
         iseq.gen_return();
         iseq.optimize();
+        if globals.iseq_opt {
+            optimizer::optimize(
+                globals,
+                &mut iseq,
+                &mut exception_table,
+                &mut iseq_sourcemap,
+            );
+        }
         self.loc = save_loc;
 
         let info = MethodInfo::RubyFunc {
@@ -289,8 +311,14 @@ impl Codegen {
                 iseq.gen_push_nil();
                 iseq.gen_set_const(id);
             }
-            NodeKind::InstanceVar(id) => iseq.gen_set_instance_var(id),
-            NodeKind::GlobalVar(id) => iseq.gen_set_global_var(id),
+            NodeKind::InstanceVar(id) => {
+                iseq.gen_set_instance_var(globals, id);
+                self.save_loc(iseq, lhs_loc);
+            }
+            NodeKind::GlobalVar(id) => {
+                iseq.gen_set_global_var(id);
+                self.save_loc(iseq, lhs_loc);
+            }
             NodeKind::SpecialVar(id) => self.emit_set_special_var(iseq, id, lhs_loc)?,
             NodeKind::ClassVar(id) => self.emit_set_class_var(iseq, id),
             NodeKind::Scope(parent, id) => {
@@ -387,7 +415,8 @@ impl Codegen {
             }
             NodeKind::InstanceVar(id) => {
                 self.gen_assign_val(globals, iseq, rhs, use_value)?;
-                iseq.gen_set_instance_var(id)
+                iseq.gen_set_instance_var(globals, id);
+                self.save_loc(iseq, lhs_loc);
             }
             NodeKind::ClassVar(id) => {
                 self.gen_assign_val(globals, iseq, rhs, use_value)?;
@@ -396,6 +425,7 @@ impl Codegen {
             NodeKind::GlobalVar(id) => {
                 self.gen_assign_val(globals, iseq, rhs, use_value)?;
                 iseq.gen_set_global_var(id);
+                self.save_loc(iseq, lhs_loc);
             }
             NodeKind::SpecialVar(id) => {
                 self.gen_assign_val(globals, iseq, rhs, use_value)?;
@@ -479,14 +509,21 @@ impl Codegen {
                 }
             }
             1 => {
-                self.gen(globals, iseq, nodes.remove(0), use_value)?;
+                let node = nodes.remove(0);
+                let loc = node.loc();
+                self.gen(globals, iseq, node, use_value)?;
+                self.save_loc(iseq, loc);
             }
             _ => {
                 let last = nodes.remove(nodes.len() - 1);
                 for node in nodes {
+                    let loc = node.loc();
                     self.gen(globals, iseq, node, false)?;
+                    self.save_loc(iseq, loc);
                 }
+                let loc = last.loc();
                 self.gen(globals, iseq, last, use_value)?;
+                self.save_loc(iseq, loc);
             }
         }
         Ok(())
@@ -608,6 +645,7 @@ impl Codegen {
                 | NodeKind::Bool(_)
                 | NodeKind::Integer(_)
                 | NodeKind::Float(_)
+                | NodeKind::Rational(_)
                 | NodeKind::Imaginary(_)
                 | NodeKind::String(_)
                 | NodeKind::Symbol(_)
@@ -621,6 +659,7 @@ impl Codegen {
             NodeKind::Integer(num) => iseq.gen_integer(globals, num),
             NodeKind::Bignum(num) => iseq.gen_const_val(globals, Value::bignum(num)),
             NodeKind::Float(num) => iseq.gen_float(globals, num),
+            NodeKind::Rational(r) => iseq.gen_const_val(globals, Value::rational(r)),
             NodeKind::Imaginary(r) => iseq.gen_complex(
                 globals,
                 match r {
@@ -629,7 +668,10 @@ impl Codegen {
                     NReal::Float(f) => crate::value::Real::Float(f),
                 },
             ),
-            NodeKind::String(s) => iseq.gen_string(globals, &s),
+            NodeKind::String(s) if self.frozen_string_literal => {
+                iseq.gen_const_val(globals, self.string_literal(s).freeze())
+            }
+            NodeKind::String(s) => iseq.gen_const_val(globals, self.string_literal(s)),
             NodeKind::Symbol(id) => iseq.gen_val(Value::symbol(id)),
             NodeKind::InterporatedString(nodes) => {
                 let mut c = 0;
@@ -637,7 +679,7 @@ impl Codegen {
                     match node.kind {
                         NodeKind::String(s) => {
                             if s.len() != 0 {
-                                iseq.gen_string(globals, &s);
+                                iseq.gen_const_val(globals, self.string_literal(s));
                                 c += 1;
                             }
                         }
@@ -670,7 +712,7 @@ impl Codegen {
                     for node in nodes {
                         match node.kind {
                             NodeKind::String(s) => {
-                                iseq.gen_string(globals, &s);
+                                iseq.gen_const_val(globals, self.string_literal(s));
                             }
                             NodeKind::CompStmt(nodes) => {
                                 self.gen_comp_stmt(globals, iseq, nodes, true)?;
@@ -800,7 +842,7 @@ impl Codegen {
                 };
             }
             NodeKind::InstanceVar(id) => {
-                iseq.gen_get_instance_var(id);
+                iseq.gen_get_instance_var(globals, id);
                 if !use_value {
                     iseq.gen_pop()
                 };
@@ -1015,6 +1057,7 @@ impl Codegen {
                     self.gen(globals, iseq, *cond, true)?;
                     iseq.gen_jmp_if_t()
                 };
+                let body_start = iseq.current();
                 self.gen(globals, iseq, *body, false)?;
                 iseq.gen_jmp_back(loop_start);
                 iseq.write_disp_from_cur(src);
@@ -1029,6 +1072,7 @@ impl Codegen {
                             iseq.write_disp_from_cur(p.pos);
                         }
                         EscapeKind::Next => iseq.write_disp(p.pos, loop_start),
+                        EscapeKind::Redo => iseq.write_disp(p.pos, body_start),
                     }
                 }
                 if !use_value {
@@ -1049,11 +1093,16 @@ impl Codegen {
                 self.gen(globals, iseq, *body, use_value)?;
                 let jump_dest = self.pop_jump_dest();
                 let body_end = iseq.current();
-                let mut dest = None;
                 let mut prev = None;
 
                 if !rescue.is_empty() {
                     let else_dest = iseq.gen_jmp();
+                    // Register the exception entry in advance so that `retry` can find
+                    // the start of the body.
+                    let dest = iseq.current();
+                    self.push_ex_rescue(body_start, body_end, dest);
+                    let entry = self.context().exception_table.len() - 1;
+                    self.context_mut().retry_entries.push(entry);
                     // Rescue clauses.
                     for RescueEntry {
                         exception_list,
@@ -1061,9 +1110,6 @@ impl Codegen {
                         body,
                     } in rescue
                     {
-                        if dest.is_none() {
-                            dest = Some(iseq.current())
-                        };
                         if let Some(prev) = prev {
                             iseq.write_disp_from_cur(prev);
                         }
@@ -1088,15 +1134,18 @@ impl Codegen {
                             None => iseq.gen_pop(),
                         }
                         self.gen(globals, iseq, *body, use_value)?;
+                        // The exception was handled, so clear `$!`.
+                        iseq.gen_push_nil();
+                        iseq.gen_set_global_var(IdentId::get_id("$!"));
+                        self.save_loc(iseq, node.loc);
                         ensure_dest.push(iseq.gen_jmp());
                     }
-                    // When no rescue clause were matched
+                    self.context_mut().retry_entries.pop().unwrap();
+                    // When no rescue clause were matched, re-raise the exception.
+                    // Ensure clause is executed in the exception path below.
                     if let Some(prev) = prev {
                         iseq.write_disp_from_cur(prev);
                     }
-                    if let Some(box ensure) = ensure.clone() {
-                        self.gen(globals, iseq, ensure, false)?;
-                    }
                     self.save_loc(iseq, node.loc);
                     iseq.push(Inst::THROW);
                     //iseq.gen_pop();
@@ -1113,6 +1162,20 @@ impl Codegen {
                     };
                     self.gen(globals, iseq, *else_, use_value)?
                 };
+                let ensure_ex = match &ensure {
+                    Some(box ensure) => {
+                        // Ensure clause for exception path.
+                        let protected_end = iseq.current();
+                        let ensure_label = iseq.gen_jmp();
+                        let ensure_start = iseq.current();
+                        self.gen(globals, iseq, ensure.clone(), false)?;
+                        self.save_loc(iseq, node.loc);
+                        iseq.push(Inst::THROW);
+                        iseq.write_disp_from_cur(ensure_label);
+                        Some((protected_end, ensure_start))
+                    }
+                    None => None,
+                };
                 if !jump_dest.is_empty() {
                     // Ensure clause for exception return path.
                     let ensure_label = iseq.gen_jmp();
@@ -1142,8 +1205,8 @@ impl Codegen {
                 for src in ensure_dest {
                     iseq.write_disp_from_cur(src);
                 }
-                if let Some(dest) = dest {
-                    self.push_ex_rescue(body_start, body_end, dest);
+                if let Some((protected_end, ensure_start)) = ensure_ex {
+                    self.push_ex_rescue(body_start, protected_end, ensure_start);
                 }
                 // Ensure clause does not return value.
                 if let Some(ensure) = ensure {
@@ -1227,7 +1290,7 @@ impl Codegen {
                                         NodeKind::Bignum(n) => Value::bignum(n.clone()),
                                         NodeKind::Float(f) => Value::float(*f),
                                         NodeKind::Symbol(sym) => Value::symbol(*sym),
-                                        NodeKind::String(str) => Value::string(str),
+                                        NodeKind::String(s) => self.string_literal(s.clone()),
                                         NodeKind::Bool(b) => Value::bool(*b),
                                         NodeKind::Nil => Value::nil(),
                                         _ => unreachable!(),
@@ -1301,6 +1364,17 @@ impl Codegen {
                     }
                 }
             }
+            NodeKind::CaseIn { cond, in_, else_ } => {
+                let else_ = else_.map(|node| *node);
+                self.gen_case_in(globals, iseq, *cond, in_, else_, use_value)?;
+            }
+            NodeKind::PatternMatch {
+                value,
+                pattern,
+                raise,
+            } => {
+                self.gen_pattern_match(globals, iseq, *value, *pattern, raise, use_value)?;
+            }
             NodeKind::MulAssign(mut mlhs, mut mrhs) => {
                 let lhs_len = mlhs.len();
                 if lhs_len == 1 && mrhs.len() == 1 {
@@ -1572,6 +1646,36 @@ impl Codegen {
                     x.escape.push(EscapeInfo::new(src, EscapeKind::Next));
                 }
             }
+            NodeKind::Redo => {
+                if self.loop_stack.last().unwrap().state == LoopState::Top {
+                    //In the case of outer of loops
+                    match self.context().kind {
+                        ContextKind::Block => {
+                            let body_start = self.context().body_start;
+                            iseq.gen_jmp_back(body_start);
+                        }
+                        _ => {
+                            return Err(
+                                self.error_syntax("Invalid redo.", node_loc.merge(self.loc))
+                            );
+                        }
+                    }
+                } else {
+                    //In the case of inner of loops
+                    let src = iseq.gen_jmp();
+                    let x = self.loop_stack.last_mut().unwrap();
+                    x.escape.push(EscapeInfo::new(src, EscapeKind::Redo));
+                }
+            }
+            NodeKind::Retry => match self.context().retry_entries.last() {
+                Some(entry) => {
+                    let body_start = self.context().exception_table[*entry].start;
+                    iseq.gen_jmp_back(body_start);
+                }
+                None => {
+                    return Err(self.error_syntax("Invalid retry.", node_loc.merge(self.loc)));
+                }
+            },
             NodeKind::Lambda(BlockInfo { params, body, lvar }) => {
                 self.loop_stack.push(LoopInfo::new_top());
                 let method = self.gen_iseq(
@@ -1610,6 +1714,31 @@ impl Codegen {
                     use_value,
                     node_loc,
                 );
+            }
+            NodeKind::UndefMethod(names) => {
+                iseq.gen_push_self();
+                let len = names.len();
+                for name in names {
+                    self.gen(globals, iseq, name, true)?;
+                }
+                self.emit_opt_send(
+                    globals,
+                    iseq,
+                    IdentId::_UNDEF_METHOD,
+                    len,
+                    None,
+                    use_value,
+                    node_loc,
+                );
+            }
+            NodeKind::ArrayPattern { .. }
+            | NodeKind::FindPattern { .. }
+            | NodeKind::HashPattern { .. }
+            | NodeKind::AltPattern(_)
+            | NodeKind::CapturePattern(..)
+            | NodeKind::PinPattern(_)
+            | NodeKind::VarPattern(_) => {
+                return Err(self.error_syntax("Unexpected pattern.", node_loc))
             } //_ => unreachable!("Codegen: Unimplemented syntax. {:?}", node.kind),
         };
         Ok(())
@@ -1773,6 +1902,15 @@ impl Codegen {
 }
 
 impl Codegen {
+    /// Create a String object for the string literal `s`, with the source encoding.
+    fn string_literal(&self, s: Vec<u8>) -> Value {
+        let val = Value::bytes(s);
+        if self.source_encoding != Encoding::Utf8 {
+            self.source_encoding.set(val);
+        }
+        val
+    }
+
     /// Evaluate constant expression and return the value.
     fn const_expr(&self, globals: &mut Globals, node: Node) -> Result<Value, RubyError> {
         let loc = node.loc();
@@ -1783,7 +1921,7 @@ impl Codegen {
             NodeKind::Float(f) => Ok(Value::float(f)),
             NodeKind::Nil => Ok(Value::nil()),
             NodeKind::Symbol(s) => Ok(Value::symbol(s)),
-            NodeKind::String(s) => Ok(Value::string(s)),
+            NodeKind::String(s) => Ok(self.string_literal(s)),
             NodeKind::Hash(key_value, true) => self.const_hash(globals, key_value),
             NodeKind::Array(nodes, true) => self.const_array(globals, nodes),
             NodeKind::RegExp(nodes, true) => self.const_regexp(globals, nodes, loc),
@@ -1814,7 +1952,7 @@ impl Codegen {
         let mut string = String::new();
         for node in nodes {
             match &node.kind {
-                NodeKind::String(s) => string += s,
+                NodeKind::String(s) => string += &String::from_utf8_lossy(s),
                 _ => unreachable!(),
             }
         }
@@ -1889,6 +2027,7 @@ impl EscapeInfo {
 enum EscapeKind {
     Break,
     Next,
+    Redo,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -1898,6 +2037,11 @@ pub struct Context {
     /// Unsolved destinations of local jumps.
     jump_dest: Vec<LocalJumpDest>,
     exception_table: Vec<ExceptionEntry>,
+    /// Indices of exception entries for rescue clauses which are currently generated.
+    /// `retry` jumps to the start of the innermost one.
+    retry_entries: Vec<usize>,
+    /// Start position of the body. `redo` in a block jumps here.
+    body_start: ISeqPos,
     kind: ContextKind,
 }
 
@@ -1908,6 +2052,8 @@ impl Context {
             iseq_sourcemap: vec![],
             jump_dest: vec![],
             exception_table: vec![],
+            retry_entries: vec![],
+            body_start: ISeqPos(0),
             kind: ContextKind::Eval,
         }
     }
@@ -1918,6 +2064,8 @@ impl Context {
             iseq_sourcemap: vec![],
             jump_dest: vec![],
             exception_table: vec![],
+            retry_entries: vec![],
+            body_start: ISeqPos(0),
             kind,
         }
     }
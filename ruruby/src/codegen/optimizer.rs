@@ -0,0 +1,843 @@
+use crate::*;
+use num::Integer;
+use std::convert::TryInto;
+
+///
+/// Bytecode optimizer.
+///
+/// This pass runs on an ISeq generated by Codegen, and rewrites it on a control flow graph.
+///
+/// - constant folding of arithmetic and comparison of literals, and conditional branches on literals.
+/// - elimination of unreachable code (e.g. after RETURN or JMP).
+/// - jump threading, and elimination of jumps to the next instruction.
+/// - elimination of DUP/POP and side-effect free push + POP.
+/// - fusion of SET_LOCAL + GET_LOCAL (and DUP + SET_LOCAL) into TEE_LOCAL.
+/// - specialization of `[]` with one argument, `size` and `empty?` without arguments,
+///   into GET_INDEX, OPT_SIZE and OPT_EMPTY.
+///
+/// Instructions are never moved, and removed instructions are treated as zero-width,
+/// so the positions in the exception table and the source map are kept consistent by remapping.
+/// Peephole rewrites are done inside of a basic block, and the start, end and destination of every
+/// exception table entry begin a basic block.
+///
+pub(crate) fn optimize(
+    globals: &mut Globals,
+    iseq: &mut ISeq,
+    exception_table: &mut [ExceptionEntry],
+    iseq_sourcemap: &mut [(ISeqPos, Loc)],
+) {
+    let mut opt = match Optimizer::new(globals, iseq, exception_table) {
+        Some(opt) => opt,
+        None => return,
+    };
+    // Every rewrite removes or simplifies some instructions, so this reaches a fixed point.
+    loop {
+        opt.split_blocks();
+        if !(opt.eliminate_dead_code() | opt.thread_jumps() | opt.peephole()) {
+            break;
+        }
+    }
+    opt.emit(globals, iseq, exception_table, iseq_sourcemap);
+}
+
+#[derive(Debug, Clone)]
+struct Insn {
+    /// Original position of this instruction.
+    pos: usize,
+    /// Encoded instruction. Empty if removed.
+    bytes: Vec<u8>,
+    /// Original position of the jump destination.
+    dest: Option<usize>,
+}
+
+impl Insn {
+    fn op(&self) -> u8 {
+        self.bytes[0]
+    }
+
+    fn is_removed(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    fn remove(&mut self) {
+        self.bytes.clear();
+        self.dest = None;
+    }
+
+    fn replace(&mut self, bytes: Vec<u8>) {
+        self.bytes = bytes;
+        if !is_jump(self.bytes[0]) {
+            self.dest = None;
+        }
+    }
+
+    fn read32(&self, offset: usize) -> u32 {
+        u32::from_le_bytes(self.bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn read16(&self, offset: usize) -> u16 {
+        u16::from_le_bytes(self.bytes[offset..offset + 2].try_into().unwrap())
+    }
+
+    /// Literal pushed by this instruction.
+    fn literal(&self) -> Option<Value> {
+        match self.op() {
+            Inst::PUSH_NIL => Some(Value::nil()),
+            Inst::PUSH_VAL => Some(Value::from(u64::from_le_bytes(
+                self.bytes[1..9].try_into().unwrap(),
+            ))),
+            _ => None,
+        }
+    }
+
+    /// Returns true if this instruction only pushes one value without any side effect.
+    fn is_pure_push(&self) -> bool {
+        match self.op() {
+            Inst::PUSH_NIL
+            | Inst::PUSH_VAL
+            | Inst::PUSH_SELF
+            | Inst::CONST_VAL
+            | Inst::GET_LOCAL
+            | Inst::GET_DYNLOCAL => true,
+            Inst::DUP => self.read32(1) == 1,
+            _ => false,
+        }
+    }
+}
+
+struct Optimizer {
+    insns: Vec<Insn>,
+    /// Index of the instruction at each original position.
+    index: Vec<Option<usize>>,
+    /// Ranges of instructions of basic blocks.
+    blocks: Vec<std::ops::Range<usize>>,
+    /// Basic block of each instruction.
+    block_of: Vec<usize>,
+    /// Original positions of destinations of OPT_CASE and OPT_CASE2.
+    case_dests: Vec<(usize, Vec<usize>)>,
+    /// Original positions in the exception table.
+    boundaries: Vec<usize>,
+    /// Original positions where an exception is caught.
+    handlers: Vec<usize>,
+}
+
+fn is_jump(op: u8) -> bool {
+    matches!(
+        op,
+        Inst::JMP
+            | Inst::JMP_BACK
+            | Inst::JMP_F
+            | Inst::JMP_T
+            | Inst::JMP_F_EQ
+            | Inst::JMP_F_NE
+            | Inst::JMP_F_GT
+            | Inst::JMP_F_GE
+            | Inst::JMP_F_LT
+            | Inst::JMP_F_LE
+            | Inst::JMP_F_EQI
+            | Inst::JMP_F_NEI
+            | Inst::JMP_F_GTI
+            | Inst::JMP_F_GEI
+            | Inst::JMP_F_LTI
+            | Inst::JMP_F_LEI
+    )
+}
+
+/// Instructions which never continue to the next instruction.
+fn is_terminal(op: u8) -> bool {
+    matches!(
+        op,
+        Inst::JMP
+            | Inst::JMP_BACK
+            | Inst::RETURN
+            | Inst::BREAK
+            | Inst::MRETURN
+            | Inst::THROW
+            | Inst::OPT_CASE
+            | Inst::OPT_CASE2
+    )
+}
+
+fn encode_u32(op: u8, operand: u32) -> Vec<u8> {
+    let mut bytes = vec![op];
+    bytes.extend_from_slice(&operand.to_le_bytes());
+    bytes
+}
+
+fn encode_val(val: Value) -> Vec<u8> {
+    let mut bytes = vec![Inst::PUSH_VAL];
+    bytes.extend_from_slice(&val.id().to_le_bytes());
+    bytes
+}
+
+impl Optimizer {
+    /// Decode `iseq` and build a CFG.
+    ///
+    /// Returns None if `iseq` contains a position which is not on an instruction boundary.
+    fn new(globals: &Globals, iseq: &ISeq, exception_table: &[ExceptionEntry]) -> Option<Self> {
+        let len = iseq.len();
+        let mut insns = vec![];
+        let mut index = vec![None; len + 1];
+        let mut case_dests = vec![];
+        let mut pos = ISeqPos::from(0);
+        while pos.into_usize() < len {
+            let op = iseq[pos];
+            let next = pos + Inst::inst_size(op);
+            if next.into_usize() > len {
+                return None;
+            }
+            let dest = match op {
+                Inst::JMP_F_EQI
+                | Inst::JMP_F_NEI
+                | Inst::JMP_F_GTI
+                | Inst::JMP_F_GEI
+                | Inst::JMP_F_LTI
+                | Inst::JMP_F_LEI => Some(next + iseq.read_disp(pos + 5)),
+                op if is_jump(op) => Some(next + iseq.read_disp(pos + 1)),
+                Inst::OPT_CASE => {
+                    let map = globals.case_dispatch.get_entry(iseq.read32(pos + 1));
+                    let mut dests = vec![(next + iseq.read_disp(pos + 5)).into_usize()];
+                    dests.extend(map.values().map(|disp| (next + *disp).into_usize()));
+                    case_dests.push((insns.len(), dests));
+                    None
+                }
+                Inst::OPT_CASE2 => {
+                    let (_, _, map) = globals.case_dispatch2.get_entry(iseq.read32(pos + 1));
+                    let mut dests = vec![(next + iseq.read_disp(pos + 5)).into_usize()];
+                    dests.extend(map.iter().map(|disp| (next + *disp).into_usize()));
+                    case_dests.push((insns.len(), dests));
+                    None
+                }
+                _ => None,
+            };
+            index[pos.into_usize()] = Some(insns.len());
+            insns.push(Insn {
+                pos: pos.into_usize(),
+                bytes: iseq[pos..next].to_vec(),
+                dest: dest.map(|dest| dest.into_usize()),
+            });
+            pos = next;
+        }
+        index[len] = Some(insns.len());
+
+        let mut boundaries = vec![];
+        let mut handlers = vec![];
+        for entry in exception_table {
+            boundaries.extend([entry.start, entry.end, entry.dest].map(|pos| pos.into_usize()));
+            handlers.push(entry.dest.into_usize());
+        }
+        let is_boundary = |pos: &usize| matches!(index.get(*pos), Some(Some(_)));
+        if !insns
+            .iter()
+            .filter_map(|insn| insn.dest)
+            .all(|dest| is_boundary(&dest))
+            || !case_dests
+                .iter()
+                .flat_map(|(_, dests)| dests)
+                .all(is_boundary)
+            || !boundaries.iter().all(is_boundary)
+        {
+            return None;
+        }
+        Some(Optimizer {
+            insns,
+            index,
+            blocks: vec![],
+            block_of: vec![],
+            case_dests,
+            boundaries,
+            handlers,
+        })
+    }
+
+    /// Split live instructions into basic blocks.
+    fn split_blocks(&mut self) {
+        let len = self.insns.len();
+        let mut leader = vec![false; len + 1];
+        leader[0] = true;
+        for (i, insn) in self.insns.iter().enumerate() {
+            if insn.is_removed() {
+                continue;
+            }
+            if let Some(dest) = insn.dest {
+                leader[self.index[dest].unwrap()] = true;
+            }
+            if insn.dest.is_some() || is_terminal(insn.op()) {
+                leader[i + 1] = true;
+            }
+        }
+        for (i, dests) in &self.case_dests {
+            if !self.insns[*i].is_removed() {
+                for dest in dests {
+                    leader[self.index[*dest].unwrap()] = true;
+                }
+            }
+        }
+        for pos in &self.boundaries {
+            leader[self.index[*pos].unwrap()] = true;
+        }
+
+        // The end of the ISeq is also treated as an (empty) block.
+        leader[len] = true;
+        self.blocks.clear();
+        self.block_of = vec![0; len + 1];
+        let mut start = 0;
+        for (i, _) in leader.iter().enumerate().skip(1).filter(|(_, leader)| **leader) {
+            self.block_of[start..i].fill(self.blocks.len());
+            self.blocks.push(start..i);
+            start = i;
+        }
+        self.block_of[len] = self.blocks.len();
+    }
+
+    /// Basic block which begins at the original position `pos`.
+    fn block_at(&self, pos: usize) -> usize {
+        self.block_of[self.index[pos].unwrap()]
+    }
+
+    /// Index of the first live instruction at or after the original position `pos`.
+    fn live_at(&self, pos: usize) -> Option<usize> {
+        let i = self.index[pos].unwrap();
+        (i..self.insns.len()).find(|i| !self.insns[*i].is_removed())
+    }
+
+    /// Index of the first live instruction after the instruction `i`.
+    fn live_after(&self, i: usize) -> Option<usize> {
+        (i + 1..self.insns.len()).find(|i| !self.insns[*i].is_removed())
+    }
+
+    /// Remove basic blocks which are not reachable from the entry or exception handlers.
+    fn eliminate_dead_code(&mut self) -> bool {
+        let mut reachable = vec![false; self.blocks.len()];
+        let mut stack = vec![0];
+        stack.extend(self.handlers.iter().map(|pos| self.block_at(*pos)));
+        while let Some(b) = stack.pop() {
+            if b >= self.blocks.len() || reachable[b] {
+                continue;
+            }
+            reachable[b] = true;
+            let last = self.blocks[b]
+                .clone()
+                .rev()
+                .find(|i| !self.insns[*i].is_removed());
+            let last = match last {
+                Some(last) => last,
+                None => {
+                    stack.push(b + 1);
+                    continue;
+                }
+            };
+            let insn = &self.insns[last];
+            if let Some(dest) = insn.dest {
+                stack.push(self.block_at(dest));
+            }
+            if let Some((_, dests)) = self.case_dests.iter().find(|(i, _)| *i == last) {
+                stack.extend(dests.iter().map(|pos| self.block_at(*pos)));
+            }
+            if !is_terminal(insn.op()) {
+                stack.push(b + 1);
+            }
+        }
+        let mut changed = false;
+        for (b, block) in self.blocks.iter().enumerate() {
+            if reachable[b] {
+                continue;
+            }
+            for insn in &mut self.insns[block.clone()] {
+                if !insn.is_removed() {
+                    insn.remove();
+                    changed = true;
+                }
+            }
+        }
+        changed
+    }
+
+    /// Thread jumps to JMP, and remove jumps to the next instruction.
+    fn thread_jumps(&mut self) -> bool {
+        let mut changed = false;
+        for i in 0..self.insns.len() {
+            let mut dest = match self.insns[i].dest {
+                Some(dest) => dest,
+                None => continue,
+            };
+            // Follow a chain of JMPs.
+            let mut target = self.live_at(dest);
+            let mut count = 0;
+            while let Some(t) = target {
+                let insn = &self.insns[t];
+                if !matches!(insn.op(), Inst::JMP | Inst::JMP_BACK) || t == i || count > 100 {
+                    break;
+                }
+                dest = insn.dest.unwrap();
+                target = self.live_at(dest);
+                count += 1;
+            }
+            let op = self.insns[i].op();
+            if self.insns[i].dest != Some(dest) {
+                self.insns[i].dest = Some(dest);
+                changed = true;
+            }
+            let target_op = target.map(|t| self.insns[t].op());
+            if matches!(op, Inst::JMP | Inst::JMP_BACK) {
+                if let Some(term @ (Inst::RETURN | Inst::BREAK | Inst::MRETURN)) = target_op {
+                    self.insns[i].replace(vec![term]);
+                    changed = true;
+                } else if target.is_some() && target == self.live_after(i) {
+                    self.insns[i].remove();
+                    changed = true;
+                }
+            } else if matches!(op, Inst::JMP_F | Inst::JMP_T)
+                && target.is_some()
+                && target == self.live_after(i)
+            {
+                // Only discard the condition.
+                self.insns[i].replace(vec![Inst::POP]);
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Apply peephole rewrites in each basic block.
+    fn peephole(&mut self) -> bool {
+        let mut changed = false;
+        for b in 0..self.blocks.len() {
+            while self.peephole_block(b) {
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    fn peephole_block(&mut self, b: usize) -> bool {
+        let live: Vec<usize> = self.blocks[b]
+            .clone()
+            .filter(|i| !self.insns[*i].is_removed())
+            .collect();
+        for (k, &i) in live.iter().enumerate() {
+            if self.specialize_send(i) {
+                return true;
+            }
+            let j = match live.get(k + 1) {
+                Some(j) => *j,
+                None => continue,
+            };
+            if self.peephole2(i, j) {
+                return true;
+            }
+            if let Some(&l) = live.get(k + 2) {
+                if self.fold_binop(i, j, l) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Rewrite two consecutive instructions `i` and `j`.
+    fn peephole2(&mut self, i: usize, j: usize) -> bool {
+        let (first, second) = (&self.insns[i], &self.insns[j]);
+        match (first.op(), second.op()) {
+            // Push without side effects + POP
+            (_, Inst::POP) if first.is_pure_push() => {
+                self.insns[i].remove();
+                self.insns[j].remove();
+            }
+            // TEE_LOCAL + POP => SET_LOCAL
+            (Inst::TEE_LOCAL, Inst::POP) => {
+                let id = first.read32(1);
+                self.insns[i].replace(encode_u32(Inst::SET_LOCAL, id));
+                self.insns[j].remove();
+            }
+            // SET_LOCAL + GET_LOCAL of the same variable => TEE_LOCAL
+            (Inst::SET_LOCAL, Inst::GET_LOCAL) if first.read32(1) == second.read32(1) => {
+                let id = first.read32(1);
+                self.insns[i].replace(encode_u32(Inst::TEE_LOCAL, id));
+                self.insns[j].remove();
+            }
+            // DUP 1 + SET_LOCAL => TEE_LOCAL
+            (Inst::DUP, Inst::SET_LOCAL) if first.read32(1) == 1 => {
+                let id = second.read32(1);
+                self.insns[i].remove();
+                self.insns[j].replace(encode_u32(Inst::TEE_LOCAL, id));
+            }
+            // literal + unary operation
+            (_, op) if first.literal().is_some() => {
+                let val = first.literal().unwrap();
+                match op {
+                    Inst::NOT => {
+                        self.insns[i].remove();
+                        self.insns[j].replace(encode_val(Value::bool(!val.to_bool())));
+                    }
+                    Inst::JMP_F => {
+                        self.insns[i].remove();
+                        self.fold_branch(j, val);
+                    }
+                    Inst::JMP_T => {
+                        self.insns[i].remove();
+                        self.fold_branch(j, Value::bool(!val.to_bool()));
+                    }
+                    Inst::ADDI
+                    | Inst::SUBI
+                    | Inst::EQI
+                    | Inst::NEI
+                    | Inst::GTI
+                    | Inst::GEI
+                    | Inst::LTI
+                    | Inst::LEI => {
+                        let imm = Value::integer(second.read32(1) as i32 as i64);
+                        let op = match op {
+                            Inst::ADDI => Inst::ADD,
+                            Inst::SUBI => Inst::SUB,
+                            Inst::EQI => Inst::EQ,
+                            Inst::NEI => Inst::NE,
+                            Inst::GTI => Inst::GT,
+                            Inst::GEI => Inst::GE,
+                            Inst::LTI => Inst::LT,
+                            _ => Inst::LE,
+                        };
+                        match fold(op, val, imm) {
+                            Some(res) => {
+                                self.insns[i].remove();
+                                self.insns[j].replace(encode_val(res));
+                            }
+                            None => return false,
+                        }
+                    }
+                    Inst::JMP_F_EQI
+                    | Inst::JMP_F_NEI
+                    | Inst::JMP_F_GTI
+                    | Inst::JMP_F_GEI
+                    | Inst::JMP_F_LTI
+                    | Inst::JMP_F_LEI => {
+                        let imm = Value::integer(second.read32(1) as i32 as i64);
+                        let op = match op {
+                            Inst::JMP_F_EQI => Inst::EQ,
+                            Inst::JMP_F_NEI => Inst::NE,
+                            Inst::JMP_F_GTI => Inst::GT,
+                            Inst::JMP_F_GEI => Inst::GE,
+                            Inst::JMP_F_LTI => Inst::LT,
+                            _ => Inst::LE,
+                        };
+                        match fold(op, val, imm) {
+                            Some(res) => {
+                                self.insns[i].remove();
+                                self.fold_branch(j, res);
+                            }
+                            None => return false,
+                        }
+                    }
+                    _ => return false,
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Fold a binary operation `l` on two literals `i` and `j`.
+    fn fold_binop(&mut self, i: usize, j: usize, l: usize) -> bool {
+        let (lhs, rhs) = match (self.insns[i].literal(), self.insns[j].literal()) {
+            (Some(lhs), Some(rhs)) => (lhs, rhs),
+            _ => return false,
+        };
+        let op = match self.insns[l].op() {
+            Inst::JMP_F_EQ => Inst::EQ,
+            Inst::JMP_F_NE => Inst::NE,
+            Inst::JMP_F_GT => Inst::GT,
+            Inst::JMP_F_GE => Inst::GE,
+            Inst::JMP_F_LT => Inst::LT,
+            Inst::JMP_F_LE => Inst::LE,
+            op => op,
+        };
+        match fold(op, lhs, rhs) {
+            Some(res) => {
+                self.insns[i].remove();
+                self.insns[j].remove();
+                if is_jump(self.insns[l].op()) {
+                    self.fold_branch(l, res);
+                } else {
+                    self.insns[l].replace(encode_val(res));
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replace a conditional branch `i` on the folded condition `cond`.
+    fn fold_branch(&mut self, i: usize, cond: Value) {
+        if cond.to_bool() {
+            self.insns[i].remove();
+        } else {
+            self.insns[i].replace(encode_u32(Inst::JMP, 0));
+        }
+    }
+
+    /// Specialize a method call of `[]`, `size` or `empty?` without a block nor special arguments.
+    fn specialize_send(&mut self, i: usize) -> bool {
+        let insn = &self.insns[i];
+        // (method, number of args, block, inline cache)
+        let (method, args, block, cache) = match insn.op() {
+            Inst::OPT_SEND => (
+                insn.read32(1),
+                insn.read16(5),
+                insn.read32(7),
+                insn.read32(11),
+            ),
+            Inst::SEND if insn.bytes[7] == 0 => (
+                insn.read32(1),
+                insn.read16(5),
+                insn.read32(8),
+                insn.read32(12),
+            ),
+            _ => return false,
+        };
+        if block != 0 {
+            return false;
+        }
+        let bytes = match (IdentId::from(method), args) {
+            (IdentId::_INDEX, 1) => vec![Inst::GET_INDEX],
+            (IdentId::SIZE, 0) => encode_u32(Inst::OPT_SIZE, cache),
+            (IdentId::EMPTY, 0) => encode_u32(Inst::OPT_EMPTY, cache),
+            _ => return false,
+        };
+        self.insns[i].replace(bytes);
+        true
+    }
+
+    /// Write back the optimized ISeq, and remap positions in the exception table, the source map
+    /// and the case dispatch tables.
+    fn emit(
+        self,
+        globals: &mut Globals,
+        iseq: &mut ISeq,
+        exception_table: &mut [ExceptionEntry],
+        iseq_sourcemap: &mut [(ISeqPos, Loc)],
+    ) {
+        // New position of each original instruction boundary.
+        let mut new_pos = vec![0; self.index.len()];
+        let mut cur = 0;
+        for insn in &self.insns {
+            new_pos[insn.pos] = cur;
+            cur += insn.bytes.len();
+        }
+        *new_pos.last_mut().unwrap() = cur;
+        let map = |pos: usize| ISeqPos::from(new_pos[pos]);
+
+        let mut res = ISeq::new();
+        for (i, insn) in self.insns.iter().enumerate() {
+            if insn.is_removed() {
+                continue;
+            }
+            let start = res.current();
+            let mut bytes = insn.bytes.clone();
+            if let Some(dest) = insn.dest {
+                if bytes[0] == Inst::JMP || bytes[0] == Inst::JMP_BACK {
+                    // Backward jumps check interrupts.
+                    bytes[0] = if map(dest) <= start {
+                        Inst::JMP_BACK
+                    } else {
+                        Inst::JMP
+                    };
+                }
+            }
+            bytes.iter().for_each(|b| res.push(*b));
+            let end = res.current();
+            if let Some(dest) = insn.dest {
+                res.write_disp(end, map(dest));
+            }
+            if let Some((_, dests)) = self.case_dests.iter().find(|(j, _)| *j == i) {
+                res.write_disp(end, map(dests[0]));
+                let old_end = ISeqPos::from(insn.pos + insn.bytes.len());
+                let remap = |disp: &mut ISeqDisp| *disp = end - map((old_end + *disp).into_usize());
+                let id = insn.read32(1);
+                if insn.op() == Inst::OPT_CASE {
+                    globals
+                        .case_dispatch
+                        .get_mut_entry(id)
+                        .values_mut()
+                        .for_each(remap);
+                } else {
+                    globals
+                        .case_dispatch2
+                        .get_mut_entry(id)
+                        .2
+                        .iter_mut()
+                        .for_each(remap);
+                }
+            }
+        }
+        *iseq = res;
+
+        for entry in exception_table {
+            entry.start = map(entry.start.into_usize());
+            entry.end = map(entry.end.into_usize());
+            entry.dest = map(entry.dest.into_usize());
+        }
+        for (pos, _) in iseq_sourcemap {
+            *pos = map(pos.into_usize());
+        }
+    }
+}
+
+/// Evaluate a binary operation `op` on literals, in the same way as the fast path of the VM.
+fn fold(op: u8, lhs: Value, rhs: Value) -> Option<Value> {
+    let res = if let (Some(lhs), Some(rhs)) = (lhs.as_fixnum(), rhs.as_fixnum()) {
+        match op {
+            Inst::ADD => Value::integer(lhs.checked_add(rhs)?),
+            Inst::SUB => Value::integer(lhs.checked_sub(rhs)?),
+            Inst::MUL => Value::integer(lhs.checked_mul(rhs)?),
+            Inst::DIV if rhs != 0 && !(lhs == i64::MIN && rhs == -1) => {
+                Value::integer(Integer::div_floor(&lhs, &rhs))
+            }
+            Inst::EQ => Value::bool(lhs == rhs),
+            Inst::NE => Value::bool(lhs != rhs),
+            Inst::GT => Value::bool(lhs > rhs),
+            Inst::GE => Value::bool(lhs >= rhs),
+            Inst::LT => Value::bool(lhs < rhs),
+            Inst::LE => Value::bool(lhs <= rhs),
+            _ => return None,
+        }
+    } else {
+        let to_f64 = |v: Value| v.as_flonum().or_else(|| v.as_fixnum().map(|i| i as f64));
+        let (lhs, rhs) = (to_f64(lhs)?, to_f64(rhs)?);
+        match op {
+            Inst::ADD => Value::float(lhs + rhs),
+            Inst::SUB => Value::float(lhs - rhs),
+            Inst::MUL => Value::float(lhs * rhs),
+            Inst::GT => Value::bool(lhs > rhs),
+            Inst::GE => Value::bool(lhs >= rhs),
+            Inst::LT => Value::bool(lhs < rhs),
+            Inst::LE => Value::bool(lhs <= rhs),
+            _ => return None,
+        }
+    };
+    // Heap-allocated results (Bignum or Float) can not be embedded in PUSH_VAL.
+    if res.is_packed_value() {
+        Some(res)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::*;
+    use crate::*;
+
+    #[test]
+    fn optimizer_fold() {
+        let program = r#"
+        def insts(src)
+          RubyVM::InstructionSequence.compile(src).to_a.map { |inst| inst[0] }
+        end
+        assert [:PUSH_VAL, :TEE_LOCAL, :RETURN], insts("a = 2 * 3 + 4; a")
+        assert 10, RubyVM::InstructionSequence.compile("a = 2 * 3 + 4; a").eval
+        assert [:PUSH_VAL, :RETURN], insts("if 1 < 2 then 3 else 4 end")
+        assert [:PUSH_VAL, :RETURN], insts("return 1; p 2")
+        assert [-4, 3, 14.0, 3.5, true, false, true], [7 / -2, 7 % 4, 7.0 * 2, 1 + 2.5, 3 <= 3.0, 1 == 2, !nil]
+        assert 9223372036854775808, 2**62 + 2**62
+        assert 4611686018427387904 * 2, 4611686018427387904 + 4611686018427387904
+        assert_error { 1 / 0 }
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn optimizer_control() {
+        let program = r##"
+        x = 0
+        while true
+          x += 1
+          next if x.odd?
+          break if x > 10
+        end
+        assert 12, x
+        y = 0
+        y += 1 until y == 5
+        assert 5, y
+        def kind(v)
+          case v
+          when 1, 2 then :small
+          when 100 then :large
+          else :other
+          end
+        end
+        assert [:small, :small, :large, :other], [1, 2, 100, 3].map { |v| kind(v) }
+        def kind2(v)
+          case v
+          when 1 then :one
+          when "s" then :s
+          end
+        end
+        assert [:one, :s, nil], [1, "s", 2].map { |v| kind2(v) }
+        def boom(n)
+          x = 1
+          raise "boom" if n > 0
+          x
+        rescue => e
+          e.backtrace[0]
+        end
+        assert 1, boom(0)
+        assert true, boom(1).end_with?(":#{__LINE__ - 6}:in `boom'")
+        count = 0
+        begin
+          count += 1
+          raise "retry" if count < 3
+        rescue
+          retry
+        end
+        assert 3, count
+        "##;
+        assert_script(program);
+    }
+
+    #[test]
+    fn optimizer_specialize() {
+        let program = r##"
+        class Foo
+          def size; 42; end
+          def empty?; :no; end
+          def [](i); i * 2; end
+        end
+        class MyArray < Array
+          def size; -1; end
+        end
+        a = [1, 2, 3]
+        h = {a: 1}
+        assert [3, false, true, 1, false], [a.size, a.empty?, [].empty?, h.size, h.empty?]
+        assert [3, true, 3], ["abc".size, "".empty?, {a: 1, b: 2, c: 3}.size]
+        assert [42, :no, 8, -1, 2], [Foo.new.size, Foo.new.empty?, Foo.new.[](4), MyArray.new.size, a.[](1)]
+        assert_error { nil.size }
+        disasm = RubyVM::InstructionSequence.compile("a = []; b = a.size; a.empty?").disasm
+        assert true, disasm.include?("O_SIZE")
+        assert true, disasm.include?("O_EMPTY")
+        def sizes(a, h); [a.size, a.empty?, h.size, h.empty?]; end
+        assert [3, false, 1, false], sizes(a, h)
+        class Array; def size; :redef; end; end
+        class Hash; def empty?; :redef; end; end
+        assert [:redef, false, 1, :redef], sizes(a, h)
+        "##;
+        assert_script(program);
+    }
+
+    #[test]
+    fn optimizer_disable() {
+        let program = "RubyVM::InstructionSequence.compile('a = 1 + 2; a.size').to_a.map { |inst| inst[0] }.inspect";
+        let mut vm = VM::new();
+        vm.globals.iseq_opt = false;
+        let res = vm.run("", program.to_string()).unwrap();
+        assert_eq!(
+            "[:PUSH_VAL, :ADDI, :SET_LOCAL, :GET_LOCAL, :O_SEND, :RETURN]",
+            res.as_string().unwrap()
+        );
+        let mut vm = VM::new();
+        let res = vm.run("", program.to_string()).unwrap();
+        assert_eq!(
+            "[:PUSH_VAL, :TEE_LOCAL, :O_SIZE, :RETURN]",
+            res.as_string().unwrap()
+        );
+    }
+}
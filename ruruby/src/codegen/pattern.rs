@@ -0,0 +1,422 @@
+use super::*;
+use ruruby_parse::{CaseInBranch, HashPatternRest};
+
+///
+/// Code generation for pattern matching.
+///
+/// Each pattern is compiled into a sequence which consumes the target value
+/// on the top of the stack and pushes the result of the matching (true or false).
+///
+/// +--------+         +------+
+/// | target |   ==>   | bool |
+/// +--------+         +------+
+///
+impl Codegen {
+    pub(super) fn gen_case_in(
+        &mut self,
+        globals: &mut Globals,
+        iseq: &mut ISeq,
+        cond: Node,
+        in_: Vec<CaseInBranch>,
+        else_: Option<Node>,
+        use_value: bool,
+    ) -> Result<(), RubyError> {
+        let loc = cond.loc();
+        // Detailed error (NoMatchingPatternKeyError) is raised only for a single pattern.
+        let keys = match (&else_, in_.as_slice()) {
+            (None, [branch]) if branch.guard.is_none() => pattern_keys(&branch.pattern),
+            _ => None,
+        };
+        let mut end = vec![];
+        self.gen(globals, iseq, cond, true)?;
+        for branch in in_ {
+            let mut next = vec![];
+            iseq.gen_dup(1);
+            self.gen_pattern(globals, iseq, *branch.pattern)?;
+            next.push(iseq.gen_jmp_if_f());
+            if let Some(guard) = branch.guard {
+                self.gen(globals, iseq, *guard, true)?;
+                next.push(iseq.gen_jmp_if_f());
+            }
+            iseq.gen_pop();
+            self.gen(globals, iseq, *branch.body, use_value)?;
+            end.push(iseq.gen_jmp());
+            for dest in next {
+                iseq.write_disp_from_cur(dest);
+            }
+        }
+        match else_ {
+            Some(else_) => {
+                iseq.gen_pop();
+                self.gen(globals, iseq, else_, use_value)?;
+            }
+            None => self.emit_no_match(globals, iseq, keys, loc),
+        }
+        for dest in end {
+            iseq.write_disp_from_cur(dest);
+        }
+        Ok(())
+    }
+
+    /// Generate `value => pattern` (raise: true) or `value in pattern` (raise: false).
+    pub(super) fn gen_pattern_match(
+        &mut self,
+        globals: &mut Globals,
+        iseq: &mut ISeq,
+        value: Node,
+        pattern: Node,
+        raise: bool,
+        use_value: bool,
+    ) -> Result<(), RubyError> {
+        let loc = value.loc().merge(pattern.loc());
+        self.gen(globals, iseq, value, true)?;
+        if raise {
+            let keys = pattern_keys(&pattern);
+            iseq.gen_dup(1);
+            self.gen_pattern(globals, iseq, pattern)?;
+            let matched = iseq.gen_jmp_if_t();
+            self.emit_no_match(globals, iseq, keys, loc);
+            iseq.write_disp_from_cur(matched);
+            iseq.gen_pop();
+            if use_value {
+                iseq.gen_push_nil();
+            }
+        } else {
+            self.gen_pattern(globals, iseq, pattern)?;
+            if !use_value {
+                iseq.gen_pop();
+            }
+        }
+        Ok(())
+    }
+
+    fn gen_pattern(
+        &mut self,
+        globals: &mut Globals,
+        iseq: &mut ISeq,
+        pattern: Node,
+    ) -> Result<(), RubyError> {
+        let loc = pattern.loc();
+        match pattern.kind {
+            NodeKind::VarPattern(id) => {
+                self.emit_set_local(iseq, id);
+                iseq.gen_val(Value::true_val());
+            }
+            NodeKind::PinPattern(box node) => self.gen_value_pattern(globals, iseq, node, loc)?,
+            NodeKind::CapturePattern(box pattern, id) => {
+                iseq.gen_dup(1);
+                self.gen_pattern(globals, iseq, pattern)?;
+                let fail = iseq.gen_jmp_if_f();
+                iseq.gen_dup(1);
+                self.emit_set_local(iseq, id);
+                gen_pattern_result(iseq, vec![fail]);
+            }
+            NodeKind::AltPattern(alts) => {
+                let mut matched = vec![];
+                for alt in alts {
+                    iseq.gen_dup(1);
+                    self.gen_pattern(globals, iseq, alt)?;
+                    matched.push(iseq.gen_jmp_if_t());
+                }
+                let fail = iseq.gen_jmp();
+                for dest in matched {
+                    iseq.write_disp_from_cur(dest);
+                }
+                gen_pattern_result(iseq, vec![fail]);
+            }
+            NodeKind::ArrayPattern {
+                const_,
+                pre,
+                rest,
+                post,
+            } => {
+                let mut fail = vec![];
+                let deconstruct = IdentId::get_id("deconstruct");
+                self.gen_check_deconstruct(globals, iseq, const_, deconstruct, &mut fail, loc)?;
+                self.emit_opt_send(globals, iseq, deconstruct, 0, None, true, loc);
+                // check the length of the array.
+                let len = pre.len() + post.len();
+                iseq.gen_dup(1);
+                self.emit_opt_send(globals, iseq, IdentId::get_id("size"), 0, None, true, loc);
+                iseq.gen_integer(globals, len as i64);
+                iseq.push(if rest.is_some() { Inst::GE } else { Inst::EQ });
+                fail.push(iseq.gen_jmp_if_f());
+                let post_len = post.len() as i64;
+                let pre_len = pre.len() as i64;
+                for (i, pattern) in pre.into_iter().enumerate() {
+                    self.gen_array_elem_pattern(globals, iseq, i as i64, pattern, &mut fail)?;
+                }
+                for (i, pattern) in post.into_iter().enumerate() {
+                    let index = i as i64 - post_len;
+                    self.gen_array_elem_pattern(globals, iseq, index, pattern, &mut fail)?;
+                }
+                if let Some(Some(id)) = rest {
+                    // rest = ary[pre_len..-(post_len + 1)]
+                    iseq.gen_dup(1);
+                    iseq.gen_val(Value::false_val());
+                    iseq.gen_integer(globals, -post_len - 1);
+                    iseq.gen_integer(globals, pre_len);
+                    iseq.push(Inst::CREATE_RANGE);
+                    self.emit_get_array_elem(iseq, loc);
+                    self.emit_set_local(iseq, id);
+                }
+                gen_pattern_result(iseq, fail);
+            }
+            NodeKind::FindPattern {
+                const_,
+                pre,
+                mid,
+                post,
+            } => {
+                let mut fail = vec![];
+                let deconstruct = IdentId::get_id("deconstruct");
+                self.gen_check_deconstruct(globals, iseq, const_, deconstruct, &mut fail, loc)?;
+                self.emit_opt_send(globals, iseq, deconstruct, 0, None, true, loc);
+                let mid_len = mid.len() as i64;
+                // stack: ary, index
+                iseq.gen_integer(globals, 0);
+                let loop_start = iseq.current();
+                // check index + mid_len <= ary.size
+                iseq.gen_dup(2);
+                iseq.gen_integer(globals, mid_len);
+                iseq.push(Inst::ADD);
+                iseq.gen_sinkn(1);
+                self.emit_opt_send(globals, iseq, IdentId::get_id("size"), 0, None, true, loc);
+                iseq.push(Inst::LE);
+                let exhausted = iseq.gen_jmp_if_f();
+                let mut next = vec![];
+                for (i, pattern) in mid.into_iter().enumerate() {
+                    iseq.gen_dup(2);
+                    iseq.gen_integer(globals, i as i64);
+                    iseq.push(Inst::ADD);
+                    self.emit_get_array_elem(iseq, loc);
+                    self.gen_pattern(globals, iseq, pattern)?;
+                    next.push(iseq.gen_jmp_if_f());
+                }
+                if let Some(id) = pre {
+                    // pre = ary[0, index]
+                    iseq.gen_dup(2);
+                    iseq.gen_integer(globals, 0);
+                    iseq.gen_sinkn(1);
+                    self.emit_opt_send(globals, iseq, IdentId::_INDEX, 2, None, true, loc);
+                    self.emit_set_local(iseq, id);
+                }
+                if let Some(id) = post {
+                    // post = ary[index + mid_len..-1]
+                    iseq.gen_dup(2);
+                    iseq.gen_integer(globals, mid_len);
+                    iseq.push(Inst::ADD);
+                    iseq.gen_val(Value::false_val());
+                    iseq.gen_sinkn(1);
+                    iseq.gen_integer(globals, -1);
+                    iseq.gen_sinkn(1);
+                    iseq.push(Inst::CREATE_RANGE);
+                    self.emit_get_array_elem(iseq, loc);
+                    self.emit_set_local(iseq, id);
+                }
+                iseq.gen_pop();
+                let matched = iseq.gen_jmp();
+                // try the next index.
+                for dest in next {
+                    iseq.write_disp_from_cur(dest);
+                }
+                iseq.gen_integer(globals, 1);
+                iseq.push(Inst::ADD);
+                iseq.gen_jmp_back(loop_start);
+                iseq.write_disp_from_cur(exhausted);
+                iseq.gen_pop();
+                fail.push(iseq.gen_jmp());
+                iseq.write_disp_from_cur(matched);
+                gen_pattern_result(iseq, fail);
+            }
+            NodeKind::HashPattern {
+                const_,
+                pairs,
+                rest,
+            } => {
+                let mut fail = vec![];
+                let deconstruct_keys = IdentId::get_id("deconstruct_keys");
+                self.gen_check_deconstruct(
+                    globals,
+                    iseq,
+                    const_,
+                    deconstruct_keys,
+                    &mut fail,
+                    loc,
+                )?;
+                let keys: Vec<IdentId> = pairs.iter().map(|(key, _)| *key).collect();
+                // deconstruct_keys receives nil when `**rest` exists, otherwise an Array of keys.
+                if let HashPatternRest::Bind(_) = rest {
+                    iseq.gen_push_nil();
+                } else {
+                    for key in &keys {
+                        iseq.gen_val(Value::symbol(*key));
+                    }
+                    iseq.gen_create_array(keys.len());
+                }
+                self.emit_opt_send(globals, iseq, deconstruct_keys, 1, None, true, loc);
+                if pairs.is_empty() && rest == HashPatternRest::None {
+                    // `{}` matches only an empty Hash.
+                    self.gen_hash_size_check(globals, iseq, 0, &mut fail, loc);
+                }
+                for (key, pattern) in pairs {
+                    iseq.gen_dup(1);
+                    iseq.gen_val(Value::symbol(key));
+                    self.emit_opt_send(globals, iseq, IdentId::get_id("key?"), 1, None, true, loc);
+                    fail.push(iseq.gen_jmp_if_f());
+                    iseq.gen_dup(1);
+                    iseq.gen_val(Value::symbol(key));
+                    self.emit_get_array_elem(iseq, loc);
+                    match pattern {
+                        Some(pattern) => {
+                            self.gen_pattern(globals, iseq, pattern)?;
+                            fail.push(iseq.gen_jmp_if_f());
+                        }
+                        None => self.emit_set_local(iseq, key),
+                    }
+                }
+                match rest {
+                    HashPatternRest::None => {}
+                    HashPatternRest::Nil => {
+                        self.gen_hash_size_check(globals, iseq, keys.len(), &mut fail, loc)
+                    }
+                    HashPatternRest::Bind(id) => {
+                        iseq.gen_dup(1);
+                        self.emit_opt_send(
+                            globals,
+                            iseq,
+                            IdentId::get_id("dup"),
+                            0,
+                            None,
+                            true,
+                            loc,
+                        );
+                        for key in keys {
+                            iseq.gen_dup(1);
+                            iseq.gen_val(Value::symbol(key));
+                            let delete = IdentId::get_id("delete");
+                            self.emit_opt_send(globals, iseq, delete, 1, None, false, loc);
+                        }
+                        self.emit_set_local(iseq, id);
+                    }
+                }
+                gen_pattern_result(iseq, fail);
+            }
+            _ => self.gen_value_pattern(globals, iseq, pattern, loc)?,
+        };
+        Ok(())
+    }
+
+    /// Check `pattern === target`.
+    fn gen_value_pattern(
+        &mut self,
+        globals: &mut Globals,
+        iseq: &mut ISeq,
+        pattern: Node,
+        loc: Loc,
+    ) -> Result<(), RubyError> {
+        self.gen(globals, iseq, pattern, true)?;
+        iseq.gen_sinkn(1);
+        iseq.push(Inst::TEQ);
+        self.save_loc(iseq, loc);
+        Ok(())
+    }
+
+    /// Check the constant of the pattern and the existence of `method` on the target.
+    fn gen_check_deconstruct(
+        &mut self,
+        globals: &mut Globals,
+        iseq: &mut ISeq,
+        const_: Option<Box<Node>>,
+        method: IdentId,
+        fail: &mut Vec<ISeqPos>,
+        loc: Loc,
+    ) -> Result<(), RubyError> {
+        if let Some(box const_) = const_ {
+            iseq.gen_dup(1);
+            self.gen_value_pattern(globals, iseq, const_, loc)?;
+            fail.push(iseq.gen_jmp_if_f());
+        }
+        iseq.gen_dup(1);
+        iseq.push(Inst::CHECK_METHOD);
+        iseq.push32(method.into());
+        fail.push(iseq.gen_jmp_if_t());
+        Ok(())
+    }
+
+    fn gen_array_elem_pattern(
+        &mut self,
+        globals: &mut Globals,
+        iseq: &mut ISeq,
+        index: i64,
+        pattern: Node,
+        fail: &mut Vec<ISeqPos>,
+    ) -> Result<(), RubyError> {
+        let loc = pattern.loc();
+        iseq.gen_dup(1);
+        iseq.gen_integer(globals, index);
+        self.emit_get_array_elem(iseq, loc);
+        self.gen_pattern(globals, iseq, pattern)?;
+        fail.push(iseq.gen_jmp_if_f());
+        Ok(())
+    }
+
+    fn gen_hash_size_check(
+        &mut self,
+        globals: &mut Globals,
+        iseq: &mut ISeq,
+        len: usize,
+        fail: &mut Vec<ISeqPos>,
+        loc: Loc,
+    ) {
+        iseq.gen_dup(1);
+        self.emit_opt_send(globals, iseq, IdentId::get_id("size"), 0, None, true, loc);
+        iseq.gen_integer(globals, len as i64);
+        iseq.push(Inst::EQ);
+        fail.push(iseq.gen_jmp_if_f());
+    }
+
+    /// Raise NoMatchingPatternError for the value on the top of the stack.
+    /// If `keys` is given and the value lacks any of them, NoMatchingPatternKeyError is raised.
+    fn emit_no_match(
+        &mut self,
+        globals: &mut Globals,
+        iseq: &mut ISeq,
+        keys: Option<Vec<IdentId>>,
+        loc: Loc,
+    ) {
+        let keys = match keys {
+            Some(keys) => Value::array_from(keys.into_iter().map(Value::symbol).collect()),
+            None => Value::nil(),
+        };
+        let id = globals.const_values.insert(keys);
+        iseq.push(Inst::NO_MATCH);
+        iseq.push32(id as u32);
+        self.save_loc(iseq, loc);
+    }
+}
+
+/// Generate the tail of a pattern.
+/// Both of the matched path (fall through) and the failed paths (`fail`) have one item on the stack,
+/// which is replaced with the result.
+fn gen_pattern_result(iseq: &mut ISeq, fail: Vec<ISeqPos>) {
+    iseq.gen_pop();
+    iseq.gen_val(Value::true_val());
+    let end = iseq.gen_jmp();
+    for dest in fail {
+        iseq.write_disp_from_cur(dest);
+    }
+    iseq.gen_pop();
+    iseq.gen_val(Value::false_val());
+    iseq.write_disp_from_cur(end);
+}
+
+/// Keys of the top-level hash pattern which are required to exist.
+fn pattern_keys(pattern: &Node) -> Option<Vec<IdentId>> {
+    match &pattern.kind {
+        NodeKind::HashPattern { pairs, .. } if !pairs.is_empty() => {
+            Some(pairs.iter().map(|(key, _)| *key).collect())
+        }
+        _ => None,
+    }
+}
@@ -25,6 +25,7 @@ pub enum FiberState {
 pub enum FiberKind {
     Fiber(HeapCtxRef),
     Enum(Box<EnumInfo>),
+    Thread(Box<ThreadStart>),
 }
 
 impl GC<RValue> for FiberKind {
@@ -32,6 +33,7 @@ impl GC<RValue> for FiberKind {
         match self {
             FiberKind::Fiber(context) => context.mark(alloc),
             FiberKind::Enum(info) => info.mark(alloc),
+            FiberKind::Thread(start) => start.mark(alloc),
         }
     }
 }
@@ -50,6 +52,20 @@ impl GC<RValue> for EnumInfo {
     }
 }
 
+/// A block and its arguments to be executed in a new Thread.
+#[derive(Clone, Debug)]
+pub struct ThreadStart {
+    pub block: Value,
+    pub args: Vec<Value>,
+}
+
+impl GC<RValue> for ThreadStart {
+    fn mark(&self, alloc: &mut Allocator<RValue>) {
+        self.block.mark(alloc);
+        self.args.iter().for_each(|v| v.mark(alloc));
+    }
+}
+
 #[derive(Debug)]
 #[repr(C)]
 pub struct FiberContext {
@@ -91,6 +107,18 @@ impl FiberHandle {
         unsafe { &(*self.0).kind }
     }
 
+    /// Returns true if this is the root fiber of a Thread.
+    pub(crate) fn is_thread(&self) -> bool {
+        matches!(self.kind(), FiberKind::Thread(_))
+    }
+
+    /// Give back control to the thread scheduler which resumed the current thread.
+    pub(crate) fn thread_yield(vm: &mut VM) {
+        let handle = vm.handle.unwrap();
+        asm::yield_context(handle.0);
+        vm.stack_pop();
+    }
+
     /// Yield args to parent fiber. (execute Fiber.yield)
     pub(crate) fn fiber_yield(vm: &mut VM, args: &Args2) -> VMResult {
         let val = match args.len() {
@@ -100,6 +128,9 @@ impl FiberHandle {
         };
         match vm.handle {
             None => Err(RubyError::fiber("Can not yield from main fiber.")),
+            Some(handle) if handle.is_thread() => {
+                Err(RubyError::fiber("Can not yield from root fiber."))
+            }
             Some(handle) => {
                 #[cfg(feature = "perf")]
                 vm.globals.perf.get_perf(Perf::INVALID);
@@ -161,6 +192,11 @@ impl FiberContext {
         let vmref = VMRef::new(vm);
         FiberContext::new(vmref, FiberKind::Enum(Box::new(info)))
     }
+
+    pub(crate) fn new_thread(vm: VM, start: ThreadStart) -> Self {
+        let vmref = VMRef::new(vm);
+        FiberContext::new(vmref, FiberKind::Thread(Box::new(start)))
+    }
 }
 
 impl FiberContext {
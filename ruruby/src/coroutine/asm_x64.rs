@@ -1,24 +1,24 @@
 use super::FiberContext;
-use std::arch::asm;
+use std::arch::naked_asm;
 
 pub const OFFSET: isize = 48;
 
 #[cfg(not(tarpaulin_include))]
-#[naked]
+#[unsafe(naked)]
 pub(super) extern "C" fn skip() {
-    unsafe {
+    {
         // rdi <- *mut FiberContext
-        asm!("mov rdi, [rsp+8]", "ret", options(noreturn));
+        naked_asm!("mov rdi, [rsp+8]", "ret");
     };
 }
 
 /// This function is called when the child fiber is resumed at first.
 #[cfg(not(tarpaulin_include))]
-#[naked]
+#[unsafe(naked)]
 pub(super) extern "C" fn invoke_context(_fiber: *mut FiberContext) {
     // rdi <- _fiber
-    unsafe {
-        asm!(
+    {
+        naked_asm!(
             "push r15",
             "push r14",
             "push r13",
@@ -34,18 +34,18 @@ pub(super) extern "C" fn invoke_context(_fiber: *mut FiberContext) {
             "pop  r14",
             "pop  r15",
             "ret", // new_context(&mut Fiber)
-            options(noreturn)
+
         );
     }
 }
 
 /// This function is called when the child fiber is resumed.
 #[cfg(not(tarpaulin_include))]
-#[naked]
+#[unsafe(naked)]
 pub(super) extern "C" fn switch_context(_fiber: *mut FiberContext) {
     // rdi <- _fiber
-    unsafe {
-        asm!(
+    {
+        naked_asm!(
             "push r15",
             "push r14",
             "push r13",
@@ -60,19 +60,18 @@ pub(super) extern "C" fn switch_context(_fiber: *mut FiberContext) {
             "pop  r13",
             "pop  r14",
             "pop  r15",
-            "ret",
-            options(noreturn)
+            "ret"
         );
     }
 }
 
 /// This function is called when the child fiber yielded.
 #[cfg(not(tarpaulin_include))]
-#[naked]
+#[unsafe(naked)]
 pub(super) extern "C" fn yield_context(_fiber: *mut FiberContext) {
     // rdi <- _fiber
-    unsafe {
-        asm!(
+    {
+        naked_asm!(
             "push r15",
             "push r14",
             "push r13",
@@ -88,8 +87,7 @@ pub(super) extern "C" fn yield_context(_fiber: *mut FiberContext) {
             "pop  r14",
             "pop  r15",
             //"lea  rax, [rdi + 16]", // rax <- &f.result
-            "ret",
-            options(noreturn)
+            "ret"
         );
     }
 }
@@ -101,6 +101,10 @@ extern "C" fn new_context(handle: FiberHandle) {
             fiber_vm.run_loop()
         }
         FiberKind::Enum(info) => fiber_vm.enumerator_fiber(info.receiver, &info.args, info.method),
+        FiberKind::Thread(start) => {
+            fiber_vm.stack_pop();
+            fiber_vm.eval_block(&Block::Proc(start.block), &start.args)
+        }
     };
     #[cfg(feature = "trace")]
     eprintln!("<=== yield {:?} and terminate fiber.", res);
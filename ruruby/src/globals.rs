@@ -3,8 +3,11 @@ use fancy_regex::Regex;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
 mod constants;
+mod ivar_cache;
 mod method;
 use constants::*;
+pub use ivar_cache::IvarCacheEntry;
+use ivar_cache::*;
 pub use method::*;
 #[cfg(feature = "perf-method")]
 mod method_perf;
@@ -15,6 +18,7 @@ pub struct Globals {
     pub const_values: ConstantValues,
     global_var: ValueTable,
     const_cache: ConstCache,
+    ivar_cache: IvarCache,
     pub case_dispatch: CaseDispatchMap,
     pub case_dispatch2: CaseDispatchMap2,
 
@@ -24,6 +28,10 @@ pub struct Globals {
     pub main_object: Value,
     pub regexp_cache: FxHashMap<String, Rc<Regex>>,
     source_files: Vec<PathBuf>,
+    /// Directory of the ISeq cache. None if disabled.
+    pub iseq_cache_dir: Option<PathBuf>,
+    /// Run the bytecode optimizer on generated ISeqs.
+    pub iseq_opt: bool,
     #[cfg(feature = "perf")]
     pub perf: Perf,
     pub startup_flag: bool,
@@ -32,6 +40,12 @@ pub struct Globals {
     pub fiber_result: VMResult,
     pub methods: MethodRepo,
     pub classes: Classes,
+    /// Finalizers registered by ObjectSpace.define_finalizer. (object id => procs)
+    pub finalizers: FxIndexMap<u64, Vec<Value>>,
+    /// GC count when finalizers were checked last time.
+    pub finalizer_checked: usize,
+    /// Scheduler for green threads.
+    pub scheduler: Scheduler,
 }
 
 pub type GlobalsRef = Ref<Globals>;
@@ -39,6 +53,7 @@ pub type GlobalsRef = Ref<Globals>;
 impl GC<RValue> for Globals {
     fn mark(&self, alloc: &mut Allocator<RValue>) {
         self.const_values.mark(alloc);
+        self.methods.mark(alloc);
         self.main_object.mark(alloc);
         self.global_var.values().for_each(|v| v.mark(alloc));
         for t in &self.case_dispatch.table {
@@ -48,6 +63,10 @@ impl GC<RValue> for Globals {
             vm.mark(alloc);
         }
         self.val.mark(alloc);
+        self.scheduler.mark(alloc);
+        self.finalizers
+            .values()
+            .for_each(|procs| procs.iter().for_each(|v| v.mark(alloc)));
     }
 }
 
@@ -67,8 +86,9 @@ impl Globals {
         let main_object = Value::ordinary_object(object);
         let mut globals = Globals {
             const_values: ConstantValues::new(),
-            global_var: FxHashMap::default(),
+            global_var: FxIndexMap::default(),
             const_cache: ConstCache::new(),
+            ivar_cache: IvarCache::new(),
             main_fiber: None,
             instant: std::time::Instant::now(),
             const_version: 0,
@@ -77,6 +97,8 @@ impl Globals {
             case_dispatch2: CaseDispatchMap2::new(),
             regexp_cache: FxHashMap::default(),
             source_files: vec![],
+            iseq_cache_dir: None,
+            iseq_opt: true,
             #[cfg(feature = "perf")]
             perf: Perf::new(),
             startup_flag: false,
@@ -84,6 +106,9 @@ impl Globals {
             fiber_result: Ok(Value::nil()),
             methods: MethodRepo::new(),
             classes: Classes::dummy(),
+            finalizers: FxIndexMap::default(),
+            finalizer_checked: 0,
+            scheduler: Scheduler::new(),
         };
 
         BuiltinClass::initialize(&mut globals);
@@ -215,7 +240,7 @@ impl Globals {
     pub(crate) fn from_exception(&self, err: &RubyError) -> Option<Value> {
         let err = err.clone();
         let val = match &err.kind {
-            RubyErrorKind::Exception => return None,
+            RubyErrorKind::Exception | RubyErrorKind::Throw => return None,
             RubyErrorKind::ParseErr(_) => {
                 let err_class = self.get_toplevel_constant("SyntaxError").into_module();
                 Value::exception(err_class, err)
@@ -263,6 +288,10 @@ impl Globals {
                     let err_class = self.get_toplevel_constant("IndexError").into_module();
                     Value::exception(err_class, err)
                 }
+                RuntimeErrKind::Key => {
+                    let err_class = self.get_toplevel_constant("KeyError").into_module();
+                    Value::exception(err_class, err)
+                }
                 RuntimeErrKind::Regexp => {
                     let err_class = self.get_toplevel_constant("RegexpError").into_module();
                     Value::exception(err_class, err)
@@ -275,6 +304,65 @@ impl Globals {
                     let err_class = self.get_toplevel_constant("LocalJumpError").into_module();
                     Value::exception(err_class, err)
                 }
+                RuntimeErrKind::NoMatchingPattern => {
+                    let err_class = self
+                        .get_toplevel_constant("NoMatchingPatternError")
+                        .into_module();
+                    Value::exception(err_class, err)
+                }
+                RuntimeErrKind::NoMatchingPatternKey => {
+                    let err_class = self
+                        .get_toplevel_constant("NoMatchingPatternKeyError")
+                        .into_module();
+                    Value::exception(err_class, err)
+                }
+                RuntimeErrKind::Thread => {
+                    let err_class = self.get_toplevel_constant("ThreadError").into_module();
+                    Value::exception(err_class, err)
+                }
+                RuntimeErrKind::ClosedQueue => {
+                    let err_class = self.get_toplevel_constant("ClosedQueueError").into_module();
+                    Value::exception(err_class, err)
+                }
+                RuntimeErrKind::Frozen => {
+                    let err_class = self.get_toplevel_constant("FrozenError").into_module();
+                    Value::exception(err_class, err)
+                }
+                RuntimeErrKind::Fatal => {
+                    let err_class = self
+                        .get_toplevel_constant("Exception")
+                        .into_module()
+                        .get_const_noautoload(IdentId::get_id("fatal"))
+                        .unwrap()
+                        .into_module();
+                    Value::exception(err_class, err)
+                }
+                RuntimeErrKind::IO => {
+                    let err_class = self.get_toplevel_constant("IOError").into_module();
+                    Value::exception(err_class, err)
+                }
+                RuntimeErrKind::EOF => {
+                    let err_class = self.get_toplevel_constant("EOFError").into_module();
+                    Value::exception(err_class, err)
+                }
+                RuntimeErrKind::Errno(name) => {
+                    let err_class = self
+                        .get_toplevel_constant("Errno")
+                        .into_module()
+                        .get_const_noautoload(IdentId::get_id(name))
+                        .unwrap()
+                        .into_module();
+                    Value::exception(err_class, err)
+                }
+                RuntimeErrKind::Encoding(name) => {
+                    let err_class = self
+                        .get_toplevel_constant("Encoding")
+                        .into_module()
+                        .get_const_noautoload(IdentId::get_id(name))
+                        .unwrap()
+                        .into_module();
+                    Value::exception(err_class, err)
+                }
                 RuntimeErrKind::DomainError => {
                     let math = self.get_toplevel_constant("Math");
                     let err_class = math
@@ -293,6 +381,7 @@ impl Globals {
                 let err_class = self.get_toplevel_constant("SystemExit").into_module();
                 Value::exception(err_class, err)
             }
+            RubyErrorKind::ThreadKill => Value::exception(BuiltinClass::exception(), err),
             _ => {
                 let standard = BuiltinClass::standard();
                 Value::exception(standard, err)
@@ -316,7 +405,7 @@ impl Globals {
         ALLOC.with(|m| m.borrow_mut().print_mark());
     }
 
-    #[cfg(any(feature = "emit-iseq", feature = "trace"))]
+    /// Disassemble an instruction at `pc` of `iseq_ref`.
     pub(crate) fn inst_info(&self, iseq_ref: ISeqRef, pc: ISeqPos) -> String {
         fn imm_i32(iseq: &ISeq, pc: ISeqPos) -> String {
             format!(
@@ -370,7 +459,7 @@ impl Globals {
                 "OPT_CASE {:>05}",
                 (pc + 13 + iseq.read_disp(pc + 9)).into_usize(),
             ),
-            Inst::SET_LOCAL | Inst::GET_LOCAL => {
+            Inst::SET_LOCAL | Inst::GET_LOCAL | Inst::TEE_LOCAL => {
                 let id = iseq.read32(pc + 1);
                 let ident_name = iseq_ref.lvar.get_name(id.into());
                 format!("{} '{}'", Inst::inst_name(iseq[pc]), ident_name,)
@@ -459,6 +548,10 @@ impl Globals {
                 let id = iseq.read32(pc + 1);
                 format!("CONST_VAL {:?}", self.const_values.get(id as usize))
             }
+            Inst::NO_MATCH => {
+                let id = iseq.read32(pc + 1);
+                format!("NO_MATCH {:?}", self.const_values.get(id as usize))
+            }
             Inst::DEF_CLASS => format!(
                 "DEF_CLASS {} '{}' method:{}",
                 if iseq.read8(pc + 1) == 1 {
@@ -539,8 +632,16 @@ impl ConstantValues {
         id
     }
 
+    /// Get the constant value `id`.
+    ///
+    /// Frozen objects (e.g. string literals with `# frozen_string_literal: true`) are shared,
+    /// and others are duplicated.
     pub(crate) fn get(&self, id: usize) -> Value {
-        self.table[id].shallow_dup()
+        let val = self.table[id];
+        match val.as_rvalue() {
+            Some(rv) if rv.is_frozen() => val,
+            _ => val.shallow_dup(),
+        }
     }
 
     #[cfg(not(tarpaulin_include))]
@@ -0,0 +1,45 @@
+use crate::*;
+
+///
+///  Inline instance variable cache
+///
+///  This module supports inline caches of GET_IVAR/SET_IVAR which are embedded in the instruction
+///  sequence directly. Each entry is keyed by the shape of the receiver.
+///  Since shapes never change, entries need not to be invalidated.
+///
+#[derive(Debug, Clone)]
+pub(super) struct IvarCache {
+    table: Vec<Option<IvarCacheEntry>>,
+}
+
+impl IvarCache {
+    pub(super) fn new() -> Self {
+        IvarCache { table: vec![] }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IvarCacheEntry {
+    /// Shape of the receiver.
+    pub shape: ShapeId,
+    /// Shape of the receiver after SET_IVAR. Differs from `shape` if a new instance variable is added.
+    pub new_shape: ShapeId,
+    /// Slot index of the instance variable.
+    pub slot: usize,
+}
+
+impl Globals {
+    pub(crate) fn add_ivar_cache_entry(&mut self) -> u32 {
+        self.ivar_cache.table.push(None);
+        self.ivar_cache.table.len() as u32 - 1
+    }
+
+    #[inline(always)]
+    pub(crate) fn find_ivar_cache(&self, id: u32) -> Option<IvarCacheEntry> {
+        self.ivar_cache.table[id as usize]
+    }
+
+    pub(crate) fn set_ivar_cache(&mut self, id: u32, entry: IvarCacheEntry) {
+        self.ivar_cache.table[id as usize] = Some(entry);
+    }
+}
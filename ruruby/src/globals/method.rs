@@ -40,12 +40,20 @@ impl MethodRepo {
                     name: IdentId::_ENUM_FUNC,
                     class: IdentId::get_id("Enumerator"),
                 }, // METHOD_ENUM
+                MethodInfo::Void, // METHOD_UNDEF
+                MethodInfo::BuiltinFunc {
+                    func: enumerable::each_block,
+                    name: IdentId::EACH,
+                    class: IdentId::get_id("Enumerable"),
+                }, // METHOD_ENUMERABLE
             ],
             #[cfg(feature = "perf-method")]
             counter: vec![
                 MethodRepoCounter::default(),
                 MethodRepoCounter::default(),
                 MethodRepoCounter::default(),
+                MethodRepoCounter::default(),
+                MethodRepoCounter::default(),
             ],
             class_version: 0,
             i_cache: InlineCache::new(),
@@ -71,6 +79,12 @@ impl MethodRepo {
         self.class_version += 1;
     }
 
+    #[cfg(feature = "jit")]
+    #[inline(always)]
+    pub(crate) fn class_version(&self) -> u32 {
+        self.class_version
+    }
+
     #[inline(always)]
     pub(crate) fn add_inline_cache_entry(&mut self) -> u32 {
         self.i_cache.add_entry()
@@ -95,6 +109,17 @@ impl MethodRepo {
         Some(fid)
     }
 
+    /// Get the class and the method cached in the inline cache `id`, if the entry is not outdated.
+    #[cfg(feature = "jit")]
+    pub(crate) fn inline_cache_entry(&self, id: u32) -> Option<(Module, FnId)> {
+        let entry = &self.i_cache.table[id as usize];
+        if entry.version == self.class_version && entry.fid != FnId::default() {
+            Some((entry.class, entry.fid))
+        } else {
+            None
+        }
+    }
+
     /// Get corresponding instance method(MethodId) for the class object `class` and `method`.
     ///
     /// If an entry for `class` and `method` exists in global method cache and the entry is not outdated,
@@ -122,6 +147,12 @@ impl MethodRepo {
     }
 }
 
+impl GC<RValue> for MethodRepo {
+    fn mark(&self, alloc: &mut Allocator<RValue>) {
+        self.table.iter().for_each(|info| info.mark(alloc));
+    }
+}
+
 #[cfg(feature = "perf-method")]
 impl MethodRepo {
     pub(crate) fn inc_counter(&mut self, id: FnId) {
@@ -185,6 +216,11 @@ pub type BuiltinFunc = fn(vm: &mut VM, self_val: Value, args: &Args2) -> VMResul
 pub type MethodTable = FxIndexMap<IdentId, FnId>;
 
 pub static METHOD_ENUM: FnId = FnId::new_unchecked(2);
+/// A tombstone entry in method tables which was registered by `undef` or `Module#undef_method`.
+/// Method search stops at this entry.
+pub static METHOD_UNDEF: FnId = FnId::new_unchecked(3);
+/// The block which Enumerable methods pass to `each` of the receiver.
+pub static METHOD_ENUMERABLE: FnId = FnId::new_unchecked(4);
 
 #[derive(Clone)]
 pub enum MethodInfo {
@@ -202,6 +238,10 @@ pub enum MethodInfo {
         func: BuiltinFunc,
         class: IdentId,
     },
+    /// A method defined by `Module#define_method`. `proc` holds a Proc object with lambda semantics.
+    ProcFunc {
+        proc: Value,
+    },
     Void,
 }
 
@@ -209,6 +249,7 @@ impl GC<RValue> for MethodInfo {
     fn mark(&self, alloc: &mut Allocator<RValue>) {
         match self {
             MethodInfo::RubyFunc { iseq } => iseq.class_defined.iter().for_each(|c| c.mark(alloc)),
+            MethodInfo::ProcFunc { proc } => proc.mark(alloc),
             _ => {}
         };
     }
@@ -223,6 +264,7 @@ impl std::fmt::Debug for MethodInfo {
             MethodInfo::BuiltinFunc { name, class, .. } => {
                 write!(f, r##"BuiltinFunc {:?}#{:?}"##, class, name)
             }
+            MethodInfo::ProcFunc { proc } => write!(f, "ProcFunc {:?}", proc),
             MethodInfo::Void => write!(f, "Void"),
         }
     }
@@ -455,6 +497,8 @@ pub struct ISeqInfo {
     pub source_info: SourceInfoRef,
     pub kind: ISeqKind,
     pub loc: Loc,
+    #[cfg(feature = "jit")]
+    pub jit: JitInfo,
 }
 
 impl std::fmt::Debug for ISeqInfo {
@@ -505,6 +549,8 @@ impl ISeqInfo {
             source_info,
             kind,
             loc,
+            #[cfg(feature = "jit")]
+            jit: JitInfo::default(),
         }
     }
 
@@ -540,6 +586,8 @@ impl ISeqInfo {
             source_info,
             kind: ISeqKind::Method(None),
             loc: Loc(0, 0),
+            #[cfg(feature = "jit")]
+            jit: JitInfo::default(),
         }
     }
 
@@ -561,6 +609,22 @@ impl ISeqInfo {
         }
     }
 
+    /// Get the source location of `pc`.
+    ///
+    /// If `pc` is not found in the sourcemap, returns the nearest preceding location.
+    pub fn find_loc(&self, pc: ISeqPos) -> Option<Loc> {
+        match self.iseq_sourcemap.iter().find(|x| x.0 == pc) {
+            Some((_, loc)) => Some(*loc),
+            None => self
+                .iseq_sourcemap
+                .iter()
+                .filter(|x| x.0 <= pc)
+                .max_by_key(|x| x.0.into_usize())
+                .or_else(|| self.iseq_sourcemap.first())
+                .map(|(_, loc)| *loc),
+        }
+    }
+
     pub fn get_loc(&self, pc: ISeqPos) -> Loc {
         match self.iseq_sourcemap.iter().find(|x| x.0 == pc) {
             Some((_, loc)) => *loc,
@@ -24,10 +24,13 @@ pub mod tests;
 mod value;
 mod vm;
 pub use crate::alloc::*;
+use crate::builtin::encoding::*;
 use crate::builtin::enumerator::*;
+pub use crate::builtin::io::IoInfo;
 pub use crate::builtin::procobj::*;
 pub use crate::builtin::range::*;
 pub use crate::builtin::regexp::*;
+pub use crate::builtin::thread::*;
 pub use crate::builtin::time::*;
 pub use crate::builtin::*;
 pub use crate::codegen::Codegen;
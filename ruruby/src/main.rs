@@ -19,26 +19,43 @@ struct Cli {
     #[clap(short)]
     verbose: bool,
 
+    /// disable the ISeq cache of required files
+    #[clap(long)]
+    disable_iseq_cache: bool,
+
+    /// disable the bytecode optimizer
+    #[clap(long)]
+    disable_iseq_opt: bool,
+
     /// program file and arguments
     args: Vec<String>,
 }
 
+fn new_vm(cli: &Cli) -> VMRef {
+    let mut vm = VM::new();
+    if !cli.disable_iseq_cache {
+        vm.globals.iseq_cache_dir = iseq_cache::default_dir();
+    }
+    vm.globals.iseq_opt = !cli.disable_iseq_opt;
+    vm
+}
+
 #[cfg(not(tarpaulin_include))]
 fn main() {
     let cli = Cli::parse();
     if cli.verbose {
         println!("{} {}", crate_name!(), crate_version!());
     }
-    match cli.exec {
+    match &cli.exec {
         Some(command) => {
-            let mut vm = VM::new();
-            vm.set_global_var(IdentId::get_id("$0"), Value::string("-e"));
-            execute(&mut vm, std::path::PathBuf::default(), command);
+            let mut vm = new_vm(&cli);
+            vm.globals.set_global_var_by_str("$0", Value::string("-e"));
+            execute(&mut vm, std::path::PathBuf::default(), command.clone());
             return;
         }
         None => {}
     }
-    let mut vm = VM::new();
+    let mut vm = new_vm(&cli);
 
     let file = if cli.args.is_empty() {
         let argv = Value::array_from(vec![]);
@@ -8,6 +8,10 @@ mod array;
 pub use array::*;
 mod hash;
 pub use hash::*;
+mod set;
+pub use set::*;
+mod shape;
+pub use shape::*;
 pub mod real;
 pub use real::*;
 pub mod string;
@@ -193,6 +193,7 @@ impl Module {
         let mut singleton_flag = self.is_singleton();
         loop {
             match class.get_instance_method(method) {
+                Some(method) if method == METHOD_UNDEF => return None,
                 Some(method) => {
                     return Some(DefinedMethod::new(method, class));
                 }
@@ -213,6 +214,7 @@ impl Module {
 
     pub(crate) fn search_method_no_inherit(&self, method: IdentId) -> Option<FnId> {
         self.get_instance_method(method)
+            .filter(|fid| *fid != METHOD_UNDEF)
     }
 
     /// Find method `id` from method tables of `self` class and all of its superclasses including their included modules.
@@ -533,8 +535,11 @@ impl ClassInfo {
     }
 
     #[inline(always)]
-    pub(crate) fn method_names(&self) -> indexmap::map::Keys<'_, IdentId, FnId> {
-        self.ext.method_table.keys()
+    pub(crate) fn method_names(&self) -> impl Iterator<Item = &IdentId> {
+        self.ext
+            .method_table
+            .iter()
+            .filter_map(|(k, v)| if *v == METHOD_UNDEF { None } else { Some(k) })
     }
 
     #[inline(always)]
@@ -571,6 +576,18 @@ impl ClassInfo {
         self.ext.add_method(globals, name, method_id)
     }
 
+    /// Undefine the method `name` of `self` by adding a tombstone entry to the method table.
+    pub(crate) fn undef_method(&mut self, globals: &mut Globals, name: IdentId) {
+        self.ext.add_method(globals, name, METHOD_UNDEF);
+    }
+
+    /// Remove the method `name` from the method table of `self`.
+    ///
+    /// Return None if `self` does not have the method.
+    pub(crate) fn remove_method(&mut self, globals: &mut Globals, name: IdentId) -> Option<FnId> {
+        self.ext.remove_method(globals, name)
+    }
+
     /// Set a constant (`self`::`id`) to `val`.
     ///
     /// If `val` is a module or class object, set the name of `val` to the name of the constant.
@@ -722,6 +739,16 @@ impl ClassExt {
         self.method_table.insert(id, info)
     }
 
+    fn remove_method(&mut self, globals: &mut Globals, id: IdentId) -> Option<FnId> {
+        match self.method_table.get(&id) {
+            Some(fid) if *fid != METHOD_UNDEF => {
+                globals.methods.inc_class_version();
+                self.method_table.shift_remove(&id)
+            }
+            _ => None,
+        }
+    }
+
     fn insert_const(&mut self, id: IdentId, val: Value) -> Option<ConstEntry> {
         self.const_table.insert(id, ConstEntry::Value(val))
     }
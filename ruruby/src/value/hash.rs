@@ -70,6 +70,7 @@ impl Hash for HashKey {
                 ObjKind::ARRAY => lhs.array().hash(state),
                 ObjKind::RANGE => lhs.range().hash(state),
                 ObjKind::HASH => lhs.rhash().hash(state),
+                ObjKind::SET => lhs.set().hash(state),
                 ObjKind::METHOD => lhs.method().hash(state),
                 _ => self.0.hash(state),
             },
@@ -257,8 +258,8 @@ impl HashInfo {
 
     pub(crate) fn remove(&mut self, k: Value) -> Option<Value> {
         match self {
-            HashInfo::Map(map) => map.remove(&HashKey(k)),
-            HashInfo::IdentMap(map) => map.remove(&IdentKey(k)),
+            HashInfo::Map(map) => map.shift_remove(&HashKey(k)),
+            HashInfo::IdentMap(map) => map.shift_remove(&IdentKey(k)),
         }
     }
 
@@ -1,5 +1,5 @@
 use crate::*;
-use num::{BigInt, FromPrimitive, Integer, Signed, ToPrimitive, Zero};
+use num::{BigInt, BigRational, FromPrimitive, Integer, Signed, ToPrimitive, Zero};
 
 /// This module represents real values and their basic calculations.
 #[derive(Clone)]
@@ -7,6 +7,7 @@ pub enum Real {
     Bignum(BigInt),
     Integer(i64),
     Float(f64),
+    Rational(BigRational),
 }
 
 impl Real {
@@ -15,6 +16,7 @@ impl Real {
             Real::Bignum(n) => Value::bignum(n),
             Real::Integer(i) => Value::integer(i),
             Real::Float(f) => Value::float(f),
+            Real::Rational(r) => Value::rational(r),
         }
     }
 
@@ -30,6 +32,7 @@ impl Real {
             Real::Bignum(n) => !n.is_positive(),
             Real::Integer(i) => i.is_negative(),
             Real::Float(f) => f.is_sign_negative(),
+            Real::Rational(r) => r.is_negative(),
         }
     }
     pub(crate) fn is_zero(&self) -> bool {
@@ -37,6 +40,7 @@ impl Real {
             Real::Bignum(n) => n.is_zero(),
             Real::Integer(i) => i.is_zero(),
             Real::Float(f) => f.is_zero(),
+            Real::Rational(r) => r.is_zero(),
         }
     }
 
@@ -45,6 +49,17 @@ impl Real {
             Real::Bignum(n) => n.to_f64().unwrap(),
             Real::Integer(i) => *i as f64,
             Real::Float(f) => *f,
+            Real::Rational(r) => r.to_f64().unwrap(),
+        }
+    }
+
+    /// Convert `self` to an exact rational. Floats are converted without rounding.
+    pub(crate) fn into_rational(self) -> BigRational {
+        match self {
+            Real::Bignum(n) => BigRational::from_integer(n),
+            Real::Integer(i) => BigRational::from_integer(BigInt::from(i)),
+            Real::Float(f) => BigRational::from_float(f).unwrap_or_default(),
+            Real::Rational(r) => r,
         }
     }
 
@@ -83,6 +98,7 @@ impl Debug for Real {
             Real::Bignum(n) => write!(f, "{}", *n),
             Real::Integer(num) => write!(f, "{}", *num),
             Real::Float(num) => write!(f, "{}", *num),
+            Real::Rational(r) => write!(f, "({}/{})", r.numer(), r.denom()),
         }
     }
 }
@@ -93,6 +109,14 @@ macro_rules! impl_ops {
             type Output = Real;
             fn $fname(self, other: Real) -> Real {
                 match (self, other) {
+                    (Real::Rational(r1), Real::Float(f2)) => {
+                        Real::Float(r1.to_f64().unwrap().$fname(f2))
+                    }
+                    (Real::Float(f1), Real::Rational(r2)) => {
+                        Real::Float(f1.$fname(r2.to_f64().unwrap()))
+                    }
+                    (Real::Rational(r1), rhs) => Real::Rational(r1.$fname(rhs.into_rational())),
+                    (lhs, Real::Rational(r2)) => Real::Rational(lhs.into_rational().$fname(r2)),
                     (Real::Bignum(n1), Real::Bignum(n2)) => Real::integer(n1.$fname(n2)),
                     (Real::Bignum(n1), Real::Integer(i2)) => Real::integer(n1.$fname(i2)),
                     (Real::Bignum(n1), Real::Float(f2)) => {
@@ -127,6 +151,10 @@ impl Div for Real {
     type Output = Real;
     fn div(self, other: Real) -> Real {
         match (self, other) {
+            (Real::Rational(r1), Real::Float(f2)) => Real::Float(r1.to_f64().unwrap() / f2),
+            (Real::Float(f1), Real::Rational(r2)) => Real::Float(f1 / r2.to_f64().unwrap()),
+            (Real::Rational(r1), rhs) => Real::Rational(r1 / rhs.into_rational()),
+            (lhs, Real::Rational(r2)) => Real::Rational(lhs.into_rational() / r2),
             (Real::Bignum(n1), Real::Bignum(n2)) => Real::integer(n1.div_floor(&n2)),
             (Real::Bignum(n1), Real::Integer(i2)) => Real::integer(n1.div_floor(&BigInt::from(i2))),
             (Real::Bignum(n1), Real::Float(f2)) => Real::Float(n1.to_f64().unwrap() / f2),
@@ -143,6 +171,10 @@ impl Div for Real {
 impl PartialEq for Real {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
+            (Real::Rational(r1), Real::Float(f2)) => r1.to_f64().unwrap() == *f2,
+            (Real::Float(f1), Real::Rational(r2)) => *f1 == r2.to_f64().unwrap(),
+            (Real::Rational(r1), rhs) => *r1 == rhs.clone().into_rational(),
+            (lhs, Real::Rational(r2)) => lhs.clone().into_rational() == *r2,
             (Real::Bignum(n1), Real::Bignum(n2)) => n1 == n2,
             (Real::Bignum(_), Real::Integer(_)) => false,
             (Real::Bignum(n1), Real::Float(f2)) => n1.to_f64().unwrap() == *f2,
@@ -163,6 +195,7 @@ impl Neg for Real {
             Real::Bignum(n) => Real::Bignum(-n),
             Real::Integer(i) => Real::Integer(-i),
             Real::Float(f) => Real::Float(-f),
+            Real::Rational(r) => Real::Rational(-r),
         }
     }
 }
@@ -173,6 +206,7 @@ impl PartialOrd for Real {
             Real::Bignum(n1) => arith::cmp_bignum(n1, other.clone().into_val()),
             Real::Integer(i1) => arith::cmp_fixnum(*i1, other.clone().into_val()),
             Real::Float(f1) => arith::cmp_float(*f1, other.clone().into_val()),
+            Real::Rational(r1) => arith::cmp_rational(r1, other.clone().into_val()),
         }
     }
 }
@@ -1,6 +1,6 @@
 use crate::coroutine::*;
 use crate::*;
-use num::BigInt;
+use num::{BigInt, BigRational};
 use std::borrow::Cow;
 use std::default;
 
@@ -8,7 +8,7 @@ use std::default;
 pub struct RValue {
     flags: RVFlag,
     class: Module,
-    var_table: Option<Box<ValueTable>>,
+    ivars: Option<Box<IvarTable>>,
     pub kind: ObjKind,
 }
 
@@ -25,6 +25,9 @@ impl default::Default for RVFlag {
     }
 }
 
+/// Frozen bit of `RVFlag`.
+const FROZEN: u64 = 0b10;
+
 impl RVFlag {
     #[inline(always)]
     fn new(kind: u8) -> Self {
@@ -32,6 +35,14 @@ impl RVFlag {
             flag: ((kind as u64) << 8) | 1,
         }
     }
+
+    /// Flags for a copy of the object. Copies are not frozen.
+    #[inline(always)]
+    fn dup(&self) -> Self {
+        RVFlag {
+            flag: unsafe { self.flag } & !FROZEN,
+        }
+    }
 }
 
 impl std::fmt::Debug for RValue {
@@ -43,9 +54,9 @@ impl std::fmt::Debug for RValue {
 
         write!(f, "RValue {{ ")?;
         write!(f, "class: {:?} ", self.class)?;
-        match &self.var_table {
-            None => write!(f, "var_table: None ")?,
-            Some(box t) => write!(f, "var_table: {:?} ", t)?,
+        match &self.ivars {
+            None => write!(f, "ivars: None ")?,
+            Some(box t) => write!(f, "ivars: {:?} ", t)?,
         }
         write!(
             f,
@@ -56,6 +67,7 @@ impl std::fmt::Debug for RValue {
                 ObjKind::BIGNUM => format!("Bignum {:?}", *self.bignum()),
                 ObjKind::FLOAT => format!("Float {}", self.float()),
                 ObjKind::COMPLEX => format!("{:?}", *self.complex()),
+                ObjKind::RATIONAL => format!("Rational {:?}", *self.rational()),
                 ObjKind::MODULE => format!("Module {:?}", *self.module()),
                 ObjKind::CLASS => format!("Class {:?}", *self.module()),
                 ObjKind::STRING => format!("String {:?}", *self.string()),
@@ -72,6 +84,9 @@ impl std::fmt::Debug for RValue {
                 ObjKind::EXCEPTION => format!("Exception {:?}", *self.exception()),
                 ObjKind::BINDING => format!("Binding {:?}", self.binding()),
                 ObjKind::UNBOUND_METHOD => format!("UnboundMethod {:?}", *self.method()),
+                ObjKind::THREAD => format!("Thread {:?}", *self.thread()),
+                ObjKind::IO => format!("IO {:?}", *self.io()),
+                ObjKind::SET => format!("Set {:?}", *self.set()),
                 k => panic!("invalid RValue kind. {}", k),
             }
         )?;
@@ -86,11 +101,20 @@ impl std::hash::Hash for RValue {
             ObjKind::BIGNUM => self.bignum().hash(state),
             ObjKind::FLOAT => self.float().to_bits().hash(state),
             ObjKind::COMPLEX => self.complex().hash(state),
+            ObjKind::RATIONAL => self.rational().hash(state),
             ObjKind::STRING => self.string().hash(state),
             ObjKind::ARRAY => self.array().hash(state),
             ObjKind::RANGE => self.range().hash(state),
             ObjKind::HASH => self.rhash().hash(state),
+            ObjKind::SET => self.set().hash(state),
             ObjKind::METHOD | ObjKind::UNBOUND_METHOD => self.method().hash(state),
+            ObjKind::ORDINARY => match builtin::structobj::struct_values(self) {
+                Some(values) => {
+                    self.real_class().id().hash(state);
+                    values.hash(state);
+                }
+                None => self.id().hash(state),
+            },
             _ => self.id().hash(state),
         }
     }
@@ -103,6 +127,7 @@ pub union ObjKind {
     pub bignum: ManuallyDrop<BigInt>,
     pub float: f64,
     pub complex: ManuallyDrop<RubyComplex>,
+    pub rational: ManuallyDrop<Box<BigRational>>,
     pub module: ManuallyDrop<ClassInfo>,
     pub string: ManuallyDrop<RString>,
     pub array: ManuallyDrop<ArrayInfo>,
@@ -117,6 +142,9 @@ pub union ObjKind {
     pub time: ManuallyDrop<TimeInfo>,
     pub exception: ManuallyDrop<Box<RubyError>>,
     pub binding: EnvFrame,
+    pub thread: ManuallyDrop<Box<ThreadInfo>>,
+    pub io: ManuallyDrop<Box<IoInfo>>,
+    pub set: ManuallyDrop<Box<SetInfo>>,
     pub other: (),
 }
 
@@ -142,6 +170,10 @@ impl ObjKind {
     pub const EXCEPTION: u8 = 17;
     pub const BINDING: u8 = 18;
     pub const UNBOUND_METHOD: u8 = 19;
+    pub const THREAD: u8 = 21;
+    pub const IO: u8 = 22;
+    pub const RATIONAL: u8 = 23;
+    pub const SET: u8 = 24;
 }
 
 #[derive(Debug, Clone, PartialEq, Hash)]
@@ -163,6 +195,13 @@ impl ObjKind {
         }
     }
 
+    #[inline(always)]
+    fn rational(r: BigRational) -> Self {
+        Self {
+            rational: ManuallyDrop::new(Box::new(r)),
+        }
+    }
+
     #[inline(always)]
     fn bignum(b: BigInt) -> Self {
         Self {
@@ -263,6 +302,27 @@ impl ObjKind {
             time: ManuallyDrop::new(info),
         }
     }
+
+    #[inline(always)]
+    fn thread(info: ThreadInfo) -> Self {
+        Self {
+            thread: ManuallyDrop::new(Box::new(info)),
+        }
+    }
+
+    #[inline(always)]
+    fn io(info: IoInfo) -> Self {
+        Self {
+            io: ManuallyDrop::new(Box::new(info)),
+        }
+    }
+
+    #[inline(always)]
+    fn set(info: SetInfo) -> Self {
+        Self {
+            set: ManuallyDrop::new(Box::new(info)),
+        }
+    }
 }
 
 impl RValue {
@@ -271,6 +331,11 @@ impl RValue {
         unsafe { &*self.kind.complex }
     }
 
+    #[inline(always)]
+    pub fn rational(&self) -> &BigRational {
+        unsafe { &*self.kind.rational }
+    }
+
     #[inline(always)]
     pub fn float(&self) -> f64 {
         unsafe { self.kind.float }
@@ -371,6 +436,11 @@ impl RValue {
         unsafe { &**self.kind.exception }
     }
 
+    #[inline(always)]
+    pub fn exception_mut(&mut self) -> &mut RubyError {
+        unsafe { &mut **self.kind.exception }
+    }
+
     #[inline(always)]
     pub fn binding(&self) -> EnvFrame {
         unsafe { self.kind.binding }
@@ -385,6 +455,36 @@ impl RValue {
     pub fn time_mut(&mut self) -> &mut TimeInfo {
         unsafe { &mut *self.kind.time }
     }
+
+    #[inline(always)]
+    pub fn thread(&self) -> &ThreadInfo {
+        unsafe { &self.kind.thread }
+    }
+
+    #[inline(always)]
+    pub fn thread_mut(&mut self) -> &mut ThreadInfo {
+        unsafe { &mut self.kind.thread }
+    }
+
+    #[inline(always)]
+    pub fn io(&self) -> &IoInfo {
+        unsafe { &self.kind.io }
+    }
+
+    #[inline(always)]
+    pub fn io_mut(&mut self) -> &mut IoInfo {
+        unsafe { &mut self.kind.io }
+    }
+
+    #[inline(always)]
+    pub fn set(&self) -> &SetInfo {
+        unsafe { &self.kind.set }
+    }
+
+    #[inline(always)]
+    pub fn set_mut(&mut self) -> &mut SetInfo {
+        unsafe { &mut self.kind.set }
+    }
 }
 
 impl RValue {
@@ -395,6 +495,16 @@ impl RValue {
         (flag >> 8) as u8
     }
 
+    #[inline(always)]
+    pub(crate) fn is_frozen(&self) -> bool {
+        unsafe { self.flags.flag & FROZEN != 0 }
+    }
+
+    #[inline(always)]
+    pub(crate) fn freeze(&mut self) {
+        unsafe { self.flags.flag |= FROZEN }
+    }
+
     #[inline(always)]
     pub fn kind_or_none(&self) -> Option<u8> {
         let flag = unsafe { self.flags.flag };
@@ -410,9 +520,24 @@ impl RValue {
     // This type of equality is used for comparison for keys of Hash.
     pub(crate) fn eql(&self, other: &Self) -> bool {
         match (self.kind(), other.kind()) {
-            (ObjKind::ORDINARY, ObjKind::ORDINARY) => self.id() == other.id(),
+            (ObjKind::ORDINARY, ObjKind::ORDINARY) => {
+                if self.id() == other.id() {
+                    return true;
+                }
+                if self.real_class().id() != other.real_class().id() {
+                    return false;
+                }
+                match (
+                    builtin::structobj::struct_values(self),
+                    builtin::structobj::struct_values(other),
+                ) {
+                    (Some(lhs), Some(rhs)) => lhs.iter().zip(rhs.iter()).all(|(l, r)| l.eql(r)),
+                    _ => false,
+                }
+            }
             (ObjKind::BIGNUM, ObjKind::BIGNUM) => *self.bignum() == *other.bignum(),
             (ObjKind::FLOAT, ObjKind::FLOAT) => self.float() == other.float(),
+            (ObjKind::RATIONAL, ObjKind::RATIONAL) => *self.rational() == *other.rational(),
             (ObjKind::COMPLEX, ObjKind::COMPLEX) => {
                 self.complex().r.eql(&other.complex().r) && self.complex().i.eql(&other.complex().i)
             }
@@ -436,6 +561,7 @@ impl RValue {
             }
             (ObjKind::RANGE, ObjKind::RANGE) => self.range().eql(&other.range()),
             (ObjKind::HASH, ObjKind::HASH) => *self.rhash() == *other.rhash(),
+            (ObjKind::SET, ObjKind::SET) => *self.set() == *other.set(),
             (ObjKind::METHOD, ObjKind::METHOD) => *self.method() == *other.method(),
             (ObjKind::UNBOUND_METHOD, ObjKind::UNBOUND_METHOD) => *self.method() == *other.method(),
             (ObjKind::INVALID, _) => panic!("Invalid rvalue. (maybe GC problem) {:?}", self),
@@ -451,9 +577,8 @@ impl GC<RValue> for RValue {
             return;
         }
         self.class.mark(alloc);
-        match &self.var_table {
-            Some(table) => table.values().for_each(|v| v.mark(alloc)),
-            None => {}
+        if let Some(table) = &self.ivars {
+            table.mark(alloc);
         }
         match self.kind() {
             ObjKind::INVALID => panic!(
@@ -463,10 +588,12 @@ impl GC<RValue> for RValue {
             ObjKind::ORDINARY
             | ObjKind::BIGNUM
             | ObjKind::FLOAT
+            | ObjKind::RATIONAL
             | ObjKind::STRING
             | ObjKind::REGEXP
             | ObjKind::TIME
-            | ObjKind::EXCEPTION => {}
+            | ObjKind::EXCEPTION
+            | ObjKind::IO => {}
             ObjKind::COMPLEX => {
                 let RubyComplex { r, i } = *self.complex();
                 r.mark(alloc);
@@ -475,6 +602,7 @@ impl GC<RValue> for RValue {
             ObjKind::MODULE | ObjKind::CLASS => self.module().mark(alloc),
             ObjKind::ARRAY => self.array().mark(alloc),
             ObjKind::HASH => self.rhash().mark(alloc),
+            ObjKind::SET => self.set().mark(alloc),
             ObjKind::RANGE => {
                 let RangeInfo { start, end, .. } = *self.range();
                 start.mark(alloc);
@@ -485,6 +613,7 @@ impl GC<RValue> for RValue {
             ObjKind::METHOD | ObjKind::UNBOUND_METHOD => self.method().mark(alloc),
             ObjKind::ENUMERATOR => self.enumerator().mark(alloc),
             ObjKind::FIBER => self.fiber().mark(alloc),
+            ObjKind::THREAD => self.thread().mark(alloc),
             ObjKind::BINDING => self.binding().mark(alloc),
             _ => unreachable!("{:?}", self),
         }
@@ -505,6 +634,7 @@ impl GCBox for RValue {
                 match k {
                     ObjKind::INVALID => panic!("Invalid rvalue. (maybe GC problem) {:?}", &self),
                     ObjKind::BIGNUM => ManuallyDrop::drop(&mut self.kind.bignum),
+                    ObjKind::RATIONAL => ManuallyDrop::drop(&mut self.kind.rational),
                     ObjKind::MODULE | ObjKind::CLASS => ManuallyDrop::drop(&mut self.kind.module),
                     ObjKind::STRING => ManuallyDrop::drop(&mut self.kind.string),
                     ObjKind::ARRAY => ManuallyDrop::drop(&mut self.kind.array),
@@ -514,11 +644,14 @@ impl GCBox for RValue {
                     ObjKind::ENUMERATOR => ManuallyDrop::drop(&mut self.kind.enumerator),
                     ObjKind::TIME => ManuallyDrop::drop(&mut self.kind.time),
                     ObjKind::EXCEPTION => ManuallyDrop::drop(&mut self.kind.exception),
+                    ObjKind::THREAD => ManuallyDrop::drop(&mut self.kind.thread),
+                    ObjKind::IO => ManuallyDrop::drop(&mut self.kind.io),
+                    ObjKind::SET => ManuallyDrop::drop(&mut self.kind.set),
                     ObjKind::BINDING => {}
                     _ => {}
                 }
                 self.set_next_none();
-                self.var_table = None;
+                self.ivars = None;
             }
         }
     }
@@ -546,7 +679,7 @@ impl GCBox for RValue {
             flags: RVFlag { next: None },
             class: Module::default(),
             kind: ObjKind::other(),
-            var_table: None,
+            ivars: None,
         }
     }
 }
@@ -559,9 +692,9 @@ impl RValue {
 
     pub(crate) fn shallow_dup(&self) -> Self {
         RValue {
-            flags: self.flags,
+            flags: self.flags.dup(),
             class: self.class,
-            var_table: self.var_table.clone(),
+            ivars: self.ivars.clone(),
             kind: match self.kind() {
                 ObjKind::INVALID => panic!("Invalid rvalue. (maybe GC problem) {:?}", &self),
                 ObjKind::COMPLEX => {
@@ -572,11 +705,15 @@ impl RValue {
                 ObjKind::MODULE | ObjKind::CLASS => ObjKind::module(self.module().clone()),
                 ObjKind::ENUMERATOR => ObjKind::other(), //ObjKind::enumerator((**self.enumerator()).clone()),
                 ObjKind::FIBER => ObjKind::other(),      //ObjKind::fiber((**self.fiber()).clone()),
+                ObjKind::THREAD => ObjKind::other(),
+                ObjKind::IO => ObjKind::other(),
                 ObjKind::FLOAT => ObjKind {
                     float: self.float(),
                 },
                 ObjKind::BIGNUM => ObjKind::bignum(self.bignum().clone()),
+                ObjKind::RATIONAL => ObjKind::rational(self.rational().clone()),
                 ObjKind::HASH => ObjKind::hash(self.rhash().clone()),
+                ObjKind::SET => ObjKind::set(self.set().clone()),
                 ObjKind::METHOD | ObjKind::UNBOUND_METHOD => ObjKind::method(self.method().clone()),
                 ObjKind::ORDINARY => ObjKind::other(),
                 ObjKind::PROC => ObjKind::proc(self.proc().clone()),
@@ -598,10 +735,10 @@ impl RValue {
 
     pub(crate) fn inspect(&self) -> Result<String, RubyError> {
         let mut s = format! {"#<{}:0x{:016x}", self.class_name(), self.id()};
-        match self.var_table() {
+        match self.ivars() {
             Some(table) => {
-                for (k, v) in table {
-                    s = format!("{} {:?}={:?}", s, k, *v);
+                for (k, v) in table.iter() {
+                    s = format!("{} {:?}={:?}", s, k, v);
                 }
             }
             None => {}
@@ -620,7 +757,7 @@ impl RValue {
             flags: RVFlag::new(kind),
             class,
             kind: objkind,
-            var_table: None,
+            ivars: None,
         }
     }
 
@@ -644,6 +781,14 @@ impl RValue {
         RValue::new(ObjKind::FLOAT, BuiltinClass::float(), ObjKind { float: f })
     }
 
+    pub(crate) fn new_rational(r: BigRational) -> Self {
+        RValue::new(
+            ObjKind::RATIONAL,
+            BuiltinClass::rational(),
+            ObjKind::rational(r),
+        )
+    }
+
     pub(crate) fn new_complex(r: Value, i: Value) -> Self {
         RValue::new(
             ObjKind::COMPLEX,
@@ -749,6 +894,18 @@ impl RValue {
         RValue::new(ObjKind::FIBER, BuiltinClass::fiber(), ObjKind::fiber(fiber))
     }
 
+    pub(crate) fn new_thread(thread_class: Module, info: ThreadInfo) -> Self {
+        RValue::new(ObjKind::THREAD, thread_class, ObjKind::thread(info))
+    }
+
+    pub(crate) fn new_io(io_class: Module, info: IoInfo) -> Self {
+        RValue::new(ObjKind::IO, io_class, ObjKind::io(info))
+    }
+
+    pub(crate) fn new_set(set_class: Module, info: SetInfo) -> Self {
+        RValue::new(ObjKind::SET, set_class, ObjKind::set(info))
+    }
+
     pub(crate) fn new_enumerator(fiber: FiberContext) -> Self {
         RValue::new(
             ObjKind::ENUMERATOR,
@@ -763,14 +920,8 @@ impl RValue {
 
     pub(crate) fn new_exception(exception_class: Module, err: RubyError) -> Self {
         let message = Value::string(err.message());
-        let mut backtrace = vec![];
-        for pos in 0..err.info.len() {
-            backtrace.push(Value::string(err.get_location(pos)));
-        }
-        let backtrace = Value::array_from(backtrace);
         let mut rval = RValue::new(ObjKind::EXCEPTION, exception_class, ObjKind::exception(err));
         rval.set_var(IdentId::get_id("@message"), message);
-        rval.set_var(IdentId::get_id("@backtrace"), backtrace);
         rval
     }
 
@@ -820,16 +971,16 @@ impl RValue {
 
     #[inline(always)]
     pub(crate) fn get_var(&self, id: IdentId) -> Option<Value> {
-        match &self.var_table {
-            Some(table) => table.get(&id).cloned(),
+        match &self.ivars {
+            Some(table) => table.get(id),
             None => None,
         }
     }
 
     #[inline(always)]
     pub(crate) fn get_mut_var(&mut self, id: IdentId) -> Option<&mut Value> {
-        match &mut self.var_table {
-            Some(table) => table.get_mut(&id),
+        match &mut self.ivars {
+            Some(table) => table.get_mut(id),
             None => None,
         }
     }
@@ -837,21 +988,19 @@ impl RValue {
     /// Set `val` for `id` in variable table. <br>
     /// Return Some(old_value) or None if no old value exists.
     pub(crate) fn set_var(&mut self, id: IdentId, val: Value) -> Option<Value> {
-        match &mut self.var_table {
-            Some(table) => table.insert(id, val),
-            None => {
-                let mut table = FxHashMap::default();
-                let v = table.insert(id, val);
-                self.var_table = Some(Box::new(table));
-                v
-            }
-        }
+        self.ivars_mut().set(id, val)
     }
 
-    pub(crate) fn var_table(&self) -> Option<&ValueTable> {
-        match &self.var_table {
+    pub(crate) fn ivars(&self) -> Option<&IvarTable> {
+        match &self.ivars {
             Some(table) => Some(table),
             None => None,
         }
     }
+
+    /// Variable table of the object. Allocated if not exists.
+    #[inline(always)]
+    pub(crate) fn ivars_mut(&mut self) -> &mut IvarTable {
+        self.ivars.get_or_insert_with(|| Box::new(IvarTable::new()))
+    }
 }
@@ -0,0 +1,88 @@
+use crate::*;
+use std::hash::{Hash, Hasher};
+
+/// Elements of Set. Insertion order is preserved.
+#[derive(Debug, Clone, Default)]
+pub struct SetInfo(FxIndexSet<HashKey>);
+
+impl Hash for SetInfo {
+    // The hash value must not depend on the order of elements.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut sum: u64 = 0;
+        for key in self.0.iter() {
+            let mut hasher = fxhash::FxHasher64::default();
+            key.hash(&mut hasher);
+            sum = sum.wrapping_add(hasher.finish());
+        }
+        self.0.len().hash(state);
+        sum.hash(state);
+    }
+}
+
+impl PartialEq for SetInfo {
+    // This type of equality is used for comparison for keys of Hash.
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len() && self.0.iter().all(|k| other.0.contains(k))
+    }
+}
+
+impl GC<RValue> for SetInfo {
+    fn mark(&self, alloc: &mut Allocator<RValue>) {
+        for k in self.0.iter() {
+            k.mark(alloc);
+        }
+    }
+}
+
+impl SetInfo {
+    pub(crate) fn new() -> Self {
+        SetInfo(FxIndexSet::default())
+    }
+
+    pub(crate) fn from_iter(iter: impl Iterator<Item = Value>) -> Self {
+        SetInfo(iter.map(HashKey).collect())
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = Value> + '_ {
+        self.0.iter().map(|k| k.0)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn contains(&self, v: Value) -> bool {
+        self.0.contains(&HashKey(v))
+    }
+
+    /// Add `v` to the set. Returns false if `v` was already in the set.
+    pub(crate) fn insert(&mut self, v: Value) -> bool {
+        self.0.insert(HashKey(v))
+    }
+
+    /// Remove `v` from the set preserving the order of the rest.
+    /// Returns false if `v` was not in the set.
+    pub(crate) fn remove(&mut self, v: Value) -> bool {
+        self.0.shift_remove(&HashKey(v))
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.0.clear()
+    }
+
+    pub(crate) fn to_vec(&self) -> Vec<Value> {
+        self.iter().collect()
+    }
+
+    pub(crate) fn is_subset(&self, other: &SetInfo) -> bool {
+        self.0.is_subset(&other.0)
+    }
+
+    pub(crate) fn is_disjoint(&self, other: &SetInfo) -> bool {
+        self.0.is_disjoint(&other.0)
+    }
+}
@@ -0,0 +1,257 @@
+use crate::*;
+use smallvec::SmallVec;
+use std::cell::RefCell;
+
+///
+/// Object shapes.
+///
+/// A shape represents an ordered sequence of instance variable names.
+/// Shapes form a transition tree shared by all objects: setting a new instance variable `@x`
+/// on an object of shape S moves the object to the child of S for `@x`.
+/// Since the slot index of every instance variable is determined by the shape,
+/// objects store their instance variables in a slot vector (`IvarTable`),
+/// and inline caches of GET_IVAR/SET_IVAR are keyed by shape ids.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ShapeId(u32);
+
+thread_local!(
+    static SHAPES: RefCell<ShapeTable> = RefCell::new(ShapeTable::new());
+);
+
+impl ShapeId {
+    /// The shape of objects without instance variables.
+    pub const ROOT: ShapeId = ShapeId(0);
+
+    /// The child shape of `self` for the instance variable `name`.
+    pub(crate) fn transition(self, name: IdentId) -> ShapeId {
+        SHAPES.with(|shapes| shapes.borrow_mut().transition(self, name))
+    }
+
+    /// The slot index of the instance variable `name`.
+    pub(crate) fn slot(self, name: IdentId) -> Option<usize> {
+        SHAPES.with(|shapes| shapes.borrow().slot(self, name))
+    }
+
+    /// Names of instance variables in slot order.
+    pub(crate) fn names(self) -> Vec<IdentId> {
+        SHAPES.with(|shapes| shapes.borrow().names(self))
+    }
+}
+
+#[derive(Debug)]
+struct Shape {
+    parent: ShapeId,
+    /// The instance variable added by the transition from `parent`. None for the root.
+    /// Its slot index is `len - 1`.
+    name: Option<IdentId>,
+    /// Number of instance variables.
+    len: usize,
+    transitions: FxHashMap<IdentId, ShapeId>,
+}
+
+#[derive(Debug)]
+struct ShapeTable {
+    shapes: Vec<Shape>,
+}
+
+impl ShapeTable {
+    fn new() -> Self {
+        let root = Shape {
+            parent: ShapeId::ROOT,
+            name: None,
+            len: 0,
+            transitions: FxHashMap::default(),
+        };
+        ShapeTable { shapes: vec![root] }
+    }
+
+    fn get(&self, id: ShapeId) -> &Shape {
+        &self.shapes[id.0 as usize]
+    }
+
+    fn transition(&mut self, shape: ShapeId, name: IdentId) -> ShapeId {
+        if let Some(child) = self.get(shape).transitions.get(&name) {
+            return *child;
+        }
+        let child = ShapeId(self.shapes.len() as u32);
+        let len = self.get(shape).len + 1;
+        self.shapes.push(Shape {
+            parent: shape,
+            name: Some(name),
+            len,
+            transitions: FxHashMap::default(),
+        });
+        self.shapes[shape.0 as usize]
+            .transitions
+            .insert(name, child);
+        child
+    }
+
+    fn slot(&self, mut shape: ShapeId, name: IdentId) -> Option<usize> {
+        while shape != ShapeId::ROOT {
+            let info = self.get(shape);
+            if info.name == Some(name) {
+                return Some(info.len - 1);
+            }
+            shape = info.parent;
+        }
+        None
+    }
+
+    fn names(&self, mut shape: ShapeId) -> Vec<IdentId> {
+        let mut names = vec![];
+        while shape != ShapeId::ROOT {
+            let info = self.get(shape);
+            names.extend(info.name);
+            shape = info.parent;
+        }
+        names.reverse();
+        names
+    }
+}
+
+///
+/// Instance variables of an object.
+///
+/// `slots[i]` holds the value of the instance variable with the slot index `i` in `shape`.
+///
+#[derive(Debug, Clone)]
+pub struct IvarTable {
+    shape: ShapeId,
+    slots: SmallVec<[Value; 6]>,
+}
+
+impl GC<RValue> for IvarTable {
+    fn mark(&self, alloc: &mut Allocator<RValue>) {
+        self.slots.iter().for_each(|v| v.mark(alloc));
+    }
+}
+
+impl IvarTable {
+    pub(crate) fn new() -> Self {
+        IvarTable {
+            shape: ShapeId::ROOT,
+            slots: SmallVec::new(),
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn shape(&self) -> ShapeId {
+        self.shape
+    }
+
+    pub(crate) fn get(&self, name: IdentId) -> Option<Value> {
+        self.shape.slot(name).map(|slot| self.slots[slot])
+    }
+
+    pub(crate) fn get_mut(&mut self, name: IdentId) -> Option<&mut Value> {
+        self.shape.slot(name).map(move |slot| &mut self.slots[slot])
+    }
+
+    /// Set `val` for `name`. <br>
+    /// Return Some(old_value) or None if no old value exists.
+    pub(crate) fn set(&mut self, name: IdentId, val: Value) -> Option<Value> {
+        match self.shape.slot(name) {
+            Some(slot) => Some(std::mem::replace(&mut self.slots[slot], val)),
+            None => {
+                self.shape = self.shape.transition(name);
+                self.slots.push(val);
+                None
+            }
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn get_slot(&self, slot: usize) -> Value {
+        self.slots[slot]
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_slot(&mut self, slot: usize, val: Value) {
+        self.slots[slot] = val;
+    }
+
+    /// Add a new instance variable `val` by the transition to `shape`, a child of the current shape.
+    #[inline(always)]
+    pub(crate) fn push(&mut self, shape: ShapeId, val: Value) {
+        self.shape = shape;
+        self.slots.push(val);
+    }
+
+    /// Pairs of names and values of instance variables in the order of definition.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (IdentId, Value)> + '_ {
+        self.shape
+            .names()
+            .into_iter()
+            .zip(self.slots.iter().cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+
+    #[test]
+    fn shape_transition() {
+        let (a, b, c) = (
+            IdentId::get_id("@shape_a"),
+            IdentId::get_id("@shape_b"),
+            IdentId::get_id("@shape_c"),
+        );
+        let ab = ShapeId::ROOT.transition(a).transition(b);
+        assert_eq!(ab, ShapeId::ROOT.transition(a).transition(b));
+        assert_ne!(ab, ShapeId::ROOT.transition(b).transition(a));
+        assert_eq!(Some(0), ab.slot(a));
+        assert_eq!(Some(1), ab.slot(b));
+        assert_eq!(None, ab.slot(c));
+        assert_eq!(vec![a, b], ab.names());
+
+        let mut table = IvarTable::new();
+        assert_eq!(None, table.set(a, Value::integer(1)));
+        assert_eq!(None, table.set(b, Value::integer(2)));
+        assert_eq!(Some(Value::integer(1)), table.set(a, Value::integer(3)));
+        assert_eq!(ab, table.shape());
+        assert_eq!(
+            vec![(a, Value::integer(3)), (b, Value::integer(2))],
+            table.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn shape_ivars() {
+        let program = r##"
+        class Foo
+          def initialize(a, b)
+            @a = a
+            @b = b if b
+          end
+          attr_accessor :a, :b, :c
+          def get; [@a, @b, @c]; end
+          def set(v); @c = v; end
+        end
+        foos = [Foo.new(1, 2), Foo.new(3, nil), Foo.new(5, 6)]
+        foos[1].b = 4
+        foos.each_with_index { |foo, i| foo.set(i) }
+        assert [[1, 2, 0], [3, 4, 1], [5, 6, 2]], foos.map(&:get)
+        assert [[:@a, :@b, :@c], [:@a, :@b, :@c]], [foos[0].instance_variables, foos[1].instance_variables]
+        assert [nil, nil, nil], Foo.allocate.get
+        foos[0].instance_variable_set(:@d, 7)
+        assert [1, 7], [foos[0].a, foos[0].instance_variable_get(:@d)]
+        dup = foos[2].dup
+        dup.a = 10
+        assert [[5, 6, 2], [10, 6, 2]], [foos[2].get, dup.get]
+        foos[2].freeze
+        assert_error { foos[2].set(3) }
+        class Bar
+          @count = 3
+          def self.count; @count; end
+        end
+        assert 3, Bar.count
+        100.times { |i| foos[0].instance_variable_set("@v#{i}", i) }
+        assert 99, foos[0].instance_variable_get(:@v99)
+        "##;
+        assert_script(program);
+    }
+}
@@ -13,32 +13,43 @@ pub enum RString {
 }
 
 use std::fmt;
+
+/// Write `s` to `f`, escaping quotes, backslashes and control characters.
+fn write_escaped(s: &str, f: &mut impl fmt::Write) -> fmt::Result {
+    for ch in s.chars() {
+        match ch {
+            c @ '\'' | c @ '"' | c @ '\\' => {
+                write!(f, "\\{}", c)?;
+            }
+            c if '\x00' <= c && c <= '\x06' => write!(f, "\\x{:02}", c as u32)?,
+            '\x07' => write!(f, "\\a")?,
+            '\x08' => write!(f, "\\b")?,
+            '\x09' => write!(f, "\\t")?,
+            '\x0a' => write!(f, "\\n")?,
+            '\x0b' => write!(f, "\\v")?,
+            '\x0c' => write!(f, "\\f")?,
+            '\x0d' => write!(f, "\\r")?,
+            '\x1b' => write!(f, "\\e")?,
+            c => write!(f, "{}", c)?,
+        };
+    }
+    Ok(())
+}
+
 impl fmt::Debug for RString {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fn write(s: &str, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-            for ch in s.chars() {
-                match ch {
-                    c @ '\'' | c @ '"' | c @ '\\' => {
-                        write!(f, "\\{}", c)?;
+        match self {
+            RString::Str(s) => write_escaped(s, f),
+            RString::SmallStr(s) => write_escaped(s, f),
+            RString::Bytes(v) => {
+                for chunk in v.utf8_chunks() {
+                    write_escaped(chunk.valid(), f)?;
+                    for b in chunk.invalid() {
+                        write!(f, "\\x{:02X}", b)?;
                     }
-                    c if '\x00' <= c && c <= '\x06' => write!(f, "\\x{:02}", c as u32)?,
-                    '\x07' => write!(f, "\\a")?,
-                    '\x08' => write!(f, "\\b")?,
-                    '\x09' => write!(f, "\\t")?,
-                    '\x0a' => write!(f, "\\n")?,
-                    '\x0b' => write!(f, "\\v")?,
-                    '\x0c' => write!(f, "\\f")?,
-                    '\x0d' => write!(f, "\\r")?,
-                    '\x1b' => write!(f, "\\e")?,
-                    c => write!(f, "{}", c)?,
-                };
+                }
+                Ok(())
             }
-            Ok(())
-        }
-        match self {
-            RString::Str(s) => write(s, f),
-            RString::SmallStr(s) => write(s, f),
-            RString::Bytes(v) => write!(f, "{}", String::from_utf8_lossy(v)),
         }
     }
 }
@@ -217,7 +228,7 @@ impl RString {
 
     pub(crate) fn replacen<'a, P>(&'a mut self, pat: P, to: &str, count: usize) -> String
     where
-        P: std::str::pattern::Pattern<'a>,
+        P: std::str::pattern::Pattern,
     {
         match self {
             RString::Str(s) => s.replacen(pat, to, count),
@@ -250,6 +261,20 @@ impl RString {
         format!(r#""{:?}""#, self)
     }
 
+    /// Inspect `self` as a binary string, escaping every non-ASCII byte as `\xHH`.
+    pub(crate) fn inspect_binary(&self) -> String {
+        let mut res = "\"".to_string();
+        for b in self.as_bytes() {
+            if b.is_ascii() {
+                write_escaped(char::from(*b).encode_utf8(&mut [0; 1]), &mut res).unwrap();
+            } else {
+                res += &format!("\\x{:02X}", b);
+            }
+        }
+        res.push('"');
+        res
+    }
+
     pub(crate) fn cmp(&self, other: Value) -> Option<Ordering> {
         let lhs = self.as_bytes();
         let rhs = other.as_bytes()?;
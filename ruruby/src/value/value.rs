@@ -1,5 +1,5 @@
 use num::bigint::{Sign, ToBigInt};
-use num::{BigInt, ToPrimitive};
+use num::{BigInt, BigRational, ToPrimitive};
 
 use crate::coroutine::*;
 use crate::*;
@@ -347,9 +347,46 @@ impl Value {
         }
     }
 
+    /// Examine whether `self` is frozen.
+    ///
+    /// Packed values (including Symbols) and numerics are always frozen.
+    pub(crate) fn is_frozen(&self) -> bool {
+        match self.as_rvalue() {
+            Some(rv) => {
+                rv.is_frozen()
+                    || matches!(
+                        rv.kind(),
+                        ObjKind::BIGNUM | ObjKind::FLOAT | ObjKind::COMPLEX | ObjKind::RATIONAL
+                    )
+            }
+            None => true,
+        }
+    }
+
+    pub(crate) fn freeze(mut self) -> Self {
+        if let Some(rv) = self.as_mut_rvalue() {
+            rv.freeze();
+        }
+        self
+    }
+
+    /// Return FrozenError if `self` is frozen.
+    pub(crate) fn check_frozen(&self) -> Result<(), RubyError> {
+        if self.is_frozen() {
+            Err(RubyError::frozen(format!(
+                "can't modify frozen {}: {:?}",
+                self.get_class_name(),
+                self
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
     pub(crate) fn is_real(&self) -> bool {
         match self.unpack() {
             RV::Float(_) | RV::Integer(_) => true,
+            RV::Object(obj) => matches!(obj.kind(), ObjKind::BIGNUM | ObjKind::RATIONAL),
             _ => false,
         }
     }
@@ -594,6 +631,16 @@ impl Value {
         }
     }
 
+    pub(crate) fn as_rational(&self) -> Option<&BigRational> {
+        match self.as_rvalue() {
+            Some(info) => match info.kind() {
+                ObjKind::RATIONAL => Some(info.rational()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
     pub(crate) fn as_complex(&self) -> Option<(Value, Value)> {
         match self.as_rvalue() {
             Some(info) => match info.kind() {
@@ -925,6 +972,58 @@ impl Value {
         }
     }
 
+    pub(crate) fn as_thread(&mut self) -> Option<&mut ThreadInfo> {
+        match self.as_mut_rvalue() {
+            Some(oref) => match oref.kind() {
+                ObjKind::THREAD => Some(oref.thread_mut()),
+                _ => None,
+            },
+            None => None,
+        }
+    }
+
+    pub(crate) fn expect_thread(&mut self, msg: &str) -> Result<&mut ThreadInfo, RubyError> {
+        let val = *self;
+        self.as_thread()
+            .ok_or_else(|| VMError::wrong_type(msg, "Thread", val))
+    }
+
+    pub(crate) fn as_set(&self) -> Option<&SetInfo> {
+        match self.as_rvalue() {
+            Some(oref) => match oref.kind() {
+                ObjKind::SET => Some(oref.set()),
+                _ => None,
+            },
+            None => None,
+        }
+    }
+
+    pub(crate) fn as_mut_set(&mut self) -> Option<&mut SetInfo> {
+        match self.as_mut_rvalue() {
+            Some(oref) => match oref.kind() {
+                ObjKind::SET => Some(oref.set_mut()),
+                _ => None,
+            },
+            None => None,
+        }
+    }
+
+    pub(crate) fn as_io(&mut self) -> Option<&mut IoInfo> {
+        match self.as_mut_rvalue() {
+            Some(oref) => match oref.kind() {
+                ObjKind::IO => Some(oref.io_mut()),
+                _ => None,
+            },
+            None => None,
+        }
+    }
+
+    pub(crate) fn expect_io(&mut self, msg: &str) -> Result<&mut IoInfo, RubyError> {
+        let val = *self;
+        self.as_io()
+            .ok_or_else(|| VMError::wrong_type(msg, "IO", val))
+    }
+
     pub(crate) fn if_exception(&self) -> Option<&RubyError> {
         match self.as_rvalue() {
             Some(oref) => match oref.kind() {
@@ -1047,6 +1146,10 @@ impl Value {
         }
     }
 
+    pub fn rational(r: BigRational) -> Self {
+        RValue::new_rational(r).pack()
+    }
+
     pub fn complex(r: Value, i: Value) -> Self {
         RValue::new_complex(r, i).pack()
     }
@@ -1143,6 +1246,18 @@ impl Value {
         RValue::new_enumerator(fiber).pack()
     }
 
+    pub(crate) fn thread(thread_class: Module, info: ThreadInfo) -> Self {
+        RValue::new_thread(thread_class, info).pack()
+    }
+
+    pub(crate) fn io(io_class: Module, info: IoInfo) -> Self {
+        RValue::new_io(io_class, info).pack()
+    }
+
+    pub(crate) fn set(set_class: Module, info: SetInfo) -> Self {
+        RValue::new_set(set_class, info).pack()
+    }
+
     pub(crate) fn time(time_class: Module, time: TimeInfo) -> Self {
         RValue::new_time(time_class, time).pack()
     }
@@ -1251,13 +1366,14 @@ impl Value {
     }
 
     /// Convert `self` to `Option<Real>`.
-    /// If `self` was not a integer nor a float, return `None`.
+    /// If `self` was not a integer nor a float nor a rational, return `None`.
     pub(crate) fn to_real(&self) -> Option<Real> {
         match self.unpack() {
             RV::Integer(i) => Some(Real::Integer(i)),
             RV::Float(f) => Some(Real::Float(f)),
             RV::Object(obj) => match obj.kind() {
                 ObjKind::BIGNUM => Some(Real::Bignum((*obj.bignum()).clone())),
+                ObjKind::RATIONAL => Some(Real::Rational(obj.rational().clone())),
                 _ => None,
             },
             _ => None,
@@ -1275,6 +1391,9 @@ impl Value {
                     let RubyComplex { r, i } = *obj.complex();
                     Some((r.to_real().unwrap(), i.to_real().unwrap()))
                 }
+                ObjKind::BIGNUM | ObjKind::RATIONAL => {
+                    Some((self.to_real().unwrap(), Real::Integer(0)))
+                }
                 _ => None,
             },
             _ => None,
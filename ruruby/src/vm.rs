@@ -1,6 +1,7 @@
 mod args;
 mod executor;
 pub mod iseq;
+pub mod iseq_cache;
 #[cfg(feature = "perf")]
 pub mod perf;
 
@@ -232,6 +232,7 @@ impl Args {
         }
     }
 
+    #[cfg(test)]
     pub(crate) fn new2(arg0: Value, arg1: Value) -> Self {
         Args {
             block: None,
@@ -10,13 +10,18 @@ use std::path::PathBuf;
 mod constants;
 mod fiber;
 pub mod frame;
+#[cfg(feature = "jit")]
+mod jit;
 mod loader;
 mod method;
 mod ops;
 mod opt_core;
 pub mod repl;
+#[cfg(feature = "jit")]
+pub use jit::JitInfo;
+pub(crate) use loader::load_file;
 
-pub type ValueTable = FxHashMap<IdentId, Value>;
+pub type ValueTable = FxIndexMap<IdentId, Value>;
 pub type VMResult = Result<Value, RubyError>;
 pub type InvokeResult = Result<VMResKind, RubyError>;
 
@@ -39,6 +44,10 @@ pub struct VM {
     sp_last_match: Option<String>,   // $&        : Regexp.last_match(0)
     sp_post_match: Option<String>,   // $'        : Regexp.post_match
     sp_matches: Vec<Option<String>>, // $1 ... $n : Regexp.last_match(n)
+    /// tags of active `catch` blocks.
+    pub(crate) catch_tags: Vec<Value>,
+    /// blocks of running Enumerable methods which are passed to `each`.
+    pub(crate) each_blocks: Vec<enumerable::EachBlock>,
     pub gc_count: usize,
 }
 
@@ -72,6 +81,8 @@ impl GC<RValue> for VM {
     fn mark(&self, alloc: &mut Allocator<RValue>) {
         self.stack.iter().for_each(|v| v.mark(alloc));
         self.temp_stack.iter().for_each(|v| v.mark(alloc));
+        self.catch_tags.iter().for_each(|v| v.mark(alloc));
+        self.each_blocks.iter().for_each(|b| b.mark(alloc));
         let mut cfp = Some(self.cfp);
         while let Some(f) = cfp {
             if f.is_ruby_func() {
@@ -97,6 +108,8 @@ impl VM {
             globals,
             stack: RubyStack::new(),
             temp_stack: vec![],
+            catch_tags: vec![],
+            each_blocks: vec![],
             pc: ISeqPtr::default(),
             lfp: LocalFrame::default(),
             cfp: ControlFrame::default(),
@@ -153,6 +166,8 @@ impl VM {
         let mut vm = VM {
             globals: self.globals,
             temp_stack: vec![],
+            catch_tags: vec![],
+            each_blocks: vec![],
             stack: RubyStack::new(),
             pc: ISeqPtr::default(),
             lfp: LocalFrame::default(),
@@ -258,6 +273,67 @@ impl VM {
         self.get_outer_frame(outer).get_lfp()
     }
 
+    /// Get the instance variable `id` of `self_val` using the inline cache `cache_id`.
+    #[inline]
+    pub(super) fn get_ivar(&mut self, self_val: Value, id: IdentId, cache_id: u32) -> Value {
+        let table = match self_val.rvalue().ivars() {
+            Some(table) => table,
+            None => return Value::nil(),
+        };
+        let shape = table.shape();
+        match self.globals.find_ivar_cache(cache_id) {
+            Some(entry) if entry.shape == shape => table.get_slot(entry.slot),
+            _ => match shape.slot(id) {
+                Some(slot) => {
+                    let new_shape = shape;
+                    let entry = IvarCacheEntry {
+                        shape,
+                        new_shape,
+                        slot,
+                    };
+                    self.globals.set_ivar_cache(cache_id, entry);
+                    table.get_slot(slot)
+                }
+                None => Value::nil(),
+            },
+        }
+    }
+
+    /// Set `val` to the instance variable `id` of `self_val` using the inline cache `cache_id`.
+    #[inline]
+    pub(super) fn set_ivar(
+        &mut self,
+        self_val: Value,
+        id: IdentId,
+        cache_id: u32,
+        val: Value,
+    ) -> Result<(), RubyError> {
+        self_val.check_frozen()?;
+        let table = self_val.rvalue_mut().ivars_mut();
+        let shape = table.shape();
+        match self.globals.find_ivar_cache(cache_id) {
+            Some(entry) if entry.shape == shape => {
+                if entry.new_shape == shape {
+                    table.set_slot(entry.slot, val);
+                } else {
+                    table.push(entry.new_shape, val);
+                }
+            }
+            _ => {
+                table.set(id, val);
+                let new_shape = table.shape();
+                let slot = new_shape.slot(id).unwrap();
+                let entry = IvarCacheEntry {
+                    shape,
+                    new_shape,
+                    slot,
+                };
+                self.globals.set_ivar_cache(cache_id, entry);
+            }
+        }
+        Ok(())
+    }
+
     #[cfg(not(tarpaulin_include))]
     pub fn clear(&mut self) {
         self.stack.sp = self.stack.bottom() + frame::CONT_FRAME_LEN + frame::RUBY_FRAME_LEN;
@@ -325,8 +401,13 @@ impl VM {
     }
 
     pub fn run(&mut self, path: impl Into<PathBuf>, program: String) -> VMResult {
-        let prev_len = self.stack_len();
         let method = self.parse_program(path, program)?;
+        self.run_toplevel(method)
+    }
+
+    /// Execute the toplevel ISeq `method` on the main object.
+    pub(crate) fn run_toplevel(&mut self, method: FnId) -> VMResult {
+        let prev_len = self.stack_len();
         let self_value = self.globals.main_object;
         let val = self.eval_method0(method, self_value)?;
         #[cfg(feature = "perf")]
@@ -354,11 +435,45 @@ impl VM {
             }
         }
         ALLOC.with(|m| m.borrow_mut().check_gc(&*self.globals));
+        if !self.globals.finalizers.is_empty() {
+            self.run_finalizers();
+        }
     }
 
     #[inline(always)]
     pub fn gc(&mut self) {
         ALLOC.with(|m| m.borrow_mut().gc(&*self.globals));
+        if !self.globals.finalizers.is_empty() {
+            self.run_finalizers();
+        }
+    }
+
+    /// Run finalizers of the objects which were swept in the last GC.
+    fn run_finalizers(&mut self) {
+        let count = ALLOC.with(|m| m.borrow().count());
+        if self.globals.finalizer_checked == count {
+            return;
+        }
+        self.globals.finalizer_checked = count;
+        let dead: Vec<u64> = ALLOC.with(|m| {
+            let alloc = m.borrow();
+            self.globals
+                .finalizers
+                .keys()
+                .filter(|id| !alloc.is_marked(**id as *const RValue))
+                .cloned()
+                .collect()
+        });
+        let temp_len = self.temp_len();
+        for id in dead {
+            let procs = self.globals.finalizers.shift_remove(&id).unwrap();
+            self.temp_extend_from_slice(&procs);
+            for proc in procs {
+                // Exceptions in finalizers are ignored.
+                let _ = self.eval_send1(IdentId::get_id("call"), proc, Value::integer(id as i64));
+            }
+        }
+        self.temp_stack.truncate(temp_len);
     }
 
     #[inline]
@@ -422,12 +537,14 @@ impl VM {
                         _ => {}
                     }
                     // Handle Exception.
+                    self.record_backtrace(&mut err);
                     loop {
                         let cur_pc = self.pc_offset();
                         let iseq = self.iseq;
                         if err.info.is_empty() || iseq.kind != ISeqKind::Block {
-                            err.info
-                                .push((iseq.source_info.clone(), iseq.get_loc(cur_pc)));
+                            // The kill error of a thread may be raised at any pc, even in an empty block.
+                            let loc = iseq.find_loc(cur_pc).unwrap_or_default();
+                            err.info.push((iseq.source_info.clone(), loc));
                         }
                         if let RubyErrorKind::Internal(msg) = &err.kind {
                             self.globals.show_err(&err);
@@ -446,6 +563,9 @@ impl VM {
                                 .globals
                                 .from_exception(&err)
                                 .unwrap_or(self.globals.val);
+                            if entry.ty == ExceptionType::Rescue {
+                                self.set_errinfo(val);
+                            }
                             #[cfg(feature = "trace")]
                             eprintln!(":::: Exception({:?})", val);
                             self.stack_push(val);
@@ -471,6 +591,49 @@ impl VM {
 }
 
 impl VM {
+    /// Record the current call stack to `err`, or to the exception object in `globals.val`
+    /// if `err` was raised by Kernel#raise, unless a backtrace was already recorded.
+    fn record_backtrace(&mut self, err: &mut RubyError) {
+        match err.kind {
+            RubyErrorKind::Exception => {
+                let ex = self.globals.val;
+                match ex.if_exception() {
+                    Some(info) if info.backtrace.is_empty() => {}
+                    _ => return,
+                }
+                if ex.get_var(IdentId::get_id("@backtrace")).is_some() {
+                    return;
+                }
+                let backtrace = self.backtrace();
+                ex.rvalue_mut().exception_mut().backtrace = backtrace;
+            }
+            RubyErrorKind::RuntimeErr { .. }
+            | RubyErrorKind::ParseErr(_)
+            | RubyErrorKind::None(_)
+                if err.backtrace.is_empty() =>
+            {
+                err.backtrace = self.backtrace();
+            }
+            _ => {}
+        }
+    }
+
+    /// Set `$!` to the exception `val` which is rescued.
+    ///
+    /// If the cause of `val` is not yet determined, the previous `$!` becomes the cause.
+    fn set_errinfo(&mut self, val: Value) {
+        let id = IdentId::get_id("$!");
+        let prev = self.globals.get_global_var(id).unwrap_or_default();
+        let cause_id = IdentId::get_id("/cause");
+        if val.if_exception().is_none() {
+            return;
+        }
+        if val.get_var(cause_id).is_none() && prev.id() != val.id() {
+            val.set_var(cause_id, prev);
+        }
+        self.globals.set_global_var(id, val);
+    }
+
     pub fn show_err(&self, err: &RubyError) {
         if err.is_exception() {
             let val = self.globals.val;
@@ -508,11 +671,15 @@ impl VM {
 // Handling global varables.
 impl VM {
     pub(crate) fn get_global_var(&self, id: IdentId) -> Option<Value> {
-        self.globals.get_global_var(id)
+        self.globals
+            .get_global_var(builtin::io::global_var_alias(id))
     }
 
-    pub fn set_global_var(&mut self, id: IdentId, val: Value) {
+    pub fn set_global_var(&mut self, id: IdentId, val: Value) -> Result<(), RubyError> {
+        let id = builtin::io::global_var_alias(id);
+        builtin::io::check_output_var(self, id, val)?;
         self.globals.set_global_var(id, val);
+        Ok(())
     }
 }
 
@@ -629,6 +796,12 @@ impl VM {
 
 impl VM {
     fn eval_rescue(&self, val: Value, exceptions: &[Value]) -> bool {
+        // `throw` to an active `catch` and Thread#kill can not be rescued.
+        if builtin::kernel::is_pending_throw(val)
+            || val.if_exception().is_some_and(|err| err.is_thread_kill())
+        {
+            return false;
+        }
         let mut module = if val.is_class() {
             Module::new(val)
         } else {
@@ -771,11 +944,14 @@ impl VM {
             RV::Symbol(sym) => format!(":{:?}", sym),
             RV::Object(oref) => match oref.kind() {
                 ObjKind::INVALID => "[Invalid]".to_string(),
-                ObjKind::STRING => oref.string().inspect(),
+                ObjKind::STRING => Encoding::inspect_string(val),
                 ObjKind::RANGE => oref.range().inspect(self)?,
                 ObjKind::MODULE | ObjKind::CLASS => oref.module().inspect(),
                 ObjKind::REGEXP => format!("/{}/", oref.regexp().as_str().to_string()),
-                ObjKind::ORDINARY => oref.inspect()?,
+                ObjKind::ORDINARY => match Encoding::of_object(val) {
+                    Some(enc) => format!("#<Encoding:{}>", enc.name()),
+                    None => oref.inspect()?,
+                },
                 ObjKind::HASH => oref.rhash().to_s(self)?,
                 ObjKind::COMPLEX => format!("{:?}", oref.complex()),
                 _ => {
@@ -939,6 +1115,26 @@ impl VM {
         Ok(Value::range(start, end, exclude_end))
     }
 
+    /// Create an error for the value `val` which did not match any pattern.
+    /// If `keys` is an Array and `val` is a Hash lacking any of them, NoMatchingPatternKeyError is returned.
+    pub(crate) fn no_matching_pattern(
+        &mut self,
+        val: Value,
+        keys: Value,
+    ) -> Result<RubyError, RubyError> {
+        let inspect = self.val_inspect(val)?;
+        if let (Some(keys), Some(hash)) = (keys.as_array(), val.as_hash()) {
+            if let Some(key) = keys.iter().find(|key| !hash.contains_key(**key)) {
+                let key = self.val_inspect(*key)?;
+                return Ok(RubyError::no_matching_pattern_key(format!(
+                    "{}: key not found: {}",
+                    inspect, key
+                )));
+            }
+        }
+        Ok(RubyError::no_matching_pattern(inspect))
+    }
+
     /// Create new Proc object from `block`,
     /// moving outer `Context`s on stack to heap.
     pub(crate) fn create_proc(&mut self, block: &Block) -> Value {
@@ -2,9 +2,12 @@ use crate::coroutine::*;
 use crate::*;
 
 impl VM {
-    pub(crate) fn dup_enum(&mut self, eref: &FiberContext) -> Box<FiberContext> {
+    /// Create a new Enumerator object which iterates from the beginning of `eref`.
+    ///
+    /// The new object must be kept alive (e.g. by `temp_push`) while it is resumed.
+    pub(crate) fn dup_enum(&mut self, eref: &FiberContext) -> Value {
         match &eref.kind {
-            FiberKind::Enum(box info) => Box::new(self.create_enum_info(info.clone())),
+            FiberKind::Enum(box info) => Value::enumerator(self.create_enum_info(info.clone())),
             _ => unreachable!(),
         }
     }
@@ -17,10 +20,7 @@ impl VM {
         receiver: Value,
         mut args: Args,
     ) -> VMResult {
-        let outer = self.caller_cfp();
-        let self_val = outer.self_value();
-        let proc = Value::procobj(self, self_val, METHOD_ENUM, outer);
-
+        let proc = self.create_enum_proc();
         args.block = Some(proc.into());
         let fiber = self.create_enum_info(EnumInfo {
             method,
@@ -30,6 +30,39 @@ impl VM {
         Ok(Value::enumerator(fiber))
     }
 
+    /// Create a Proc which yields given values from the running enumerator fiber.
+    fn create_enum_proc(&mut self) -> Value {
+        match self.try_caller_cfp() {
+            Some(outer) => {
+                let self_val = outer.self_value();
+                Value::procobj(self, self_val, METHOD_ENUM, outer)
+            }
+            // Native methods called on an enumerator fiber have no Ruby frame.
+            // The Proc only yields, so the one of the enumerator itself can be shared.
+            None => match self.handle.as_ref().map(|h| h.kind()) {
+                Some(FiberKind::Enum(info)) => info.args.block.as_ref().unwrap().encode(),
+                _ => unreachable!("no caller frame"),
+            },
+        }
+    }
+
+    /// Create a Proc which calls the builtin `method` with `self_val` as self.
+    pub(crate) fn create_builtin_proc(&mut self, self_val: Value, method: FnId) -> Value {
+        match self.try_caller_cfp() {
+            Some(outer) => Value::procobj(self, self_val, method, outer),
+            // Native methods called on an enumerator fiber have no Ruby frame.
+            // Builtin methods never refer to the outer context, so the one of the enumerator is borrowed.
+            None => match self.handle.as_ref().map(|h| h.kind()) {
+                Some(FiberKind::Enum(info)) => {
+                    let outer = info.args.block.as_ref().unwrap().encode();
+                    let outer = outer.as_proc().unwrap().outer;
+                    RValue::new_proc(ProcInfo::new(self_val, method, outer)).pack()
+                }
+                _ => unreachable!("no caller frame"),
+            },
+        }
+    }
+
     /// This func is called in the fiber thread of a enumerator.
     /// `vm`: VM of created fiber.
     pub(crate) fn enumerator_fiber(
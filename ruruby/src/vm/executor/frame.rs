@@ -8,11 +8,11 @@ mod heap;
 pub mod ruby_stack;
 
 const EV_PREV_CFP: isize = 0;
-const EV_EP: isize = 1;
-const EV_FLAG: isize = 2;
+pub(super) const EV_EP: isize = 1;
+pub(super) const EV_FLAG: isize = 2;
 
 const EV_MFP: isize = 3;
-const EV_OUTER: isize = 4;
+pub(super) const EV_OUTER: isize = 4;
 const EV_PC: isize = 5;
 const EV_ISEQ: isize = 6;
 const EV_BLK: isize = 7;
@@ -352,6 +352,7 @@ impl EnvFrame {
 /// You can handle local variables of the frame.
 ///
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(transparent)]
 pub struct LocalFrame(*mut Value);
 
 impl_ptr_ops!(LocalFrame);
@@ -446,14 +447,23 @@ impl VM {
     }
 
     pub(crate) fn caller_cfp(&self) -> ControlFrame {
+        match self.try_caller_cfp() {
+            Some(f) => f,
+            None => unreachable!("no caller frame"),
+        }
+    }
+
+    /// Get the nearest control frame of Ruby method or block.
+    /// Returns None if there is no such frame in this VM (e.g. native methods on an enumerator fiber).
+    pub(crate) fn try_caller_cfp(&self) -> Option<ControlFrame> {
         let mut cfp = self.cfp.prev();
         while let Some(f) = cfp {
             if f.is_ruby_func() {
-                return f;
+                return Some(f);
             }
             cfp = f.prev();
         }
-        unreachable!("no caller frame");
+        None
     }
 
     #[inline(always)]
@@ -484,6 +494,61 @@ impl VM {
     }
 }
 
+impl VM {
+    /// Collect locations of Ruby frames on the call stack. (the innermost frame first)
+    ///
+    /// Native frames are skipped.
+    pub(crate) fn backtrace(&self) -> Vec<BacktraceLoc> {
+        let mut res = vec![];
+        let mut cfp = Some(self.cfp);
+        while let Some(f) = cfp {
+            if f.is_ruby_func() {
+                let ep = f.ep();
+                let iseq = ep.iseq();
+                let pc = if f == self.cfp {
+                    self.pc_offset()
+                } else {
+                    f.pc()
+                };
+                if let Some(loc) = iseq.find_loc(pc) {
+                    let label = frame_label(ep);
+                    res.push(BacktraceLoc::new(iseq.source_info.clone(), loc, label));
+                }
+            }
+            cfp = f.prev();
+        }
+        res
+    }
+}
+
+/// Label of the frame `ep` which is shown in backtraces.
+///
+/// e.g. "foo", "block in foo", "block (2 levels) in <main>", "<class:Foo>"
+fn frame_label(mut ep: EnvFrame) -> String {
+    let mut levels = 0;
+    while matches!(ep.iseq().kind, ISeqKind::Block | ISeqKind::Method(None)) {
+        match ep.outer() {
+            Some(outer) => {
+                levels += 1;
+                ep = outer;
+            }
+            None => break,
+        }
+    }
+    let base = match ep.iseq().kind {
+        ISeqKind::Method(Some(id)) => format!("{:?}", id),
+        ISeqKind::Class(id) => format!("<class:{:?}>", id),
+        // The toplevel is compiled as an unnamed method without outer frame.
+        ISeqKind::Other | ISeqKind::Method(None) => "<main>".to_string(),
+        ISeqKind::Block => return "block".to_string(),
+    };
+    match levels {
+        0 => base,
+        1 => format!("block in {}", base),
+        n => format!("block ({} levels) in {}", n, base),
+    }
+}
+
 impl VM {
     pub(crate) fn init_frame(&mut self) {
         self.stack_push(Value::nil());
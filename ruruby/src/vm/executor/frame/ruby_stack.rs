@@ -120,7 +120,6 @@ impl RubyStack {
         (ptr[0], ptr[1])
     }
 
-    #[cfg(feature = "trace")]
     #[inline(always)]
     pub(crate) fn last(&self) -> Value {
         debug_assert!(self.len() != 0);
@@ -155,6 +154,7 @@ impl RubyStack {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, std::cmp::PartialOrd)]
+#[repr(transparent)]
 pub struct StackPtr(*mut Value);
 
 impl_ptr_ops!(StackPtr);
@@ -0,0 +1,842 @@
+///
+/// Baseline JIT compiler for x86-64.
+///
+/// An ISeq is compiled to native code when it was called (or iterated a loop) `JIT_THRESHOLD` times.
+/// Each instruction is translated with a fixed template.
+///
+/// The native code works directly on the VM state (the execution stack, local variables and pc),
+/// so the control can move between the interpreter and the native code at any instruction boundary.
+///
+/// - Fast paths of Integer arithmetic and comparison, local (and outer local) variable access
+///   and branches are inlined.
+/// - Method calls and some other instructions are executed by calling helper functions.
+///   A send whose inline cache was warm at compile time is dispatched directly to the cached method.
+/// - When a type guard failed, or an instruction is not supported, the native code exits to the interpreter
+///   at that instruction (deoptimization).
+/// - The native code is discarded when any method was (re)defined, and it returns to the interpreter
+///   as soon as it noticed the redefinition.
+///
+#[cfg(not(target_arch = "x86_64"))]
+compile_error!("The jit feature is supported only on x86-64.");
+
+mod asm;
+
+use super::*;
+use asm::*;
+use region::Protection;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Number of calls or loop iterations before an ISeq is compiled.
+const JIT_THRESHOLD: usize = 20;
+
+// Status codes returned from the native code and helper functions.
+/// Continue the native code. (returned only from helper functions)
+const CONTINUE: u64 = 0;
+/// Continue with the interpreter from the current pc.
+const EXIT: u64 = 1;
+/// A new Ruby frame was pushed.
+const INVOKE: u64 = 2;
+/// An error was raised. The error is stored in `JIT_ERROR`.
+const ERROR: u64 = 3;
+
+/// Marks an instruction where the native code can not be entered.
+const NO_ENTRY: u32 = u32::MAX;
+
+thread_local!(
+    static JIT_ERROR: RefCell<Option<RubyError>> = RefCell::new(None);
+);
+
+/// Native code: fn(vm, entry address) -> status.
+type JitFunc = extern "sysv64" fn(*mut VM, *const u8) -> u64;
+
+/// JIT compilation state of an ISeq.
+#[derive(Default)]
+pub struct JitInfo {
+    /// Number of calls and loop iterations.
+    counter: usize,
+    code: Option<Rc<JitCode>>,
+    /// Set when the compilation failed.
+    failed: bool,
+}
+
+impl Clone for JitInfo {
+    /// Native code is not shared, as it refers to the bytecode of its own ISeq.
+    fn clone(&self) -> Self {
+        JitInfo::default()
+    }
+}
+
+struct JitCode {
+    mem: region::Allocation,
+    /// Offset of the native code for each bytecode position, or NO_ENTRY.
+    entries: Vec<u32>,
+    /// Class version when compiled.
+    class_version: u32,
+}
+
+pub(super) enum JitResult {
+    /// Continue with the interpreter from the current pc.
+    Interpret,
+    /// A new Ruby frame was pushed.
+    Invoke,
+}
+
+impl VM {
+    /// Execute the native code of the current ISeq from the current pc.
+    ///
+    /// The ISeq is compiled here when it got hot.
+    pub(super) fn jit_exec(&mut self) -> Result<JitResult, RubyError> {
+        let pos = self.pc_offset().into_usize();
+        if pos == 0 {
+            let mut iseq = self.iseq;
+            iseq.jit.counter += 1;
+        }
+        let code = match self.jit_code() {
+            Some(code) => code,
+            None => return Ok(JitResult::Interpret),
+        };
+        let entry = code.entries[pos];
+        if entry == NO_ENTRY {
+            return Ok(JitResult::Interpret);
+        }
+        let func: JitFunc = unsafe { std::mem::transmute(code.mem.as_ptr::<u8>()) };
+        let entry = unsafe { code.mem.as_ptr::<u8>().add(entry as usize) };
+        match func(self, entry) {
+            EXIT => Ok(JitResult::Interpret),
+            INVOKE => Ok(JitResult::Invoke),
+            _ => Err(JIT_ERROR.with(|err| err.borrow_mut().take()).unwrap()),
+        }
+    }
+
+    /// Count a backward branch of the current ISeq.
+    ///
+    /// Returns true if the native code can be entered at the current pc.
+    pub(super) fn jit_back_edge(&mut self) -> bool {
+        let mut iseq = self.iseq;
+        iseq.jit.counter += 1;
+        match self.jit_code() {
+            Some(code) => code.entries[self.pc_offset().into_usize()] != NO_ENTRY,
+            None => false,
+        }
+    }
+
+    /// Get the native code of the current ISeq, compiling it if the ISeq is hot.
+    ///
+    /// Outdated native code is discarded.
+    fn jit_code(&mut self) -> Option<Rc<JitCode>> {
+        let mut iseq = self.iseq;
+        let class_version = self.globals.methods.class_version();
+        if let Some(code) = &iseq.jit.code {
+            if code.class_version == class_version {
+                return Some(code.clone());
+            }
+            iseq.jit.code = None;
+            iseq.jit.counter = 0;
+            return None;
+        }
+        if iseq.jit.failed || iseq.jit.counter < JIT_THRESHOLD {
+            return None;
+        }
+        match JitCompiler::new(self).compile() {
+            Some(code) => {
+                let code = Rc::new(code);
+                iseq.jit.code = Some(code.clone());
+                Some(code)
+            }
+            None => {
+                iseq.jit.failed = true;
+                None
+            }
+        }
+    }
+
+    /// Handle the result of an instruction executed by a helper function.
+    fn jit_dispatch(&mut self, res: InvokeResult, use_value: bool) -> u64 {
+        match res {
+            Ok(VMResKind::Invoke) => INVOKE,
+            Ok(VMResKind::Return(val)) => {
+                if use_value {
+                    self.stack_push(val);
+                }
+                self.jit_continue()
+            }
+            Err(err) if err.kind == RubyErrorKind::BlockReturn => self.jit_continue(),
+            Err(err) => {
+                JIT_ERROR.with(|e| *e.borrow_mut() = Some(err));
+                ERROR
+            }
+        }
+    }
+
+    /// Returns EXIT if the native code of the current ISeq got outdated.
+    fn jit_continue(&self) -> u64 {
+        match &self.iseq.jit.code {
+            Some(code) if code.class_version == self.globals.methods.class_version() => CONTINUE,
+            _ => EXIT,
+        }
+    }
+
+    /// Execute the instruction at pc for the native code.
+    fn jit_inst(&mut self, inst: u8) -> InvokeResult {
+        let val = match inst {
+            Inst::GET_CONST => {
+                let id = self.pc.read_id();
+                let slot = self.pc.read32();
+                match self.globals.find_const_cache(slot) {
+                    Some(val) => val,
+                    None => {
+                        let val = self.find_const(id)?;
+                        self.globals.set_const_cache(slot, val);
+                        val
+                    }
+                }
+            }
+            Inst::CONST_VAL => {
+                let id = self.pc.read_usize();
+                self.globals.const_values.get(id)
+            }
+            Inst::GET_IVAR => {
+                let var_id = self.pc.read_id();
+                let cache_id = self.pc.read32();
+                self.get_ivar(self.self_value(), var_id, cache_id)
+            }
+            Inst::SET_IVAR => {
+                let var_id = self.pc.read_id();
+                let cache_id = self.pc.read32();
+                let new_val = self.stack_pop();
+                self.set_ivar(self.self_value(), var_id, cache_id, new_val)?;
+                Value::nil()
+            }
+            Inst::CREATE_ARRAY => {
+                let arg_num = self.pc.read_usize();
+                self.pop_args_to_array(arg_num)
+            }
+            Inst::GET_INDEX => {
+                let idx = self.stack_pop();
+                let receiver = self.stack_pop();
+                return self.invoke_get_index(receiver, idx);
+            }
+            Inst::SET_INDEX => return self.invoke_set_index(),
+            Inst::GET_IDX_I => {
+                let idx = self.pc.read32();
+                let receiver = self.stack_pop();
+                return self.invoke_get_index_imm(receiver, idx);
+            }
+            Inst::SET_IDX_I => {
+                let idx = self.pc.read32();
+                return self.invoke_set_index_imm(idx);
+            }
+            Inst::MUL => return self.invoke_mul(),
+            Inst::DIV => return self.invoke_div(),
+            Inst::REM => return self.invoke_rem(),
+            Inst::YIELD => {
+                let args_num = self.pc.read32() as usize;
+                let args = self.pop_args_to_args(args_num);
+                return self.vm_yield(&args);
+            }
+            Inst::SEND => return self.vm_send(),
+            Inst::OPT_SIZE | Inst::OPT_EMPTY => {
+                let cache_id = self.pc.read32();
+                return self.invoke_size(inst == Inst::OPT_EMPTY, cache_id);
+            }
+            _ => unreachable!(),
+        };
+        Ok(VMResKind::Return(val))
+    }
+}
+
+/// Instructions executed by `exec_inst`.
+fn is_helper_inst(inst: u8) -> bool {
+    matches!(
+        inst,
+        Inst::GET_CONST
+            | Inst::CONST_VAL
+            | Inst::GET_IVAR
+            | Inst::SET_IVAR
+            | Inst::CREATE_ARRAY
+            | Inst::GET_INDEX
+            | Inst::SET_INDEX
+            | Inst::GET_IDX_I
+            | Inst::SET_IDX_I
+            | Inst::MUL
+            | Inst::DIV
+            | Inst::REM
+            | Inst::YIELD
+            | Inst::SEND
+            | Inst::OPT_SIZE
+            | Inst::OPT_EMPTY
+    )
+}
+
+/// Execute the instruction at `pos`.
+extern "sysv64" fn exec_inst(vm: &mut VM, pos: u64) -> u64 {
+    vm.set_pc(ISeqPos::from(pos as usize));
+    let inst = vm.pc.read8();
+    let use_value = !matches!(inst, Inst::SET_IVAR | Inst::SET_INDEX | Inst::SET_IDX_I);
+    let res = vm.jit_inst(inst);
+    vm.jit_dispatch(res, use_value)
+}
+
+/// Execute OPT_SEND or OPT_SEND_N at `pos`.
+///
+/// If the class of the receiver is `class`, `fid` is invoked without method lookup.
+extern "sysv64" fn opt_send(vm: &mut VM, pos: u64, class: u64, fid: u64) -> u64 {
+    vm.set_pc(ISeqPos::from(pos as usize));
+    let use_value = vm.pc.read8() == Inst::OPT_SEND;
+    let method_name = vm.pc.read_id();
+    let args_num = vm.pc.read16() as usize;
+    let block = vm.pc.read32();
+    let cache_id = vm.pc.read32();
+    let args = if block != 0 {
+        Args2::new_with_block(args_num, Block::Block(block.into(), vm.cur_frame()))
+    } else {
+        Args2::new(args_num)
+    };
+    let receiver = (vm.sp() - args_num - 1)[0];
+    let res = if class != 0 && vm.globals.get_class_for_method(receiver).id() == class {
+        vm.invoke_method(FnId::from(fid as u32), &args, use_value)
+    } else {
+        vm.send(method_name, receiver, &args, use_value, cache_id)
+    };
+    vm.jit_dispatch(res, use_value)
+}
+
+/// Backward branch to `dest`.
+extern "sysv64" fn back_edge(vm: &mut VM, dest: u64) -> u64 {
+    vm.checked_gc();
+    if let Err(err) = vm.check_timeslice() {
+        vm.set_pc(ISeqPos::from(dest as usize));
+        return vm.jit_dispatch(Err(err), false);
+    }
+    let status = vm.jit_continue();
+    if status == EXIT {
+        vm.set_pc(ISeqPos::from(dest as usize));
+    }
+    status
+}
+
+/// Register usage in the native code.
+/// - r12: stack pointer
+/// - r13: local frame pointer
+/// - r14: &mut VM
+const SP: Reg = Reg::R12;
+const LFP: Reg = Reg::R13;
+const VM_REG: Reg = Reg::R14;
+
+struct JitCompiler {
+    asm: Assembler,
+    iseq: ISeqRef,
+    /// Label of the instruction at each bytecode position.
+    labels: Vec<Option<Label>>,
+    entries: Vec<u32>,
+    /// Deoptimization exits and positions of their instructions.
+    deopts: Vec<(Label, usize)>,
+    epilogue: Label,
+    /// Offsets of the stack pointer, the local frame pointer, pc and cfp in VM.
+    sp_offset: i32,
+    lfp_offset: i32,
+    pc_offset: i32,
+    cfp_offset: i32,
+    class_version: u32,
+    /// Inline cache entries (class id, FnId) of each inline cache.
+    inline_cache: Vec<Option<(u64, u32)>>,
+}
+
+impl JitCompiler {
+    fn new(vm: &VM) -> Self {
+        let base = vm as *const VM as usize;
+        let offset = |ptr: usize| (ptr - base) as i32;
+        let iseq = vm.iseq;
+        let mut asm = Assembler::default();
+        let epilogue = asm.new_label();
+        let mut inline_cache = vec![];
+        let mut pc = ISeqPos::from(0);
+        while pc.into_usize() < iseq.iseq.len() {
+            if matches!(iseq.iseq[pc], Inst::OPT_SEND | Inst::OPT_SEND_N) {
+                let id = iseq.iseq.read32(pc + 11);
+                let entry = vm.globals.methods.inline_cache_entry(id);
+                inline_cache.push(entry.map(|(class, fid)| (class.id(), u32::from(fid))));
+            }
+            pc += Inst::inst_size(iseq.iseq[pc]);
+        }
+        JitCompiler {
+            asm,
+            iseq,
+            labels: vec![None; iseq.iseq.len()],
+            entries: vec![NO_ENTRY; iseq.iseq.len()],
+            deopts: vec![],
+            epilogue,
+            sp_offset: offset(std::ptr::addr_of!(vm.stack.sp) as usize),
+            lfp_offset: offset(std::ptr::addr_of!(vm.lfp) as usize),
+            pc_offset: offset(std::ptr::addr_of!(vm.pc) as usize),
+            cfp_offset: offset(std::ptr::addr_of!(vm.cfp) as usize),
+            class_version: vm.globals.methods.class_version(),
+            inline_cache,
+        }
+    }
+
+    fn compile(mut self) -> Option<JitCode> {
+        let iseq = self.iseq;
+        let mut pc = ISeqPos::from(0);
+        while pc.into_usize() < iseq.iseq.len() {
+            self.labels[pc.into_usize()] = Some(self.asm.new_label());
+            pc += Inst::inst_size(iseq.iseq[pc]);
+        }
+        self.prologue();
+
+        let mut pc = ISeqPos::from(0);
+        let mut inline_cache = std::mem::take(&mut self.inline_cache).into_iter();
+        while pc.into_usize() < iseq.iseq.len() {
+            let pos = pc.into_usize();
+            let inst = iseq.iseq[pc];
+            let next = pc + Inst::inst_size(inst);
+            self.asm.bind(self.labels[pos].unwrap());
+            self.entries[pos] = self.asm.pos() as u32;
+            match inst {
+                Inst::PUSH_NIL => {
+                    self.asm.mov_ri(Reg::Rax, NIL_VALUE);
+                    self.push(Reg::Rax);
+                }
+                Inst::PUSH_VAL => {
+                    self.asm.mov_ri(Reg::Rax, iseq.iseq.read64(pc + 1));
+                    self.push(Reg::Rax);
+                }
+                Inst::PUSH_SELF => {
+                    self.asm.load(Reg::Rax, LFP, -8);
+                    self.push(Reg::Rax);
+                }
+                Inst::POP => self.asm.alu_ri(AluOp::Sub, SP, 8),
+                Inst::DUP if iseq.iseq.read32(pc + 1) <= 16 => {
+                    let len = iseq.iseq.read32(pc + 1) as i32;
+                    for i in 0..len {
+                        self.asm.load(Reg::Rax, SP, (i - len) * 8);
+                        self.asm.store(SP, i * 8, Reg::Rax);
+                    }
+                    self.asm.alu_ri(AluOp::Add, SP, len * 8);
+                }
+                Inst::GET_LOCAL => {
+                    let id = iseq.iseq.read32(pc + 1) as i32;
+                    self.asm.load(Reg::Rax, LFP, id * 8);
+                    self.push(Reg::Rax);
+                }
+                Inst::SET_LOCAL => {
+                    let id = iseq.iseq.read32(pc + 1) as i32;
+                    self.pop(Reg::Rax);
+                    self.asm.store(LFP, id * 8, Reg::Rax);
+                }
+                Inst::TEE_LOCAL => {
+                    let id = iseq.iseq.read32(pc + 1) as i32;
+                    self.asm.load(Reg::Rax, SP, -8);
+                    self.asm.store(LFP, id * 8, Reg::Rax);
+                }
+                Inst::GET_DYNLOCAL => {
+                    let id = iseq.iseq.read32(pc + 1) as i32;
+                    self.load_outer_lfp(iseq.iseq.read32(pc + 5));
+                    self.asm.load(Reg::Rax, Reg::Rax, id * 8);
+                    self.push(Reg::Rax);
+                }
+                Inst::SET_DYNLOCAL => {
+                    let id = iseq.iseq.read32(pc + 1) as i32;
+                    self.load_outer_lfp(iseq.iseq.read32(pc + 5));
+                    self.pop(Reg::Rcx);
+                    self.asm.store(Reg::Rax, id * 8, Reg::Rcx);
+                }
+                Inst::ADDI | Inst::SUBI => {
+                    let op = if inst == Inst::ADDI {
+                        AluOp::Add
+                    } else {
+                        AluOp::Sub
+                    };
+                    let imm = iseq.iseq.read32(pc + 1) as i32 as i64;
+                    let deopt = self.deopt(pos);
+                    self.asm.load(Reg::Rax, SP, -8);
+                    self.guard_fixnum(Reg::Rax, deopt);
+                    self.asm.mov_ri(Reg::Rcx, (imm << 1) as u64);
+                    self.asm.alu_rr(op, Reg::Rax, Reg::Rcx);
+                    self.asm.jcc(Cond::O, deopt);
+                    self.asm.store(SP, -8, Reg::Rax);
+                }
+                Inst::ADD | Inst::SUB => {
+                    let deopt = self.deopt(pos);
+                    self.load_fixnum2(deopt);
+                    if inst == Inst::ADD {
+                        self.asm.alu_ri(AluOp::Sub, Reg::Rax, 1);
+                        self.asm.alu_rr(AluOp::Add, Reg::Rax, Reg::Rcx);
+                        self.asm.jcc(Cond::O, deopt);
+                    } else {
+                        self.asm.alu_rr(AluOp::Sub, Reg::Rax, Reg::Rcx);
+                        self.asm.jcc(Cond::O, deopt);
+                        self.asm.alu_ri(AluOp::Add, Reg::Rax, 1);
+                    }
+                    self.asm.store(SP, -16, Reg::Rax);
+                    self.asm.alu_ri(AluOp::Sub, SP, 8);
+                }
+                Inst::EQ | Inst::NE | Inst::GT | Inst::GE | Inst::LT | Inst::LE => {
+                    let deopt = self.deopt(pos);
+                    self.load_fixnum2(deopt);
+                    self.asm.alu_rr(AluOp::Cmp, Reg::Rax, Reg::Rcx);
+                    self.set_bool(cond(inst), -16);
+                    self.asm.alu_ri(AluOp::Sub, SP, 8);
+                }
+                Inst::EQI | Inst::NEI | Inst::GTI | Inst::GEI | Inst::LTI | Inst::LEI => {
+                    let deopt = self.deopt(pos);
+                    self.load_fixnum_imm(deopt, iseq.iseq.read32(pc + 1));
+                    self.asm.alu_rr(AluOp::Cmp, Reg::Rax, Reg::Rcx);
+                    self.set_bool(cond(inst), -8);
+                }
+                Inst::JMP_F_EQ
+                | Inst::JMP_F_NE
+                | Inst::JMP_F_GT
+                | Inst::JMP_F_GE
+                | Inst::JMP_F_LT
+                | Inst::JMP_F_LE => {
+                    let dest = self.label(next + iseq.iseq.read_disp(pc + 1))?;
+                    let deopt = self.deopt(pos);
+                    self.load_fixnum2(deopt);
+                    self.asm.alu_ri(AluOp::Sub, SP, 16);
+                    self.asm.alu_rr(AluOp::Cmp, Reg::Rax, Reg::Rcx);
+                    self.asm.jcc(cond(inst).not(), dest);
+                }
+                Inst::JMP_F_EQI
+                | Inst::JMP_F_NEI
+                | Inst::JMP_F_GTI
+                | Inst::JMP_F_GEI
+                | Inst::JMP_F_LTI
+                | Inst::JMP_F_LEI => {
+                    let dest = self.label(next + iseq.iseq.read_disp(pc + 5))?;
+                    let deopt = self.deopt(pos);
+                    self.load_fixnum_imm(deopt, iseq.iseq.read32(pc + 1));
+                    self.asm.alu_ri(AluOp::Sub, SP, 8);
+                    self.asm.alu_rr(AluOp::Cmp, Reg::Rax, Reg::Rcx);
+                    self.asm.jcc(cond(inst).not(), dest);
+                }
+                Inst::NOT => {
+                    self.asm.load(Reg::Rax, SP, -8);
+                    self.test_falsy(Reg::Rax);
+                    self.set_bool(Cond::E, -8);
+                }
+                Inst::JMP => {
+                    let dest = self.label(next + iseq.iseq.read_disp(pc + 1))?;
+                    self.asm.jmp(dest);
+                }
+                Inst::JMP_BACK => {
+                    let dest_pos = next + iseq.iseq.read_disp(pc + 1);
+                    let dest = self.label(dest_pos)?;
+                    self.call_helper(
+                        back_edge as *const () as u64,
+                        &[dest_pos.into_usize() as u64],
+                    );
+                    self.asm.jmp(dest);
+                }
+                Inst::JMP_F | Inst::JMP_T => {
+                    let dest = self.label(next + iseq.iseq.read_disp(pc + 1))?;
+                    self.pop(Reg::Rax);
+                    self.test_falsy(Reg::Rax);
+                    let cond = if inst == Inst::JMP_F {
+                        Cond::E
+                    } else {
+                        Cond::Ne
+                    };
+                    self.asm.jcc(cond, dest);
+                }
+                Inst::OPT_SEND | Inst::OPT_SEND_N => {
+                    let (class, fid) = inline_cache.next().unwrap().unwrap_or((0, 0));
+                    self.call_helper(
+                        opt_send as *const () as u64,
+                        &[pos as u64, class, fid as u64],
+                    );
+                }
+                inst if is_helper_inst(inst) => {
+                    self.call_helper(exec_inst as *const () as u64, &[pos as u64]);
+                }
+                _ => {
+                    // Not supported. Return to the interpreter.
+                    self.entries[pos] = NO_ENTRY;
+                    self.exit(pos);
+                }
+            }
+            pc = next;
+        }
+
+        for (label, pos) in std::mem::take(&mut self.deopts) {
+            self.asm.bind(label);
+            self.exit(pos);
+        }
+
+        let code = self.asm.finalize()?;
+        let mut mem = region::alloc(code.len(), Protection::READ_WRITE).ok()?;
+        unsafe {
+            std::ptr::copy_nonoverlapping(code.as_ptr(), mem.as_mut_ptr::<u8>(), code.len());
+            region::protect(mem.as_ptr::<u8>(), code.len(), Protection::READ_EXECUTE).ok()?;
+        }
+        Some(JitCode {
+            mem,
+            entries: self.entries,
+            class_version: self.class_version,
+        })
+    }
+
+    fn label(&self, pos: ISeqPos) -> Option<Label> {
+        *self.labels.get(pos.into_usize())?
+    }
+
+    /// Save callee-saved registers, load the VM state and jump to the entry.
+    fn prologue(&mut self) {
+        for reg in [Reg::Rbx, Reg::R12, Reg::R13, Reg::R14, Reg::R15] {
+            self.asm.push(reg);
+        }
+        self.asm.mov_rr(VM_REG, Reg::Rdi);
+        self.asm.load(SP, VM_REG, self.sp_offset);
+        self.asm.load(LFP, VM_REG, self.lfp_offset);
+        self.asm.jmp_r(Reg::Rsi);
+
+        self.asm.bind(self.epilogue);
+        for reg in [Reg::R15, Reg::R14, Reg::R13, Reg::R12, Reg::Rbx] {
+            self.asm.pop(reg);
+        }
+        self.asm.ret();
+    }
+
+    /// Write back the stack pointer and pc, and return to the interpreter.
+    fn exit(&mut self, pos: usize) {
+        self.asm.store(VM_REG, self.sp_offset, SP);
+        let pc = self.iseq.iseq.as_bytes()[pos..].as_ptr();
+        self.asm.mov_ri(Reg::Rax, pc as u64);
+        self.asm.store(VM_REG, self.pc_offset, Reg::Rax);
+        self.asm.mov_ri(Reg::Rax, EXIT);
+        self.asm.jmp(self.epilogue);
+    }
+
+    /// A new deoptimization exit for the instruction at `pos`.
+    fn deopt(&mut self, pos: usize) -> Label {
+        let label = self.asm.new_label();
+        self.deopts.push((label, pos));
+        label
+    }
+
+    /// Call a helper function `func(vm, args..)`.
+    ///
+    /// When the helper returned other than CONTINUE, return the status to the interpreter.
+    fn call_helper(&mut self, func: u64, args: &[u64]) {
+        self.asm.store(VM_REG, self.sp_offset, SP);
+        self.asm.mov_rr(Reg::Rdi, VM_REG);
+        for (reg, arg) in [Reg::Rsi, Reg::Rdx, Reg::Rcx].iter().zip(args) {
+            self.asm.mov_ri(*reg, *arg);
+        }
+        self.asm.mov_ri(Reg::Rax, func);
+        self.asm.call_r(Reg::Rax);
+        self.asm.alu_ri(AluOp::Cmp, Reg::Rax, CONTINUE as i32);
+        self.asm.jcc(Cond::Ne, self.epilogue);
+        // The stack and the local frame may have been changed.
+        self.asm.load(SP, VM_REG, self.sp_offset);
+        self.asm.load(LFP, VM_REG, self.lfp_offset);
+    }
+
+    /// Load the local frame pointer of the `outer`-th outer frame to rax. rcx is clobbered.
+    fn load_outer_lfp(&mut self, outer: u32) {
+        // ep = cfp[EV_EP]
+        self.asm.load(Reg::Rax, VM_REG, self.cfp_offset);
+        self.asm.load(Reg::Rax, Reg::Rax, EV_EP as i32 * 8);
+        self.asm.alu_ri(AluOp::And, Reg::Rax, -2);
+        for _ in 0..outer {
+            self.asm.load(Reg::Rax, Reg::Rax, EV_OUTER as i32 * 8);
+            self.asm.alu_ri(AluOp::And, Reg::Rax, -2);
+        }
+        // lfp = ep - (number of local variables + 1)
+        self.asm.load(Reg::Rcx, Reg::Rax, EV_FLAG as i32 * 8);
+        self.asm.shr_ri(Reg::Rcx, 29);
+        self.asm.alu_ri(AluOp::And, Reg::Rcx, -8);
+        self.asm.alu_rr(AluOp::Sub, Reg::Rax, Reg::Rcx);
+        self.asm.alu_ri(AluOp::Sub, Reg::Rax, 8);
+    }
+
+    fn push(&mut self, reg: Reg) {
+        self.asm.store(SP, 0, reg);
+        self.asm.alu_ri(AluOp::Add, SP, 8);
+    }
+
+    fn pop(&mut self, reg: Reg) {
+        self.asm.alu_ri(AluOp::Sub, SP, 8);
+        self.asm.load(reg, SP, 0);
+    }
+
+    fn guard_fixnum(&mut self, reg: Reg, deopt: Label) {
+        self.asm.test_ri(reg, 1);
+        self.asm.jcc(Cond::E, deopt);
+    }
+
+    /// Load two Integers on the stack top to rax and rcx.
+    fn load_fixnum2(&mut self, deopt: Label) {
+        self.asm.load(Reg::Rax, SP, -16);
+        self.asm.load(Reg::Rcx, SP, -8);
+        self.guard_fixnum(Reg::Rax, deopt);
+        self.guard_fixnum(Reg::Rcx, deopt);
+    }
+
+    /// Load an Integer on the stack top to rax, and an immediate Integer `imm` to rcx.
+    fn load_fixnum_imm(&mut self, deopt: Label, imm: u32) {
+        self.asm.load(Reg::Rax, SP, -8);
+        self.guard_fixnum(Reg::Rax, deopt);
+        self.asm
+            .mov_ri(Reg::Rcx, Value::integer(imm as i32 as i64).id());
+    }
+
+    /// Set ZF if `reg` is falsy. `reg` is clobbered.
+    fn test_falsy(&mut self, reg: Reg) {
+        self.asm.alu_ri(AluOp::And, reg, !0x30);
+        self.asm.alu_ri(AluOp::Cmp, reg, 0x04);
+    }
+
+    /// Store true or false to `[sp + disp]` according to `cond`.
+    fn set_bool(&mut self, cond: Cond, disp: i32) {
+        self.asm.mov_ri(Reg::Rax, FALSE_VALUE);
+        self.asm.mov_ri(Reg::Rdx, TRUE_VALUE);
+        self.asm.cmov(cond, Reg::Rax, Reg::Rdx);
+        self.asm.store(SP, disp, Reg::Rax);
+    }
+}
+
+/// Condition of a comparison instruction.
+fn cond(inst: u8) -> Cond {
+    match inst {
+        Inst::EQ | Inst::EQI | Inst::JMP_F_EQ | Inst::JMP_F_EQI => Cond::E,
+        Inst::NE | Inst::NEI | Inst::JMP_F_NE | Inst::JMP_F_NEI => Cond::Ne,
+        Inst::GT | Inst::GTI | Inst::JMP_F_GT | Inst::JMP_F_GTI => Cond::G,
+        Inst::GE | Inst::GEI | Inst::JMP_F_GE | Inst::JMP_F_GEI => Cond::Ge,
+        Inst::LT | Inst::LTI | Inst::JMP_F_LT | Inst::JMP_F_LTI => Cond::L,
+        _ => Cond::Le,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::*;
+    use crate::*;
+
+    #[test]
+    fn jit_compile() {
+        let program = r#"
+        def fib(n)
+          if n < 2 then n else fib(n - 1) + fib(n - 2) end
+        end
+        fib(20)
+        "#;
+        let mut vm = VM::new();
+        let res = vm.run("", program.to_string()).unwrap();
+        assert_eq!(Some(6765), res.as_fixnum());
+        let fid = vm
+            .globals
+            .classes
+            .object
+            .search_method(IdentId::get_id("fib"))
+            .unwrap()
+            .fid();
+        let iseq = vm.globals.methods[fid].as_iseq();
+        assert!(iseq.jit.code.is_some());
+    }
+
+    #[test]
+    fn jit_deopt() {
+        let program = r#"
+        # Integer overflow
+        x = 4611686018427387900
+        i = 0
+        while i < 30
+          x += 1
+          i += 1
+        end
+        assert 4611686018427387930, x
+        # Float
+        f = 0
+        50.times { f += 1.5 }
+        assert 75.0, f
+        def cmp(a, b)
+          [a < b, a <= b, a > b, a >= b, a == b, a != b, !(a < b)]
+        end
+        res = []
+        30.times { |i| res << cmp(i, 10) }
+        assert [false, true, false, true, true, false, true], res[10]
+        assert [true, true, false, false, false, true, false], cmp(1.5, 10)
+        assert [false, false, true, true, false, true, true], cmp("b", "a")
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn jit_redefine() {
+        let program = r#"
+        def m
+          1
+        end
+        sum = 0
+        100.times do |i|
+          sum += m
+          if i == 50
+            eval("def m; 2; end")
+          end
+        end
+        assert 51 * 1 + 49 * 2, sum
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn jit_control() {
+        let program = r#"
+        r = 0
+        30.times do |i|
+          begin
+            raise "error" if i % 3 == 0
+            r += 1
+          rescue
+            r += 100
+          end
+        end
+        assert 1020, r
+        def find(ary)
+          ary.each { |x| return x if x > 40 }
+          nil
+        end
+        assert 41, find((0..100).to_a)
+        sum = 0
+        i = 0
+        while true
+          i += 1
+          next if i.odd?
+          break if i > 100
+          sum += i
+        end
+        assert 2550, sum
+        a = [0] * 50
+        50.times { |i| a[i] = i * 2 }
+        assert 2450, a.inject(:+)
+        "#;
+        assert_script(program);
+    }
+
+    #[test]
+    fn jit_dynlocal() {
+        let program = r#"
+        x = 0
+        n = 30
+        n.times do |i|
+          y = i
+          n.times do
+            n.times { x += 1 }
+            y += 1
+          end
+          assert i + n, y
+        end
+        assert 27000, x
+        def counter
+          c = 0
+          [-> { c += 1 }, -> { c }]
+        end
+        inc, get = counter
+        50.times { inc.call }
+        assert 50, get.call
+        "#;
+        assert_script(program);
+    }
+}
@@ -0,0 +1,321 @@
+///
+/// A minimal x86-64 assembler for the JIT compiler.
+///
+/// Only the instructions and addressing modes which are used by the JIT are supported.
+/// Memory operands are always `[base + disp32]`.
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(dead_code)]
+pub(super) enum Reg {
+    Rax = 0,
+    Rcx = 1,
+    Rdx = 2,
+    Rbx = 3,
+    Rsp = 4,
+    Rbp = 5,
+    Rsi = 6,
+    Rdi = 7,
+    R8 = 8,
+    R9 = 9,
+    R10 = 10,
+    R11 = 11,
+    R12 = 12,
+    R13 = 13,
+    R14 = 14,
+    R15 = 15,
+}
+
+impl Reg {
+    fn low(self) -> u8 {
+        self as u8 & 7
+    }
+
+    fn high(self) -> u8 {
+        self as u8 >> 3
+    }
+}
+
+/// Condition codes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(dead_code)]
+pub(super) enum Cond {
+    O = 0x0,
+    No = 0x1,
+    B = 0x2,
+    Ae = 0x3,
+    E = 0x4,
+    Ne = 0x5,
+    Be = 0x6,
+    A = 0x7,
+    L = 0xc,
+    Ge = 0xd,
+    Le = 0xe,
+    G = 0xf,
+}
+
+impl Cond {
+    /// The negated condition.
+    pub(super) fn not(self) -> Self {
+        match self {
+            Cond::O => Cond::No,
+            Cond::No => Cond::O,
+            Cond::B => Cond::Ae,
+            Cond::Ae => Cond::B,
+            Cond::E => Cond::Ne,
+            Cond::Ne => Cond::E,
+            Cond::Be => Cond::A,
+            Cond::A => Cond::Be,
+            Cond::L => Cond::Ge,
+            Cond::Ge => Cond::L,
+            Cond::Le => Cond::G,
+            Cond::G => Cond::Le,
+        }
+    }
+}
+
+/// Opcode extensions of `op r/m64, imm32` (0x81 /ext).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum AluOp {
+    Add = 0,
+    And = 4,
+    Sub = 5,
+    Cmp = 7,
+}
+
+impl AluOp {
+    /// Opcode of `op r/m64, r64`.
+    fn rr_opcode(self) -> u8 {
+        match self {
+            AluOp::Add => 0x01,
+            AluOp::And => 0x21,
+            AluOp::Sub => 0x29,
+            AluOp::Cmp => 0x39,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct Label(usize);
+
+#[derive(Debug, Default)]
+pub(super) struct Assembler {
+    code: Vec<u8>,
+    labels: Vec<Option<usize>>,
+    /// Positions of rel32 operands and their destinations.
+    fixups: Vec<(usize, Label)>,
+}
+
+impl Assembler {
+    /// Current position in the code.
+    pub(super) fn pos(&self) -> usize {
+        self.code.len()
+    }
+
+    pub(super) fn new_label(&mut self) -> Label {
+        self.labels.push(None);
+        Label(self.labels.len() - 1)
+    }
+
+    pub(super) fn bind(&mut self, label: Label) {
+        debug_assert!(self.labels[label.0].is_none());
+        self.labels[label.0] = Some(self.pos());
+    }
+
+    /// Resolve all labels and return the machine code.
+    ///
+    /// Returns None if there is any unbound label.
+    pub(super) fn finalize(mut self) -> Option<Vec<u8>> {
+        for (pos, label) in std::mem::take(&mut self.fixups) {
+            let dest = self.labels[label.0]?;
+            let rel = dest as i64 - (pos + 4) as i64;
+            self.code[pos..pos + 4].copy_from_slice(&(rel as i32).to_le_bytes());
+        }
+        Some(self.code)
+    }
+
+    fn emit(&mut self, bytes: &[u8]) {
+        self.code.extend_from_slice(bytes);
+    }
+
+    fn emit32(&mut self, val: u32) {
+        self.emit(&val.to_le_bytes());
+    }
+
+    fn rel32(&mut self, label: Label) {
+        self.fixups.push((self.pos(), label));
+        self.emit32(0);
+    }
+
+    fn rex_w(&mut self, reg: Reg, rm: Reg) {
+        self.emit(&[0x48 | reg.high() << 2 | rm.high()]);
+    }
+
+    /// ModR/M (and SIB) bytes for a register operand `reg` and a memory operand `[base + disp]`.
+    fn mem(&mut self, reg: Reg, base: Reg, disp: i32) {
+        self.emit(&[0x80 | reg.low() << 3 | base.low()]);
+        if base.low() == 4 {
+            // rsp and r12 need a SIB byte.
+            self.emit(&[0x24]);
+        }
+        self.emit32(disp as u32);
+    }
+
+    fn modrm_rr(&mut self, reg: u8, rm: Reg) {
+        self.emit(&[0xc0 | (reg & 7) << 3 | rm.low()]);
+    }
+
+    /// push r64
+    pub(super) fn push(&mut self, reg: Reg) {
+        if reg.high() != 0 {
+            self.emit(&[0x41]);
+        }
+        self.emit(&[0x50 + reg.low()]);
+    }
+
+    /// pop r64
+    pub(super) fn pop(&mut self, reg: Reg) {
+        if reg.high() != 0 {
+            self.emit(&[0x41]);
+        }
+        self.emit(&[0x58 + reg.low()]);
+    }
+
+    /// ret
+    pub(super) fn ret(&mut self) {
+        self.emit(&[0xc3]);
+    }
+
+    /// mov dst, src
+    pub(super) fn mov_rr(&mut self, dst: Reg, src: Reg) {
+        self.rex_w(src, dst);
+        self.emit(&[0x89]);
+        self.modrm_rr(src as u8, dst);
+    }
+
+    /// mov dst, imm64
+    pub(super) fn mov_ri(&mut self, dst: Reg, imm: u64) {
+        self.emit(&[0x48 | dst.high(), 0xb8 + dst.low()]);
+        self.emit(&imm.to_le_bytes());
+    }
+
+    /// mov dst, [base + disp]
+    pub(super) fn load(&mut self, dst: Reg, base: Reg, disp: i32) {
+        self.rex_w(dst, base);
+        self.emit(&[0x8b]);
+        self.mem(dst, base, disp);
+    }
+
+    /// mov [base + disp], src
+    pub(super) fn store(&mut self, base: Reg, disp: i32, src: Reg) {
+        self.rex_w(src, base);
+        self.emit(&[0x89]);
+        self.mem(src, base, disp);
+    }
+
+    /// op dst, imm32 (sign-extended)
+    pub(super) fn alu_ri(&mut self, op: AluOp, dst: Reg, imm: i32) {
+        self.rex_w(Reg::Rax, dst);
+        self.emit(&[0x81]);
+        self.modrm_rr(op as u8, dst);
+        self.emit32(imm as u32);
+    }
+
+    /// op dst, src
+    pub(super) fn alu_rr(&mut self, op: AluOp, dst: Reg, src: Reg) {
+        self.rex_w(src, dst);
+        self.emit(&[op.rr_opcode()]);
+        self.modrm_rr(src as u8, dst);
+    }
+
+    /// shr reg, imm8
+    pub(super) fn shr_ri(&mut self, reg: Reg, imm: u8) {
+        self.rex_w(Reg::Rax, reg);
+        self.emit(&[0xc1]);
+        self.modrm_rr(5, reg);
+        self.emit(&[imm]);
+    }
+
+    /// test reg, imm32 (sign-extended)
+    pub(super) fn test_ri(&mut self, reg: Reg, imm: i32) {
+        self.rex_w(Reg::Rax, reg);
+        self.emit(&[0xf7]);
+        self.modrm_rr(0, reg);
+        self.emit32(imm as u32);
+    }
+
+    /// cmovcc dst, src
+    pub(super) fn cmov(&mut self, cond: Cond, dst: Reg, src: Reg) {
+        self.rex_w(dst, src);
+        self.emit(&[0x0f, 0x40 + cond as u8]);
+        self.modrm_rr(dst as u8, src);
+    }
+
+    /// jmp label
+    pub(super) fn jmp(&mut self, label: Label) {
+        self.emit(&[0xe9]);
+        self.rel32(label);
+    }
+
+    /// jcc label
+    pub(super) fn jcc(&mut self, cond: Cond, label: Label) {
+        self.emit(&[0x0f, 0x80 + cond as u8]);
+        self.rel32(label);
+    }
+
+    /// jmp reg
+    pub(super) fn jmp_r(&mut self, reg: Reg) {
+        if reg.high() != 0 {
+            self.emit(&[0x41]);
+        }
+        self.emit(&[0xff]);
+        self.modrm_rr(4, reg);
+    }
+
+    /// call reg
+    pub(super) fn call_r(&mut self, reg: Reg) {
+        if reg.high() != 0 {
+            self.emit(&[0x41]);
+        }
+        self.emit(&[0xff]);
+        self.modrm_rr(2, reg);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode() {
+        let mut asm = Assembler::default();
+        asm.push(Reg::R12);
+        asm.pop(Reg::Rbx);
+        asm.mov_rr(Reg::R14, Reg::Rdi);
+        asm.load(Reg::Rax, Reg::R12, -8);
+        asm.store(Reg::R13, 16, Reg::Rcx);
+        asm.alu_ri(AluOp::Sub, Reg::R12, 8);
+        asm.alu_rr(AluOp::Add, Reg::Rax, Reg::Rcx);
+        asm.shr_ri(Reg::Rcx, 29);
+        asm.test_ri(Reg::Rax, 1);
+        asm.cmov(Cond::L, Reg::Rax, Reg::Rdx);
+        asm.call_r(Reg::Rax);
+        asm.jmp_r(Reg::Rsi);
+        assert_eq!(
+            asm.finalize().unwrap(),
+            vec![
+                0x41, 0x54, // push r12
+                0x5b, // pop rbx
+                0x49, 0x89, 0xfe, // mov r14, rdi
+                0x49, 0x8b, 0x84, 0x24, 0xf8, 0xff, 0xff, 0xff, // mov rax, [r12 - 8]
+                0x49, 0x89, 0x8d, 0x10, 0x00, 0x00, 0x00, // mov [r13 + 16], rcx
+                0x49, 0x81, 0xec, 0x08, 0x00, 0x00, 0x00, // sub r12, 8
+                0x48, 0x01, 0xc8, // add rax, rcx
+                0x48, 0xc1, 0xe9, 0x1d, // shr rcx, 29
+                0x48, 0xf7, 0xc0, 0x01, 0x00, 0x00, 0x00, // test rax, 1
+                0x48, 0x0f, 0x4c, 0xc2, // cmovl rax, rdx
+                0xff, 0xd0, // call rax
+                0xff, 0xe6, // jmp rsi
+            ]
+        );
+    }
+}
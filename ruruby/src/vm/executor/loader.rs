@@ -3,6 +3,9 @@ use std::fs::*;
 use std::io::Read;
 use std::path::{Path, PathBuf};
 
+/// Names of standard libraries which are provided as builtins.
+const BUILTIN_FEATURES: &[&str] = &["set", "time", "open3"];
+
 impl VM {
     pub fn load_file(&mut self, absolute_path: &Path) -> Result<String, RubyError> {
         match load_file(absolute_path) {
@@ -18,6 +21,10 @@ impl VM {
     }
 
     pub(crate) fn require(&mut self, file_name: &str) -> Result<bool, RubyError> {
+        // Libraries which are implemented as builtins are already loaded.
+        if BUILTIN_FEATURES.contains(&file_name) {
+            return Ok(false);
+        }
         let mut path = PathBuf::from(file_name);
         if path.is_absolute() {
             path.set_extension("rb");
@@ -73,20 +80,56 @@ impl VM {
             return Ok(false);
         }
         let program = self.load_file(&absolute_path)?;
-        self.run(absolute_path, program)?;
+        let method = self.compile_file(&absolute_path, program)?;
+        self.run_toplevel(method)?;
         Ok(true)
     }
+
+    /// Compile a source file, using the ISeq cache if enabled.
+    fn compile_file(&mut self, path: &Path, program: String) -> Result<FnId, RubyError> {
+        let dir = match &self.globals.iseq_cache_dir {
+            Some(dir) => dir.clone(),
+            None => return self.parse_program(path, program),
+        };
+        let key = match iseq_cache::SourceKey::new(path, &program) {
+            Some(key) => key,
+            None => return self.parse_program(path, program),
+        };
+        if let Some(method) = iseq_cache::load(self, &dir, &key, &program) {
+            return Ok(method);
+        }
+        let method = self.parse_program(path, program)?;
+        iseq_cache::save(self, &dir, &key, method);
+        Ok(method)
+    }
 }
 
+/// Read a source file.
+///
+/// A file which is not valid as UTF-8 is converted to UTF-8
+/// according to its encoding magic comment (e.g. `# encoding: Shift_JIS`).
 pub(crate) fn load_file(path: &Path) -> Result<String, String> {
-    let mut file_body = String::new();
+    let mut file_body = vec![];
     match OpenOptions::new().read(true).open(path) {
-        Ok(mut file) => match file.read_to_string(&mut file_body) {
+        Ok(mut file) => match file.read_to_end(&mut file_body) {
             Ok(_) => {}
             Err(ioerr) => return Err(format!("{}", ioerr)),
         },
         Err(ioerr) => return Err(format!("{}", ioerr)),
     };
+    let file_body = match String::from_utf8(file_body) {
+        Ok(body) => body,
+        Err(err) => {
+            let bytes = err.into_bytes();
+            let encoding = magic_comment(&String::from_utf8_lossy(&bytes), "encoding")
+                .and_then(|name| Encoding::from_name(&name))
+                .ok_or_else(|| "invalid byte sequence in UTF-8".to_string())?;
+            let opt = TranscodeOptions::default();
+            let body =
+                transcode(&bytes, encoding, Encoding::Utf8, &opt).map_err(|err| err.message())?;
+            String::from_utf8(body).map_err(|err| err.to_string())?
+        }
+    };
 
     Ok(file_body)
 }
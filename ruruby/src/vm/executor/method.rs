@@ -514,6 +514,18 @@ impl VM {
                     return self.push_method_frame_slow(iseq, args, use_value);
                 }
             }
+            ProcFunc { proc } => {
+                // A method defined by define_method has lambda semantics. Self is the receiver.
+                let pinfo = proc.as_proc().unwrap();
+                let outer = pinfo.outer;
+                let iseq = self.globals.methods[pinfo.method].as_iseq();
+                let params = &iseq.params;
+                let positional_kwarg =
+                    !args.kw_arg.is_nil() && params.keyword.is_empty() && !params.kwrest;
+                params.check_arity(positional_kwarg, args)?;
+                self.push_block_frame_slow(iseq, args, outer, use_value)?;
+                return Ok(VMResKind::Invoke);
+            }
             _ => unreachable!(),
         };
         Ok(VMResKind::Return(val))
@@ -586,6 +598,7 @@ impl VM {
         args.check_args_num(1)?;
         let val = self.stack_pop();
         let mut self_val = self.stack_pop();
+        self_val.check_frozen()?;
         match self_val.as_mut_rvalue() {
             Some(oref) => {
                 oref.set_var(id, val);
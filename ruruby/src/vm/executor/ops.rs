@@ -171,6 +171,7 @@ impl VM {
             }
         }
         if let Some(mut ainfo) = lhs.as_array() {
+            lhs.check_frozen()?;
             ainfo.push(rhs);
             Ok(VMResKind::Return(lhs))
         } else {
@@ -308,7 +309,12 @@ impl VM {
         let (lhs, rhs) = self.stack_pop2();
         let b = match lhs.as_rvalue() {
             Some(oref) => match oref.kind() {
-                ObjKind::MODULE | ObjKind::CLASS => {
+                ObjKind::MODULE
+                | ObjKind::CLASS
+                | ObjKind::RANGE
+                | ObjKind::PROC
+                | ObjKind::SET
+                | ObjKind::ORDINARY => {
                     return self.invoke_send1(IdentId::_TEQ, lhs, rhs);
                 }
                 ObjKind::REGEXP => self.teq_regexp(oref, rhs)?,
@@ -335,7 +341,12 @@ impl VM {
     pub(crate) fn eval_teq(&mut self, rhs: Value, lhs: Value) -> Result<bool, RubyError> {
         match lhs.as_rvalue() {
             Some(oref) => match oref.kind() {
-                ObjKind::MODULE | ObjKind::CLASS => {
+                ObjKind::MODULE
+                | ObjKind::CLASS
+                | ObjKind::RANGE
+                | ObjKind::PROC
+                | ObjKind::SET
+                | ObjKind::ORDINARY => {
                     let v = self.eval_send1(IdentId::_TEQ, lhs, rhs)?;
                     Ok(v.to_bool())
                 }
@@ -373,6 +384,12 @@ impl VM {
                 return Ok(rhsi as f64 == lhsf);
             }
         }
+        if lhs.as_rational().is_some() || rhs.as_rational().is_some() {
+            return Ok(match (lhs.to_real(), rhs.to_real()) {
+                (Some(lhs), Some(rhs)) => lhs == rhs,
+                _ => false,
+            });
+        }
         if rhs.is_packed_value() || lhs.is_packed_value() {
             return Ok(lhs.id() == rhs.id());
         }
@@ -416,6 +433,7 @@ impl VM {
                     && self.eval_eq2(rhs.end, lhs.end)?)
             }
             (ObjKind::HASH, ObjKind::HASH) => Ok(*lhsr.rhash() == *rhsr.rhash()),
+            (ObjKind::SET, ObjKind::SET) => Ok(*lhsr.set() == *rhsr.set()),
             (ObjKind::REGEXP, ObjKind::REGEXP) => Ok(*lhsr.regexp() == *rhsr.regexp()),
             (ObjKind::TIME, ObjKind::TIME) => Ok(*lhsr.time() == *rhsr.time()),
             (ObjKind::INVALID, _) | (_, ObjKind::INVALID) => {
@@ -446,10 +464,6 @@ impl VM {
     eval_cmp!(eval_le, le, _LE);
     eval_cmp!(eval_lt, lt, _LT);
 
-    pub(crate) fn eval_gt2(&mut self, rhs: Value, lhs: Value) -> Result<bool, RubyError> {
-        eval_cmp2!(self, rhs, lhs, gt, IdentId::_GT)
-    }
-
     pub(super) fn eval_eqi(&mut self, lhs: Value, i: i32) -> Result<bool, RubyError> {
         let res = if let Some(lhsi) = lhs.as_fixnum() {
             lhsi == i as i64
@@ -497,7 +511,7 @@ impl VM {
         let mut receiver = self.stack_pop();
 
         match receiver.as_mut_rvalue() {
-            Some(oref) => {
+            Some(oref) if !oref.is_frozen() => {
                 match oref.kind() {
                     ObjKind::ARRAY => {
                         oref.array_mut().set_elem1(idx, val)?;
@@ -510,7 +524,7 @@ impl VM {
                     _ => {}
                 };
             }
-            None => {}
+            _ => {}
         }
         self.invoke_send2(IdentId::_INDEX_ASSIGN, receiver, idx, val, false)
     }
@@ -519,7 +533,7 @@ impl VM {
         let val = self.stack_pop();
         let mut receiver = self.stack_pop();
         match receiver.as_mut_rvalue() {
-            Some(oref) => {
+            Some(oref) if !oref.is_frozen() => {
                 match oref.kind() {
                     ObjKind::ARRAY => {
                         oref.array_mut().set_elem_imm(idx as usize, val);
@@ -532,7 +546,7 @@ impl VM {
                     _ => {}
                 };
             }
-            None => {}
+            _ => {}
         }
         self.invoke_send2(
             IdentId::_INDEX_ASSIGN,
@@ -551,8 +565,17 @@ impl VM {
                     return Ok(VMResKind::Return(val));
                 }
                 ObjKind::HASH => {
-                    let val = oref.rhash().get(&idx).cloned().unwrap_or_default();
-                    return Ok(VMResKind::Return(val));
+                    // The default proc is called via Hash#[].
+                    let val = match oref.rhash().get(&idx) {
+                        Some(val) => Some(*val),
+                        None => match oref.get_var(IdentId::_DEFAULT_PROC) {
+                            Some(proc) if !proc.is_nil() => None,
+                            _ => Some(oref.get_var(IdentId::_DEFAULT).unwrap_or_default()),
+                        },
+                    };
+                    if let Some(val) = val {
+                        return Ok(VMResKind::Return(val));
+                    }
                 }
                 _ => {}
             }
@@ -568,12 +591,17 @@ impl VM {
                     return Ok(VMResKind::Return(val));
                 }
                 ObjKind::HASH => {
-                    let val = oref
-                        .rhash()
-                        .get(&Value::fixnum(idx as i64))
-                        .cloned()
-                        .unwrap_or_default();
-                    return Ok(VMResKind::Return(val));
+                    // The default proc is called via Hash#[].
+                    let val = match oref.rhash().get(&Value::fixnum(idx as i64)) {
+                        Some(val) => Some(*val),
+                        None => match oref.get_var(IdentId::_DEFAULT_PROC) {
+                            Some(proc) if !proc.is_nil() => None,
+                            _ => Some(oref.get_var(IdentId::_DEFAULT).unwrap_or_default()),
+                        },
+                    };
+                    if let Some(val) = val {
+                        return Ok(VMResKind::Return(val));
+                    }
                 }
                 ObjKind::METHOD => {
                     let mref = oref.method();
@@ -595,6 +623,50 @@ impl VM {
         };
         self.invoke_send1(IdentId::_INDEX, receiver, Value::integer(idx as i64))
     }
+
+    /// `size` (or `empty?` if `empty` is true) without arguments.
+    ///
+    /// Array and Hash are handled without a method call unless they are instances of subclasses,
+    /// or the builtin method was redefined.
+    pub(super) fn invoke_size(&mut self, empty: bool, cache_id: u32) -> InvokeResult {
+        let receiver = self.stack.last();
+        let method = if empty { IdentId::EMPTY } else { IdentId::SIZE };
+        if let Some(oref) = receiver.as_rvalue() {
+            let class = oref.class();
+            let len = match oref.kind() {
+                ObjKind::ARRAY if class.id() == BuiltinClass::array().id() => {
+                    Some(oref.array().len())
+                }
+                ObjKind::HASH if class.id() == BuiltinClass::hash().id() => {
+                    Some(oref.rhash().len())
+                }
+                _ => None,
+            };
+            if let Some(len) = len.filter(|_| self.is_builtin_method(cache_id, class, method)) {
+                self.stack_pop();
+                let val = if empty {
+                    Value::bool(len == 0)
+                } else {
+                    Value::integer(len as i64)
+                };
+                return Ok(VMResKind::Return(val));
+            }
+        }
+        self.send(method, receiver, &Args2::new(0), true, cache_id)
+    }
+
+    /// Examine whether `method` of `class` is the builtin one (i.e. not redefined),
+    /// looking up through the inline cache `cache_id`.
+    fn is_builtin_method(&mut self, cache_id: u32, class: Module, method: IdentId) -> bool {
+        let fid = self
+            .globals
+            .methods
+            .find_method_inline_cache(cache_id, class, method);
+        matches!(
+            fid.map(|fid| &self.globals.methods[fid]),
+            Some(MethodInfo::BuiltinFunc { name, .. }) if *name == method
+        )
+    }
 }
 
 #[cfg(test)]
@@ -18,6 +18,29 @@ impl VM {
             // Reach this point when a Ruby method/block was 'invoke'ed/'call'ed,
             // or returned from a Ruby method/block.
             self.checked_gc();
+            self.check_timeslice()?;
+            #[cfg(feature = "jit")]
+            match self.jit_exec() {
+                Ok(jit::JitResult::Interpret) => {}
+                Ok(jit::JitResult::Invoke) => {
+                    *invoke_count += 1;
+                    continue;
+                }
+                Err(err) => match err.kind {
+                    RubyErrorKind::MethodReturn if self.iseq.is_method() => {
+                        let val = self.globals.val;
+                        if *invoke_count == 0 {
+                            return Ok(val);
+                        } else {
+                            self.unwind_frame();
+                            *invoke_count -= 1;
+                            self.stack_push(val);
+                            continue;
+                        }
+                    }
+                    _ => return Err(err),
+                },
+            }
             let self_val = self.self_value();
 
             #[cfg(not(tarpaulin_include))]
@@ -113,10 +136,13 @@ impl VM {
                     }
                 }
                 match self.pc.read8() {
-                    Inst::RETURN => {
+                    inst @ (Inst::RETURN | Inst::MRETURN)
+                        if inst == Inst::RETURN || matches!(self.kind(), ISeqKind::Method(_)) =>
+                    {
                         // - reached the end of the method or block.
                         // - `return` in method.
                         // - `next` in block AND outer of loops.
+                        // - `return` in lambda or a method defined by define_method.
                         if *invoke_count == 0 {
                             return Ok(self.stack_pop());
                         } else {
@@ -158,8 +184,20 @@ impl VM {
                     }
                     Inst::THROW => {
                         // - raise error
-                        self.globals.val = self.stack_pop();
+                        // - resume `throw` after ensure clause
+                        let val = self.stack_pop();
+                        self.globals.val = val;
                         self.pc -= 1;
+                        if let Some(err) = val.if_exception() {
+                            if matches!(
+                                err.kind,
+                                RubyErrorKind::Throw
+                                    | RubyErrorKind::SystemExit(_)
+                                    | RubyErrorKind::ThreadKill
+                            ) {
+                                return Err(err.clone());
+                            }
+                        }
                         return Err(RubyError::value());
                     }
                     Inst::PUSH_NIL => self.stack_push(Value::nil()),
@@ -224,12 +262,22 @@ impl VM {
                     }
                     Inst::CONCAT_STRING => {
                         let num = self.pc.read32() as usize;
-                        let res = (self.stack.sp - num)[0..num]
+                        let args = &(self.stack.sp - num)[0..num];
+                        let res = args.iter().fold(vec![], |mut acc, x| {
+                            acc.extend_from_slice(x.as_rstring().unwrap().as_bytes());
+                            acc
+                        });
+                        let enc = args
                             .iter()
-                            .fold(String::new(), |acc, x| acc + x.as_string().unwrap());
+                            .map(|x| Encoding::of(*x))
+                            .find(|enc| *enc != Encoding::Utf8)
+                            .unwrap_or(Encoding::Utf8);
                         self.stack.sp -= num;
 
-                        let val = Value::string(res);
+                        let val = Value::bytes(res);
+                        if enc != Encoding::Utf8 {
+                            enc.set(val);
+                        }
                         self.stack_push(val);
                     }
                     Inst::SET_LOCAL => {
@@ -242,6 +290,10 @@ impl VM {
                         let val = self.lfp[id];
                         self.stack_push(val);
                     }
+                    Inst::TEE_LOCAL => {
+                        let id = self.pc.read_lvar_id();
+                        self.lfp[id] = self.stack.last();
+                    }
                     Inst::SET_DYNLOCAL => {
                         let id = self.pc.read_lvar_id();
                         let outer = self.pc.read32();
@@ -316,12 +368,14 @@ impl VM {
                     }
                     Inst::SET_IVAR => {
                         let var_id = self.pc.read_id();
+                        let cache_id = self.pc.read32();
                         let new_val = self.stack_pop();
-                        self_val.set_var(var_id, new_val);
+                        self.set_ivar(self_val, var_id, cache_id, new_val)?;
                     }
                     Inst::GET_IVAR => {
                         let var_id = self.pc.read_id();
-                        let val = self_val.get_var(var_id).unwrap_or_default();
+                        let cache_id = self.pc.read32();
+                        let val = self.get_ivar(self_val, var_id, cache_id);
                         self.stack_push(val);
                     }
                     Inst::CHECK_IVAR => {
@@ -332,7 +386,7 @@ impl VM {
                     Inst::SET_GVAR => {
                         let var_id = self.pc.read_id();
                         let new_val = self.stack_pop();
-                        self.set_global_var(var_id, new_val);
+                        self.set_global_var(var_id, new_val)?;
                     }
                     Inst::GET_GVAR => {
                         let var_id = self.pc.read_id();
@@ -391,6 +445,12 @@ impl VM {
                         let val = self.globals.const_values.get(id);
                         self.stack_push(val);
                     }
+                    Inst::NO_MATCH => {
+                        let keys = self.globals.const_values.get(self.pc.read_usize());
+                        let val = self.stack_pop();
+                        let err = self.no_matching_pattern(val, keys)?;
+                        return Err(err);
+                    }
                     Inst::CREATE_RANGE => {
                         let start = self.stack_pop();
                         let end = self.stack_pop();
@@ -426,7 +486,12 @@ impl VM {
                     Inst::JMP_BACK => {
                         let disp = self.pc.read_disp();
                         self.checked_gc();
+                        self.check_timeslice()?;
                         self.pc += disp;
+                        #[cfg(feature = "jit")]
+                        if self.jit_back_edge() {
+                            break;
+                        }
                     }
                     Inst::JMP_F => {
                         let val = self.stack_pop();
@@ -490,6 +555,14 @@ impl VM {
                     Inst::SEND => dispatch!(self.vm_send(), true),
                     Inst::OPT_SEND => dispatch!(self.vm_fast_send(true), true),
                     Inst::OPT_SEND_N => dispatch!(self.vm_fast_send(false), false),
+                    Inst::OPT_SIZE => {
+                        let cache_id = self.pc.read32();
+                        dispatch!(self.invoke_size(false, cache_id), true);
+                    }
+                    Inst::OPT_EMPTY => {
+                        let cache_id = self.pc.read32();
+                        dispatch!(self.invoke_size(true, cache_id), true);
+                    }
                     Inst::YIELD => {
                         let args_num = self.pc.read32() as usize;
                         let args = self.pop_args_to_args(args_num);
@@ -699,7 +772,7 @@ impl VM {
     /// hashsp: [optional] hash splat arguments (Array of Hash object)
     /// block:  [optional] block argument
     ///
-    fn vm_send(&mut self) -> InvokeResult {
+    pub(super) fn vm_send(&mut self) -> InvokeResult {
         let method_name = self.pc.read_id();
         let args_num = self.pc.read16() as usize;
         let flag = self.pc.read_argflag();
@@ -726,7 +799,7 @@ impl VM {
         self.send(method_name, receiver, &args, use_value, cache_id)
     }
 
-    fn send(
+    pub(super) fn send(
         &mut self,
         method_name: IdentId,
         receiver: Value,
@@ -782,7 +855,7 @@ impl VM {
     }
 
     /// Invoke the block given to the method with `args`.
-    fn vm_yield(&mut self, args: &Args2) -> InvokeResult {
+    pub(super) fn vm_yield(&mut self, args: &Args2) -> InvokeResult {
         match &self.get_method_block() {
             Some(Block::Block(method, outer)) => {
                 let outer = self.cfp_from_frame(*outer).ep();
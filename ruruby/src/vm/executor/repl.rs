@@ -34,7 +34,7 @@ impl VM {
             format!("{}", Red.bold().paint("irrb:"))
         };
         let mut script = String::new();
-        self.set_global_var(IdentId::get_id("$0"), Value::string("irrb"));
+        self.globals.set_global_var_by_str("$0", Value::string("irrb"));
 
         loop {
             let prompt = if script.len() == 0 { ">" } else { "*" };
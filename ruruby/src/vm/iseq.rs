@@ -95,9 +95,67 @@ impl ISeq {
     pub(crate) fn read_disp(&self, offset: ISeqPos) -> ISeqDisp {
         ISeqDisp::from_i32(self.read32(offset) as i32)
     }
+
+    pub(crate) fn from_bytes(bytes: Vec<u8>) -> Self {
+        ISeq(bytes)
+    }
+
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub(crate) fn write32(&mut self, pc: ISeqPos, val: u32) {
+        self.0[pc.0..pc.0 + 4].copy_from_slice(&val.to_le_bytes());
+    }
+
+    pub(crate) fn write64(&mut self, pc: ISeqPos, val: u64) {
+        self.0[pc.0..pc.0 + 8].copy_from_slice(&val.to_le_bytes());
+    }
+
+    /// Position of the IdentId operand of the instruction at `pc`.
+    pub(crate) fn ident_operand(&self, pc: ISeqPos) -> Option<ISeqPos> {
+        match self[pc] {
+            Inst::GET_CONST
+            | Inst::GET_CONST_TOP
+            | Inst::SET_CONST
+            | Inst::CHECK_CONST
+            | Inst::CHECK_METHOD
+            | Inst::GET_SCOPE
+            | Inst::CHECK_SCOPE
+            | Inst::GET_IVAR
+            | Inst::SET_IVAR
+            | Inst::CHECK_IVAR
+            | Inst::GET_CVAR
+            | Inst::SET_CVAR
+            | Inst::GET_GVAR
+            | Inst::SET_GVAR
+            | Inst::CHECK_GVAR
+            | Inst::SEND
+            | Inst::OPT_SEND
+            | Inst::OPT_SEND_N
+            | Inst::DEF_METHOD
+            | Inst::DEF_SMETHOD => Some(pc + 1),
+            Inst::DEF_CLASS => Some(pc + 2),
+            _ => None,
+        }
+    }
+
+    /// Position of the FnId operand (a block, a method body or a class body) of the instruction at `pc`.
+    ///
+    /// The operand is 0 for a method call without a block.
+    pub(crate) fn method_operand(&self, pc: ISeqPos) -> Option<ISeqPos> {
+        match self[pc] {
+            Inst::CREATE_PROC | Inst::DEF_SCLASS => Some(pc + 1),
+            Inst::SUPER => Some(pc + 3),
+            Inst::DEF_METHOD | Inst::DEF_SMETHOD => Some(pc + 5),
+            Inst::DEF_CLASS => Some(pc + 6),
+            Inst::OPT_SEND | Inst::OPT_SEND_N => Some(pc + 7),
+            Inst::SEND => Some(pc + 8),
+            _ => None,
+        }
+    }
 }
 
-#[cfg(any(feature = "emit-iseq", feature = "trace"))]
 impl ISeq {
     pub(crate) fn ident_name(&self, pc: ISeqPos) -> String {
         let id: IdentId = self.read32(pc).into();
@@ -317,14 +375,16 @@ impl ISeq {
         self.current()
     }
 
-    pub(crate) fn gen_get_instance_var(&mut self, id: IdentId) {
+    pub(crate) fn gen_get_instance_var(&mut self, globals: &mut Globals, id: IdentId) {
         self.push(Inst::GET_IVAR);
         self.push32(id.into());
+        self.push32(globals.add_ivar_cache_entry());
     }
 
-    pub(crate) fn gen_set_instance_var(&mut self, id: IdentId) {
+    pub(crate) fn gen_set_instance_var(&mut self, globals: &mut Globals, id: IdentId) {
         self.push(Inst::SET_IVAR);
         self.push32(id.into());
+        self.push32(globals.add_ivar_cache_entry());
     }
 
     pub(crate) fn gen_get_global_var(&mut self, id: IdentId) {
@@ -416,6 +476,7 @@ enum DestKind {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(transparent)]
 pub(crate) struct ISeqPtr(*const u8);
 
 impl std::ops::Add<usize> for ISeqPtr {
@@ -0,0 +1,740 @@
+///
+/// ISeq cache
+///
+/// Compiled ISeqs of `require`d or `load`ed files are serialized into the cache directory,
+/// and reused while the source file is unchanged.
+///
+/// A cache file consists of a header and a payload.
+/// The header holds the format version, the ruruby version, whether the bytecode optimizer is enabled
+/// and the identity of the source file (path, size, mtime and hash of the source code),
+/// which must all match for the cache to be used.
+/// The payload holds tables of symbols, constant literals and case-dispatch maps, followed by ISeqs.
+/// IdentIds, FnIds, constant ids and case-dispatch map ids in the bytecode are replaced by
+/// indexes into these tables, and remapped when loaded. Inline caches are allocated on load.
+///
+use crate::*;
+use indexmap::IndexSet;
+use std::convert::TryInto;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+const MAGIC: &[u8; 4] = b"RRBC";
+
+/// Version of the cache format.
+///
+/// This must be bumped whenever the layout of cache files or the instruction set is changed.
+const FORMAT_VERSION: u32 = 3;
+
+/// Default directory of the ISeq cache.
+///
+/// `RURUBY_ISEQ_CACHE_DIR` overrides the default.
+pub fn default_dir() -> Option<PathBuf> {
+    match std::env::var_os("RURUBY_ISEQ_CACHE_DIR") {
+        Some(dir) => Some(PathBuf::from(dir)),
+        None => dirs::cache_dir().map(|dir| dir.join("ruruby").join("iseq")),
+    }
+}
+
+/// Identity of a source file.
+#[derive(Debug, PartialEq)]
+pub(crate) struct SourceKey {
+    path: String,
+    size: u64,
+    mtime: u64,
+    hash: u64,
+}
+
+impl SourceKey {
+    pub(crate) fn new(path: &Path, code: &str) -> Option<Self> {
+        let meta = fs::metadata(path).ok()?;
+        let mtime = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(SourceKey {
+            path: path.to_string_lossy().to_string(),
+            size: meta.len(),
+            mtime: mtime.as_nanos() as u64,
+            hash: fxhash::hash64(code.as_bytes()),
+        })
+    }
+
+    /// Path of the cache file for this source file.
+    fn cache_path(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("{:016x}.rbc", fxhash::hash64(self.path.as_bytes())))
+    }
+
+    fn write(&self, w: &mut Writer) {
+        w.str(&self.path);
+        w.u64(self.size);
+        w.u64(self.mtime);
+        w.u64(self.hash);
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        Some(SourceKey {
+            path: r.str()?.to_string(),
+            size: r.u64()?,
+            mtime: r.u64()?,
+            hash: r.u64()?,
+        })
+    }
+}
+
+/// Load the toplevel ISeq of the source file `key` from the cache in `dir`.
+///
+/// Returns None if there is no valid cache.
+pub(crate) fn load(vm: &mut VM, dir: &Path, key: &SourceKey, code: &str) -> Option<FnId> {
+    let data = fs::read(key.cache_path(dir)).ok()?;
+    let mut r = Reader::new(&data);
+    if r.bytes(MAGIC.len())? != MAGIC
+        || r.u32()? != FORMAT_VERSION
+        || r.str()? != env!("CARGO_PKG_VERSION")
+        || r.u8()? != vm.globals.iseq_opt as u8
+        || SourceKey::read(&mut r)? != *key
+    {
+        return None;
+    }
+    let checksum = r.u64()?;
+    let payload = r.rest();
+    if fxhash::hash64(payload) != checksum {
+        return None;
+    }
+    let source_info = SourceInfoRef::new(SourceInfo::new(&key.path, code));
+    Deserializer::new(payload).load(vm, source_info)
+}
+
+/// Write the toplevel ISeq `method` of the source file `key` and Ruby functions defined in it
+/// into the cache in `dir`.
+///
+/// Failures are ignored, as the cache is only an optimization.
+pub(crate) fn save(vm: &mut VM, dir: &Path, key: &SourceKey, method: FnId) {
+    let payload = match Serializer::new(method).serialize(vm) {
+        Some(payload) => payload,
+        None => return,
+    };
+    let mut w = Writer::default();
+    w.buf.extend_from_slice(MAGIC);
+    w.u32(FORMAT_VERSION);
+    w.str(env!("CARGO_PKG_VERSION"));
+    w.u8(vm.globals.iseq_opt as u8);
+    key.write(&mut w);
+    w.u64(fxhash::hash64(&payload));
+    w.buf.extend_from_slice(&payload);
+    if fs::create_dir_all(dir).is_err() {
+        return;
+    }
+    // Write into a temporary file and rename it, not to leave a broken cache file.
+    let path = key.cache_path(dir);
+    let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+    if fs::write(&tmp, &w.buf).is_err() || fs::rename(&tmp, &path).is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+}
+
+#[derive(Default)]
+struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, val: u8) {
+        self.buf.push(val);
+    }
+
+    fn u32(&mut self, val: u32) {
+        self.buf.extend_from_slice(&val.to_le_bytes());
+    }
+
+    fn u64(&mut self, val: u64) {
+        self.buf.extend_from_slice(&val.to_le_bytes());
+    }
+
+    fn usize(&mut self, val: usize) {
+        self.u64(val as u64);
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.u32(bytes.len() as u32);
+        self.buf.extend_from_slice(bytes);
+    }
+
+    fn str(&mut self, s: &str) {
+        self.bytes(s.as_bytes());
+    }
+
+    /// Write Option<LvarId>. None is written as u32::MAX.
+    fn lvar(&mut self, id: Option<LvarId>) {
+        self.u32(id.map_or(u32::MAX, |id| id.as_u32()));
+    }
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Reader { buf, pos: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.buf.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        let bytes = &self.buf[self.pos..];
+        self.pos = self.buf.len();
+        bytes
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.bytes(1)?[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.bytes(4)?.try_into().ok()?))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.bytes(8)?.try_into().ok()?))
+    }
+
+    fn usize(&mut self) -> Option<usize> {
+        Some(self.u64()? as usize)
+    }
+
+    fn byte_str(&mut self) -> Option<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.bytes(len)
+    }
+
+    fn str(&mut self) -> Option<&'a str> {
+        std::str::from_utf8(self.byte_str()?).ok()
+    }
+
+    fn lvar(&mut self) -> Option<Option<LvarId>> {
+        Some(match self.u32()? {
+            u32::MAX => None,
+            id => Some(LvarId::from(id)),
+        })
+    }
+}
+
+/// Position of the inline method cache id of the instruction at `pc`.
+fn inline_cache_operand(iseq: &ISeq, pc: ISeqPos) -> Option<ISeqPos> {
+    match iseq[pc] {
+        Inst::OPT_SEND | Inst::OPT_SEND_N => Some(pc + 11),
+        Inst::SEND => Some(pc + 12),
+        Inst::OPT_SIZE | Inst::OPT_EMPTY => Some(pc + 1),
+        _ => None,
+    }
+}
+
+struct Serializer {
+    methods: IndexSet<FnId>,
+    symbols: IndexSet<IdentId>,
+    consts: IndexSet<u32>,
+    case_maps: IndexSet<u32>,
+    case_maps2: IndexSet<u32>,
+    w: Writer,
+}
+
+impl Serializer {
+    fn new(method: FnId) -> Self {
+        let mut methods = IndexSet::default();
+        methods.insert(method);
+        Serializer {
+            methods,
+            symbols: IndexSet::default(),
+            consts: IndexSet::default(),
+            case_maps: IndexSet::default(),
+            case_maps2: IndexSet::default(),
+            w: Writer::default(),
+        }
+    }
+
+    /// Index of the symbol `id` in the symbol table, starting from 1.
+    fn symbol(&mut self, id: IdentId) -> u32 {
+        self.symbols.insert_full(id).0 as u32 + 1
+    }
+
+    fn serialize(mut self, vm: &mut VM) -> Option<Vec<u8>> {
+        // Ruby functions referred by ISeqs are appended to `methods` while serializing.
+        let mut i = 0;
+        while i < self.methods.len() {
+            let iseq = match vm.globals.methods[self.methods[i]] {
+                MethodInfo::RubyFunc { iseq } => iseq,
+                _ => return None,
+            };
+            self.write_iseq(iseq);
+            i += 1;
+        }
+
+        let mut w = Writer::default();
+        w.u32(self.symbols.len() as u32);
+        for id in &self.symbols {
+            w.str(&id.get_name());
+        }
+        w.u32(self.consts.len() as u32);
+        for id in &self.consts {
+            let val = vm.globals.const_values.get(*id as usize);
+            w.u8(val.is_frozen() as u8);
+            w.bytes(&marshal::dump_value(vm, val, -1).ok()?);
+        }
+        w.u32(self.case_maps.len() as u32);
+        for id in &self.case_maps {
+            let map = vm.globals.case_dispatch.get_entry(*id).clone();
+            w.u32(map.len() as u32);
+            for (key, disp) in map {
+                w.bytes(&marshal::dump_value(vm, key.0, -1).ok()?);
+                w.u32(disp.to_i32() as u32);
+            }
+        }
+        w.u32(self.case_maps2.len() as u32);
+        for id in &self.case_maps2 {
+            let (min, max, map) = vm.globals.case_dispatch2.get_entry(*id);
+            w.u64(*min as u64);
+            w.u64(*max as u64);
+            w.u32(map.len() as u32);
+            map.iter().for_each(|disp| w.u32(disp.to_i32() as u32));
+        }
+        w.u32(self.methods.len() as u32);
+        w.buf.extend_from_slice(&self.w.buf);
+        Some(w.buf)
+    }
+
+    fn write_iseq(&mut self, info: ISeqRef) {
+        let params = &info.params;
+        let param_ident: Vec<_> = params
+            .param_ident
+            .iter()
+            .map(|id| self.symbol(*id))
+            .collect();
+        self.w.u32(param_ident.len() as u32);
+        param_ident.into_iter().for_each(|id| self.w.u32(id));
+        self.w.usize(params.req);
+        self.w.usize(params.opt);
+        self.w.u8(match params.rest {
+            None => 0,
+            Some(false) => 1,
+            Some(true) => 2,
+        });
+        self.w.usize(params.post);
+        self.w.u8(params.block as u8);
+        let keyword: Vec<_> = params
+            .keyword
+            .iter()
+            .map(|(id, lvar)| (self.symbol(*id), *lvar))
+            .collect();
+        self.w.u32(keyword.len() as u32);
+        for (id, lvar) in keyword {
+            self.w.u32(id);
+            self.w.u32(lvar.as_u32());
+        }
+        self.w.u8(params.kwrest as u8);
+        self.w.lvar(params.delegate);
+
+        let iseq = self.convert_iseq(&info.iseq);
+        self.w.bytes(iseq.as_bytes());
+
+        let lvar = &info.lvar;
+        self.w.u32(lvar.kw.len() as u32);
+        lvar.kw.iter().for_each(|id| self.w.u32(id.as_u32()));
+        let table: Vec<_> = lvar.table().iter().map(|id| self.symbol(*id)).collect();
+        self.w.u32(table.len() as u32);
+        table.into_iter().for_each(|id| self.w.u32(id));
+        self.w.lvar(lvar.kwrest_param());
+        self.w.lvar(lvar.block_param());
+        self.w.lvar(lvar.delegate_param);
+
+        self.w.u32(info.exception_table.len() as u32);
+        for entry in &info.exception_table {
+            self.w.u8(match entry.ty {
+                ExceptionType::Rescue => 0,
+                ExceptionType::Continue => 1,
+            });
+            self.w.usize(entry.start.into_usize());
+            self.w.usize(entry.end.into_usize());
+            self.w.usize(entry.dest.into_usize());
+        }
+
+        self.w.u32(info.iseq_sourcemap.len() as u32);
+        for (pos, loc) in &info.iseq_sourcemap {
+            self.w.usize(pos.into_usize());
+            self.w.usize(loc.0);
+            self.w.usize(loc.1);
+        }
+
+        match info.kind {
+            ISeqKind::Other => self.w.u8(0),
+            ISeqKind::Method(None) => self.w.u8(1),
+            ISeqKind::Method(Some(id)) => {
+                self.w.u8(2);
+                let id = self.symbol(id);
+                self.w.u32(id);
+            }
+            ISeqKind::Class(id) => {
+                self.w.u8(3);
+                let id = self.symbol(id);
+                self.w.u32(id);
+            }
+            ISeqKind::Block => self.w.u8(4),
+        }
+        self.w.usize(info.loc.0);
+        self.w.usize(info.loc.1);
+    }
+
+    /// Replace process-local ids in the bytecode with indexes into the tables.
+    fn convert_iseq(&mut self, iseq: &ISeq) -> ISeq {
+        let mut iseq = ISeq::from_bytes(iseq.as_bytes().to_vec());
+        let mut pc = ISeqPos::from(0);
+        while pc.into_usize() < iseq.len() {
+            if let Some(pos) = iseq.ident_operand(pc) {
+                let id = self.symbol(iseq.read32(pos).into());
+                iseq.write32(pos, id);
+            }
+            if let Some(pos) = iseq.method_operand(pc) {
+                if let id @ 1.. = iseq.read32(pos) {
+                    let idx = self.methods.insert_full(FnId::from(id)).0;
+                    iseq.write32(pos, idx as u32 + 1);
+                }
+            }
+            if let Some(pos) = inline_cache_operand(&iseq, pc) {
+                iseq.write32(pos, 0);
+            }
+            match iseq[pc] {
+                Inst::PUSH_VAL => {
+                    if let Some(id) = Value::from(iseq.read64(pc + 1)).as_symbol() {
+                        let id = self.symbol(id);
+                        iseq.write64(pc + 1, Value::symbol(id.into()).id());
+                    }
+                }
+                Inst::CONST_VAL | Inst::NO_MATCH => {
+                    let idx = self.consts.insert_full(iseq.read32(pc + 1)).0;
+                    iseq.write32(pc + 1, idx as u32);
+                }
+                Inst::GET_CONST | Inst::GET_IVAR | Inst::SET_IVAR => iseq.write32(pc + 5, 0),
+                Inst::OPT_CASE => {
+                    let idx = self.case_maps.insert_full(iseq.read32(pc + 1)).0;
+                    iseq.write32(pc + 1, idx as u32);
+                }
+                Inst::OPT_CASE2 => {
+                    let idx = self.case_maps2.insert_full(iseq.read32(pc + 1)).0;
+                    iseq.write32(pc + 1, idx as u32);
+                }
+                _ => {}
+            }
+            pc += Inst::inst_size(iseq[pc]);
+        }
+        iseq
+    }
+}
+
+struct Deserializer<'a> {
+    r: Reader<'a>,
+    methods: Vec<FnId>,
+    symbols: Vec<IdentId>,
+    consts: Vec<u32>,
+    case_maps: Vec<u32>,
+    case_maps2: Vec<u32>,
+}
+
+impl<'a> Deserializer<'a> {
+    fn new(payload: &'a [u8]) -> Self {
+        Deserializer {
+            r: Reader::new(payload),
+            methods: vec![],
+            symbols: vec![],
+            consts: vec![],
+            case_maps: vec![],
+            case_maps2: vec![],
+        }
+    }
+
+    fn symbol(&self, idx: u32) -> Option<IdentId> {
+        self.symbols.get((idx as usize).checked_sub(1)?).cloned()
+    }
+
+    fn read_symbol(&mut self) -> Option<IdentId> {
+        let idx = self.r.u32()?;
+        self.symbol(idx)
+    }
+
+    fn load(mut self, vm: &mut VM, source_info: SourceInfoRef) -> Option<FnId> {
+        for _ in 0..self.r.u32()? {
+            let id = IdentId::get_id(self.r.str()?);
+            self.symbols.push(id);
+        }
+        for _ in 0..self.r.u32()? {
+            let frozen = self.r.u8()? != 0;
+            let mut val = marshal::load_value(vm, self.r.byte_str()?, None).ok()?;
+            if frozen {
+                val = val.freeze();
+            }
+            let id = vm.globals.const_values.insert(val);
+            self.consts.push(id as u32);
+        }
+        for _ in 0..self.r.u32()? {
+            let mut map = FxHashMap::default();
+            for _ in 0..self.r.u32()? {
+                let key = marshal::load_value(vm, self.r.byte_str()?, None).ok()?;
+                let disp = ISeqDisp::from_i32(self.r.u32()? as i32);
+                map.insert(HashKey(key), disp);
+            }
+            let id = vm.globals.case_dispatch.new_entry();
+            *vm.globals.case_dispatch.get_mut_entry(id) = map;
+            self.case_maps.push(id);
+        }
+        for _ in 0..self.r.u32()? {
+            let min = self.r.u64()? as i64;
+            let max = self.r.u64()? as i64;
+            let mut map = vec![];
+            for _ in 0..self.r.u32()? {
+                map.push(ISeqDisp::from_i32(self.r.u32()? as i32));
+            }
+            let id = vm.globals.case_dispatch2.new_entry();
+            *vm.globals.case_dispatch2.get_mut_entry(id) = (min, max, map);
+            self.case_maps2.push(id);
+        }
+        let len = self.r.u32()?;
+        self.methods = (0..len)
+            .map(|_| vm.globals.methods.add(MethodInfo::default()))
+            .collect();
+        for i in 0..self.methods.len() {
+            let method = self.methods[i];
+            let info = self.read_iseq(&mut vm.globals, method, source_info.clone())?;
+            let info = MethodInfo::RubyFunc {
+                iseq: ISeqRef::new(info),
+            };
+            vm.globals.methods.update(method, info);
+        }
+        self.methods.first().cloned()
+    }
+
+    fn read_iseq(
+        &mut self,
+        globals: &mut Globals,
+        method: FnId,
+        source_info: SourceInfoRef,
+    ) -> Option<ISeqInfo> {
+        let mut params = ISeqParams::default();
+        for _ in 0..self.r.u32()? {
+            let id = self.read_symbol()?;
+            params.param_ident.push(id);
+        }
+        params.req = self.r.usize()?;
+        params.opt = self.r.usize()?;
+        params.rest = match self.r.u8()? {
+            0 => None,
+            1 => Some(false),
+            _ => Some(true),
+        };
+        params.post = self.r.usize()?;
+        params.block = self.r.u8()? != 0;
+        for _ in 0..self.r.u32()? {
+            let id = self.read_symbol()?;
+            let lvar = LvarId::from(self.r.u32()?);
+            params.keyword.insert(id, lvar);
+        }
+        params.kwrest = self.r.u8()? != 0;
+        params.delegate = self.r.lvar()?;
+
+        let iseq = ISeq::from_bytes(self.r.byte_str()?.to_vec());
+        let iseq = self.restore_iseq(globals, iseq)?;
+
+        let mut kw = vec![];
+        for _ in 0..self.r.u32()? {
+            kw.push(LvarId::from(self.r.u32()?));
+        }
+        let mut table = vec![];
+        for _ in 0..self.r.u32()? {
+            table.push(self.read_symbol()?);
+        }
+        let kwrest = self.r.lvar()?;
+        let block = self.r.lvar()?;
+        let delegate_param = self.r.lvar()?;
+        let lvar = LvarCollector::restore(kw, table, kwrest, block, delegate_param);
+
+        let mut exception_table = vec![];
+        for _ in 0..self.r.u32()? {
+            let ty = self.r.u8()?;
+            let start = ISeqPos::from(self.r.usize()?);
+            let end = ISeqPos::from(self.r.usize()?);
+            let dest = ISeqPos::from(self.r.usize()?);
+            exception_table.push(match ty {
+                0 => ExceptionEntry::new_rescue(start, end, dest),
+                _ => ExceptionEntry::new_continue(start, end, dest),
+            });
+        }
+
+        let mut iseq_sourcemap = vec![];
+        for _ in 0..self.r.u32()? {
+            let pos = ISeqPos::from(self.r.usize()?);
+            let loc = Loc(self.r.usize()?, self.r.usize()?);
+            iseq_sourcemap.push((pos, loc));
+        }
+
+        let kind = match self.r.u8()? {
+            0 => ISeqKind::Other,
+            1 => ISeqKind::Method(None),
+            2 => ISeqKind::Method(Some(self.read_symbol()?)),
+            3 => ISeqKind::Class(self.read_symbol()?),
+            _ => ISeqKind::Block,
+        };
+        let loc = Loc(self.r.usize()?, self.r.usize()?);
+
+        Some(ISeqInfo::new(
+            method,
+            params,
+            iseq,
+            lvar,
+            exception_table,
+            iseq_sourcemap,
+            source_info,
+            kind,
+            loc,
+        ))
+    }
+
+    /// Replace indexes into the tables in the bytecode with ids of this process.
+    fn restore_iseq(&self, globals: &mut Globals, mut iseq: ISeq) -> Option<ISeq> {
+        let mut pc = ISeqPos::from(0);
+        while pc.into_usize() < iseq.len() {
+            let next = pc + Inst::inst_size(iseq[pc]);
+            if next.into_usize() > iseq.len() {
+                return None;
+            }
+            if let Some(pos) = iseq.ident_operand(pc) {
+                let id = self.symbol(iseq.read32(pos))?;
+                iseq.write32(pos, id.into());
+            }
+            if let Some(pos) = iseq.method_operand(pc) {
+                if let idx @ 1.. = iseq.read32(pos) {
+                    let method = *self.methods.get(idx as usize - 1)?;
+                    iseq.write32(pos, method.into());
+                }
+            }
+            if let Some(pos) = inline_cache_operand(&iseq, pc) {
+                iseq.write32(pos, globals.methods.add_inline_cache_entry());
+            }
+            match iseq[pc] {
+                Inst::PUSH_VAL => {
+                    if let Some(idx) = Value::from(iseq.read64(pc + 1)).as_symbol() {
+                        let id = self.symbol(idx.into())?;
+                        iseq.write64(pc + 1, Value::symbol(id).id());
+                    }
+                }
+                Inst::CONST_VAL | Inst::NO_MATCH => {
+                    let id = *self.consts.get(iseq.read32(pc + 1) as usize)?;
+                    iseq.write32(pc + 1, id);
+                }
+                Inst::GET_CONST => iseq.write32(pc + 5, globals.add_const_cache_entry()),
+                Inst::GET_IVAR | Inst::SET_IVAR => {
+                    iseq.write32(pc + 5, globals.add_ivar_cache_entry())
+                }
+                Inst::OPT_CASE => {
+                    let id = *self.case_maps.get(iseq.read32(pc + 1) as usize)?;
+                    iseq.write32(pc + 1, id);
+                }
+                Inst::OPT_CASE2 => {
+                    let id = *self.case_maps2.get(iseq.read32(pc + 1) as usize)?;
+                    iseq.write32(pc + 1, id);
+                }
+                _ => {}
+            }
+            pc = next;
+        }
+        Some(iseq)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iseq_cache() {
+        let dir = std::env::temp_dir().join("ruruby_iseq_cache_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let lib = dir.join("lib.rb");
+        let code = r##"
+        # frozen_string_literal: true
+        class Foo
+          attr_reader :name
+          def initialize(name = "foo", *rest, key: :sym, &block)
+            @name = name
+            @key = key
+          end
+          def kind(x)
+            case x
+            when 1, 2 then :small
+            when "str" then :string
+            when 100..200 then :large
+            else :other
+            end
+          end
+          def key
+            @key
+          end
+        end
+        def sum(ary)
+          ary.map { |x| x * 2 }.inject(0) { |acc, x| acc + x }
+        end
+        def re_match(s)
+          !!(/b+c/ =~ s)
+        end
+        def rescued
+          raise "boom"
+        rescue => e
+          "rescued #{e.message}"
+        ensure
+          $ensured = true
+        end
+        CONST = ["a", 1.5, 12345678901234567890, 3..7]
+        LIBRARY = "lib"
+        "##;
+        fs::write(&lib, code).unwrap();
+        let script = format!(
+            r##"
+            require "{}"
+            foo = Foo.new
+            assert "foo", foo.name
+            assert :sym, foo.key
+            assert :baz, Foo.new("bar", key: :baz).key
+            assert [:small, :string, :large, :other], [2, "str", 150, nil].map {{ |x| foo.kind(x) }}
+            assert 12, sum([1, 2, 3])
+            assert true, re_match("abbc")
+            assert false, re_match("ac")
+            assert "rescued boom", rescued
+            assert true, $ensured
+            assert ["a", 1.5, 12345678901234567890, 3..7], CONST
+            assert true, LIBRARY.frozen?
+            "##,
+            lib.to_string_lossy()
+        );
+
+        let mut vm = VM::new();
+        vm.globals.iseq_cache_dir = Some(dir.clone());
+        vm.run("", script.clone()).unwrap();
+        let key = SourceKey::new(&lib.canonicalize().unwrap(), code).unwrap();
+        assert!(key.cache_path(&dir).exists());
+
+        let mut vm = VM::new();
+        vm.globals.iseq_cache_dir = Some(dir.clone());
+        assert!(load(&mut vm, &dir, &key, code).is_some());
+        let mut vm = VM::new();
+        vm.globals.iseq_cache_dir = Some(dir.clone());
+        vm.run("", script).unwrap();
+
+        // A modified source file is recompiled.
+        let code = format!("{}\nLIBRARY2 = 1\n", code);
+        fs::write(&lib, &code).unwrap();
+        let key = SourceKey::new(&lib.canonicalize().unwrap(), &code).unwrap();
+        let mut vm = VM::new();
+        assert!(load(&mut vm, &dir, &key, &code).is_none());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -497,6 +497,119 @@ fn case_less_case() {
     assert_script(program);
 }
 
+#[test]
+fn case_in_array() {
+    let program = r#"
+        def f(x)
+          case x
+          in [] then :empty
+          in [Integer => a] then a
+          in [1, *rest] then rest
+          in [*, :x, *post] then post
+          in [_, [b, c]] then b + c
+          else :none
+          end
+        end
+        assert :empty, f([])
+        assert 7, f([7])
+        assert [2, 3], f([1, 2, 3])
+        assert [:y], f([5, :x, :y])
+        assert 5, f([0, [2, 3]])
+        assert :none, f("a")
+    "#;
+    assert_script(program);
+}
+
+#[test]
+fn case_in_hash() {
+    let program = r##"
+        def f(x)
+          case x
+          in {name: String => name, age: Integer => age} if age >= 20 then "#{name} adult"
+          in {name:, age: 0..19} then "#{name} minor"
+          in {id: 1, **nil} then :exact
+          in {id:, **rest} then rest
+          end
+        end
+        assert "Bob adult", f({name: "Bob", age: 30})
+        assert "Ann minor", f({name: "Ann", age: 10})
+        assert :exact, f({id: 1})
+        assert({b: 2}, f({id: 1, b: 2}))
+    "##;
+    assert_script(program);
+}
+
+#[test]
+fn case_in_misc() {
+    let program = r#"
+        v = 3
+        r = case 3
+            in ^v then :pinned
+            end
+        assert :pinned, r
+        r = case :b
+            in :a | :b then :alt
+            end
+        assert :alt, r
+        r = case 5
+            in Integer unless 5 > 10 then :small
+            end
+        assert :small, r
+        r = case nil
+            in nil then :nil
+            end
+        assert :nil, r
+        S = Struct.new(:x, :y)
+        r = case S.new(1, 2)
+            in S[x, 2] then x
+            end
+        assert 1, r
+        r = case S.new(1, 2)
+            in S(x:, y: 2) then x
+            end
+        assert 1, r
+    "#;
+    assert_script(program);
+}
+
+#[test]
+fn pattern_match_expr() {
+    let program = r#"
+        {a: [1, {b: 2}]} => {a: [x, {b:}]}
+        assert 1, x
+        assert 2, b
+        assert true, (1 in Integer)
+        assert false, (1 in String)
+        begin
+          42 => String
+        rescue NoMatchingPatternError => e
+          assert true, e.message.include?("42")
+        end
+        begin
+          {a: 1} => {b:}
+        rescue NoMatchingPatternKeyError => e
+          assert true, e.message.include?("{:a=>1}: key not found: :b")
+        end
+    "#;
+    assert_script(program);
+}
+
+#[test]
+fn case_when_teq() {
+    let program = r#"
+        r = case 5
+            when 1..3 then :low
+            when 4..6 then :mid
+            end
+        assert :mid, r
+        r = case 4
+            when ->(x) { x.even? } then :even
+            end
+        assert :even, r
+    "#;
+    assert_script(program);
+}
+
 #[test]
 fn block_break() {
     let program = "
@@ -516,6 +629,30 @@ fn block_next() {
     assert_script(program);
 }
 
+#[test]
+fn block_redo() {
+    let program = "
+        a = []
+        c = 0
+        [1, 2, 3].each do |x|
+          c += 1
+          a << x
+          redo if x == 2 && c < 4
+        end
+        assert([1, 2, 2, 2, 3], a)
+        a = []
+        i = 0
+        while i < 3
+          i += 1
+          a << i
+          redo if a.size == 2
+        end
+        assert([1, 2, 3], a)
+        assert_error { eval('def f; redo; end') }
+    ";
+    assert_script(program);
+}
+
 #[test]
 fn block_return() {
     let program = "
@@ -1558,7 +1695,120 @@ fn exception3() {
     let program = "
     e = Exception.new
     assert(true, String === e.message)
-    assert(true, Array === e.backtrace)
+    assert(nil, e.backtrace)
     ";
     assert_script(program);
 }
+
+#[test]
+fn exception_ensure() {
+    let program = r##"
+    a = []
+    begin
+      begin
+        a << "begin"
+        raise "error"
+      ensure
+        a << "ensure"
+      end
+    rescue => ex
+      a << ex.message
+    end
+    assert ["begin", "ensure", "error"], a
+
+    a = []
+    begin
+      begin
+        raise "first"
+      rescue
+        raise "second"
+      ensure
+        a << "ensure"
+      end
+    rescue => ex
+      a << ex.message
+    end
+    assert ["ensure", "second"], a
+    "##;
+    assert_script(program);
+}
+
+#[test]
+fn exception_retry() {
+    let program = r##"
+    a = []
+    attempts = 0
+    res = begin
+      attempts += 1
+      a << attempts
+      raise "error" if attempts < 3
+      attempts * 10
+    rescue
+      retry if attempts < 3
+    ensure
+      a << "ensure"
+    end
+    assert [1, 2, 3, "ensure"], a
+    assert 30, res
+    assert_error { eval("retry") }
+    "##;
+    assert_script(program);
+}
+
+#[test]
+fn frozen_string_literal() {
+    let program = r##"
+    # frozen_string_literal: true
+    assert true, "abc".frozen?
+    assert false, "a#{1}c".frozen?
+    assert false, "abc".dup.frozen?
+    assert_error { "abc" << "d" }
+    "##;
+    assert_script(program);
+}
+
+#[test]
+fn encoding_magic_comment() {
+    let program = r##"
+    # -*- coding: Shift_JIS -*-
+    assert Encoding::Shift_JIS, __ENCODING__
+    assert Encoding::Shift_JIS, "x".encoding
+    "##;
+    assert_script(program);
+    let program = r##"
+    # encoding: ascii-8bit
+    assert Encoding::ASCII_8BIT, __ENCODING__
+    assert Encoding::ASCII_8BIT, "x".encoding
+    assert Encoding::ASCII_8BIT, "#{1}x".encoding
+    assert '"h\xC3\xA9"', "hé".inspect
+    "##;
+    assert_script(program);
+    let program = r##"
+    # encoding: us-ascii
+    assert Encoding::US_ASCII, "x".encoding
+    "##;
+    assert_script(program);
+    let program = r##"
+    assert Encoding::UTF_8, __ENCODING__
+    assert Encoding::UTF_8, "x".encoding
+    "##;
+    assert_script(program);
+}
+
+#[test]
+fn reserved_word_keyword_args() {
+    let program = r##"
+    def f(**kw); kw; end
+    assert({if: 1, class: 2, undef: 3}, f(if: 1, class: 2, undef: 3))
+    "##;
+    assert_script(program);
+}
+
+#[test]
+fn encoding_inspect() {
+    let program = r##"
+    assert "[#<Encoding:UTF-8>]", [Encoding::UTF_8].inspect
+    assert "{:e=>#<Encoding:ASCII-8BIT>}", {e: Encoding::BINARY}.inspect
+    "##;
+    assert_script(program);
+}